@@ -34,6 +34,7 @@ fn typical_rbac_policy() -> Policy<'static> {
                 principal: Matcher::Any,
                 action: Matcher::OneOf(&["read", "list", "describe"]),
                 resource: Matcher::Any,
+                environment: Matcher::Any,
             },
             Some(Condition::Equals {
                 attr: "role",
@@ -47,6 +48,7 @@ fn typical_rbac_policy() -> Policy<'static> {
                 principal: Matcher::Any,
                 action: Matcher::OneOf(&["read", "write", "update", "delete"]),
                 resource: Matcher::Any,
+                environment: Matcher::Any,
             },
             Some(Condition::Equals {
                 attr: "role",
@@ -60,6 +62,7 @@ fn typical_rbac_policy() -> Policy<'static> {
                 principal: Matcher::Any,
                 action: Matcher::Exact("delete"),
                 resource: Matcher::Any,
+                environment: Matcher::Any,
             },
             Some(Condition::Equals {
                 attr: "mfa_verified",
@@ -73,6 +76,7 @@ fn typical_rbac_policy() -> Policy<'static> {
                 principal: Matcher::Any,
                 action: Matcher::OneOf(&["read"]),
                 resource: Matcher::Any,
+                environment: Matcher::Any,
             },
             None,
             ReasonCode(200),
@@ -93,6 +97,7 @@ fn heavy_policy() -> Policy<'static> {
                 principal: Matcher::Exact("user"),
                 action: Matcher::Exact("action"),
                 resource: Matcher::Exact("resource"),
+                environment: Matcher::Any,
             },
             Some(Condition::And(
                 Box::new(Condition::Equals {
@@ -20,7 +20,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Target {
                 principal: Matcher::Any,
                 action: Matcher::Exact("read"),
-                resource: Matcher::Any, // In a real app prefix matching might be done in Condition
+                resource: Matcher::Any, // In a real app prefix matching might be done in Condition,
+                environment: Matcher::Any,
             },
             Some(Condition::Equals {
                 attr: "team",
@@ -34,6 +35,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 principal: Matcher::Any,
                 action: Matcher::Any,
                 resource: Matcher::Exact("salaries.pdf"),
+                environment: Matcher::Any,
             },
             SENSITIVE_DENY,
         ))
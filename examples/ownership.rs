@@ -0,0 +1,81 @@
+//! Illustrative scenario: Owners act on their own resources.
+//!
+//! This example demonstrates the ownership helper:
+//! 1. Anyone may read any document.
+//! 2. Only a document's owner may edit or delete it, via `Rule::owner_allow`.
+//! 3. Archived documents are read-only, even for their owner.
+//!
+//! The caller looks up the owner and passes it as the `resource_owner`
+//! context attribute.
+
+use gate0::{
+    Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, Value,
+    DEFAULT_OWNER_ATTR,
+};
+
+const READ_OK: ReasonCode = ReasonCode(1);
+const OWNER_OK: ReasonCode = ReasonCode(2);
+const ARCHIVED: ReasonCode = ReasonCode(3);
+
+fn main() -> Result<(), gate0::PolicyError> {
+    println!("--- Gate0 Ownership Example ---");
+
+    let policy = Policy::builder()
+        .rule(Rule::allow(
+            Target {
+                principal: Matcher::Any,
+                action: Matcher::Exact("read"),
+                resource: Matcher::Any,
+                environment: Matcher::Any,
+            },
+            READ_OK,
+        ))
+        .rule(Rule::owner_allow(
+            Matcher::OneOf(&["edit", "delete"]),
+            OWNER_OK,
+        ))
+        .rule(Rule::new(
+            Effect::Deny,
+            Target {
+                principal: Matcher::Any,
+                action: Matcher::OneOf(&["edit", "delete"]),
+                resource: Matcher::Any,
+                environment: Matcher::Any,
+            },
+            Some(Condition::Equals {
+                attr: "archived",
+                value: Value::Bool(true),
+            }),
+            ARCHIVED,
+        ))
+        .build()?;
+
+    let alices: &[(&str, Value)] = &[(DEFAULT_OWNER_ATTR, Value::String("alice"))];
+    let archived: &[(&str, Value)] = &[
+        (DEFAULT_OWNER_ATTR, Value::String("alice")),
+        ("archived", Value::Bool(true)),
+    ];
+
+    let scenarios = [
+        ("Bob reads Alice's draft", "bob", "read", alices),
+        ("Alice edits her draft", "alice", "edit", alices),
+        ("Bob edits Alice's draft", "bob", "edit", alices),
+        (
+            "Alice deletes her archived report",
+            "alice",
+            "delete",
+            archived,
+        ),
+    ];
+    for (label, principal, action, ctx) in scenarios {
+        let request = Request::with_context(principal, action, "doc", ctx);
+        let decision = policy.evaluate(&request)?;
+        println!(
+            "{:<36} -> {:?} (reason {})",
+            label,
+            decision.effect,
+            decision.reason.value()
+        );
+    }
+    Ok(())
+}
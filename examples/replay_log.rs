@@ -0,0 +1,101 @@
+//! Illustrative scenario: Forensic decision replay.
+//!
+//! This example demonstrates the replay log:
+//! 1. A gateway records every decision made under policy v1 to a log file.
+//! 2. Policy v2 tightens production writes to require MFA.
+//! 3. The log is replayed against v2 to see exactly which past decisions
+//!    would have changed.
+//!
+//! Pass a path to keep the log (`cargo run --example replay_log -- out.g0rl`);
+//! otherwise a temporary file is used.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use gate0::replay::{replay, RecordedOutcome, ReplayReader, ReplayWriter};
+use gate0::{Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, Value};
+
+const WRITE_OK: ReasonCode = ReasonCode(1);
+
+fn policy(require_mfa: bool) -> Result<Policy<'static>, gate0::PolicyError> {
+    let condition = if require_mfa {
+        Some(Condition::Equals {
+            attr: "mfa",
+            value: Value::Bool(true),
+        })
+    } else {
+        None
+    };
+    Policy::builder()
+        .rule(Rule::new(
+            Effect::Allow,
+            Target {
+                principal: Matcher::Any,
+                action: Matcher::Exact("write"),
+                resource: Matcher::Any,
+                environment: Matcher::Any,
+            },
+            condition,
+            WRITE_OK,
+        ))
+        .build()
+}
+
+fn describe(outcome: &RecordedOutcome) -> String {
+    match outcome {
+        RecordedOutcome::Decision(d) => format!("{:?}({})", d.effect, d.reason.value()),
+        RecordedOutcome::Error(e) => format!("Error({})", e),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args()
+        .nth(1)
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("gate0-replay-example.g0rl"));
+
+    println!("--- Gate0 Replay Example ---");
+
+    // 1. Record traffic under v1.
+    let v1 = policy(false)?;
+    let mut writer = ReplayWriter::new(BufWriter::new(File::create(&path)?))?;
+    let with_mfa: &[(&str, Value)] = &[("mfa", Value::Bool(true))];
+    let without_mfa: &[(&str, Value)] = &[("mfa", Value::Bool(false))];
+    writer.evaluate_and_record(
+        &v1,
+        &Request::with_context("alice", "write", "db", with_mfa),
+    )??;
+    writer.evaluate_and_record(
+        &v1,
+        &Request::with_context("bob", "write", "db", without_mfa),
+    )??;
+    writer.evaluate_and_record(&v1, &Request::new("carol", "read", "db"))??;
+    writer.into_inner()?;
+    println!("Recorded 3 decisions under v1 ({:016x})", v1.fingerprint());
+
+    // 2. Replay against v2.
+    let v2 = policy(true)?;
+    let reader = ReplayReader::new(BufReader::new(File::open(&path)?))?;
+    let report = replay(&v2, reader)?;
+    println!(
+        "Replayed {} decisions against v2 ({:016x})",
+        report.total,
+        v2.fingerprint()
+    );
+
+    // 3. Show the behavioral diff.
+    for diff in &report.changed {
+        println!(
+            "  #{} {} {} {}: {} -> {}",
+            diff.index,
+            diff.record.principal,
+            diff.record.action,
+            diff.record.resource,
+            describe(&diff.record.outcome),
+            describe(&diff.replayed)
+        );
+    }
+    assert_eq!(report.changed.len(), 1);
+
+    Ok(())
+}
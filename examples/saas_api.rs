@@ -3,9 +3,12 @@
 //! This example demonstrates standard RBAC/Multi-tenancy logic:
 //! 1. Admins have full access to their tenant's resources.
 //! 2. Users can read/list resources within their tenant.
-//! 3. Cross-tenant access is denied by default.
+//! 3. Cross-tenant access is denied by `TenantScopedPolicy`, whatever the rules say.
 
-use gate0::{Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, Value};
+use gate0::{
+    context, Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, TenantBinding,
+    TenantScopedPolicy, Value,
+};
 
 // Application-specific reason codes
 const ADMIN_ACCESS: ReasonCode = ReasonCode(100);
@@ -32,6 +35,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 principal: Matcher::Any,
                 action: Matcher::OneOf(&["read", "list"]),
                 resource: Matcher::Any,
+                environment: Matcher::Any,
             },
             Some(Condition::Equals {
                 attr: "role",
@@ -39,37 +43,46 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }),
             MEMBER_READ,
         ))
-        // Rule: Explicitly deny if tenant_id doesn't match resource owner (ABAC pattern)
-        // In a real app, you might compare request.tenant_id to resource.tenant_id
         .build()?;
 
+    // Resources are named "<tenant>/<id>"; the wrapper denies any request
+    // whose tenant_id does not match the resource prefix.
+    let policy = TenantScopedPolicy::new(policy, TenantBinding::Prefix('/'), CROSS_TENANT_DENY);
+
     println!("--- Gate0 SaaS API Example ---");
 
     // Scenario A: Admin trying to update a resource
-    let alice_ctx: &[(&str, Value)] = &[
-        ("role", Value::String("admin")),
-        ("tenant_id", Value::String("tenant-1")),
-    ];
-    let req_a = Request::with_context("alice", "update", "doc-123", alice_ctx);
+    let alice_ctx = context! {
+        "role" => "admin",
+        "tenant_id" => "tenant-1",
+    };
+    let req_a = Request::with_context("alice", "update", "tenant-1/doc-123", &alice_ctx);
     let dec_a = policy.evaluate(&req_a)?;
-    println!("Alice (Admin) update doc-123: {:?}", dec_a.effect);
+    println!("Alice (Admin) update tenant-1/doc-123: {:?}", dec_a.effect);
     assert!(dec_a.is_allow());
 
     // Scenario B: Regular member trying to update a resource (Denied)
-    let bob_ctx: &[(&str, Value)] = &[
-        ("role", Value::String("member")),
-        ("tenant_id", Value::String("tenant-1")),
-    ];
-    let req_b = Request::with_context("bob", "update", "doc-123", bob_ctx);
+    let bob_ctx = context! {
+        "role" => "member",
+        "tenant_id" => "tenant-1",
+    };
+    let req_b = Request::with_context("bob", "update", "tenant-1/doc-123", &bob_ctx);
     let dec_b = policy.evaluate(&req_b)?;
-    println!("Bob (Member) update doc-123: {:?}", dec_b.effect);
+    println!("Bob (Member) update tenant-1/doc-123: {:?}", dec_b.effect);
     assert!(dec_b.is_deny());
 
     // Scenario C: Regular member trying to read a resource (Allowed)
-    let req_c = Request::with_context("bob", "read", "doc-123", bob_ctx);
+    let req_c = Request::with_context("bob", "read", "tenant-1/doc-123", &bob_ctx);
     let dec_c = policy.evaluate(&req_c)?;
-    println!("Bob (Member) read doc-123: {:?}", dec_c.effect);
+    println!("Bob (Member) read tenant-1/doc-123: {:?}", dec_c.effect);
     assert!(dec_c.is_allow());
 
+    // Scenario D: Admin trying to read another tenant's resource (Denied)
+    let req_d = Request::with_context("alice", "read", "tenant-2/doc-9", &alice_ctx);
+    let dec_d = policy.evaluate(&req_d)?;
+    println!("Alice (Admin) read tenant-2/doc-9: {:?}", dec_d.effect);
+    assert!(dec_d.is_deny());
+    assert_eq!(dec_d.reason, CROSS_TENANT_DENY);
+
     Ok(())
 }
@@ -5,7 +5,9 @@
 //! 2. Access is restricted based on IP ranges (simulated via context).
 //! 3. High-security resources require an additional 'secure_device' flag.
 
-use gate0::{Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, Value};
+use gate0::{
+    context, Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, Value,
+};
 
 const ACCESS_GRANTED: ReasonCode = ReasonCode(200);
 const MFA_REQUIRED: ReasonCode = ReasonCode(401);
@@ -40,6 +42,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 principal: Matcher::Any,
                 action: Matcher::Exact("ssh_connect"),
                 resource: Matcher::Exact("dev-server"),
+                environment: Matcher::Any,
             },
             ACCESS_GRANTED,
         ))
@@ -48,21 +51,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("--- Gate0 Zero-Trust Network Example ---");
 
     // Scenario 1: Authenticated user from trusted location
-    let alice_ctx: &[(&str, Value)] = &[
-        ("mfa_authenticated", Value::Bool(true)),
-        ("country", Value::String("US")),
-    ];
-    let req1 = Request::with_context("alice", "ssh_connect", "dev-server", alice_ctx);
+    let alice_ctx = context! {
+        "mfa_authenticated" => true,
+        "country" => "US",
+    };
+    let req1 = Request::with_context("alice", "ssh_connect", "dev-server", &alice_ctx);
     let dec1 = policy.evaluate(&req1)?;
     println!("Alice (MFA=True, US) -> dev-server: {:?}", dec1.effect);
     assert!(dec1.is_allow());
 
     // Scenario 2: User forgot MFA (Denied with MFA_REQUIRED)
-    let bob_ctx: &[(&str, Value)] = &[
-        ("mfa_authenticated", Value::Bool(false)),
-        ("country", Value::String("US")),
-    ];
-    let req2 = Request::with_context("bob", "ssh_connect", "dev-server", bob_ctx);
+    let bob_ctx = context! {
+        "mfa_authenticated" => false,
+        "country" => "US",
+    };
+    let req2 = Request::with_context("bob", "ssh_connect", "dev-server", &bob_ctx);
     let dec2 = policy.evaluate(&req2)?;
     println!(
         "Bob (MFA=False, US) -> dev-server: {:?} (Reason: {:?})",
@@ -72,11 +75,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(dec2.reason, MFA_REQUIRED);
 
     // Scenario 3: Authenticated user from untrusted location (Denied with UNTRUSTED_LOCATION)
-    let charlie_ctx: &[(&str, Value)] = &[
-        ("mfa_authenticated", Value::Bool(true)),
-        ("country", Value::String("untrusted")),
-    ];
-    let req3 = Request::with_context("charlie", "ssh_connect", "dev-server", charlie_ctx);
+    let charlie_ctx = context! {
+        "mfa_authenticated" => true,
+        "country" => "untrusted",
+    };
+    let req3 = Request::with_context("charlie", "ssh_connect", "dev-server", &charlie_ctx);
     let dec3 = policy.evaluate(&req3)?;
     println!(
         "Charlie (MFA=True, Untrusted) -> dev-server: {:?} (Reason: {:?})",
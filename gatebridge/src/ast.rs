@@ -3,37 +3,77 @@
 //! These types represent the parsed YAML policy structure.
 //! Kept deliberately simple - this is data, not behavior.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Root of a policy file.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PolicyFile {
     #[serde(default = "default_version")]
     pub policy_schema_version: u32,
     pub default: DefaultPolicy,
     #[serde(default)]
     pub policies: Vec<Policy>,
+    /// Host groups that restrict policies to some target hosts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<HostGroup>,
 }
 
 fn default_version() -> u32 { 1 }
 
+impl PolicyFile {
+    /// Host patterns of every group that lists `policy`, or `None` if no
+    /// group does and the policy applies to every host.
+    pub fn host_patterns(&self, policy: &str) -> Option<Vec<String>> {
+        let mut patterns = None;
+        for group in self.hosts.iter().filter(|g| g.policies.iter().any(|p| p == policy)) {
+            patterns
+                .get_or_insert_with(Vec::new)
+                .extend(group.patterns.iter().cloned());
+        }
+        patterns
+    }
+}
+
+/// A named set of target hosts and the policies restricted to them.
+///
+/// A policy listed in any group only matches requests whose `target_host`
+/// matches a pattern of one of its groups. Policies in no group apply to
+/// every host.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HostGroup {
+    pub name: String,
+    /// fnmatch patterns, e.g. `db-*.prod.example.com`.
+    pub patterns: Vec<String>,
+    /// Names of the policies restricted to these hosts.
+    pub policies: Vec<String>,
+}
+
 /// Fallback when no policy matches.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DefaultPolicy {
     pub principals: Vec<String>,
     pub max_duration: String,
 }
 
 /// A single policy entry.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Policy {
     pub name: String,
-    #[serde(default)]
+    #[serde(default, rename(serialize = "match"), skip_serializing_if = "MatchBlock::is_empty")]
     pub match_block: MatchBlock,
     pub principals: Vec<String>,
     pub max_duration: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub trust_budget: Option<TrustBudget>,
+    /// Stable code for the gateway to log instead of the policy name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason_code: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audit_tags: Vec<String>,
+    /// Emergency access: only matches requests carrying a justification,
+    /// and marks the result as break-glass for audit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub break_glass: bool,
 }
 
 // serde expects "match" but that's a keyword, so we rename it
@@ -45,24 +85,24 @@ impl Policy {
 
 /// Match conditions for a policy.
 /// First three are OR triggers, last three are AND filters.
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct MatchBlock {
     // OR triggers - at least one must match
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub oidc_groups: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub emails: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub local_usernames: Vec<String>,
 
     // AND filters - all specified must match
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub source_ip: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub hours: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub is_business_hours: Option<bool>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub webauthn_ids: Vec<String>,
 }
 
@@ -90,6 +130,11 @@ impl MatchBlock {
             || self.is_business_hours.is_some()
             || !self.webauthn_ids.is_empty()
     }
+
+    /// True if nothing is specified: the policy matches every request.
+    pub fn is_empty(&self) -> bool {
+        !self.has_triggers() && !self.has_filters()
+    }
 }
 
 /// A request to evaluate against the policy.
@@ -106,11 +151,16 @@ pub struct EvalRequest {
     pub hour_utc: u8,
     pub weekday_utc: String, // Expect lowercase "monday", etc.
     pub webauthn_id: Option<String>,
+    /// Host being logged into; checked against `hosts` groups.
+    pub target_host: Option<String>,
+    /// Why emergency access is needed; required by break-glass policies.
+    pub justification: Option<String>,
 }
 
 impl EvalRequest {
     /// Canonicalize the request (e.g., lowercase identity fields).
     /// Bridge responsibility: ensure input is in "Gold Standard" form.
+    /// The justification is free text and is kept as given.
     pub fn normalize(&mut self) {
         if let Some(e) = self.email.as_mut() {
             *e = e.to_lowercase();
@@ -121,8 +171,29 @@ impl EvalRequest {
         for g in self.oidc_groups.iter_mut() {
             *g = g.to_lowercase();
         }
+        if let Some(h) = self.target_host.as_mut() {
+            *h = h.to_lowercase();
+        }
         self.weekday_utc = self.weekday_utc.to_lowercase();
     }
+
+    /// Fill `hour_utc` and `weekday_utc` from `clock`.
+    /// `is_business_hours` stays the caller's call: its definition is site-specific.
+    pub fn stamp_time(&mut self, clock: &dyn gate0::Clock) {
+        const WEEKDAYS: [&str; 7] = [
+            "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+        ];
+        let now = clock.now();
+        // 1970-01-01 was a Thursday (index 3 with Monday = 0).
+        let weekday = (now.div_euclid(86_400) + 3).rem_euclid(7) as usize;
+        self.hour_utc = (now.rem_euclid(86_400) / 3600) as u8;
+        self.weekday_utc = WEEKDAYS[weekday].to_string();
+    }
+
+    /// The justification, if it has any non-whitespace text.
+    pub fn justification(&self) -> Option<&str> {
+        self.justification.as_deref().filter(|j| !j.trim().is_empty())
+    }
 }
 
 impl Default for EvalRequest {
@@ -136,10 +207,13 @@ impl Default for EvalRequest {
             hour_utc: 0,
             weekday_utc: "monday".to_string(),
             webauthn_id: None,
+            target_host: None,
+            justification: None,
         }
     }
 }
 
+
 /// Result of policy evaluation.
 #[derive(Debug, Clone, PartialEq)]
 pub struct EvalResult {
@@ -149,6 +223,12 @@ pub struct EvalResult {
     pub principals: Vec<String>,
     pub max_duration: String,
     pub trust_budget: Option<TrustBudget>,
+    pub reason_code: Option<u32>,
+    pub audit_tags: Vec<String>,
+    /// Set when a break-glass policy matched; audit logs must flag these.
+    pub break_glass: bool,
+    /// The request's justification, for break-glass results.
+    pub justification: Option<String>,
 }
 
 impl EvalResult {
@@ -160,10 +240,14 @@ impl EvalResult {
             principals: default.principals.clone(),
             max_duration: default.max_duration.clone(),
             trust_budget: None,
+            reason_code: None,
+            audit_tags: vec![],
+            break_glass: false,
+            justification: None,
         }
     }
 
-    pub fn from_policy(policy: &Policy, index: usize) -> Self {
+    pub fn from_policy(policy: &Policy, index: usize, request: &EvalRequest) -> Self {
         EvalResult {
             matched: true,
             policy_name: Some(policy.name.clone()),
@@ -171,6 +255,14 @@ impl EvalResult {
             principals: policy.principals.clone(),
             max_duration: policy.max_duration.clone(),
             trust_budget: policy.trust_budget.clone(),
+            reason_code: policy.reason_code,
+            audit_tags: policy.audit_tags.clone(),
+            break_glass: policy.break_glass,
+            justification: if policy.break_glass {
+                request.justification().map(str::to_string)
+            } else {
+                None
+            },
         }
     }
 }
@@ -192,6 +284,8 @@ mod tests {
             hour_utc: 14,
             weekday_utc: "MONDAY".to_string(),
             webauthn_id: Some("yubi-123".to_string()),
+            target_host: Some("Bastion-1.Example.com".to_string()),
+            justification: Some("INC-42: Outage".to_string()),
         };
 
         // First normalization
@@ -211,6 +305,23 @@ mod tests {
         // Verify lowercase
         assert_eq!(after_second.email, Some("alice@example.com".to_string()));
         assert_eq!(after_second.weekday_utc, "monday");
+        assert_eq!(after_second.target_host, Some("bastion-1.example.com".to_string()));
+        assert_eq!(after_second.justification(), Some("INC-42: Outage"));
+    }
+
+    #[test]
+    fn test_stamp_time_from_frozen_clock() {
+        // 2024-01-06 14:30 UTC, a Saturday.
+        let clock = gate0::FrozenClock::new(1_704_551_400);
+        let mut request = EvalRequest::default();
+        request.stamp_time(&clock);
+        assert_eq!(request.hour_utc, 14);
+        assert_eq!(request.weekday_utc, "saturday");
+
+        clock.advance(10 * 3600);
+        request.stamp_time(&clock);
+        assert_eq!(request.hour_utc, 0);
+        assert_eq!(request.weekday_utc, "sunday");
     }
 
     /// Bridge Context Contract Test: Verify serialization is stable and deterministic.
@@ -225,6 +336,8 @@ mod tests {
             hour_utc: 14,
             weekday_utc: "monday".to_string(),
             webauthn_id: None,
+            target_host: Some("bastion-1".to_string()),
+            justification: None,
         };
         request.normalize();
 
@@ -0,0 +1,362 @@
+//! Programmatic policy construction.
+//!
+//! For provisioning systems that generate policies in Rust rather than
+//! templating YAML. `build` applies the same checks as strict loading, and
+//! `PolicyFile::to_yaml` writes a file that loads back unchanged.
+
+use std::collections::HashSet;
+
+use crate::ast::{DefaultPolicy, HostGroup, MatchBlock, Policy, PolicyFile, TrustBudget};
+use crate::loader::{check_default, check_hosts, check_policy};
+
+/// Builds a `PolicyFile`.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyFileBuilder {
+    default: Option<DefaultPolicy>,
+    policies: Vec<PolicyBuilder>,
+    hosts: Vec<HostGroup>,
+}
+
+/// Builds one `Policy`; added to a file with `PolicyFileBuilder::policy`.
+#[derive(Debug, Clone)]
+pub struct PolicyBuilder {
+    name: String,
+    match_block: MatchBlock,
+    principals: Vec<String>,
+    max_duration: Option<String>,
+    trust_budget: Option<TrustBudget>,
+    reason_code: Option<u32>,
+    audit_tags: Vec<String>,
+    break_glass: bool,
+}
+
+/// Errors from `PolicyFileBuilder::build` and `PolicyFile::to_yaml`.
+#[derive(Debug)]
+pub enum BuildError {
+    /// Validation failures, all of them, in policy order.
+    Invalid(Vec<String>),
+    /// YAML serialization failed.
+    Serialize(String),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Invalid(v) => write!(f, "Invalid policy: {}", v.join("; ")),
+            BuildError::Serialize(e) => write!(f, "Serialize error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+fn strings<I, S>(items: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    items.into_iter().map(Into::into).collect()
+}
+
+impl PolicyFile {
+    /// Start building a policy file.
+    pub fn builder() -> PolicyFileBuilder {
+        PolicyFileBuilder::default()
+    }
+
+    /// Serialize as YAML in the format `parse_policy` reads.
+    pub fn to_yaml(&self) -> Result<String, BuildError> {
+        serde_yaml::to_string(self).map_err(|e| BuildError::Serialize(e.to_string()))
+    }
+}
+
+impl Policy {
+    /// Start building a policy named `name`.
+    pub fn builder(name: impl Into<String>) -> PolicyBuilder {
+        PolicyBuilder {
+            name: name.into(),
+            match_block: MatchBlock::default(),
+            principals: vec![],
+            max_duration: None,
+            trust_budget: None,
+            reason_code: None,
+            audit_tags: vec![],
+            break_glass: false,
+        }
+    }
+}
+
+impl PolicyFileBuilder {
+    /// Set the fallback used when no policy matches.
+    pub fn default_policy<I, S>(mut self, principals: I, max_duration: impl Into<String>) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.default = Some(DefaultPolicy {
+            principals: strings(principals),
+            max_duration: max_duration.into(),
+        });
+        self
+    }
+
+    /// Append a policy. Policies are evaluated in the order added.
+    pub fn policy(mut self, policy: PolicyBuilder) -> Self {
+        self.policies.push(policy);
+        self
+    }
+
+    /// Add a host group restricting `policies` to hosts matching `patterns`.
+    pub fn host_group<P, Q, S, T>(mut self, name: impl Into<String>, patterns: P, policies: Q) -> Self
+    where
+        P: IntoIterator<Item = S>,
+        S: Into<String>,
+        Q: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.hosts.push(HostGroup {
+            name: name.into(),
+            patterns: strings(patterns),
+            policies: strings(policies),
+        });
+        self
+    }
+
+    /// Validate and build.
+    pub fn build(self) -> Result<PolicyFile, BuildError> {
+        let mut violations = Vec::new();
+        let default = match self.default {
+            Some(default) => {
+                check_default(&default, &mut violations);
+                default
+            }
+            None => {
+                violations.push("default: missing".to_string());
+                DefaultPolicy { principals: vec![], max_duration: String::new() }
+            }
+        };
+
+        let mut policies = Vec::with_capacity(self.policies.len());
+        let mut missing_duration = Vec::with_capacity(self.policies.len());
+        for builder in self.policies {
+            missing_duration.push(builder.max_duration.is_none());
+            policies.push(Policy {
+                name: builder.name,
+                match_block: builder.match_block,
+                principals: builder.principals,
+                max_duration: builder.max_duration.unwrap_or_default(),
+                trust_budget: builder.trust_budget,
+                reason_code: builder.reason_code,
+                audit_tags: builder.audit_tags,
+                break_glass: builder.break_glass,
+            });
+        }
+
+        let mut names = HashSet::new();
+        for (i, policy) in policies.iter().enumerate() {
+            let place = format!("policies[{}] ({})", i, policy.name);
+            if missing_duration[i] {
+                violations.push(format!("{}: missing max_duration", place));
+            }
+            let scoped = self.hosts.iter().any(|g| g.policies.contains(&policy.name));
+            check_policy(&place, policy, scoped, &mut names, &mut violations);
+        }
+        check_hosts(&self.hosts, &policies, &mut violations);
+
+        if violations.is_empty() {
+            Ok(PolicyFile { policy_schema_version: 1, default, policies, hosts: self.hosts })
+        } else {
+            Err(BuildError::Invalid(violations))
+        }
+    }
+}
+
+impl PolicyBuilder {
+    /// SSH principals granted when the policy matches.
+    pub fn principals<I, S>(mut self, principals: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.principals = strings(principals);
+        self
+    }
+
+    /// Maximum certificate validity, e.g. `"60m"`.
+    pub fn max_duration(mut self, max_duration: impl Into<String>) -> Self {
+        self.max_duration = Some(max_duration.into());
+        self
+    }
+
+    /// Replace the whole match block.
+    pub fn match_block(mut self, match_block: MatchBlock) -> Self {
+        self.match_block = match_block;
+        self
+    }
+
+    /// OR trigger: any of these OIDC groups.
+    pub fn oidc_groups<I, S>(mut self, groups: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.match_block.oidc_groups = strings(groups);
+        self
+    }
+
+    /// OR trigger: any of these email patterns.
+    pub fn emails<I, S>(mut self, emails: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.match_block.emails = strings(emails);
+        self
+    }
+
+    /// OR trigger: any of these local username patterns.
+    pub fn local_usernames<I, S>(mut self, usernames: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.match_block.local_usernames = strings(usernames);
+        self
+    }
+
+    /// AND filter: source IP in one of these CIDRs.
+    pub fn source_ip<I, S>(mut self, cidrs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.match_block.source_ip = strings(cidrs);
+        self
+    }
+
+    /// AND filter: UTC hour in one of these `"HH:MM-HH:MM"` ranges.
+    pub fn hours<I, S>(mut self, ranges: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.match_block.hours = strings(ranges);
+        self
+    }
+
+    /// AND filter: business hours must equal `required`.
+    pub fn business_hours(mut self, required: bool) -> Self {
+        self.match_block.is_business_hours = Some(required);
+        self
+    }
+
+    /// AND filter: WebAuthn credential is one of these IDs.
+    pub fn webauthn_ids<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.match_block.webauthn_ids = strings(ids);
+        self
+    }
+
+    /// Trust budget charged when the policy matches.
+    pub fn trust_budget(mut self, trust_budget: TrustBudget) -> Self {
+        self.trust_budget = Some(trust_budget);
+        self
+    }
+
+    /// Stable code returned in `EvalResult::reason_code`.
+    pub fn reason_code(mut self, code: u32) -> Self {
+        self.reason_code = Some(code);
+        self
+    }
+
+    /// Tags returned in `EvalResult::audit_tags`.
+    pub fn audit_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.audit_tags = strings(tags);
+        self
+    }
+
+    /// Emergency access: require a justification and mark results.
+    pub fn break_glass(mut self) -> Self {
+        self.break_glass = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{parse_policy_with, LoadOptions};
+
+    #[test]
+    fn test_build_round_trips_through_yaml() {
+        let file = PolicyFile::builder()
+            .default_policy(["sandbox"], "15m")
+            .policy(
+                Policy::builder("AdminAccess")
+                    .oidc_groups(["admins"])
+                    .source_ip(["10.0.0.0/8"])
+                    .business_hours(true)
+                    .principals(["root"])
+                    .max_duration("60m")
+                    .reason_code(7)
+                    .audit_tags(["privileged"]),
+            )
+            .policy(
+                Policy::builder("DevAccess")
+                    .emails(["*@dev.example.com"])
+                    .principals(["developer"])
+                    .max_duration("30m"),
+            )
+            .policy(
+                Policy::builder("Emergency")
+                    .oidc_groups(["oncall"])
+                    .principals(["root"])
+                    .max_duration("15m")
+                    .break_glass(),
+            )
+            .host_group("dev-boxes", ["dev-*"], ["DevAccess"])
+            .build()
+            .unwrap();
+
+        let yaml = file.to_yaml().unwrap();
+        assert!(yaml.contains("match:"));
+        assert!(!yaml.contains("match_block"));
+        assert!(!yaml.contains("trust_budget"));
+        assert_eq!(yaml.matches("break_glass: true").count(), 1);
+
+        let loaded = parse_policy_with(&yaml, LoadOptions { strict: true }).unwrap();
+        assert_eq!(loaded.to_yaml().unwrap(), yaml);
+        assert_eq!(loaded.policies[0].match_block.source_ip, vec!["10.0.0.0/8"]);
+        assert_eq!(loaded.policies[0].reason_code, Some(7));
+        assert_eq!(loaded.policies[1].match_block.emails, vec!["*@dev.example.com"]);
+        assert_eq!(loaded.host_patterns("DevAccess"), Some(vec!["dev-*".to_string()]));
+        assert!(!loaded.policies[1].break_glass);
+        assert!(loaded.policies[2].break_glass);
+    }
+
+    #[test]
+    fn test_build_reports_all_violations() {
+        let err = PolicyFile::builder()
+            .policy(Policy::builder("Open").principals(["root"]).max_duration("60m"))
+            .policy(Policy::builder("Open").oidc_groups(["admins"]))
+            .build()
+            .unwrap_err();
+        match err {
+            BuildError::Invalid(v) => assert_eq!(v, vec![
+                "default: missing",
+                "policies[0] (Open): no triggers or filters, matches every request",
+                "policies[1] (Open): missing max_duration",
+                "policies[1] (Open): empty principals",
+                "policies[1] (Open): duplicate policy name",
+            ]),
+            other => panic!("expected violations, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Behavioral diff between two policy files.
+//!
+//! Evaluates a corpus of requests against an old and a new policy file with
+//! the reference evaluator and reports every request whose outcome changes,
+//! so a policy PR can show what it actually does.
+
+use crate::ast::{EvalRequest, EvalResult, PolicyFile};
+use crate::loader::LoadError;
+use crate::reference_evaluate;
+
+/// A request whose outcome differs between the two policy files.
+#[derive(Debug, Clone)]
+pub struct DecisionChange {
+    /// Position of the request in the corpus (0-based).
+    pub index: usize,
+    /// The request, normalized.
+    pub request: EvalRequest,
+    /// Outcome under the old policy file.
+    pub old: EvalResult,
+    /// Outcome under the new policy file.
+    pub new: EvalResult,
+}
+
+/// Evaluate `requests` against both files and return the changed outcomes.
+///
+/// Two outcomes are the same if they grant the same principals, duration,
+/// trust budget, reason code, audit tags, and break-glass marker from a policy of the same name. A policy moving to a
+/// different index is not a change.
+pub fn diff_eval(old: &PolicyFile, new: &PolicyFile, requests: &[EvalRequest]) -> Vec<DecisionChange> {
+    let mut changes = Vec::new();
+    for (index, request) in requests.iter().enumerate() {
+        let mut request = request.clone();
+        request.normalize();
+        let before = reference_evaluate(old, &request);
+        let after = reference_evaluate(new, &request);
+        if !same_outcome(&before, &after) {
+            changes.push(DecisionChange {
+                index,
+                request,
+                old: before,
+                new: after,
+            });
+        }
+    }
+    changes
+}
+
+fn same_outcome(a: &EvalResult, b: &EvalResult) -> bool {
+    a.matched == b.matched
+        && a.policy_name == b.policy_name
+        && a.principals == b.principals
+        && a.max_duration == b.max_duration
+        && a.trust_budget == b.trust_budget
+        && a.reason_code == b.reason_code
+        && a.audit_tags == b.audit_tags
+        && a.break_glass == b.break_glass
+}
+
+/// Parse a JSON Lines corpus: one `EvalRequest` per line, blank lines skipped.
+pub fn parse_request_corpus(jsonl: &str) -> Result<Vec<EvalRequest>, LoadError> {
+    let mut requests = Vec::new();
+    for (number, line) in jsonl.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = serde_json::from_str(line)
+            .map_err(|e| LoadError::Parse(format!("line {}: {}", number + 1, e)))?;
+        requests.push(request);
+    }
+    Ok(requests)
+}
+
+/// One line describing an outcome, e.g. `AdminAccess -> [root] for 60m`,
+/// with ` (break-glass)` appended for break-glass policies.
+pub fn describe_outcome(result: &EvalResult) -> String {
+    let name = result.policy_name.as_deref().unwrap_or("default");
+    let marker = if result.break_glass { " (break-glass)" } else { "" };
+    format!("{} -> {:?} for {}{}", name, result.principals, result.max_duration, marker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::parse_policy;
+
+    const OLD: &str = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "AdminAccess"
+    match:
+      oidc_groups: ["admins"]
+    principals: ["root"]
+    max_duration: "60m"
+  - name: "DevAccess"
+    match:
+      oidc_groups: ["devs"]
+    principals: ["developer"]
+    max_duration: "30m"
+"#;
+
+    #[test]
+    fn test_diff_eval() {
+        // Admin sessions shortened; developer access unchanged.
+        let new = OLD.replace("60m", "20m");
+        let old = parse_policy(OLD).unwrap();
+        let new = parse_policy(&new).unwrap();
+
+        let corpus = r#"
+{"oidc_groups": ["ADMINS"], "email": null, "local_username": null, "source_ip": null, "is_business_hours": false, "hour_utc": 0, "weekday_utc": "monday", "webauthn_id": null}
+
+{"oidc_groups": ["devs"], "email": null, "local_username": null, "source_ip": null, "is_business_hours": false, "hour_utc": 0, "weekday_utc": "monday", "webauthn_id": null}
+"#;
+        let requests = parse_request_corpus(corpus).unwrap();
+        assert_eq!(requests.len(), 2);
+
+        let changes = diff_eval(&old, &new, &requests);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].index, 0);
+        assert_eq!(changes[0].request.oidc_groups, vec!["admins"]);
+        assert_eq!(describe_outcome(&changes[0].old), r#"AdminAccess -> ["root"] for 60m"#);
+        assert_eq!(describe_outcome(&changes[0].new), r#"AdminAccess -> ["root"] for 20m"#);
+    }
+
+    #[test]
+    fn test_diff_eval_break_glass() {
+        let new = OLD.replace("max_duration: \"60m\"", "max_duration: \"60m\"\n    break_glass: true");
+        let old = parse_policy(OLD).unwrap();
+        let new = parse_policy(&new).unwrap();
+
+        let corpus = r#"
+{"oidc_groups": ["admins"], "email": null, "local_username": null, "source_ip": null, "is_business_hours": false, "hour_utc": 0, "weekday_utc": "monday", "webauthn_id": null, "justification": "INC-9"}
+{"oidc_groups": ["admins"], "email": null, "local_username": null, "source_ip": null, "is_business_hours": false, "hour_utc": 0, "weekday_utc": "monday", "webauthn_id": null}
+"#;
+        let requests = parse_request_corpus(corpus).unwrap();
+        let changes = diff_eval(&old, &new, &requests);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            describe_outcome(&changes[0].new),
+            r#"AdminAccess -> ["root"] for 60m (break-glass)"#
+        );
+        assert_eq!(describe_outcome(&changes[1].new), r#"default -> ["sandbox"] for 15m"#);
+    }
+
+    #[test]
+    fn test_corpus_errors_name_the_line() {
+        let err = parse_request_corpus("\n{not json}\n").unwrap_err();
+        assert!(err.to_string().starts_with("Parse error: line 2:"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Encrypted policy files (feature `encryption`).
+//!
+//! Policy files name users by email and describe the internal network, so
+//! bastion hosts may keep them encrypted at rest. An encrypted file is
+//! `ENCRYPTED_MAGIC`, a 12-byte nonce, and the AES-256-GCM ciphertext of the
+//! YAML; the magic is authenticated along with it. The key never touches
+//! the disk: the host app supplies it, directly or from a KMS.
+
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+use crate::ast::PolicyFile;
+use crate::loader::{parse_policy_with, LoadError, LoadOptions, ENCRYPTED_MAGIC};
+
+const NONCE_LEN: usize = 12;
+
+/// Supplies the AES-256 key for encrypted policy files.
+///
+/// Implemented for raw keys and for closures, so a KMS lookup can be
+/// passed as `|| kms.data_key("gatebridge")`.
+pub trait KeyProvider {
+    /// The 32-byte key, or why it is unavailable.
+    fn key(&self) -> Result<[u8; 32], String>;
+}
+
+impl KeyProvider for [u8; 32] {
+    fn key(&self) -> Result<[u8; 32], String> {
+        Ok(*self)
+    }
+}
+
+impl<F: Fn() -> Result<[u8; 32], String>> KeyProvider for F {
+    fn key(&self) -> Result<[u8; 32], String> {
+        self()
+    }
+}
+
+/// Encrypt policy YAML under the key from `keys`, with a fresh random nonce.
+pub fn encrypt_policy(yaml: &str, keys: &impl KeyProvider) -> Result<Vec<u8>, LoadError> {
+    let cipher = cipher(keys)?;
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let payload = Payload { msg: yaml.as_bytes(), aad: ENCRYPTED_MAGIC };
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .map_err(|_| LoadError::Decrypt("encryption failed".to_string()))?;
+
+    let mut out = Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(ENCRYPTED_MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt the output of `encrypt_policy` back to YAML.
+///
+/// A wrong key and a tampered file fail alike.
+pub fn decrypt_policy(bytes: &[u8], keys: &impl KeyProvider) -> Result<String, LoadError> {
+    let body = bytes
+        .strip_prefix(ENCRYPTED_MAGIC)
+        .ok_or_else(|| LoadError::Decrypt("not an encrypted policy file".to_string()))?;
+    if body.len() < NONCE_LEN {
+        return Err(LoadError::Decrypt("truncated file".to_string()));
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+    let cipher = cipher(keys)?;
+    let payload = Payload { msg: ciphertext, aad: ENCRYPTED_MAGIC };
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| LoadError::Decrypt("wrong key or corrupted file".to_string()))?;
+    String::from_utf8(plaintext).map_err(|e| LoadError::Decrypt(e.to_string()))
+}
+
+/// Load an encrypted policy file from disk with `options`.
+///
+/// Plaintext files are rejected, so a file that was never encrypted
+/// cannot pass for one that was.
+pub fn load_encrypted_policy_file(
+    path: &Path,
+    keys: &impl KeyProvider,
+    options: LoadOptions,
+) -> Result<PolicyFile, LoadError> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| LoadError::Io(e.to_string()))?;
+    let yaml = decrypt_policy(&bytes, keys)?;
+
+    parse_policy_with(&yaml, options)
+}
+
+fn cipher(keys: &impl KeyProvider) -> Result<Aes256Gcm, LoadError> {
+    let key = keys.key().map_err(LoadError::Decrypt)?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_policy_file;
+
+    const KEY: [u8; 32] = [7; 32];
+    const YAML: &str = r#"
+policy_schema_version: 1
+default:
+  principals: [sandbox]
+  max_duration: 15m
+policies:
+  - name: AdminAccess
+    match:
+      emails: [root@example.com]
+    principals: [root]
+    max_duration: 60m
+"#;
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = encrypt_policy(YAML, &KEY).unwrap();
+        assert!(bytes.starts_with(ENCRYPTED_MAGIC));
+        assert!(!String::from_utf8_lossy(&bytes).contains("root@example.com"));
+        assert_eq!(decrypt_policy(&bytes, &KEY).unwrap(), YAML);
+
+        // Fresh nonce per encryption
+        assert_ne!(encrypt_policy(YAML, &KEY).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_wrong_key_and_tampering() {
+        let mut bytes = encrypt_policy(YAML, &KEY).unwrap();
+        assert!(matches!(decrypt_policy(&bytes, &[8; 32]), Err(LoadError::Decrypt(_))));
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(matches!(decrypt_policy(&bytes, &KEY), Err(LoadError::Decrypt(_))));
+
+        let kms = || Err::<[u8; 32], _>("kms unavailable".to_string());
+        assert!(matches!(
+            decrypt_policy(&bytes, &kms),
+            Err(LoadError::Decrypt(e)) if e == "kms unavailable"
+        ));
+        assert!(matches!(decrypt_policy(YAML.as_bytes(), &KEY), Err(LoadError::Decrypt(_))));
+    }
+
+    #[test]
+    fn test_load_encrypted_file() {
+        let dir = std::env::temp_dir().join(format!("gatebridge-enc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("policy.yaml.enc");
+        std::fs::write(&path, encrypt_policy(YAML, &KEY).unwrap()).unwrap();
+
+        let kms = || Ok(KEY);
+        let file = load_encrypted_policy_file(&path, &kms, LoadOptions { strict: true }).unwrap();
+        assert_eq!(file.policies[0].name, "AdminAccess");
+        assert!(matches!(load_policy_file(&path), Err(LoadError::Encrypted)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 //! Step-by-step policy evaluation for debugging.
 
-use crate::ast::{EvalRequest, MatchBlock, Policy, PolicyFile};
+use crate::ast::{EvalRequest, Policy, PolicyFile};
 use crate::reference_eval::{check_cidr, check_exact, check_fnmatch, check_oidc_groups, check_time_range_from_hour};
 
 /// Result of explaining a single condition check.
@@ -39,7 +39,8 @@ pub fn explain(policy_file: &PolicyFile, request: &EvalRequest) -> ExplainResult
     let mut matched_index = None;
 
     for (index, policy) in policy_file.policies.iter().enumerate() {
-        let policy_explain = explain_policy(index, policy, request);
+        let hosts = policy_file.host_patterns(&policy.name);
+        let policy_explain = explain_policy(index, policy, hosts.as_deref(), request);
         
         if policy_explain.overall_matched && matched_policy.is_none() {
             matched_policy = Some(policy.name.clone());
@@ -56,7 +57,12 @@ pub fn explain(policy_file: &PolicyFile, request: &EvalRequest) -> ExplainResult
     }
 }
 
-fn explain_policy(index: usize, policy: &Policy, request: &EvalRequest) -> PolicyExplain {
+fn explain_policy(
+    index: usize,
+    policy: &Policy,
+    hosts: Option<&[String]>,
+    request: &EvalRequest,
+) -> PolicyExplain {
     let m = &policy.match_block;
     
     let mut triggers = Vec::new();
@@ -124,6 +130,26 @@ fn explain_policy(index: usize, policy: &Policy, request: &EvalRequest) -> Polic
         });
     }
 
+    if let Some(patterns) = hosts {
+        let matched = check_fnmatch(patterns, request.target_host.as_deref());
+        filters.push(ConditionExplain {
+            field: "hosts".to_string(),
+            pattern: format!("{:?}", patterns),
+            request_value: request.target_host.clone().unwrap_or_else(|| "(none)".to_string()),
+            matched,
+        });
+    }
+
+    if policy.break_glass {
+        let justification = request.justification();
+        filters.push(ConditionExplain {
+            field: "break_glass".to_string(),
+            pattern: "justification required".to_string(),
+            request_value: format!("{:?}", justification.unwrap_or("(none)")),
+            matched: justification.is_some(),
+        });
+    }
+
     // Compute pass/fail
     let trigger_passed = if triggers.is_empty() {
         true // No triggers = open policy
@@ -0,0 +1,176 @@
+//! Exports a YAML policy file as a gatelang document.
+//!
+//! gate0 has no JSON policy format; gatelang is its canonical text form.
+//! The exported document compiles to the same rules as `to_gate0`, so it
+//! uses the same precomputed attributes (`p{i}_trigger`, `p{i}_ip`, ...)
+//! and expects the context built by `shadow_evaluate`. Principals and
+//! durations have no gate0 equivalent and are kept as comments.
+
+use crate::ast::{MatchBlock, PolicyFile};
+
+/// Reason code of the catch-all rule, as in `to_gate0`.
+const DEFAULT_REASON: u32 = u32::MAX - 1;
+
+/// Render `policy_file` as gatelang source.
+///
+/// Each policy declares a reason named after it, uppercased with other
+/// characters replaced by `_`, whose code is the policy index.
+pub fn to_gatelang(policy_file: &PolicyFile) -> String {
+    let mut names: Vec<String> = Vec::new();
+    for policy in &policy_file.policies {
+        names.push(unique_name(reason_name(&policy.name), &names));
+    }
+    let default_name = unique_name("DEFAULT".to_string(), &names);
+
+    let mut out = String::from("# Exported by gatebridge.\n\n");
+    for (index, name) in names.iter().enumerate() {
+        out.push_str(&format!("reason {} = {};\n", name, index));
+    }
+    out.push_str(&format!("reason {} = {};\n", default_name, DEFAULT_REASON));
+
+    for (index, policy) in policy_file.policies.iter().enumerate() {
+        out.push('\n');
+        out.push_str(&format!(
+            "# {}: {:?} for {}\n",
+            comment(&policy.name),
+            policy.principals,
+            comment(&policy.max_duration)
+        ));
+        let host_scoped = policy_file.host_patterns(&policy.name).is_some();
+        match render_condition(index, &policy.match_block, host_scoped, policy.break_glass) {
+            Some(cond) => out.push_str(&format!("allow any on any if {} reason {};\n", cond, names[index])),
+            None => out.push_str(&format!("allow any on any reason {};\n", names[index])),
+        }
+    }
+
+    out.push_str(&format!(
+        "\n# default: {:?} for {}\nallow any on any reason {};\n",
+        policy_file.default.principals,
+        comment(&policy_file.default.max_duration),
+        default_name
+    ));
+    out
+}
+
+/// Same conditions, in the same nesting, as `translate::build_condition`.
+fn render_condition(
+    index: usize,
+    m: &MatchBlock,
+    host_scoped: bool,
+    break_glass: bool,
+) -> Option<String> {
+    let mut conditions = Vec::new();
+    if m.has_triggers() {
+        conditions.push(format!("p{}_trigger == true", index));
+    }
+    if !m.source_ip.is_empty() {
+        conditions.push(format!("p{}_ip == true", index));
+    }
+    if !m.hours.is_empty() {
+        conditions.push(format!("p{}_time == true", index));
+    }
+    if let Some(required) = m.is_business_hours {
+        conditions.push(format!("is_business_hours == {}", required));
+    }
+    if !m.webauthn_ids.is_empty() {
+        conditions.push(format!("p{}_webauthn == true", index));
+    }
+    if host_scoped {
+        conditions.push(format!("p{}_host == true", index));
+    }
+    if break_glass {
+        conditions.push("has_justification == true".to_string());
+    }
+
+    // to_gate0 nests to the right: a and (b and c).
+    let mut result = conditions.pop()?;
+    let mut nested = false;
+    while let Some(c) = conditions.pop() {
+        result = if nested {
+            format!("{} and ({})", c, result)
+        } else {
+            format!("{} and {}", c, result)
+        };
+        nested = true;
+    }
+    Some(result)
+}
+
+fn reason_name(policy_name: &str) -> String {
+    let mut name: String = policy_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert_str(0, "P_");
+    }
+    name
+}
+
+fn unique_name(name: String, taken: &[String]) -> String {
+    if !taken.contains(&name) {
+        return name;
+    }
+    let mut n = 2;
+    loop {
+        let candidate = format!("{}_{}", name, n);
+        if !taken.contains(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Keep a comment on one line.
+fn comment(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::parse_policy;
+    use crate::to_gate0;
+    use gate0::gatelang::PolicyDoc;
+
+    #[test]
+    fn test_export_compiles_to_same_policy() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "Admin Access"
+    match:
+      oidc_groups: ["admins"]
+      source_ip: ["10.0.0.0/8"]
+      is_business_hours: true
+    principals: ["root"]
+    max_duration: "60m"
+  - name: "admin-access"
+    principals: ["ops"]
+    max_duration: "30m"
+    break_glass: true
+hosts:
+  - name: "ops-boxes"
+    patterns: ["ops-*"]
+    policies: ["admin-access"]
+"#;
+        let policy_file = parse_policy(yaml).unwrap();
+        let source = to_gatelang(&policy_file);
+        assert!(source.contains("reason ADMIN_ACCESS = 0;"));
+        assert!(source.contains("reason ADMIN_ACCESS_2 = 1;"));
+        assert!(source.contains(
+            "if p0_trigger == true and (p0_ip == true and is_business_hours == true) reason ADMIN_ACCESS;"
+        ));
+        assert!(source.contains(
+            "allow any on any if p1_host == true and has_justification == true reason ADMIN_ACCESS_2;"
+        ));
+
+        let doc = PolicyDoc::parse(&source).unwrap();
+        let exported = doc.to_policy().unwrap();
+        let translated = to_gate0(&policy_file).unwrap();
+        assert_eq!(exported.fingerprint(), translated.fingerprint());
+        assert_eq!(doc.reason_name(gate0::ReasonCode(DEFAULT_REASON)), Some("DEFAULT"));
+    }
+}
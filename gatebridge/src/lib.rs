@@ -4,16 +4,40 @@
 //! and provides shadow evaluation for validation.
 
 mod ast;
+mod builder;
+mod diff;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod explain;
+mod export;
 mod loader;
+#[cfg(feature = "metrics")]
+mod metrics;
 pub mod reference_eval;
+#[cfg(feature = "async")]
+mod resolve;
 mod shadow;
+mod simulate;
 mod translate;
 
 pub use ast::*;
+pub use builder::{BuildError, PolicyBuilder, PolicyFileBuilder};
+pub use diff::{describe_outcome, diff_eval, parse_request_corpus, DecisionChange};
+#[cfg(feature = "encryption")]
+pub use encryption::{decrypt_policy, encrypt_policy, load_encrypted_policy_file, KeyProvider};
 pub use explain::{explain, format_explain, ExplainResult};
-pub use loader::{load_policy_file, parse_policy};
-pub use reference_eval::evaluate as reference_evaluate;
+pub use export::to_gatelang;
+pub use loader::{
+    load_policy_file, load_policy_file_with, parse_policy, parse_policy_with, LoadError, LoadOptions,
+};
+#[cfg(feature = "metrics")]
+pub use metrics::{HttpResponse, Metrics};
+pub use reference_eval::{
+    evaluate as reference_evaluate, evaluate_with_stats as reference_evaluate_with_stats, EvalStats,
+};
+#[cfg(feature = "async")]
+pub use resolve::{AsyncResolver, GroupResolver, ResolveError};
 pub use shadow::{shadow_evaluate, ShadowResult};
+pub use simulate::{parse_timestamp, simulate, SimulationResult, TimestampError};
 pub use translate::to_gate0;
 
@@ -1,16 +1,41 @@
 //! YAML policy loader
 //!
-//! Reads and parses policy files. Nothing fancy.
+//! Reads and parses policy files. Nothing fancy, unless `strict` is set.
 
+use std::collections::HashSet;
 use std::path::Path;
-use crate::ast::PolicyFile;
+use crate::ast::{DefaultPolicy, HostGroup, Policy, PolicyFile};
+
+/// How strictly to check a policy file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadOptions {
+    /// Reject unknown keys, empty `principals`, policies with no triggers
+    /// or filters, duplicate policy names, and host groups that are empty,
+    /// duplicated, or list unknown policies.
+    pub strict: bool,
+}
+
+/// Marks an encrypted policy file; see `load_encrypted_policy_file`.
+pub(crate) const ENCRYPTED_MAGIC: &[u8] = b"gatebridge-aes-256-gcm-v1\n";
 
 /// Load a policy file from disk.
+///
+/// Encrypted files are rejected with `LoadError::Encrypted`.
 pub fn load_policy_file(path: &Path) -> Result<PolicyFile, LoadError> {
-    let contents = std::fs::read_to_string(path)
+    load_policy_file_with(path, LoadOptions::default())
+}
+
+/// Load a policy file from disk with `options`.
+pub fn load_policy_file_with(path: &Path, options: LoadOptions) -> Result<PolicyFile, LoadError> {
+    let bytes = std::fs::read(path)
         .map_err(|e| LoadError::Io(e.to_string()))?;
-    
-    parse_policy(&contents)
+    if bytes.starts_with(ENCRYPTED_MAGIC) {
+        return Err(LoadError::Encrypted);
+    }
+    let contents = String::from_utf8(bytes)
+        .map_err(|e| LoadError::Io(e.to_string()))?;
+
+    parse_policy_with(&contents, options)
 }
 
 /// Parse policy from a YAML string.
@@ -22,10 +47,140 @@ pub fn parse_policy(yaml: &str) -> Result<PolicyFile, LoadError> {
         .map_err(|e| LoadError::Parse(e.to_string()))
 }
 
+/// Parse policy from a YAML string with `options`.
+///
+/// In strict mode every violation is reported at once, in file order.
+pub fn parse_policy_with(yaml: &str, options: LoadOptions) -> Result<PolicyFile, LoadError> {
+    if !options.strict {
+        return parse_policy(yaml);
+    }
+
+    let tree: serde_yaml::Value = serde_yaml::from_str(yaml)
+        .map_err(|e| LoadError::Parse(e.to_string()))?;
+    let policy_file = parse_policy(yaml)?;
+
+    let mut violations = Vec::new();
+    check_keys(&tree, "file", ROOT_KEYS, &mut violations);
+    if let Some(default) = tree.get("default") {
+        check_keys(default, "default", DEFAULT_KEYS, &mut violations);
+    }
+    check_default(&policy_file.default, &mut violations);
+
+    let policies = tree.get("policies").and_then(|p| p.as_sequence());
+    let mut names = HashSet::new();
+    for (i, policy) in policy_file.policies.iter().enumerate() {
+        let place = format!("policies[{}] ({})", i, policy.name);
+        if let Some(node) = policies.and_then(|p| p.get(i)) {
+            check_keys(node, &place, POLICY_KEYS, &mut violations);
+            if let Some(m) = node.get("match") {
+                check_keys(m, &format!("{} match", place), MATCH_KEYS, &mut violations);
+            }
+            if let Some(budget) = node.get("trust_budget") {
+                check_keys(budget, &format!("{} trust_budget", place), TRUST_BUDGET_KEYS, &mut violations);
+            }
+        }
+        let scoped = policy_file.host_patterns(&policy.name).is_some();
+        check_policy(&place, policy, scoped, &mut names, &mut violations);
+    }
+
+    let hosts = tree.get("hosts").and_then(|h| h.as_sequence());
+    for (i, group) in policy_file.hosts.iter().enumerate() {
+        if let Some(node) = hosts.and_then(|h| h.get(i)) {
+            check_keys(node, &format!("hosts[{}] ({})", i, group.name), HOST_KEYS, &mut violations);
+        }
+    }
+    check_hosts(&policy_file.hosts, &policy_file.policies, &mut violations);
+
+    if violations.is_empty() {
+        Ok(policy_file)
+    } else {
+        Err(LoadError::Invalid(violations))
+    }
+}
+
+pub(crate) fn check_default(default: &DefaultPolicy, violations: &mut Vec<String>) {
+    if default.principals.is_empty() {
+        violations.push("default: empty principals".to_string());
+    }
+}
+
+/// Strict checks on one policy; `names` collects names seen so far.
+/// `scoped` is whether a host group lists the policy.
+pub(crate) fn check_policy<'p>(
+    place: &str,
+    policy: &'p Policy,
+    scoped: bool,
+    names: &mut HashSet<&'p str>,
+    violations: &mut Vec<String>,
+) {
+    if policy.principals.is_empty() {
+        violations.push(format!("{}: empty principals", place));
+    }
+    if !policy.match_block.has_triggers() && !policy.match_block.has_filters() && !scoped {
+        violations.push(format!("{}: no triggers or filters, matches every request", place));
+    }
+    if !names.insert(policy.name.as_str()) {
+        violations.push(format!("{}: duplicate policy name", place));
+    }
+}
+
+/// Strict checks on the host groups.
+pub(crate) fn check_hosts(hosts: &[HostGroup], policies: &[Policy], violations: &mut Vec<String>) {
+    let mut names = HashSet::new();
+    for (i, group) in hosts.iter().enumerate() {
+        let place = format!("hosts[{}] ({})", i, group.name);
+        if group.patterns.is_empty() {
+            violations.push(format!("{}: empty patterns", place));
+        }
+        if group.policies.is_empty() {
+            violations.push(format!("{}: empty policies", place));
+        }
+        for name in &group.policies {
+            if !policies.iter().any(|p| p.name == *name) {
+                violations.push(format!("{}: unknown policy '{}'", place, name));
+            }
+        }
+        if !names.insert(group.name.as_str()) {
+            violations.push(format!("{}: duplicate host group name", place));
+        }
+    }
+}
+
+const ROOT_KEYS: &[&str] = &["policy_schema_version", "default", "policies", "hosts"];
+const DEFAULT_KEYS: &[&str] = &["principals", "max_duration"];
+const POLICY_KEYS: &[&str] = &[
+    "name", "match", "principals", "max_duration", "trust_budget", "reason_code", "audit_tags",
+    "break_glass",
+];
+const MATCH_KEYS: &[&str] = &[
+    "oidc_groups", "emails", "local_usernames",
+    "source_ip", "hours", "is_business_hours", "webauthn_ids",
+];
+const HOST_KEYS: &[&str] = &["name", "patterns", "policies"];
+const TRUST_BUDGET_KEYS: &[&str] = &["budget_id", "cost", "initial_balance", "reset_interval_hours"];
+
+fn check_keys(node: &serde_yaml::Value, place: &str, known: &[&str], violations: &mut Vec<String>) {
+    let Some(map) = node.as_mapping() else {
+        return;
+    };
+    for key in map.keys() {
+        let name = key.as_str().map(str::to_string).unwrap_or_else(|| format!("{:?}", key));
+        if !known.contains(&name.as_str()) {
+            violations.push(format!("{}: unknown key '{}'", place, name));
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum LoadError {
     Io(String),
     Parse(String),
+    /// Strict-mode violations, all of them.
+    Invalid(Vec<String>),
+    /// The file is encrypted and was loaded without a key.
+    Encrypted,
+    /// The key could not be obtained, or the file did not decrypt with it.
+    Decrypt(String),
 }
 
 impl std::fmt::Display for LoadError {
@@ -33,6 +188,9 @@ impl std::fmt::Display for LoadError {
         match self {
             LoadError::Io(e) => write!(f, "IO error: {}", e),
             LoadError::Parse(e) => write!(f, "Parse error: {}", e),
+            LoadError::Invalid(v) => write!(f, "Invalid policy: {}", v.join("; ")),
+            LoadError::Encrypted => write!(f, "Policy file is encrypted; load it with a key"),
+            LoadError::Decrypt(e) => write!(f, "Decryption error: {}", e),
         }
     }
 }
@@ -74,4 +232,82 @@ policies:
         assert_eq!(policy.policies[0].name, "AdminAccess");
         assert_eq!(policy.policies[0].match_block.oidc_groups, vec!["admins"]);
     }
+
+    #[test]
+    fn test_strict_reports_all_violations() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "AdminAccess"
+    match:
+      ocid_groups: ["admins"]
+    principals: ["root"]
+    max_duration: "60m"
+  - name: "AdminAccess"
+    match:
+      oidc_groups: ["admins"]
+    principals: []
+    max_duration: "60m"
+"#;
+        // Lenient loading accepts the typo; the policy just never matches.
+        assert!(parse_policy(yaml).is_ok());
+
+        let strict = LoadOptions { strict: true };
+        match parse_policy_with(yaml, strict) {
+            Err(LoadError::Invalid(v)) => assert_eq!(v, vec![
+                "policies[0] (AdminAccess) match: unknown key 'ocid_groups'",
+                "policies[0] (AdminAccess): no triggers or filters, matches every request",
+                "policies[1] (AdminAccess): empty principals",
+                "policies[1] (AdminAccess): duplicate policy name",
+            ]),
+            other => panic!("expected violations, got {:?}", other),
+        }
+
+        let fixed = yaml
+            .replace("AdminAccess\"\n    match:\n      oidc", "OpsAccess\"\n    match:\n      oidc")
+            .replace("principals: []", "principals: [\"ops\"]")
+            .replace("ocid", "oidc");
+        assert!(parse_policy_with(&fixed, strict).is_ok());
+    }
+
+    #[test]
+    fn test_strict_checks_host_groups() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "BastionAccess"
+    principals: ["ops"]
+    max_duration: "60m"
+hosts:
+  - name: "bastions"
+    patterns: ["bastion-*"]
+    policies: ["BastionAccess"]
+  - name: "bastions"
+    patterns: []
+    policies: ["BastionAcess"]
+    owner: "ops"
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        assert_eq!(policy.host_patterns("BastionAccess"), Some(vec!["bastion-*".to_string()]));
+        assert_eq!(policy.host_patterns("Other"), None);
+
+        let strict = LoadOptions { strict: true };
+        match parse_policy_with(yaml, strict) {
+            Err(LoadError::Invalid(v)) => assert_eq!(v, vec![
+                "hosts[1] (bastions): unknown key 'owner'",
+                "hosts[1] (bastions): empty patterns",
+                "hosts[1] (bastions): unknown policy 'BastionAcess'",
+                "hosts[1] (bastions): duplicate host group name",
+            ]),
+            other => panic!("expected violations, got {:?}", other),
+        }
+
+        // A host-scoped policy with no match block is not a catch-all.
+        let fixed = &yaml[..yaml.find("  - name: \"bastions\"\n    patterns: []").unwrap()];
+        assert!(parse_policy_with(fixed, strict).is_ok());
+    }
 }
@@ -1,10 +1,13 @@
 //! GateBridge CLI
 //!
 //! Commands:
-//!   validate   - Check policy file syntax
+//!   validate   - Check policy file syntax (--strict: unknown keys, empty policies)
 //!   translate  - Convert to Gate0 (outputs Rust code)
 //!   shadow     - Run dual evaluation and compare
 //!   explain    - Show step-by-step evaluation for debugging
+//!   export     - Print the policy as a gatelang document
+//!   diff-eval  - Compare decisions of two policy files over a request corpus
+//!   simulate   - Shadow evaluation as of a given time (--at) or now
 
 use std::env;
 use std::io::{self, Read};
@@ -21,11 +24,13 @@ fn main() -> ExitCode {
 
     match args[1].as_str() {
         "validate" => {
-            if args.len() < 3 {
-                eprintln!("Usage: gatebridge validate <policy.yaml>");
+            let strict = args.len() > 2 && args[2] == "--strict";
+            let path_arg = if strict { 3 } else { 2 };
+            if args.len() <= path_arg {
+                eprintln!("Usage: gatebridge validate [--strict] <policy.yaml>");
                 return ExitCode::from(2);
             }
-            cmd_validate(&args[2])
+            cmd_validate(&args[path_arg], strict)
         }
         "translate" => {
             if args.len() < 3 {
@@ -48,6 +53,15 @@ fn main() -> ExitCode {
             }
             cmd_explain(&args[2], &args[3])
         }
+        "export" => {
+            if args.len() < 3 {
+                eprintln!("Usage: gatebridge export <policy.yaml>");
+                return ExitCode::from(2);
+            }
+            cmd_export(&args[2])
+        }
+        "diff-eval" => cmd_diff_eval(&args[2..]),
+        "simulate" => cmd_simulate(&args[2..]),
         "help" | "--help" | "-h" => {
             print_usage();
             ExitCode::SUCCESS
@@ -65,22 +79,30 @@ fn print_usage() {
     eprintln!();
     eprintln!("Usage:");
     eprintln!("  gatebridge validate <policy.yaml>              Check policy syntax");
+    eprintln!("  gatebridge validate --strict <policy.yaml>     Also reject typos and catch-alls");
     eprintln!("  gatebridge translate <policy.yaml>             Convert to Gate0");
     eprintln!("  gatebridge shadow <policy.yaml> <request.json> Dual evaluation");
     eprintln!("  gatebridge shadow <policy.yaml> -              Read request from stdin");
     eprintln!("  gatebridge explain <policy.yaml> <request.json> Debug evaluation");
+    eprintln!("  gatebridge export <policy.yaml>                Print as gatelang");
+    eprintln!("  gatebridge diff-eval --old <a.yaml> --new <b.yaml> --requests <corpus.jsonl>");
+    eprintln!("                                                 Show decisions that change");
+    eprintln!("  gatebridge simulate [--at <time>] <policy.yaml> <request.json | ->");
+    eprintln!("                                                 Shadow evaluation at a time,");
+    eprintln!("                                                 e.g. 2025-03-30T02:30+01:00");
     eprintln!("  gatebridge help                                Show this message");
     eprintln!();
     eprintln!("Exit codes:");
-    eprintln!("  0 = success (shadow: decisions match)");
-    eprintln!("  1 = mismatch (shadow: decisions differ)");
+    eprintln!("  0 = success (shadow, simulate: decisions match; diff-eval: no changes)");
+    eprintln!("  1 = mismatch (shadow, simulate: decisions differ; diff-eval: decisions change)");
     eprintln!("  2 = error");
 }
 
-fn cmd_validate(path: &str) -> ExitCode {
+fn cmd_validate(path: &str, strict: bool) -> ExitCode {
     let path = Path::new(path);
+    let options = gatebridge::LoadOptions { strict };
     
-    match gatebridge::load_policy_file(path) {
+    match gatebridge::load_policy_file_with(path, options) {
         Ok(policy) => {
             println!("Policy valid.");
             println!("  Default principals: {:?}", policy.default.principals);
@@ -90,6 +112,13 @@ fn cmd_validate(path: &str) -> ExitCode {
             }
             ExitCode::SUCCESS
         }
+        Err(gatebridge::LoadError::Invalid(violations)) => {
+            eprintln!("Validation failed:");
+            for v in &violations {
+                eprintln!("  {}", v);
+            }
+            ExitCode::from(2)
+        }
         Err(e) => {
             eprintln!("Validation failed: {}", e);
             ExitCode::from(2)
@@ -139,39 +168,91 @@ fn cmd_shadow(policy_path: &str, request_source: &str) -> ExitCode {
     };
 
     // Load request (from file or stdin)
-    let request_json = if request_source == "-" {
-        let mut buffer = String::new();
-        if let Err(e) = io::stdin().read_to_string(&mut buffer) {
-            eprintln!("{{\"error\": \"Failed to read stdin: {}\"}}", e);
+    let request = match read_request(request_source) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{{\"error\": \"{}\"}}", e);
             return ExitCode::from(2);
         }
+    };
+
+    // Run shadow evaluation
+    match gatebridge::shadow_evaluate(&policy_file, &request) {
+        Ok(result) => {
+            let json = serde_json::to_string_pretty(&result).unwrap();
+            println!("{}", json);
+            
+            if result.decisions_match {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(1)
+            }
+        }
+        Err(e) => {
+            eprintln!("{{\"error\": \"{}\"}}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Read and parse an evaluation request from a file, or stdin for `-`.
+fn read_request(source: &str) -> Result<gatebridge::EvalRequest, String> {
+    let request_json = if source == "-" {
+        let mut buffer = String::new();
+        io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
         buffer
     } else {
-        match std::fs::read_to_string(request_source) {
-            Ok(s) => s,
+        std::fs::read_to_string(source)
+            .map_err(|e| format!("Failed to read request file: {}", e))?
+    };
+    serde_json::from_str(&request_json).map_err(|e| format!("Failed to parse request JSON: {}", e))
+}
+
+fn cmd_simulate(args: &[String]) -> ExitCode {
+    const USAGE: &str = "Usage: gatebridge simulate [--at <time>] <policy.yaml> <request.json | ->";
+    let (at, paths) = match args {
+        [flag, time, rest @ ..] if flag == "--at" => (Some(time.as_str()), rest),
+        _ => (None, args),
+    };
+    let [policy_path, request_source] = paths else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let clock: Box<dyn gate0::Clock> = match at {
+        Some(time) => match gatebridge::parse_timestamp(time) {
+            Ok(unix_time) => Box::new(gate0::FrozenClock::new(unix_time)),
             Err(e) => {
-                eprintln!("{{\"error\": \"Failed to read request file: {}\"}}", e);
+                eprintln!("{{\"error\": \"{}\"}}", e);
                 return ExitCode::from(2);
             }
-        }
+        },
+        None => Box::new(gate0::SystemClock),
     };
 
-    // Parse request
-    let request: gatebridge::EvalRequest = match serde_json::from_str(&request_json) {
+    let policy_file = match gatebridge::load_policy_file(Path::new(policy_path)) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{{\"error\": \"Failed to load policy: {}\"}}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let request = match read_request(request_source) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("{{\"error\": \"Failed to parse request JSON: {}\"}}", e);
+            eprintln!("{{\"error\": \"{}\"}}", e);
             return ExitCode::from(2);
         }
     };
 
-    // Run shadow evaluation
-    match gatebridge::shadow_evaluate(&policy_file, &request) {
+    match gatebridge::simulate(&policy_file, &request, clock.as_ref()) {
         Ok(result) => {
             let json = serde_json::to_string_pretty(&result).unwrap();
             println!("{}", json);
-            
-            if result.decisions_match {
+
+            if result.shadow.decisions_match {
                 ExitCode::SUCCESS
             } else {
                 ExitCode::from(1)
@@ -218,3 +299,78 @@ fn cmd_explain(policy_path: &str, request_path: &str) -> ExitCode {
 
     ExitCode::SUCCESS
 }
+
+fn cmd_export(path: &str) -> ExitCode {
+    let policy_file = match gatebridge::load_policy_file(Path::new(path)) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Failed to load: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    print!("{}", gatebridge::to_gatelang(&policy_file));
+    ExitCode::SUCCESS
+}
+
+fn cmd_diff_eval(args: &[String]) -> ExitCode {
+    const USAGE: &str = "Usage: gatebridge diff-eval --old <a.yaml> --new <b.yaml> --requests <corpus.jsonl>";
+    let (mut old, mut new, mut requests) = (None, None, None);
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let slot = match flag.as_str() {
+            "--old" => &mut old,
+            "--new" => &mut new,
+            "--requests" => &mut requests,
+            _ => {
+                eprintln!("Unknown argument: {}", flag);
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        };
+        *slot = iter.next();
+    }
+    let (Some(old_path), Some(new_path), Some(corpus_path)) = (old, new, requests) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let mut files = Vec::new();
+    for path in [old_path, new_path] {
+        match gatebridge::load_policy_file(Path::new(path)) {
+            Ok(p) => files.push(p),
+            Err(e) => {
+                eprintln!("Failed to load {}: {}", path, e);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let corpus = match std::fs::read_to_string(corpus_path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to read request corpus: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let requests = match gatebridge::parse_request_corpus(&corpus) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Failed to parse request corpus: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let changes = gatebridge::diff_eval(&files[0], &files[1], &requests);
+    for change in &changes {
+        println!("request {}: {}", change.index + 1, serde_json::to_string(&change.request).unwrap());
+        println!("  - {}", gatebridge::describe_outcome(&change.old));
+        println!("  + {}", gatebridge::describe_outcome(&change.new));
+    }
+    println!("{} of {} requests change.", changes.len(), requests.len());
+
+    if changes.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
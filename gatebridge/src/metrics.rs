@@ -0,0 +1,283 @@
+//! Health and metrics endpoints (feature `metrics`).
+//!
+//! A gateway records policy reloads and evaluation results on a shared
+//! `Metrics` and routes `GET /healthz` and `GET /metrics` to
+//! `Metrics::handle`. The handler is plain request-path-in,
+//! response-out, so it fits whatever HTTP server the gateway already runs;
+//! `HttpResponse::write_to` covers a bare `TcpStream`.
+//!
+//! `/healthz` is JSON and answers 503 until a policy has loaded.
+//! `/metrics` is the Prometheus text format.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ast::{EvalResult, PolicyFile};
+use crate::loader::LoadError;
+use crate::translate::to_gate0;
+
+/// Reload and evaluation counters, safe to share between threads.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    reload: Mutex<ReloadState>,
+    evaluations: AtomicU64,
+    matched: AtomicU64,
+    break_glass: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ReloadState {
+    /// Fingerprint of the loaded policy; `None` until one loads.
+    policy_version: Option<String>,
+    schema_version: Option<u32>,
+    last_reload_unix: Option<u64>,
+    last_reload_ok: bool,
+    last_reload_error: Option<String>,
+}
+
+impl Metrics {
+    /// Create with nothing loaded yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `file` was loaded and is now in use.
+    ///
+    /// Its version is the fingerprint of the translated Gate0 policy, so
+    /// gateways serving the same rules report the same version.
+    pub fn reload_succeeded(&self, file: &PolicyFile) {
+        let version = match to_gate0(file) {
+            Ok(policy) => format!("{:016x}", policy.fingerprint()),
+            Err(_) => "untranslatable".to_string(),
+        };
+        let mut state = self.reload.lock().unwrap_or_else(|e| e.into_inner());
+        state.policy_version = Some(version);
+        state.schema_version = Some(file.policy_schema_version);
+        state.last_reload_unix = Some(unix_now());
+        state.last_reload_ok = true;
+        state.last_reload_error = None;
+    }
+
+    /// Record a failed reload; the previous policy stays in use.
+    pub fn reload_failed(&self, error: &LoadError) {
+        let mut state = self.reload.lock().unwrap_or_else(|e| e.into_inner());
+        state.last_reload_unix = Some(unix_now());
+        state.last_reload_ok = false;
+        state.last_reload_error = Some(error.to_string());
+    }
+
+    /// Count one evaluation result.
+    pub fn record(&self, result: &EvalResult) {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        if result.matched {
+            self.matched.fetch_add(1, Ordering::Relaxed);
+        }
+        if result.break_glass {
+            self.break_glass.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Answer a request for `path`. Only `GET` is served.
+    pub fn handle(&self, method: &str, path: &str) -> HttpResponse {
+        let path = path.split('?').next().unwrap_or(path);
+        match (method, path) {
+            ("GET", "/healthz") => self.health(),
+            ("GET", "/metrics") => {
+                HttpResponse::new(200, "text/plain; version=0.0.4", self.prometheus())
+            }
+            (_, "/healthz" | "/metrics") => {
+                HttpResponse::new(405, "text/plain", "method not allowed\n".to_string())
+            }
+            _ => HttpResponse::new(404, "text/plain", "not found\n".to_string()),
+        }
+    }
+
+    fn health(&self) -> HttpResponse {
+        let state = self.reload.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let (status, word) = match state.policy_version {
+            Some(_) => (200, "ok"),
+            None => (503, "unavailable"),
+        };
+        let body = serde_json::json!({
+            "status": word,
+            "policy_version": state.policy_version,
+            "schema_version": state.schema_version,
+            "last_reload_unix": state.last_reload_unix,
+            "last_reload_ok": state.last_reload_ok,
+            "last_reload_error": state.last_reload_error,
+            "evaluations": self.evaluations.load(Ordering::Relaxed),
+        });
+        HttpResponse::new(status, "application/json", format!("{}\n", body))
+    }
+
+    fn prometheus(&self) -> String {
+        let state = self.reload.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let evaluations = self.evaluations.load(Ordering::Relaxed);
+        let matched = self.matched.load(Ordering::Relaxed);
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+            out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+            for (labels, value) in samples {
+                out.push_str(&format!("{}{} {}\n", name, labels, value));
+            }
+        };
+        metric(
+            "gatebridge_evaluations_total",
+            "counter",
+            "Evaluations by outcome.",
+            &[
+                ("{outcome=\"matched\"}", matched),
+                ("{outcome=\"default\"}", evaluations - matched),
+            ],
+        );
+        metric(
+            "gatebridge_break_glass_total",
+            "counter",
+            "Evaluations granted by a break-glass policy.",
+            &[("", self.break_glass.load(Ordering::Relaxed))],
+        );
+        metric(
+            "gatebridge_last_reload_success",
+            "gauge",
+            "Whether the last policy reload succeeded.",
+            &[("", state.last_reload_ok as u64)],
+        );
+        if let Some(at) = state.last_reload_unix {
+            metric(
+                "gatebridge_last_reload_timestamp_seconds",
+                "gauge",
+                "Time of the last policy reload attempt.",
+                &[("", at)],
+            );
+        }
+        if let Some(version) = &state.policy_version {
+            let labels = format!("{{version=\"{}\"}}", version);
+            metric(
+                "gatebridge_policy_info",
+                "gauge",
+                "The policy in use.",
+                &[(labels.as_str(), 1)],
+            );
+        }
+        out
+    }
+}
+
+/// A response from `Metrics::handle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        HttpResponse { status, content_type, body }
+    }
+
+    /// Write as an HTTP/1.1 response that closes the connection.
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Unknown",
+        };
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::EvalRequest;
+    use crate::loader::parse_policy;
+    use crate::reference_evaluate;
+
+    const YAML: &str = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "AdminAccess"
+    match:
+      oidc_groups: ["admins"]
+    principals: ["root"]
+    max_duration: "60m"
+"#;
+
+    #[test]
+    fn test_health_follows_reloads() {
+        let metrics = Metrics::new();
+        let response = metrics.handle("GET", "/healthz");
+        assert_eq!(response.status, 503);
+
+        let file = parse_policy(YAML).unwrap();
+        metrics.reload_succeeded(&file);
+        let response = metrics.handle("GET", "/healthz?verbose=1");
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        let version = format!("{:016x}", to_gate0(&file).unwrap().fingerprint());
+        assert_eq!(body["policy_version"], version.as_str());
+        assert_eq!(body["last_reload_ok"], true);
+
+        // A failed reload keeps the loaded policy healthy but says so
+        metrics.reload_failed(&LoadError::Parse("bad indent".to_string()));
+        let response = metrics.handle("GET", "/healthz");
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["policy_version"], version.as_str());
+        assert_eq!(body["last_reload_ok"], false);
+        assert_eq!(body["last_reload_error"], "Parse error: bad indent");
+    }
+
+    #[test]
+    fn test_prometheus_counters() {
+        let metrics = Metrics::new();
+        let file = parse_policy(YAML).unwrap();
+        metrics.reload_succeeded(&file);
+
+        let admin = EvalRequest { oidc_groups: vec!["admins".to_string()], ..Default::default() };
+        metrics.record(&reference_evaluate(&file, &admin));
+        metrics.record(&reference_evaluate(&file, &EvalRequest::default()));
+        metrics.record(&reference_evaluate(&file, &EvalRequest::default()));
+
+        let body = metrics.handle("GET", "/metrics").body;
+        assert!(body.contains("gatebridge_evaluations_total{outcome=\"matched\"} 1\n"));
+        assert!(body.contains("gatebridge_evaluations_total{outcome=\"default\"} 2\n"));
+        assert!(body.contains("gatebridge_break_glass_total 0\n"));
+        assert!(body.contains("gatebridge_last_reload_success 1\n"));
+        assert!(body.contains("# TYPE gatebridge_policy_info gauge\n"));
+    }
+
+    #[test]
+    fn test_routing_and_wire_format() {
+        let metrics = Metrics::new();
+        assert_eq!(metrics.handle("GET", "/").status, 404);
+        assert_eq!(metrics.handle("POST", "/metrics").status, 405);
+
+        let mut wire = Vec::new();
+        metrics.handle("GET", "/healthz").write_to(&mut wire).unwrap();
+        let wire = String::from_utf8(wire).unwrap();
+        assert!(wire.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(wire.contains("Content-Type: application/json\r\n"));
+    }
+}
@@ -1,29 +1,69 @@
 //! Reference policy evaluator. Correctness-first, not optimized.
 
-use crate::ast::{EvalRequest, EvalResult, MatchBlock, Policy, PolicyFile};
+use std::time::{Duration, Instant};
+
+use crate::ast::{EvalRequest, EvalResult, Policy, PolicyFile};
+
+/// Counters from one evaluation, for gateway metrics.
+///
+/// Mirrors gate0's `EvaluationStats`: cheap to collect, `Copy`, and
+/// returned next to the result rather than logged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalStats {
+    /// Policies checked, including the one that matched.
+    pub policies_considered: u32,
+    /// Policies whose OR triggers matched (or that have none).
+    pub triggers_matched: u32,
+    /// AND filters evaluated; later filters are skipped once one fails.
+    pub filters_evaluated: u32,
+    /// Wall-clock time spent evaluating.
+    pub duration: Duration,
+}
 
 /// Evaluate a request against a policy file.
 ///
 /// Returns the result with matched policy info or default.
 pub fn evaluate(policy_file: &PolicyFile, request: &EvalRequest) -> EvalResult {
+    evaluate_with_stats(policy_file, request).0
+}
+
+/// Like `evaluate`, also returning evaluation statistics.
+pub fn evaluate_with_stats(policy_file: &PolicyFile, request: &EvalRequest) -> (EvalResult, EvalStats) {
+    let start = Instant::now();
+    let mut stats = EvalStats::default();
+
     // Try each policy in order
+    let mut result = None;
     for (index, policy) in policy_file.policies.iter().enumerate() {
-        if matches_policy(policy, request) {
-            return EvalResult::from_policy(policy, index);
+        stats.policies_considered += 1;
+        let hosts = policy_file.host_patterns(&policy.name);
+        if matches_policy(policy, hosts.as_deref(), request, &mut stats) {
+            result = Some(EvalResult::from_policy(policy, index, request));
+            break;
         }
     }
 
     // No match - use default
-    EvalResult::default_policy(&policy_file.default)
+    let result = result.unwrap_or_else(|| EvalResult::default_policy(&policy_file.default));
+    stats.duration = start.elapsed();
+    (result, stats)
 }
 
 /// Check if a request matches a policy's conditions.
-fn matches_policy(policy: &Policy, request: &EvalRequest) -> bool {
+///
+/// `hosts` are the policy's host patterns, if it is restricted to some hosts.
+fn matches_policy(
+    policy: &Policy,
+    hosts: Option<&[String]>,
+    request: &EvalRequest,
+    stats: &mut EvalStats,
+) -> bool {
     let m = &policy.match_block;
 
     // If no triggers defined, policy matches anyone (open policy)
     if !m.has_triggers() {
-        return check_filters(m, request);
+        stats.triggers_matched += 1;
+        return check_filters(policy, hosts, request, stats);
     }
 
     // Phase 1: At least one OR trigger must match
@@ -34,15 +74,24 @@ fn matches_policy(policy: &Policy, request: &EvalRequest) -> bool {
     if !trigger_matched {
         return false;
     }
+    stats.triggers_matched += 1;
 
     // Phase 2: All AND filters must pass
-    check_filters(m, request)
+    check_filters(policy, hosts, request, stats)
 }
 
 /// Check AND filters (all must pass).
-fn check_filters(m: &MatchBlock, request: &EvalRequest) -> bool {
+fn check_filters(
+    policy: &Policy,
+    hosts: Option<&[String]>,
+    request: &EvalRequest,
+    stats: &mut EvalStats,
+) -> bool {
+    let m = &policy.match_block;
+
     // source_ip: CIDR match
     if !m.source_ip.is_empty() {
+        stats.filters_evaluated += 1;
         if !check_cidr(&m.source_ip, request.source_ip.as_deref()) {
             return false;
         }
@@ -50,6 +99,7 @@ fn check_filters(m: &MatchBlock, request: &EvalRequest) -> bool {
 
     // hours: legacy check (using hour_utc as proxy if current_time is gone)
     if !m.hours.is_empty() {
+        stats.filters_evaluated += 1;
         if !check_time_range_from_hour(&m.hours, request.hour_utc) {
             return false;
         }
@@ -57,6 +107,7 @@ fn check_filters(m: &MatchBlock, request: &EvalRequest) -> bool {
 
     // business_hours: explicit precomputed check
     if let Some(required) = m.is_business_hours {
+        stats.filters_evaluated += 1;
         if request.is_business_hours != required {
             return false;
         }
@@ -64,11 +115,28 @@ fn check_filters(m: &MatchBlock, request: &EvalRequest) -> bool {
 
     // webauthn_ids: exact match
     if !m.webauthn_ids.is_empty() {
+        stats.filters_evaluated += 1;
         if !check_exact(&m.webauthn_ids, request.webauthn_id.as_deref()) {
             return false;
         }
     }
 
+    // hosts: fnmatch on the target host, for host-scoped policies
+    if let Some(patterns) = hosts {
+        stats.filters_evaluated += 1;
+        if !check_fnmatch(patterns, request.target_host.as_deref()) {
+            return false;
+        }
+    }
+
+    // break_glass: a justification must be given
+    if policy.break_glass {
+        stats.filters_evaluated += 1;
+        if request.justification().is_none() {
+            return false;
+        }
+    }
+
     true
 }
 
@@ -262,8 +330,10 @@ policies:
     max_duration: "60m"
 "#;
         let policy = parse_policy(yaml).unwrap();
-        let mut request = EvalRequest::default();
-        request.oidc_groups = vec!["admins".to_string()];
+        let request = EvalRequest {
+            oidc_groups: vec!["admins".to_string()],
+            ..Default::default()
+        };
         
         let result = evaluate(&policy, &request);
         
@@ -271,4 +341,163 @@ policies:
         assert_eq!(result.policy_name, Some("AdminAccess".to_string()));
         assert_eq!(result.principals, vec!["root"]);
     }
+
+    #[test]
+    fn test_evaluate_carries_reason_code_and_tags() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "AdminAccess"
+    match:
+      oidc_groups: ["admins"]
+    principals: ["root"]
+    max_duration: "60m"
+    reason_code: 1001
+    audit_tags: ["privileged", "sox"]
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let request = EvalRequest {
+            oidc_groups: vec!["admins".to_string()],
+            ..Default::default()
+        };
+
+        let result = evaluate(&policy, &request);
+        assert_eq!(result.reason_code, Some(1001));
+        assert_eq!(result.audit_tags, vec!["privileged", "sox"]);
+
+        let result = evaluate(&policy, &EvalRequest::default());
+        assert_eq!(result.reason_code, None);
+        assert!(result.audit_tags.is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_host_scoped() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+hosts:
+  - name: "prod-db"
+    patterns: ["db-*.prod.example.com"]
+    policies: ["DbAdmins"]
+  - name: "bastions"
+    patterns: ["bastion-?"]
+    policies: ["DbAdmins"]
+policies:
+  - name: "DbAdmins"
+    match:
+      oidc_groups: ["dba"]
+    principals: ["postgres"]
+    max_duration: "60m"
+  - name: "Engineers"
+    match:
+      oidc_groups: ["dba", "eng"]
+    principals: ["eng"]
+    max_duration: "30m"
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let mut request = EvalRequest {
+            oidc_groups: vec!["dba".to_string()],
+            target_host: Some("DB-1.prod.example.com".to_string()),
+            ..Default::default()
+        };
+        request.normalize();
+        let (result, stats) = evaluate_with_stats(&policy, &request);
+        assert_eq!(result.policy_name.as_deref(), Some("DbAdmins"));
+        assert_eq!(stats.filters_evaluated, 1);
+
+        // Patterns of every group listing the policy apply
+        request.target_host = Some("bastion-2".to_string());
+        assert_eq!(evaluate(&policy, &request).policy_name.as_deref(), Some("DbAdmins"));
+
+        // Other hosts, or no host, fall through to unscoped policies
+        request.target_host = Some("web-1.prod.example.com".to_string());
+        assert_eq!(evaluate(&policy, &request).policy_name.as_deref(), Some("Engineers"));
+        request.target_host = None;
+        assert_eq!(evaluate(&policy, &request).policy_name.as_deref(), Some("Engineers"));
+    }
+
+    #[test]
+    fn test_evaluate_break_glass() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "Emergency"
+    match:
+      oidc_groups: ["oncall"]
+    principals: ["root"]
+    max_duration: "30m"
+    break_glass: true
+  - name: "OnCall"
+    match:
+      oidc_groups: ["oncall"]
+    principals: ["operator"]
+    max_duration: "60m"
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let mut request = EvalRequest {
+            oidc_groups: vec!["oncall".to_string()],
+            ..Default::default()
+        };
+
+        // Without a justification the break-glass policy is skipped
+        for justification in [None, Some("   ")] {
+            request.justification = justification.map(str::to_string);
+            let result = evaluate(&policy, &request);
+            assert_eq!(result.policy_name.as_deref(), Some("OnCall"));
+            assert!(!result.break_glass);
+            assert_eq!(result.justification, None);
+        }
+
+        request.justification = Some("INC-1234 database down".to_string());
+        let result = evaluate(&policy, &request);
+        assert_eq!(result.policy_name.as_deref(), Some("Emergency"));
+        assert!(result.break_glass);
+        assert_eq!(result.justification.as_deref(), Some("INC-1234 database down"));
+    }
+
+    #[test]
+    fn test_evaluate_with_stats() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "OfficeAdmins"
+    match:
+      oidc_groups: ["admins"]
+      source_ip: ["10.0.0.0/8"]
+      is_business_hours: true
+    principals: ["root"]
+    max_duration: "60m"
+  - name: "Contractors"
+    match:
+      oidc_groups: ["contractors"]
+    principals: ["guest"]
+    max_duration: "10m"
+  - name: "Anyone"
+    match:
+      is_business_hours: false
+    principals: ["nobody"]
+    max_duration: "5m"
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let request = EvalRequest {
+            oidc_groups: vec!["admins".to_string()],
+            source_ip: Some("192.168.1.1".to_string()),
+            ..Default::default()
+        };
+
+        let (result, stats) = evaluate_with_stats(&policy, &request);
+        assert_eq!(result, evaluate(&policy, &request));
+        assert_eq!(result.policy_name.as_deref(), Some("Anyone"));
+        assert_eq!(stats.policies_considered, 3);
+        // OfficeAdmins fails at source_ip, skipping business hours.
+        assert_eq!(stats.triggers_matched, 2);
+        assert_eq!(stats.filters_evaluated, 2);
+    }
 }
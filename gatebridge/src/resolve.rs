@@ -0,0 +1,165 @@
+//! Async group resolution (feature `async`).
+//!
+//! Evaluation is synchronous and expects `EvalRequest::oidc_groups` to be
+//! filled in. When groups come from a slow directory (LDAP, an IdP API),
+//! resolve them here first, on the gateway's runtime, so a stalled lookup
+//! costs one connection a timeout instead of blocking the accept loop.
+//! A failed or timed-out lookup is an error, never an empty group list.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+
+use crate::ast::{EvalRequest, EvalResult, PolicyFile};
+use crate::reference_evaluate;
+
+/// Looks up a requester's groups.
+pub trait GroupResolver: Send + Sync {
+    /// Groups for the identity in `request`, added to any it already has.
+    fn groups(
+        &self,
+        request: &EvalRequest,
+    ) -> impl Future<Output = Result<Vec<String>, ResolveError>> + Send;
+}
+
+/// Resolution failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The lookup did not finish within the timeout.
+    Timeout,
+    /// The resolver reported an error.
+    Failed(String),
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::Timeout => write!(f, "Resolver timed out"),
+            ResolveError::Failed(e) => write!(f, "Resolver failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// A `GroupResolver` with a per-lookup timeout and a cap on lookups in flight.
+#[derive(Debug)]
+pub struct AsyncResolver<G> {
+    resolver: G,
+    timeout: Duration,
+    permits: Arc<Semaphore>,
+}
+
+impl<G: GroupResolver> AsyncResolver<G> {
+    /// Wrap `resolver`. Lookups beyond `max_concurrent` wait for a slot,
+    /// and that wait counts against `timeout`.
+    pub fn new(resolver: G, timeout: Duration, max_concurrent: usize) -> Self {
+        AsyncResolver {
+            resolver,
+            timeout,
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+        }
+    }
+
+    /// Add the resolved groups to `request`.
+    pub async fn resolve(&self, request: &mut EvalRequest) -> Result<(), ResolveError> {
+        let lookup = async {
+            // The semaphore is never closed.
+            let _permit = self
+                .permits
+                .acquire()
+                .await
+                .map_err(|e| ResolveError::Failed(e.to_string()))?;
+            self.resolver.groups(request).await
+        };
+        let groups = tokio::time::timeout(self.timeout, lookup)
+            .await
+            .map_err(|_| ResolveError::Timeout)??;
+        request.oidc_groups.extend(groups);
+        Ok(())
+    }
+
+    /// Resolve, normalize, and evaluate with the reference evaluator.
+    pub async fn evaluate(
+        &self,
+        policy_file: &PolicyFile,
+        request: &EvalRequest,
+    ) -> Result<EvalResult, ResolveError> {
+        let mut request = request.clone();
+        self.resolve(&mut request).await?;
+        request.normalize();
+        Ok(reference_evaluate(policy_file, &request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::parse_policy;
+
+    struct Directory {
+        delay: Duration,
+    }
+
+    impl GroupResolver for Directory {
+        async fn groups(&self, request: &EvalRequest) -> Result<Vec<String>, ResolveError> {
+            tokio::time::sleep(self.delay).await;
+            match request.email.as_deref() {
+                Some("alice@example.com") => Ok(vec!["Admins".to_string()]),
+                Some(_) => Ok(vec![]),
+                None => Err(ResolveError::Failed("no identity".to_string())),
+            }
+        }
+    }
+
+    const YAML: &str = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "AdminAccess"
+    match:
+      oidc_groups: ["admins"]
+    principals: ["root"]
+    max_duration: "60m"
+"#;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resolve_then_evaluate() {
+        let policy = parse_policy(YAML).unwrap();
+        let directory = Directory { delay: Duration::from_millis(10) };
+        let resolver = AsyncResolver::new(directory, Duration::from_secs(1), 4);
+
+        let request = EvalRequest {
+            email: Some("alice@example.com".to_string()),
+            ..Default::default()
+        };
+        let result = resolver.evaluate(&policy, &request).await.unwrap();
+        assert_eq!(result.policy_name.as_deref(), Some("AdminAccess"));
+
+        let anonymous = EvalRequest::default();
+        assert_eq!(
+            resolver.evaluate(&policy, &anonymous).await,
+            Err(ResolveError::Failed("no identity".to_string()))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_includes_waiting_for_a_slot() {
+        let directory = Directory { delay: Duration::from_millis(600) };
+        let resolver = AsyncResolver::new(directory, Duration::from_secs(1), 1);
+        let mut first = EvalRequest {
+            email: Some("alice@example.com".to_string()),
+            ..Default::default()
+        };
+        let mut second = first.clone();
+
+        let (a, b) = tokio::join!(resolver.resolve(&mut first), resolver.resolve(&mut second));
+        assert_eq!(a, Ok(()));
+        assert_eq!(b, Err(ResolveError::Timeout));
+        assert_eq!(first.oidc_groups, vec!["Admins"]);
+        assert!(second.oidc_groups.is_empty());
+    }
+}
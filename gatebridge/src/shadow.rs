@@ -25,6 +25,10 @@ pub struct ReferenceDecision {
     pub policy_name: Option<String>,
     pub policy_index: Option<usize>,
     pub trust_budget: Option<crate::ast::TrustBudget>,
+    pub reason_code: Option<u32>,
+    pub audit_tags: Vec<String>,
+    pub break_glass: bool,
+    pub justification: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,6 +36,7 @@ pub struct Gate0Decision {
     pub effect: String,
     pub reason_code: u32,
     pub trust_budget: Option<crate::ast::TrustBudget>,
+    pub break_glass: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,12 +62,13 @@ pub fn shadow_evaluate(
 
     // The adapter pattern: we pre-compute complex matching into booleans.
     // Build binary context with all pre-computed facts for each policy.
-    let mut context: Vec<(&'static str, Value<'static>)> = Vec::new();
-
     // Global "Gold Standard" facts
-    context.push(("is_business_hours", Value::Bool(request.is_business_hours)));
-    context.push(("hour_utc", Value::Int(request.hour_utc as i64)));
-    context.push(("weekday_utc", Value::String(Box::leak(request.weekday_utc.clone().into_boxed_str()))));
+    let mut context: Vec<(&'static str, Value<'static>)> = vec![
+        ("is_business_hours", Value::Bool(request.is_business_hours)),
+        ("hour_utc", Value::Int(request.hour_utc as i64)),
+        ("weekday_utc", Value::String(Box::leak(request.weekday_utc.clone().into_boxed_str()))),
+        ("has_justification", Value::Bool(request.justification().is_some())),
+    ];
 
     // Per-policy pre-computed facts
     for (index, policy) in policy_file.policies.iter().enumerate() {
@@ -97,6 +103,13 @@ pub fn shadow_evaluate(
             let name = Box::leak(format!("p{}_webauthn", index).into_boxed_str());
             context.push((name, Value::Bool(matched)));
         }
+
+        // Host filter (AND), for policies listed in a host group
+        if let Some(patterns) = policy_file.host_patterns(&policy.name) {
+            let matched = check_fnmatch(&patterns, request.target_host.as_deref());
+            let name = Box::leak(format!("p{}_host", index).into_boxed_str());
+            context.push((name, Value::Bool(matched)));
+        }
     }
 
     // Build request
@@ -116,6 +129,8 @@ pub fn shadow_evaluate(
     let gate0_effect = match gate0_decision.effect {
         gate0::Effect::Allow => "allow",
         gate0::Effect::Deny => "deny",
+        gate0::Effect::Challenge(_) => "challenge",
+        gate0::Effect::Indeterminate => "indeterminate",
     };
 
     // Map Gate0 reason code back to expected index
@@ -127,14 +142,14 @@ pub fn shadow_evaluate(
 
     let decisions_match = gate0_decision.reason.value() == expected_reason;
 
-    // Get the trust budget from the matched policy for Gate0 result
+    // Get the trust budget and break-glass marker from the matched policy for Gate0 result
+    let gate0_policy_entry = policy_file.policies.get(gate0_decision.reason.value() as usize);
     let gate0_trust_budget = if decisions_match && ref_result.matched {
         ref_result.trust_budget.clone()
-    } else if gate0_decision.reason.value() < policy_file.policies.len() as u32 {
-        policy_file.policies[gate0_decision.reason.value() as usize].trust_budget.clone()
     } else {
-        None
+        gate0_policy_entry.and_then(|p| p.trust_budget.clone())
     };
+    let gate0_break_glass = gate0_policy_entry.is_some_and(|p| p.break_glass);
 
     Ok(ShadowResult {
         reference_decision: ReferenceDecision {
@@ -142,11 +157,16 @@ pub fn shadow_evaluate(
             policy_name: ref_result.policy_name,
             policy_index: ref_result.policy_index,
             trust_budget: ref_result.trust_budget,
+            reason_code: ref_result.reason_code,
+            audit_tags: ref_result.audit_tags,
+            break_glass: ref_result.break_glass,
+            justification: ref_result.justification,
         },
         gate0_decision: Gate0Decision {
             effect: gate0_effect.to_string(),
             reason_code: gate0_decision.reason.value(),
             trust_budget: gate0_trust_budget,
+            break_glass: gate0_break_glass,
         },
         decisions_match,
         stats: ShadowStats {
@@ -209,8 +229,10 @@ policies:
     max_duration: "60m"
 "#;
         let policy = parse_policy(yaml).unwrap();
-        let mut request = EvalRequest::default();
-        request.oidc_groups = vec!["admins".to_string()];
+        let request = EvalRequest {
+            oidc_groups: vec!["admins".to_string()],
+            ..Default::default()
+        };
         
         let result = shadow_evaluate(&policy, &request).unwrap();
         
@@ -218,4 +240,67 @@ policies:
         assert_eq!(result.reference_decision.policy_name, Some("AdminAccess".to_string()));
         assert_eq!(result.gate0_decision.reason_code, 0);
     }
+
+    #[test]
+    fn test_shadow_break_glass() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "Emergency"
+    match:
+      oidc_groups: ["oncall"]
+    principals: ["root"]
+    max_duration: "30m"
+    break_glass: true
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let mut request = EvalRequest {
+            oidc_groups: vec!["oncall".to_string()],
+            ..Default::default()
+        };
+        let result = shadow_evaluate(&policy, &request).unwrap();
+        assert!(result.decisions_match);
+        assert!(!result.gate0_decision.break_glass);
+
+        request.justification = Some("INC-7".to_string());
+        let result = shadow_evaluate(&policy, &request).unwrap();
+        assert!(result.decisions_match);
+        assert_eq!(result.gate0_decision.reason_code, 0);
+        assert!(result.reference_decision.break_glass);
+        assert!(result.gate0_decision.break_glass);
+        assert_eq!(result.reference_decision.justification.as_deref(), Some("INC-7"));
+    }
+
+    #[test]
+    fn test_shadow_host_scoped() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+hosts:
+  - name: "bastions"
+    patterns: ["bastion-*"]
+    policies: ["AdminAccess"]
+policies:
+  - name: "AdminAccess"
+    match:
+      oidc_groups: ["admins"]
+    principals: ["root"]
+    max_duration: "60m"
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let cases = [(Some("Bastion-1"), 0), (Some("web-1"), u32::MAX - 1), (None, u32::MAX - 1)];
+        for (host, reason) in cases {
+            let request = EvalRequest {
+                oidc_groups: vec!["admins".to_string()],
+                target_host: host.map(str::to_string),
+                ..Default::default()
+            };
+            let result = shadow_evaluate(&policy, &request).unwrap();
+            assert!(result.decisions_match, "{:?}", host);
+            assert_eq!(result.gate0_decision.reason_code, reason, "{:?}", host);
+        }
+    }
 }
@@ -0,0 +1,216 @@
+//! Time simulation - shadow evaluation as of a chosen instant
+//!
+//! Policies with `hours` filters decide differently depending on when a
+//! request arrives. `simulate` stamps the request's `hour_utc` and
+//! `weekday_utc` from an injected `gate0::Clock` before shadow evaluation,
+//! so operators can check a DST change or a change freeze before it
+//! happens. `is_business_hours` is left as given: its definition is
+//! site-specific.
+
+use crate::ast::{EvalRequest, PolicyFile};
+use crate::shadow::{shadow_evaluate, ShadowError, ShadowResult};
+use serde::Serialize;
+
+/// Result of a simulated evaluation.
+#[derive(Debug, Serialize)]
+pub struct SimulationResult {
+    /// The simulated time, in Unix seconds.
+    pub unix_time: i64,
+    /// The UTC hour the request was stamped with.
+    pub hour_utc: u8,
+    /// The UTC weekday the request was stamped with.
+    pub weekday_utc: String,
+    #[serde(flatten)]
+    pub shadow: ShadowResult,
+}
+
+/// Shadow-evaluate `request` as if it arrived at `clock.now()`.
+pub fn simulate(
+    policy_file: &PolicyFile,
+    request: &EvalRequest,
+    clock: &dyn gate0::Clock,
+) -> Result<SimulationResult, ShadowError> {
+    let mut request = request.clone();
+    request.stamp_time(clock);
+    let shadow = shadow_evaluate(policy_file, &request)?;
+    Ok(SimulationResult {
+        unix_time: clock.now(),
+        hour_utc: request.hour_utc,
+        weekday_utc: request.weekday_utc,
+        shadow,
+    })
+}
+
+/// Parse an RFC 3339 timestamp into Unix seconds.
+///
+/// Accepts `YYYY-MM-DDTHH:MM[:SS]` followed by `Z` or a `+HH:MM`/`-HH:MM`
+/// offset, e.g. `2025-03-30T02:30+01:00`. The offset is required, so a
+/// local time is never silently read as UTC. Fractional seconds are not
+/// accepted.
+pub fn parse_timestamp(text: &str) -> Result<i64, TimestampError> {
+    let invalid = || TimestampError(text.to_string());
+    let (date, time) = text.split_once(['T', 't']).ok_or_else(invalid)?;
+
+    let mut fields = date.splitn(3, '-');
+    let year: i64 = number(fields.next(), 4).ok_or_else(invalid)?;
+    let month = number(fields.next(), 2).ok_or_else(invalid)?;
+    let day = number(fields.next(), 2).ok_or_else(invalid)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else {
+        let split = time.rfind(['+', '-']).ok_or_else(invalid)?;
+        let (clock, offset) = time.split_at(split);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+        let hours = number(Some(hours), 2)
+            .filter(|h| *h < 24)
+            .ok_or_else(invalid)?;
+        let minutes = number(Some(minutes), 2)
+            .filter(|m| *m < 60)
+            .ok_or_else(invalid)?;
+        (clock, sign * (hours * 3600 + minutes * 60))
+    };
+
+    let mut fields = clock.split(':');
+    let hour = number(fields.next(), 2)
+        .filter(|h| *h < 24)
+        .ok_or_else(invalid)?;
+    let minute = number(fields.next(), 2)
+        .filter(|m| *m < 60)
+        .ok_or_else(invalid)?;
+    let second = match fields.next() {
+        None => 0,
+        Some(s) => number(Some(s), 2).filter(|s| *s < 60).ok_or_else(invalid)?,
+    };
+    if fields.next().is_some() {
+        return Err(invalid());
+    }
+
+    let days = days_from_civil(year, month, day);
+    Ok(days * 86_400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+/// A timestamp `parse_timestamp` could not read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampError(pub String);
+
+impl std::fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid timestamp '{}': expected e.g. 2025-03-30T02:30+01:00",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for TimestampError {}
+
+/// A field of exactly `digits` ASCII digits.
+fn number(field: Option<&str>, digits: usize) -> Option<i64> {
+    let field = field?;
+    if field.len() != digits || !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's
+/// `days_from_civil`).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::parse_policy;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00Z"), Ok(0));
+        assert_eq!(parse_timestamp("2024-01-06T14:30:00Z"), Ok(1_704_551_400));
+        assert_eq!(parse_timestamp("2024-01-06T15:30+01:00"), Ok(1_704_551_400));
+        assert_eq!(parse_timestamp("2024-01-06T09:00-05:30"), Ok(1_704_551_400));
+        assert_eq!(parse_timestamp("2024-02-29T00:00Z"), Ok(1_709_164_800));
+        assert_eq!(parse_timestamp("1969-12-31T23:59:59Z"), Ok(-1));
+
+        for text in [
+            "2024-01-06T14:30",
+            "2024-01-06 14:30Z",
+            "2023-02-29T00:00Z",
+            "2024-13-01T00:00Z",
+            "2024-01-06T24:00Z",
+            "2024-01-06T14:30:00.5Z",
+            "2024-1-06T14:30Z",
+            "2024-01-06T14:30+1:00",
+        ] {
+            assert_eq!(
+                parse_timestamp(text),
+                Err(TimestampError(text.to_string())),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_simulate_across_dst_change() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+policies:
+  - name: "NightOps"
+    match:
+      oidc_groups: ["ops"]
+      hours: ["01:00-01:59"]
+    principals: ["root"]
+    max_duration: "30m"
+"#;
+        let policy = parse_policy(yaml).unwrap();
+        let request = EvalRequest {
+            oidc_groups: vec!["ops".to_string()],
+            ..EvalRequest::default()
+        };
+
+        // 02:30 CET on the night clocks go forward is 01:30 UTC.
+        let at = parse_timestamp("2025-03-30T02:30+01:00").unwrap();
+        let clock = gate0::FrozenClock::new(at);
+        let result = simulate(&policy, &request, &clock).unwrap();
+        assert_eq!(result.hour_utc, 1);
+        assert_eq!(result.weekday_utc, "sunday");
+        assert_eq!(
+            result.shadow.reference_decision.policy_name.as_deref(),
+            Some("NightOps")
+        );
+        assert!(result.shadow.decisions_match);
+
+        // 02:30 never happens locally that night: it is 03:30 summer time.
+        assert_eq!(parse_timestamp("2025-03-30T03:30+02:00"), Ok(at));
+
+        clock.advance(3600);
+        let result = simulate(&policy, &request, &clock).unwrap();
+        assert_eq!(result.hour_utc, 2);
+        assert_eq!(result.shadow.reference_decision.policy_name, None);
+        assert!(result.shadow.decisions_match);
+    }
+}
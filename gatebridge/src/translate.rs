@@ -38,7 +38,9 @@ pub fn to_gate0(policy_file: &PolicyFile) -> Result<Policy<'static>, TranslateEr
     // Add each policy as a rule
     for (index, policy) in policy_file.policies.iter().enumerate() {
         let reason = ReasonCode(index as u32);
-        let condition = build_condition(index, &policy.match_block)?;
+        let host_scoped = policy_file.host_patterns(&policy.name).is_some();
+        let condition =
+            build_condition(index, &policy.match_block, host_scoped, policy.break_glass)?;
 
         let rule = match condition {
             Some(cond) => Rule::new(Effect::Allow, Target::any(), Some(cond), reason),
@@ -58,9 +60,19 @@ pub fn to_gate0(policy_file: &PolicyFile) -> Result<Policy<'static>, TranslateEr
         .map_err(|e| TranslateError::BuildFailed(format!("{:?}", e)))
 }
 
-/// Build a Gate0 Condition from a MatchBlock.
-fn build_condition(index: usize, m: &MatchBlock) -> Result<Option<Condition<'static>>, TranslateError> {
-    if !m.has_triggers() && !m.has_filters() {
+/// Build a Gate0 Condition from a MatchBlock, plus a host filter if the
+/// policy is listed in a host group and a justification filter if it is
+/// break-glass.
+///
+/// Break-glass policies are not gate0 break-glass rules: those override
+/// every other rule, while YAML policies stay first-match.
+fn build_condition(
+    index: usize,
+    m: &MatchBlock,
+    host_scoped: bool,
+    break_glass: bool,
+) -> Result<Option<Condition<'static>>, TranslateError> {
+    if !m.has_triggers() && !m.has_filters() && !host_scoped && !break_glass {
         return Ok(None); // No conditions = match all
     }
 
@@ -104,6 +116,19 @@ fn build_condition(index: usize, m: &MatchBlock) -> Result<Option<Condition<'sta
             value: Value::Bool(true),
         });
     }
+    if host_scoped {
+        let attr = format!("p{}_host", index);
+        conditions.push(Condition::Equals {
+            attr: Box::leak(attr.into_boxed_str()),
+            value: Value::Bool(true),
+        });
+    }
+    if break_glass {
+        conditions.push(Condition::Equals {
+            attr: "has_justification",
+            value: Value::Bool(true),
+        });
+    }
 
     if conditions.is_empty() {
         Ok(None)
@@ -158,4 +183,33 @@ policies:
         // Policy rule + default rule
         assert_eq!(gate0_policy.rule_count(), 2);
     }
+
+    #[test]
+    fn test_translate_host_scoped() {
+        let yaml = r#"
+default:
+  principals: ["sandbox"]
+  max_duration: "15m"
+hosts:
+  - name: "bastions"
+    patterns: ["bastion-*"]
+    policies: ["Anyone"]
+policies:
+  - name: "Anyone"
+    principals: ["guest"]
+    max_duration: "10m"
+"#;
+        let policy_file = parse_policy(yaml).unwrap();
+        let scoped = to_gate0(&policy_file).unwrap();
+
+        let mut unscoped_file = policy_file.clone();
+        unscoped_file.hosts.clear();
+        let unscoped = to_gate0(&unscoped_file).unwrap();
+        assert_ne!(scoped.fingerprint(), unscoped.fingerprint());
+
+        let context: &[(&str, Value)] = &[("p0_host", Value::Bool(false))];
+        let request = gate0::Request::with_context("u", "ssh_login", "default", context);
+        assert_eq!(scoped.evaluate(&request).unwrap().reason, ReasonCode(u32::MAX - 1));
+        assert_eq!(unscoped.evaluate(&request).unwrap().reason, ReasonCode(0));
+    }
 }
@@ -0,0 +1,247 @@
+//! Anomaly reporting.
+//!
+//! Security teams want alerts for evaluation failures, exhausted budgets,
+//! break-glass use, and bursts of denials, without scraping logs.
+//! `Policy::evaluate_with_anomalies` evaluates normally and reports any of
+//! these to a caller-supplied `AnomalySink`. Denial bursts are tracked in
+//! an `AnomalyState` the caller owns, so the policy itself stays immutable
+//! and shareable.
+
+use crate::error::PolicyError;
+use crate::policy::Policy;
+use crate::types::{Decision, Effect, ReasonCode, Request};
+
+/// Maximum number of principals an `AnomalyState` tracks at once.
+///
+/// When full, the principal whose window started earliest is forgotten.
+pub const MAX_TRACKED_PRINCIPALS: usize = 1024;
+
+/// Something unusual about a single evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anomaly<'e> {
+    /// Evaluation returned an error.
+    EvaluationFailed(&'e PolicyError),
+    /// Evaluation hit a configured or hard limit.
+    BudgetExhausted(&'e PolicyError),
+    /// A rule failed and the decision is Indeterminate.
+    Indeterminate(ReasonCode),
+    /// A break-glass rule decided the request.
+    BreakGlass(ReasonCode),
+    /// The principal reached the configured number of denials within one window.
+    RepeatedDenials {
+        /// Denials counted in the current window.
+        denials: u32,
+        /// The window length, in seconds.
+        window_seconds: u64,
+    },
+}
+
+/// Receives anomalies, e.g. to raise alerts.
+///
+/// Takes `&self` like `GroupProvider`; use interior mutability to record.
+pub trait AnomalySink {
+    /// Called once per anomaly, after the request was evaluated.
+    fn report(&self, request: &Request<'_>, anomaly: &Anomaly<'_>);
+}
+
+/// Report a principal's denials once they reach `threshold` within `window_seconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DenialPattern {
+    /// Number of denials that triggers a report.
+    pub threshold: u32,
+    /// Fixed window length, in seconds, starting at a principal's first denial.
+    pub window_seconds: u64,
+}
+
+/// Caller-owned state for pattern-based anomalies.
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyState {
+    pattern: Option<DenialPattern>,
+    /// `(principal, window start, denials)`.
+    denials: Vec<(String, u64, u32)>,
+}
+
+impl AnomalyState {
+    /// State that reports errors, budgets, Indeterminate, and break-glass only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// State that also reports repeated denials.
+    pub fn with_denial_pattern(pattern: DenialPattern) -> Self {
+        AnomalyState {
+            pattern: Some(pattern),
+            denials: Vec::new(),
+        }
+    }
+
+    /// Number of principals currently tracked.
+    pub fn tracked_principals(&self) -> usize {
+        self.denials.len()
+    }
+
+    /// Count a denial for `principal` at `now`; returns the count if it
+    /// just reached the threshold.
+    fn record_denial(&mut self, principal: &str, now: u64) -> Option<(u32, DenialPattern)> {
+        let pattern = self.pattern?;
+        let index = match self.denials.iter().position(|(p, _, _)| p == principal) {
+            Some(index) => index,
+            None => {
+                if self.denials.len() >= MAX_TRACKED_PRINCIPALS {
+                    let oldest = (0..self.denials.len()).min_by_key(|&i| self.denials[i].1)?;
+                    self.denials.swap_remove(oldest);
+                }
+                self.denials.push((principal.to_string(), now, 0));
+                self.denials.len() - 1
+            }
+        };
+        let (_, start, count) = &mut self.denials[index];
+        if now.saturating_sub(*start) >= pattern.window_seconds {
+            *start = now;
+            *count = 0;
+        }
+        *count = count.saturating_add(1);
+        (*count == pattern.threshold).then_some((*count, pattern))
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Evaluate this policy, reporting anomalies to `sink`.
+    ///
+    /// Returns exactly what `evaluate()` returns. `now` is the current time
+    /// in seconds on any monotonic scale the caller chooses; it only
+    /// positions denials within `state`'s windows.
+    pub fn evaluate_with_anomalies(
+        &self,
+        request: &Request<'_>,
+        now: u64,
+        state: &mut AnomalyState,
+        sink: &dyn AnomalySink,
+    ) -> Result<Decision, PolicyError> {
+        let result = self.evaluate(request);
+        match &result {
+            Err(error) => sink.report(request, &classify(error)),
+            Ok(decision) => {
+                if decision.break_glass {
+                    sink.report(request, &Anomaly::BreakGlass(decision.reason));
+                }
+                match decision.effect {
+                    Effect::Indeterminate => {
+                        sink.report(request, &Anomaly::Indeterminate(decision.reason));
+                    }
+                    Effect::Deny => {
+                        if let Some((denials, pattern)) =
+                            state.record_denial(request.principal, now)
+                        {
+                            let anomaly = Anomaly::RepeatedDenials {
+                                denials,
+                                window_seconds: pattern.window_seconds,
+                            };
+                            sink.report(request, &anomaly);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        result
+    }
+}
+
+fn classify(error: &PolicyError) -> Anomaly<'_> {
+    match error {
+        PolicyError::ContextTooLarge { .. }
+        | PolicyError::StringTooLong { .. }
+        | PolicyError::EvalStackOverflow { .. }
+        | PolicyError::GroupLookupBudgetExceeded { .. }
+        | PolicyError::EvalBudgetExceeded { .. } => Anomaly::BudgetExhausted(error),
+        _ => Anomaly::EvaluationFailed(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::target::{Matcher, Target};
+    use crate::value::Value;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct Recorder(RefCell<Vec<String>>);
+
+    impl AnomalySink for Recorder {
+        fn report(&self, request: &Request<'_>, anomaly: &Anomaly<'_>) {
+            let entry = format!("{}: {:?}", request.principal, anomaly);
+            self.0.borrow_mut().push(entry);
+        }
+    }
+
+    fn policy() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                Some(Condition::MemberOf("staff")),
+                ReasonCode(1),
+            ))
+            .rule(Rule::break_glass(Target::any(), "emergency", ReasonCode(9)))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_errors_and_break_glass() {
+        let policy = policy();
+        let sink = Recorder::default();
+        let mut state = AnomalyState::new();
+
+        let read = Request::new("alice", "read", "doc");
+        assert!(policy
+            .evaluate_with_anomalies(&read, 0, &mut state, &sink)
+            .is_err());
+        let ctx = [("emergency", Value::Bool(true))];
+        let urgent = Request::with_context("bob", "write", "doc", &ctx);
+        assert!(
+            policy
+                .evaluate_with_anomalies(&urgent, 0, &mut state, &sink)
+                .unwrap()
+                .break_glass
+        );
+
+        assert_eq!(
+            sink.0.into_inner(),
+            vec![
+                "alice: EvaluationFailed(GroupLookupFailed)".to_string(),
+                "bob: BreakGlass(ReasonCode(9))".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeated_denials() {
+        let policy = policy();
+        let sink = Recorder::default();
+        let mut state = AnomalyState::with_denial_pattern(DenialPattern {
+            threshold: 3,
+            window_seconds: 60,
+        });
+        let write = Request::new("mallory", "write", "doc");
+        for now in [0, 10, 20, 30, 70, 80, 90] {
+            policy
+                .evaluate_with_anomalies(&write, now, &mut state, &sink)
+                .unwrap();
+        }
+
+        // Reported once when the first window reaches 3, then again in the next.
+        let expected = "mallory: RepeatedDenials { denials: 3, window_seconds: 60 }";
+        assert_eq!(sink.0.into_inner(), vec![expected, expected]);
+        assert_eq!(state.tracked_principals(), 1);
+    }
+}
@@ -0,0 +1,353 @@
+//! Typed attribute keys.
+//!
+//! Declare each context attribute once, with its type, and use the key
+//! everywhere the name would otherwise be spelled out:
+//!
+//! ```
+//! use gate0::{AttrKey, ContextBuilder, Request};
+//!
+//! const ROLE: AttrKey<&str> = AttrKey::new("role");
+//! const LEVEL: AttrKey<i64> = AttrKey::new("level");
+//!
+//! let condition = ROLE.equals("admin");
+//!
+//! let ctx = ContextBuilder::new().set(ROLE, "admin").set(LEVEL, 5).build();
+//! let request = Request::with_context("alice", "read", "doc", &ctx);
+//!
+//! assert_eq!(condition.evaluate(request.context), Ok(true));
+//! assert_eq!(LEVEL.get(request.context), Some(5));
+//! // LEVEL.equals("5") does not compile.
+//! ```
+//!
+//! Keys are a name plus a zero-sized type marker; they compile down to the
+//! same `Condition` and `Value` a hand-written literal would.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::net::IpAddr;
+
+use crate::condition::Condition;
+use crate::error::PolicyError;
+use crate::pattern::Pattern;
+use crate::value::{checked_int, parse_int, Value, ValueList};
+
+/// A Rust type that maps onto one `Value` variant.
+pub trait AttrType {
+    /// The borrowed form used in conditions and contexts.
+    type Borrowed<'a>;
+
+    /// Wrap a value.
+    fn to_value<'a>(v: Self::Borrowed<'a>) -> Value<'a>;
+
+    /// Unwrap a value of the matching variant.
+    fn from_value<'a>(v: &Value<'a>) -> Option<Self::Borrowed<'a>>;
+}
+
+impl AttrType for bool {
+    type Borrowed<'a> = bool;
+
+    fn to_value<'a>(v: bool) -> Value<'a> {
+        Value::Bool(v)
+    }
+
+    fn from_value<'a>(v: &Value<'a>) -> Option<bool> {
+        v.as_bool()
+    }
+}
+
+impl AttrType for i64 {
+    type Borrowed<'a> = i64;
+
+    fn to_value<'a>(v: i64) -> Value<'a> {
+        Value::Int(v)
+    }
+
+    fn from_value<'a>(v: &Value<'a>) -> Option<i64> {
+        v.as_int()
+    }
+}
+
+impl AttrType for &str {
+    type Borrowed<'a> = &'a str;
+
+    fn to_value<'a>(v: Self::Borrowed<'a>) -> Value<'a> {
+        Value::String(v)
+    }
+
+    fn from_value<'a>(v: &Value<'a>) -> Option<&'a str> {
+        v.as_str()
+    }
+}
+
+impl AttrType for &[u8] {
+    type Borrowed<'a> = &'a [u8];
+
+    fn to_value<'a>(v: Self::Borrowed<'a>) -> Value<'a> {
+        Value::Secret(v)
+    }
+
+    fn from_value<'a>(v: &Value<'a>) -> Option<&'a [u8]> {
+        v.as_secret()
+    }
+}
+
+impl AttrType for IpAddr {
+    type Borrowed<'a> = IpAddr;
+
+    fn to_value<'a>(v: Self::Borrowed<'a>) -> Value<'a> {
+        Value::Ip(v)
+    }
+
+    fn from_value<'a>(v: &Value<'a>) -> Option<IpAddr> {
+        v.as_ip()
+    }
+}
+
+/// Lists are context-only: `equals` on a list key builds a condition
+/// that fails validation with `PolicyError::InvalidList`. Use `contains`.
+impl AttrType for ValueList<'_> {
+    type Borrowed<'a> = ValueList<'a>;
+
+    fn to_value<'a>(v: Self::Borrowed<'a>) -> Value<'a> {
+        Value::List(v)
+    }
+
+    fn from_value<'a>(v: &Value<'a>) -> Option<ValueList<'a>> {
+        v.as_list()
+    }
+}
+
+/// A context attribute name with a fixed value type.
+pub struct AttrKey<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> AttrKey<T> {
+    /// Declare a key.
+    pub const fn new(name: &'static str) -> Self {
+        AttrKey {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    /// Get the attribute name.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Condition: `inner`, but evaluation fails if the attribute is
+    /// missing (see `Condition::Require`).
+    pub fn require<'a>(&self, inner: Condition<'a>) -> Condition<'a> {
+        Condition::Require(self.name, Box::new(inner))
+    }
+}
+
+impl<T: AttrType> AttrKey<T> {
+    /// Condition: the attribute equals `value`.
+    pub fn equals<'a>(&self, value: T::Borrowed<'a>) -> Condition<'a> {
+        Condition::Equals {
+            attr: self.name,
+            value: T::to_value(value),
+        }
+    }
+
+    /// Condition: the attribute does not equal `value`.
+    pub fn not_equals<'a>(&self, value: T::Borrowed<'a>) -> Condition<'a> {
+        Condition::NotEquals {
+            attr: self.name,
+            value: T::to_value(value),
+        }
+    }
+
+    /// A context entry for this attribute.
+    pub fn entry<'a>(&self, value: T::Borrowed<'a>) -> (&'a str, Value<'a>) {
+        (self.name, T::to_value(value))
+    }
+
+    /// Read the attribute from a context.
+    ///
+    /// Returns `None` if it is missing or holds a different type.
+    pub fn get<'a>(&self, context: &[(&str, Value<'a>)]) -> Option<T::Borrowed<'a>> {
+        context
+            .iter()
+            .find(|(k, _)| *k == self.name)
+            .and_then(|(_, v)| T::from_value(v))
+    }
+}
+
+impl AttrKey<i64> {
+    /// Condition: every bit of `mask` is set in the attribute.
+    pub fn has_flags<'a>(&self, mask: i64) -> Condition<'a> {
+        Condition::HasFlags {
+            attr: self.name,
+            mask,
+        }
+    }
+}
+
+impl AttrKey<&str> {
+    /// Condition: the attribute equals `value`, ignoring case.
+    pub fn equals_ignore_case<'a>(&self, value: &'a str) -> Condition<'a> {
+        Condition::EqualsIgnoreCase {
+            attr: self.name,
+            value,
+        }
+    }
+
+    /// Condition: the attribute matches `pattern`.
+    pub fn matches<'a>(&self, pattern: Pattern) -> Condition<'a> {
+        Condition::Matches {
+            attr: self.name,
+            pattern,
+        }
+    }
+
+    /// Condition: the whole attribute matches the glob `pattern`.
+    pub fn glob<'a>(&self, pattern: &'a str) -> Condition<'a> {
+        Condition::GlobMatches {
+            attr: self.name,
+            pattern,
+        }
+    }
+}
+
+impl AttrKey<ValueList<'_>> {
+    /// Condition: the list attribute contains `value`.
+    pub fn contains<'a>(&self, value: Value<'a>) -> Condition<'a> {
+        Condition::AttrContains {
+            attr: self.name,
+            value,
+        }
+    }
+}
+
+// Manual impls: derives would needlessly require `T: Clone` etc.
+impl<T> Clone for AttrKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for AttrKey<T> {}
+
+impl<T> fmt::Debug for AttrKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AttrKey").field(&self.name).finish()
+    }
+}
+
+/// Builds request context from typed keys.
+///
+/// Setting a key twice replaces the earlier value, so the resulting
+/// context never contains duplicate names.
+#[derive(Debug, Clone, Default)]
+pub struct ContextBuilder<'a> {
+    entries: Vec<(&'a str, Value<'a>)>,
+}
+
+impl<'a> ContextBuilder<'a> {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        ContextBuilder {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Set a typed attribute.
+    pub fn set<T: AttrType>(mut self, key: AttrKey<T>, value: T::Borrowed<'a>) -> Self {
+        let (name, value) = key.entry(value);
+        match self.entries.iter_mut().find(|(k, _)| *k == name) {
+            Some(slot) => slot.1 = value,
+            None => self.entries.push((name, value)),
+        }
+        self
+    }
+
+    /// Set an integer attribute from any integer type, failing with
+    /// `PolicyError::IntegerOutOfRange` rather than truncating.
+    pub fn set_checked<N: TryInto<i64>>(
+        self,
+        key: AttrKey<i64>,
+        value: N,
+    ) -> Result<Self, PolicyError> {
+        Ok(self.set(key, checked_int(value)?))
+    }
+
+    /// Set an integer attribute from decimal text; see `Value::parse_int`.
+    pub fn set_parsed(self, key: AttrKey<i64>, text: &str) -> Result<Self, PolicyError> {
+        Ok(self.set(key, parse_int(text)?))
+    }
+
+    /// Finish, returning the context entries in insertion order.
+    pub fn build(self) -> Vec<(&'a str, Value<'a>)> {
+        self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLE: AttrKey<&str> = AttrKey::new("role");
+    const MFA: AttrKey<bool> = AttrKey::new("mfa");
+    const LEVEL: AttrKey<i64> = AttrKey::new("level");
+
+    #[test]
+    fn test_conditions_match_literals() {
+        assert_eq!(
+            ROLE.equals("admin"),
+            Condition::Equals {
+                attr: "role",
+                value: Value::String("admin"),
+            }
+        );
+        assert_eq!(
+            MFA.not_equals(true),
+            Condition::NotEquals {
+                attr: "mfa",
+                value: Value::Bool(true),
+            }
+        );
+    }
+
+    #[test]
+    fn test_builder_and_get() {
+        // Runtime-owned strings work, not just literals
+        let role = String::from("editor");
+        let ctx = ContextBuilder::new()
+            .set(ROLE, "viewer")
+            .set(LEVEL, 3)
+            .set(ROLE, &role)
+            .build();
+
+        assert_eq!(ctx.len(), 2);
+        assert_eq!(ROLE.get(&ctx), Some("editor"));
+        assert_eq!(LEVEL.get(&ctx), Some(3));
+        assert_eq!(MFA.get(&ctx), None);
+
+        // Same name, different type: reads as absent
+        const LEVEL_AS_STR: AttrKey<&str> = AttrKey::new("level");
+        assert_eq!(LEVEL_AS_STR.get(&ctx), None);
+    }
+
+    #[test]
+    fn test_checked_ints() {
+        const USER_ID: AttrKey<i64> = AttrKey::new("user_id");
+
+        let ctx = ContextBuilder::new()
+            .set_checked(USER_ID, 7u64)
+            .unwrap()
+            .set_parsed(LEVEL, "-3")
+            .unwrap()
+            .build();
+        assert_eq!(USER_ID.get(&ctx), Some(7));
+        assert_eq!(LEVEL.get(&ctx), Some(-3));
+
+        let big = ContextBuilder::new().set_checked(USER_ID, u64::MAX);
+        assert_eq!(big.unwrap_err(), PolicyError::IntegerOutOfRange);
+        let float = ContextBuilder::new().set_parsed(LEVEL, "2.5");
+        assert_eq!(float.unwrap_err(), PolicyError::InvalidInteger);
+    }
+}
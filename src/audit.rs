@@ -0,0 +1,454 @@
+//! One-pass audited evaluation.
+//!
+//! `Policy::evaluate_with_audit` produces the decision, its
+//! `EvaluationStats`, a bounded trace of which rules ran, and a correlation
+//! id, then hands the resulting `AuditRecord` to every sink registered on
+//! the caller's `AuditBuffer`. The buffer keeps the last record and reuses
+//! its trace allocation across calls.
+//!
+//! High-QPS services can set an `AuditSampling` on the buffer to dispatch
+//! only a fraction of routine Allow records while still sending every
+//! denial, failure, break-glass use, and listed reason code.
+
+use crate::error::PolicyError;
+use crate::observe::EvalObserver;
+use crate::policy::{Policy, Rule};
+use crate::stats::EvaluationStats;
+use crate::types::{Decision, ReasonCode, Request};
+
+/// Maximum number of events kept in one record's trace.
+pub const MAX_TRACE_EVENTS: usize = 64;
+
+/// A step of an evaluation, identified by rule index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The rule's target matched and its condition ran.
+    ConditionEvaluated(usize),
+    /// The rule's target and condition both matched.
+    RuleMatched(usize),
+    /// An active break-glass rule decided the request.
+    BreakGlass(usize),
+    /// A Deny rule matched under `DenyAggregation::CollectAll`.
+    Denied(usize, ReasonCode),
+}
+
+/// Everything recorded about one audited evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// Identifier shared by every sink that sees this evaluation.
+    pub correlation_id: u64,
+    /// The decision, or the error `evaluate()` would have returned.
+    pub result: Result<Decision, PolicyError>,
+    /// Bound usage for this evaluation.
+    pub stats: EvaluationStats,
+    /// Up to `MAX_TRACE_EVENTS` events, in evaluation order.
+    pub trace: Vec<TraceEvent>,
+    /// Whether events were dropped because the trace was full.
+    pub trace_truncated: bool,
+    /// The policy's metadata name, or empty if it has none.
+    pub policy_name: String,
+    /// The policy's metadata version, or empty if it has none.
+    pub policy_version: String,
+    /// Tags of the rules that matched, each once, in first-match order.
+    /// Unlike the trace, never truncated.
+    pub matched_tags: Vec<String>,
+}
+
+impl AuditRecord {
+    /// Whether a rule carrying `tag` matched during this evaluation.
+    pub fn matched_tag(&self, tag: &str) -> bool {
+        self.matched_tags.iter().any(|t| t == tag)
+    }
+}
+
+/// Receives audit records, e.g. to log them or export metrics.
+///
+/// Takes `&self` like `GroupProvider`; use interior mutability to record.
+pub trait AuditSink {
+    /// Called once per audited evaluation, in registration order.
+    fn record(&self, request: &Request<'_>, record: &AuditRecord);
+}
+
+/// Which records reach the sinks.
+///
+/// Sampling is deterministic: every `allow_one_in`-th routine Allow is
+/// dispatched, counting from the first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditSampling {
+    /// Dispatch one in this many routine Allow decisions; 0 and 1 keep all.
+    pub allow_one_in: u32,
+    /// Reason codes that are always dispatched, whatever the effect.
+    pub always_reasons: Vec<ReasonCode>,
+}
+
+impl AuditSampling {
+    /// Whether a record must be dispatched regardless of sampling.
+    fn always(&self, result: &Result<Decision, PolicyError>) -> bool {
+        match result {
+            Err(_) => true,
+            Ok(decision) => {
+                !decision.is_allow()
+                    || decision.break_glass
+                    || self.always_reasons.contains(&decision.reason)
+            }
+        }
+    }
+}
+
+/// Caller-owned sinks, correlation counter, and last record.
+pub struct AuditBuffer<'s> {
+    sinks: Vec<&'s dyn AuditSink>,
+    next_id: u64,
+    last: Option<AuditRecord>,
+    sampling: AuditSampling,
+    routine_allows: u64,
+    sampled_out: u64,
+}
+
+impl<'s> AuditBuffer<'s> {
+    /// A buffer with no sinks whose first correlation id is 1.
+    pub fn new() -> Self {
+        Self::with_first_id(1)
+    }
+
+    /// A buffer whose first correlation id is `id`, e.g. a per-process
+    /// random seed so ids from different instances do not collide.
+    pub fn with_first_id(id: u64) -> Self {
+        AuditBuffer {
+            sinks: Vec::new(),
+            next_id: id,
+            last: None,
+            sampling: AuditSampling::default(),
+            routine_allows: 0,
+            sampled_out: 0,
+        }
+    }
+
+    /// Dispatch only the records `sampling` selects. `last()` still holds
+    /// every record.
+    pub fn set_sampling(&mut self, sampling: AuditSampling) {
+        self.sampling = sampling;
+    }
+
+    /// Number of records withheld from the sinks by sampling so far.
+    pub fn sampled_out(&self) -> u64 {
+        self.sampled_out
+    }
+
+    /// Register a sink. Sinks are called in registration order.
+    pub fn add_sink(&mut self, sink: &'s dyn AuditSink) {
+        self.sinks.push(sink);
+    }
+
+    /// The record of the most recent evaluation, or `None` before the first.
+    pub fn last(&self) -> Option<&AuditRecord> {
+        self.last.as_ref()
+    }
+}
+
+impl Default for AuditBuffer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for AuditBuffer<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditBuffer")
+            .field("sinks", &self.sinks.len())
+            .field("next_id", &self.next_id)
+            .field("last", &self.last)
+            .field("sampling", &self.sampling)
+            .field("sampled_out", &self.sampled_out)
+            .finish()
+    }
+}
+
+/// Feeds the stats, the trace, and the matched tags from one evaluation.
+struct AuditObserver<'r, 'a> {
+    stats: EvaluationStats,
+    trace: &'r mut Vec<TraceEvent>,
+    truncated: bool,
+    rules: &'r [Rule<'a>],
+    tags: &'r mut Vec<String>,
+}
+
+impl AuditObserver<'_, '_> {
+    fn push(&mut self, event: TraceEvent) {
+        if self.trace.len() < MAX_TRACE_EVENTS {
+            self.trace.push(event);
+        } else {
+            self.truncated = true;
+        }
+    }
+
+    fn collect_tags(&mut self, index: usize) {
+        let Some(rule) = self.rules.get(index) else {
+            return;
+        };
+        for tag in rule.tags {
+            if !self.tags.iter().any(|t| t == tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+}
+
+impl EvalObserver for AuditObserver<'_, '_> {
+    fn rule_checked(&mut self, index: usize) {
+        self.stats.rule_checked(index);
+    }
+
+    fn condition_evaluated(&mut self, index: usize) {
+        self.stats.condition_evaluated(index);
+        self.push(TraceEvent::ConditionEvaluated(index));
+    }
+
+    fn rule_matched(&mut self, index: usize) {
+        self.push(TraceEvent::RuleMatched(index));
+        self.collect_tags(index);
+    }
+
+    fn deny_collected(&mut self, index: usize, reason: ReasonCode) {
+        self.push(TraceEvent::Denied(index, reason));
+        self.collect_tags(index);
+    }
+
+    fn break_glass_used(&mut self, index: usize) {
+        self.stats.break_glass_used(index);
+        self.push(TraceEvent::BreakGlass(index));
+        self.collect_tags(index);
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Evaluate this policy, recording stats and a trace into `buffer` and
+    /// dispatching the record to its sinks.
+    ///
+    /// Returns exactly what `evaluate()` returns; the full record is
+    /// available from `buffer.last()` afterwards.
+    pub fn evaluate_with_audit(
+        &self,
+        request: &Request<'_>,
+        buffer: &mut AuditBuffer<'_>,
+    ) -> Result<Decision, PolicyError> {
+        // Reuse the previous record's allocations.
+        let (mut trace, mut name, mut version, mut tags) = buffer
+            .last
+            .take()
+            .map(|r| (r.trace, r.policy_name, r.policy_version, r.matched_tags))
+            .unwrap_or_default();
+        trace.clear();
+        tags.clear();
+        name.clear();
+        name.push_str(self.metadata().name.unwrap_or(""));
+        version.clear();
+        version.push_str(self.metadata().version.unwrap_or(""));
+        let mut observer = AuditObserver {
+            stats: EvaluationStats::new(),
+            trace: &mut trace,
+            truncated: false,
+            rules: self.rules(),
+            tags: &mut tags,
+        };
+        let result = self.evaluate_observed(request, None, None, &mut observer);
+        let (stats, truncated) = (observer.stats, observer.truncated);
+
+        let correlation_id = buffer.next_id;
+        buffer.next_id = buffer.next_id.wrapping_add(1);
+        let record = buffer.last.insert(AuditRecord {
+            correlation_id,
+            result: result.clone(),
+            stats,
+            trace,
+            trace_truncated: truncated,
+            policy_name: name,
+            policy_version: version,
+            matched_tags: tags,
+        });
+        let dispatch = buffer.sampling.always(&record.result) || {
+            let n = buffer.routine_allows;
+            buffer.routine_allows = n.wrapping_add(1);
+            n.is_multiple_of(u64::from(buffer.sampling.allow_one_in.max(1)))
+        };
+        if dispatch {
+            for sink in &buffer.sinks {
+                sink.record(request, record);
+            }
+        } else {
+            buffer.sampled_out += 1;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode};
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct Collect(RefCell<Vec<(u64, String)>>);
+
+    impl AuditSink for Collect {
+        fn record(&self, request: &Request<'_>, record: &AuditRecord) {
+            let entry = (record.correlation_id, request.principal.to_string());
+            self.0.borrow_mut().push(entry);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_with_audit() {
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::False),
+                ReasonCode(2),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::MemberOf("staff")),
+                ReasonCode(3),
+            ))
+            .build()
+            .unwrap();
+        let (first, second) = (Collect::default(), Collect::default());
+        let mut buffer = AuditBuffer::with_first_id(41);
+        buffer.add_sink(&first);
+        buffer.add_sink(&second);
+        assert!(buffer.last().is_none());
+
+        let request = Request::new("alice", "read", "doc");
+        let result = policy.evaluate_with_audit(&request, &mut buffer);
+        assert_eq!(result, policy.evaluate(&request));
+        let record = buffer.last().unwrap();
+        assert_eq!(record.correlation_id, 41);
+        assert_eq!(record.result, Err(PolicyError::GroupLookupFailed));
+        assert_eq!(record.stats.rules_checked, 3);
+        assert_eq!(
+            record.trace,
+            vec![
+                TraceEvent::RuleMatched(0),
+                TraceEvent::ConditionEvaluated(1),
+                TraceEvent::ConditionEvaluated(2),
+            ]
+        );
+
+        let bob = Request::new("bob", "read", "doc");
+        let _ = policy.evaluate_with_audit(&bob, &mut buffer);
+        let expected = vec![(41, "alice".to_string()), (42, "bob".to_string())];
+        assert_eq!(first.0.into_inner(), expected);
+        assert_eq!(second.0.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_collect_all_denials() {
+        let config = crate::policy::PolicyConfig {
+            deny_aggregation: crate::policy::DenyAggregation::CollectAll,
+            ..Default::default()
+        };
+        let rules = vec![
+            Rule::deny(Target::any(), ReasonCode(7)),
+            Rule::allow(Target::any(), ReasonCode(1)),
+            Rule::deny(Target::any(), ReasonCode(3)),
+        ];
+        let policy = Policy::with_config(rules, config).unwrap();
+        let mut buffer = AuditBuffer::new();
+        let request = Request::new("alice", "read", "doc");
+        let decision = policy.evaluate_with_audit(&request, &mut buffer).unwrap();
+        assert_eq!(decision.reason, ReasonCode(7));
+        let denied = buffer.last().unwrap().trace.iter().filter_map(|e| match e {
+            TraceEvent::Denied(rule, reason) => Some((*rule, *reason)),
+            _ => None,
+        });
+        assert_eq!(
+            denied.collect::<Vec<_>>(),
+            vec![(0, ReasonCode(7)), (2, ReasonCode(3))]
+        );
+    }
+
+    #[test]
+    fn test_sampling() {
+        let policy = Policy::builder()
+            .rule(Rule::deny(
+                Target {
+                    principal: crate::target::Matcher::Exact("mallory"),
+                    action: crate::target::Matcher::Any,
+                    resource: crate::target::Matcher::Any,
+                    environment: crate::target::Matcher::Any,
+                },
+                ReasonCode(2),
+            ))
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .build()
+            .unwrap();
+        let sink = Collect::default();
+        let mut buffer = AuditBuffer::new();
+        buffer.add_sink(&sink);
+        buffer.set_sampling(AuditSampling {
+            allow_one_in: 3,
+            always_reasons: Vec::new(),
+        });
+        for principal in ["a", "b", "mallory", "c", "d", "e"] {
+            let request = Request::new(principal, "read", "doc");
+            let _ = policy.evaluate_with_audit(&request, &mut buffer);
+        }
+        let logged: Vec<String> = sink.0.borrow().iter().map(|(_, p)| p.clone()).collect();
+        assert_eq!(logged, ["a", "mallory", "d"]);
+        assert_eq!(buffer.sampled_out(), 3);
+        assert_eq!(buffer.last().unwrap().correlation_id, 6);
+
+        buffer.set_sampling(AuditSampling {
+            allow_one_in: 1000,
+            always_reasons: vec![ReasonCode(1)],
+        });
+        let _ = policy.evaluate_with_audit(&Request::new("f", "read", "doc"), &mut buffer);
+        assert_eq!(sink.0.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_trace_is_bounded() {
+        let rules = vec![Rule::allow(Target::any(), ReasonCode(1)); MAX_TRACE_EVENTS + 1];
+        let policy = Policy::new(rules).unwrap();
+        let mut buffer = AuditBuffer::new();
+        let request = Request::new("alice", "read", "doc");
+        assert!(policy
+            .evaluate_with_audit(&request, &mut buffer)
+            .unwrap()
+            .is_allow());
+        let record = buffer.last().unwrap();
+        assert_eq!(record.trace.len(), MAX_TRACE_EVENTS);
+        assert!(record.trace_truncated);
+    }
+
+    #[test]
+    fn test_matched_tags() {
+        let policy = Policy::builder()
+            .rule(
+                Rule::new(
+                    Effect::Deny,
+                    Target::any(),
+                    Some(Condition::False),
+                    ReasonCode(1),
+                )
+                .with_tags(&["pci"]),
+            )
+            .rule(Rule::allow(Target::any(), ReasonCode(2)).with_tags(&["temporary", "pci"]))
+            .build()
+            .unwrap();
+        let mut buffer = AuditBuffer::new();
+        let request = Request::new("alice", "read", "doc");
+        let _ = policy.evaluate_with_audit(&request, &mut buffer);
+        let record = buffer.last().unwrap();
+        assert_eq!(record.matched_tags, ["temporary", "pci"]);
+        assert!(record.matched_tag("pci"));
+        assert!(!record.matched_tag("team:payments"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Batch evaluation over many resources.
+//!
+//! List endpoints ask the same question for every row: may this principal
+//! perform this action, with this context, on each of these resources?
+//! Only the resource changes, so everything else a rule depends on (its
+//! principal and action matchers, break-glass flag, schedule, and
+//! condition) has the same answer for the whole batch. `BatchMemo` keeps
+//! those answers per rule, computed on first use, and each resource then
+//! costs one resource match per rule. Decisions are identical to calling
+//! `evaluate()` once per resource.
+
+use crate::error::PolicyError;
+use crate::groups::GroupProvider;
+use crate::observe::NoopObserver;
+use crate::policy::Policy;
+use crate::types::{Decision, Request};
+use crate::value::Value;
+
+/// Per-rule answers shared by a batch of requests.
+pub(crate) struct BatchMemo {
+    /// Whether each rule applies apart from its resource matcher.
+    pub(crate) active: Vec<Option<bool>>,
+    /// Each rule's condition result, errors and unknowns included.
+    pub(crate) conditions: Vec<Option<Result<Option<bool>, PolicyError>>>,
+    /// The shared context has passed validation.
+    pub(crate) context_checked: bool,
+}
+
+impl BatchMemo {
+    pub(crate) fn new(rules: usize) -> Self {
+        BatchMemo {
+            active: vec![None; rules],
+            conditions: vec![None; rules],
+            context_checked: false,
+        }
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Evaluate `principal` performing `action` on each of `resources`.
+    ///
+    /// Returns one result per resource, in order, each equal to what
+    /// `evaluate()` returns for that resource. Rule conditions are
+    /// evaluated at most once for the whole batch.
+    pub fn evaluate_batch(
+        &self,
+        principal: &str,
+        action: &str,
+        context: &[(&str, Value<'_>)],
+        resources: &[&str],
+    ) -> Vec<Result<Decision, PolicyError>> {
+        self.batch(principal, action, context, resources, None)
+    }
+
+    /// Like `evaluate_batch`, resolving `MemberOf` conditions through
+    /// `groups`. Each group is looked up at most once for the batch.
+    pub fn evaluate_batch_with_groups(
+        &self,
+        principal: &str,
+        action: &str,
+        context: &[(&str, Value<'_>)],
+        resources: &[&str],
+        groups: &dyn GroupProvider,
+    ) -> Vec<Result<Decision, PolicyError>> {
+        self.batch(principal, action, context, resources, Some(groups))
+    }
+
+    fn batch(
+        &self,
+        principal: &str,
+        action: &str,
+        context: &[(&str, Value<'_>)],
+        resources: &[&str],
+        groups: Option<&dyn GroupProvider>,
+    ) -> Vec<Result<Decision, PolicyError>> {
+        let mut memo = BatchMemo::new(self.rule_count());
+        resources
+            .iter()
+            .map(|resource| {
+                let request = Request::with_context(principal, action, resource, context);
+                self.evaluate_memoized(
+                    &request,
+                    groups,
+                    None,
+                    Some(&mut memo),
+                    false,
+                    &mut NoopObserver,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::groups::ProviderError;
+    use crate::policy::{PolicyConfig, Rule};
+    use crate::target::{Matcher, Target};
+    use crate::types::{Effect, ReasonCode};
+    use std::cell::Cell;
+
+    struct Directory {
+        calls: Cell<usize>,
+    }
+
+    impl GroupProvider for Directory {
+        fn is_member(&self, _principal: &str, group: &str) -> Result<bool, ProviderError> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(group == "eng")
+        }
+    }
+
+    fn on(resource: Matcher<'static>) -> Target<'static> {
+        Target {
+            principal: Matcher::Any,
+            action: Matcher::Exact("read"),
+            resource,
+            environment: Matcher::Any,
+        }
+    }
+
+    #[test]
+    fn test_batch_matches_single_evaluation() {
+        let secret: &[&str] = &["payroll", "keys"];
+        let policy = Policy::builder()
+            .rule(Rule::deny(on(Matcher::OneOf(secret)), ReasonCode(1)))
+            .rule(Rule::new(
+                Effect::Allow,
+                on(Matcher::Exact("wiki")),
+                Some(Condition::MemberOf("eng")),
+                ReasonCode(2),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                on(Matcher::Any),
+                Some(Condition::Equals {
+                    attr: "tier",
+                    value: Value::String("gold"),
+                }),
+                ReasonCode(3),
+            ))
+            .build()
+            .unwrap();
+        let resources = ["wiki", "payroll", "blog", "keys", "wiki"];
+
+        for tier in ["gold", "free"] {
+            let ctx: &[(&str, Value)] = &[("tier", Value::String(tier))];
+            let batch = policy.evaluate_batch("alice", "read", ctx, &resources);
+            for (resource, result) in resources.iter().zip(&batch) {
+                let request = Request::with_context("alice", "read", resource, ctx);
+                assert_eq!(*result, policy.evaluate(&request));
+            }
+
+            let dir = Directory {
+                calls: Cell::new(0),
+            };
+            let batch = policy.evaluate_batch_with_groups("alice", "read", ctx, &resources, &dir);
+            for (resource, result) in resources.iter().zip(&batch) {
+                let request = Request::with_context("alice", "read", resource, ctx);
+                assert_eq!(*result, policy.evaluate_with_groups(&request, &dir));
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_reuses_condition_results() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::MemberOf("eng")),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let dir = Directory {
+            calls: Cell::new(0),
+        };
+        let resources = ["a"; 100];
+        let batch = policy.evaluate_batch_with_groups("alice", "read", &[], &resources, &dir);
+        assert!(batch.iter().all(|r| r.as_ref().unwrap().is_allow()));
+        assert_eq!(dir.calls.get(), 1);
+    }
+
+    #[test]
+    fn test_batch_errors_per_resource() {
+        let config = PolicyConfig {
+            max_string_len: 4,
+            ..PolicyConfig::default()
+        };
+        let policy =
+            Policy::with_config(vec![Rule::allow(Target::any(), ReasonCode(1))], config).unwrap();
+        let batch = policy.evaluate_batch("bob", "read", &[], &["doc", "too-long"]);
+        assert!(batch[0].as_ref().unwrap().is_allow());
+        assert!(matches!(batch[1], Err(PolicyError::StringTooLong { .. })));
+
+        // Both fail: the shared context is invalid.
+        let ctx: &[(&str, Value)] = &[("k", Value::String("too-long"))];
+        let batch = policy.evaluate_batch("bob", "read", ctx, &["doc", "img"]);
+        assert!(batch.iter().all(|r| r.is_err()));
+    }
+}
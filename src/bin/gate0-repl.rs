@@ -0,0 +1,292 @@
+//! Interactive policy REPL.
+//!
+//! Loads a gatelang policy and evaluates requests typed at the prompt,
+//! printing the decision, a per-rule explanation, and evaluation stats.
+//! Meant for learning how combining and conditions behave.
+//!
+//! ```text
+//! cargo run --bin gate0-repl -- policy.g0
+//! > :set role "staff"
+//! > alice read report.pdf
+//! ```
+//!
+//! Only gatelang is supported: JSON and YAML loaders would need
+//! dependencies, which gate0 does not take.
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use gate0::gatelang::PolicyDoc;
+use gate0::{Decision, Effect, Policy, Request, Value, ValueBuf};
+
+const HELP: &str = "\
+commands:
+  PRINCIPAL ACTION RESOURCE  evaluate a request with the current context
+  :load PATH                 load a gatelang policy
+  :rules                     list the loaded rules
+  :set KEY VALUE             set a context attribute (true, false, 42, \"text\", text,
+                             or a list like [admin, ops])
+  :unset KEY                 remove a context attribute
+  :context                   show the current context
+  :clear                     remove every context attribute
+  :help                      show this help
+  :quit                      exit";
+
+struct Session {
+    path: Option<String>,
+    source: String,
+    context: Vec<(String, ValueBuf)>,
+}
+
+fn main() -> ExitCode {
+    let mut session = Session {
+        path: None,
+        source: String::new(),
+        context: Vec::new(),
+    };
+    if let Some(path) = env::args().nth(1) {
+        if let Err(e) = session.load(&path) {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!("gate0 policy REPL. Type :help for commands.");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("error reading input: {}", e);
+                return ExitCode::FAILURE;
+            }
+            None => return ExitCode::SUCCESS,
+        };
+        match session.command(line.trim()) {
+            Ok(true) => {}
+            Ok(false) => return ExitCode::SUCCESS,
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
+
+impl Session {
+    /// Run one line of input; returns `Ok(false)` to exit.
+    fn command(&mut self, line: &str) -> Result<bool, String> {
+        let (head, rest) = split_word(line);
+        match head {
+            "" => {}
+            ":quit" | ":q" => return Ok(false),
+            ":help" => println!("{}", HELP),
+            ":load" => self.load(rest)?,
+            ":rules" => self.print_rules()?,
+            ":set" => {
+                let (key, value) = split_word(rest);
+                if key.is_empty() || value.is_empty() {
+                    return Err("usage: :set KEY VALUE".to_string());
+                }
+                let value = parse_value(value);
+                match self.context.iter_mut().find(|(k, _)| k == key) {
+                    Some(entry) => entry.1 = value,
+                    None => self.context.push((key.to_string(), value)),
+                }
+            }
+            ":unset" => self.context.retain(|(k, _)| k != rest),
+            ":clear" => self.context.clear(),
+            ":context" => {
+                for (key, value) in &self.context {
+                    println!("  {} = {}", key, render_value(&value.as_value()));
+                }
+            }
+            _ if head.starts_with(':') => {
+                return Err(format!("unknown command {}; try :help", head));
+            }
+            _ => {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                let [principal, action, resource] = words[..] else {
+                    return Err("expected PRINCIPAL ACTION RESOURCE".to_string());
+                };
+                self.evaluate(principal, action, resource)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn load(&mut self, path: &str) -> Result<(), String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let rules = {
+            let doc = PolicyDoc::parse(&source).map_err(|e| format!("{}: {}", path, e))?;
+            doc.to_policy().map_err(|e| format!("{}: {}", path, e))?;
+            doc.rules.len()
+        };
+        println!("loaded {} rule(s) from {}", rules, path);
+        self.path = Some(path.to_string());
+        self.source = source;
+        Ok(())
+    }
+
+    fn with_policy<T>(
+        &self,
+        f: impl FnOnce(&PolicyDoc<'_>, &Policy<'_>) -> T,
+    ) -> Result<T, String> {
+        if self.path.is_none() {
+            return Err("no policy loaded; use :load PATH".to_string());
+        }
+        // Validated by `load`, so these only fail if the source was never set.
+        let doc = PolicyDoc::parse(&self.source).map_err(|e| e.to_string())?;
+        let policy = doc.to_policy().map_err(|e| e.to_string())?;
+        Ok(f(&doc, &policy))
+    }
+
+    fn print_rules(&self) -> Result<(), String> {
+        self.with_policy(|doc, _| {
+            for (index, rule) in doc.rules.iter().enumerate() {
+                println!(
+                    "  #{} line {}: {:?} reason {}",
+                    index,
+                    rule.line,
+                    rule.effect,
+                    reason_label(doc, rule.reason.0)
+                );
+            }
+        })
+    }
+
+    fn evaluate(&self, principal: &str, action: &str, resource: &str) -> Result<(), String> {
+        let context: Vec<(&str, Value<'_>)> = self
+            .context
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_value()))
+            .collect();
+        let request = Request::with_context(principal, action, resource, &context);
+        self.with_policy(|doc, policy| match policy.evaluate_with_stats(&request) {
+            Ok((decision, stats)) => {
+                print_decision(doc, &decision);
+                explain(doc, policy, &request);
+                println!(
+                    "stats: {} rule(s) checked, {} condition node(s), max depth {}",
+                    stats.rules_checked, stats.condition_evals, stats.max_depth_reached
+                );
+            }
+            Err(e) => println!("evaluation failed: {}", e),
+        })
+    }
+}
+
+fn print_decision(doc: &PolicyDoc<'_>, decision: &Decision) {
+    let effect = match decision.effect {
+        Effect::Allow => "ALLOW".to_string(),
+        Effect::Deny => "DENY".to_string(),
+        Effect::Challenge(method) => format!("CHALLENGE ({})", method),
+        Effect::Indeterminate => "INDETERMINATE".to_string(),
+    };
+    println!("{} reason {}", effect, reason_label(doc, decision.reason.0));
+    if let Some(ttl) = decision.cache_ttl {
+        println!("  cache ttl: {}s", ttl);
+    }
+    for obligation in decision.obligations.iter() {
+        println!("  obligation: {:?}", obligation);
+    }
+    if decision.break_glass {
+        println!("  break-glass rule decided this request");
+    }
+}
+
+/// Show, rule by rule, why each rule did or did not match.
+///
+/// Conditions are re-evaluated against the context alone, so `MemberOf`
+/// conditions report as errors and schedules and break-glass flags are not
+/// shown; the decision above is authoritative.
+fn explain(doc: &PolicyDoc<'_>, policy: &Policy<'_>, request: &Request<'_>) {
+    for (index, rule) in policy.rules().iter().enumerate() {
+        let status = if !rule
+            .target
+            .matches(request.principal, request.action, request.resource)
+            || !rule.target.matches_environment(request.environment)
+        {
+            "target does not match".to_string()
+        } else {
+            match &rule.condition {
+                None => format!("matches -> {:?}", rule.effect),
+                Some(condition) => match condition.evaluate(request.context) {
+                    Ok(true) => format!("condition holds -> {:?}", rule.effect),
+                    Ok(false) => "condition is false".to_string(),
+                    Err(e) => format!("condition failed: {}", e),
+                },
+            }
+        };
+        let line = doc.rules.get(index).map_or(0, |r| r.line);
+        println!("  #{} (line {}): {}", index, line, status);
+    }
+}
+
+fn reason_label(doc: &PolicyDoc<'_>, code: u32) -> String {
+    match doc.reason_name(gate0::ReasonCode(code)) {
+        Some(name) => format!("{} ({})", name, code),
+        None => code.to_string(),
+    }
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    match s.split_once(char::is_whitespace) {
+        Some((head, rest)) => (head, rest.trim()),
+        None => (s, ""),
+    }
+}
+
+fn parse_value(s: &str) -> ValueBuf {
+    if let Some(items) = s.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        return ValueBuf::List(
+            items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(parse_scalar)
+                .collect(),
+        );
+    }
+    parse_scalar(s)
+}
+
+fn parse_scalar(s: &str) -> ValueBuf {
+    match s {
+        "true" => ValueBuf::Bool(true),
+        "false" => ValueBuf::Bool(false),
+        _ => {
+            if let Ok(i) = s.parse() {
+                ValueBuf::Int(i)
+            } else if let Some(quoted) = s.strip_prefix('"').and_then(|q| q.strip_suffix('"')) {
+                ValueBuf::String(quoted.to_string())
+            } else {
+                ValueBuf::String(s.to_string())
+            }
+        }
+    }
+}
+
+fn render_value(value: &Value<'_>) -> String {
+    match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::String(s) => format!("{:?}", s),
+        Value::Secret(_) => "<redacted>".to_string(),
+        Value::Ip(ip) => ip.to_string(),
+        Value::List(list) => {
+            let values: Vec<String> = list.iter().map(|v| render_value(&v)).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Map(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, render_value(&v)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
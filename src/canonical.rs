@@ -0,0 +1,246 @@
+//! Canonical decision encoding for signing.
+//!
+//! `encode_decision` turns a (request, policy fingerprint, decision) triple
+//! into a stable byte string. A PEP can HMAC or sign those bytes and hand
+//! the signature downstream as proof that the request was authorized by a
+//! specific policy revision; the verifier re-encodes what it was told and
+//! checks the signature. gate0 does no cryptography itself.
+//!
+//! # Format (version 4)
+//!
+//! ```text
+//! encoding: b"G0CD" u8(version)
+//!           u64(fingerprint)
+//!           str(principal) str(action) str(resource) env
+//!           u32(context len) { str(key) value }*
+//!           effect u32(reason) ttl u8(break_glass) obligations
+//! str:      u32(len) bytes (UTF-8)
+//! env:      u8(0) | u8(1) str
+//! value:    u8(0) u8(bool) | u8(1) i64 | u8(2) str | u8(3) Secret | u8(4) ip
+//!           | u8(5) u32(len) value* | u8(6) u32(len) { str(key) value }*
+//! ip:       u8(4) [u8; 4] | u8(6) [u8; 16]
+//! ttl:      u8(0) | u8(1) u32(seconds)
+//! obligations: u8(count) { u8(0) Audit | u8(1) u32 Custom
+//!              | u8(2) u32(approvers) u32(expires_in) Approval }*
+//! effect:   u8(0) Allow | u8(1) Deny | u8(2) method Challenge | u8(3) Indeterminate
+//! method:   u8(0) Mfa | u8(1) Reauthenticate | u8(2) u32 Custom
+//! ```
+//!
+//! All integers are little-endian. Context entries are sorted by key
+//! (bytewise), and so are the entries of each map, so callers that build
+//! the same context in a different order produce the same bytes. Entries
+//! with equal keys keep their relative order: lookups take the first one,
+//! so `[("a", 1), ("a", 2)]` and `[("a", 2), ("a", 1)]` are different
+//! requests and encode differently. Obligations keep their decision order,
+//! which is itself deterministic.
+//!
+//! Secrets are not written: tag 3 has no payload, so signed encodings
+//! stored as evidence never hold a credential, as in the replay log.
+//! Version 2 wrote the secret's bytes. Version 3 sorted entries with equal
+//! keys by encoded value.
+//!
+//! The format is frozen per version: any change to how existing values
+//! encode bumps `VERSION`. New tags, such as the `Approval` obligation, are
+//! appended without one, in a minor release (see docs/STABILITY.md).
+
+use std::net::IpAddr;
+
+use crate::obligation::Obligation;
+use crate::types::{ChallengeMethod, Decision, Effect, Request};
+use crate::value::Value;
+
+const MAGIC: &[u8; 4] = b"G0CD";
+
+/// Current encoding version.
+pub const VERSION: u8 = 4;
+
+/// Encode a decision and the request it answers.
+///
+/// `fingerprint` should be `Policy::fingerprint()` of the policy that
+/// produced `decision`.
+pub fn encode_decision(request: &Request<'_>, fingerprint: u64, decision: &Decision) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&fingerprint.to_le_bytes());
+    put_str(&mut out, request.principal);
+    put_str(&mut out, request.action);
+    put_str(&mut out, request.resource);
+    match request.environment {
+        None => out.push(0),
+        Some(environment) => {
+            out.push(1);
+            put_str(&mut out, environment);
+        }
+    }
+
+    put_entries(
+        &mut out,
+        request
+            .context
+            .iter()
+            .map(|(key, value)| (*key, value.clone())),
+    );
+
+    match decision.effect {
+        Effect::Allow => out.push(0),
+        Effect::Deny => out.push(1),
+        Effect::Challenge(ChallengeMethod::Mfa) => out.extend_from_slice(&[2, 0]),
+        Effect::Challenge(ChallengeMethod::Reauthenticate) => out.extend_from_slice(&[2, 1]),
+        Effect::Challenge(ChallengeMethod::Custom(code)) => {
+            out.extend_from_slice(&[2, 2]);
+            out.extend_from_slice(&code.to_le_bytes());
+        }
+        Effect::Indeterminate => out.push(3),
+    }
+    out.extend_from_slice(&decision.reason.value().to_le_bytes());
+    match decision.cache_ttl {
+        None => out.push(0),
+        Some(ttl) => {
+            out.push(1);
+            out.extend_from_slice(&ttl.to_le_bytes());
+        }
+    }
+    out.push(decision.break_glass as u8);
+    out.push(decision.obligations.len() as u8);
+    for obligation in decision.obligations.iter() {
+        match obligation {
+            Obligation::Audit => out.push(0),
+            Obligation::Custom(code) => {
+                out.push(1);
+                out.extend_from_slice(&code.to_le_bytes());
+            }
+            Obligation::Approval(approval) => {
+                out.push(2);
+                out.extend_from_slice(&approval.approvers.to_le_bytes());
+                out.extend_from_slice(&approval.expires_in.to_le_bytes());
+            }
+        }
+    }
+    out
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Entries sorted by key, then by encoded value.
+fn put_entries<'a>(out: &mut Vec<u8>, entries: impl Iterator<Item = (&'a str, Value<'a>)>) {
+    let mut entries: Vec<(&str, Vec<u8>)> = entries
+        .map(|(key, value)| {
+            let mut v = Vec::new();
+            put_value(&mut v, &value);
+            (key, v)
+        })
+        .collect();
+    // Stable, so duplicate keys stay first-wins.
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for (key, value) in &entries {
+        put_str(out, key);
+        out.extend_from_slice(value);
+    }
+}
+
+fn put_value(out: &mut Vec<u8>, value: &Value<'_>) {
+    match value {
+        Value::Bool(b) => out.extend_from_slice(&[0, *b as u8]),
+        Value::Int(i) => {
+            out.push(1);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::String(s) => {
+            out.push(2);
+            put_str(out, s);
+        }
+        Value::Secret(_) => out.push(3),
+        Value::Ip(IpAddr::V4(v4)) => {
+            out.extend_from_slice(&[4, 4]);
+            out.extend_from_slice(&v4.octets());
+        }
+        Value::Ip(IpAddr::V6(v6)) => {
+            out.extend_from_slice(&[4, 6]);
+            out.extend_from_slice(&v6.octets());
+        }
+        // Validation keeps lists flat, so this recurses at most once.
+        Value::List(list) => {
+            out.push(5);
+            out.extend_from_slice(&(list.len() as u32).to_le_bytes());
+            for element in list.iter() {
+                put_value(out, &element);
+            }
+        }
+        // Validated requests nest maps at most `max_path_depth` deep.
+        Value::Map(map) => {
+            out.push(6);
+            put_entries(out, map.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ReasonCode;
+
+    #[test]
+    fn test_golden_encoding() {
+        // Pinned bytes: a change here is a format change and needs a new VERSION.
+        let ctx: &[(&str, Value)] = &[("mfa", Value::Bool(true)), ("key", Value::Secret(b"sk"))];
+        let request = Request::with_context("a", "r", "d", ctx);
+        let bytes = encode_decision(&request, 0x0102, &Decision::allow(ReasonCode(7)));
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            b'G', b'0', b'C', b'D', 4,
+            0x02, 0x01, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, b'a',
+            1, 0, 0, 0, b'r',
+            1, 0, 0, 0, b'd',
+            0,
+            2, 0, 0, 0,
+            3, 0, 0, 0, b'k', b'e', b'y', 3,
+            3, 0, 0, 0, b'm', b'f', b'a', 0, 1,
+            0, 7, 0, 0, 0,
+            0, 0, 0,
+        ];
+        assert_eq!(bytes, expected);
+
+        // A secret's value is never written
+        let other: &[(&str, Value)] = &[("mfa", Value::Bool(true)), ("key", Value::Secret(b"xy"))];
+        let request = Request::with_context("a", "r", "d", other);
+        assert_eq!(
+            encode_decision(&request, 0x0102, &Decision::allow(ReasonCode(7))),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_context_order_independent() {
+        let a: &[(&str, Value)] = &[("role", Value::String("admin")), ("level", Value::Int(3))];
+        let b: &[(&str, Value)] = &[("level", Value::Int(3)), ("role", Value::String("admin"))];
+        let decision = Decision::deny(ReasonCode(2)).with_cache_ttl(Some(60));
+        let encode =
+            |ctx| encode_decision(&Request::with_context("u", "x", "y", ctx), 9, &decision);
+        assert_eq!(encode(a), encode(b));
+
+        // Everything else is significant
+        let other = encode_decision(&Request::with_context("u", "x", "y", a), 10, &decision);
+        assert_ne!(encode(a), other);
+        let c: &[(&str, Value)] = &[("role", Value::String("admin")), ("level", Value::Int(4))];
+        assert_ne!(encode(a), encode(c));
+        let prod = Request::with_context("u", "x", "y", a).in_environment("prod");
+        assert_ne!(encode(a), encode_decision(&prod, 9, &decision));
+
+        // The first of several equal keys is the one a policy sees
+        let first: &[(&str, Value)] = &[
+            ("role", Value::String("admin")),
+            ("role", Value::String("guest")),
+        ];
+        let last: &[(&str, Value)] = &[
+            ("role", Value::String("guest")),
+            ("role", Value::String("admin")),
+        ];
+        assert_ne!(encode(first), encode(last));
+    }
+}
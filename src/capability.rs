@@ -0,0 +1,430 @@
+//! Capability tokens.
+//!
+//! On Allow, `mint()` issues a short-lived token naming the rule that
+//! allowed the request, the exact (principal, action, resource,
+//! environment) scope, and an expiry. A downstream service holding the same key can `verify()` the
+//! token and honor the decision without calling the PDP again.
+//!
+//! gate0 has no dependencies, so it does not ship a MAC: the caller plugs in
+//! HMAC-SHA256 (or an asymmetric signature) through the `Signer` trait.
+//!
+//! # Format (version 2)
+//!
+//! ```text
+//! token:   payload u32(sig len) sig
+//! payload: b"G0CT" u8(version)
+//!          u64(policy fingerprint) u32(rule index) u32(reason)
+//!          str(principal) str(action) str(resource) env
+//!          i64(expires_at, Unix seconds)
+//! str:     u32(len) bytes (UTF-8)
+//! env:     u8(0) | u8(1) str
+//! ```
+//!
+//! All integers are little-endian. The signature covers the payload only.
+
+use std::fmt;
+
+use crate::error::PolicyError;
+use crate::observe::EvalObserver;
+use crate::policy::{Policy, Rule};
+use crate::types::{Decision, Effect, ReasonCode, Request};
+
+const MAGIC: &[u8; 4] = b"G0CT";
+const VERSION: u8 = 2;
+
+/// Upper bound on signature length, to bound memory on hostile input.
+const MAX_SIGNATURE_BYTES: usize = 512;
+
+/// Produces and checks signatures over token payloads.
+pub trait Signer {
+    /// Sign `payload`, e.g. HMAC-SHA256 with a shared key.
+    fn sign(&self, payload: &[u8]) -> Vec<u8>;
+
+    /// Check a signature. The default re-signs and compares in constant time.
+    fn verify(&self, payload: &[u8], signature: &[u8]) -> bool {
+        let expected = self.sign(payload);
+        expected.len() == signature.len()
+            && expected
+                .iter()
+                .zip(signature)
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+/// Errors verifying a capability token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityError {
+    /// The token is truncated, has a bad header, or has trailing bytes.
+    Malformed,
+    /// The signature does not match the payload.
+    BadSignature,
+    /// The token's expiry has passed.
+    Expired,
+    /// The token does not cover the presented request.
+    OutOfScope,
+}
+
+impl fmt::Display for CapabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapabilityError::Malformed => write!(f, "malformed capability token"),
+            CapabilityError::BadSignature => write!(f, "capability token signature mismatch"),
+            CapabilityError::Expired => write!(f, "capability token expired"),
+            CapabilityError::OutOfScope => {
+                write!(f, "capability token does not cover this request")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CapabilityError {}
+
+/// The verified contents of a capability token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+    /// Fingerprint of the policy that allowed the request.
+    pub fingerprint: u64,
+    /// Index of the allowing rule within that policy.
+    pub rule_index: u32,
+    /// Reason code of the allowing rule.
+    pub reason: ReasonCode,
+    /// The principal the token was issued to.
+    pub principal: String,
+    /// The allowed action.
+    pub action: String,
+    /// The allowed resource.
+    pub resource: String,
+    /// The environment the request named, if any.
+    pub environment: Option<String>,
+    /// Expiry, in Unix seconds (exclusive).
+    pub expires_at: i64,
+}
+
+impl Capability {
+    /// Returns `true` if the token covers `request` exactly.
+    pub fn covers(&self, request: &Request<'_>) -> bool {
+        self.principal == request.principal
+            && self.action == request.action
+            && self.resource == request.resource
+            && self.environment.as_deref() == request.environment
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.fingerprint.to_le_bytes());
+        out.extend_from_slice(&self.rule_index.to_le_bytes());
+        out.extend_from_slice(&self.reason.value().to_le_bytes());
+        put_str(&mut out, &self.principal);
+        put_str(&mut out, &self.action);
+        put_str(&mut out, &self.resource);
+        match &self.environment {
+            None => out.push(0),
+            Some(environment) => {
+                out.push(1);
+                put_str(&mut out, environment);
+            }
+        }
+        out.extend_from_slice(&self.expires_at.to_le_bytes());
+        out
+    }
+
+    /// Encode and sign this capability.
+    pub fn to_token(&self, signer: &dyn Signer) -> Vec<u8> {
+        let mut token = self.payload();
+        let sig = signer.sign(&token);
+        token.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+        token.extend_from_slice(&sig);
+        token
+    }
+
+    /// Decode a token and check its signature, without checking expiry or scope.
+    pub fn decode(token: &[u8], signer: &dyn Signer) -> Result<Self, CapabilityError> {
+        let mut r = Reader(token);
+        if r.take(4)? != MAGIC || r.take(1)? != [VERSION] {
+            return Err(CapabilityError::Malformed);
+        }
+        let capability = Capability {
+            fingerprint: u64::from_le_bytes(r.array()?),
+            rule_index: u32::from_le_bytes(r.array()?),
+            reason: ReasonCode(u32::from_le_bytes(r.array()?)),
+            principal: r.string()?,
+            action: r.string()?,
+            resource: r.string()?,
+            environment: match r.take(1)? {
+                [0] => None,
+                [1] => Some(r.string()?),
+                _ => return Err(CapabilityError::Malformed),
+            },
+            expires_at: i64::from_le_bytes(r.array()?),
+        };
+        let payload_len = token.len() - r.0.len();
+        let sig_len = u32::from_le_bytes(r.array()?) as usize;
+        if sig_len > MAX_SIGNATURE_BYTES {
+            return Err(CapabilityError::Malformed);
+        }
+        let sig = r.take(sig_len)?;
+        if !r.0.is_empty() {
+            return Err(CapabilityError::Malformed);
+        }
+        if !signer.verify(&token[..payload_len], sig) {
+            return Err(CapabilityError::BadSignature);
+        }
+        Ok(capability)
+    }
+}
+
+/// Evaluate `request` and, on Allow, mint a token valid for `ttl_seconds`.
+///
+/// If the allowing rule has a shorter `cache_ttl`, the token expires with
+/// it. Non-Allow decisions return no token, and so do break-glass decisions,
+/// which are never reused, and decisions carrying obligations, which the
+/// token has no room to pass on to the PEP.
+pub fn mint(
+    policy: &Policy<'_>,
+    request: &Request<'_>,
+    signer: &dyn Signer,
+    now: i64,
+    ttl_seconds: u32,
+) -> Result<(Decision, Option<Vec<u8>>), PolicyError> {
+    let mut observer = AllowingRule {
+        rules: policy.rules(),
+        first_allow: None,
+    };
+    let decision = policy.evaluate_observed(request, None, None, &mut observer)?;
+    if !decision.is_allow() || decision.break_glass || !decision.obligations.is_empty() {
+        return Ok((decision, None));
+    }
+    let Some(rule_index) = observer.first_allow else {
+        return Ok((decision, None));
+    };
+    let ttl = decision
+        .cache_ttl
+        .map_or(ttl_seconds, |t| t.min(ttl_seconds));
+    let capability = Capability {
+        fingerprint: policy.fingerprint(),
+        rule_index: rule_index as u32,
+        reason: decision.reason,
+        principal: request.principal.to_string(),
+        action: request.action.to_string(),
+        resource: request.resource.to_string(),
+        environment: request.environment.map(str::to_string),
+        expires_at: now.saturating_add(ttl as i64),
+    };
+    Ok((decision, Some(capability.to_token(signer))))
+}
+
+/// Verify a token against the request it is presented with.
+pub fn verify(
+    token: &[u8],
+    signer: &dyn Signer,
+    request: &Request<'_>,
+    now: i64,
+) -> Result<Capability, CapabilityError> {
+    let capability = Capability::decode(token, signer)?;
+    if now >= capability.expires_at {
+        return Err(CapabilityError::Expired);
+    }
+    if !capability.covers(request) {
+        return Err(CapabilityError::OutOfScope);
+    }
+    Ok(capability)
+}
+
+/// Records which rule an Allow decision came from: the first matching
+/// Allow rule that needs no approval, as `mint` never presents one.
+struct AllowingRule<'r, 'a> {
+    rules: &'r [Rule<'a>],
+    first_allow: Option<usize>,
+}
+
+impl EvalObserver for AllowingRule<'_, '_> {
+    fn rule_matched(&mut self, index: usize) {
+        let rule = &self.rules[index];
+        if self.first_allow.is_none() && rule.effect == Effect::Allow && rule.approval.is_none() {
+            self.first_allow = Some(index);
+        }
+    }
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+struct Reader<'t>(&'t [u8]);
+
+impl<'t> Reader<'t> {
+    fn take(&mut self, n: usize) -> Result<&'t [u8], CapabilityError> {
+        if self.0.len() < n {
+            return Err(CapabilityError::Malformed);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CapabilityError> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    fn string(&mut self) -> Result<String, CapabilityError> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| CapabilityError::Malformed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::Fnv64;
+    use crate::obligation::Obligation;
+    use crate::target::{Matcher, Target};
+    use crate::value::Value;
+
+    /// Keyed FNV "MAC". Not secure; stands in for HMAC in tests.
+    struct TestSigner(u64);
+
+    impl Signer for TestSigner {
+        fn sign(&self, payload: &[u8]) -> Vec<u8> {
+            let mut h = Fnv64::new();
+            h.write_u64(self.0);
+            h.write(payload);
+            h.finish().to_le_bytes().to_vec()
+        }
+    }
+
+    fn policy() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Exact("mallory"),
+                    action: Matcher::Any,
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(9),
+            ))
+            .rule(Rule::allow(Target::any(), ReasonCode(1)).with_cache_ttl(30))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mint_and_verify() {
+        let policy = policy();
+        let signer = TestSigner(42);
+        let request = Request::new("alice", "read", "doc-1");
+
+        let (decision, token) = mint(&policy, &request, &signer, 1000, 300).unwrap();
+        assert!(decision.is_allow());
+        let token = token.unwrap();
+
+        let cap = verify(&token, &signer, &request, 1010).unwrap();
+        assert_eq!(cap.rule_index, 1);
+        assert_eq!(cap.reason, ReasonCode(1));
+        assert_eq!(cap.fingerprint, policy.fingerprint());
+        // The rule's 30s cache TTL caps the requested 300s
+        assert_eq!(cap.expires_at, 1030);
+
+        assert_eq!(
+            verify(&token, &signer, &request, 1030),
+            Err(CapabilityError::Expired)
+        );
+        let other = Request::new("alice", "write", "doc-1");
+        assert_eq!(
+            verify(&token, &signer, &other, 1010),
+            Err(CapabilityError::OutOfScope)
+        );
+        // Tokens are scoped to the request's environment too
+        let staging = Request::new("alice", "read", "doc-1").in_environment("staging");
+        assert_eq!(
+            verify(&token, &signer, &staging, 1010),
+            Err(CapabilityError::OutOfScope)
+        );
+        let (_, token) = mint(&policy, &staging, &signer, 1000, 300).unwrap();
+        let cap = verify(&token.unwrap(), &signer, &staging, 1010).unwrap();
+        assert_eq!(cap.environment.as_deref(), Some("staging"));
+
+        // Denied requests get no token
+        let denied = Request::new("mallory", "read", "doc-1");
+        let (decision, token) = mint(&policy, &denied, &signer, 1000, 300).unwrap();
+        assert!(decision.is_deny());
+        assert!(token.is_none());
+    }
+
+    #[test]
+    fn test_only_plain_allows_minted() {
+        let signer = TestSigner(42);
+        let request = Request::new("alice", "read", "doc-1");
+
+        // The token names the rule that allowed, not a pending approval
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(2)).with_approval(7, 600))
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .build()
+            .unwrap();
+        let (_, token) = mint(&policy, &request, &signer, 0, 60).unwrap();
+        let cap = verify(&token.unwrap(), &signer, &request, 0).unwrap();
+        assert_eq!(cap.rule_index, 1);
+
+        let policy = Policy::builder()
+            .rule(
+                Rule::allow(Target::any(), ReasonCode(1))
+                    .with_obligations(&[Obligation::Custom(3)]),
+            )
+            .build()
+            .unwrap();
+        let (decision, token) = mint(&policy, &request, &signer, 0, 60).unwrap();
+        assert!(decision.is_allow());
+        assert!(token.is_none());
+
+        let policy = Policy::builder()
+            .rule(Rule::break_glass(Target::any(), "emergency", ReasonCode(5)))
+            .build()
+            .unwrap();
+        let ctx: &[(&str, Value)] = &[("emergency", Value::Bool(true))];
+        let request = Request::with_context("alice", "read", "doc-1", ctx);
+        let (decision, token) = mint(&policy, &request, &signer, 0, 60).unwrap();
+        assert!(decision.is_allow() && decision.break_glass);
+        assert!(token.is_none());
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let signer = TestSigner(42);
+        let request = Request::new("alice", "read", "doc-1");
+        let (_, token) = mint(&policy(), &request, &signer, 0, 60).unwrap();
+        let token = token.unwrap();
+
+        assert_eq!(
+            verify(&token, &TestSigner(7), &request, 0),
+            Err(CapabilityError::BadSignature)
+        );
+
+        // Flip a byte inside the resource name
+        let mut forged = token.clone();
+        let pos = forged.windows(5).position(|w| w == b"doc-1").unwrap();
+        forged[pos + 4] = b'2';
+        assert_eq!(
+            verify(&forged, &signer, &Request::new("alice", "read", "doc-2"), 0),
+            Err(CapabilityError::BadSignature)
+        );
+
+        assert_eq!(
+            Capability::decode(&token[..token.len() - 1], &signer),
+            Err(CapabilityError::Malformed)
+        );
+        let mut long = token.clone();
+        long.push(0);
+        assert_eq!(
+            Capability::decode(&long, &signer),
+            Err(CapabilityError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! IP network prefixes for `Condition::IpInCidr`.
+//!
+//! A `Cidr` is parsed and checked once, when the condition is built, and
+//! stored as a masked network address and prefix length, so matching an
+//! address at evaluation time is a few integer operations with no parsing
+//! or allocation.
+//!
+//! Address families never match each other: an IPv4 address is not in an
+//! IPv6 prefix, including the IPv4-mapped range `::ffff:0:0/96`.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::error::PolicyError;
+
+/// An IP network: an address prefix of a given length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    /// The network of `addr` with a `prefix_len`-bit prefix. Host bits of
+    /// `addr` are cleared.
+    ///
+    /// Fails with `InvalidCidr` if `prefix_len` exceeds 32 for IPv4 or 128
+    /// for IPv6.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, PolicyError> {
+        let network = match addr {
+            IpAddr::V4(v4) if prefix_len <= 32 => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & v4_mask(prefix_len)))
+            }
+            IpAddr::V6(v6) if prefix_len <= 128 => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & v6_mask(prefix_len)))
+            }
+            _ => return Err(PolicyError::InvalidCidr),
+        };
+        Ok(Cidr {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Parse `address/prefix_len`, e.g. `10.0.0.0/8` or `fd00::/8`.
+    ///
+    /// Fails with `InvalidCidr` if the text is not of that form or the
+    /// prefix is too long for the address family.
+    pub fn parse(text: &str) -> Result<Self, PolicyError> {
+        let (addr, len) = text.split_once('/').ok_or(PolicyError::InvalidCidr)?;
+        let addr: IpAddr = addr.parse().map_err(|_| PolicyError::InvalidCidr)?;
+        if len.is_empty() || len.len() > 3 || !len.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(PolicyError::InvalidCidr);
+        }
+        let len: u8 = len.parse().map_err(|_| PolicyError::InvalidCidr)?;
+        Cidr::new(addr, len)
+    }
+
+    /// The network address, with host bits cleared.
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    /// The prefix length in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns `true` if `addr` is in this network.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(net), IpAddr::V4(a)) => {
+                u32::from(a) & v4_mask(self.prefix_len) == u32::from(net)
+            }
+            (IpAddr::V6(net), IpAddr::V6(a)) => {
+                u128::from(a) & v6_mask(self.prefix_len) == u128::from(net)
+            }
+            _ => false,
+        }
+    }
+
+    /// The highest address in this network.
+    pub(crate) fn last(&self) -> IpAddr {
+        match self.network {
+            IpAddr::V4(net) => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(net) | !v4_mask(self.prefix_len)))
+            }
+            IpAddr::V6(net) => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(net) | !v6_mask(self.prefix_len)))
+            }
+        }
+    }
+}
+
+/// `address/prefix_len`, with host bits cleared.
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Serialized as its `Display` text, e.g. `"10.0.0.0/8"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cidr {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cidr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Cidr::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+fn v4_mask(prefix_len: u8) -> u32 {
+    u32::MAX
+        .checked_shl(32 - u32::from(prefix_len))
+        .unwrap_or(0)
+}
+
+fn v6_mask(prefix_len: u8) -> u128 {
+    u128::MAX
+        .checked_shl(128 - u32::from(prefix_len))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_contains() {
+        let private = Cidr::parse("10.0.0.0/8").unwrap();
+        assert!(private.contains(ip("10.1.2.3")));
+        assert!(private.contains(ip("10.255.255.255")));
+        assert!(!private.contains(ip("11.0.0.0")));
+        assert!(!private.contains(ip("::ffff:10.1.2.3")));
+        assert_eq!(private.last(), ip("10.255.255.255"));
+
+        // Host bits are cleared
+        let host = Cidr::parse("192.168.1.77/24").unwrap();
+        assert_eq!(host.to_string(), "192.168.1.0/24");
+        assert_eq!(host, Cidr::parse("192.168.1.0/24").unwrap());
+
+        let everything = Cidr::parse("0.0.0.0/0").unwrap();
+        assert!(everything.contains(ip("203.0.113.9")));
+        let single = Cidr::parse("203.0.113.9/32").unwrap();
+        assert!(single.contains(ip("203.0.113.9")));
+        assert!(!single.contains(ip("203.0.113.8")));
+
+        let ula = Cidr::parse("fd00::/8").unwrap();
+        assert!(ula.contains(ip("fd12:3456::1")));
+        assert!(!ula.contains(ip("fe80::1")));
+        assert!(!ula.contains(ip("10.0.0.1")));
+        assert!(Cidr::parse("::/0").unwrap().contains(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for text in [
+            "10.0.0.0",
+            "10.0.0.0/",
+            "10.0.0.0/33",
+            "10.0.0.0/+8",
+            "10.0.0/8",
+            "::/129",
+            "fd00::/0008",
+            "host/8",
+        ] {
+            assert_eq!(Cidr::parse(text), Err(PolicyError::InvalidCidr), "{}", text);
+        }
+        assert_eq!(Cidr::new(ip("10.0.0.0"), 40), Err(PolicyError::InvalidCidr));
+    }
+}
@@ -0,0 +1,142 @@
+//! Time sources.
+//!
+//! Time-dependent features read the current time from a `Clock` instead of
+//! the system directly, so tests can freeze time and policies stay
+//! deterministic. `Policy::evaluate_at` is the injection point for rule
+//! schedules and the `TimeOfDayBetween` and `DayOfWeekIn` conditions;
+//! without it, they read the time from the request context.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the current time.
+pub trait Clock {
+    /// The current time in Unix seconds.
+    fn now(&self) -> i64;
+}
+
+/// The system wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs().min(i64::MAX as u64) as i64,
+            Err(before) => -(before.duration().as_secs().min(i64::MAX as u64) as i64),
+        }
+    }
+}
+
+/// A clock that only moves when told to, for tests and simulations.
+///
+/// Shareable across threads; `set` and `advance` take `&self`.
+#[derive(Debug, Default)]
+pub struct FrozenClock {
+    now: AtomicI64,
+}
+
+impl FrozenClock {
+    /// A clock stopped at `unix_seconds`.
+    pub const fn new(unix_seconds: i64) -> Self {
+        FrozenClock {
+            now: AtomicI64::new(unix_seconds),
+        }
+    }
+
+    /// Move the clock to `unix_seconds`.
+    pub fn set(&self, unix_seconds: i64) {
+        self.now.store(unix_seconds, Ordering::Relaxed);
+    }
+
+    /// Move the clock forward by `seconds` (backward if negative).
+    pub fn advance(&self, seconds: i64) {
+        let _ = self
+            .now
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |now| {
+                Some(now.saturating_add(seconds))
+            });
+    }
+}
+
+impl Clock for FrozenClock {
+    fn now(&self) -> i64 {
+        self.now.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::{Policy, Rule};
+    use crate::schedule::{Days, Schedule, TimeWindow};
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode, Request};
+    use crate::value::Value;
+
+    // 2024-01-01 10:00:00 UTC, a Monday.
+    const MONDAY_10AM: i64 = 1_704_067_200 + 10 * 3600;
+    const BUSINESS_HOURS: &[TimeWindow] = &[TimeWindow::new(9, 0, 17, 0)];
+
+    #[test]
+    fn test_frozen_clock() {
+        let clock = FrozenClock::new(MONDAY_10AM);
+        clock.advance(90);
+        assert_eq!(clock.now(), MONDAY_10AM + 90);
+        clock.set(0);
+        clock.advance(i64::MIN);
+        assert_eq!(clock.now(), i64::MIN);
+        // 2020-01-01
+        assert!(SystemClock.now() > 1_577_836_800);
+    }
+
+    #[test]
+    fn test_evaluate_at() {
+        let policy = Policy::builder()
+            .rule(
+                Rule::allow(Target::any(), ReasonCode(1))
+                    .with_schedule(Schedule::new(Days::WEEKDAYS, BUSINESS_HOURS)),
+            )
+            .build()
+            .unwrap();
+        let clock = FrozenClock::new(MONDAY_10AM);
+        let request = Request::new("alice", "write", "doc");
+        assert!(policy.evaluate_at(&request, &clock).unwrap().is_allow());
+
+        // Saturday: the injected clock wins over a context timestamp
+        clock.advance(5 * 24 * 3600);
+        let ctx: &[(&str, Value)] = &[("now", Value::Int(MONDAY_10AM))];
+        let spoofed = Request::with_context("alice", "write", "doc", ctx);
+        assert!(policy.evaluate_at(&spoofed, &clock).unwrap().is_deny());
+        assert!(policy.evaluate(&spoofed).unwrap().is_allow());
+    }
+
+    #[test]
+    fn test_time_conditions_read_clock() {
+        let business_hours = Condition::TimeOfDayBetween {
+            window: BUSINESS_HOURS[0],
+            utc_offset_minutes: 0,
+        };
+        let weekdays = Condition::DayOfWeekIn {
+            days: Days::WEEKDAYS,
+            utc_offset_minutes: 0,
+        };
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::And(Box::new(weekdays), Box::new(business_hours))),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let clock = FrozenClock::new(MONDAY_10AM);
+        let request = Request::new("alice", "write", "doc");
+        assert!(policy.evaluate_at(&request, &clock).unwrap().is_allow());
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+
+        clock.advance(8 * 3600);
+        assert!(policy.evaluate_at(&request, &clock).unwrap().is_deny());
+    }
+}
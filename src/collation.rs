@@ -0,0 +1,118 @@
+//! String comparison rules.
+//!
+//! By default gate0 compares names and string values byte for byte, so
+//! `"Alice"` and `"alice"`, or an `"é"` typed precomposed and one typed as
+//! `e` plus a combining accent, are different principals. A
+//! `PolicyConfig::collation` other than `Binary` makes every target
+//! matcher and every string comparison in a condition agree on a looser
+//! notion of equality instead.
+//!
+//! Secrets, attribute names, group names, and custom operator names are
+//! always compared byte for byte.
+
+use std::borrow::Cow;
+
+use crate::value::Value;
+
+/// How strings are compared during evaluation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Collation {
+    /// Byte-for-byte equality.
+    #[default]
+    Binary,
+    /// Equal once ASCII letters are lowercased; other characters compare
+    /// byte for byte.
+    AsciiCaseInsensitive,
+    /// Equal once both strings are Unicode case-folded and NFC-normalized,
+    /// so case and canonically equivalent spellings do not matter.
+    ///
+    /// Requires the `unicode` feature. Comparing two strings that are not
+    /// both ASCII may allocate.
+    #[cfg(feature = "unicode")]
+    UnicodeCaseFold,
+}
+
+impl Collation {
+    /// Whether `a` and `b` are equal under this collation.
+    pub fn eq(self, a: &str, b: &str) -> bool {
+        match self {
+            Collation::Binary => a == b,
+            Collation::AsciiCaseInsensitive => a.eq_ignore_ascii_case(b),
+            #[cfg(feature = "unicode")]
+            Collation::UnicodeCaseFold => {
+                if a.is_ascii() && b.is_ascii() {
+                    return a.eq_ignore_ascii_case(b);
+                }
+                fold(a).eq(fold(b))
+            }
+        }
+    }
+
+    /// `s` in a canonical form: two strings are equal under this collation
+    /// exactly when their keys are byte-for-byte equal.
+    pub(crate) fn key(self, s: &str) -> Cow<'_, str> {
+        match self {
+            Collation::Binary => Cow::Borrowed(s),
+            Collation::AsciiCaseInsensitive if s.bytes().any(|b| b.is_ascii_uppercase()) => {
+                Cow::Owned(s.to_ascii_lowercase())
+            }
+            Collation::AsciiCaseInsensitive => Cow::Borrowed(s),
+            #[cfg(feature = "unicode")]
+            Collation::UnicodeCaseFold => Cow::Owned(fold(s).collect()),
+        }
+    }
+
+    /// Whether two values are equal, comparing strings under this collation.
+    pub(crate) fn values_eq(self, a: &Value<'_>, b: &Value<'_>) -> bool {
+        match (a, b) {
+            (Value::String(a), Value::String(b)) => self.eq(a, b),
+            _ => a == b,
+        }
+    }
+}
+
+/// NFC of the full case folding of `s`'s canonical decomposition, which
+/// makes caseless matching respect canonical equivalence.
+#[cfg(feature = "unicode")]
+fn fold(s: &str) -> impl Iterator<Item = char> + '_ {
+    use caseless::Caseless;
+    use unicode_normalization::UnicodeNormalization;
+
+    s.nfd().default_case_fold().nfc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_collations() {
+        assert!(Collation::Binary.eq("alice", "alice"));
+        assert!(!Collation::Binary.eq("Alice", "alice"));
+        assert!(Collation::AsciiCaseInsensitive.eq("Alice", "aLICE"));
+        assert!(!Collation::AsciiCaseInsensitive.eq("ÉMILE", "émile"));
+
+        let collation = Collation::AsciiCaseInsensitive;
+        assert!(collation.values_eq(&Value::String("DOC"), &Value::String("doc")));
+        assert!(!collation.values_eq(&Value::Secret(b"DOC"), &Value::Secret(b"doc")));
+        assert!(!collation.values_eq(&Value::String("1"), &Value::Int(1)));
+
+        assert_eq!(collation.key("ALICE"), collation.key("alice"));
+        assert_ne!(collation.key("ÉMILE"), collation.key("émile"));
+        assert_eq!(Collation::Binary.key("ALICE"), "ALICE");
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn test_unicode_case_fold() {
+        let collation = Collation::UnicodeCaseFold;
+        assert!(collation.eq("ÉMILE", "émile"));
+        // Precomposed and combining spellings of é
+        assert!(collation.eq("caf\u{e9}", "CAFE\u{301}"));
+        // Full case folding maps ß to ss
+        assert!(collation.eq("Straße", "STRASSE"));
+        assert!(!collation.eq("emile", "émile"));
+        assert_eq!(collation.key("caf\u{e9}"), collation.key("CAFE\u{301}"));
+        assert_eq!(collation.key("Straße"), collation.key("STRASSE"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Policy complexity statistics.
+//!
+//! A static summary of a policy's size and shape, for tracking growth over
+//! time and enforcing complexity budgets in CI. Unlike `EvaluationStats`,
+//! nothing here depends on a request.
+
+use crate::policy::Policy;
+use crate::target::Matcher;
+use crate::types::Effect;
+
+/// Number of entries kept in `PolicyStatistics::largest_rules`.
+pub const LARGEST_RULES: usize = 5;
+
+/// Usage counts for one kind of matcher, across every rule's target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatcherUsage {
+    /// `Matcher::Any` count.
+    pub any: usize,
+    /// `Matcher::Exact` count.
+    pub exact: usize,
+    /// `Matcher::OneOf` count.
+    pub one_of: usize,
+    /// Total options across all `Matcher::OneOf`s.
+    pub one_of_options: usize,
+}
+
+impl MatcherUsage {
+    fn record(&mut self, matcher: &Matcher<'_>) {
+        match matcher {
+            Matcher::Any => self.any += 1,
+            Matcher::Exact(_) => self.exact += 1,
+            Matcher::OneOf(options) => {
+                self.one_of += 1;
+                self.one_of_options += options.len();
+            }
+        }
+    }
+}
+
+/// Static complexity statistics for a policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyStatistics {
+    /// Total number of rules.
+    pub rules: usize,
+    /// Allow rules.
+    pub allow: usize,
+    /// Deny rules.
+    pub deny: usize,
+    /// Challenge rules.
+    pub challenge: usize,
+    /// Indeterminate rules.
+    pub indeterminate: usize,
+    /// Break-glass rules, also counted under their effect.
+    pub break_glass: usize,
+    /// Scheduled rules, also counted under their effect.
+    pub scheduled: usize,
+    /// Principal matcher usage.
+    pub principal: MatcherUsage,
+    /// Action matcher usage.
+    pub action: MatcherUsage,
+    /// Resource matcher usage.
+    pub resource: MatcherUsage,
+    /// Environment matcher usage.
+    pub environment: MatcherUsage,
+    /// `(depth, rules)` pairs, ascending by depth. Rules without a
+    /// condition count as depth 0.
+    pub depth_histogram: Vec<(usize, usize)>,
+    /// `(nodes, rules)` pairs, ascending by node count. Rules without a
+    /// condition count as 0 nodes.
+    pub node_histogram: Vec<(usize, usize)>,
+    /// Total condition nodes across all rules.
+    pub total_nodes: usize,
+    /// Up to `LARGEST_RULES` `(rule index, nodes)` pairs with the most
+    /// condition nodes, largest first; ties keep declared order.
+    pub largest_rules: Vec<(usize, usize)>,
+}
+
+impl PolicyStatistics {
+    /// The deepest condition in the policy, or 0 if there are none.
+    pub fn max_depth(&self) -> usize {
+        self.depth_histogram.last().map_or(0, |(depth, _)| *depth)
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Compute static complexity statistics for this policy.
+    pub fn statistics(&self) -> PolicyStatistics {
+        let mut stats = PolicyStatistics {
+            rules: self.rules().len(),
+            ..PolicyStatistics::default()
+        };
+        let mut sizes = Vec::with_capacity(self.rules().len());
+        for (index, rule) in self.rules().iter().enumerate() {
+            match rule.effect {
+                Effect::Allow => stats.allow += 1,
+                Effect::Deny => stats.deny += 1,
+                Effect::Challenge(_) => stats.challenge += 1,
+                Effect::Indeterminate => stats.indeterminate += 1,
+            }
+            stats.break_glass += rule.break_glass.is_some() as usize;
+            stats.scheduled += rule.schedule.is_some() as usize;
+            stats.principal.record(&rule.target.principal);
+            stats.action.record(&rule.target.action);
+            stats.resource.record(&rule.target.resource);
+            stats.environment.record(&rule.target.environment);
+
+            let (depth, nodes) = rule
+                .condition
+                .as_ref()
+                .map_or((0, 0), |c| (c.depth(), c.node_count()));
+            bump(&mut stats.depth_histogram, depth);
+            bump(&mut stats.node_histogram, nodes);
+            stats.total_nodes += nodes;
+            sizes.push((index, nodes));
+        }
+        // Stable sort keeps declared order among equal sizes.
+        sizes.sort_by_key(|&(_, nodes)| std::cmp::Reverse(nodes));
+        sizes.truncate(LARGEST_RULES);
+        stats.largest_rules = sizes;
+        stats
+    }
+}
+
+fn bump(histogram: &mut Vec<(usize, usize)>, key: usize) {
+    match histogram.binary_search_by_key(&key, |(k, _)| *k) {
+        Ok(i) => histogram[i].1 += 1,
+        Err(i) => histogram.insert(i, (key, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::ReasonCode;
+    use crate::value::Value;
+
+    #[test]
+    fn test_statistics() {
+        let role = || Condition::Equals {
+            attr: "role",
+            value: Value::String("admin"),
+        };
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::OneOf(&["read", "list"]),
+                    resource: Matcher::Exact("doc"),
+                    environment: Matcher::Any,
+                },
+                Some(Condition::And(Box::new(role()), Box::new(role()))),
+                ReasonCode(2),
+            ))
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(role()),
+                ReasonCode(3),
+            ))
+            .rule(Rule::break_glass(Target::any(), "emergency", ReasonCode(4)))
+            .build()
+            .unwrap();
+
+        let stats = policy.statistics();
+        assert_eq!((stats.rules, stats.allow, stats.deny), (4, 3, 1));
+        assert_eq!(stats.break_glass, 1);
+        assert_eq!(
+            stats.action,
+            MatcherUsage {
+                any: 3,
+                exact: 0,
+                one_of: 1,
+                one_of_options: 2,
+            }
+        );
+        assert_eq!(stats.resource.exact, 1);
+        assert_eq!(stats.depth_histogram, vec![(0, 2), (1, 1), (2, 1)]);
+        assert_eq!(stats.node_histogram, vec![(0, 2), (1, 1), (3, 1)]);
+        assert_eq!(stats.max_depth(), 2);
+        assert_eq!(stats.total_nodes, 4);
+        assert_eq!(stats.largest_rules, vec![(1, 3), (2, 1), (0, 0), (3, 0)]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Policy composition.
+//!
+//! Concatenating an organization's baseline rules with a team's rules
+//! mixes their configs and lets either side's rules interact in ways
+//! neither author reviewed. Composition instead evaluates both policies
+//! independently and combines the two decisions:
+//!
+//! - **Intersection** (`Policy::intersect`): allowed only if both policies
+//!   allow. Any Deny, including a default deny, denies; otherwise an
+//!   Indeterminate, then a Challenge, outranks Allow.
+//! - **Union** (`Policy::union`): allowed if either policy allows, but a
+//!   Deny rule matched in either still overrides. Ranks explicit Deny,
+//!   then Indeterminate, Challenge, and Allow, and reports a default deny
+//!   (`NO_MATCHING_RULE`) only if both policies fall through.
+//!
+//! Ties go to the left (`self`) policy's decision. When both decisions have
+//! the winning effect, the result carries the obligations of both, the
+//! break-glass flag of either, and the shorter cache TTL (none if either
+//! has none). If either evaluation fails, the composition fails, left first.
+
+use crate::error::PolicyError;
+use crate::groups::GroupProvider;
+use crate::policy::Policy;
+use crate::types::{Decision, Effect, Request, NO_MATCHING_RULE};
+
+/// How a `ComposedPolicy` combines its two decisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Composition {
+    /// Allow only if both policies allow.
+    Intersection,
+    /// Allow if either policy allows; explicit Deny still overrides.
+    Union,
+}
+
+/// Two policies evaluated independently, their decisions combined.
+#[derive(Debug, Clone)]
+pub struct ComposedPolicy<'a> {
+    left: Policy<'a>,
+    right: Policy<'a>,
+    composition: Composition,
+}
+
+impl<'a> Policy<'a> {
+    /// Combine with `other` so a request is allowed only if both allow.
+    pub fn intersect(self, other: Policy<'a>) -> ComposedPolicy<'a> {
+        ComposedPolicy::new(self, other, Composition::Intersection)
+    }
+
+    /// Combine with `other` so a request is allowed if either allows,
+    /// unless either matches a Deny rule.
+    pub fn union(self, other: Policy<'a>) -> ComposedPolicy<'a> {
+        ComposedPolicy::new(self, other, Composition::Union)
+    }
+}
+
+impl<'a> ComposedPolicy<'a> {
+    /// Combine `left` and `right`.
+    pub fn new(left: Policy<'a>, right: Policy<'a>, composition: Composition) -> Self {
+        ComposedPolicy {
+            left,
+            right,
+            composition,
+        }
+    }
+
+    /// Get the left (`self`) policy.
+    pub fn left(&self) -> &Policy<'a> {
+        &self.left
+    }
+
+    /// Get the right (`other`) policy.
+    pub fn right(&self) -> &Policy<'a> {
+        &self.right
+    }
+
+    /// Get how the decisions are combined.
+    pub fn composition(&self) -> Composition {
+        self.composition
+    }
+
+    /// Evaluate both policies and combine their decisions.
+    pub fn evaluate(&self, request: &Request<'_>) -> Result<Decision, PolicyError> {
+        let left = self.left.evaluate(request)?;
+        let right = self.right.evaluate(request)?;
+        Ok(self.combine(left, right))
+    }
+
+    /// Like `evaluate`, resolving `MemberOf` conditions through `groups`.
+    /// Each policy has its own lookup budget.
+    pub fn evaluate_with_groups(
+        &self,
+        request: &Request<'_>,
+        groups: &dyn GroupProvider,
+    ) -> Result<Decision, PolicyError> {
+        let left = self.left.evaluate_with_groups(request, groups)?;
+        let right = self.right.evaluate_with_groups(request, groups)?;
+        Ok(self.combine(left, right))
+    }
+
+    /// Combine two decisions under this composition.
+    pub fn combine(&self, left: Decision, right: Decision) -> Decision {
+        let rank = |d: &Decision| match (self.composition, d.effect) {
+            (Composition::Intersection, Effect::Deny) if d.reason == NO_MATCHING_RULE => 4,
+            (Composition::Union, Effect::Deny) if d.reason == NO_MATCHING_RULE => 0,
+            (_, Effect::Deny) => 5,
+            (_, Effect::Indeterminate) => 3,
+            (_, Effect::Challenge(_)) => 2,
+            (_, Effect::Allow) => 1,
+        };
+        let (mut winner, other) = if rank(&right) > rank(&left) {
+            (right, left)
+        } else {
+            (left, right)
+        };
+        if other.effect == winner.effect {
+            for obligation in other.obligations.iter() {
+                winner.obligations.push(obligation);
+            }
+            winner.break_glass |= other.break_glass;
+            winner.cache_ttl = match (winner.cache_ttl, other.cache_ttl) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                _ => None,
+            };
+        }
+        winner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obligation::Obligation;
+    use crate::policy::Rule;
+    use crate::target::{Matcher, Target};
+    use crate::types::{ChallengeMethod, ReasonCode};
+
+    const BASE_READ: ReasonCode = ReasonCode(1);
+    const BASE_BLOCK: ReasonCode = ReasonCode(2);
+    const TEAM_READ: ReasonCode = ReasonCode(3);
+    const TEAM_WRITE: ReasonCode = ReasonCode(4);
+
+    fn action(name: &'static str) -> Target<'static> {
+        Target {
+            principal: Matcher::Any,
+            action: Matcher::Exact(name),
+            resource: Matcher::Any,
+            environment: Matcher::Any,
+        }
+    }
+
+    /// Baseline: anyone reads, nobody deletes.
+    fn baseline() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(action("read"), BASE_READ))
+            .rule(Rule::deny(action("delete"), BASE_BLOCK))
+            .build()
+            .unwrap()
+    }
+
+    /// Team: reads and writes, and would allow deletes.
+    fn team() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(action("read"), TEAM_READ).with_cache_ttl(60))
+            .rule(Rule::allow(action("write"), TEAM_WRITE))
+            .rule(Rule::allow(action("delete"), TEAM_WRITE))
+            .build()
+            .unwrap()
+    }
+
+    fn decide(policy: &ComposedPolicy<'_>, action: &str) -> Decision {
+        policy
+            .evaluate(&Request::new("alice", action, "doc"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_intersection() {
+        let both = baseline().intersect(team());
+        assert_eq!(both.composition(), Composition::Intersection);
+
+        let read = decide(&both, "read");
+        assert!(read.is_allow());
+        assert_eq!(read.reason, BASE_READ);
+        // The baseline gave no TTL hint, so neither does the result
+        assert_eq!(read.cache_ttl, None);
+
+        // Only the team allows writes: the baseline's default deny wins
+        assert_eq!(decide(&both, "write"), Decision::deny(NO_MATCHING_RULE));
+        assert_eq!(decide(&both, "delete"), Decision::deny(BASE_BLOCK));
+        assert_eq!(decide(&both, "list"), Decision::deny(NO_MATCHING_RULE));
+    }
+
+    #[test]
+    fn test_union() {
+        let either = baseline().union(team());
+        assert_eq!(decide(&either, "read").reason, BASE_READ);
+        assert_eq!(decide(&either, "write"), Decision::allow(TEAM_WRITE));
+        // The baseline's Deny rule still overrides the team's Allow
+        assert_eq!(decide(&either, "delete"), Decision::deny(BASE_BLOCK));
+        assert_eq!(decide(&either, "list"), Decision::deny(NO_MATCHING_RULE));
+    }
+
+    #[test]
+    fn test_combine() {
+        let union = baseline().union(team());
+        let intersection = baseline().intersect(team());
+        let mfa = Decision::challenge(ChallengeMethod::Mfa, ReasonCode(7));
+        let allow = Decision::allow(ReasonCode(8));
+        let failed = Decision::indeterminate(ReasonCode(9));
+
+        assert_eq!(union.combine(allow, mfa), mfa);
+        assert_eq!(intersection.combine(allow, mfa), mfa);
+        assert_eq!(union.combine(failed, allow), failed);
+        assert_eq!(intersection.combine(mfa, failed), failed);
+        assert_eq!(
+            intersection.combine(failed, Decision::deny(NO_MATCHING_RULE)),
+            Decision::deny(NO_MATCHING_RULE)
+        );
+        assert_eq!(
+            union.combine(Decision::deny(NO_MATCHING_RULE), Decision::deny(BASE_BLOCK)),
+            Decision::deny(BASE_BLOCK)
+        );
+
+        // Same effect on both sides: obligations and TTLs merge
+        let left = Decision::allow(ReasonCode(1))
+            .with_cache_ttl(Some(60))
+            .with_obligation(Obligation::Audit);
+        let right = Decision::allow(ReasonCode(2))
+            .with_cache_ttl(Some(30))
+            .with_obligation(Obligation::Custom(5));
+        let merged = intersection.combine(left, right);
+        assert_eq!(merged.reason, ReasonCode(1));
+        assert_eq!(merged.cache_ttl, Some(30));
+        assert!(merged.obligations.contains(Obligation::Audit));
+        assert!(merged.obligations.contains(Obligation::Custom(5)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let strict = Policy::builder()
+            .config(crate::policy::PolicyConfig {
+                max_string_len: 4,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let composed = baseline().union(strict);
+        assert_eq!(
+            composed.evaluate(&Request::new("alice", "read", "doc")),
+            Err(PolicyError::StringTooLong { max: 4, actual: 5 })
+        );
+    }
+}
@@ -1,7 +1,14 @@
 //! Boolean condition evaluation.
 //!
-//! Minimal expression language: Equals, NotEquals, And, Or, Not.
-//! Depth is checked at construction time.
+//! Minimal expression language: Equals, NotEquals, EqualsIgnoreCase, set
+//! membership (In, NotIn), list membership (AttrContains), ordered Int
+//! comparisons (GreaterThan, GreaterOrEqual, LessThan, LessOrEqual,
+//! Between), Int bit flags (HasFlags), IpInCidr, Matches, GlobMatches,
+//! MemberOf, AttrIsPrincipal, Custom, the
+//! clock-based TimeOfDayBetween and DayOfWeekIn, And, Or, Not, Implies, Xor,
+//! the n-ary AllOf and AnyOf, Require for attributes that must be present,
+//! and Ref to a named condition.
+//! Depth and node count are checked at construction time.
 //! Evaluation is stack-based (non-recursive) to guarantee termination.
 //!
 //! # Zero-Allocation Guarantee
@@ -10,14 +17,63 @@
 //! Stack sizes are derived from the absolute maximum condition depth:
 //!
 //! - **Traversal stack**: At most `2*D + 2` items.
-//!   Proof: For each And/Or node, we push 1 operator + 2 child evals.
+//!   Proof: For each binary node, we push 1 operator + 2 child evals.
 //!   At depth D, worst case is a left-leaning chain: D operators + D right-child evals + 1 leaf = 2D+1.
 //!
 //! - **Results stack**: At most `D + 2` items.
 //!   Proof: Each operator consumes its children before parent is processed.
+//!
+//! `AllOf` and `AnyOf` evaluate one child at a time, keeping the running
+//! result in their own stack item, so each uses at most 2 traversal items
+//! and 1 result however many children it has; the bounds above still hold.
+//! Their width is bounded by `PolicyConfig::max_condition_nodes` instead.
+//!
+//! Short-circuiting only ever pushes fewer items than eager evaluation, so
+//! the same bounds hold in both modes.
+//!
+//! # Short-Circuiting
+//!
+//! `evaluate()` short-circuits: `And` skips its right operand when the left
+//! is false, `Or` when the left is true, and `Implies` when the left is
+//! false; `Xor` always needs both. `AllOf` stops at its first
+//! false child and `AnyOf` at its first true one. A skipped operand is never
+//! evaluated, so it cannot fail and makes no group lookups.
+//! `evaluate_eager()` always evaluates both operands, so the work done does
+//! not depend on the outcome of the left one; policies choose with
+//! `PolicyConfig::short_circuit`.
+//!
+//! # Type Mismatches
+//!
+//! Comparing an attribute with a literal of another type evaluates like a
+//! comparison that does not hold: `Equals` is false, `NotEquals` true.
+//! Under `PolicyConfig::strict_types` it fails with
+//! `PolicyError::TypeMismatch` instead. Missing attributes are never a
+//! mismatch.
+//!
+//! # Three-Valued Logic
+//!
+//! `evaluate_three_valued()` and `PolicyConfig::three_valued_logic` treat a
+//! leaf whose attribute is missing as unknown (`None`) instead of false, or
+//! true for `NotEquals` and `NotIn`. The time conditions are unknown when
+//! they read the clock attribute and it is missing. Connectives follow
+//! Kleene's strong logic: `And` is false if either operand is false and
+//! `Or` true if either is true; otherwise an unknown operand makes the
+//! result unknown, as it does for `Not` and `Xor`. `AllOf` and `AnyOf` fold
+//! like chains of `And` and `Or`. Short-circuiting skips an operand only
+//! once the result is known. Attributes present with another type keep
+//! their usual result, and unknown nodes are not reported to observers.
 
+use std::task::Poll;
+
+use crate::cidr::Cidr;
+use crate::collation::Collation;
+use crate::custom::{CustomOpCalls, MAX_CUSTOM_OP_ARGS};
 use crate::error::PolicyError;
 use crate::fixed_stack::FixedStack;
+use crate::groups::GroupLookup;
+use crate::observe::{EvalObserver, NoopObserver};
+use crate::pattern::{glob_matches, validate_glob, Pattern};
+use crate::schedule::{local_time, valid_utc_offset, Days, TimeWindow, DEFAULT_CLOCK_ATTR};
 use crate::value::Value;
 
 /// Hard compile-time cap on condition depth.
@@ -25,12 +81,48 @@ use crate::value::Value;
 /// This enables const-generic stack sizing for zero-allocation evaluation.
 pub const ABSOLUTE_MAX_CONDITION_DEPTH: usize = 16;
 
+/// Hard cap on dotted attribute path depth.
+/// PolicyConfig::max_path_depth must be <= this value, which also bounds
+/// the nesting of `Value::Map`s that decoders accept.
+pub const ABSOLUTE_MAX_PATH_DEPTH: usize = 16;
+
 /// Traversal stack size: 2*D + 2 (proven O(depth) bound).
 const TRAVERSAL_STACK_SIZE: usize = 2 * ABSOLUTE_MAX_CONDITION_DEPTH + 2;
 
 /// Results stack size: D + 2 (proven O(depth) bound).
 const VALUE_STACK_SIZE: usize = ABSOLUTE_MAX_CONDITION_DEPTH + 2;
 
+/// How `Condition::evaluate_observed` evaluates.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EvalMode {
+    /// Skip operands that cannot change the result.
+    pub short_circuit: bool,
+    /// Fail with `TypeMismatch` instead of comparing values of different
+    /// types as unequal.
+    pub strict_types: bool,
+    /// How strings compare.
+    pub collation: Collation,
+    /// The injected clock's time in Unix seconds; `None` reads the
+    /// `DEFAULT_CLOCK_ATTR` context attribute instead.
+    pub now: Option<i64>,
+    /// Evaluate leaves over missing attributes to unknown, with Kleene
+    /// connectives (see module docs).
+    pub three_valued: bool,
+}
+
+impl Default for EvalMode {
+    /// `Condition::evaluate`'s mode: short-circuiting, lenient types.
+    fn default() -> Self {
+        EvalMode {
+            short_circuit: true,
+            strict_types: false,
+            collation: Collation::Binary,
+            now: None,
+            three_valued: false,
+        }
+    }
+}
+
 /// A boolean condition that can be evaluated against request context.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Condition<'a> {
@@ -52,15 +144,259 @@ pub enum Condition<'a> {
         /// The value to compare against.
         value: Value<'a>,
     },
+    /// True if the attribute is a String equal to the value, ignoring case.
+    ///
+    /// For names that arrive with inconsistent casing, such as emails from
+    /// different identity providers. Characters are compared by their
+    /// Unicode lowercase forms, so `"Alice@Example.COM"` matches
+    /// `"alice@example.com"`. False if the attribute is missing or not a
+    /// String.
+    EqualsIgnoreCase {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The string to compare against.
+        value: &'a str,
+    },
+    /// True if the attribute equals any of the values.
+    ///
+    /// One node instead of a chain of `Or`ed `Equals`, so large sets do not
+    /// count against the depth limit; policies bound their size with
+    /// `PolicyConfig::max_set_values`. False if the attribute is missing.
+    In {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The values to compare against.
+        values: &'a [Value<'a>],
+    },
+    /// True if the attribute equals none of the values, or is missing.
+    NotIn {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The values to compare against.
+        values: &'a [Value<'a>],
+    },
+    /// True if the attribute is an Int greater than the value.
+    ///
+    /// Like the other ordered comparisons, false if the attribute is
+    /// missing or not an Int (fail-closed).
+    GreaterThan {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// True if the attribute is an Int greater than or equal to the value.
+    GreaterOrEqual {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// True if the attribute is an Int less than the value.
+    LessThan {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// True if the attribute is an Int less than or equal to the value.
+    LessOrEqual {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// True if the attribute is an Int from `min` to `max`, both included.
+    ///
+    /// One node instead of an `And` of two comparisons. Validation rejects
+    /// `min > max` with `PolicyError::InvalidRange`.
+    Between {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The lower bound.
+        min: i64,
+        /// The upper bound.
+        max: i64,
+    },
+    /// True if the attribute is an `Ip` in the network.
+    ///
+    /// The network is parsed when the condition is built (see
+    /// `Cidr::parse`), so evaluation only masks and compares. False if the
+    /// attribute is missing, not an `Ip`, or of the other address family.
+    IpInCidr {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The network to match.
+        cidr: Cidr,
+    },
+    /// True if the attribute is a String matching the pattern.
+    ///
+    /// The pattern is compiled when the condition is built (see
+    /// `Pattern::parse`) and matched in time linear in the string's length.
+    /// Under a case-insensitive `PolicyConfig::collation`, ASCII letters
+    /// match either case. False if the attribute is missing or not a
+    /// String.
+    Matches {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The pattern to match.
+        pattern: Pattern,
+    },
+    /// True if the request's principal is a member of the group.
+    ///
+    /// Resolved through the `GroupProvider` passed to
+    /// `Policy::evaluate_with_groups`; without one, evaluation fails with
+    /// `PolicyError::GroupLookupFailed`.
+    MemberOf(&'a str),
+    /// True if the named attribute is a string equal to the request's principal.
+    ///
+    /// The building block for "owners can act on their own resources". An
+    /// empty principal owns nothing, so this is false when evaluated
+    /// without a request (e.g. `Condition::evaluate`).
+    AttrIsPrincipal(&'a str),
+    /// True if the current local time of day falls in `window`.
+    ///
+    /// The time comes from the clock passed to `Policy::evaluate_at`, or
+    /// else from the `DEFAULT_CLOCK_ATTR` context attribute as Unix seconds;
+    /// without either it is false. Unlike a rule `Schedule`, it can be
+    /// combined with other conditions, e.g. "business hours or on call".
+    TimeOfDayBetween {
+        /// The accepted times of day, possibly wrapping past midnight.
+        window: TimeWindow,
+        /// Offset of local time from UTC, in minutes.
+        utc_offset_minutes: i32,
+    },
+    /// True if the current local day of the week is one of `days`.
+    ///
+    /// Reads the time like `TimeOfDayBetween`.
+    DayOfWeekIn {
+        /// The accepted days.
+        days: Days,
+        /// Offset of local time from UTC, in minutes.
+        utc_offset_minutes: i32,
+    },
+    /// Decided by the application-defined operator `op`, given the values
+    /// of the `args` attributes.
+    ///
+    /// The operator must be registered with `PolicyBuilder::custom_op`;
+    /// without a policy (e.g. `Condition::evaluate`) evaluation fails with
+    /// `PolicyError::CustomOpFailed`.
+    Custom {
+        /// The registered operator's name.
+        op: &'a str,
+        /// Context attributes passed to the operator, at most
+        /// `MAX_CUSTOM_OP_ARGS`.
+        args: &'a [&'a str],
+    },
+    /// The condition defined under this name with
+    /// `PolicyBuilder::define_condition`.
+    ///
+    /// Replaced by its definition when the policy is built, so built
+    /// policies never contain one. Evaluating an unresolved reference fails
+    /// with `PolicyError::InvalidConditionRef`.
+    Ref(&'a str),
     /// True if both conditions are true.
     And(Box<Condition<'a>>, Box<Condition<'a>>),
     /// True if either condition is true.
     Or(Box<Condition<'a>>, Box<Condition<'a>>),
     /// True if the inner condition is false.
     Not(Box<Condition<'a>>),
+    /// True if the first condition is false or the second is true: "if
+    /// `a` then `b` must hold".
+    Implies(Box<Condition<'a>>, Box<Condition<'a>>),
+    /// True if exactly one of the conditions is true.
+    Xor(Box<Condition<'a>>, Box<Condition<'a>>),
+    /// True if every condition is true; true when empty.
+    ///
+    /// Adds one level of depth however many conditions it holds, so long
+    /// conjunctions need not nest `And`s.
+    AllOf(&'a [Condition<'a>]),
+    /// True if any condition is true; false when empty.
+    AnyOf(&'a [Condition<'a>]),
+    /// True if the attribute is a `Value::List` holding the value, e.g.
+    /// `groups` holding `"admins"`. False if the attribute is missing or
+    /// not a List (fail-closed).
+    AttrContains {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The value to look for; never a List.
+        value: Value<'a>,
+    },
+    /// True if the attribute is an Int with every bit of `mask` set, i.e.
+    /// `attr & mask == mask`. False if the attribute is missing or not an
+    /// Int (fail-closed). A zero mask matches any Int.
+    HasFlags {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The bits that must all be set.
+        mask: i64,
+    },
+    /// True if the whole attribute is a String matching the glob, where
+    /// `*` matches any run of characters and `?` any one character.
+    ///
+    /// At most `MAX_GLOB_WILDCARDS` wildcards are allowed, checked when the
+    /// policy is built. Under a case-insensitive `PolicyConfig::collation`,
+    /// ASCII letters match either case. False if the attribute is missing
+    /// or not a String.
+    GlobMatches {
+        /// The attribute name to look up in context.
+        attr: &'a str,
+        /// The glob to match, e.g. `proj-*-prod`.
+        pattern: &'a str,
+    },
+    /// The inner condition, but evaluation fails with
+    /// `PolicyError::RequiredAttributeMissing` if the attribute is missing
+    /// from the context.
+    ///
+    /// Leaves treat a missing attribute as a non-match, which is right for
+    /// most checks; wrap the ones reading identity-critical attributes so
+    /// their absence surfaces as an error instead. The check applies
+    /// whenever the wrapper is evaluated, so a `Require` skipped by
+    /// short-circuiting does not fail, and it fails under three-valued
+    /// logic too.
+    Require(&'a str, Box<Condition<'a>>),
 }
 
 impl<'a> Condition<'a> {
+    /// Returns the name of this node's variant, e.g. `"And"` or `"Equals"`.
+    ///
+    /// Used by coverage and introspection tooling.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Condition::True => "True",
+            Condition::False => "False",
+            Condition::Equals { .. } => "Equals",
+            Condition::NotEquals { .. } => "NotEquals",
+            Condition::EqualsIgnoreCase { .. } => "EqualsIgnoreCase",
+            Condition::In { .. } => "In",
+            Condition::NotIn { .. } => "NotIn",
+            Condition::AttrContains { .. } => "AttrContains",
+            Condition::GreaterThan { .. } => "GreaterThan",
+            Condition::GreaterOrEqual { .. } => "GreaterOrEqual",
+            Condition::LessThan { .. } => "LessThan",
+            Condition::LessOrEqual { .. } => "LessOrEqual",
+            Condition::Between { .. } => "Between",
+            Condition::IpInCidr { .. } => "IpInCidr",
+            Condition::Matches { .. } => "Matches",
+            Condition::MemberOf(_) => "MemberOf",
+            Condition::AttrIsPrincipal(_) => "AttrIsPrincipal",
+            Condition::TimeOfDayBetween { .. } => "TimeOfDayBetween",
+            Condition::DayOfWeekIn { .. } => "DayOfWeekIn",
+            Condition::Custom { .. } => "Custom",
+            Condition::Ref(_) => "Ref",
+            Condition::And(..) => "And",
+            Condition::Or(..) => "Or",
+            Condition::Not(..) => "Not",
+            Condition::Implies(..) => "Implies",
+            Condition::Xor(..) => "Xor",
+            Condition::AllOf(_) => "AllOf",
+            Condition::AnyOf(_) => "AnyOf",
+            Condition::HasFlags { .. } => "HasFlags",
+            Condition::GlobMatches { .. } => "GlobMatches",
+            Condition::Require(..) => "Require",
+        }
+    }
+
     /// Compute the depth of this condition tree.
     ///
     /// Used to enforce bounded complexity at construction time.
@@ -69,6 +405,7 @@ impl<'a> Condition<'a> {
     pub fn depth(&self) -> usize {
         enum DepthItem<'a, 'b> {
             Visit(&'b Condition<'a>),
+            /// Combine the depths of this many children.
             Computed(usize),
         }
 
@@ -82,28 +419,53 @@ impl<'a> Condition<'a> {
                     Condition::True
                     | Condition::False
                     | Condition::Equals { .. }
-                    | Condition::NotEquals { .. } => {
+                    | Condition::NotEquals { .. }
+                    | Condition::EqualsIgnoreCase { .. }
+                    | Condition::In { .. }
+                    | Condition::NotIn { .. }
+                    | Condition::AttrContains { .. }
+                    | Condition::HasFlags { .. }
+                    | Condition::GlobMatches { .. }
+                    | Condition::GreaterThan { .. }
+                    | Condition::GreaterOrEqual { .. }
+                    | Condition::LessThan { .. }
+                    | Condition::LessOrEqual { .. }
+                    | Condition::Between { .. }
+                    | Condition::IpInCidr { .. }
+                    | Condition::Matches { .. }
+                    | Condition::MemberOf(_)
+                    | Condition::AttrIsPrincipal(_)
+                    | Condition::TimeOfDayBetween { .. }
+                    | Condition::DayOfWeekIn { .. }
+                    | Condition::Custom { .. }
+                    | Condition::Ref(_) => {
                         results.push(1);
                     }
-                    Condition::Not(inner) => {
+                    Condition::Not(inner) | Condition::Require(_, inner) => {
                         stack.push(DepthItem::Computed(1));
                         stack.push(DepthItem::Visit(inner));
                     }
-                    Condition::And(a, b) | Condition::Or(a, b) => {
+                    Condition::And(a, b)
+                    | Condition::Or(a, b)
+                    | Condition::Implies(a, b)
+                    | Condition::Xor(a, b) => {
                         stack.push(DepthItem::Computed(2));
                         stack.push(DepthItem::Visit(b));
                         stack.push(DepthItem::Visit(a));
                     }
+                    Condition::AllOf(children) | Condition::AnyOf(children) => {
+                        stack.push(DepthItem::Computed(children.len()));
+                        for child in children.iter().rev() {
+                            stack.push(DepthItem::Visit(child));
+                        }
+                    }
                 },
                 DepthItem::Computed(count) => {
-                    if count == 1 {
-                        let d: usize = results.pop().unwrap_or(0);
-                        results.push(d.saturating_add(1));
-                    } else {
-                        let d2: usize = results.pop().unwrap_or(0);
-                        let d1: usize = results.pop().unwrap_or(0);
-                        results.push((d1.max(d2)).saturating_add(1));
+                    let mut d: usize = 0;
+                    for _ in 0..count {
+                        d = d.max(results.pop().unwrap_or(0));
                     }
+                    results.push(d.saturating_add(1));
                 }
             }
         }
@@ -111,8 +473,140 @@ impl<'a> Condition<'a> {
         results.pop().unwrap_or(0)
     }
 
-    /// Validate that this condition does not exceed the maximum depth
-    /// and that all strings are within length limits.
+    /// For an ordered comparison, its attribute, operator symbol (`>`,
+    /// `>=`, `<`, or `<=`), and bound.
+    pub(crate) fn comparison(&self) -> Option<(&'a str, &'static str, i64)> {
+        match *self {
+            Condition::GreaterThan { attr, value } => Some((attr, ">", value)),
+            Condition::GreaterOrEqual { attr, value } => Some((attr, ">=", value)),
+            Condition::LessThan { attr, value } => Some((attr, "<", value)),
+            Condition::LessOrEqual { attr, value } => Some((attr, "<=", value)),
+            _ => None,
+        }
+    }
+
+    /// The most `.`-separated segments in any attribute path this
+    /// condition reads, or 0 if it reads none.
+    ///
+    /// Non-recursive, like `depth()`.
+    pub fn max_path_depth(&self) -> usize {
+        let mut stack = vec![self];
+        let mut max = 0;
+        while let Some(cond) = stack.pop() {
+            match cond {
+                Condition::Custom { args, .. } => {
+                    for arg in args.iter() {
+                        max = max.max(path_depth(arg));
+                    }
+                }
+                Condition::Require(attr, inner) => {
+                    max = max.max(path_depth(attr));
+                    stack.push(inner);
+                }
+                Condition::Not(inner) => stack.push(inner),
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter().rev());
+                }
+                leaf => max = max.max(leaf_attr(leaf).map_or(0, path_depth)),
+            }
+        }
+        max
+    }
+
+    /// The number of values in the largest `In` or `NotIn` set, or 0.
+    ///
+    /// Non-recursive, like `depth()`.
+    pub fn max_set_len(&self) -> usize {
+        let mut stack = vec![self];
+        let mut max = 0;
+        while let Some(cond) = stack.pop() {
+            match cond {
+                Condition::In { values, .. } | Condition::NotIn { values, .. } => {
+                    max = max.max(values.len());
+                }
+                Condition::Not(inner) | Condition::Require(_, inner) => stack.push(inner),
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter().rev());
+                }
+                _ => {}
+            }
+        }
+        max
+    }
+
+    /// Count the nodes in this condition tree.
+    ///
+    /// Non-recursive, like `depth()`.
+    pub fn node_count(&self) -> usize {
+        let mut stack = vec![self];
+        let mut count = 0usize;
+        while let Some(cond) = stack.pop() {
+            count = count.saturating_add(1);
+            match cond {
+                Condition::Not(inner) | Condition::Require(_, inner) => stack.push(inner),
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter().rev());
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+
+    /// Every node, children before parents, left to right.
+    ///
+    /// Lets passes that rebuild a tree bottom-up (see `simplify`) work with
+    /// an explicit results stack instead of recursion.
+    pub(crate) fn postorder(&self) -> Vec<&Condition<'a>> {
+        let mut stack = vec![self];
+        let mut nodes = Vec::new();
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            match node {
+                Condition::Not(inner) | Condition::Require(_, inner) => stack.push(inner),
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push(a);
+                    stack.push(b);
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter())
+                }
+                _ => {}
+            }
+        }
+        // Parents before children, right to left; reversed it is postorder.
+        nodes.reverse();
+        nodes
+    }
+
+    /// Validate that this condition does not exceed the maximum depth,
+    /// that all strings are within length limits, that every `Between`
+    /// range is non-empty, that time windows and UTC offsets are in range,
+    /// that no literal is a list, and that no `Ref` is left unresolved.
     ///
     /// This implementation is non-recursive.
     pub fn validate(&self, max_depth: usize, max_string_len: usize) -> Result<(), PolicyError> {
@@ -130,19 +624,95 @@ impl<'a> Condition<'a> {
         while let Some(cond) = stack.pop() {
             match cond {
                 Condition::True | Condition::False => {}
-                Condition::Equals { attr, value } | Condition::NotEquals { attr, value } => {
+                Condition::Equals { attr, value }
+                | Condition::NotEquals { attr, value }
+                | Condition::AttrContains { attr, value } => {
+                    validate_str(attr, max_string_len)?;
+                    validate_literal(value, max_string_len)?;
+                }
+                Condition::EqualsIgnoreCase { attr, value } => {
                     validate_str(attr, max_string_len)?;
-                    if let Value::String(s) = value {
-                        validate_str(s, max_string_len)?;
+                    validate_str(value, max_string_len)?;
+                }
+                Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+                    validate_str(attr, max_string_len)?;
+                    for value in values.iter() {
+                        validate_literal(value, max_string_len)?;
                     }
                 }
+                Condition::GreaterThan { attr, .. }
+                | Condition::GreaterOrEqual { attr, .. }
+                | Condition::LessThan { attr, .. }
+                | Condition::LessOrEqual { attr, .. }
+                | Condition::HasFlags { attr, .. }
+                | Condition::IpInCidr { attr, .. }
+                | Condition::MemberOf(attr)
+                | Condition::AttrIsPrincipal(attr) => validate_str(attr, max_string_len)?,
+                Condition::Matches { attr, pattern } => {
+                    validate_str(attr, max_string_len)?;
+                    validate_str(pattern.as_str(), max_string_len)?;
+                }
+                Condition::GlobMatches { attr, pattern } => {
+                    validate_str(attr, max_string_len)?;
+                    validate_str(pattern, max_string_len)?;
+                    validate_glob(pattern)?;
+                }
+                Condition::Between { attr, min, max } => {
+                    validate_str(attr, max_string_len)?;
+                    if min > max {
+                        return Err(PolicyError::InvalidRange {
+                            attr: attr.to_string(),
+                            min: *min,
+                            max: *max,
+                        });
+                    }
+                }
+                Condition::TimeOfDayBetween {
+                    window,
+                    utc_offset_minutes,
+                } => {
+                    if !window.is_valid() || !valid_utc_offset(*utc_offset_minutes) {
+                        return Err(PolicyError::InvalidSchedule);
+                    }
+                }
+                Condition::DayOfWeekIn {
+                    utc_offset_minutes, ..
+                } => {
+                    if !valid_utc_offset(*utc_offset_minutes) {
+                        return Err(PolicyError::InvalidSchedule);
+                    }
+                }
+                Condition::Custom { op, args } => {
+                    if args.len() > MAX_CUSTOM_OP_ARGS {
+                        return Err(PolicyError::InvalidCustomOp);
+                    }
+                    validate_str(op, max_string_len)?;
+                    for arg in args.iter() {
+                        validate_str(arg, max_string_len)?;
+                    }
+                }
+                Condition::Ref(name) => {
+                    return Err(PolicyError::InvalidConditionRef {
+                        name: name.to_string(),
+                    });
+                }
                 Condition::Not(inner) => {
                     stack.push(inner);
                 }
-                Condition::And(a, b) | Condition::Or(a, b) => {
+                Condition::Require(attr, inner) => {
+                    validate_str(attr, max_string_len)?;
+                    stack.push(inner);
+                }
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
                     stack.push(b);
                     stack.push(a);
                 }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter().rev());
+                }
             }
         }
         Ok(())
@@ -157,78 +727,639 @@ impl<'a> Condition<'a> {
     /// Returns `Err` if a required attribute is missing or has wrong type.
     ///
     /// Note: Missing attributes return `Ok(false)` for Equals and `Ok(true)` for NotEquals.
-    /// This is a deliberate design choice for fail-closed semantics.
+    /// This is a deliberate design choice for fail-closed semantics. `In` and
+    /// `NotIn` follow the same rule, ordered comparisons are `Ok(false)`
+    /// for a missing or non-Int attribute, `IpInCidr` for a missing or
+    /// non-Ip one, and `Matches` for a missing or non-String one.
+    ///
+    /// There is no principal, group provider, or operator registry here, so
+    /// `MemberOf` and `Custom` fail and `AttrIsPrincipal` is false.
+    ///
+    /// `And`, `Or`, and `Implies` short-circuit (see module docs).
     pub fn evaluate(&self, context: &[(&str, Value<'_>)]) -> Result<bool, PolicyError> {
-        // Stack-based evaluation with ZERO HEAP ALLOCATIONS.
-        // Stack items represent either a condition to evaluate or an operator to apply.
-        #[derive(Clone, Copy)]
-        enum StackItem<'a, 'b> {
-            Eval(&'b Condition<'a>),
-            ApplyNot,
-            ApplyAnd,
-            ApplyOr,
+        self.evaluate_observed(
+            context,
+            &mut GroupLookup::none(),
+            &mut CustomOpCalls::none(),
+            EvalMode::default(),
+            &mut NoopObserver,
+        )
+    }
+
+    /// Like `evaluate()`, but comparing strings under `collation`.
+    #[cfg(test)]
+    pub(crate) fn evaluate_with_collation(
+        &self,
+        context: &[(&str, Value<'_>)],
+        collation: Collation,
+    ) -> Result<bool, PolicyError> {
+        let mode = EvalMode {
+            collation,
+            ..EvalMode::default()
+        };
+        self.evaluate_observed(
+            context,
+            &mut GroupLookup::none(),
+            &mut CustomOpCalls::none(),
+            mode,
+            &mut NoopObserver,
+        )
+    }
+
+    /// Like `evaluate()`, but always evaluates both operands of every
+    /// connective.
+    pub fn evaluate_eager(&self, context: &[(&str, Value<'_>)]) -> Result<bool, PolicyError> {
+        let mode = EvalMode {
+            short_circuit: false,
+            ..EvalMode::default()
+        };
+        self.evaluate_observed(
+            context,
+            &mut GroupLookup::none(),
+            &mut CustomOpCalls::none(),
+            mode,
+            &mut NoopObserver,
+        )
+    }
+
+    /// Evaluate this condition under three-valued logic: `Ok(None)` if the
+    /// result depends on a missing attribute (see module docs).
+    ///
+    /// Otherwise the same as `evaluate()`, e.g. `Or` of a missing
+    /// attribute and a true leaf is `Ok(Some(true))`.
+    pub fn evaluate_three_valued(
+        &self,
+        context: &[(&str, Value<'_>)],
+    ) -> Result<Option<bool>, PolicyError> {
+        let mode = EvalMode {
+            three_valued: true,
+            ..EvalMode::default()
+        };
+        let mut unlimited = usize::MAX;
+        match self.evaluate_budgeted(
+            context,
+            &mut GroupLookup::none(),
+            &mut CustomOpCalls::none(),
+            mode,
+            &mut NoopObserver,
+            &mut unlimited,
+        )? {
+            Poll::Ready(result) => Ok(result),
+            // An unlimited budget always runs to completion.
+            Poll::Pending => Err(PolicyError::InternalError),
         }
+    }
 
-        // Fixed-size stacks with proven O(depth) bounds.
-        let mut stack: FixedStack<StackItem<'a, '_>, TRAVERSAL_STACK_SIZE> = FixedStack::new();
-        let mut results: FixedStack<bool, VALUE_STACK_SIZE> = FixedStack::new();
+    /// Evaluate this condition, reporting every node result to `observer`.
+    ///
+    /// Identical semantics to `evaluate()`, or `evaluate_eager()` if
+    /// `mode.short_circuit` is false; the observer sees each leaf when it is
+    /// computed and each connective when its result is known. Skipped
+    /// operands are not reported. `mode` must not be three-valued.
+    pub(crate) fn evaluate_observed<O: EvalObserver>(
+        &self,
+        context: &[(&str, Value<'_>)],
+        groups: &mut GroupLookup<'_, 'a>,
+        ops: &mut CustomOpCalls<'_, '_>,
+        mode: EvalMode,
+        observer: &mut O,
+    ) -> Result<bool, PolicyError> {
+        let mut unlimited = usize::MAX;
+        match self.evaluate_budgeted(context, groups, ops, mode, observer, &mut unlimited)? {
+            Poll::Ready(Some(result)) => Ok(result),
+            // An unlimited budget always runs to completion, and only
+            // three-valued evaluation is ever unknown.
+            _ => Err(PolicyError::InternalError),
+        }
+    }
 
-        stack.push(StackItem::Eval(self))?;
+    /// `evaluate_observed`, taking at most `budget` evaluation steps and
+    /// deducting those taken from it. Returns `Poll::Pending` if it ran out
+    /// first, and `None` for an unknown three-valued result.
+    pub(crate) fn evaluate_budgeted<O: EvalObserver>(
+        &self,
+        context: &[(&str, Value<'_>)],
+        groups: &mut GroupLookup<'_, 'a>,
+        ops: &mut CustomOpCalls<'_, '_>,
+        mode: EvalMode,
+        observer: &mut O,
+        budget: &mut usize,
+    ) -> Result<Poll<Option<bool>>, PolicyError> {
+        let mut state = EvalState::new(self);
+        let result = state.step(context, groups, ops, mode, observer, *budget);
+        // `step` never takes more steps than it was given.
+        *budget -= state.steps as usize;
+        result
+    }
+}
 
-        while let Some(item) = stack.pop() {
+/// An item on the evaluation stack: a condition to evaluate or an
+/// operator to apply.
+#[derive(Clone, Copy)]
+enum StackItem<'a, 'b> {
+    Eval(&'b Condition<'a>),
+    ApplyNot(&'b Condition<'a>),
+    ApplyRequire(&'b Condition<'a>),
+    ApplyAnd(&'b Condition<'a>),
+    ApplyOr(&'b Condition<'a>),
+    ApplyImplies(&'b Condition<'a>),
+    ApplyXor(&'b Condition<'a>),
+    /// The left operand is on the results stack; decide whether
+    /// the right one is needed.
+    ThenRight(&'b Condition<'a>),
+    /// The result of an `AllOf`/`AnyOf` child is on the results
+    /// stack: fold it into the result so far, then evaluate the
+    /// child at the index, if still needed.
+    NextChild(&'b Condition<'a>, usize, Option<bool>),
+}
+
+/// A condition evaluation in progress.
+///
+/// `Condition::evaluate_observed` runs one to completion; `EvalCursor`
+/// and `PolicyCursor` run one a few steps at a time.
+pub(crate) struct EvalState<'c, 'a> {
+    // Fixed-size stacks with proven O(depth) bounds.
+    stack: FixedStack<StackItem<'a, 'c>, TRAVERSAL_STACK_SIZE>,
+    /// Node results; `None` is unknown under three-valued logic.
+    results: FixedStack<Option<bool>, VALUE_STACK_SIZE>,
+    /// Stack items processed so far.
+    steps: u64,
+    /// Set once evaluation has finished.
+    outcome: Option<Result<Option<bool>, PolicyError>>,
+}
+
+impl<'c, 'a> EvalState<'c, 'a> {
+    pub(crate) fn new(condition: &'c Condition<'a>) -> Self {
+        let mut stack = FixedStack::new();
+        // An empty stack cannot overflow.
+        let _ = stack.push(StackItem::Eval(condition));
+        EvalState {
+            stack,
+            results: FixedStack::new(),
+            steps: 0,
+            outcome: None,
+        }
+    }
+
+    /// Stack items processed so far.
+    pub(crate) fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Continue evaluating for at most `max_steps` stack items.
+    ///
+    /// Every call must pass the same context, lookups, and mode. Once
+    /// finished, the result is returned again without further work.
+    pub(crate) fn run<O: EvalObserver>(
+        &mut self,
+        context: &[(&str, Value<'_>)],
+        groups: &mut GroupLookup<'_, 'a>,
+        ops: &mut CustomOpCalls<'_, '_>,
+        mode: EvalMode,
+        observer: &mut O,
+        max_steps: usize,
+    ) -> Poll<Result<Option<bool>, PolicyError>> {
+        if let Some(outcome) = &self.outcome {
+            return Poll::Ready(outcome.clone());
+        }
+        let outcome = match self.step(context, groups, ops, mode, observer, max_steps) {
+            Ok(Poll::Ready(result)) => Ok(result),
+            Ok(Poll::Pending) => return Poll::Pending,
+            Err(e) => Err(e),
+        };
+        self.outcome = Some(outcome.clone());
+        Poll::Ready(outcome)
+    }
+
+    /// The evaluation loop. Returns `Poll::Pending` if the budget ran out
+    /// first.
+    fn step<O: EvalObserver>(
+        &mut self,
+        context: &[(&str, Value<'_>)],
+        groups: &mut GroupLookup<'_, 'a>,
+        ops: &mut CustomOpCalls<'_, '_>,
+        mode: EvalMode,
+        observer: &mut O,
+        max_steps: usize,
+    ) -> Result<Poll<Option<bool>>, PolicyError> {
+        let short_circuit = mode.short_circuit;
+        let collation = mode.collation;
+        // Under strict types, the type an attribute must have if present.
+        let check = |attr: &str, expected: &'static str| -> Result<(), PolicyError> {
+            match lookup_attr(context, attr) {
+                Some(found) if mode.strict_types && found.type_name() != expected => {
+                    Err(PolicyError::TypeMismatch {
+                        attr: attr.to_string(),
+                        expected,
+                        actual: found.type_name(),
+                    })
+                }
+                _ => Ok(()),
+            }
+        };
+        // Stack-based evaluation with ZERO HEAP ALLOCATIONS.
+        // Stack items represent either a condition to evaluate or an operator to apply.
+        let EvalState {
+            stack,
+            results,
+            steps,
+            ..
+        } = self;
+        let mut budget = max_steps;
+
+        while !stack.is_empty() {
+            if budget == 0 {
+                return Ok(Poll::Pending);
+            }
+            budget -= 1;
+            *steps += 1;
+            let item = stack.pop().ok_or(PolicyError::InternalError)?;
             match item {
+                StackItem::Eval(_) if observer.expired() => {
+                    return Err(PolicyError::DeadlineExceeded);
+                }
+                StackItem::Eval(cond)
+                    if mode.three_valued && reads_missing(cond, context, mode.now) =>
+                {
+                    results.push(None)?;
+                }
                 StackItem::Eval(cond) => match cond {
-                    Condition::True => results.push(true)?,
-                    Condition::False => results.push(false)?,
+                    Condition::True | Condition::False => {
+                        let result = matches!(cond, Condition::True);
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
                     Condition::Equals { attr, value } => {
+                        check(attr, value.type_name())?;
                         let result = lookup_attr(context, attr)
-                            .map(|v| v == value)
+                            .map(|v| collation.values_eq(&v, value))
                             .unwrap_or(false); // Missing attr = false (fail-closed)
-                        results.push(result)?;
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
                     }
                     Condition::NotEquals { attr, value } => {
+                        check(attr, value.type_name())?;
                         let result = lookup_attr(context, attr)
-                            .map(|v| v != value)
+                            .map(|v| !collation.values_eq(&v, value))
                             .unwrap_or(true); // Missing attr = true for NotEquals
-                        results.push(result)?;
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::EqualsIgnoreCase { attr, value } => {
+                        check(attr, "String")?;
+                        let result = match lookup_attr(context, attr) {
+                            Some(Value::String(s)) => {
+                                eq_ignore_case(s, value) || collation.eq(s, value)
+                            }
+                            _ => false, // Missing or non-String attr = false (fail-closed)
+                        };
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::In { attr, values } => {
+                        check_set(check, attr, values)?;
+                        let result = lookup_attr(context, attr)
+                            .map(|v| values.iter().any(|x| collation.values_eq(x, &v)))
+                            .unwrap_or(false); // Missing attr = false (fail-closed)
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::NotIn { attr, values } => {
+                        check_set(check, attr, values)?;
+                        let result = lookup_attr(context, attr)
+                            .map(|v| !values.iter().any(|x| collation.values_eq(x, &v)))
+                            .unwrap_or(true); // Missing attr = true for NotIn
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::AttrContains { attr, value } => {
+                        check(attr, "List")?;
+                        let result = match lookup_attr(context, attr) {
+                            Some(Value::List(list)) => {
+                                list.iter().any(|v| collation.values_eq(&v, value))
+                            }
+                            _ => false, // Missing or non-List attr = false (fail-closed)
+                        };
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::GreaterThan { attr, value } => {
+                        check(attr, "Int")?;
+                        let result = int_attr(context, attr).is_some_and(|v| v > *value);
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::GreaterOrEqual { attr, value } => {
+                        check(attr, "Int")?;
+                        let result = int_attr(context, attr).is_some_and(|v| v >= *value);
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::LessThan { attr, value } => {
+                        check(attr, "Int")?;
+                        let result = int_attr(context, attr).is_some_and(|v| v < *value);
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::LessOrEqual { attr, value } => {
+                        check(attr, "Int")?;
+                        let result = int_attr(context, attr).is_some_and(|v| v <= *value);
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::Between { attr, min, max } => {
+                        check(attr, "Int")?;
+                        let result =
+                            int_attr(context, attr).is_some_and(|v| (*min..=*max).contains(&v));
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::HasFlags { attr, mask } => {
+                        check(attr, "Int")?;
+                        let result = int_attr(context, attr).is_some_and(|v| v & mask == *mask);
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::IpInCidr { attr, cidr } => {
+                        check(attr, "Ip")?;
+                        let result = match lookup_attr(context, attr) {
+                            Some(Value::Ip(ip)) => cidr.contains(ip),
+                            _ => false, // Missing or non-Ip attr = false (fail-closed)
+                        };
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::Matches { attr, pattern } => {
+                        check(attr, "String")?;
+                        let result = match lookup_attr(context, attr) {
+                            Some(Value::String(s)) => {
+                                pattern.matches(s, collation != Collation::Binary)
+                            }
+                            _ => false, // Missing or non-String attr = false (fail-closed)
+                        };
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::GlobMatches { attr, pattern } => {
+                        check(attr, "String")?;
+                        let result = match lookup_attr(context, attr) {
+                            Some(Value::String(s)) => {
+                                glob_matches(pattern, s, collation != Collation::Binary)
+                            }
+                            _ => false, // Missing or non-String attr = false (fail-closed)
+                        };
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::MemberOf(group) => {
+                        let result = groups.is_member(group)?;
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::AttrIsPrincipal(attr) => {
+                        check(attr, "String")?;
+                        let principal = groups.principal();
+                        let result = !principal.is_empty()
+                            && matches!(
+                                lookup_attr(context, attr),
+                                Some(Value::String(s)) if collation.eq(s, principal)
+                            );
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::TimeOfDayBetween {
+                        window,
+                        utc_offset_minutes,
+                    } => {
+                        let now = current_time(context, mode.now, check)?;
+                        let result = now
+                            .is_some_and(|t| window.contains(local_time(t, *utc_offset_minutes).1));
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::DayOfWeekIn {
+                        days,
+                        utc_offset_minutes,
+                    } => {
+                        let now = current_time(context, mode.now, check)?;
+                        let result = now.is_some_and(|t| {
+                            days.contains_index(local_time(t, *utc_offset_minutes).0)
+                        });
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::Custom { op, args } => {
+                        let result = ops.call(op, args, context)?;
+                        observer.node_evaluated(cond, result);
+                        results.push(Some(result))?;
+                    }
+                    Condition::Ref(name) => {
+                        return Err(PolicyError::InvalidConditionRef {
+                            name: name.to_string(),
+                        });
                     }
                     Condition::Not(inner) => {
-                        stack.push(StackItem::ApplyNot)?;
+                        stack.push(StackItem::ApplyNot(cond))?;
                         stack.push(StackItem::Eval(inner))?;
                     }
+                    Condition::Require(attr, inner) => {
+                        if lookup_attr(context, attr).is_none() {
+                            return Err(PolicyError::RequiredAttributeMissing {
+                                attr: attr.to_string(),
+                            });
+                        }
+                        stack.push(StackItem::ApplyRequire(cond))?;
+                        stack.push(StackItem::Eval(inner))?;
+                    }
+                    Condition::And(a, _) | Condition::Or(a, _) | Condition::Implies(a, _)
+                        if short_circuit =>
+                    {
+                        stack.push(StackItem::ThenRight(cond))?;
+                        stack.push(StackItem::Eval(a))?;
+                    }
                     Condition::And(a, b) => {
-                        stack.push(StackItem::ApplyAnd)?;
+                        stack.push(StackItem::ApplyAnd(cond))?;
                         stack.push(StackItem::Eval(b))?;
                         stack.push(StackItem::Eval(a))?;
                     }
                     Condition::Or(a, b) => {
-                        stack.push(StackItem::ApplyOr)?;
+                        stack.push(StackItem::ApplyOr(cond))?;
                         stack.push(StackItem::Eval(b))?;
                         stack.push(StackItem::Eval(a))?;
                     }
+                    Condition::Implies(a, b) => {
+                        stack.push(StackItem::ApplyImplies(cond))?;
+                        stack.push(StackItem::Eval(b))?;
+                        stack.push(StackItem::Eval(a))?;
+                    }
+                    Condition::Xor(a, b) => {
+                        stack.push(StackItem::ApplyXor(cond))?;
+                        stack.push(StackItem::Eval(b))?;
+                        stack.push(StackItem::Eval(a))?;
+                    }
+                    Condition::AllOf(children) | Condition::AnyOf(children) => {
+                        let all = matches!(cond, Condition::AllOf(_));
+                        match children.first() {
+                            None => {
+                                observer.node_evaluated(cond, all);
+                                results.push(Some(all))?;
+                            }
+                            Some(first) => {
+                                stack.push(StackItem::NextChild(cond, 1, Some(all)))?;
+                                stack.push(StackItem::Eval(first))?;
+                            }
+                        }
+                    }
                 },
-                StackItem::ApplyNot => {
+                StackItem::ThenRight(node) => {
+                    let left = results.pop().ok_or(PolicyError::InternalError)?;
+                    let (apply, right, decider, result) = match node {
+                        Condition::And(_, b) => (StackItem::ApplyAnd(node), b, false, false),
+                        Condition::Or(_, b) => (StackItem::ApplyOr(node), b, true, true),
+                        Condition::Implies(_, b) => (StackItem::ApplyImplies(node), b, false, true),
+                        _ => return Err(PolicyError::InternalError),
+                    };
+                    if left == Some(decider) {
+                        // The left operand alone decides the result.
+                        observer.node_evaluated(node, result);
+                        results.push(Some(result))?;
+                    } else {
+                        results.push(left)?;
+                        stack.push(apply)?;
+                        stack.push(StackItem::Eval(right))?;
+                    }
+                }
+                StackItem::NextChild(node, next, so_far) => {
+                    let child = results.pop().ok_or(PolicyError::InternalError)?;
+                    let (children, result, decided) = match node {
+                        Condition::AllOf(children) => {
+                            (children, kleene_and(so_far, child), child == Some(false))
+                        }
+                        Condition::AnyOf(children) => {
+                            (children, kleene_or(so_far, child), child == Some(true))
+                        }
+                        _ => return Err(PolicyError::InternalError),
+                    };
+                    match children.get(next) {
+                        Some(child) if !(short_circuit && decided) => {
+                            stack.push(StackItem::NextChild(node, next + 1, result))?;
+                            stack.push(StackItem::Eval(child))?;
+                        }
+                        _ => {
+                            report(observer, node, result);
+                            results.push(result)?;
+                        }
+                    }
+                }
+                StackItem::ApplyNot(node) => {
                     let val = results.pop().ok_or(PolicyError::InternalError)?;
-                    results.push(!val)?;
+                    let result = val.map(|v| !v);
+                    report(observer, node, result);
+                    results.push(result)?;
+                }
+                StackItem::ApplyRequire(node) => {
+                    let result = results.pop().ok_or(PolicyError::InternalError)?;
+                    report(observer, node, result);
+                    results.push(result)?;
+                }
+                StackItem::ApplyAnd(node) => {
+                    let b = results.pop().ok_or(PolicyError::InternalError)?;
+                    let a = results.pop().ok_or(PolicyError::InternalError)?;
+                    let result = kleene_and(a, b);
+                    report(observer, node, result);
+                    results.push(result)?;
+                }
+                StackItem::ApplyOr(node) => {
+                    let b = results.pop().ok_or(PolicyError::InternalError)?;
+                    let a = results.pop().ok_or(PolicyError::InternalError)?;
+                    let result = kleene_or(a, b);
+                    report(observer, node, result);
+                    results.push(result)?;
                 }
-                StackItem::ApplyAnd => {
+                StackItem::ApplyImplies(node) => {
                     let b = results.pop().ok_or(PolicyError::InternalError)?;
                     let a = results.pop().ok_or(PolicyError::InternalError)?;
-                    results.push(a && b)?;
+                    let result = kleene_or(a.map(|a| !a), b);
+                    report(observer, node, result);
+                    results.push(result)?;
                 }
-                StackItem::ApplyOr => {
+                StackItem::ApplyXor(node) => {
                     let b = results.pop().ok_or(PolicyError::InternalError)?;
                     let a = results.pop().ok_or(PolicyError::InternalError)?;
-                    results.push(a || b)?;
+                    let result = a.zip(b).map(|(a, b)| a != b);
+                    report(observer, node, result);
+                    results.push(result)?;
                 }
             }
         }
 
         // Final result should be the only item on the stack
-        results.pop().ok_or(PolicyError::InternalError)
+        results
+            .pop()
+            .ok_or(PolicyError::InternalError)
+            .map(Poll::Ready)
+    }
+}
+
+/// Kleene conjunction: false if either operand is, else unknown if either is.
+fn kleene_and(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
     }
 }
 
+/// Kleene disjunction: true if either operand is, else unknown if either is.
+fn kleene_or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    kleene_and(a.map(|a| !a), b.map(|b| !b)).map(|r| !r)
+}
+
+/// Report a connective's result, unless it is unknown.
+fn report<O: EvalObserver>(observer: &mut O, node: &Condition<'_>, result: Option<bool>) {
+    if let Some(result) = result {
+        observer.node_evaluated(node, result);
+    }
+}
+
+/// Whether `cond` is a leaf reading an attribute missing from `context`.
+/// Time conditions read the clock attribute unless a time is injected.
+fn reads_missing(cond: &Condition<'_>, context: &[(&str, Value<'_>)], now: Option<i64>) -> bool {
+    let attr = match cond {
+        Condition::TimeOfDayBetween { .. } | Condition::DayOfWeekIn { .. } if now.is_none() => {
+            DEFAULT_CLOCK_ATTR
+        }
+        _ => match leaf_attr(cond) {
+            Some(attr) => attr,
+            None => return false,
+        },
+    };
+    lookup_attr(context, attr).is_none()
+}
+
+/// The attribute a single-attribute leaf reads, if `cond` is one.
+fn leaf_attr<'a>(cond: &Condition<'a>) -> Option<&'a str> {
+    Some(match cond {
+        Condition::Equals { attr, .. }
+        | Condition::NotEquals { attr, .. }
+        | Condition::EqualsIgnoreCase { attr, .. }
+        | Condition::In { attr, .. }
+        | Condition::NotIn { attr, .. }
+        | Condition::AttrContains { attr, .. }
+        | Condition::GreaterThan { attr, .. }
+        | Condition::GreaterOrEqual { attr, .. }
+        | Condition::LessThan { attr, .. }
+        | Condition::LessOrEqual { attr, .. }
+        | Condition::Between { attr, .. }
+        | Condition::HasFlags { attr, .. }
+        | Condition::IpInCidr { attr, .. }
+        | Condition::Matches { attr, .. }
+        | Condition::GlobMatches { attr, .. }
+        | Condition::AttrIsPrincipal(attr) => attr,
+        _ => return None,
+    })
+}
+
 /// Manual Drop implementation to prevent stack overflows on deep trees.
 impl<'a> Drop for Condition<'a> {
     fn drop(&mut self) {
@@ -236,11 +1367,14 @@ impl<'a> Drop for Condition<'a> {
         let mut stack = Vec::new();
 
         match self {
-            Condition::And(a, b) | Condition::Or(a, b) => {
+            Condition::And(a, b)
+            | Condition::Or(a, b)
+            | Condition::Implies(a, b)
+            | Condition::Xor(a, b) => {
                 stack.push(std::mem::replace(a, Box::new(Condition::True)));
                 stack.push(std::mem::replace(b, Box::new(Condition::True)));
             }
-            Condition::Not(inner) => {
+            Condition::Not(inner) | Condition::Require(_, inner) => {
                 stack.push(std::mem::replace(inner, Box::new(Condition::True)));
             }
             _ => return,
@@ -248,11 +1382,14 @@ impl<'a> Drop for Condition<'a> {
 
         while let Some(mut boxed_cond) = stack.pop() {
             match *boxed_cond {
-                Condition::And(ref mut a, ref mut b) | Condition::Or(ref mut a, ref mut b) => {
+                Condition::And(ref mut a, ref mut b)
+                | Condition::Or(ref mut a, ref mut b)
+                | Condition::Implies(ref mut a, ref mut b)
+                | Condition::Xor(ref mut a, ref mut b) => {
                     stack.push(std::mem::replace(a, Box::new(Condition::True)));
                     stack.push(std::mem::replace(b, Box::new(Condition::True)));
                 }
-                Condition::Not(ref mut inner) => {
+                Condition::Not(ref mut inner) | Condition::Require(_, ref mut inner) => {
                     stack.push(std::mem::replace(inner, Box::new(Condition::True)));
                 }
                 _ => {}
@@ -261,9 +1398,81 @@ impl<'a> Drop for Condition<'a> {
     }
 }
 
-/// Look up an attribute in the context by name.
-fn lookup_attr<'a, 'b>(context: &'b [(&'b str, Value<'a>)], name: &str) -> Option<&'b Value<'a>> {
-    context.iter().find(|(k, _)| *k == name).map(|(_, v)| v)
+/// Look up an attribute in the context by name or dotted path.
+///
+/// An attribute whose whole name matches wins, so flat keys that contain
+/// dots keep working. Otherwise the first segment names a context
+/// attribute and each later one an entry of the `Value::Map` before it.
+pub(crate) fn lookup_attr<'a>(context: &[(&str, Value<'a>)], name: &str) -> Option<Value<'a>> {
+    if let Some((_, value)) = context.iter().find(|(k, _)| *k == name) {
+        return Some(value.clone());
+    }
+    let (root, path) = name.split_once('.')?;
+    let (_, value) = context.iter().find(|(k, _)| *k == root)?;
+    let mut value = value.clone();
+    for segment in path.split('.') {
+        value = value.as_map()?.get(segment)?;
+    }
+    Some(value)
+}
+
+/// The number of `.`-separated segments in an attribute path.
+fn path_depth(attr: &str) -> usize {
+    attr.split('.').count()
+}
+
+/// Under strict types, fail unless the attribute, if present, has the type
+/// of some value in a non-empty set.
+fn check_set(
+    check: impl Fn(&str, &'static str) -> Result<(), PolicyError>,
+    attr: &str,
+    values: &[Value<'_>],
+) -> Result<(), PolicyError> {
+    match values.iter().find(|v| check(attr, v.type_name()).is_ok()) {
+        Some(_) => Ok(()),
+        None => values
+            .first()
+            .map_or(Ok(()), |v| check(attr, v.type_name())),
+    }
+}
+
+/// Look up an Int attribute; `None` if missing or of another type.
+fn int_attr(context: &[(&str, Value<'_>)], name: &str) -> Option<i64> {
+    lookup_attr(context, name)?.as_int()
+}
+
+/// The injected time if there is one, else the context clock attribute.
+fn current_time(
+    context: &[(&str, Value<'_>)],
+    injected: Option<i64>,
+    check: impl Fn(&str, &'static str) -> Result<(), PolicyError>,
+) -> Result<Option<i64>, PolicyError> {
+    if injected.is_some() {
+        return Ok(injected);
+    }
+    check(DEFAULT_CLOCK_ATTR, "Int")?;
+    Ok(int_attr(context, DEFAULT_CLOCK_ATTR))
+}
+
+/// Whether `a` and `b` are equal once every character is lowercased.
+///
+/// Compares character by character, so it allocates nothing.
+pub(crate) fn eq_ignore_case(a: &str, b: &str) -> bool {
+    if a.is_ascii() && b.is_ascii() {
+        return a.eq_ignore_ascii_case(b);
+    }
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Validate a literal's length, and that it is not a list or map.
+fn validate_literal(value: &Value<'_>, max_len: usize) -> Result<(), PolicyError> {
+    match value {
+        Value::List(_) => Err(PolicyError::InvalidList),
+        Value::Map(_) => Err(PolicyError::InvalidMap),
+        _ => value.validate_len(max_len),
+    }
 }
 
 /// Validate that a string does not exceed the maximum allowed length.
@@ -281,6 +1490,7 @@ fn validate_str(s: &str, max_len: usize) -> Result<(), PolicyError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::value::ValueBuf;
 
     #[test]
     fn test_condition_true() {
@@ -331,6 +1541,424 @@ mod tests {
         assert_eq!(c.evaluate(&[]), Ok(true));
     }
 
+    #[test]
+    fn test_condition_equals_ignore_case() {
+        let c = Condition::EqualsIgnoreCase {
+            attr: "email",
+            value: "alice@example.com",
+        };
+        for email in ["alice@example.com", "Alice@Example.COM"] {
+            let ctx: &[(&str, Value)] = &[("email", Value::String(email))];
+            assert_eq!(c.evaluate(ctx), Ok(true));
+        }
+        let ctx: &[(&str, Value)] = &[("email", Value::String("alice@example.org"))];
+        assert_eq!(c.evaluate(ctx), Ok(false));
+        // Missing or non-String attribute = false
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        let ctx: &[(&str, Value)] = &[("email", Value::Int(1))];
+        assert_eq!(c.evaluate(ctx), Ok(false));
+
+        let c = Condition::EqualsIgnoreCase {
+            attr: "name",
+            value: "jürgen",
+        };
+        let ctx: &[(&str, Value)] = &[("name", Value::String("JÜRGEN"))];
+        assert_eq!(c.evaluate(ctx), Ok(true));
+        let ctx: &[(&str, Value)] = &[("name", Value::String("JURGEN"))];
+        assert_eq!(c.evaluate(ctx), Ok(false));
+    }
+
+    #[test]
+    fn test_condition_in() {
+        let departments = [
+            Value::String("eng"),
+            Value::String("ops"),
+            Value::String("sec"),
+        ];
+        let within = Condition::In {
+            attr: "dept",
+            values: &departments,
+        };
+        let outside = Condition::NotIn {
+            attr: "dept",
+            values: &departments,
+        };
+        assert_eq!(within.depth(), 1);
+        assert_eq!(within.max_set_len(), 3);
+
+        let ops = [("dept", Value::String("ops"))];
+        let sales = [("dept", Value::String("sales"))];
+        assert_eq!(within.evaluate(&ops), Ok(true));
+        assert_eq!(within.evaluate(&sales), Ok(false));
+        assert_eq!(outside.evaluate(&ops), Ok(false));
+        assert_eq!(outside.evaluate(&sales), Ok(true));
+
+        // Missing attribute: In is false, NotIn is true
+        assert_eq!(within.evaluate(&[]), Ok(false));
+        assert_eq!(outside.evaluate(&[]), Ok(true));
+    }
+
+    #[test]
+    fn test_condition_attr_contains() {
+        let c = Condition::AttrContains {
+            attr: "groups",
+            value: Value::String("admins"),
+        };
+        let groups = [Value::String("staff"), Value::String("admins")];
+        let member = [("groups", Value::list(&groups))];
+        let other = [("groups", Value::list(&groups[..1]))];
+        let scalar = [("groups", Value::String("admins"))];
+        assert_eq!(c.evaluate(&member), Ok(true));
+        assert_eq!(c.evaluate(&other), Ok(false));
+        // Missing or non-List attribute: false
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        assert_eq!(c.evaluate(&scalar), Ok(false));
+
+        // Owned lists evaluate the same way
+        let owned = ValueBuf::List(vec![ValueBuf::String("ADMINS".to_string())]);
+        let ctx = [("groups", owned.as_value())];
+        assert_eq!(c.evaluate(&ctx), Ok(false));
+        assert_eq!(
+            c.evaluate_with_collation(&ctx, Collation::AsciiCaseInsensitive),
+            Ok(true)
+        );
+
+        assert_eq!(c.validate(8, 256), Ok(()));
+        let nested = Condition::AttrContains {
+            attr: "groups",
+            value: Value::list(&groups),
+        };
+        assert_eq!(nested.validate(8, 256), Err(PolicyError::InvalidList));
+    }
+
+    #[test]
+    fn test_condition_dotted_path() {
+        let c = Condition::Equals {
+            attr: "device.posture.compliant",
+            value: Value::Bool(true),
+        };
+        assert_eq!(c.max_path_depth(), 3);
+        let posture = [("compliant", Value::Bool(true))];
+        let device = [
+            ("os", Value::String("linux")),
+            ("posture", Value::map(&posture)),
+        ];
+        let ctx = [("device", Value::map(&device))];
+        assert_eq!(c.evaluate(&ctx), Ok(true));
+        // Missing segments and non-Map parents: false
+        assert_eq!(
+            c.evaluate(&[("device", Value::map(&device[..1]))]),
+            Ok(false)
+        );
+        assert_eq!(c.evaluate(&[("device", Value::String("linux"))]), Ok(false));
+        assert_eq!(c.evaluate(&[]), Ok(false));
+
+        // A flat attribute named with dots wins over the path
+        let flat = [
+            ("device.posture.compliant", Value::Bool(true)),
+            ("device", Value::map(&[])),
+        ];
+        assert_eq!(c.evaluate(&flat), Ok(true));
+
+        // Owned maps resolve the same way
+        let owned = ValueBuf::Map(vec![(
+            "posture".to_string(),
+            ValueBuf::Map(vec![("compliant".to_string(), ValueBuf::Bool(true))]),
+        )]);
+        assert_eq!(c.evaluate(&[("device", owned.as_value())]), Ok(true));
+
+        let literal = Condition::Equals {
+            attr: "device",
+            value: Value::map(&posture),
+        };
+        assert_eq!(literal.validate(8, 256), Err(PolicyError::InvalidMap));
+    }
+
+    #[test]
+    fn test_condition_has_flags() {
+        const READ: i64 = 0b001;
+        const WRITE: i64 = 0b010;
+        let c = Condition::HasFlags {
+            attr: "caps",
+            mask: READ | WRITE,
+        };
+        assert_eq!(c.evaluate(&[("caps", Value::Int(0b111))]), Ok(true));
+        assert_eq!(c.evaluate(&[("caps", Value::Int(READ | WRITE))]), Ok(true));
+        assert_eq!(c.evaluate(&[("caps", Value::Int(READ))]), Ok(false));
+        // Negative Ints are two's complement bit patterns
+        assert_eq!(c.evaluate(&[("caps", Value::Int(-1))]), Ok(true));
+        // Missing or non-Int attribute: false
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        assert_eq!(c.evaluate(&[("caps", Value::String("3"))]), Ok(false));
+
+        let any = Condition::HasFlags {
+            attr: "caps",
+            mask: 0,
+        };
+        assert_eq!(any.evaluate(&[("caps", Value::Int(0))]), Ok(true));
+        assert_eq!(any.evaluate(&[]), Ok(false));
+    }
+
+    #[test]
+    fn test_condition_require() {
+        let c = Condition::Require(
+            "user_id",
+            Box::new(Condition::Equals {
+                attr: "user_id",
+                value: Value::String("u-1"),
+            }),
+        );
+        assert_eq!(c.depth(), 2);
+        assert_eq!(c.node_count(), 2);
+        assert_eq!(c.evaluate(&[("user_id", Value::String("u-1"))]), Ok(true));
+        assert_eq!(c.evaluate(&[("user_id", Value::String("u-2"))]), Ok(false));
+        let missing = PolicyError::RequiredAttributeMissing {
+            attr: "user_id".to_string(),
+        };
+        assert_eq!(c.evaluate(&[]), Err(missing.clone()));
+        assert_eq!(c.evaluate_three_valued(&[]), Err(missing.clone()));
+
+        // Other leaves stay lenient, and a skipped `Require` never fails
+        let lenient = Condition::Equals {
+            attr: "team",
+            value: Value::String("ops"),
+        };
+        assert_eq!(lenient.evaluate(&[]), Ok(false));
+        let either = Condition::Or(Box::new(Condition::True), Box::new(c.clone()));
+        assert_eq!(either.evaluate(&[]), Ok(true));
+        assert_eq!(either.evaluate_eager(&[]), Err(missing));
+
+        // Dotted paths are checked like any attribute
+        let nested = Condition::Require("device.id", Box::new(Condition::True));
+        assert_eq!(nested.max_path_depth(), 2);
+        let device = [("id", Value::Int(7))];
+        assert_eq!(
+            nested.evaluate(&[("device", Value::map(&device))]),
+            Ok(true)
+        );
+        assert!(nested.evaluate(&[("device", Value::map(&[]))]).is_err());
+    }
+
+    #[test]
+    fn test_condition_glob_matches() {
+        let c = Condition::GlobMatches {
+            attr: "project",
+            pattern: "proj-*-prod",
+        };
+        assert_eq!(
+            c.evaluate(&[("project", Value::String("proj-billing-prod"))]),
+            Ok(true)
+        );
+        assert_eq!(
+            c.evaluate(&[("project", Value::String("proj-billing-dev"))]),
+            Ok(false)
+        );
+        // Missing or non-String attribute: false
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        assert_eq!(c.evaluate(&[("project", Value::Int(1))]), Ok(false));
+
+        let upper = [("project", Value::String("PROJ-billing-PROD"))];
+        assert_eq!(c.evaluate(&upper), Ok(false));
+        assert_eq!(
+            c.evaluate_with_collation(&upper, Collation::AsciiCaseInsensitive),
+            Ok(true)
+        );
+
+        assert_eq!(c.validate(8, 256), Ok(()));
+        let wild = Condition::GlobMatches {
+            attr: "project",
+            pattern: "*-*-*-*-*-*-*-*-*",
+        };
+        assert_eq!(
+            wild.validate(8, 256),
+            Err(PolicyError::TooManyWildcards { max: 8, actual: 9 })
+        );
+    }
+
+    #[test]
+    fn test_condition_ip_in_cidr() {
+        let c = Condition::IpInCidr {
+            attr: "client",
+            cidr: Cidr::parse("10.0.0.0/8").unwrap(),
+        };
+        let inside = [("client", Value::Ip("10.20.30.40".parse().unwrap()))];
+        let outside = [("client", Value::Ip("192.168.0.1".parse().unwrap()))];
+        let text = [("client", Value::String("10.20.30.40"))];
+        assert_eq!(c.evaluate(&inside), Ok(true));
+        assert_eq!(c.evaluate(&outside), Ok(false));
+        // Strings are not parsed at evaluation time
+        assert_eq!(c.evaluate(&text), Ok(false));
+        assert_eq!(c.evaluate(&[]), Ok(false));
+    }
+
+    #[test]
+    fn test_condition_matches() {
+        let c = Condition::Matches {
+            attr: "service",
+            pattern: Pattern::parse("^svc-[a-z]*$").unwrap(),
+        };
+        let name = |s| [("service", Value::String(s))];
+        assert_eq!(c.evaluate(&name("svc-billing")), Ok(true));
+        assert_eq!(c.evaluate(&name("svc-billing2")), Ok(false));
+        assert_eq!(c.evaluate(&name("SVC-Billing")), Ok(false));
+        assert_eq!(
+            c.evaluate_with_collation(&name("SVC-Billing"), Collation::AsciiCaseInsensitive),
+            Ok(true)
+        );
+        // Missing or non-String attribute = false
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        assert_eq!(c.evaluate(&[("service", Value::Int(1))]), Ok(false));
+
+        assert_eq!(c.validate(10, 64), Ok(()));
+        assert!(c.validate(10, 8).is_err());
+    }
+
+    #[test]
+    fn test_condition_between() {
+        let between = |min, max| Condition::Between {
+            attr: "risk_score",
+            min,
+            max,
+        };
+        let score = |v| [("risk_score", Value::Int(v))];
+        for (v, expected) in [(-1, false), (0, true), (25, true), (40, true), (41, false)] {
+            assert_eq!(between(0, 40).evaluate(&score(v)), Ok(expected));
+        }
+        // Missing or non-Int attribute = false
+        assert_eq!(between(0, 40).evaluate(&[]), Ok(false));
+        let text = [("risk_score", Value::String("10"))];
+        assert_eq!(between(0, 40).evaluate(&text), Ok(false));
+
+        assert_eq!(between(7, 7).validate(10, 64), Ok(()));
+        assert_eq!(
+            between(40, 0).validate(10, 64),
+            Err(PolicyError::InvalidRange {
+                attr: "risk_score".to_string(),
+                min: 40,
+                max: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_time_conditions() {
+        // 2024-01-01 00:00:00 UTC, a Monday.
+        const MONDAY_MIDNIGHT: i64 = 1_704_067_200;
+        let business_hours = Condition::TimeOfDayBetween {
+            window: TimeWindow::new(9, 0, 17, 0),
+            utc_offset_minutes: 0,
+        };
+        let weekend = Condition::DayOfWeekIn {
+            days: Days::WEEKEND,
+            utc_offset_minutes: 60,
+        };
+        let at = |t| [(DEFAULT_CLOCK_ATTR, Value::Int(t))];
+        assert_eq!(
+            business_hours.evaluate(&at(MONDAY_MIDNIGHT + 9 * 3600)),
+            Ok(true)
+        );
+        assert_eq!(
+            business_hours.evaluate(&at(MONDAY_MIDNIGHT + 17 * 3600)),
+            Ok(false)
+        );
+        assert_eq!(weekend.evaluate(&at(MONDAY_MIDNIGHT)), Ok(false));
+        // Sunday 23:30 UTC is already Monday at UTC+1
+        assert_eq!(weekend.evaluate(&at(MONDAY_MIDNIGHT - 1800)), Ok(false));
+        assert_eq!(weekend.evaluate(&at(MONDAY_MIDNIGHT - 3 * 3600)), Ok(true));
+        // No clock = false
+        assert_eq!(business_hours.evaluate(&[]), Ok(false));
+
+        // An injected clock wins over the context
+        let mode = EvalMode {
+            now: Some(MONDAY_MIDNIGHT + 10 * 3600),
+            strict_types: true,
+            ..EvalMode::default()
+        };
+        let spoofed = [(DEFAULT_CLOCK_ATTR, Value::String("noon"))];
+        let result = business_hours.evaluate_observed(
+            &spoofed,
+            &mut GroupLookup::none(),
+            &mut CustomOpCalls::none(),
+            mode,
+            &mut NoopObserver,
+        );
+        assert_eq!(result, Ok(true));
+
+        let bad = Condition::TimeOfDayBetween {
+            window: TimeWindow::new(25, 0, 26, 0),
+            utc_offset_minutes: 0,
+        };
+        assert_eq!(bad.validate(10, 64), Err(PolicyError::InvalidSchedule));
+        let bad = Condition::DayOfWeekIn {
+            days: Days::ALL,
+            utc_offset_minutes: 24 * 60,
+        };
+        assert_eq!(bad.validate(10, 64), Err(PolicyError::InvalidSchedule));
+        let bad = Condition::TimeOfDayBetween {
+            window: TimeWindow::new(9, 0, 17, 0),
+            utc_offset_minutes: i32::MIN,
+        };
+        assert_eq!(bad.validate(10, 64), Err(PolicyError::InvalidSchedule));
+        let bad = Condition::DayOfWeekIn {
+            days: Days::ALL,
+            utc_offset_minutes: i32::MIN,
+        };
+        assert_eq!(bad.validate(10, 64), Err(PolicyError::InvalidSchedule));
+    }
+
+    #[test]
+    fn test_ordered_comparisons() {
+        let ctx = [("level", Value::Int(5)), ("name", Value::String("5"))];
+        let cmp = |cond: Condition<'_>| cond.evaluate(&ctx).unwrap();
+
+        assert!(cmp(Condition::GreaterThan {
+            attr: "level",
+            value: 4
+        }));
+        assert!(!cmp(Condition::GreaterThan {
+            attr: "level",
+            value: 5
+        }));
+        assert!(cmp(Condition::GreaterOrEqual {
+            attr: "level",
+            value: 5
+        }));
+        assert!(cmp(Condition::LessThan {
+            attr: "level",
+            value: 6
+        }));
+        assert!(!cmp(Condition::LessThan {
+            attr: "level",
+            value: 5
+        }));
+        assert!(cmp(Condition::LessOrEqual {
+            attr: "level",
+            value: 5
+        }));
+
+        // Missing and non-Int attributes fail closed, in both directions
+        for attr in ["missing", "name"] {
+            assert!(!cmp(Condition::GreaterOrEqual {
+                attr,
+                value: i64::MIN
+            }));
+            assert!(!cmp(Condition::LessOrEqual {
+                attr,
+                value: i64::MAX
+            }));
+        }
+    }
+
+    #[test]
+    fn test_condition_attr_is_principal() {
+        let c = Condition::AttrIsPrincipal("owner");
+        assert_eq!(c.depth(), 1);
+
+        // No principal here, so nothing is owned
+        let ctx: &[(&str, Value)] = &[("owner", Value::String(""))];
+        assert_eq!(c.evaluate(ctx), Ok(false));
+    }
+
     #[test]
     fn test_condition_not() {
         let c = Condition::Not(Box::new(Condition::True));
@@ -361,6 +1989,226 @@ mod tests {
         assert_eq!(c.evaluate(&[]), Ok(false));
     }
 
+    #[test]
+    fn test_short_circuit() {
+        // MemberOf fails without a group provider, so it shows whether the
+        // right operand ran.
+        let group = || Box::new(Condition::MemberOf("eng"));
+        let c = Condition::And(Box::new(Condition::False), group());
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        assert_eq!(c.evaluate_eager(&[]), Err(PolicyError::GroupLookupFailed));
+
+        let c = Condition::Or(Box::new(Condition::True), group());
+        assert_eq!(c.evaluate(&[]), Ok(true));
+        assert_eq!(c.evaluate_eager(&[]), Err(PolicyError::GroupLookupFailed));
+
+        // An undecided left operand still needs the right one
+        let c = Condition::Or(Box::new(Condition::False), group());
+        assert_eq!(c.evaluate(&[]), Err(PolicyError::GroupLookupFailed));
+    }
+
+    #[test]
+    fn test_strict_types() {
+        let strict = |c: &Condition, ctx: &[(&str, Value)]| {
+            let mode = EvalMode {
+                strict_types: true,
+                ..EvalMode::default()
+            };
+            let (mut groups, mut ops) = (GroupLookup::none(), CustomOpCalls::none());
+            c.evaluate_observed(ctx, &mut groups, &mut ops, mode, &mut NoopObserver)
+        };
+        let ctx: &[(&str, Value)] = &[("count", Value::Int(3))];
+        let c = Condition::Equals {
+            attr: "count",
+            value: Value::String("3"),
+        };
+        assert_eq!(c.evaluate(ctx), Ok(false));
+        assert_eq!(
+            strict(&c, ctx),
+            Err(PolicyError::TypeMismatch {
+                attr: "count".to_string(),
+                expected: "String",
+                actual: "Int",
+            })
+        );
+        // Missing attributes keep their lenient result
+        assert_eq!(strict(&c, &[]), Ok(false));
+
+        // A set matches if any of its values has the attribute's type
+        let c = Condition::In {
+            attr: "count",
+            values: &[Value::String("3"), Value::Int(3)],
+        };
+        assert_eq!(strict(&c, ctx), Ok(true));
+        let c = Condition::NotIn {
+            attr: "count",
+            values: &[Value::Bool(true)],
+        };
+        assert!(matches!(
+            strict(&c, ctx),
+            Err(PolicyError::TypeMismatch { .. })
+        ));
+
+        let c = Condition::GreaterThan {
+            attr: "count",
+            value: 1,
+        };
+        assert_eq!(strict(&c, ctx), Ok(true));
+        let ctx: &[(&str, Value)] = &[("count", Value::String("3"))];
+        assert_eq!(c.evaluate(ctx), Ok(false));
+        assert!(matches!(
+            strict(&c, ctx),
+            Err(PolicyError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_condition_implies_and_xor() {
+        let b = |v: bool| Box::new(if v { Condition::True } else { Condition::False });
+        for (a, c) in [(false, false), (false, true), (true, false), (true, true)] {
+            let implies = Condition::Implies(b(a), b(c));
+            assert_eq!(implies.evaluate(&[]), Ok(!a || c));
+            assert_eq!(implies.evaluate_eager(&[]), Ok(!a || c));
+            let xor = Condition::Xor(b(a), b(c));
+            assert_eq!(xor.evaluate(&[]), Ok(a != c));
+            assert_eq!(xor.evaluate_eager(&[]), Ok(a != c));
+        }
+        assert_eq!(Condition::Implies(b(true), b(true)).depth(), 2);
+
+        // A false premise skips the conclusion; Xor always needs both.
+        let group = || Box::new(Condition::MemberOf("eng"));
+        let c = Condition::Implies(b(false), group());
+        assert_eq!(c.evaluate(&[]), Ok(true));
+        assert_eq!(c.evaluate_eager(&[]), Err(PolicyError::GroupLookupFailed));
+        let c = Condition::Implies(b(true), group());
+        assert_eq!(c.evaluate(&[]), Err(PolicyError::GroupLookupFailed));
+        let c = Condition::Xor(b(true), group());
+        assert_eq!(c.evaluate(&[]), Err(PolicyError::GroupLookupFailed));
+    }
+
+    #[test]
+    fn test_all_of_any_of() {
+        let children = [Condition::True, Condition::True, Condition::False];
+        let all = Condition::AllOf(&children);
+        let any = Condition::AnyOf(&children);
+        assert_eq!(all.evaluate(&[]), Ok(false));
+        assert_eq!(any.evaluate(&[]), Ok(true));
+        assert_eq!(all.evaluate(&[]), all.evaluate_eager(&[]));
+        assert_eq!(any.evaluate(&[]), any.evaluate_eager(&[]));
+
+        // Empty lists are the identity of their connective
+        assert_eq!(Condition::AllOf(&[]).evaluate(&[]), Ok(true));
+        assert_eq!(Condition::AnyOf(&[]).evaluate(&[]), Ok(false));
+
+        // Width counts toward nodes, not depth
+        let wide: Vec<Condition> = (0..64).map(|_| Condition::True).collect();
+        let c = Condition::AllOf(&wide);
+        assert_eq!(c.depth(), 2);
+        assert_eq!(c.node_count(), 65);
+        assert!(c.validate(2, 256).is_ok());
+        assert_eq!(c.evaluate(&[]), Ok(true));
+    }
+
+    #[test]
+    fn test_all_of_any_of_short_circuit() {
+        let children = [Condition::False, Condition::MemberOf("eng")];
+        let c = Condition::AllOf(&children);
+        assert_eq!(c.evaluate(&[]), Ok(false));
+        assert_eq!(c.evaluate_eager(&[]), Err(PolicyError::GroupLookupFailed));
+
+        let children = [Condition::True, Condition::MemberOf("eng")];
+        let c = Condition::AnyOf(&children);
+        assert_eq!(c.evaluate(&[]), Ok(true));
+        assert_eq!(c.evaluate_eager(&[]), Err(PolicyError::GroupLookupFailed));
+
+        // Undecided children still need the rest
+        let c = Condition::AllOf(&children);
+        assert_eq!(c.evaluate(&[]), Err(PolicyError::GroupLookupFailed));
+    }
+
+    #[test]
+    fn test_three_valued() {
+        let ctx: &[(&str, Value)] = &[("role", Value::String("admin")), ("age", Value::Int(9))];
+        let b = |v: bool| Box::new(if v { Condition::True } else { Condition::False });
+        let missing = || {
+            Box::new(Condition::NotEquals {
+                attr: "team",
+                value: Value::String("ops"),
+            })
+        };
+
+        // Missing attributes are unknown; present ones decide as usual.
+        assert_eq!(missing().evaluate(ctx), Ok(true));
+        assert_eq!(missing().evaluate_three_valued(ctx), Ok(None));
+        let age = Condition::GreaterThan {
+            attr: "age",
+            value: 18,
+        };
+        assert_eq!(age.evaluate_three_valued(ctx), Ok(Some(false)));
+        let role = Condition::Equals {
+            attr: "role",
+            value: Value::Int(1),
+        };
+        assert_eq!(role.evaluate_three_valued(ctx), Ok(Some(false)));
+        let now = Condition::DayOfWeekIn {
+            days: Days::WEEKDAYS,
+            utc_offset_minutes: 0,
+        };
+        assert_eq!(now.evaluate_three_valued(ctx), Ok(None));
+
+        // Kleene truth tables, short-circuiting or not
+        let tables = [
+            (Condition::Not(missing()), None),
+            (Condition::And(missing(), b(true)), None),
+            (Condition::And(missing(), b(false)), Some(false)),
+            (Condition::And(b(false), missing()), Some(false)),
+            (Condition::Or(missing(), b(false)), None),
+            (Condition::Or(missing(), b(true)), Some(true)),
+            (Condition::Or(b(true), missing()), Some(true)),
+            (Condition::Implies(b(false), missing()), Some(true)),
+            (Condition::Implies(missing(), b(true)), Some(true)),
+            (Condition::Implies(b(true), missing()), None),
+            (Condition::Xor(missing(), b(true)), None),
+        ];
+        for (c, expected) in &tables {
+            assert_eq!(c.evaluate_three_valued(ctx), Ok(*expected), "{:?}", c);
+            let mode = EvalMode {
+                short_circuit: false,
+                three_valued: true,
+                ..EvalMode::default()
+            };
+            let mut unlimited = usize::MAX;
+            let eager = c.evaluate_budgeted(
+                ctx,
+                &mut GroupLookup::none(),
+                &mut CustomOpCalls::none(),
+                mode,
+                &mut NoopObserver,
+                &mut unlimited,
+            );
+            assert_eq!(eager, Ok(Poll::Ready(*expected)), "{:?}", c);
+        }
+
+        let children = [*missing(), Condition::True, *missing()];
+        assert_eq!(
+            Condition::AllOf(&children).evaluate_three_valued(ctx),
+            Ok(None)
+        );
+        assert_eq!(
+            Condition::AnyOf(&children).evaluate_three_valued(ctx),
+            Ok(Some(true))
+        );
+        let children = [*missing(), Condition::False];
+        assert_eq!(
+            Condition::AllOf(&children).evaluate_three_valued(ctx),
+            Ok(Some(false))
+        );
+        assert_eq!(
+            Condition::AnyOf(&children).evaluate_three_valued(ctx),
+            Ok(None)
+        );
+    }
+
     #[test]
     fn test_condition_depth_nested() {
         // (A AND (B OR (NOT C)))
@@ -372,6 +2220,7 @@ mod tests {
             )),
         );
         assert_eq!(c.depth(), 4);
+        assert_eq!(c.node_count(), 6);
     }
 
     #[test]
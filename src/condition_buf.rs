@@ -0,0 +1,660 @@
+//! Owned conditions, for storage and transport.
+//!
+//! `Condition<'a>` borrows every name and value, which keeps evaluation
+//! allocation-free but means a condition cannot outlive its source. A
+//! `ConditionBuf` owns its data instead, so it can be sent over the wire or
+//! stored in a database (with the `serde` feature it is `Serialize` and
+//! `Deserialize`), then lowered back into a borrowed `Condition`:
+//!
+//! ```
+//! use gate0::{Condition, ConditionBuf, Value};
+//!
+//! let values = [Value::String("admin"), Value::String("owner")];
+//! let original = Condition::In { attr: "role", values: &values };
+//! let buf = ConditionBuf::from(&original);
+//!
+//! // Sets and operator arguments are lent out of a `ConditionStorage`.
+//! let storage = buf.storage();
+//! let condition = storage.condition();
+//! assert_eq!(condition, original);
+//! ```
+//!
+//! `AllOf` and `AnyOf` hold a slice of conditions, which a storage cannot
+//! lend without borrowing from itself, so they lower to balanced trees of
+//! `And` and `Or`. The result evaluates identically but may be
+//! `ceil(log2(n))` levels deeper for `n` children.
+//!
+//! Both conversions walk the tree with an explicit stack, like
+//! `Condition::depth`, so an unvalidated deep tree cannot overflow the
+//! call stack. A lowered condition is not validated; building a `Policy`
+//! from it does that.
+
+use crate::cidr::Cidr;
+use crate::condition::Condition;
+use crate::pattern::Pattern;
+use crate::schedule::{Days, TimeWindow};
+use crate::value::{Value, ValueBuf};
+
+/// An owned counterpart of `Condition`; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConditionBuf {
+    /// `Condition::True`.
+    True,
+    /// `Condition::False`.
+    False,
+    /// `Condition::Equals`.
+    Equals {
+        /// The attribute name.
+        attr: String,
+        /// The value to compare against.
+        value: ValueBuf,
+    },
+    /// `Condition::NotEquals`.
+    NotEquals {
+        /// The attribute name.
+        attr: String,
+        /// The value to compare against.
+        value: ValueBuf,
+    },
+    /// `Condition::EqualsIgnoreCase`.
+    EqualsIgnoreCase {
+        /// The attribute name.
+        attr: String,
+        /// The string to compare against.
+        value: String,
+    },
+    /// `Condition::In`.
+    In {
+        /// The attribute name.
+        attr: String,
+        /// The values to compare against.
+        values: Vec<ValueBuf>,
+    },
+    /// `Condition::NotIn`.
+    NotIn {
+        /// The attribute name.
+        attr: String,
+        /// The values to compare against.
+        values: Vec<ValueBuf>,
+    },
+    /// `Condition::GreaterThan`.
+    GreaterThan {
+        /// The attribute name.
+        attr: String,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// `Condition::GreaterOrEqual`.
+    GreaterOrEqual {
+        /// The attribute name.
+        attr: String,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// `Condition::LessThan`.
+    LessThan {
+        /// The attribute name.
+        attr: String,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// `Condition::LessOrEqual`.
+    LessOrEqual {
+        /// The attribute name.
+        attr: String,
+        /// The bound to compare against.
+        value: i64,
+    },
+    /// `Condition::Between`.
+    Between {
+        /// The attribute name.
+        attr: String,
+        /// The lower bound.
+        min: i64,
+        /// The upper bound.
+        max: i64,
+    },
+    /// `Condition::IpInCidr`.
+    IpInCidr {
+        /// The attribute name.
+        attr: String,
+        /// The network to match.
+        cidr: Cidr,
+    },
+    /// `Condition::Matches`.
+    Matches {
+        /// The attribute name.
+        attr: String,
+        /// The pattern to match.
+        pattern: Pattern,
+    },
+    /// `Condition::TimeOfDayBetween`.
+    TimeOfDayBetween {
+        /// Start of the window, in minutes since midnight.
+        start: u16,
+        /// End of the window (exclusive), in minutes since midnight.
+        end: u16,
+        /// Offset of local time from UTC, in minutes.
+        utc_offset_minutes: i32,
+    },
+    /// `Condition::DayOfWeekIn`.
+    DayOfWeekIn {
+        /// The accepted days as a bitmask (bit 0 = Monday).
+        days: u8,
+        /// Offset of local time from UTC, in minutes.
+        utc_offset_minutes: i32,
+    },
+    /// `Condition::MemberOf`.
+    MemberOf(String),
+    /// `Condition::AttrIsPrincipal`.
+    AttrIsPrincipal(String),
+    /// `Condition::Custom`.
+    Custom {
+        /// The registered operator's name.
+        op: String,
+        /// Context attributes passed to the operator.
+        args: Vec<String>,
+    },
+    /// `Condition::Ref`.
+    Ref(String),
+    /// `Condition::And`.
+    And(Box<ConditionBuf>, Box<ConditionBuf>),
+    /// `Condition::Or`.
+    Or(Box<ConditionBuf>, Box<ConditionBuf>),
+    /// `Condition::Not`.
+    Not(Box<ConditionBuf>),
+    /// `Condition::Implies`.
+    Implies(Box<ConditionBuf>, Box<ConditionBuf>),
+    /// `Condition::Xor`.
+    Xor(Box<ConditionBuf>, Box<ConditionBuf>),
+    /// `Condition::AllOf`; lowers to balanced `And`s.
+    AllOf(Vec<ConditionBuf>),
+    /// `Condition::AnyOf`; lowers to balanced `Or`s.
+    AnyOf(Vec<ConditionBuf>),
+    /// `Condition::AttrContains`.
+    AttrContains {
+        /// The attribute name.
+        attr: String,
+        /// The value to look for.
+        value: ValueBuf,
+    },
+    /// `Condition::HasFlags`.
+    HasFlags {
+        /// The attribute name.
+        attr: String,
+        /// The bits that must all be set.
+        mask: i64,
+    },
+    /// `Condition::GlobMatches`.
+    GlobMatches {
+        /// The attribute name.
+        attr: String,
+        /// The glob to match.
+        pattern: String,
+    },
+    /// `Condition::Require`.
+    Require(String, Box<ConditionBuf>),
+}
+
+impl ConditionBuf {
+    /// Borrow the sets and operator arguments of this condition, ready
+    /// for `ConditionStorage::condition`.
+    pub fn storage(&self) -> ConditionStorage<'_> {
+        let nodes = self.postorder();
+        let mut sets = Vec::new();
+        let mut args = Vec::new();
+        for node in &nodes {
+            match node {
+                ConditionBuf::In { values, .. } | ConditionBuf::NotIn { values, .. } => {
+                    sets.push(values.iter().map(ValueBuf::as_value).collect());
+                }
+                ConditionBuf::Custom { args: names, .. } => {
+                    args.push(names.iter().map(String::as_str).collect());
+                }
+                _ => {}
+            }
+        }
+        ConditionStorage { nodes, sets, args }
+    }
+
+    /// Every node, children before parents, left to right.
+    fn postorder(&self) -> Vec<&ConditionBuf> {
+        let mut stack = vec![self];
+        let mut nodes = Vec::new();
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            match node {
+                ConditionBuf::Not(inner) | ConditionBuf::Require(_, inner) => stack.push(inner),
+                ConditionBuf::And(a, b)
+                | ConditionBuf::Or(a, b)
+                | ConditionBuf::Implies(a, b)
+                | ConditionBuf::Xor(a, b) => {
+                    stack.push(a);
+                    stack.push(b);
+                }
+                ConditionBuf::AllOf(children) | ConditionBuf::AnyOf(children) => {
+                    stack.extend(children.iter())
+                }
+                _ => {}
+            }
+        }
+        // Parents before children, right to left; reversed it is postorder.
+        nodes.reverse();
+        nodes
+    }
+}
+
+/// The borrowed parts of a `ConditionBuf` that a `Condition` lends out.
+#[derive(Debug)]
+pub struct ConditionStorage<'b> {
+    nodes: Vec<&'b ConditionBuf>,
+    /// `In` and `NotIn` sets, in postorder.
+    sets: Vec<Vec<Value<'b>>>,
+    /// `Custom` arguments, in postorder.
+    args: Vec<Vec<&'b str>>,
+}
+
+impl<'b> ConditionStorage<'b> {
+    /// Lower the condition into a `Condition` borrowing from this storage.
+    pub fn condition(&self) -> Condition<'_> {
+        let mut results: Vec<Condition<'_>> = Vec::with_capacity(16);
+        let (mut sets, mut args) = (self.sets.iter(), self.args.iter());
+        // `postorder` yields every child before its parent, so each pop
+        // below finds the node's children on `results`.
+        for node in &self.nodes {
+            let lowered = match node {
+                ConditionBuf::True => Condition::True,
+                ConditionBuf::False => Condition::False,
+                ConditionBuf::Equals { attr, value } => Condition::Equals {
+                    attr,
+                    value: value.as_value(),
+                },
+                ConditionBuf::NotEquals { attr, value } => Condition::NotEquals {
+                    attr,
+                    value: value.as_value(),
+                },
+                ConditionBuf::AttrContains { attr, value } => Condition::AttrContains {
+                    attr,
+                    value: value.as_value(),
+                },
+                ConditionBuf::EqualsIgnoreCase { attr, value } => {
+                    Condition::EqualsIgnoreCase { attr, value }
+                }
+                ConditionBuf::In { attr, .. } => Condition::In {
+                    attr,
+                    values: sets.next().map_or(&[], Vec::as_slice),
+                },
+                ConditionBuf::NotIn { attr, .. } => Condition::NotIn {
+                    attr,
+                    values: sets.next().map_or(&[], Vec::as_slice),
+                },
+                ConditionBuf::GreaterThan { attr, value } => Condition::GreaterThan {
+                    attr,
+                    value: *value,
+                },
+                ConditionBuf::GreaterOrEqual { attr, value } => Condition::GreaterOrEqual {
+                    attr,
+                    value: *value,
+                },
+                ConditionBuf::LessThan { attr, value } => Condition::LessThan {
+                    attr,
+                    value: *value,
+                },
+                ConditionBuf::LessOrEqual { attr, value } => Condition::LessOrEqual {
+                    attr,
+                    value: *value,
+                },
+                ConditionBuf::Between { attr, min, max } => Condition::Between {
+                    attr,
+                    min: *min,
+                    max: *max,
+                },
+                ConditionBuf::HasFlags { attr, mask } => Condition::HasFlags { attr, mask: *mask },
+                ConditionBuf::GlobMatches { attr, pattern } => {
+                    Condition::GlobMatches { attr, pattern }
+                }
+                ConditionBuf::IpInCidr { attr, cidr } => Condition::IpInCidr { attr, cidr: *cidr },
+                ConditionBuf::Matches { attr, pattern } => Condition::Matches {
+                    attr,
+                    pattern: pattern.clone(),
+                },
+                ConditionBuf::TimeOfDayBetween {
+                    start,
+                    end,
+                    utc_offset_minutes,
+                } => Condition::TimeOfDayBetween {
+                    window: TimeWindow {
+                        start: *start,
+                        end: *end,
+                    },
+                    utc_offset_minutes: *utc_offset_minutes,
+                },
+                ConditionBuf::DayOfWeekIn {
+                    days,
+                    utc_offset_minutes,
+                } => Condition::DayOfWeekIn {
+                    days: Days::from_bits(*days),
+                    utc_offset_minutes: *utc_offset_minutes,
+                },
+                ConditionBuf::MemberOf(group) => Condition::MemberOf(group),
+                ConditionBuf::AttrIsPrincipal(attr) => Condition::AttrIsPrincipal(attr),
+                ConditionBuf::Custom { op, .. } => Condition::Custom {
+                    op,
+                    args: args.next().map_or(&[], Vec::as_slice),
+                },
+                ConditionBuf::Ref(name) => Condition::Ref(name),
+                ConditionBuf::Not(_) => Condition::Not(pop_boxed(&mut results, Condition::False)),
+                ConditionBuf::Require(attr, _) => {
+                    Condition::Require(attr, pop_boxed(&mut results, Condition::False))
+                }
+                ConditionBuf::And(..)
+                | ConditionBuf::Or(..)
+                | ConditionBuf::Implies(..)
+                | ConditionBuf::Xor(..) => {
+                    let b = pop_boxed(&mut results, Condition::False);
+                    let a = pop_boxed(&mut results, Condition::False);
+                    match node {
+                        ConditionBuf::And(..) => Condition::And(a, b),
+                        ConditionBuf::Or(..) => Condition::Or(a, b),
+                        ConditionBuf::Implies(..) => Condition::Implies(a, b),
+                        _ => Condition::Xor(a, b),
+                    }
+                }
+                ConditionBuf::AllOf(children) | ConditionBuf::AnyOf(children) => {
+                    let at = results.len().saturating_sub(children.len());
+                    let children = results.split_off(at);
+                    match node {
+                        ConditionBuf::AllOf(_) => {
+                            balanced(children, Condition::True, Condition::And)
+                        }
+                        _ => balanced(children, Condition::False, Condition::Or),
+                    }
+                }
+            };
+            results.push(lowered);
+        }
+        results.pop().unwrap_or(Condition::False)
+    }
+}
+
+/// Pop a lowered child. `default` is unreachable for well-formed
+/// postorders but keeps this panic-free.
+fn pop_boxed<T>(results: &mut Vec<T>, default: T) -> Box<T> {
+    Box::new(results.pop().unwrap_or(default))
+}
+
+/// Combine `children` pairwise with `connective` until one is left.
+fn balanced<'a>(
+    mut children: Vec<Condition<'a>>,
+    empty: Condition<'a>,
+    connective: fn(Box<Condition<'a>>, Box<Condition<'a>>) -> Condition<'a>,
+) -> Condition<'a> {
+    while children.len() > 1 {
+        let mut paired = Vec::with_capacity(children.len().div_ceil(2));
+        let mut iter = children.into_iter();
+        while let Some(a) = iter.next() {
+            paired.push(match iter.next() {
+                Some(b) => connective(Box::new(a), Box::new(b)),
+                None => a,
+            });
+        }
+        children = paired;
+    }
+    children.pop().unwrap_or(empty)
+}
+
+impl Drop for ConditionBuf {
+    fn drop(&mut self) {
+        // Move descendants onto a heap stack so a deep tree is dropped
+        // iteratively, as `Condition`'s `Drop` does.
+        let mut stack = Vec::new();
+        take_children(self, &mut stack);
+        while let Some(mut node) = stack.pop() {
+            take_children(&mut node, &mut stack);
+        }
+    }
+}
+
+fn take_children(node: &mut ConditionBuf, stack: &mut Vec<ConditionBuf>) {
+    let mut take = |child: &mut Box<ConditionBuf>| {
+        stack.push(std::mem::replace(&mut **child, ConditionBuf::True));
+    };
+    match node {
+        ConditionBuf::Not(inner) | ConditionBuf::Require(_, inner) => take(inner),
+        ConditionBuf::And(a, b)
+        | ConditionBuf::Or(a, b)
+        | ConditionBuf::Implies(a, b)
+        | ConditionBuf::Xor(a, b) => {
+            take(a);
+            take(b);
+        }
+        ConditionBuf::AllOf(children) | ConditionBuf::AnyOf(children) => stack.append(children),
+        _ => {}
+    }
+}
+
+impl From<&Condition<'_>> for ConditionBuf {
+    fn from(condition: &Condition<'_>) -> Self {
+        let mut results: Vec<ConditionBuf> = Vec::with_capacity(16);
+        for node in condition.postorder() {
+            let owned = match node {
+                Condition::True => ConditionBuf::True,
+                Condition::False => ConditionBuf::False,
+                Condition::Equals { attr, value } => ConditionBuf::Equals {
+                    attr: attr.to_string(),
+                    value: value.into(),
+                },
+                Condition::NotEquals { attr, value } => ConditionBuf::NotEquals {
+                    attr: attr.to_string(),
+                    value: value.into(),
+                },
+                Condition::AttrContains { attr, value } => ConditionBuf::AttrContains {
+                    attr: attr.to_string(),
+                    value: value.into(),
+                },
+                Condition::EqualsIgnoreCase { attr, value } => ConditionBuf::EqualsIgnoreCase {
+                    attr: attr.to_string(),
+                    value: value.to_string(),
+                },
+                Condition::In { attr, values } => ConditionBuf::In {
+                    attr: attr.to_string(),
+                    values: values.iter().map(ValueBuf::from).collect(),
+                },
+                Condition::NotIn { attr, values } => ConditionBuf::NotIn {
+                    attr: attr.to_string(),
+                    values: values.iter().map(ValueBuf::from).collect(),
+                },
+                Condition::GreaterThan { attr, value } => ConditionBuf::GreaterThan {
+                    attr: attr.to_string(),
+                    value: *value,
+                },
+                Condition::GreaterOrEqual { attr, value } => ConditionBuf::GreaterOrEqual {
+                    attr: attr.to_string(),
+                    value: *value,
+                },
+                Condition::LessThan { attr, value } => ConditionBuf::LessThan {
+                    attr: attr.to_string(),
+                    value: *value,
+                },
+                Condition::LessOrEqual { attr, value } => ConditionBuf::LessOrEqual {
+                    attr: attr.to_string(),
+                    value: *value,
+                },
+                Condition::Between { attr, min, max } => ConditionBuf::Between {
+                    attr: attr.to_string(),
+                    min: *min,
+                    max: *max,
+                },
+                Condition::HasFlags { attr, mask } => ConditionBuf::HasFlags {
+                    attr: attr.to_string(),
+                    mask: *mask,
+                },
+                Condition::GlobMatches { attr, pattern } => ConditionBuf::GlobMatches {
+                    attr: attr.to_string(),
+                    pattern: pattern.to_string(),
+                },
+                Condition::IpInCidr { attr, cidr } => ConditionBuf::IpInCidr {
+                    attr: attr.to_string(),
+                    cidr: *cidr,
+                },
+                Condition::Matches { attr, pattern } => ConditionBuf::Matches {
+                    attr: attr.to_string(),
+                    pattern: pattern.clone(),
+                },
+                Condition::TimeOfDayBetween {
+                    window,
+                    utc_offset_minutes,
+                } => ConditionBuf::TimeOfDayBetween {
+                    start: window.start,
+                    end: window.end,
+                    utc_offset_minutes: *utc_offset_minutes,
+                },
+                Condition::DayOfWeekIn {
+                    days,
+                    utc_offset_minutes,
+                } => ConditionBuf::DayOfWeekIn {
+                    days: days.bits(),
+                    utc_offset_minutes: *utc_offset_minutes,
+                },
+                Condition::MemberOf(group) => ConditionBuf::MemberOf(group.to_string()),
+                Condition::AttrIsPrincipal(attr) => ConditionBuf::AttrIsPrincipal(attr.to_string()),
+                Condition::Custom { op, args } => ConditionBuf::Custom {
+                    op: op.to_string(),
+                    args: args.iter().map(|arg| arg.to_string()).collect(),
+                },
+                Condition::Ref(name) => ConditionBuf::Ref(name.to_string()),
+                Condition::Not(_) => {
+                    ConditionBuf::Not(pop_boxed(&mut results, ConditionBuf::False))
+                }
+                Condition::Require(attr, _) => ConditionBuf::Require(
+                    attr.to_string(),
+                    pop_boxed(&mut results, ConditionBuf::False),
+                ),
+                Condition::And(..)
+                | Condition::Or(..)
+                | Condition::Implies(..)
+                | Condition::Xor(..) => {
+                    let b = pop_boxed(&mut results, ConditionBuf::False);
+                    let a = pop_boxed(&mut results, ConditionBuf::False);
+                    match node {
+                        Condition::And(..) => ConditionBuf::And(a, b),
+                        Condition::Or(..) => ConditionBuf::Or(a, b),
+                        Condition::Implies(..) => ConditionBuf::Implies(a, b),
+                        _ => ConditionBuf::Xor(a, b),
+                    }
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    let at = results.len().saturating_sub(children.len());
+                    let children = results.split_off(at);
+                    match node {
+                        Condition::AllOf(_) => ConditionBuf::AllOf(children),
+                        _ => ConditionBuf::AnyOf(children),
+                    }
+                }
+            };
+            results.push(owned);
+        }
+        results.pop().unwrap_or(ConditionBuf::False)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample<'a>(values: &'a [Value<'a>], args: &'a [&'a str]) -> Condition<'a> {
+        Condition::Or(
+            Box::new(Condition::And(
+                Box::new(Condition::In {
+                    attr: "role",
+                    values,
+                }),
+                Box::new(Condition::Not(Box::new(Condition::IpInCidr {
+                    attr: "ip",
+                    cidr: Cidr::parse("10.0.0.0/8").unwrap(),
+                }))),
+            )),
+            Box::new(Condition::Implies(
+                Box::new(Condition::Custom { op: "near", args }),
+                Box::new(Condition::And(
+                    Box::new(Condition::NotIn {
+                        attr: "level",
+                        values: &values[1..],
+                    }),
+                    Box::new(Condition::Matches {
+                        attr: "name",
+                        pattern: Pattern::parse("^svc-[a-z]*$").unwrap(),
+                    }),
+                )),
+            )),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let values = [Value::String("admin"), Value::Int(3)];
+        let args = ["lat", "lon"];
+        let original = sample(&values, &args);
+
+        let buf = ConditionBuf::from(&original);
+        let storage = buf.storage();
+        assert_eq!(storage.condition(), original);
+        assert_eq!(ConditionBuf::from(&storage.condition()), buf);
+    }
+
+    #[test]
+    fn test_all_of_lowers_to_balanced_tree() {
+        let leaves = [Condition::True, Condition::False, Condition::True];
+        let buf = ConditionBuf::from(&Condition::AllOf(&leaves));
+        let t = || Box::new(Condition::True);
+        assert_eq!(
+            buf.storage().condition(),
+            Condition::And(
+                Box::new(Condition::And(t(), Box::new(Condition::False))),
+                t()
+            )
+        );
+
+        assert_eq!(
+            ConditionBuf::AllOf(vec![]).storage().condition(),
+            Condition::True
+        );
+        assert_eq!(
+            ConditionBuf::AnyOf(vec![]).storage().condition(),
+            Condition::False
+        );
+        let any = ConditionBuf::AnyOf(vec![ConditionBuf::MemberOf("eng".to_string())]);
+        assert_eq!(any.storage().condition(), Condition::MemberOf("eng"));
+    }
+
+    #[test]
+    fn test_deep_tree_does_not_overflow() {
+        let mut buf = ConditionBuf::True;
+        for _ in 0..100_000 {
+            buf = ConditionBuf::Not(Box::new(buf));
+        }
+        assert_eq!(buf.storage().condition().depth(), 100_001);
+        let back = ConditionBuf::from(&buf.storage().condition());
+        assert_eq!(back.storage().condition().depth(), 100_001);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let values = [Value::String("admin"), Value::Int(3)];
+        let args = ["lat", "lon"];
+        let buf = ConditionBuf::from(&sample(&values, &args));
+
+        let json = serde_json::to_string(&buf).unwrap();
+        assert!(json.contains(r#""cidr":"10.0.0.0/8""#));
+        assert!(json.contains(r#""pattern":"^svc-[a-z]*$""#));
+        let back: ConditionBuf = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, buf);
+
+        let bad = r#"{"IpInCidr":{"attr":"ip","cidr":"10.0.0.0/40"}}"#;
+        assert!(serde_json::from_str::<ConditionBuf>(bad).is_err());
+        let bad = r#"{"Matches":{"attr":"name","pattern":"(a|b)"}}"#;
+        assert!(serde_json::from_str::<ConditionBuf>(bad).is_err());
+    }
+}
@@ -0,0 +1,574 @@
+//! Policy test coverage reporting.
+//!
+//! Given a policy and a suite of test requests, reports which rules fired
+//! and which condition nodes were observed evaluating to `true` and `false`.
+//! CI can use this to enforce "every rule has at least one test that makes
+//! it fire" and to find condition branches no test exercises.
+//!
+//! `Condition::coverage` does the same for a single condition over a
+//! corpus of contexts, without building a policy around it.
+//!
+//! Condition nodes are identified by their pre-order index within the
+//! rule's condition tree (the root is node 0).
+
+use std::collections::HashMap;
+
+use crate::condition::{Condition, EvalMode};
+use crate::custom::CustomOpCalls;
+use crate::groups::GroupLookup;
+use crate::observe::EvalObserver;
+use crate::policy::Policy;
+use crate::types::{Effect, ReasonCode, Request};
+use crate::value::Value;
+
+/// Coverage of a single condition node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchCoverage {
+    /// Pre-order index of the node within its condition tree.
+    pub node: usize,
+    /// The node kind, e.g. `"And"` or `"Equals"`.
+    pub kind: &'static str,
+    /// True if the node was observed evaluating to `true`.
+    pub seen_true: bool,
+    /// True if the node was observed evaluating to `false`.
+    pub seen_false: bool,
+}
+
+impl BranchCoverage {
+    /// Returns `true` if both outcomes of this node were observed.
+    pub fn is_covered(&self) -> bool {
+        self.seen_true && self.seen_false
+    }
+
+    /// Returns `true` if this node was evaluated at all. Nodes skipped by
+    /// short-circuiting in every evaluation are never exercised.
+    pub fn is_exercised(&self) -> bool {
+        self.seen_true || self.seen_false
+    }
+
+    fn record(&mut self, result: bool) {
+        if result {
+            self.seen_true = true;
+        } else {
+            self.seen_false = true;
+        }
+    }
+}
+
+/// Coverage of a single rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleCoverage {
+    /// Index of the rule in the policy.
+    pub index: usize,
+    /// The rule's effect.
+    pub effect: Effect,
+    /// The rule's reason code.
+    pub reason: ReasonCode,
+    /// The rule's tags.
+    pub tags: Vec<String>,
+    /// Number of requests whose target matched this rule.
+    pub target_hits: usize,
+    /// Number of requests for which the rule fired (target and condition matched).
+    pub fired: usize,
+    /// Per-node condition coverage, in pre-order. Empty if the rule has no condition.
+    pub branches: Vec<BranchCoverage>,
+}
+
+impl RuleCoverage {
+    /// Returns `true` if every condition node saw both outcomes.
+    pub fn branches_covered(&self) -> bool {
+        self.branches.iter().all(BranchCoverage::is_covered)
+    }
+}
+
+/// Coverage of a policy over a suite of requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// Per-rule coverage in declared order.
+    pub rules: Vec<RuleCoverage>,
+    /// Number of requests evaluated.
+    pub requests: usize,
+    /// Number of requests whose evaluation returned an error.
+    pub errors: usize,
+}
+
+impl CoverageReport {
+    /// Rules that never fired.
+    pub fn unfired_rules(&self) -> impl Iterator<Item = &RuleCoverage> {
+        self.rules.iter().filter(|r| r.fired == 0)
+    }
+
+    /// Rules carrying `tag`, e.g. to check that every `"pci"` rule fired.
+    pub fn rules_tagged<'s>(&'s self, tag: &'s str) -> impl Iterator<Item = &'s RuleCoverage> {
+        self.rules
+            .iter()
+            .filter(move |r| r.tags.iter().any(|t| t == tag))
+    }
+
+    /// `(rule index, branch)` pairs for condition nodes missing an outcome.
+    pub fn uncovered_branches(&self) -> impl Iterator<Item = (usize, &BranchCoverage)> {
+        self.rules.iter().flat_map(|r| {
+            r.branches
+                .iter()
+                .filter(|b| !b.is_covered())
+                .map(move |b| (r.index, b))
+        })
+    }
+
+    /// Fraction of rules that fired at least once (1.0 for an empty policy).
+    pub fn rule_ratio(&self) -> f64 {
+        if self.rules.is_empty() {
+            return 1.0;
+        }
+        let fired = self.rules.iter().filter(|r| r.fired > 0).count();
+        fired as f64 / self.rules.len() as f64
+    }
+
+    /// Fraction of condition node outcomes observed (1.0 if there are none).
+    pub fn branch_ratio(&self) -> f64 {
+        let mut total = 0usize;
+        let mut seen = 0usize;
+        for branch in self.rules.iter().flat_map(|r| &r.branches) {
+            total += 2;
+            seen += branch.seen_true as usize + branch.seen_false as usize;
+        }
+        if total == 0 {
+            1.0
+        } else {
+            seen as f64 / total as f64
+        }
+    }
+
+    /// Returns `Err` with the unfired rule indices if any rule never fired.
+    pub fn require_all_rules_fired(&self) -> Result<(), Vec<usize>> {
+        let unfired: Vec<usize> = self.unfired_rules().map(|r| r.index).collect();
+        if unfired.is_empty() {
+            Ok(())
+        } else {
+            Err(unfired)
+        }
+    }
+}
+
+/// Coverage of a single condition over a corpus of contexts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionCoverage {
+    /// Per-node coverage, in pre-order.
+    pub branches: Vec<BranchCoverage>,
+    /// Number of contexts evaluated.
+    pub contexts: usize,
+    /// Number of contexts whose evaluation returned an error.
+    pub errors: usize,
+}
+
+impl ConditionCoverage {
+    /// Nodes no context ever evaluated.
+    pub fn unexercised(&self) -> impl Iterator<Item = &BranchCoverage> {
+        self.branches.iter().filter(|b| !b.is_exercised())
+    }
+
+    /// Nodes missing an outcome, including unexercised ones.
+    pub fn uncovered(&self) -> impl Iterator<Item = &BranchCoverage> {
+        self.branches.iter().filter(|b| !b.is_covered())
+    }
+
+    /// Returns `true` if every node saw both outcomes.
+    pub fn is_covered(&self) -> bool {
+        self.branches.iter().all(BranchCoverage::is_covered)
+    }
+
+    /// Fraction of node outcomes observed.
+    pub fn branch_ratio(&self) -> f64 {
+        let seen: usize = self
+            .branches
+            .iter()
+            .map(|b| b.seen_true as usize + b.seen_false as usize)
+            .sum();
+        // A condition always has at least one node.
+        seen as f64 / (2 * self.branches.len()) as f64
+    }
+}
+
+impl<'a> Condition<'a> {
+    /// Evaluate this condition against every context and report which
+    /// nodes were exercised and which outcomes each produced.
+    ///
+    /// Evaluation short-circuits as it does in a policy, so an `Or` arm
+    /// behind an always-true sibling shows up as unexercised. `Custom`
+    /// conditions have no operators to call and count as errors; outcomes
+    /// observed before an error are still recorded.
+    pub fn coverage(&self, contexts: &[&[(&str, Value<'_>)]]) -> ConditionCoverage {
+        let mut collector = NodeCollector::new(self);
+        let mut errors = 0;
+        for context in contexts {
+            let result = self.evaluate_observed(
+                context,
+                &mut GroupLookup::none(),
+                &mut CustomOpCalls::none(),
+                EvalMode::default(),
+                &mut collector,
+            );
+            if result.is_err() {
+                errors += 1;
+            }
+        }
+        ConditionCoverage {
+            branches: collector.branches,
+            contexts: contexts.len(),
+            errors,
+        }
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Evaluate every request and report which rules and condition branches
+    /// were exercised.
+    ///
+    /// Evaluation errors are counted in `CoverageReport::errors`; outcomes
+    /// observed before the error are still recorded.
+    pub fn coverage(&self, requests: &[Request<'_>]) -> CoverageReport {
+        let mut collector = Collector::new(self);
+        let mut errors = 0;
+        for request in requests {
+            if self
+                .evaluate_observed(request, None, None, &mut collector)
+                .is_err()
+            {
+                errors += 1;
+            }
+        }
+        CoverageReport {
+            rules: collector.rules,
+            requests: requests.len(),
+            errors,
+        }
+    }
+}
+
+/// Observer that accumulates coverage across evaluations.
+struct Collector {
+    rules: Vec<RuleCoverage>,
+    /// Per rule, node address -> pre-order indices. Rules may share
+    /// nodes, so addresses only identify a node within one rule.
+    slots: Vec<HashMap<usize, Vec<usize>>>,
+    /// The rule whose condition is being evaluated.
+    current: usize,
+}
+
+impl Collector {
+    fn new(policy: &Policy<'_>) -> Self {
+        let mut slots = Vec::new();
+        let rules = policy
+            .rules()
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let (branches, nodes) = match &rule.condition {
+                    Some(cond) => branch_slots(cond),
+                    None => (Vec::new(), HashMap::new()),
+                };
+                slots.push(nodes);
+                RuleCoverage {
+                    index,
+                    effect: rule.effect,
+                    reason: rule.reason,
+                    tags: rule.tags.iter().map(|t| t.to_string()).collect(),
+                    target_hits: 0,
+                    fired: 0,
+                    branches,
+                }
+            })
+            .collect();
+        Collector {
+            rules,
+            slots,
+            current: 0,
+        }
+    }
+}
+
+impl EvalObserver for Collector {
+    fn condition_evaluated(&mut self, index: usize) {
+        self.current = index;
+        if let Some(rule) = self.rules.get_mut(index) {
+            rule.target_hits += 1;
+        }
+    }
+
+    fn node_evaluated(&mut self, node: &Condition<'_>, result: bool) {
+        let key = node as *const Condition<'_> as usize;
+        let (Some(rule), Some(slots)) = (
+            self.rules.get_mut(self.current),
+            self.slots.get(self.current),
+        ) else {
+            return;
+        };
+        for &idx in slots.get(&key).into_iter().flatten() {
+            rule.branches[idx].record(result);
+        }
+    }
+
+    fn rule_matched(&mut self, index: usize) {
+        if let Some(rule) = self.rules.get_mut(index) {
+            rule.fired += 1;
+            // Unconditional rules only report here, so count the target hit too.
+            if rule.branches.is_empty() {
+                rule.target_hits += 1;
+            }
+        }
+    }
+}
+
+/// Observer that accumulates coverage of a single condition.
+struct NodeCollector {
+    branches: Vec<BranchCoverage>,
+    /// Node address -> pre-order indices.
+    slots: HashMap<usize, Vec<usize>>,
+}
+
+impl NodeCollector {
+    fn new(cond: &Condition<'_>) -> Self {
+        let (branches, slots) = branch_slots(cond);
+        NodeCollector { branches, slots }
+    }
+}
+
+impl EvalObserver for NodeCollector {
+    fn node_evaluated(&mut self, node: &Condition<'_>, result: bool) {
+        let key = node as *const Condition<'_> as usize;
+        for &idx in self.slots.get(&key).into_iter().flatten() {
+            self.branches[idx].record(result);
+        }
+    }
+}
+
+/// Empty coverage of `cond`'s nodes in pre-order, and the pre-order
+/// indices of each node address. A node at several positions, such as a
+/// child slice shared by two `AllOf` nodes, records at every one of them.
+fn branch_slots(cond: &Condition<'_>) -> (Vec<BranchCoverage>, HashMap<usize, Vec<usize>>) {
+    let mut slots: HashMap<usize, Vec<usize>> = HashMap::new();
+    let branches = preorder(cond)
+        .into_iter()
+        .enumerate()
+        .map(|(node, c)| {
+            slots
+                .entry(c as *const Condition<'_> as usize)
+                .or_default()
+                .push(node);
+            BranchCoverage {
+                node,
+                kind: c.kind_name(),
+                seen_true: false,
+                seen_false: false,
+            }
+        })
+        .collect();
+    (branches, slots)
+}
+
+/// Collect condition nodes in pre-order without recursion.
+fn preorder<'c, 'a>(root: &'c Condition<'a>) -> Vec<&'c Condition<'a>> {
+    let mut out = Vec::new();
+    let mut stack = vec![root];
+    while let Some(cond) = stack.pop() {
+        out.push(cond);
+        match cond {
+            Condition::Not(inner) | Condition::Require(_, inner) => stack.push(inner),
+            Condition::And(a, b)
+            | Condition::Or(a, b)
+            | Condition::Implies(a, b)
+            | Condition::Xor(a, b) => {
+                stack.push(b);
+                stack.push(a);
+            }
+            Condition::AllOf(children) | Condition::AnyOf(children) => {
+                stack.extend(children.iter().rev());
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+    use crate::target::{Matcher, Target};
+    use crate::value::Value;
+
+    fn policy() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Exact("blocked"),
+                    action: Matcher::Any,
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(1),
+            ))
+            .rule(
+                Rule::new(
+                    Effect::Allow,
+                    Target {
+                        principal: Matcher::Any,
+                        action: Matcher::Exact("read"),
+                        resource: Matcher::Any,
+                        environment: Matcher::Any,
+                    },
+                    Some(Condition::And(
+                        Box::new(Condition::Equals {
+                            attr: "role",
+                            value: Value::String("admin"),
+                        }),
+                        Box::new(Condition::Equals {
+                            attr: "mfa",
+                            value: Value::Bool(true),
+                        }),
+                    )),
+                    ReasonCode(2),
+                )
+                .with_tags(&["pci"]),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_unfired_rules() {
+        let policy = policy();
+        let report = policy.coverage(&[Request::new("alice", "read", "doc")]);
+
+        assert_eq!(report.requests, 1);
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.require_all_rules_fired(), Err(vec![0, 1]));
+        assert_eq!(report.rule_ratio(), 0.0);
+        // Rule 1's target matched but its condition did not.
+        assert_eq!(report.rules[1].target_hits, 1);
+        assert_eq!(report.rules[1].fired, 0);
+        let tagged: Vec<usize> = report.rules_tagged("pci").map(|r| r.index).collect();
+        assert_eq!(tagged, [1]);
+    }
+
+    #[test]
+    fn test_branch_coverage() {
+        let policy = policy();
+        let admin: &[(&str, Value)] =
+            &[("role", Value::String("admin")), ("mfa", Value::Bool(true))];
+        let no_mfa: &[(&str, Value)] = &[
+            ("role", Value::String("admin")),
+            ("mfa", Value::Bool(false)),
+        ];
+
+        let report = policy.coverage(&[
+            Request::new("blocked", "write", "doc"),
+            Request::with_context("alice", "read", "doc", admin),
+            Request::with_context("alice", "read", "doc", no_mfa),
+        ]);
+
+        assert!(report.require_all_rules_fired().is_ok());
+        let branches = &report.rules[1].branches;
+        assert_eq!(branches.len(), 3);
+        assert_eq!(branches[0].kind, "And");
+        assert!(branches[0].is_covered());
+        // `role == admin` was never false.
+        assert_eq!(branches[1].kind, "Equals");
+        assert!(branches[1].seen_true && !branches[1].seen_false);
+        assert!(branches[2].is_covered());
+
+        let uncovered: Vec<(usize, usize)> = report
+            .uncovered_branches()
+            .map(|(r, b)| (r, b.node))
+            .collect();
+        assert_eq!(uncovered, vec![(1, 1)]);
+        assert!((report.branch_ratio() - 5.0 / 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_shared_children() {
+        static ADMIN: [Condition<'static>; 1] = [Condition::Equals {
+            attr: "role",
+            value: Value::String("admin"),
+        }];
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::AllOf(&ADMIN)),
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::Not(Box::new(Condition::AnyOf(&ADMIN)))),
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+        let admin: &[(&str, Value)] = &[("role", Value::String("admin"))];
+        let guest: &[(&str, Value)] = &[("role", Value::String("guest"))];
+
+        let report = policy.coverage(&[
+            Request::with_context("alice", "read", "doc", admin),
+            Request::with_context("bob", "read", "doc", guest),
+        ]);
+        // Each rule records its own outcomes of the shared node
+        assert!(report.require_all_rules_fired().is_ok());
+        assert_eq!(report.uncovered_branches().count(), 0);
+        assert_eq!(report.rules[0].branches.len(), 2);
+        assert_eq!(report.rules[1].branches.len(), 3);
+
+        // Within one condition, the node records at both of its positions
+        let both = Condition::And(
+            Box::new(Condition::AllOf(&ADMIN)),
+            Box::new(Condition::AnyOf(&ADMIN)),
+        );
+        let report = both.coverage(&[admin, guest]);
+        let unexercised: Vec<usize> = report.unexercised().map(|b| b.node).collect();
+        assert!(unexercised.is_empty());
+    }
+
+    #[test]
+    fn test_condition_coverage() {
+        // role == "admin" || (mfa == true && !(country == "XX"))
+        let cond = Condition::Or(
+            Box::new(Condition::Equals {
+                attr: "role",
+                value: Value::String("admin"),
+            }),
+            Box::new(Condition::And(
+                Box::new(Condition::Equals {
+                    attr: "mfa",
+                    value: Value::Bool(true),
+                }),
+                Box::new(Condition::Not(Box::new(Condition::Equals {
+                    attr: "country",
+                    value: Value::String("XX"),
+                }))),
+            )),
+        );
+        let admin: &[(&str, Value)] = &[("role", Value::String("admin"))];
+        let no_mfa: &[(&str, Value)] = &[("mfa", Value::Bool(false))];
+
+        let report = cond.coverage(&[admin, no_mfa]);
+        assert_eq!(report.contexts, 2);
+        assert_eq!(report.errors, 0);
+        let kinds: Vec<&str> = report.branches.iter().map(|b| b.kind).collect();
+        assert_eq!(kinds, ["Or", "Equals", "And", "Equals", "Not", "Equals"]);
+        // The `Not` arm was short-circuited away every time.
+        let unexercised: Vec<usize> = report.unexercised().map(|b| b.node).collect();
+        assert_eq!(unexercised, [4, 5]);
+        // `mfa == true` was never true, and the `And` never allowed.
+        let uncovered: Vec<usize> = report.uncovered().map(|b| b.node).collect();
+        assert_eq!(uncovered, [2, 3, 4, 5]);
+        assert!(!report.is_covered());
+
+        let mfa: &[(&str, Value)] = &[("mfa", Value::Bool(true))];
+        let blocked: &[(&str, Value)] =
+            &[("mfa", Value::Bool(true)), ("country", Value::String("XX"))];
+        let report = cond.coverage(&[admin, no_mfa, mfa, blocked]);
+        assert!(report.is_covered());
+        assert_eq!(report.branch_ratio(), 1.0);
+    }
+}
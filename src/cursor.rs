@@ -0,0 +1,378 @@
+//! Time-sliced evaluation.
+//!
+//! `evaluate()` runs to completion. An event loop with a frame deadline
+//! cannot always afford that for a large policy, so a cursor evaluates a
+//! bounded number of steps per `resume` call and picks up where it left
+//! off on the next one:
+//!
+//! ```
+//! use std::task::Poll;
+//! use gate0::{Policy, ReasonCode, Request, Rule, Target};
+//!
+//! let policy = Policy::builder()
+//!     .rule(Rule::allow(Target::any(), ReasonCode(1)))
+//!     .build()
+//!     .unwrap();
+//! let request = Request::new("alice", "read", "doc");
+//!
+//! let mut cursor = policy.cursor(&request);
+//! let decision = loop {
+//!     match cursor.resume(8) {
+//!         Poll::Ready(decision) => break decision.unwrap(),
+//!         Poll::Pending => { /* run other work */ }
+//!     }
+//! };
+//! assert!(decision.is_allow());
+//! ```
+//!
+//! A step is one rule checked or one condition node visited, the same
+//! unit `evaluate_with_deadline` polls at. Results match `evaluate()`
+//! exactly; only the work is spread out. A cursor holds no heap memory and
+//! may simply be dropped to abandon an evaluation.
+
+use std::fmt;
+use std::task::Poll;
+
+use crate::clock::Clock;
+use crate::condition::{Condition, EvalMode, EvalState};
+use crate::custom::CustomOpCalls;
+use crate::error::PolicyError;
+use crate::groups::{GroupLookup, GroupProvider};
+use crate::observe::NoopObserver;
+use crate::policy::{Policy, RuleTally};
+use crate::types::{Decision, Request};
+use crate::value::Value;
+
+impl<'a> Condition<'a> {
+    /// Start a resumable evaluation of this condition against `context`.
+    ///
+    /// Same semantics as `evaluate()`; see `EvalCursor::resume`.
+    pub fn cursor<'c>(&'c self, context: &'c [(&'c str, Value<'c>)]) -> EvalCursor<'c, 'a> {
+        EvalCursor {
+            state: EvalState::new(self),
+            context,
+        }
+    }
+}
+
+/// A condition evaluation that runs a bounded number of steps at a time.
+pub struct EvalCursor<'c, 'a> {
+    state: EvalState<'c, 'a>,
+    context: &'c [(&'c str, Value<'c>)],
+}
+
+impl<'c, 'a> EvalCursor<'c, 'a> {
+    /// Evaluate at most `max_steps` more nodes.
+    ///
+    /// Returns `Poll::Pending` if the budget ran out first. Once the result
+    /// is ready, further calls return it again without doing any work.
+    pub fn resume(&mut self, max_steps: usize) -> Poll<Result<bool, PolicyError>> {
+        self.state
+            .run(
+                self.context,
+                &mut GroupLookup::none(),
+                &mut CustomOpCalls::none(),
+                EvalMode::default(),
+                &mut NoopObserver,
+                max_steps,
+            )
+            // Only three-valued evaluation is ever unknown.
+            .map(|result| result?.ok_or(PolicyError::InternalError))
+    }
+
+    /// Steps taken so far.
+    pub fn steps(&self) -> u64 {
+        self.state.steps()
+    }
+}
+
+impl fmt::Debug for EvalCursor<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EvalCursor")
+            .field("steps", &self.steps())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Start a resumable evaluation of `request`.
+    ///
+    /// Same semantics as `evaluate()`, or `evaluate_with_groups()` and
+    /// `evaluate_at()` once `with_groups` and `with_clock` are set; see
+    /// `PolicyCursor::resume`.
+    pub fn cursor<'p>(&'p self, request: &'p Request<'p>) -> PolicyCursor<'p, 'a> {
+        PolicyCursor {
+            policy: self,
+            request,
+            groups: GroupLookup::new(None, request.principal, self.config().max_group_lookups),
+            ops: CustomOpCalls::new(self.custom_ops(), self.config().max_custom_op_cost),
+            evals: self.config().max_condition_evals,
+            now: None,
+            started: false,
+            next_rule: 0,
+            condition: None,
+            tally: RuleTally::default(),
+            steps: 0,
+            outcome: None,
+        }
+    }
+}
+
+/// A policy evaluation that runs a bounded number of steps at a time.
+pub struct PolicyCursor<'p, 'a> {
+    policy: &'p Policy<'a>,
+    request: &'p Request<'p>,
+    groups: GroupLookup<'p, 'a>,
+    ops: CustomOpCalls<'p, 'a>,
+    /// Condition evaluation steps left under `max_condition_evals`.
+    evals: usize,
+    now: Option<i64>,
+    /// Whether the request has been validated.
+    started: bool,
+    /// The rule being checked, or the number of rules once all are.
+    next_rule: usize,
+    /// The condition of `next_rule`, if it is being evaluated.
+    condition: Option<EvalState<'p, 'a>>,
+    tally: RuleTally<'p, 'a>,
+    steps: u64,
+    /// Set once evaluation has finished.
+    outcome: Option<Result<Decision, PolicyError>>,
+}
+
+impl<'p, 'a> PolicyCursor<'p, 'a> {
+    /// Resolve `MemberOf` through `groups`. Call before the first `resume`.
+    pub fn with_groups(mut self, groups: &'p dyn GroupProvider) -> Self {
+        let max = self.policy.config().max_group_lookups;
+        self.groups = GroupLookup::new(Some(groups), self.request.principal, max);
+        self
+    }
+
+    /// Check schedules and time conditions against `clock`, read once,
+    /// instead of the context.
+    /// Call before the first `resume`.
+    pub fn with_clock(mut self, clock: &dyn Clock) -> Self {
+        self.now = Some(clock.now());
+        self
+    }
+
+    /// Evaluate at most `max_steps` more steps.
+    ///
+    /// Returns `Poll::Pending` if the budget ran out first. Once the
+    /// decision is ready, further calls return it again without doing any
+    /// work.
+    pub fn resume(&mut self, max_steps: usize) -> Poll<Result<Decision, PolicyError>> {
+        if let Some(outcome) = &self.outcome {
+            return Poll::Ready(outcome.clone());
+        }
+        let outcome = match self.advance(max_steps).transpose() {
+            Some(outcome) => outcome,
+            None => return Poll::Pending,
+        };
+        self.outcome = Some(outcome.clone());
+        Poll::Ready(outcome)
+    }
+
+    /// Steps taken so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// The rule loop of `Policy::evaluate_memoized`, unrolled so it can
+    /// stop between any two steps. Returns `None` if the budget ran out.
+    fn advance(&mut self, max_steps: usize) -> Result<Option<Decision>, PolicyError> {
+        let policy = self.policy;
+        let config = policy.config();
+        let request = self.request;
+        let mut budget = max_steps;
+
+        loop {
+            if let Some(state) = &mut self.condition {
+                let before = state.steps();
+                let poll = state.run(
+                    request.context,
+                    &mut self.groups,
+                    &mut self.ops,
+                    EvalMode {
+                        now: self.now,
+                        ..config.eval_mode()
+                    },
+                    &mut NoopObserver,
+                    budget.min(self.evals),
+                );
+                let taken = state.steps() - before;
+                self.steps += taken;
+                budget -= taken as usize;
+                self.evals -= taken as usize;
+                let Poll::Ready(result) = poll else {
+                    if self.evals == 0 {
+                        return Err(PolicyError::EvalBudgetExceeded {
+                            max: config.max_condition_evals,
+                        });
+                    }
+                    return Ok(None);
+                };
+
+                self.condition = None;
+                let index = self.next_rule;
+                let rule = &policy.rules()[index];
+                self.next_rule += 1;
+                match result {
+                    Ok(Some(true)) => {
+                        if let Some(decision) =
+                            self.tally.matched(index, rule, config, &mut NoopObserver)
+                        {
+                            return Ok(Some(decision));
+                        }
+                    }
+                    Ok(Some(false)) => {}
+                    Ok(None) => self.tally.unknown(rule),
+                    Err(e) => self.tally.failed(rule, e, config)?,
+                }
+                continue;
+            }
+
+            let Some(rule) = policy.rules().get(self.next_rule) else {
+                return std::mem::take(&mut self.tally).decide(config).map(Some);
+            };
+            if budget == 0 {
+                return Ok(None);
+            }
+            budget -= 1;
+            self.steps += 1;
+            if !self.started {
+                policy.validate_request(request, true)?;
+                self.started = true;
+            }
+
+            if !rule
+                .target
+                .resource
+                .matches_with(request.resource, config.collation)
+                || !policy.rule_active(rule, request, self.now)
+            {
+                self.next_rule += 1;
+                continue;
+            }
+            match &rule.condition {
+                Some(cond) => self.condition = Some(EvalState::new(cond)),
+                None => {
+                    let index = self.next_rule;
+                    self.next_rule += 1;
+                    if let Some(decision) =
+                        self.tally.matched(index, rule, config, &mut NoopObserver)
+                    {
+                        return Ok(Some(decision));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PolicyCursor<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolicyCursor")
+            .field("next_rule", &self.next_rule)
+            .field("steps", &self.steps)
+            .field("outcome", &self.outcome)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode};
+
+    fn run<T>(mut resume: impl FnMut(usize) -> Poll<T>, slice: usize) -> (T, usize) {
+        let mut calls = 1;
+        loop {
+            if let Poll::Ready(result) = resume(slice) {
+                return (result, calls);
+            }
+            calls += 1;
+        }
+    }
+
+    #[test]
+    fn test_condition_cursor() {
+        let condition = Condition::And(
+            Box::new(Condition::Equals {
+                attr: "role",
+                value: Value::String("admin"),
+            }),
+            Box::new(Condition::Not(Box::new(Condition::Equals {
+                attr: "locked",
+                value: Value::Bool(true),
+            }))),
+        );
+        let ctx = [("role", Value::String("admin"))];
+
+        let mut cursor = condition.cursor(&ctx);
+        assert_eq!(cursor.resume(0), Poll::Pending);
+        let (result, calls) = run(|n| cursor.resume(n), 1);
+        assert_eq!(result, Ok(true));
+        assert_eq!(calls as u64, cursor.steps());
+        assert!(calls > 1);
+        // Finished cursors answer without more work
+        assert_eq!(cursor.resume(0), Poll::Ready(Ok(true)));
+
+        let mut cursor = condition.cursor(&ctx);
+        assert_eq!(
+            cursor.resume(usize::MAX),
+            Poll::Ready(condition.evaluate(&ctx))
+        );
+        let staff = Condition::MemberOf("staff");
+        let mut cursor = staff.cursor(&[]);
+        assert_eq!(
+            cursor.resume(1),
+            Poll::Ready(Err(PolicyError::GroupLookupFailed))
+        );
+    }
+
+    #[test]
+    fn test_policy_cursor_matches_evaluate() {
+        let locked = Condition::Equals {
+            attr: "locked",
+            value: Value::Bool(true),
+        };
+        let either = Condition::Or(Box::new(Condition::False), Box::new(Condition::True));
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(locked),
+                ReasonCode(2),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(either),
+                ReasonCode(1),
+            ))
+            .rule(Rule::allow(Target::any(), ReasonCode(3)))
+            .build()
+            .unwrap();
+
+        let contexts: [&[(&str, Value)]; 2] = [&[], &[("locked", Value::Bool(true))]];
+        for ctx in contexts {
+            let request = Request::with_context("alice", "read", "doc", ctx);
+            let expected = policy.evaluate(&request);
+            for slice in [1, 2, 3, 100] {
+                let mut cursor = policy.cursor(&request);
+                let (result, calls) = run(|n| cursor.resume(n), slice);
+                assert_eq!(result, expected);
+                assert_eq!(calls as u64, cursor.steps().div_ceil(slice as u64));
+            }
+        }
+
+        let long = "x".repeat(1_000);
+        let request = Request::new(&long, "read", "doc");
+        assert!(matches!(
+            policy.cursor(&request).resume(1),
+            Poll::Ready(Err(PolicyError::StringTooLong { .. }))
+        ));
+    }
+}
@@ -0,0 +1,334 @@
+//! Application-defined condition operators.
+//!
+//! `Condition::Custom` names an operator and the context attributes it
+//! reads. Operators are registered on the `PolicyBuilder`, and every
+//! reference is resolved when the policy is built, so a typo fails
+//! construction rather than a request. At evaluation time the operator
+//! receives the attribute values (`None` when absent) and a zeroed,
+//! stack-allocated scratch buffer; evaluation stays allocation-free as
+//! long as the operator itself does not allocate.
+//!
+//! Each operator declares a cost. A request may spend at most
+//! `PolicyConfig::max_custom_op_cost` on operator calls; the call that
+//! would exceed it fails with `PolicyError::CustomOpBudgetExceeded`
+//! before the operator runs.
+
+use crate::condition::{lookup_attr, Condition};
+use crate::error::PolicyError;
+use crate::groups::ProviderError;
+use crate::value::Value;
+
+/// Maximum number of attributes a `Condition::Custom` may pass.
+pub const MAX_CUSTOM_OP_ARGS: usize = 4;
+
+/// Size in bytes of the scratch buffer handed to each operator call.
+pub const CUSTOM_OP_SCRATCH_LEN: usize = 256;
+
+/// A domain-specific predicate usable from `Condition::Custom`.
+pub trait CustomOp: Send + Sync {
+    /// The name conditions refer to this operator by.
+    fn name(&self) -> &str;
+
+    /// Budget units charged per call (default: 1).
+    fn cost(&self) -> u32 {
+        1
+    }
+
+    /// Decide the predicate for the given attribute values, in the order
+    /// the condition lists them. `scratch` is zeroed before every call.
+    fn evaluate(
+        &self,
+        args: &[Option<&Value<'_>>],
+        scratch: &mut [u8],
+    ) -> Result<bool, ProviderError>;
+}
+
+/// The operators registered on a policy.
+#[derive(Clone, Default)]
+pub(crate) struct CustomOps<'a> {
+    ops: Vec<&'a dyn CustomOp>,
+}
+
+impl<'a> CustomOps<'a> {
+    /// Add `op`; checked by `validate`.
+    pub(crate) fn push(&mut self, op: &'a dyn CustomOp) {
+        self.ops.push(op);
+    }
+
+    /// Heap bytes held by the registry.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.ops.capacity() * std::mem::size_of::<&dyn CustomOp>()
+    }
+
+    /// Check that names are non-empty, unique, and within `max_string_len`.
+    pub(crate) fn validate(&self, max_string_len: usize) -> Result<(), PolicyError> {
+        for (i, op) in self.ops.iter().enumerate() {
+            let name = op.name();
+            if name.is_empty()
+                || name.len() > max_string_len
+                || self.ops[..i].iter().any(|other| other.name() == name)
+            {
+                return Err(PolicyError::InvalidCustomOp);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every `Condition::Custom` in `cond` names a registered
+    /// operator. Non-recursive, like `Condition::validate`.
+    pub(crate) fn resolve(&self, cond: &Condition<'_>) -> Result<(), PolicyError> {
+        let mut stack = vec![cond];
+        while let Some(cond) = stack.pop() {
+            match cond {
+                Condition::Custom { op, .. } if self.get(op).is_none() => {
+                    return Err(PolicyError::InvalidCustomOp);
+                }
+                Condition::Not(inner) | Condition::Require(_, inner) => stack.push(inner),
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter().rev());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up an operator by name.
+    pub(crate) fn get(&self, name: &str) -> Option<&'a dyn CustomOp> {
+        self.ops.iter().copied().find(|op| op.name() == name)
+    }
+}
+
+impl std::fmt::Debug for CustomOps<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.ops.iter().map(|op| op.name()))
+            .finish()
+    }
+}
+
+/// Per-request operator state: registry, budget, and scratch space.
+pub(crate) struct CustomOpCalls<'p, 'a> {
+    ops: Option<&'p CustomOps<'a>>,
+    remaining: usize,
+    max: usize,
+    scratch: [u8; CUSTOM_OP_SCRATCH_LEN],
+}
+
+impl<'p, 'a> CustomOpCalls<'p, 'a> {
+    /// Calls against `ops`, bounded by `max` cost units.
+    pub(crate) fn new(ops: &'p CustomOps<'a>, max: usize) -> Self {
+        CustomOpCalls {
+            ops: Some(ops),
+            remaining: max,
+            max,
+            scratch: [0; CUSTOM_OP_SCRATCH_LEN],
+        }
+    }
+
+    /// No registry: every call fails.
+    pub(crate) fn none() -> Self {
+        CustomOpCalls {
+            ops: None,
+            remaining: 0,
+            max: 0,
+            scratch: [0; CUSTOM_OP_SCRATCH_LEN],
+        }
+    }
+
+    /// Run operator `name` over the named attributes of `context`.
+    pub(crate) fn call(
+        &mut self,
+        name: &str,
+        attrs: &[&str],
+        context: &[(&str, Value<'_>)],
+    ) -> Result<bool, PolicyError> {
+        let op = self
+            .ops
+            .and_then(|ops| ops.get(name))
+            .ok_or(PolicyError::CustomOpFailed)?;
+        if attrs.len() > MAX_CUSTOM_OP_ARGS {
+            return Err(PolicyError::InvalidCustomOp);
+        }
+        let cost = op.cost() as usize;
+        if cost > self.remaining {
+            return Err(PolicyError::CustomOpBudgetExceeded { max: self.max });
+        }
+        self.remaining -= cost;
+
+        let mut values: [Option<Value<'_>>; MAX_CUSTOM_OP_ARGS] = Default::default();
+        for (slot, attr) in values.iter_mut().zip(attrs) {
+            *slot = lookup_attr(context, attr);
+        }
+        let mut args: [Option<&Value<'_>>; MAX_CUSTOM_OP_ARGS] = [None; MAX_CUSTOM_OP_ARGS];
+        for (slot, value) in args.iter_mut().zip(&values) {
+            *slot = value.as_ref();
+        }
+        self.scratch.fill(0);
+        op.evaluate(&args[..attrs.len()], &mut self.scratch)
+            .map_err(|_| PolicyError::CustomOpFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Policy, PolicyConfig, Rule};
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode, Request};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// True if the first argument is a string with the second as a suffix.
+    struct EndsWith;
+
+    impl CustomOp for EndsWith {
+        fn name(&self) -> &str {
+            "ends_with"
+        }
+
+        fn evaluate(
+            &self,
+            args: &[Option<&Value<'_>>],
+            scratch: &mut [u8],
+        ) -> Result<bool, ProviderError> {
+            assert!(scratch.iter().all(|b| *b == 0));
+            scratch[0] = 1;
+            match args {
+                [Some(Value::String(s)), Some(Value::String(suffix))] => Ok(s.ends_with(suffix)),
+                [_, _] => Ok(false),
+                _ => Err(ProviderError),
+            }
+        }
+    }
+
+    /// Costs 3 units per call and counts its calls.
+    struct Expensive {
+        calls: AtomicUsize,
+    }
+
+    impl CustomOp for Expensive {
+        fn name(&self) -> &str {
+            "expensive"
+        }
+
+        fn cost(&self) -> u32 {
+            3
+        }
+
+        fn evaluate(&self, _: &[Option<&Value<'_>>], _: &mut [u8]) -> Result<bool, ProviderError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(false)
+        }
+    }
+
+    fn rule(op: &'static str, args: &'static [&'static str]) -> Rule<'static> {
+        Rule::new(
+            Effect::Allow,
+            Target::any(),
+            Some(Condition::Custom { op, args }),
+            ReasonCode(1),
+        )
+    }
+
+    #[test]
+    fn test_custom_op_decides() {
+        let op = EndsWith;
+        let policy = Policy::builder()
+            .custom_op(&op)
+            .rule(rule("ends_with", &["email", "domain"]))
+            .build()
+            .unwrap();
+
+        let ctx: &[(&str, Value)] = &[
+            ("email", Value::String("alice@example.com")),
+            ("domain", Value::String("@example.com")),
+        ];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+
+        // A missing attribute reaches the operator as None
+        let request = Request::with_context("alice", "read", "doc", &ctx[..1]);
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+    }
+
+    #[test]
+    fn test_unresolved_ops_rejected() {
+        let op = EndsWith;
+        let result = Policy::builder()
+            .rule(rule("ends_with", &["a", "b"]))
+            .build();
+        assert_eq!(result.unwrap_err(), PolicyError::InvalidCustomOp);
+
+        let result = Policy::builder()
+            .custom_op(&op)
+            .rule(rule("ends_with", &["a", "b", "c", "d", "e"]))
+            .build();
+        assert_eq!(result.unwrap_err(), PolicyError::InvalidCustomOp);
+
+        let result = Policy::builder().custom_op(&op).custom_op(&op).build();
+        assert_eq!(result.unwrap_err(), PolicyError::InvalidCustomOp);
+
+        // Without a policy there is no registry
+        let cond = Condition::Custom {
+            op: "ends_with",
+            args: &[],
+        };
+        assert_eq!(cond.evaluate(&[]), Err(PolicyError::CustomOpFailed));
+    }
+
+    #[test]
+    fn test_operator_failure() {
+        let op = EndsWith;
+        let policy = Policy::builder()
+            .custom_op(&op)
+            .rule(rule("ends_with", &["email"]))
+            .build()
+            .unwrap();
+        let request = Request::new("alice", "read", "doc");
+        assert_eq!(policy.evaluate(&request), Err(PolicyError::CustomOpFailed));
+    }
+
+    #[test]
+    fn test_budget() {
+        let op = Expensive {
+            calls: AtomicUsize::new(0),
+        };
+        let config = PolicyConfig {
+            max_custom_op_cost: 7,
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::builder()
+            .config(config)
+            .custom_op(&op)
+            .rule(rule("expensive", &[]))
+            .rule(rule("expensive", &[]))
+            .rule(rule("expensive", &[]))
+            .build()
+            .unwrap();
+
+        let request = Request::new("alice", "read", "doc");
+        assert_eq!(
+            policy.evaluate(&request),
+            Err(PolicyError::CustomOpBudgetExceeded { max: 7 })
+        );
+        // The third call was refused before running
+        assert_eq!(op.calls.load(Ordering::Relaxed), 2);
+
+        // The budget is per request
+        let policy = Policy::builder()
+            .config(config)
+            .custom_op(&op)
+            .rule(rule("expensive", &[]))
+            .build()
+            .unwrap();
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+    }
+}
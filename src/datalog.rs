@@ -0,0 +1,397 @@
+//! Biscuit datalog export.
+//!
+//! Translates a policy into biscuit-auth authorizer policies, so token
+//! checks can be generated from the same source as the PDP. The authorizer
+//! is expected to provide these facts for each request:
+//!
+//! ```text
+//! principal($p)        operation($a)        resource($r)
+//! environment($e)          // if the request names one
+//! context($key, $value)    // one per context attribute
+//! member_of($group)        // one per group the principal belongs to
+//! ```
+//!
+//! Biscuit policies are first-match, so deny-overrides is preserved by
+//! emitting every Deny rule before every Allow rule, followed by a final
+//! `deny if true` for the default deny. Reason codes are carried as comments.
+//!
+//! Only the fragment datalog can express faithfully is supported: Allow and
+//! Deny rules whose conditions use `True`, `False`, `Equals`, `In`,
+//! `MemberOf`, `AttrIsPrincipal`, `And`, `Or`, `AllOf`, and `AnyOf`.
+//! Negation (`Not`, `NotEquals`, `NotIn`, and the negated operands of
+//! `Implies` and `Xor`) is true for missing
+//! attributes in gate0 but has no datalog equivalent, so it is rejected,
+//! as are ordered comparisons and bit flag tests (datalog fails rather than
+//! being false on a non-Int attribute), CIDR matches, list membership,
+//! custom operators, schedules, break-glass,
+//! Challenge/Indeterminate effects, and Allow rules widened by an action hierarchy.
+
+use std::fmt::{self, Write};
+
+use crate::condition::Condition;
+use crate::policy::{Policy, Rule};
+use crate::target::Matcher;
+use crate::types::Effect;
+use crate::value::Value;
+
+/// Maximum number of `or` alternatives generated for one rule.
+pub const MAX_ALTERNATIVES: usize = 64;
+
+/// Errors exporting a policy to datalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportError {
+    /// The rule uses a feature outside the supported fragment.
+    Unsupported {
+        /// Index of the rule.
+        rule: usize,
+        /// The unsupported feature.
+        feature: &'static str,
+    },
+    /// The rule's condition expands to more than `MAX_ALTERNATIVES` bodies.
+    TooManyAlternatives {
+        /// Index of the rule.
+        rule: usize,
+    },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Unsupported { rule, feature } => {
+                write!(
+                    f,
+                    "rule {} uses {}, which datalog cannot express",
+                    rule, feature
+                )
+            }
+            ExportError::TooManyAlternatives { rule } => write!(
+                f,
+                "rule {} expands to more than {} alternatives",
+                rule, MAX_ALTERNATIVES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// A positive atom in a rule body.
+#[derive(Debug, Clone, Copy)]
+enum Literal<'a> {
+    Equals(&'a str, &'a Value<'a>),
+    MemberOf(&'a str),
+    IsPrincipal(&'a str),
+}
+
+/// A condition in disjunctive normal form: a list of conjunctions, where
+/// an empty list is false and an empty conjunction is true.
+type Dnf<'a> = Vec<Vec<Literal<'a>>>;
+
+/// Export `policy` as biscuit authorizer policies, one per line.
+pub fn to_biscuit_datalog(policy: &Policy<'_>) -> Result<String, ExportError> {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// gate0 policy export, fingerprint {:016x}",
+        policy.fingerprint()
+    );
+    for pass in [Effect::Deny, Effect::Allow] {
+        for (index, rule) in policy.rules().iter().enumerate() {
+            check_supported(index, rule)?;
+            if rule.effect == Effect::Allow && !policy.action_hierarchy().is_empty() {
+                return Err(ExportError::Unsupported {
+                    rule: index,
+                    feature: "an action hierarchy",
+                });
+            }
+            if rule.effect != pass {
+                continue;
+            }
+            let bodies = match &rule.condition {
+                None => vec![Vec::new()],
+                Some(cond) => dnf(cond).map_err(|feature| ExportError::Unsupported {
+                    rule: index,
+                    feature,
+                })?,
+            };
+            if bodies.len() > MAX_ALTERNATIVES {
+                return Err(ExportError::TooManyAlternatives { rule: index });
+            }
+            if bodies.is_empty() {
+                // Condition is always false: the rule never applies.
+                continue;
+            }
+            let _ = writeln!(out, "// rule {} reason {}", index, rule.reason.value());
+            let keyword = if pass == Effect::Deny {
+                "deny"
+            } else {
+                "allow"
+            };
+            let rendered: Vec<String> = bodies.iter().map(|b| render_body(rule, b)).collect();
+            let _ = writeln!(out, "{} if {};", keyword, rendered.join(" or "));
+        }
+    }
+    out.push_str("deny if true;\n");
+    Ok(out)
+}
+
+fn check_supported(index: usize, rule: &Rule<'_>) -> Result<(), ExportError> {
+    let feature = if rule.break_glass.is_some() {
+        "break-glass"
+    } else if rule.schedule.is_some() {
+        "a schedule"
+    } else {
+        match rule.effect {
+            Effect::Allow | Effect::Deny => return Ok(()),
+            Effect::Challenge(_) => "a Challenge effect",
+            Effect::Indeterminate => "an Indeterminate effect",
+        }
+    };
+    Err(ExportError::Unsupported {
+        rule: index,
+        feature,
+    })
+}
+
+/// Convert to DNF, or name the feature that has no Datalog form.
+///
+/// Recursion is bounded by the policy's validated condition depth.
+fn dnf<'a>(cond: &'a Condition<'a>) -> Result<Dnf<'a>, &'static str> {
+    Ok(match cond {
+        Condition::True => vec![Vec::new()],
+        Condition::False => Vec::new(),
+        Condition::Equals { attr, value } => vec![vec![Literal::Equals(attr, value)]],
+        Condition::In { attr, values } => values
+            .iter()
+            .map(|value| vec![Literal::Equals(attr, value)])
+            .collect(),
+        Condition::MemberOf(group) => vec![vec![Literal::MemberOf(group)]],
+        Condition::AttrIsPrincipal(attr) => vec![vec![Literal::IsPrincipal(attr)]],
+        Condition::Or(a, b) => {
+            let mut left = dnf(a)?;
+            left.extend(dnf(b)?);
+            left
+        }
+        Condition::And(a, b) => product(&dnf(a)?, &dnf(b)?),
+        Condition::AnyOf(children) => {
+            let mut alternatives = Vec::new();
+            for child in children.iter() {
+                alternatives.extend(dnf(child)?);
+            }
+            alternatives
+        }
+        Condition::AllOf(children) => {
+            let mut alternatives = vec![Vec::new()];
+            for child in children.iter() {
+                alternatives = product(&alternatives, &dnf(child)?);
+            }
+            alternatives
+        }
+        Condition::NotEquals { .. }
+        | Condition::NotIn { .. }
+        | Condition::Not(_)
+        | Condition::Implies(..)
+        | Condition::Xor(..) => return Err("negation"),
+        Condition::GreaterThan { .. }
+        | Condition::GreaterOrEqual { .. }
+        | Condition::LessThan { .. }
+        | Condition::LessOrEqual { .. }
+        | Condition::Between { .. } => return Err("an ordered comparison"),
+        Condition::HasFlags { .. } => return Err("a bit flag test"),
+        Condition::IpInCidr { .. } => return Err("a CIDR match"),
+        Condition::AttrContains { .. } => return Err("list membership"),
+        Condition::Matches { .. } | Condition::GlobMatches { .. } => return Err("a pattern match"),
+        Condition::EqualsIgnoreCase { .. } => return Err("a case-insensitive comparison"),
+        Condition::TimeOfDayBetween { .. } | Condition::DayOfWeekIn { .. } => {
+            return Err("a time condition")
+        }
+        Condition::Custom { .. } => return Err("a custom operator"),
+        Condition::Require(..) => return Err("a required attribute"),
+        Condition::Ref(_) => return Err("an unresolved condition reference"),
+    })
+}
+
+/// Every conjunction of one alternative from each side, stopping once
+/// there are more than `MAX_ALTERNATIVES`.
+fn product<'a>(left: &Dnf<'a>, right: &Dnf<'a>) -> Dnf<'a> {
+    let mut product = Vec::with_capacity(left.len() * right.len());
+    for l in left {
+        for r in right {
+            product.push(l.iter().chain(r).copied().collect());
+            if product.len() > MAX_ALTERNATIVES {
+                return product;
+            }
+        }
+    }
+    product
+}
+
+fn render_body(rule: &Rule<'_>, literals: &[Literal<'_>]) -> String {
+    let mut atoms = Vec::new();
+    render_matcher(&mut atoms, "principal", "$p", &rule.target.principal);
+    render_matcher(&mut atoms, "operation", "$a", &rule.target.action);
+    render_matcher(&mut atoms, "resource", "$r", &rule.target.resource);
+    render_matcher(&mut atoms, "environment", "$e", &rule.target.environment);
+    for literal in literals {
+        atoms.push(match literal {
+            Literal::Equals(attr, value) => {
+                format!("context({}, {})", quote(attr), render_value(value))
+            }
+            Literal::MemberOf(group) => format!("member_of({})", quote(group)),
+            Literal::IsPrincipal(attr) => format!("principal($p), context({}, $p)", quote(attr)),
+        });
+    }
+    if atoms.is_empty() {
+        "true".to_string()
+    } else {
+        atoms.join(", ")
+    }
+}
+
+fn render_matcher(atoms: &mut Vec<String>, fact: &str, var: &str, matcher: &Matcher<'_>) {
+    match matcher {
+        Matcher::Any => {}
+        Matcher::Exact(s) => atoms.push(format!("{}({})", fact, quote(s))),
+        Matcher::OneOf(options) => {
+            let set: Vec<String> = options.iter().map(|o| quote(o)).collect();
+            atoms.push(format!("{}({})", fact, var));
+            atoms.push(format!("[{}].contains({})", set.join(", "), var));
+        }
+    }
+}
+
+fn render_value(value: &Value<'_>) -> String {
+    match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::String(s) => quote(s),
+        Value::Secret(_) => quote("<redacted>"),
+        Value::Ip(ip) => quote(&ip.to_string()),
+        Value::List(list) => {
+            let values: Vec<String> = list.iter().map(|v| render_value(&v)).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Map(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", quote(k), render_value(&v)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::{Days, Schedule, TimeWindow};
+    use crate::target::Target;
+    use crate::types::ReasonCode;
+
+    #[test]
+    fn test_export() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::OneOf(&["read", "list"]),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                Some(Condition::And(
+                    Box::new(Condition::Or(
+                        Box::new(Condition::Equals {
+                            attr: "role",
+                            value: Value::String("admin"),
+                        }),
+                        Box::new(Condition::MemberOf("eng")),
+                    )),
+                    Box::new(Condition::Equals {
+                        attr: "mfa",
+                        value: Value::Bool(true),
+                    }),
+                )),
+                ReasonCode(1),
+            ))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Exact("mal\"lory"),
+                    action: Matcher::Any,
+                    resource: Matcher::Any,
+                    environment: Matcher::Exact("prod"),
+                },
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+
+        let text = to_biscuit_datalog(&policy).unwrap();
+        let lines: Vec<&str> = text.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            [
+                "// rule 1 reason 2",
+                r#"deny if principal("mal\"lory"), environment("prod");"#,
+                "// rule 0 reason 1",
+                concat!(
+                    r#"allow if operation($a), ["read", "list"].contains($a), "#,
+                    r#"context("role", "admin"), context("mfa", true) or "#,
+                    r#"operation($a), ["read", "list"].contains($a), "#,
+                    r#"member_of("eng"), context("mfa", true);"#
+                ),
+                "deny if true;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_fragment() {
+        let negated = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::NotEquals {
+                    attr: "role",
+                    value: Value::String("guest"),
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(
+            to_biscuit_datalog(&negated),
+            Err(ExportError::Unsupported {
+                rule: 0,
+                feature: "negation"
+            })
+        );
+
+        let windows = [TimeWindow::all_day()];
+        let scheduled = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .rule(
+                Rule::deny(Target::any(), ReasonCode(2))
+                    .with_schedule(Schedule::new(Days::WEEKEND, &windows)),
+            )
+            .build()
+            .unwrap();
+        assert!(matches!(
+            to_biscuit_datalog(&scheduled),
+            Err(ExportError::Unsupported { rule: 1, .. })
+        ));
+    }
+}
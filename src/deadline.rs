@@ -0,0 +1,166 @@
+//! Per-request evaluation deadlines.
+//!
+//! Structural limits bound the worst case of any policy; a `Deadline`
+//! bounds one request. It counts steps (one per rule checked and one per
+//! condition node visited) and can also poll a caller-supplied check, such
+//! as a comparison against a wall-clock instant. Evaluation that runs out
+//! fails with `PolicyError::DeadlineExceeded`, which `indeterminate_on_error`
+//! does not absorb: the whole request is abandoned, not just one rule.
+
+use crate::observe::EvalObserver;
+
+/// Steps between polls of the caller's check.
+const CHECK_INTERVAL: usize = 16;
+
+/// A step budget and optional expiry check for one evaluation.
+///
+/// ```
+/// use gate0::{Deadline, Policy, PolicyError, Request, Rule, Target, ReasonCode};
+/// use std::time::{Duration, Instant};
+///
+/// let policy = Policy::builder()
+///     .rule(Rule::allow(Target::any(), ReasonCode(1)))
+///     .build()
+///     .unwrap();
+///
+/// let stop = Instant::now() + Duration::from_millis(5);
+/// let expired = || Instant::now() >= stop;
+/// let mut deadline = Deadline::steps(10_000).with_check(&expired);
+/// let decision = policy.evaluate_with_deadline(&Request::new("a", "b", "c"), &mut deadline);
+/// assert!(decision.is_ok());
+///
+/// let mut deadline = Deadline::steps(0);
+/// let result = policy.evaluate_with_deadline(&Request::new("a", "b", "c"), &mut deadline);
+/// assert_eq!(result, Err(PolicyError::DeadlineExceeded));
+/// ```
+pub struct Deadline<'d> {
+    max_steps: usize,
+    used: usize,
+    check: Option<&'d dyn Fn() -> bool>,
+}
+
+impl<'d> Deadline<'d> {
+    /// Allow at most `max_steps` steps.
+    pub fn steps(max_steps: usize) -> Self {
+        Deadline {
+            max_steps,
+            used: 0,
+            check: None,
+        }
+    }
+
+    /// No step limit; only `expired` ends the evaluation.
+    pub fn check(expired: &'d dyn Fn() -> bool) -> Self {
+        Self::steps(usize::MAX).with_check(expired)
+    }
+
+    /// Also stop once `expired` returns true. It is polled on the first
+    /// step and every 16 steps after, so it should be cheap.
+    pub fn with_check(mut self, expired: &'d dyn Fn() -> bool) -> Self {
+        self.check = Some(expired);
+        self
+    }
+
+    /// Steps taken so far.
+    pub fn steps_used(&self) -> usize {
+        self.used
+    }
+}
+
+impl std::fmt::Debug for Deadline<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deadline")
+            .field("max_steps", &self.max_steps)
+            .field("used", &self.used)
+            .field("check", &self.check.is_some())
+            .finish()
+    }
+}
+
+impl EvalObserver for Deadline<'_> {
+    fn expired(&mut self) -> bool {
+        if self.used >= self.max_steps {
+            return true;
+        }
+        let poll = self.used.is_multiple_of(CHECK_INTERVAL);
+        self.used += 1;
+        poll && self.check.is_some_and(|expired| expired())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::error::PolicyError;
+    use crate::policy::{Policy, PolicyConfig, Rule};
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode, Request};
+    use std::cell::Cell;
+
+    fn wide_policy() -> Policy<'static> {
+        // Three rules, each with a five-node condition that evaluates fully.
+        let cond = || {
+            Condition::Or(
+                Box::new(Condition::And(
+                    Box::new(Condition::True),
+                    Box::new(Condition::False),
+                )),
+                Box::new(Condition::False),
+            )
+        };
+        let rules = (0..3)
+            .map(|i| Rule::new(Effect::Allow, Target::any(), Some(cond()), ReasonCode(i)))
+            .collect();
+        let config = PolicyConfig {
+            indeterminate_on_error: true,
+            ..PolicyConfig::default()
+        };
+        Policy::with_config(rules, config).unwrap()
+    }
+
+    #[test]
+    fn test_step_budget() {
+        let policy = wide_policy();
+        let request = Request::new("alice", "read", "doc");
+
+        let mut deadline = Deadline::steps(1000);
+        assert!(policy
+            .evaluate_with_deadline(&request, &mut deadline)
+            .unwrap()
+            .is_deny());
+        let needed = deadline.steps_used();
+        assert_eq!(needed, 3 * (1 + 5));
+
+        let mut deadline = Deadline::steps(needed);
+        assert!(policy
+            .evaluate_with_deadline(&request, &mut deadline)
+            .is_ok());
+
+        // Not absorbed by indeterminate_on_error.
+        let mut deadline = Deadline::steps(needed - 1);
+        assert_eq!(
+            policy.evaluate_with_deadline(&request, &mut deadline),
+            Err(PolicyError::DeadlineExceeded)
+        );
+    }
+
+    #[test]
+    fn test_check_is_polled() {
+        let policy = wide_policy();
+        let request = Request::new("alice", "read", "doc");
+        let polls = Cell::new(0);
+        let expired = || {
+            polls.set(polls.get() + 1);
+            polls.get() > 1
+        };
+
+        let mut deadline = Deadline::check(&expired);
+        assert_eq!(
+            policy.evaluate_with_deadline(&request, &mut deadline),
+            Err(PolicyError::DeadlineExceeded)
+        );
+        assert_eq!(polls.get(), 2);
+        assert_eq!(deadline.steps_used(), CHECK_INTERVAL + 1);
+    }
+}
@@ -0,0 +1,358 @@
+//! Named, reusable conditions.
+//!
+//! Policies repeat the same checks (MFA, network location) across many
+//! rules, and the copies drift apart when edited. A condition defined once
+//! with `PolicyBuilder::define_condition` can be used from any rule as
+//! `Condition::Ref(name)`. `build` replaces every reference with its
+//! definition, so the built policy evaluates, fingerprints, and exports
+//! exactly as if each use had been written out.
+//!
+//! A definition may refer to earlier definitions only, which rules out
+//! cycles. Each one is held to the same depth, node, and string limits as
+//! a rule condition, and so is every condition after expansion.
+
+use crate::condition::Condition;
+use crate::error::PolicyError;
+use crate::partial::balanced;
+use crate::policy::{validate_str, PolicyConfig, Rule};
+
+/// Resolved definitions, in definition order.
+#[derive(Debug, Default)]
+pub(crate) struct Definitions<'a> {
+    /// Name, expanded condition, and its node count.
+    items: Vec<(&'a str, Condition<'a>, usize)>,
+}
+
+impl<'a> Definitions<'a> {
+    /// Resolve `definitions` in order against `config`'s limits.
+    pub(crate) fn resolve(
+        definitions: Vec<(&'a str, Condition<'a>)>,
+        config: &PolicyConfig,
+    ) -> Result<Self, PolicyError> {
+        let mut resolved = Definitions::default();
+        for (name, condition) in definitions {
+            validate_str(name, config.max_string_len)?;
+            if resolved.get(name).is_some() {
+                return Err(PolicyError::InvalidConditionRef {
+                    name: name.to_string(),
+                });
+            }
+            let condition = resolved.expand(condition, config)?;
+            condition.validate(config.max_condition_depth, config.max_string_len)?;
+            let nodes = condition.node_count();
+            resolved.items.push((name, condition, nodes));
+        }
+        Ok(resolved)
+    }
+
+    fn get(&self, name: &str) -> Option<&(&'a str, Condition<'a>, usize)> {
+        self.items.iter().find(|(n, ..)| *n == name)
+    }
+
+    /// Replace every reference in `rule`'s condition.
+    pub(crate) fn expand_rule(
+        &self,
+        rule: &mut Rule<'a>,
+        config: &PolicyConfig,
+    ) -> Result<(), PolicyError> {
+        if let Some(condition) = rule.condition.take() {
+            rule.condition = Some(self.expand(condition, config)?);
+        }
+        Ok(())
+    }
+
+    /// Replace every `Ref` in `condition` with its definition.
+    ///
+    /// Fails without copying anything if the result would have more than
+    /// `max_condition_nodes` nodes. An `AllOf`/`AnyOf` with a reference
+    /// among its children cannot borrow a new slice, so it is rebuilt as a
+    /// balanced `And`/`Or` tree, one level deeper per doubling of its width.
+    fn expand(
+        &self,
+        condition: Condition<'a>,
+        config: &PolicyConfig,
+    ) -> Result<Condition<'a>, PolicyError> {
+        let nodes = condition.postorder();
+        let mut total = 0usize;
+        let mut refs = 0usize;
+        for node in &nodes {
+            total = total.saturating_add(match node {
+                Condition::Ref(name) => {
+                    refs += 1;
+                    match self.get(name) {
+                        Some((.., count)) => *count,
+                        None => {
+                            return Err(PolicyError::InvalidConditionRef {
+                                name: name.to_string(),
+                            })
+                        }
+                    }
+                }
+                _ => 1,
+            });
+        }
+        if refs == 0 {
+            drop(nodes);
+            return Ok(condition);
+        }
+        if total > config.max_condition_nodes {
+            return Err(PolicyError::TooManyConditionNodes {
+                max: config.max_condition_nodes,
+                actual: total,
+            });
+        }
+
+        // Each result carries whether it differs from the original node.
+        let mut results: Vec<(Condition<'a>, bool)> = Vec::with_capacity(16);
+        for node in nodes {
+            let result = match node {
+                Condition::Ref(name) => {
+                    // Definitions are depth-limited, so the derived clone's
+                    // recursion is bounded.
+                    let definition = self.get(name).map(|(_, c, _)| c.clone());
+                    (definition.ok_or(PolicyError::InternalError)?, true)
+                }
+                Condition::Not(_) => {
+                    let (inner, changed) = pop(&mut results)?;
+                    (Condition::Not(Box::new(inner)), changed)
+                }
+                Condition::Require(attr, _) => {
+                    let (inner, changed) = pop(&mut results)?;
+                    (Condition::Require(attr, Box::new(inner)), changed)
+                }
+                Condition::And(..)
+                | Condition::Or(..)
+                | Condition::Implies(..)
+                | Condition::Xor(..) => {
+                    let (b, b_changed) = pop(&mut results)?;
+                    let (a, a_changed) = pop(&mut results)?;
+                    let (a, b) = (Box::new(a), Box::new(b));
+                    let joined = match node {
+                        Condition::And(..) => Condition::And(a, b),
+                        Condition::Or(..) => Condition::Or(a, b),
+                        Condition::Implies(..) => Condition::Implies(a, b),
+                        _ => Condition::Xor(a, b),
+                    };
+                    (joined, a_changed || b_changed)
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    let start = results
+                        .len()
+                        .checked_sub(children.len())
+                        .ok_or(PolicyError::InternalError)?;
+                    let items: Vec<_> = results.drain(start..).collect();
+                    if !items.iter().any(|(_, changed)| *changed) {
+                        (node.clone_shallow(), false)
+                    } else if matches!(node, Condition::AllOf(_)) {
+                        let items = items.into_iter().map(|(c, _)| c).collect();
+                        let joined =
+                            balanced(items, |a, b| Condition::And(Box::new(a), Box::new(b)));
+                        (joined.unwrap_or(Condition::True), true)
+                    } else {
+                        let items = items.into_iter().map(|(c, _)| c).collect();
+                        let joined =
+                            balanced(items, |a, b| Condition::Or(Box::new(a), Box::new(b)));
+                        (joined.unwrap_or(Condition::False), true)
+                    }
+                }
+                leaf => (leaf.clone_shallow(), false),
+            };
+            results.push(result);
+        }
+        Ok(pop(&mut results)?.0)
+    }
+}
+
+fn pop<'a>(results: &mut Vec<(Condition<'a>, bool)>) -> Result<(Condition<'a>, bool), PolicyError> {
+    // The postorder walk always leaves a node's operands on the stack.
+    results.pop().ok_or(PolicyError::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode, Request};
+    use crate::value::Value;
+
+    fn mfa() -> Condition<'static> {
+        Condition::Equals {
+            attr: "mfa",
+            value: Value::Bool(true),
+        }
+    }
+
+    fn boxed(c: Condition<'_>) -> Box<Condition<'_>> {
+        Box::new(c)
+    }
+
+    fn allow_if(condition: Condition<'_>, reason: ReasonCode) -> Rule<'_> {
+        Rule::new(Effect::Allow, Target::any(), Some(condition), reason)
+    }
+
+    fn deny_if(condition: Condition<'_>, reason: ReasonCode) -> Rule<'_> {
+        Rule::new(Effect::Deny, Target::any(), Some(condition), reason)
+    }
+
+    #[test]
+    fn test_refs_are_expanded() {
+        let office = [Value::String("hq"), Value::String("lab")];
+        let trusted = Condition::And(
+            boxed(Condition::Ref("mfa")),
+            boxed(Condition::In {
+                attr: "site",
+                values: &office,
+            }),
+        );
+        let policy = Policy::builder()
+            .define_condition("mfa", mfa())
+            .define_condition("trusted", trusted)
+            .rule(allow_if(Condition::Ref("trusted"), ReasonCode(1)))
+            .rule(deny_if(
+                Condition::Not(boxed(Condition::Ref("mfa"))),
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+
+        let expected = Condition::And(
+            boxed(mfa()),
+            boxed(Condition::In {
+                attr: "site",
+                values: &office,
+            }),
+        );
+        assert_eq!(policy.rules()[0].condition, Some(expected));
+        assert_eq!(
+            policy.rules()[1].condition,
+            Some(Condition::Not(boxed(mfa())))
+        );
+
+        let ctx = [("mfa", Value::Bool(true)), ("site", Value::String("hq"))];
+        let decision = policy
+            .evaluate(&Request::with_context("alice", "read", "doc", &ctx))
+            .unwrap();
+        assert_eq!(decision.reason, ReasonCode(1));
+        let decision = policy
+            .evaluate(&Request::new("alice", "read", "doc"))
+            .unwrap();
+        assert_eq!(decision.reason, ReasonCode(2));
+    }
+
+    #[test]
+    fn test_all_of_with_refs_is_rebuilt() {
+        let children = [Condition::Ref("mfa"), Condition::MemberOf("staff")];
+        let policy = Policy::builder()
+            .define_condition("mfa", mfa())
+            .rule(allow_if(Condition::AllOf(&children), ReasonCode(1)))
+            .build()
+            .unwrap();
+        assert_eq!(
+            policy.rules()[0].condition,
+            Some(Condition::And(
+                boxed(mfa()),
+                boxed(Condition::MemberOf("staff"))
+            ))
+        );
+
+        // Slices without references are kept as they are
+        let plain = [mfa(), Condition::MemberOf("staff")];
+        let policy = Policy::builder()
+            .define_condition("mfa", mfa())
+            .rule(allow_if(
+                Condition::Or(
+                    boxed(Condition::AnyOf(&plain)),
+                    boxed(Condition::Ref("mfa")),
+                ),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(
+            policy.rules()[0].condition,
+            Some(Condition::Or(boxed(Condition::AnyOf(&plain)), boxed(mfa())))
+        );
+    }
+
+    #[test]
+    fn test_invalid_refs_rejected() {
+        let invalid = |name: &str| {
+            Err(PolicyError::InvalidConditionRef {
+                name: name.to_string(),
+            })
+        };
+        // Unknown
+        let result = Policy::builder()
+            .rule(allow_if(Condition::Ref("nope"), ReasonCode(1)))
+            .build();
+        assert_eq!(result.map(|_| ()), invalid("nope"));
+        // Later definitions, including itself, are not visible
+        let result = Policy::builder()
+            .define_condition("a", Condition::Ref("b"))
+            .define_condition("b", mfa())
+            .build();
+        assert_eq!(result.map(|_| ()), invalid("b"));
+        let result = Policy::builder()
+            .define_condition("a", Condition::Not(boxed(Condition::Ref("a"))))
+            .build();
+        assert_eq!(result.map(|_| ()), invalid("a"));
+        // Duplicate
+        let result = Policy::builder()
+            .define_condition("a", mfa())
+            .define_condition("a", Condition::True)
+            .build();
+        assert_eq!(result.map(|_| ()), invalid("a"));
+        // Bypassing the builder leaves references unresolved
+        let rules = vec![allow_if(Condition::Ref("mfa"), ReasonCode(1))];
+        assert_eq!(Policy::new(rules).map(|_| ()), invalid("mfa"));
+        assert_eq!(
+            Condition::Ref("mfa").evaluate(&[]).map(|_| ()),
+            invalid("mfa")
+        );
+    }
+
+    #[test]
+    fn test_expansion_is_bounded() {
+        // Each level doubles the size of the one before
+        let config = PolicyConfig {
+            max_condition_nodes: 100,
+            ..PolicyConfig::default()
+        };
+        let names = ["l0", "l1", "l2", "l3", "l4", "l5", "l6"];
+        let mut builder = Policy::builder()
+            .config(config)
+            .define_condition(names[0], mfa());
+        for pair in names.windows(2) {
+            builder = builder.define_condition(
+                pair[1],
+                Condition::And(
+                    boxed(Condition::Ref(pair[0])),
+                    boxed(Condition::Ref(pair[0])),
+                ),
+            );
+        }
+        assert!(matches!(
+            builder.build(),
+            Err(PolicyError::TooManyConditionNodes {
+                max: 100,
+                actual: 127
+            })
+        ));
+
+        // Definitions are held to the depth limit after expansion
+        let config = PolicyConfig {
+            max_condition_depth: 2,
+            ..PolicyConfig::default()
+        };
+        let result = Policy::builder()
+            .config(config)
+            .define_condition("mfa", Condition::Not(boxed(mfa())))
+            .define_condition("outer", Condition::Not(boxed(Condition::Ref("mfa"))))
+            .build();
+        assert!(matches!(
+            result,
+            Err(PolicyError::ConditionTooDeep { max: 2, actual: 3 })
+        ));
+    }
+}
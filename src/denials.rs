@@ -0,0 +1,158 @@
+//! Approximate per-principal denial counts.
+//!
+//! `DenialTracker` answers "has this principal been denied more than K
+//! times in the current window?" for any number of principals in fixed
+//! memory, to drive lockouts or alerts. It is a count-min sketch: each
+//! principal hashes to one counter in each of `DENIAL_SKETCH_DEPTH` rows,
+//! and its estimate is the smallest of those counters. Collisions can only
+//! add to a counter, so an estimate may be too high but is never too low.
+//! A wider sketch makes collisions rarer.
+//!
+//! Windows are aligned to multiples of `window_seconds` on the caller's
+//! clock; every counter resets when a new window starts. Nothing allocates
+//! after construction.
+//!
+//! Unlike `AnomalyState`, which tracks exact counts for a bounded number
+//! of principals, the tracker never forgets a principal under load.
+
+use crate::fingerprint::Fnv64;
+use crate::types::Decision;
+
+/// Number of hash rows in a `DenialTracker`.
+pub const DENIAL_SKETCH_DEPTH: usize = 4;
+
+/// A fixed-size sketch of denials per principal.
+#[derive(Debug, Clone)]
+pub struct DenialTracker {
+    width: usize,
+    window_seconds: u64,
+    /// Index of the window the counters belong to.
+    window: u64,
+    /// `DENIAL_SKETCH_DEPTH` rows of `width` counters.
+    counters: Vec<u32>,
+}
+
+impl DenialTracker {
+    /// A tracker with `width` counters per row and windows of
+    /// `window_seconds` (both at least 1).
+    pub fn new(width: usize, window_seconds: u64) -> Self {
+        let width = width.max(1);
+        DenialTracker {
+            width,
+            window_seconds: window_seconds.max(1),
+            window: 0,
+            counters: vec![0; width.saturating_mul(DENIAL_SKETCH_DEPTH)],
+        }
+    }
+
+    /// Count `decision` for `principal` at `now` (seconds) if it is a Deny.
+    pub fn record(&mut self, principal: &str, decision: &Decision, now: u64) {
+        if decision.is_deny() {
+            self.record_denial(principal, now);
+        }
+    }
+
+    /// Count one denial for `principal` at `now` (seconds).
+    pub fn record_denial(&mut self, principal: &str, now: u64) {
+        let window = now / self.window_seconds;
+        if window != self.window {
+            self.counters.fill(0);
+            self.window = window;
+        }
+        for row in 0..DENIAL_SKETCH_DEPTH {
+            let slot = self.slot(row, principal);
+            self.counters[slot] = self.counters[slot].saturating_add(1);
+        }
+    }
+
+    /// Estimated denials for `principal` in the window containing `now`.
+    ///
+    /// Never less than the true count; may be more after collisions.
+    pub fn denials(&self, principal: &str, now: u64) -> u32 {
+        if now / self.window_seconds != self.window {
+            return 0;
+        }
+        (0..DENIAL_SKETCH_DEPTH)
+            .map(|row| self.counters[self.slot(row, principal)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Whether `principal` has been denied more than `limit` times in the
+    /// window containing `now`.
+    pub fn exceeds(&self, principal: &str, now: u64, limit: u32) -> bool {
+        self.denials(principal, now) > limit
+    }
+
+    /// Forget every denial.
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+    }
+
+    fn slot(&self, row: usize, principal: &str) -> usize {
+        let mut h = Fnv64::new();
+        h.write_u8(row as u8);
+        h.write_str(principal);
+        row * self.width + (h.finish() % self.width as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ReasonCode;
+
+    #[test]
+    fn test_counts_denials_per_window() {
+        let mut tracker = DenialTracker::new(64, 60);
+        let deny = Decision::deny(ReasonCode(1));
+        let allow = Decision::allow(ReasonCode(2));
+
+        for t in 0..5 {
+            tracker.record("alice", &deny, 120 + t);
+        }
+        tracker.record("alice", &allow, 125);
+        tracker.record("bob", &deny, 125);
+
+        assert_eq!(tracker.denials("alice", 130), 5);
+        assert!(tracker.exceeds("alice", 130, 4));
+        assert!(!tracker.exceeds("alice", 130, 5));
+        assert_eq!(tracker.denials("bob", 130), 1);
+        assert_eq!(tracker.denials("carol", 130), 0);
+
+        // The next window starts from zero
+        assert_eq!(tracker.denials("alice", 180), 0);
+        tracker.record_denial("bob", 185);
+        assert_eq!(tracker.denials("bob", 185), 1);
+        assert_eq!(tracker.denials("alice", 185), 0);
+
+        tracker.clear();
+        assert_eq!(tracker.denials("bob", 185), 0);
+    }
+
+    #[test]
+    fn test_never_underestimates() {
+        // A single counter per row: every principal collides
+        let mut tracker = DenialTracker::new(1, 60);
+        let names: Vec<String> = (0..50).map(|i| format!("user{}", i)).collect();
+        for (i, name) in names.iter().enumerate() {
+            for _ in 0..=i % 3 {
+                tracker.record_denial(name, 0);
+            }
+        }
+        for (i, name) in names.iter().enumerate() {
+            assert!(tracker.denials(name, 0) >= (i % 3 + 1) as u32);
+        }
+
+        // A wide sketch keeps them apart
+        let mut tracker = DenialTracker::new(4096, 60);
+        for (i, name) in names.iter().enumerate() {
+            for _ in 0..=i % 3 {
+                tracker.record_denial(name, 0);
+            }
+        }
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(tracker.denials(name, 0), (i % 3 + 1) as u32);
+        }
+    }
+}
@@ -0,0 +1,1146 @@
+//! Policies embedded in the binary.
+//!
+//! `Policy::to_embedded` encodes a built policy in a compact binary form.
+//! A build step writes it to a file, the program includes it with
+//! `include_bytes!`, and `Policy::from_embedded` decodes and validates it
+//! on first use. The built policy is cached for the life of the process,
+//! so later calls cost a lookup:
+//!
+//! ```
+//! use gate0::{Policy, ReasonCode, Request, Rule, Target};
+//!
+//! # let policy = Policy::new(vec![Rule::allow(Target::any(), ReasonCode(1))]).unwrap();
+//! # let bytes: &'static [u8] = Box::leak(policy.to_embedded().into_boxed_slice());
+//! // static DEFAULT_POLICY: &[u8] = include_bytes!("default.g0p");
+//! let policy = Policy::from_embedded(bytes).unwrap();
+//! assert!(policy.evaluate(&Request::new("alice", "read", "doc")).unwrap().is_allow());
+//! ```
+//!
+//! Names and values are borrowed from the embedded bytes. Tables such as
+//! matcher option lists and value sets are allocated when the policy is
+//! decoded and, like the cached policy, never freed. Operators are code,
+//! so a policy with `Condition::Custom` nodes encodes but fails to load
+//! with `InvalidCustomOp`.
+//!
+//! # Format (version 2)
+//!
+//! ```text
+//! policy:    b"G0PL" u8(version) config metadata
+//!            u32(len) { str(stronger) str(weaker) }*
+//!            u32(len) { str(attr) u8(0 Pii | 1 Secret) }* u32(len) rule*
+//! config:    u64(max_rules) u64(max_condition_depth) u64(max_condition_nodes)
+//!            u64(max_context_attrs) u64(max_matcher_options)
+//!            u64(max_string_len) u64(max_set_values) u64(max_group_lookups)
+//!            u64(max_custom_op_cost) u8(indeterminate_on_error)
+//!            u8(deny_aggregation) u8(short_circuit) u8(strict_types)
+//!            names(known_actions) names(known_resources)
+//!            u8(reject_unknown_names) u8(collation) u64(max_obligations)
+//!            u64(max_condition_evals) u8(three_valued_logic)
+//!            u64(max_path_depth)
+//! metadata:  opt(name) opt(version) opt(author) opt(description)
+//!            (u8(0) | u8(1) i64(created_at))
+//! rule:      effect u32(reason) target (u8(0) | u8(1) condition)
+//!            (u8(0) | u8(1) u32(cache_ttl)) schedule opt(break_glass)
+//!            u32(priority) u8(on_provider_failure)
+//!            (u8(0) | u8(1) u32(approvers) u32(expires_in)) list(tags)
+//!            u8(len) obligation*
+//! obligation: u8(0) Audit | u8(1) u32 Custom | u8(2) u32(approvers) u32(expires_in) Approval
+//! effect:    u8(0) Allow | u8(1) Deny | u8(2) method Challenge | u8(3) Indeterminate
+//! method:    u8(0) Mfa | u8(1) Reauthenticate | u8(2) u32 Custom
+//! target:    matcher(principal) matcher(action) matcher(resource) matcher(environment)
+//! matcher:   u8(0) Any | u8(1) str Exact | u8(2) list OneOf
+//! schedule:  u8(0) | u8(1) u8(days) u32(len) { u16(start) u16(end) }*
+//!            i32(utc_offset_minutes) str(clock_attr)
+//! condition: u8(tag) fields, tags in `Condition` declaration order
+//! value:     u8(0) u8(bool) | u8(1) i64 | u8(2) str | u8(3) bytes | u8(4) ip
+//!            | u8(5) u32(len) value* | u8(6) u32(len) { str value }*
+//!            (elements and entries are never lists or maps)
+//! ip:        u8(4) [u8; 4] | u8(6) [u8; 16]
+//! str:       u32(len) bytes (UTF-8)
+//! opt:       u8(0) | u8(1) str
+//! list:      u32(len) str*
+//! names:     u8(0) | u8(1) list
+//! ```
+//!
+//! All integers are little-endian. Unlike replay logs, secrets in
+//! conditions are written: they are part of the policy. Version 1 wrote
+//! `u16` lengths, which truncated option lists, value sets, and known
+//! names past `u16::MAX` entries.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use crate::cidr::Cidr;
+use crate::collation::Collation;
+use crate::condition::{Condition, ABSOLUTE_MAX_CONDITION_DEPTH};
+use crate::error::PolicyError;
+use crate::hierarchy::ActionHierarchy;
+use crate::metadata::PolicyMetadata;
+use crate::obligation::{Approval, Obligation};
+use crate::pattern::Pattern;
+use crate::policy::{DenyAggregation, Policy, PolicyConfig, ProviderFailure, Rule};
+use crate::redact::Sensitivity;
+use crate::schedule::{Days, Schedule, TimeWindow};
+use crate::target::{Matcher, Target};
+use crate::types::{ChallengeMethod, Effect, ReasonCode};
+use crate::value::Value;
+
+const MAGIC: &[u8; 4] = b"G0PL";
+const VERSION: u8 = 2;
+
+type Loaded = Result<Policy<'static>, PolicyError>;
+
+/// The address and length of an embedded policy's bytes.
+type Key = (usize, usize);
+
+/// One cell per embedded policy. Each cell is leaked so it can hand out
+/// `'static` references.
+static EMBEDDED: Mutex<Vec<(Key, &'static OnceLock<Loaded>)>> = Mutex::new(Vec::new());
+
+impl Policy<'static> {
+    /// The policy encoded in `bytes` by `to_embedded`, typically included
+    /// with `include_bytes!`.
+    ///
+    /// The first call decodes and validates the policy; later calls with
+    /// the same bytes return the cached result, including a cached error.
+    /// Fails with `MalformedEmbeddedPolicy` if the bytes are not an
+    /// encoded policy, or with whatever error building the decoded policy
+    /// returns.
+    pub fn from_embedded(bytes: &'static [u8]) -> Result<&'static Policy<'static>, PolicyError> {
+        let key = (bytes.as_ptr() as usize, bytes.len());
+        let cell = {
+            let mut cells = EMBEDDED.lock().unwrap_or_else(PoisonError::into_inner);
+            match cells.iter().find(|(k, _)| *k == key) {
+                Some((_, cell)) => *cell,
+                None => {
+                    let cell: &'static OnceLock<Loaded> = Box::leak(Box::default());
+                    cells.push((key, cell));
+                    cell
+                }
+            }
+        };
+        cell.get_or_init(|| decode(bytes))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+}
+
+impl Policy<'_> {
+    /// Encode this policy for `Policy::from_embedded`; see the module docs.
+    pub fn to_embedded(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(256);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        put_config(&mut out, self.config());
+
+        let metadata = self.metadata();
+        for field in [
+            metadata.name,
+            metadata.version,
+            metadata.author,
+            metadata.description,
+        ] {
+            put_opt(&mut out, field);
+        }
+        match metadata.created_at {
+            None => out.push(0),
+            Some(t) => {
+                out.push(1);
+                out.extend_from_slice(&t.to_le_bytes());
+            }
+        }
+
+        let implications = self.action_hierarchy().implications();
+        put_len(&mut out, implications.len());
+        for (stronger, weaker) in implications {
+            put_str(&mut out, stronger);
+            put_str(&mut out, weaker);
+        }
+
+        let sensitive = self.sensitive_attributes();
+        put_len(&mut out, sensitive.len());
+        for (attr, sensitivity) in sensitive {
+            put_str(&mut out, attr);
+            out.push(match sensitivity {
+                Sensitivity::Pii => 0,
+                Sensitivity::Secret => 1,
+            });
+        }
+
+        out.extend_from_slice(&(self.rules().len() as u32).to_le_bytes());
+        for rule in self.rules() {
+            put_rule(&mut out, rule);
+        }
+        out
+    }
+}
+
+fn decode(bytes: &'static [u8]) -> Loaded {
+    let mut r = Reader(bytes);
+    if r.take(4)? != MAGIC || r.take(1)? != [VERSION] {
+        return Err(PolicyError::MalformedEmbeddedPolicy);
+    }
+    let config = r.config()?;
+    let metadata = PolicyMetadata {
+        name: r.opt()?,
+        version: r.opt()?,
+        author: r.opt()?,
+        description: r.opt()?,
+        created_at: match r.u8()? {
+            0 => None,
+            1 => Some(i64::from_le_bytes(r.array()?)),
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        },
+    };
+    let mut hierarchy = ActionHierarchy::new();
+    for _ in 0..r.len()? {
+        hierarchy = hierarchy.implies(r.str()?, r.str()?);
+    }
+
+    let mut builder = Policy::builder()
+        .config(config)
+        .metadata(metadata)
+        .action_hierarchy(hierarchy);
+    for _ in 0..r.len()? {
+        let attr = r.str()?;
+        let sensitivity = match r.u8()? {
+            0 => Sensitivity::Pii,
+            1 => Sensitivity::Secret,
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        };
+        builder = builder.sensitive_attribute(attr, sensitivity);
+    }
+    for _ in 0..u32::from_le_bytes(r.array()?) {
+        builder = builder.rule(r.rule()?);
+    }
+    if !r.0.is_empty() {
+        return Err(PolicyError::MalformedEmbeddedPolicy);
+    }
+    builder.build()
+}
+
+fn put_config(out: &mut Vec<u8>, config: &PolicyConfig) {
+    for limit in [
+        config.max_rules,
+        config.max_condition_depth,
+        config.max_condition_nodes,
+        config.max_context_attrs,
+        config.max_matcher_options,
+        config.max_string_len,
+        config.max_set_values,
+        config.max_group_lookups,
+        config.max_custom_op_cost,
+    ] {
+        out.extend_from_slice(&(limit as u64).to_le_bytes());
+    }
+    out.push(config.indeterminate_on_error as u8);
+    out.push(match config.deny_aggregation {
+        DenyAggregation::FirstMatch => 0,
+        DenyAggregation::LowestReason => 1,
+        DenyAggregation::HighestPriority => 2,
+        DenyAggregation::CollectAll => 3,
+    });
+    out.push(config.short_circuit as u8);
+    out.push(config.strict_types as u8);
+    for names in [config.known_actions, config.known_resources] {
+        match names {
+            None => out.push(0),
+            Some(names) => {
+                out.push(1);
+                put_list(out, names);
+            }
+        }
+    }
+    out.push(config.reject_unknown_names as u8);
+    out.push(match config.collation {
+        Collation::Binary => 0,
+        Collation::AsciiCaseInsensitive => 1,
+        #[cfg(feature = "unicode")]
+        Collation::UnicodeCaseFold => 2,
+    });
+    out.extend_from_slice(&(config.max_obligations as u64).to_le_bytes());
+    out.extend_from_slice(&(config.max_condition_evals as u64).to_le_bytes());
+    out.push(config.three_valued_logic as u8);
+    out.extend_from_slice(&(config.max_path_depth as u64).to_le_bytes());
+}
+
+fn put_rule(out: &mut Vec<u8>, rule: &Rule<'_>) {
+    match rule.effect {
+        Effect::Allow => out.push(0),
+        Effect::Deny => out.push(1),
+        Effect::Challenge(method) => {
+            out.push(2);
+            match method {
+                ChallengeMethod::Mfa => out.push(0),
+                ChallengeMethod::Reauthenticate => out.push(1),
+                ChallengeMethod::Custom(code) => {
+                    out.push(2);
+                    out.extend_from_slice(&code.to_le_bytes());
+                }
+            }
+        }
+        Effect::Indeterminate => out.push(3),
+    }
+    out.extend_from_slice(&rule.reason.value().to_le_bytes());
+    for matcher in [
+        &rule.target.principal,
+        &rule.target.action,
+        &rule.target.resource,
+        &rule.target.environment,
+    ] {
+        match matcher {
+            Matcher::Any => out.push(0),
+            Matcher::Exact(name) => {
+                out.push(1);
+                put_str(out, name);
+            }
+            Matcher::OneOf(options) => {
+                out.push(2);
+                put_list(out, options);
+            }
+        }
+    }
+    match &rule.condition {
+        None => out.push(0),
+        Some(condition) => {
+            out.push(1);
+            put_condition(out, condition);
+        }
+    }
+    match rule.cache_ttl {
+        None => out.push(0),
+        Some(ttl) => {
+            out.push(1);
+            out.extend_from_slice(&ttl.to_le_bytes());
+        }
+    }
+    match &rule.schedule {
+        None => out.push(0),
+        Some(schedule) => {
+            out.push(1);
+            out.push(schedule.days.bits());
+            put_len(out, schedule.windows.len());
+            for window in schedule.windows {
+                out.extend_from_slice(&window.start.to_le_bytes());
+                out.extend_from_slice(&window.end.to_le_bytes());
+            }
+            out.extend_from_slice(&schedule.utc_offset_minutes.to_le_bytes());
+            put_str(out, schedule.clock_attr);
+        }
+    }
+    put_opt(out, rule.break_glass);
+    out.extend_from_slice(&rule.priority.to_le_bytes());
+    out.push(match rule.on_provider_failure {
+        None => 0,
+        Some(ProviderFailure::Deny) => 1,
+        Some(ProviderFailure::Skip) => 2,
+        Some(ProviderFailure::Indeterminate) => 3,
+    });
+    match rule.approval {
+        None => out.push(0),
+        Some(approval) => {
+            out.push(1);
+            out.extend_from_slice(&approval.approvers.to_le_bytes());
+            out.extend_from_slice(&approval.expires_in.to_le_bytes());
+        }
+    }
+    put_list(out, rule.tags);
+    out.push(rule.obligations.len() as u8);
+    for obligation in rule.obligations {
+        match obligation {
+            Obligation::Audit => out.push(0),
+            Obligation::Custom(code) => {
+                out.push(1);
+                out.extend_from_slice(&code.to_le_bytes());
+            }
+            Obligation::Approval(approval) => {
+                out.push(2);
+                out.extend_from_slice(&approval.approvers.to_le_bytes());
+                out.extend_from_slice(&approval.expires_in.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Recursion is bounded by the depth limit the policy was built with.
+fn put_condition(out: &mut Vec<u8>, condition: &Condition<'_>) {
+    match condition {
+        Condition::True => out.push(0),
+        Condition::False => out.push(1),
+        Condition::Equals { attr, value } | Condition::NotEquals { attr, value } => {
+            out.push(if matches!(condition, Condition::Equals { .. }) {
+                2
+            } else {
+                3
+            });
+            put_str(out, attr);
+            put_value(out, value);
+        }
+        Condition::EqualsIgnoreCase { attr, value } => {
+            out.push(4);
+            put_str(out, attr);
+            put_str(out, value);
+        }
+        Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+            out.push(if matches!(condition, Condition::In { .. }) {
+                5
+            } else {
+                6
+            });
+            put_str(out, attr);
+            put_len(out, values.len());
+            for value in values.iter() {
+                put_value(out, value);
+            }
+        }
+        Condition::GreaterThan { attr, value }
+        | Condition::GreaterOrEqual { attr, value }
+        | Condition::LessThan { attr, value }
+        | Condition::LessOrEqual { attr, value } => {
+            out.push(match condition {
+                Condition::GreaterThan { .. } => 7,
+                Condition::GreaterOrEqual { .. } => 8,
+                Condition::LessThan { .. } => 9,
+                _ => 10,
+            });
+            put_str(out, attr);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        Condition::Between { attr, min, max } => {
+            out.push(11);
+            put_str(out, attr);
+            out.extend_from_slice(&min.to_le_bytes());
+            out.extend_from_slice(&max.to_le_bytes());
+        }
+        Condition::IpInCidr { attr, cidr } => {
+            out.push(12);
+            put_str(out, attr);
+            put_ip(out, cidr.network());
+            out.push(cidr.prefix_len());
+        }
+        Condition::Matches { attr, pattern } => {
+            out.push(13);
+            put_str(out, attr);
+            put_str(out, pattern.as_str());
+        }
+        Condition::MemberOf(group) => {
+            out.push(14);
+            put_str(out, group);
+        }
+        Condition::AttrIsPrincipal(attr) => {
+            out.push(15);
+            put_str(out, attr);
+        }
+        Condition::TimeOfDayBetween {
+            window,
+            utc_offset_minutes,
+        } => {
+            out.push(16);
+            out.extend_from_slice(&window.start.to_le_bytes());
+            out.extend_from_slice(&window.end.to_le_bytes());
+            out.extend_from_slice(&utc_offset_minutes.to_le_bytes());
+        }
+        Condition::DayOfWeekIn {
+            days,
+            utc_offset_minutes,
+        } => {
+            out.push(17);
+            out.push(days.bits());
+            out.extend_from_slice(&utc_offset_minutes.to_le_bytes());
+        }
+        Condition::Custom { op, args } => {
+            out.push(18);
+            put_str(out, op);
+            put_list(out, args);
+        }
+        Condition::Ref(name) => {
+            out.push(19);
+            put_str(out, name);
+        }
+        Condition::And(a, b)
+        | Condition::Or(a, b)
+        | Condition::Implies(a, b)
+        | Condition::Xor(a, b) => {
+            out.push(match condition {
+                Condition::And(..) => 20,
+                Condition::Or(..) => 21,
+                Condition::Implies(..) => 23,
+                _ => 24,
+            });
+            put_condition(out, a);
+            put_condition(out, b);
+        }
+        Condition::Not(inner) => {
+            out.push(22);
+            put_condition(out, inner);
+        }
+        Condition::AllOf(children) | Condition::AnyOf(children) => {
+            out.push(if matches!(condition, Condition::AllOf(_)) {
+                25
+            } else {
+                26
+            });
+            put_len(out, children.len());
+            for child in children.iter() {
+                put_condition(out, child);
+            }
+        }
+        Condition::AttrContains { attr, value } => {
+            out.push(27);
+            put_str(out, attr);
+            put_value(out, value);
+        }
+        Condition::HasFlags { attr, mask } => {
+            out.push(28);
+            put_str(out, attr);
+            out.extend_from_slice(&mask.to_le_bytes());
+        }
+        Condition::GlobMatches { attr, pattern } => {
+            out.push(29);
+            put_str(out, attr);
+            put_str(out, pattern);
+        }
+        Condition::Require(attr, inner) => {
+            out.push(30);
+            put_str(out, attr);
+            put_condition(out, inner);
+        }
+    }
+}
+
+fn put_value(out: &mut Vec<u8>, value: &Value<'_>) {
+    match value {
+        Value::Bool(b) => {
+            out.push(0);
+            out.push(*b as u8);
+        }
+        Value::Int(i) => {
+            out.push(1);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::String(s) => {
+            out.push(2);
+            put_str(out, s);
+        }
+        Value::Secret(bytes) => {
+            out.push(3);
+            out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            out.extend_from_slice(bytes);
+        }
+        Value::Ip(ip) => {
+            out.push(4);
+            put_ip(out, *ip);
+        }
+        // Validation keeps lists and maps out of conditions; written for
+        // symmetry.
+        Value::List(list) => {
+            out.push(5);
+            put_len(out, list.len());
+            for item in list.iter() {
+                put_value(out, &item);
+            }
+        }
+        Value::Map(map) => {
+            out.push(6);
+            put_len(out, map.len());
+            for (key, value) in map.iter() {
+                put_str(out, key);
+                put_value(out, &value);
+            }
+        }
+    }
+}
+
+fn put_ip(out: &mut Vec<u8>, ip: IpAddr) {
+    match ip {
+        IpAddr::V4(v4) => {
+            out.push(4);
+            out.extend_from_slice(&v4.octets());
+        }
+        IpAddr::V6(v6) => {
+            out.push(6);
+            out.extend_from_slice(&v6.octets());
+        }
+    }
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn put_opt(out: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        None => out.push(0),
+        Some(s) => {
+            out.push(1);
+            put_str(out, s);
+        }
+    }
+}
+
+fn put_list(out: &mut Vec<u8>, names: &[&str]) {
+    put_len(out, names.len());
+    for name in names {
+        put_str(out, name);
+    }
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+/// A table that lives as long as the cached policy.
+fn leak<T>(items: Vec<T>) -> &'static [T] {
+    Box::leak(items.into_boxed_slice())
+}
+
+struct Reader(&'static [u8]);
+
+impl Reader {
+    fn take(&mut self, n: usize) -> Result<&'static [u8], PolicyError> {
+        if self.0.len() < n {
+            return Err(PolicyError::MalformedEmbeddedPolicy);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], PolicyError> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8, PolicyError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, PolicyError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn len(&mut self) -> Result<u32, PolicyError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn flag(&mut self) -> Result<bool, PolicyError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PolicyError::MalformedEmbeddedPolicy),
+        }
+    }
+
+    fn usize(&mut self) -> Result<usize, PolicyError> {
+        usize::try_from(u64::from_le_bytes(self.array()?))
+            .map_err(|_| PolicyError::MalformedEmbeddedPolicy)
+    }
+
+    fn bytes(&mut self) -> Result<&'static [u8], PolicyError> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        self.take(len)
+    }
+
+    fn str(&mut self) -> Result<&'static str, PolicyError> {
+        std::str::from_utf8(self.bytes()?).map_err(|_| PolicyError::MalformedEmbeddedPolicy)
+    }
+
+    fn opt(&mut self) -> Result<Option<&'static str>, PolicyError> {
+        Ok(if self.flag()? {
+            Some(self.str()?)
+        } else {
+            None
+        })
+    }
+
+    fn list(&mut self) -> Result<&'static [&'static str], PolicyError> {
+        let mut names = Vec::new();
+        for _ in 0..self.len()? {
+            names.push(self.str()?);
+        }
+        Ok(leak(names))
+    }
+
+    fn config(&mut self) -> Result<PolicyConfig, PolicyError> {
+        let mut config = PolicyConfig {
+            max_rules: self.usize()?,
+            max_condition_depth: self.usize()?,
+            max_condition_nodes: self.usize()?,
+            max_context_attrs: self.usize()?,
+            max_matcher_options: self.usize()?,
+            max_string_len: self.usize()?,
+            max_set_values: self.usize()?,
+            max_group_lookups: self.usize()?,
+            max_custom_op_cost: self.usize()?,
+            ..PolicyConfig::default()
+        };
+        config.indeterminate_on_error = self.flag()?;
+        config.deny_aggregation = match self.u8()? {
+            0 => DenyAggregation::FirstMatch,
+            1 => DenyAggregation::LowestReason,
+            2 => DenyAggregation::HighestPriority,
+            3 => DenyAggregation::CollectAll,
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        };
+        config.short_circuit = self.flag()?;
+        config.strict_types = self.flag()?;
+        config.known_actions = if self.flag()? {
+            Some(self.list()?)
+        } else {
+            None
+        };
+        config.known_resources = if self.flag()? {
+            Some(self.list()?)
+        } else {
+            None
+        };
+        config.reject_unknown_names = self.flag()?;
+        config.collation = match self.u8()? {
+            0 => Collation::Binary,
+            1 => Collation::AsciiCaseInsensitive,
+            #[cfg(feature = "unicode")]
+            2 => Collation::UnicodeCaseFold,
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        };
+        config.max_obligations = self.usize()?;
+        config.max_condition_evals = self.usize()?;
+        config.three_valued_logic = self.flag()?;
+        config.max_path_depth = self.usize()?;
+        Ok(config)
+    }
+
+    fn rule(&mut self) -> Result<Rule<'static>, PolicyError> {
+        let effect = match self.u8()? {
+            0 => Effect::Allow,
+            1 => Effect::Deny,
+            2 => Effect::Challenge(match self.u8()? {
+                0 => ChallengeMethod::Mfa,
+                1 => ChallengeMethod::Reauthenticate,
+                2 => ChallengeMethod::Custom(u32::from_le_bytes(self.array()?)),
+                _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+            }),
+            3 => Effect::Indeterminate,
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        };
+        let reason = ReasonCode(u32::from_le_bytes(self.array()?));
+        let target = Target {
+            principal: self.matcher()?,
+            action: self.matcher()?,
+            resource: self.matcher()?,
+            environment: self.matcher()?,
+        };
+        let condition = if self.flag()? {
+            Some(self.condition(1)?)
+        } else {
+            None
+        };
+        let mut rule = Rule::new(effect, target, condition, reason);
+        if self.flag()? {
+            rule.cache_ttl = Some(u32::from_le_bytes(self.array()?));
+        }
+        if self.flag()? {
+            let days = Days::from_bits(self.u8()?);
+            let mut windows = Vec::new();
+            for _ in 0..self.len()? {
+                windows.push(TimeWindow {
+                    start: self.u16()?,
+                    end: self.u16()?,
+                });
+            }
+            rule.schedule = Some(Schedule {
+                days,
+                windows: leak(windows),
+                utc_offset_minutes: i32::from_le_bytes(self.array()?),
+                clock_attr: self.str()?,
+            });
+        }
+        rule.break_glass = self.opt()?;
+        rule.priority = u32::from_le_bytes(self.array()?);
+        rule.on_provider_failure = match self.u8()? {
+            0 => None,
+            1 => Some(ProviderFailure::Deny),
+            2 => Some(ProviderFailure::Skip),
+            3 => Some(ProviderFailure::Indeterminate),
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        };
+        if self.flag()? {
+            rule.approval = Some(Approval {
+                approvers: u32::from_le_bytes(self.array()?),
+                expires_in: u32::from_le_bytes(self.array()?),
+            });
+        }
+        rule.tags = self.list()?;
+        let mut obligations = Vec::new();
+        for _ in 0..self.u8()? {
+            obligations.push(match self.u8()? {
+                0 => Obligation::Audit,
+                1 => Obligation::Custom(u32::from_le_bytes(self.array()?)),
+                2 => Obligation::Approval(Approval {
+                    approvers: u32::from_le_bytes(self.array()?),
+                    expires_in: u32::from_le_bytes(self.array()?),
+                }),
+                _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+            });
+        }
+        rule.obligations = leak(obligations);
+        Ok(rule)
+    }
+
+    fn matcher(&mut self) -> Result<Matcher<'static>, PolicyError> {
+        match self.u8()? {
+            0 => Ok(Matcher::Any),
+            1 => Ok(Matcher::Exact(self.str()?)),
+            2 => Ok(Matcher::OneOf(self.list()?)),
+            _ => Err(PolicyError::MalformedEmbeddedPolicy),
+        }
+    }
+
+    /// Recursion is bounded by `ABSOLUTE_MAX_CONDITION_DEPTH`; building the
+    /// policy then applies its own, possibly lower, limit.
+    fn condition(&mut self, depth: usize) -> Result<Condition<'static>, PolicyError> {
+        if depth > ABSOLUTE_MAX_CONDITION_DEPTH {
+            return Err(PolicyError::MalformedEmbeddedPolicy);
+        }
+        let tag = self.u8()?;
+        Ok(match tag {
+            0 => Condition::True,
+            1 => Condition::False,
+            2 => Condition::Equals {
+                attr: self.str()?,
+                value: self.value()?,
+            },
+            3 => Condition::NotEquals {
+                attr: self.str()?,
+                value: self.value()?,
+            },
+            4 => Condition::EqualsIgnoreCase {
+                attr: self.str()?,
+                value: self.str()?,
+            },
+            5 | 6 => {
+                let attr = self.str()?;
+                let mut values = Vec::new();
+                for _ in 0..self.len()? {
+                    values.push(self.value()?);
+                }
+                let values = leak(values);
+                if tag == 5 {
+                    Condition::In { attr, values }
+                } else {
+                    Condition::NotIn { attr, values }
+                }
+            }
+            7..=10 => {
+                let attr = self.str()?;
+                let value = i64::from_le_bytes(self.array()?);
+                match tag {
+                    7 => Condition::GreaterThan { attr, value },
+                    8 => Condition::GreaterOrEqual { attr, value },
+                    9 => Condition::LessThan { attr, value },
+                    _ => Condition::LessOrEqual { attr, value },
+                }
+            }
+            11 => Condition::Between {
+                attr: self.str()?,
+                min: i64::from_le_bytes(self.array()?),
+                max: i64::from_le_bytes(self.array()?),
+            },
+            12 => Condition::IpInCidr {
+                attr: self.str()?,
+                cidr: Cidr::new(self.ip()?, self.u8()?)?,
+            },
+            13 => Condition::Matches {
+                attr: self.str()?,
+                pattern: Pattern::parse(self.str()?)?,
+            },
+            14 => Condition::MemberOf(self.str()?),
+            15 => Condition::AttrIsPrincipal(self.str()?),
+            16 => Condition::TimeOfDayBetween {
+                window: TimeWindow {
+                    start: self.u16()?,
+                    end: self.u16()?,
+                },
+                utc_offset_minutes: i32::from_le_bytes(self.array()?),
+            },
+            17 => Condition::DayOfWeekIn {
+                days: Days::from_bits(self.u8()?),
+                utc_offset_minutes: i32::from_le_bytes(self.array()?),
+            },
+            18 => Condition::Custom {
+                op: self.str()?,
+                args: self.list()?,
+            },
+            19 => Condition::Ref(self.str()?),
+            20 | 21 | 23 | 24 => {
+                let a = Box::new(self.condition(depth + 1)?);
+                let b = Box::new(self.condition(depth + 1)?);
+                match tag {
+                    20 => Condition::And(a, b),
+                    21 => Condition::Or(a, b),
+                    23 => Condition::Implies(a, b),
+                    _ => Condition::Xor(a, b),
+                }
+            }
+            22 => Condition::Not(Box::new(self.condition(depth + 1)?)),
+            25 | 26 => {
+                let mut children = Vec::new();
+                for _ in 0..self.len()? {
+                    children.push(self.condition(depth + 1)?);
+                }
+                let children = leak(children);
+                if tag == 25 {
+                    Condition::AllOf(children)
+                } else {
+                    Condition::AnyOf(children)
+                }
+            }
+            27 => Condition::AttrContains {
+                attr: self.str()?,
+                value: self.value()?,
+            },
+            28 => Condition::HasFlags {
+                attr: self.str()?,
+                mask: i64::from_le_bytes(self.array()?),
+            },
+            29 => Condition::GlobMatches {
+                attr: self.str()?,
+                pattern: self.str()?,
+            },
+            30 => Condition::Require(self.str()?, Box::new(self.condition(depth + 1)?)),
+            _ => return Err(PolicyError::MalformedEmbeddedPolicy),
+        })
+    }
+
+    fn value(&mut self) -> Result<Value<'static>, PolicyError> {
+        match self.u8()? {
+            5 => {
+                let mut items = Vec::new();
+                for _ in 0..self.len()? {
+                    let tag = self.u8()?;
+                    items.push(self.scalar(tag)?);
+                }
+                Ok(Value::list(leak(items)))
+            }
+            6 => {
+                let mut entries = Vec::new();
+                for _ in 0..self.len()? {
+                    let key = self.str()?;
+                    let tag = self.u8()?;
+                    entries.push((key, self.scalar(tag)?));
+                }
+                Ok(Value::map(leak(entries)))
+            }
+            tag => self.scalar(tag),
+        }
+    }
+
+    fn scalar(&mut self, tag: u8) -> Result<Value<'static>, PolicyError> {
+        match tag {
+            0 => Ok(Value::Bool(self.flag()?)),
+            1 => Ok(Value::Int(i64::from_le_bytes(self.array()?))),
+            2 => Ok(Value::String(self.str()?)),
+            3 => Ok(Value::Secret(self.bytes()?)),
+            4 => Ok(Value::Ip(self.ip()?)),
+            _ => Err(PolicyError::MalformedEmbeddedPolicy),
+        }
+    }
+
+    fn ip(&mut self) -> Result<IpAddr, PolicyError> {
+        match self.u8()? {
+            4 => Ok(IpAddr::V4(Ipv4Addr::from(self.array::<4>()?))),
+            6 => Ok(IpAddr::V6(Ipv6Addr::from(self.array::<16>()?))),
+            _ => Err(PolicyError::MalformedEmbeddedPolicy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Request;
+
+    fn embed(policy: &Policy<'_>) -> &'static [u8] {
+        leak(policy.to_embedded())
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        static WINDOWS: [TimeWindow; 1] = [TimeWindow {
+            start: 9 * 60,
+            end: 17 * 60,
+        }];
+        static ROLES: [Value<'static>; 2] = [Value::String("admin"), Value::Int(3)];
+        static CHILDREN: [Condition<'static>; 5] = [
+            Condition::Between {
+                attr: "risk",
+                min: 0,
+                max: 40,
+            },
+            Condition::DayOfWeekIn {
+                days: Days::WEEKDAYS,
+                utc_offset_minutes: 60,
+            },
+            Condition::AttrContains {
+                attr: "groups",
+                value: Value::String("billing"),
+            },
+            Condition::HasFlags {
+                attr: "caps",
+                mask: 0b110,
+            },
+            Condition::GlobMatches {
+                attr: "project",
+                pattern: "proj-*-prod",
+            },
+        ];
+        let policy = Policy::builder()
+            .config(PolicyConfig {
+                deny_aggregation: DenyAggregation::CollectAll,
+                collation: Collation::AsciiCaseInsensitive,
+                known_actions: Some(&["read", "write", "admin"]),
+                max_obligations: 3,
+                max_condition_evals: 500,
+                three_valued_logic: true,
+                max_path_depth: 6,
+                ..PolicyConfig::default()
+            })
+            .metadata(PolicyMetadata {
+                name: Some("payments"),
+                created_at: Some(1_700_000_000),
+                ..PolicyMetadata::default()
+            })
+            .action_hierarchy(ActionHierarchy::new().implies("admin", "write"))
+            .sensitive_attribute("email", Sensitivity::Pii)
+            .sensitive_attribute("token", Sensitivity::Secret)
+            .rule(
+                Rule::deny(
+                    Target {
+                        resource: Matcher::OneOf(&["vault", "keys"]),
+                        ..Target::any()
+                    },
+                    ReasonCode(9),
+                )
+                .with_priority(5)
+                .with_tags(&["pci"]),
+            )
+            .rule(Rule::new(
+                Effect::Challenge(ChallengeMethod::Custom(7)),
+                Target::any(),
+                Some(Condition::Or(
+                    Box::new(Condition::Not(Box::new(Condition::In {
+                        attr: "role",
+                        values: &ROLES,
+                    }))),
+                    Box::new(Condition::IpInCidr {
+                        attr: "ip",
+                        cidr: Cidr::parse("fd00::/8").unwrap(),
+                    }),
+                )),
+                ReasonCode(2),
+            ))
+            .rule(
+                Rule::new(
+                    Effect::Allow,
+                    Target {
+                        action: Matcher::Exact("write"),
+                        ..Target::any()
+                    },
+                    Some(Condition::And(
+                        Box::new(Condition::AllOf(&CHILDREN)),
+                        Box::new(Condition::Require(
+                            "name",
+                            Box::new(Condition::Matches {
+                                attr: "name",
+                                pattern: Pattern::parse("^svc-[a-z]*$").unwrap(),
+                            }),
+                        )),
+                    )),
+                    ReasonCode(1),
+                )
+                .with_cache_ttl(60)
+                .with_schedule(Schedule::new(Days::WEEKDAYS, &WINDOWS))
+                .with_approval(3, 600)
+                .with_obligations(&[Obligation::Custom(4), Obligation::Audit]),
+            )
+            .build()
+            .unwrap();
+
+        let bytes = embed(&policy);
+        let loaded = Policy::from_embedded(bytes).unwrap();
+        assert_eq!(loaded.fingerprint(), policy.fingerprint());
+        assert_eq!(loaded.rules().len(), 3);
+        assert_eq!(loaded.rules()[2].condition, policy.rules()[2].condition);
+        assert_eq!(loaded.rules()[0].tags, ["pci"]);
+        assert_eq!(loaded.rules()[2].obligations, policy.rules()[2].obligations);
+        assert_eq!(loaded.config().max_obligations, 3);
+        assert_eq!(loaded.config().max_condition_evals, 500);
+        assert!(loaded.config().three_valued_logic);
+        assert_eq!(loaded.config().max_path_depth, 6);
+        assert_eq!(loaded.metadata(), policy.metadata());
+        assert_eq!(loaded.action_hierarchy(), policy.action_hierarchy());
+        assert_eq!(loaded.sensitive_attributes(), policy.sensitive_attributes());
+        assert_eq!(loaded.config().known_actions, policy.config().known_actions);
+        assert_eq!(loaded.to_embedded(), bytes);
+
+        // Cached: the same bytes give the same policy
+        assert!(std::ptr::eq(Policy::from_embedded(bytes).unwrap(), loaded));
+        let request = Request::new("alice", "read", "vault");
+        assert_eq!(loaded.evaluate(&request), policy.evaluate(&request));
+    }
+
+    #[test]
+    fn test_long_lists() {
+        // Lists past `u16::MAX` entries keep their length
+        let names: Vec<&'static str> = (0..70_000)
+            .map(|i| &*Box::leak(format!("action-{i}").into_boxed_str()))
+            .collect();
+        let policy = Policy::with_config(
+            vec![Rule::allow(Target::any(), ReasonCode(1))],
+            PolicyConfig {
+                known_actions: Some(leak(names)),
+                ..PolicyConfig::default()
+            },
+        )
+        .unwrap();
+        let loaded = Policy::from_embedded(leak(policy.to_embedded())).unwrap();
+        assert_eq!(loaded.config().known_actions, policy.config().known_actions);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let policy = Policy::new(vec![Rule::allow(Target::any(), ReasonCode(1))]).unwrap();
+        let bytes = policy.to_embedded();
+
+        let truncated = leak(bytes[..bytes.len() - 1].to_vec());
+        assert_eq!(
+            Policy::from_embedded(truncated).unwrap_err(),
+            PolicyError::MalformedEmbeddedPolicy
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Policy::from_embedded(leak(trailing)).unwrap_err(),
+            PolicyError::MalformedEmbeddedPolicy
+        );
+        let mut version = bytes.clone();
+        version[4] = 1;
+        assert_eq!(
+            Policy::from_embedded(leak(version)).unwrap_err(),
+            PolicyError::MalformedEmbeddedPolicy
+        );
+        assert_eq!(
+            Policy::from_embedded(b"not a policy").unwrap_err(),
+            PolicyError::MalformedEmbeddedPolicy
+        );
+
+        // Validation still runs: here the rule count exceeds `max_rules`
+        let mut too_many = bytes;
+        too_many[5..13].copy_from_slice(&0u64.to_le_bytes());
+        assert!(matches!(
+            Policy::from_embedded(leak(too_many)).unwrap_err(),
+            PolicyError::TooManyRules { .. }
+        ));
+    }
+}
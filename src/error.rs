@@ -16,6 +16,14 @@ pub enum PolicyError {
         actual: usize,
     },
 
+    /// A condition has more nodes than allowed.
+    TooManyConditionNodes {
+        /// The configured maximum node count.
+        max: usize,
+        /// The actual node count of the condition.
+        actual: usize,
+    },
+
     /// The policy contains too many rules.
     TooManyRules {
         /// The configured maximum number of rules.
@@ -39,9 +47,12 @@ pub enum PolicyError {
     },
 
     /// A context attribute has an unexpected type.
+    ///
+    /// Returned under `PolicyConfig::strict_types` when a condition compares
+    /// an attribute with a literal of another type.
     TypeMismatch {
         /// The name of the attribute with the wrong type.
-        attr: &'static str,
+        attr: String,
         /// The expected type name.
         expected: &'static str,
         /// The actual type name.
@@ -71,6 +82,185 @@ pub enum PolicyError {
         max: usize,
     },
 
+    /// A group membership lookup failed or no group provider was supplied.
+    GroupLookupFailed,
+
+    /// The request needed more group lookups than allowed.
+    GroupLookupBudgetExceeded {
+        /// The configured maximum number of lookups per request.
+        max: usize,
+    },
+
+    /// A rule schedule has an out-of-range window or UTC offset,
+    /// or too many windows.
+    InvalidSchedule,
+
+    /// A reverse query would evaluate more requests than allowed.
+    QueryTooLarge {
+        /// The maximum number of evaluations.
+        max: usize,
+        /// The number the query would need.
+        actual: usize,
+    },
+
+    /// An action hierarchy declares too many implications.
+    TooManyImplications {
+        /// The maximum number of implications.
+        max: usize,
+        /// The number declared.
+        actual: usize,
+    },
+
+    /// A `RevocationSet` would exceed `MAX_REVOCATIONS` entries.
+    TooManyRevocations {
+        /// The maximum number of entries.
+        max: usize,
+        /// The number the change would need.
+        actual: usize,
+    },
+
+    /// The request's `Deadline` ran out before a decision was reached.
+    DeadlineExceeded,
+
+    /// A `Condition::Custom` names an unregistered operator or passes too
+    /// many attributes, or a registered operator's name is empty, too long,
+    /// or taken.
+    InvalidCustomOp,
+
+    /// A custom operator failed, or no operators were available.
+    CustomOpFailed,
+
+    /// The request's custom operator calls cost more than allowed.
+    CustomOpBudgetExceeded {
+        /// The configured maximum cost per request.
+        max: usize,
+    },
+
+    /// The request context repeats an attribute with a different value.
+    ConflictingAttribute {
+        /// Index in the context of the contradicting entry.
+        index: usize,
+    },
+
+    /// A rule names an action or resource outside the configured
+    /// vocabularies, and `reject_unknown_names` is set.
+    UnknownName {
+        /// Index of the rule.
+        rule: usize,
+    },
+
+    /// The request's action is not in `PolicyConfig::known_actions`.
+    UnknownAction,
+
+    /// Text is not a CIDR network, or its prefix is too long for the
+    /// address family.
+    InvalidCidr,
+
+    /// Text is not a pattern gate0 supports (see `Pattern`).
+    InvalidPattern,
+
+    /// Bytes passed to `Policy::from_embedded` are not an encoded policy
+    /// of a supported version.
+    MalformedEmbeddedPolicy,
+
+    /// A `Condition::In` or `NotIn` set, or a `Value::List` in the request
+    /// context, has too many values.
+    TooManySetValues {
+        /// The configured maximum set size.
+        max: usize,
+        /// The size of the largest set.
+        actual: usize,
+    },
+
+    /// An integer does not fit in `Value::Int` (an `i64`).
+    IntegerOutOfRange,
+
+    /// Text is not a decimal integer.
+    InvalidInteger,
+
+    /// A `Condition::Ref` names no earlier definition, or a condition
+    /// name is defined twice.
+    InvalidConditionRef {
+        /// The referenced or redefined name.
+        name: String,
+    },
+
+    /// A `Condition::Between` has its lower bound above its upper bound.
+    InvalidRange {
+        /// The compared attribute.
+        attr: String,
+        /// The lower bound.
+        min: i64,
+        /// The upper bound.
+        max: i64,
+    },
+
+    /// A rule requires approval but is not an ordinary Allow rule, or its
+    /// approval expires at once.
+    InvalidApproval {
+        /// Index of the offending rule.
+        rule: usize,
+    },
+
+    /// A rule has more tags than allowed.
+    TooManyTags {
+        /// The maximum number of tags per rule.
+        max: usize,
+        /// The actual number of tags.
+        actual: usize,
+    },
+
+    /// A rule, or the matching rules behind a decision, carry more
+    /// distinct obligations than allowed, or the configured limit is above
+    /// `MAX_OBLIGATIONS`.
+    TooManyObligations {
+        /// The maximum number of obligations per decision.
+        max: usize,
+    },
+
+    /// The request's conditions took more evaluation steps than allowed.
+    EvalBudgetExceeded {
+        /// The configured maximum number of steps per request.
+        max: usize,
+    },
+
+    /// A `Value::List` holds a list, or a condition compares against one.
+    InvalidList,
+
+    /// A `Value::List` holds a map, or a condition compares against one.
+    InvalidMap,
+
+    /// A condition's dotted attribute path, or a `Value::Map` in the
+    /// request context, is nested deeper than allowed, or
+    /// `PolicyConfig::max_path_depth` exceeds `ABSOLUTE_MAX_PATH_DEPTH`.
+    PathTooDeep {
+        /// The maximum path depth.
+        max: usize,
+        /// The depth of the deepest path.
+        actual: usize,
+    },
+
+    /// A `GlobMatches` pattern has more wildcards than
+    /// `MAX_GLOB_WILDCARDS`.
+    TooManyWildcards {
+        /// The maximum number of wildcards.
+        max: usize,
+        /// The number of wildcards in the pattern.
+        actual: usize,
+    },
+
+    /// An attribute checked by `Condition::Require` is missing from the
+    /// request context.
+    RequiredAttributeMissing {
+        /// The name or dotted path of the missing attribute.
+        attr: String,
+    },
+
+    /// `Policy::partial_evaluate` was called on a policy using
+    /// `PolicyConfig::three_valued_logic`, whose unknown results a residual
+    /// cannot express.
+    ThreeValuedPartialEvaluation,
+
     /// Internal invariant violation. Should never occur in correct usage.
     InternalError,
 }
@@ -85,6 +275,13 @@ impl fmt::Display for PolicyError {
                     max, actual
                 )
             }
+            PolicyError::TooManyConditionNodes { max, actual } => {
+                write!(
+                    f,
+                    "condition exceeds maximum node count of {}, got {}",
+                    max, actual
+                )
+            }
             PolicyError::TooManyRules { max, actual } => {
                 write!(
                     f,
@@ -130,6 +327,111 @@ impl fmt::Display for PolicyError {
             PolicyError::EvalStackOverflow { max } => {
                 write!(f, "evaluation stack overflow (max: {})", max)
             }
+            PolicyError::GroupLookupFailed => {
+                write!(f, "group membership lookup failed")
+            }
+            PolicyError::GroupLookupBudgetExceeded { max } => {
+                write!(f, "group lookup budget exceeded (max: {})", max)
+            }
+            PolicyError::InvalidSchedule => {
+                write!(f, "invalid rule schedule")
+            }
+            PolicyError::QueryTooLarge { max, actual } => {
+                write!(
+                    f,
+                    "query exceeds maximum of {} evaluations, got {}",
+                    max, actual
+                )
+            }
+            PolicyError::TooManyImplications { max, actual } => {
+                write!(
+                    f,
+                    "action hierarchy exceeds maximum of {} implications, got {}",
+                    max, actual
+                )
+            }
+            PolicyError::TooManyRevocations { max, actual } => {
+                write!(
+                    f,
+                    "revocation list exceeds maximum of {} entries, got {}",
+                    max, actual
+                )
+            }
+            PolicyError::DeadlineExceeded => {
+                write!(f, "evaluation deadline exceeded")
+            }
+            PolicyError::InvalidCustomOp => {
+                write!(f, "invalid custom operator")
+            }
+            PolicyError::CustomOpFailed => {
+                write!(f, "custom operator failed")
+            }
+            PolicyError::CustomOpBudgetExceeded { max } => {
+                write!(f, "custom operator budget exceeded (max: {})", max)
+            }
+            PolicyError::ConflictingAttribute { index } => {
+                write!(f, "context entry {} conflicts with an earlier one", index)
+            }
+            PolicyError::UnknownName { rule } => {
+                write!(f, "rule {} names an unknown action or resource", rule)
+            }
+            PolicyError::UnknownAction => write!(f, "unknown action"),
+            PolicyError::InvalidCidr => write!(f, "invalid CIDR network"),
+            PolicyError::InvalidPattern => write!(f, "invalid pattern"),
+            PolicyError::MalformedEmbeddedPolicy => write!(f, "malformed embedded policy"),
+            PolicyError::TooManySetValues { max, actual } => {
+                write!(f, "set exceeds maximum values of {}, got {}", max, actual)
+            }
+            PolicyError::IntegerOutOfRange => {
+                write!(f, "integer out of range for a 64-bit value")
+            }
+            PolicyError::InvalidInteger => {
+                write!(f, "invalid decimal integer")
+            }
+            PolicyError::InvalidConditionRef { name } => {
+                write!(f, "invalid reference to condition '{}'", name)
+            }
+            PolicyError::InvalidRange { attr, min, max } => {
+                write!(f, "invalid range for '{}': {} is above {}", attr, min, max)
+            }
+            PolicyError::InvalidApproval { rule } => {
+                write!(f, "rule {} has an invalid approval requirement", rule)
+            }
+            PolicyError::TooManyTags { max, actual } => {
+                write!(f, "rule exceeds maximum tags of {}, got {}", max, actual)
+            }
+            PolicyError::TooManyObligations { max } => {
+                write!(f, "decision exceeds maximum obligations of {}", max)
+            }
+            PolicyError::EvalBudgetExceeded { max } => {
+                write!(f, "condition evaluation budget exceeded (max: {})", max)
+            }
+            PolicyError::InvalidList => {
+                write!(f, "list values cannot nest or appear in conditions")
+            }
+            PolicyError::InvalidMap => {
+                write!(f, "map values cannot appear in lists or conditions")
+            }
+            PolicyError::PathTooDeep { max, actual } => {
+                write!(
+                    f,
+                    "attribute path exceeds maximum depth of {}, got {}",
+                    max, actual
+                )
+            }
+            PolicyError::TooManyWildcards { max, actual } => {
+                write!(
+                    f,
+                    "glob pattern exceeds maximum wildcards of {}, got {}",
+                    max, actual
+                )
+            }
+            PolicyError::RequiredAttributeMissing { attr } => {
+                write!(f, "required context attribute '{}' is missing", attr)
+            }
+            PolicyError::ThreeValuedPartialEvaluation => {
+                write!(f, "partial evaluation does not support three-valued logic")
+            }
             PolicyError::InternalError => {
                 write!(f, "internal error: stack invariant violation")
             }
@@ -188,7 +490,7 @@ mod tests {
     #[test]
     fn test_type_mismatch_display() {
         let err = PolicyError::TypeMismatch {
-            attr: "count",
+            attr: "count".to_string(),
             expected: "Int",
             actual: "String",
         };
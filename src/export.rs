@@ -0,0 +1,646 @@
+//! Decision export for offline analysis.
+//!
+//! A `DecisionRecord` flattens one audited evaluation into plain columns.
+//! `DecisionCollector` is an `AuditSink` that batches them, `CsvWriter`
+//! writes a batch as CSV, and `CsvReader` reads it back, so security data
+//! teams can load authorization history into a dataframe, a warehouse, or
+//! a Parquet converter without parsing logs. gate0 has no dependencies,
+//! so it does not write Arrow or Parquet itself.
+//!
+//! # Columns
+//!
+//! ```text
+//! correlation_id   u64
+//! policy_name      empty if the policy has none
+//! policy_version   empty if the policy has none
+//! principal
+//! action
+//! resource
+//! environment      empty if the request named none
+//! effect           allow | deny | challenge | indeterminate | error
+//! challenge        mfa | reauthenticate | custom(N); challenge only
+//! reason           u32; empty for error
+//! cache_ttl        seconds; empty if none
+//! break_glass      true | false
+//! obligations      `;`-separated audit | custom(N) | approval(GROUP,SECONDS)
+//! error            error message; error only
+//! rules_checked    u16
+//! condition_evals  u16
+//! ```
+//!
+//! Files start with a header row naming the columns and use RFC 4180
+//! quoting with `\n` line endings. Fields are written verbatim: names
+//! that start with `=` or `+` are not escaped for spreadsheets.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+
+use crate::audit::{AuditRecord, AuditSink};
+use crate::obligation::{Approval, Obligation, Obligations};
+use crate::replay::RecordedOutcome;
+use crate::types::{ChallengeMethod, Decision, Effect, ReasonCode, Request};
+
+/// Column names, in file order.
+pub const COLUMNS: [&str; 16] = [
+    "correlation_id",
+    "policy_name",
+    "policy_version",
+    "principal",
+    "action",
+    "resource",
+    "environment",
+    "effect",
+    "challenge",
+    "reason",
+    "cache_ttl",
+    "break_glass",
+    "obligations",
+    "error",
+    "rules_checked",
+    "condition_evals",
+];
+
+/// Maximum size of one CSV record, quotes and line breaks included.
+pub const MAX_RECORD_BYTES: u64 = 1 << 20;
+
+/// One audited evaluation, flattened for export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecisionRecord {
+    /// The audit correlation id.
+    pub correlation_id: u64,
+    /// The policy's metadata name, or empty.
+    pub policy_name: String,
+    /// The policy's metadata version, or empty.
+    pub policy_version: String,
+    /// The request principal.
+    pub principal: String,
+    /// The request action.
+    pub action: String,
+    /// The request resource.
+    pub resource: String,
+    /// The request environment.
+    pub environment: Option<String>,
+    /// The decision, or the error's display text.
+    pub outcome: RecordedOutcome,
+    /// Rules checked before the decision.
+    pub rules_checked: u16,
+    /// Condition nodes evaluated.
+    pub condition_evals: u16,
+}
+
+impl DecisionRecord {
+    /// Capture an audit record and the request it answers.
+    pub fn from_audit(request: &Request<'_>, record: &AuditRecord) -> Self {
+        DecisionRecord {
+            correlation_id: record.correlation_id,
+            policy_name: record.policy_name.clone(),
+            policy_version: record.policy_version.clone(),
+            principal: request.principal.to_string(),
+            action: request.action.to_string(),
+            resource: request.resource.to_string(),
+            environment: request.environment.map(str::to_string),
+            outcome: RecordedOutcome::from_result(&record.result),
+            rules_checked: record.stats.rules_checked,
+            condition_evals: record.stats.condition_evals,
+        }
+    }
+
+    fn to_row(&self) -> [String; 16] {
+        let mut effect = "error";
+        let [mut challenge, mut reason, mut cache_ttl, mut obligations, mut error] =
+            <[String; 5]>::default();
+        let mut break_glass = false;
+        match &self.outcome {
+            RecordedOutcome::Decision(decision) => {
+                effect = match decision.effect {
+                    Effect::Allow => "allow",
+                    Effect::Deny => "deny",
+                    Effect::Challenge(method) => {
+                        challenge = method.to_string();
+                        "challenge"
+                    }
+                    Effect::Indeterminate => "indeterminate",
+                };
+                reason = decision.reason.value().to_string();
+                cache_ttl = decision.cache_ttl.map_or(String::new(), |t| t.to_string());
+                break_glass = decision.break_glass;
+                obligations = decision
+                    .obligations
+                    .iter()
+                    .map(|o| match o {
+                        Obligation::Audit => "audit".to_string(),
+                        Obligation::Custom(code) => format!("custom({})", code),
+                        Obligation::Approval(approval) => {
+                            format!("approval({},{})", approval.approvers, approval.expires_in)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(";");
+            }
+            RecordedOutcome::Error(message) => error = message.clone(),
+        }
+        [
+            self.correlation_id.to_string(),
+            self.policy_name.clone(),
+            self.policy_version.clone(),
+            self.principal.clone(),
+            self.action.clone(),
+            self.resource.clone(),
+            self.environment.clone().unwrap_or_default(),
+            effect.to_string(),
+            challenge,
+            reason,
+            cache_ttl,
+            break_glass.to_string(),
+            obligations,
+            error,
+            self.rules_checked.to_string(),
+            self.condition_evals.to_string(),
+        ]
+    }
+
+    fn from_row(row: Vec<String>) -> Result<Self, &'static str> {
+        if row.len() != COLUMNS.len() {
+            return Err("wrong number of columns");
+        }
+        let mut row = row.into_iter();
+        let mut column = || row.next().unwrap_or_default();
+        let correlation_id = column().parse().map_err(|_| "invalid correlation_id")?;
+        let policy_name = column();
+        let policy_version = column();
+        let principal = column();
+        let action = column();
+        let resource = column();
+        let environment = column();
+        let effect = column();
+        let challenge = column();
+        let reason = column();
+        let cache_ttl = column();
+        let break_glass = column();
+        let obligations = column();
+        let error = column();
+        let rules_checked = column().parse().map_err(|_| "invalid rules_checked")?;
+        let condition_evals = column().parse().map_err(|_| "invalid condition_evals")?;
+
+        let outcome = if effect == "error" {
+            RecordedOutcome::Error(error)
+        } else {
+            let effect = match effect.as_str() {
+                "allow" => Effect::Allow,
+                "deny" => Effect::Deny,
+                "challenge" => Effect::Challenge(parse_method(&challenge)?),
+                "indeterminate" => Effect::Indeterminate,
+                _ => return Err("unknown effect"),
+            };
+            let reason = reason.parse().map_err(|_| "invalid reason")?;
+            let cache_ttl = match cache_ttl.as_str() {
+                "" => None,
+                ttl => Some(ttl.parse().map_err(|_| "invalid cache_ttl")?),
+            };
+            let mut decision = Decision::new(effect, ReasonCode(reason)).with_cache_ttl(cache_ttl);
+            decision.break_glass = break_glass.parse().map_err(|_| "invalid break_glass")?;
+            decision.obligations = parse_obligations(&obligations)?;
+            RecordedOutcome::Decision(decision)
+        };
+
+        Ok(DecisionRecord {
+            correlation_id,
+            policy_name,
+            policy_version,
+            principal,
+            action,
+            resource,
+            environment: Some(environment).filter(|e| !e.is_empty()),
+            outcome,
+            rules_checked,
+            condition_evals,
+        })
+    }
+}
+
+fn parse_custom(s: &str) -> Option<u32> {
+    s.strip_prefix("custom(")?.strip_suffix(')')?.parse().ok()
+}
+
+fn parse_approval(s: &str) -> Option<Obligation> {
+    let args = s.strip_prefix("approval(")?.strip_suffix(')')?;
+    let (approvers, expires_in) = args.split_once(',')?;
+    Some(Obligation::Approval(Approval {
+        approvers: approvers.parse().ok()?,
+        expires_in: expires_in.parse().ok()?,
+    }))
+}
+
+fn parse_method(s: &str) -> Result<ChallengeMethod, &'static str> {
+    match s {
+        "mfa" => Ok(ChallengeMethod::Mfa),
+        "reauthenticate" => Ok(ChallengeMethod::Reauthenticate),
+        _ => parse_custom(s)
+            .map(ChallengeMethod::Custom)
+            .ok_or("unknown challenge method"),
+    }
+}
+
+fn parse_obligations(s: &str) -> Result<Obligations, &'static str> {
+    let mut obligations = Obligations::new();
+    for item in s.split(';').filter(|item| !item.is_empty()) {
+        let obligation = match item {
+            "audit" => Obligation::Audit,
+            _ if item.starts_with("approval(") => {
+                parse_approval(item).ok_or("invalid approval obligation")?
+            }
+            _ => parse_custom(item)
+                .map(Obligation::Custom)
+                .ok_or("unknown obligation")?,
+        };
+        if !obligations.push(obligation) {
+            return Err("too many obligations");
+        }
+    }
+    Ok(obligations)
+}
+
+/// An `AuditSink` that keeps the first `max_records` records it sees.
+#[derive(Debug)]
+pub struct DecisionCollector {
+    records: RefCell<Vec<DecisionRecord>>,
+    max_records: usize,
+    dropped: Cell<u64>,
+}
+
+impl DecisionCollector {
+    /// Create a collector holding at most `max_records` records at a time.
+    pub fn new(max_records: usize) -> Self {
+        DecisionCollector {
+            records: RefCell::new(Vec::new()),
+            max_records,
+            dropped: Cell::new(0),
+        }
+    }
+
+    /// Remove and return the records collected so far.
+    pub fn take(&self) -> Vec<DecisionRecord> {
+        std::mem::take(&mut *self.records.borrow_mut())
+    }
+
+    /// Number of records dropped because the collector was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.get()
+    }
+}
+
+impl AuditSink for DecisionCollector {
+    fn record(&self, request: &Request<'_>, record: &AuditRecord) {
+        let mut records = self.records.borrow_mut();
+        if records.len() < self.max_records {
+            records.push(DecisionRecord::from_audit(request, record));
+        } else {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+}
+
+/// Errors reading or writing a CSV export.
+#[derive(Debug)]
+pub enum CsvError {
+    /// Underlying I/O failure.
+    Io(io::Error),
+    /// The first row is not the expected header.
+    BadHeader,
+    /// A record is malformed. `line` is where it starts, counting from 1.
+    Malformed {
+        /// Line of the record.
+        line: u64,
+        /// What is wrong with it.
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(e) => write!(f, "decision export io error: {}", e),
+            CsvError::BadHeader => write!(f, "not a gate0 decision export"),
+            CsvError::Malformed { line, reason } => {
+                write!(f, "malformed decision record on line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> Self {
+        CsvError::Io(e)
+    }
+}
+
+/// Writes decision records as CSV.
+pub struct CsvWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Create a writer and emit the header row.
+    pub fn new(mut inner: W) -> Result<Self, CsvError> {
+        write_row(&mut inner, COLUMNS.iter().copied())?;
+        Ok(CsvWriter { inner })
+    }
+
+    /// Append a single record.
+    pub fn write(&mut self, record: &DecisionRecord) -> Result<(), CsvError> {
+        let row = record.to_row();
+        write_row(&mut self.inner, row.iter().map(String::as_str))
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, CsvError> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+fn write_row<'s, W: Write>(
+    w: &mut W,
+    fields: impl Iterator<Item = &'s str>,
+) -> Result<(), CsvError> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            w.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(w, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            w.write_all(field.as_bytes())?;
+        }
+    }
+    w.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Reads decision records from CSV written by `CsvWriter`.
+///
+/// Iterates over `Result<DecisionRecord, CsvError>`; iteration ends
+/// cleanly at end of stream and stops after the first error.
+pub struct CsvReader<R: BufRead> {
+    inner: R,
+    /// Lines consumed so far.
+    line: u64,
+    done: bool,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    /// Create a reader and validate the header row.
+    pub fn new(inner: R) -> Result<Self, CsvError> {
+        let mut reader = CsvReader {
+            inner,
+            line: 0,
+            done: false,
+        };
+        match reader.read_row()? {
+            Some(header) if header.iter().eq(COLUMNS.iter()) => Ok(reader),
+            _ => Err(CsvError::BadHeader),
+        }
+    }
+
+    /// Read one row's fields, or `None` at end of stream.
+    fn read_row(&mut self) -> Result<Option<Vec<String>>, CsvError> {
+        let start = self.line + 1;
+        let malformed = |reason| CsvError::Malformed {
+            line: start,
+            reason,
+        };
+        let mut text = String::new();
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            text.clear();
+            let limit = MAX_RECORD_BYTES.saturating_sub(field.len() as u64) + 1;
+            let n = (&mut self.inner).take(limit).read_line(&mut text)?;
+            if n == 0 {
+                if quoted {
+                    return Err(malformed("unterminated quote"));
+                }
+                return Ok(None);
+            }
+            if n as u64 >= limit {
+                return Err(malformed("record too long"));
+            }
+            self.line += 1;
+
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (quoted, c) {
+                    (true, '"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    (true, '"') => quoted = false,
+                    (true, c) => field.push(c),
+                    (false, '"') if field.is_empty() => quoted = true,
+                    (false, ',') => fields.push(std::mem::take(&mut field)),
+                    (false, '\n') => {}
+                    (false, '\r') if chars.peek() == Some(&'\n') => {}
+                    (false, '"') => return Err(malformed("stray quote")),
+                    (false, '\r') => return Err(malformed("stray carriage return")),
+                    (false, c) => field.push(c),
+                }
+            }
+            if !quoted {
+                fields.push(field);
+                return Ok(Some(fields));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = Result<DecisionRecord, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let line = self.line + 1;
+        let result = match self.read_row() {
+            Ok(None) => None,
+            Ok(Some(row)) => Some(
+                DecisionRecord::from_row(row)
+                    .map_err(|reason| CsvError::Malformed { line, reason }),
+            ),
+            Err(e) => Some(Err(e)),
+        };
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditBuffer;
+    use crate::condition::Condition;
+    use crate::metadata::PolicyMetadata;
+    use crate::policy::{Policy, Rule};
+    use crate::target::{Matcher, Target};
+    use crate::value::Value;
+
+    fn collect() -> Vec<DecisionRecord> {
+        let policy = Policy::builder()
+            .metadata(PolicyMetadata {
+                name: Some("api"),
+                version: Some("7"),
+                ..PolicyMetadata::default()
+            })
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Exact("mallory"),
+                    ..Target::any()
+                },
+                ReasonCode(9),
+            ))
+            .rule(
+                Rule::challenge(
+                    Target::any().in_environment(Matcher::Exact("prod")),
+                    ChallengeMethod::Custom(4),
+                    ReasonCode(5),
+                )
+                .with_cache_ttl(30),
+            )
+            .rule(Rule::break_glass(Target::any(), "sos", ReasonCode(6)))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Exact("carol"),
+                    ..Target::any()
+                },
+                Some(Condition::MemberOf("staff")),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+
+        let collector = DecisionCollector::new(4);
+        let mut buffer = AuditBuffer::new();
+        buffer.add_sink(&collector);
+        let sos: &[(&str, Value)] = &[("sos", Value::Bool(true))];
+        let requests = [
+            Request::new("mallory", "read", "a,\"quoted\"\nname"),
+            Request::new("alice", "read", "doc").in_environment("prod"),
+            Request::with_context("alice", "read", "doc", sos),
+            // No group provider, so this one fails
+            Request::new("carol", "read", "doc"),
+            Request::new("bob", "read", "doc"),
+        ];
+        for request in &requests {
+            let _ = policy.evaluate_with_audit(request, &mut buffer);
+        }
+        assert_eq!(collector.dropped(), 1);
+        collector.take()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let records = collect();
+        let mut writer = CsvWriter::new(Vec::new()).unwrap();
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        let csv = writer.into_inner().unwrap();
+        let text = String::from_utf8(csv.clone()).unwrap();
+        assert!(text.starts_with("correlation_id,policy_name,"));
+        assert!(text.contains("\n2,api,7,alice,read,doc,prod,challenge,custom(4),5,30,false,,,"));
+
+        let read: Vec<DecisionRecord> = CsvReader::new(&csv[..])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, records);
+        assert_eq!(read[0].resource, "a,\"quoted\"\nname");
+        assert_eq!(read[0].environment, None);
+        let RecordedOutcome::Decision(emergency) = read[2].outcome else {
+            panic!("expected a decision");
+        };
+        assert!(emergency.break_glass);
+        assert!(emergency.obligations.contains(Obligation::Audit));
+        assert_eq!(
+            read[3].outcome,
+            RecordedOutcome::Error("group membership lookup failed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_approval_obligation() {
+        let approval = Obligation::Approval(Approval {
+            approvers: 3,
+            expires_in: 900,
+        });
+        let parsed = parse_obligations("audit;approval(3,900)").unwrap();
+        assert_eq!(
+            parsed.iter().collect::<Vec<_>>(),
+            [Obligation::Audit, approval]
+        );
+        assert_eq!(
+            parse_obligations("approval(3)"),
+            Err("invalid approval obligation")
+        );
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert!(matches!(
+            CsvReader::new(&b"principal,action\n"[..]),
+            Err(CsvError::BadHeader)
+        ));
+
+        let header = COLUMNS.join(",");
+        let parse = |rows: &str| {
+            let text = format!("{}\n{}", header, rows);
+            CsvReader::new(text.as_bytes())
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(parse("").unwrap(), vec![]);
+        let cases = [
+            (
+                "1,,,a,b,c,,allow,,1,,false,,,1,0\n1,2\n",
+                3,
+                "wrong number of columns",
+            ),
+            ("1,,,a,b,c,,permit,,1,,false,,,1,0\n", 2, "unknown effect"),
+            (
+                "1,,,a,b,c,,allow,,1,,false,nag,,1,0\n",
+                2,
+                "unknown obligation",
+            ),
+            (
+                "1,,,\"a,b,c,,allow,,1,,false,,,1,0\n",
+                2,
+                "unterminated quote",
+            ),
+            ("1,,,a\"b,b,c,,allow,,1,,false,,,1,0\n", 2, "stray quote"),
+        ];
+        for (rows, line, reason) in cases {
+            match parse(rows) {
+                Err(CsvError::Malformed { line: l, reason: r }) => {
+                    assert_eq!((l, r), (line, reason), "{:?}", rows)
+                }
+                other => panic!("{:?}: {:?}", rows, other),
+            }
+        }
+
+        let huge = format!("\"{}", "x".repeat(MAX_RECORD_BYTES as usize));
+        assert!(matches!(
+            parse(&huge),
+            Err(CsvError::Malformed {
+                reason: "record too long",
+                ..
+            })
+        ));
+    }
+}
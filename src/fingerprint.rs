@@ -0,0 +1,593 @@
+//! Stable policy fingerprints.
+//!
+//! A fingerprint is a 64-bit FNV-1a hash over a canonical, platform-
+//! independent encoding of the policy's config and rules. Two policies
+//! with the same fingerprint make the same decisions; any edit to a rule,
+//! its order, or the config changes the fingerprint.
+//!
+//! All integers are encoded little-endian with fixed widths and all
+//! strings are length-prefixed, so the encoding does not depend on
+//! `usize` width or endianness.
+
+use std::net::IpAddr;
+
+use crate::collation::Collation;
+use crate::condition::Condition;
+use crate::obligation::Obligation;
+use crate::policy::{DenyAggregation, Policy, PolicyConfig, ProviderFailure, Rule};
+use crate::target::Matcher;
+use crate::types::{ChallengeMethod, Effect};
+use crate::value::Value;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Incremental 64-bit FNV-1a hasher.
+///
+/// Not cryptographic. Used for change detection, not integrity.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Fnv64(u64);
+
+impl Fnv64 {
+    pub(crate) fn new() -> Self {
+        Fnv64(FNV_OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn write_u8(&mut self, v: u8) {
+        self.write(&[v]);
+    }
+
+    pub(crate) fn write_u32(&mut self, v: u32) {
+        self.write(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_u64(&mut self, v: u64) {
+        self.write(&v.to_le_bytes());
+    }
+
+    pub(crate) fn write_str(&mut self, s: &str) {
+        self.write_u64(s.len() as u64);
+        self.write(s.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Compute a stable fingerprint of this policy.
+    ///
+    /// The fingerprint covers the config and every rule in order. It is
+    /// stable across platforms and process runs, so it can be logged with
+    /// decisions and compared later to tell which policy revision made them.
+    pub fn fingerprint(&self) -> u64 {
+        let mut h = Fnv64::new();
+        hash_config(&mut h, self.config());
+        h.write_u64(self.rules().len() as u64);
+        for rule in self.rules() {
+            hash_rule(&mut h, rule);
+        }
+        // Only hashed when present, so existing fingerprints are unchanged.
+        let implications = self.action_hierarchy().implications();
+        if !implications.is_empty() {
+            h.write_u64(implications.len() as u64);
+            for (stronger, weaker) in implications {
+                h.write_str(stronger);
+                h.write_str(weaker);
+            }
+        }
+        h.finish()
+    }
+}
+
+fn hash_config(h: &mut Fnv64, config: &PolicyConfig) {
+    h.write_u64(config.max_rules as u64);
+    h.write_u64(config.max_condition_depth as u64);
+    h.write_u64(config.max_context_attrs as u64);
+    h.write_u64(config.max_matcher_options as u64);
+    h.write_u64(config.max_string_len as u64);
+    h.write_u64(config.max_group_lookups as u64);
+    h.write_u8(config.indeterminate_on_error as u8);
+    // Only hashed when not the default, so existing fingerprints are unchanged.
+    match config.deny_aggregation {
+        DenyAggregation::FirstMatch => {}
+        DenyAggregation::LowestReason => h.write_u8(1),
+        DenyAggregation::HighestPriority => h.write_u8(2),
+        DenyAggregation::CollectAll => h.write_u8(3),
+    }
+    if !config.short_circuit {
+        h.write_u8(0xfe);
+    }
+    if config.max_custom_op_cost != PolicyConfig::default().max_custom_op_cost {
+        h.write_u8(0xfd);
+        h.write_u64(config.max_custom_op_cost as u64);
+    }
+    for (tag, vocabulary) in [(0xfc, config.known_actions), (0xfb, config.known_resources)] {
+        if let Some(names) = vocabulary {
+            h.write_u8(tag);
+            h.write_u64(names.len() as u64);
+            for name in names {
+                h.write_str(name);
+            }
+        }
+    }
+    if config.reject_unknown_names {
+        h.write_u8(0xfa);
+    }
+    if config.max_set_values != PolicyConfig::default().max_set_values {
+        h.write_u8(0xf9);
+        h.write_u64(config.max_set_values as u64);
+    }
+    if config.max_condition_nodes != PolicyConfig::default().max_condition_nodes {
+        h.write_u8(0xf8);
+        h.write_u64(config.max_condition_nodes as u64);
+    }
+    if config.strict_types {
+        h.write_u8(0xf7);
+    }
+    match config.collation {
+        Collation::Binary => {}
+        Collation::AsciiCaseInsensitive => h.write_u8(0xf6),
+        #[cfg(feature = "unicode")]
+        Collation::UnicodeCaseFold => {
+            h.write_u8(0xf6);
+            h.write_u8(1);
+        }
+    }
+    if config.max_obligations != PolicyConfig::default().max_obligations {
+        h.write_u8(0xf5);
+        h.write_u64(config.max_obligations as u64);
+    }
+    if config.max_condition_evals != PolicyConfig::default().max_condition_evals {
+        h.write_u8(0xf4);
+        h.write_u64(config.max_condition_evals as u64);
+    }
+    if config.three_valued_logic {
+        h.write_u8(0xf3);
+    }
+    if config.max_path_depth != PolicyConfig::default().max_path_depth {
+        h.write_u8(0xf2);
+        h.write_u64(config.max_path_depth as u64);
+    }
+}
+
+fn hash_rule(h: &mut Fnv64, rule: &Rule<'_>) {
+    hash_effect(h, rule.effect);
+    hash_matcher(h, &rule.target.principal);
+    hash_matcher(h, &rule.target.action);
+    hash_matcher(h, &rule.target.resource);
+    match &rule.condition {
+        None => h.write_u8(0),
+        Some(cond) => {
+            h.write_u8(1);
+            hash_condition(h, cond);
+        }
+    }
+    h.write_u32(rule.reason.value());
+    hash_opt_u32(h, rule.cache_ttl);
+    match &rule.schedule {
+        None => h.write_u8(0),
+        Some(schedule) => {
+            h.write_u8(1);
+            h.write_u8(schedule.days.bits());
+            h.write_u64(schedule.windows.len() as u64);
+            for window in schedule.windows {
+                h.write_u32(window.start as u32);
+                h.write_u32(window.end as u32);
+            }
+            h.write_u32(schedule.utc_offset_minutes as u32);
+            h.write_str(schedule.clock_attr);
+        }
+    }
+    match rule.break_glass {
+        None => h.write_u8(0),
+        Some(flag) => {
+            h.write_u8(1);
+            h.write_str(flag);
+        }
+    }
+    // Only hashed when set, so existing fingerprints are unchanged.
+    if rule.priority != 0 {
+        h.write_u8(0xff);
+        h.write_u32(rule.priority);
+    }
+    if let Some(on_failure) = rule.on_provider_failure {
+        h.write_u8(0xfe);
+        h.write_u8(match on_failure {
+            ProviderFailure::Deny => 0,
+            ProviderFailure::Skip => 1,
+            ProviderFailure::Indeterminate => 2,
+        });
+    }
+    if rule.target.environment != Matcher::Any {
+        h.write_u8(0xfd);
+        hash_matcher(h, &rule.target.environment);
+    }
+    if let Some(approval) = rule.approval {
+        h.write_u8(0xfb);
+        h.write_u32(approval.approvers);
+        h.write_u32(approval.expires_in);
+    }
+    if !rule.tags.is_empty() {
+        h.write_u8(0xfc);
+        h.write_u64(rule.tags.len() as u64);
+        for tag in rule.tags {
+            h.write_str(tag);
+        }
+    }
+    if !rule.obligations.is_empty() {
+        h.write_u8(0xfa);
+        h.write_u64(rule.obligations.len() as u64);
+        for obligation in rule.obligations {
+            match obligation {
+                Obligation::Audit => h.write_u8(0),
+                Obligation::Custom(code) => {
+                    h.write_u8(1);
+                    h.write_u32(*code);
+                }
+                Obligation::Approval(approval) => {
+                    h.write_u8(2);
+                    h.write_u32(approval.approvers);
+                    h.write_u32(approval.expires_in);
+                }
+            }
+        }
+    }
+}
+
+fn hash_opt_u32(h: &mut Fnv64, v: Option<u32>) {
+    match v {
+        None => h.write_u8(0),
+        Some(v) => {
+            h.write_u8(1);
+            h.write_u32(v);
+        }
+    }
+}
+
+pub(crate) fn hash_effect(h: &mut Fnv64, effect: Effect) {
+    match effect {
+        Effect::Allow => h.write_u8(0),
+        Effect::Deny => h.write_u8(1),
+        Effect::Challenge(method) => {
+            h.write_u8(2);
+            match method {
+                ChallengeMethod::Mfa => h.write_u8(0),
+                ChallengeMethod::Reauthenticate => h.write_u8(1),
+                ChallengeMethod::Custom(code) => {
+                    h.write_u8(2);
+                    h.write_u32(code);
+                }
+            }
+        }
+        Effect::Indeterminate => h.write_u8(3),
+    }
+}
+
+fn hash_matcher(h: &mut Fnv64, matcher: &Matcher<'_>) {
+    match matcher {
+        Matcher::Any => h.write_u8(0),
+        Matcher::Exact(s) => {
+            h.write_u8(1);
+            h.write_str(s);
+        }
+        Matcher::OneOf(options) => {
+            h.write_u8(2);
+            h.write_u64(options.len() as u64);
+            for opt in *options {
+                h.write_str(opt);
+            }
+        }
+    }
+}
+
+/// Hash a condition tree in pre-order without recursion.
+fn hash_condition(h: &mut Fnv64, root: &Condition<'_>) {
+    let mut stack = vec![root];
+    while let Some(cond) = stack.pop() {
+        match cond {
+            Condition::True => h.write_u8(0),
+            Condition::False => h.write_u8(1),
+            Condition::Equals { attr, value } => {
+                h.write_u8(2);
+                h.write_str(attr);
+                hash_value(h, value);
+            }
+            Condition::NotEquals { attr, value } => {
+                h.write_u8(3);
+                h.write_str(attr);
+                hash_value(h, value);
+            }
+            Condition::EqualsIgnoreCase { attr, value } => {
+                h.write_u8(22);
+                h.write_str(attr);
+                h.write_str(value);
+            }
+            Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+                h.write_u8(if matches!(cond, Condition::In { .. }) {
+                    14
+                } else {
+                    15
+                });
+                h.write_str(attr);
+                h.write_u64(values.len() as u64);
+                for value in values.iter() {
+                    hash_value(h, value);
+                }
+            }
+            Condition::GreaterThan { attr, value }
+            | Condition::GreaterOrEqual { attr, value }
+            | Condition::LessThan { attr, value }
+            | Condition::LessOrEqual { attr, value } => {
+                h.write_u8(match cond {
+                    Condition::GreaterThan { .. } => 10,
+                    Condition::GreaterOrEqual { .. } => 11,
+                    Condition::LessThan { .. } => 12,
+                    _ => 13,
+                });
+                h.write_str(attr);
+                h.write_u64(*value as u64);
+            }
+            Condition::Between { attr, min, max } => {
+                h.write_u8(23);
+                h.write_str(attr);
+                h.write_u64(*min as u64);
+                h.write_u64(*max as u64);
+            }
+            Condition::IpInCidr { attr, cidr } => {
+                h.write_u8(16);
+                h.write_str(attr);
+                hash_ip(h, cidr.network());
+                h.write_u8(cidr.prefix_len());
+            }
+            Condition::Matches { attr, pattern } => {
+                h.write_u8(26);
+                h.write_str(attr);
+                h.write_str(pattern.as_str());
+            }
+            Condition::TimeOfDayBetween {
+                window,
+                utc_offset_minutes,
+            } => {
+                h.write_u8(24);
+                h.write_u32(window.start as u32);
+                h.write_u32(window.end as u32);
+                h.write_u32(*utc_offset_minutes as u32);
+            }
+            Condition::DayOfWeekIn {
+                days,
+                utc_offset_minutes,
+            } => {
+                h.write_u8(25);
+                h.write_u8(days.bits());
+                h.write_u32(*utc_offset_minutes as u32);
+            }
+            Condition::MemberOf(group) => {
+                h.write_u8(7);
+                h.write_str(group);
+            }
+            Condition::AttrIsPrincipal(attr) => {
+                h.write_u8(8);
+                h.write_str(attr);
+            }
+            Condition::Custom { op, args } => {
+                h.write_u8(9);
+                h.write_str(op);
+                h.write_u64(args.len() as u64);
+                for arg in args.iter() {
+                    h.write_str(arg);
+                }
+            }
+            Condition::Ref(name) => {
+                h.write_u8(21);
+                h.write_str(name);
+            }
+            Condition::And(a, b) => {
+                h.write_u8(4);
+                stack.push(b);
+                stack.push(a);
+            }
+            Condition::Or(a, b) => {
+                h.write_u8(5);
+                stack.push(b);
+                stack.push(a);
+            }
+            Condition::Not(inner) => {
+                h.write_u8(6);
+                stack.push(inner);
+            }
+            Condition::Implies(a, b) => {
+                h.write_u8(19);
+                stack.push(b);
+                stack.push(a);
+            }
+            Condition::Xor(a, b) => {
+                h.write_u8(20);
+                stack.push(b);
+                stack.push(a);
+            }
+            Condition::AllOf(children) | Condition::AnyOf(children) => {
+                h.write_u8(if matches!(cond, Condition::AllOf(_)) {
+                    17
+                } else {
+                    18
+                });
+                h.write_u64(children.len() as u64);
+                stack.extend(children.iter().rev());
+            }
+            Condition::AttrContains { attr, value } => {
+                h.write_u8(27);
+                h.write_str(attr);
+                hash_value(h, value);
+            }
+            Condition::HasFlags { attr, mask } => {
+                h.write_u8(28);
+                h.write_str(attr);
+                h.write_u64(*mask as u64);
+            }
+            Condition::GlobMatches { attr, pattern } => {
+                h.write_u8(29);
+                h.write_str(attr);
+                h.write_str(pattern);
+            }
+            Condition::Require(attr, inner) => {
+                h.write_u8(30);
+                h.write_str(attr);
+                stack.push(inner);
+            }
+        }
+    }
+}
+
+pub(crate) fn hash_value(h: &mut Fnv64, value: &Value<'_>) {
+    match value {
+        Value::Bool(b) => {
+            h.write_u8(0);
+            h.write_u8(*b as u8);
+        }
+        Value::Int(i) => {
+            h.write_u8(1);
+            h.write_u64(*i as u64);
+        }
+        Value::String(s) => {
+            h.write_u8(2);
+            h.write_str(s);
+        }
+        Value::Secret(b) => {
+            h.write_u8(3);
+            h.write_u64(b.len() as u64);
+            for byte in *b {
+                h.write_u8(*byte);
+            }
+        }
+        Value::Ip(ip) => {
+            h.write_u8(4);
+            hash_ip(h, *ip);
+        }
+        Value::List(list) => {
+            h.write_u8(5);
+            h.write_u64(list.len() as u64);
+            for item in list.iter() {
+                hash_value(h, &item);
+            }
+        }
+        Value::Map(map) => {
+            h.write_u8(6);
+            h.write_u64(map.len() as u64);
+            for (key, value) in map.iter() {
+                h.write_str(key);
+                hash_value(h, &value);
+            }
+        }
+    }
+}
+
+pub(crate) fn hash_ip(h: &mut Fnv64, ip: IpAddr) {
+    match ip {
+        IpAddr::V4(v4) => {
+            h.write_u8(4);
+            for byte in v4.octets() {
+                h.write_u8(byte);
+            }
+        }
+        IpAddr::V6(v6) => {
+            h.write_u8(6);
+            for byte in v6.octets() {
+                h.write_u8(byte);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Target;
+    use crate::types::ReasonCode;
+
+    fn policy(reason: u32) -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("admin"),
+                }),
+                ReasonCode(reason),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fnv_known_vector() {
+        // Reference values for FNV-1a 64.
+        assert_eq!(Fnv64::new().finish(), 0xcbf29ce484222325);
+        let mut h = Fnv64::new();
+        h.write(b"a");
+        assert_eq!(h.finish(), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_fingerprint_stable() {
+        assert_eq!(policy(1).fingerprint(), policy(1).fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_detects_changes() {
+        assert_ne!(policy(1).fingerprint(), policy(2).fingerprint());
+
+        let empty = Policy::new(vec![]).unwrap();
+        let config = PolicyConfig {
+            max_rules: 5,
+            ..PolicyConfig::default()
+        };
+        let limited = Policy::with_config(vec![], config).unwrap();
+        assert_ne!(empty.fingerprint(), limited.fingerprint());
+
+        // Equivalent connectives are still different policies.
+        let with = |cond| {
+            Policy::builder()
+                .rule(Rule::new(
+                    Effect::Allow,
+                    Target::any(),
+                    Some(cond),
+                    ReasonCode(1),
+                ))
+                .build()
+                .unwrap()
+                .fingerprint()
+        };
+        let implies = with(Condition::Implies(
+            Box::new(Condition::True),
+            Box::new(Condition::False),
+        ));
+        let or_not = with(Condition::Or(
+            Box::new(Condition::Not(Box::new(Condition::True))),
+            Box::new(Condition::False),
+        ));
+        let xor = with(Condition::Xor(
+            Box::new(Condition::True),
+            Box::new(Condition::False),
+        ));
+        assert_ne!(implies, or_not);
+        assert_ne!(implies, xor);
+
+        let scoped = |target| {
+            Policy::new(vec![Rule::allow(target, ReasonCode(1))])
+                .unwrap()
+                .fingerprint()
+        };
+        let prod = Target::any().in_environment(Matcher::Exact("prod"));
+        assert_ne!(scoped(Target::any()), scoped(prod));
+    }
+}
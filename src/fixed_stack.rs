@@ -57,6 +57,7 @@ impl<T, const N: usize> FixedStack<T, N> {
 
     /// Returns the current number of items in the stack.
     #[inline]
+    #[allow(dead_code)] // used by tests and the Kani harnesses
     pub fn len(&self) -> usize {
         self.len
     }
@@ -134,6 +135,76 @@ mod tests {
     }
 }
 
+/// Kani proof harnesses for `FixedStack`.
+///
+/// These live in-crate because `FixedStack` is private. Run with `cargo kani`.
+/// Public-API harnesses live in the `verify/` crate.
+#[cfg(kani)]
+mod proofs {
+    use super::*;
+
+    /// Capacity used by the harnesses. Small enough for CBMC to explore
+    /// every push/pop interleaving exhaustively.
+    const CAP: usize = 4;
+
+    /// Pushing succeeds exactly while `len < N`, and `len` never exceeds `N`.
+    #[kani::proof]
+    #[kani::unwind(7)]
+    fn push_respects_capacity() {
+        let mut stack: FixedStack<u8, CAP> = FixedStack::new();
+        let pushes: usize = kani::any();
+        kani::assume(pushes <= CAP + 1);
+
+        for i in 0..pushes {
+            let result = stack.push(kani::any());
+            if i < CAP {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(PolicyError::EvalStackOverflow { max: CAP })
+                ));
+            }
+            assert!(stack.len() <= CAP);
+        }
+    }
+
+    /// Arbitrary push/pop sequences never read uninitialized slots and
+    /// always return values in LIFO order.
+    #[kani::proof]
+    #[kani::unwind(10)]
+    fn push_pop_is_lifo() {
+        let mut stack: FixedStack<u8, CAP> = FixedStack::new();
+        let mut shadow: [u8; CAP] = [0; CAP];
+        let mut shadow_len: usize = 0;
+
+        let ops: usize = kani::any();
+        kani::assume(ops <= 8);
+
+        for _ in 0..ops {
+            if kani::any() {
+                let value: u8 = kani::any();
+                let pushed = stack.push(value).is_ok();
+                assert_eq!(pushed, shadow_len < CAP);
+                if pushed {
+                    shadow[shadow_len] = value;
+                    shadow_len += 1;
+                }
+            } else {
+                let popped = stack.pop();
+                if shadow_len == 0 {
+                    assert!(popped.is_none());
+                } else {
+                    shadow_len -= 1;
+                    assert_eq!(popped, Some(shadow[shadow_len]));
+                }
+            }
+            assert_eq!(stack.len(), shadow_len);
+            assert_eq!(stack.is_empty(), shadow_len == 0);
+        }
+    }
+}
+
 // ============================================================================
 // SafeFixedStack: zero-unsafe alternative
 // ============================================================================
@@ -0,0 +1,334 @@
+//! Fluent condition construction.
+//!
+//! Chain comparisons and connectives instead of nesting `Box`es by hand:
+//!
+//! ```
+//! use gate0::{Condition, Value};
+//!
+//! let condition = Condition::attr("role")
+//!     .eq("admin")
+//!     .and(Condition::attr("mfa").eq(true))
+//!     .or(!Condition::attr("level").lt(3))
+//!     .build()
+//!     .unwrap();
+//!
+//! let ctx = [("role", Value::String("admin")), ("mfa", Value::Bool(true))];
+//! assert_eq!(condition.evaluate(&ctx), Ok(true));
+//! ```
+//!
+//! The result is the same borrowed `Condition<'a>` a hand-written tree
+//! would be. Depth is checked as the tree grows: the first connective to
+//! exceed `ABSOLUTE_MAX_CONDITION_DEPTH` makes `build()` fail with
+//! `PolicyError::ConditionTooDeep`. Policies with a lower
+//! `PolicyConfig::max_condition_depth` still check it when they are built.
+
+use std::ops::Not;
+
+use crate::cidr::Cidr;
+use crate::condition::{Condition, ABSOLUTE_MAX_CONDITION_DEPTH};
+use crate::error::PolicyError;
+use crate::pattern::Pattern;
+use crate::value::Value;
+
+impl<'a> Condition<'a> {
+    /// Start a comparison on the context attribute `name`.
+    pub fn attr(name: &'a str) -> AttrBuilder<'a> {
+        AttrBuilder { name }
+    }
+}
+
+/// A context attribute awaiting a comparison; see `Condition::attr`.
+#[derive(Debug, Clone, Copy)]
+pub struct AttrBuilder<'a> {
+    name: &'a str,
+}
+
+impl<'a> AttrBuilder<'a> {
+    /// The attribute equals `value`.
+    pub fn eq(self, value: impl Into<Value<'a>>) -> ConditionBuilder<'a> {
+        self.leaf(Condition::Equals {
+            attr: self.name,
+            value: value.into(),
+        })
+    }
+
+    /// The attribute does not equal `value`, or is missing.
+    pub fn ne(self, value: impl Into<Value<'a>>) -> ConditionBuilder<'a> {
+        self.leaf(Condition::NotEquals {
+            attr: self.name,
+            value: value.into(),
+        })
+    }
+
+    /// The attribute is a string equal to `value`, ignoring case.
+    pub fn eq_ignore_case(self, value: &'a str) -> ConditionBuilder<'a> {
+        self.leaf(Condition::EqualsIgnoreCase {
+            attr: self.name,
+            value,
+        })
+    }
+
+    /// The attribute equals one of `values`.
+    pub fn is_in(self, values: &'a [Value<'a>]) -> ConditionBuilder<'a> {
+        self.leaf(Condition::In {
+            attr: self.name,
+            values,
+        })
+    }
+
+    /// The attribute equals none of `values`, or is missing.
+    pub fn not_in(self, values: &'a [Value<'a>]) -> ConditionBuilder<'a> {
+        self.leaf(Condition::NotIn {
+            attr: self.name,
+            values,
+        })
+    }
+
+    /// The attribute is an Int greater than `value`.
+    pub fn gt(self, value: i64) -> ConditionBuilder<'a> {
+        self.leaf(Condition::GreaterThan {
+            attr: self.name,
+            value,
+        })
+    }
+
+    /// The attribute is an Int greater than or equal to `value`.
+    pub fn ge(self, value: i64) -> ConditionBuilder<'a> {
+        self.leaf(Condition::GreaterOrEqual {
+            attr: self.name,
+            value,
+        })
+    }
+
+    /// The attribute is an Int less than `value`.
+    pub fn lt(self, value: i64) -> ConditionBuilder<'a> {
+        self.leaf(Condition::LessThan {
+            attr: self.name,
+            value,
+        })
+    }
+
+    /// The attribute is an Int less than or equal to `value`.
+    pub fn le(self, value: i64) -> ConditionBuilder<'a> {
+        self.leaf(Condition::LessOrEqual {
+            attr: self.name,
+            value,
+        })
+    }
+
+    /// The attribute is an Int from `min` to `max` inclusive.
+    pub fn between(self, min: i64, max: i64) -> ConditionBuilder<'a> {
+        self.leaf(Condition::Between {
+            attr: self.name,
+            min,
+            max,
+        })
+    }
+
+    /// The attribute is an `Ip` in `cidr`.
+    pub fn in_cidr(self, cidr: Cidr) -> ConditionBuilder<'a> {
+        self.leaf(Condition::IpInCidr {
+            attr: self.name,
+            cidr,
+        })
+    }
+
+    /// The attribute is a string matching `pattern`.
+    pub fn matches(self, pattern: Pattern) -> ConditionBuilder<'a> {
+        self.leaf(Condition::Matches {
+            attr: self.name,
+            pattern,
+        })
+    }
+
+    /// The attribute is a string matching the glob `pattern`.
+    pub fn glob(self, pattern: &'a str) -> ConditionBuilder<'a> {
+        self.leaf(Condition::GlobMatches {
+            attr: self.name,
+            pattern,
+        })
+    }
+
+    /// The attribute is a string equal to the request's principal.
+    pub fn is_principal(self) -> ConditionBuilder<'a> {
+        self.leaf(Condition::AttrIsPrincipal(self.name))
+    }
+
+    fn leaf(self, condition: Condition<'a>) -> ConditionBuilder<'a> {
+        ConditionBuilder {
+            condition: Ok(condition),
+            depth: 1,
+        }
+    }
+}
+
+/// A condition under construction; see the module docs.
+///
+/// Negate with `!`. Any `Condition` converts into one, so hand-built
+/// nodes such as `Condition::MemberOf` can be combined too.
+#[derive(Debug, Clone)]
+pub struct ConditionBuilder<'a> {
+    /// The tree so far, or the first error building it.
+    condition: Result<Condition<'a>, PolicyError>,
+    depth: usize,
+}
+
+impl<'a> ConditionBuilder<'a> {
+    /// Both conditions hold.
+    pub fn and(self, other: impl Into<ConditionBuilder<'a>>) -> Self {
+        self.binary(other.into(), Condition::And)
+    }
+
+    /// Either condition holds.
+    pub fn or(self, other: impl Into<ConditionBuilder<'a>>) -> Self {
+        self.binary(other.into(), Condition::Or)
+    }
+
+    /// If this condition holds, so does `other`.
+    pub fn implies(self, other: impl Into<ConditionBuilder<'a>>) -> Self {
+        self.binary(other.into(), Condition::Implies)
+    }
+
+    /// Exactly one of the conditions holds.
+    pub fn xor(self, other: impl Into<ConditionBuilder<'a>>) -> Self {
+        self.binary(other.into(), Condition::Xor)
+    }
+
+    /// This condition, but evaluation fails if `attr` is missing from the
+    /// context (see `Condition::Require`).
+    pub fn require(self, attr: &'a str) -> Self {
+        let depth = self.depth + 1;
+        match self.condition {
+            Ok(inner) => Self::checked(Condition::Require(attr, Box::new(inner)), depth),
+            Err(err) => Self::failed(err, depth),
+        }
+    }
+
+    /// The depth of the tree so far.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Finish, returning the condition or the first error.
+    pub fn build(self) -> Result<Condition<'a>, PolicyError> {
+        self.condition
+    }
+
+    fn binary(
+        self,
+        other: Self,
+        connective: fn(Box<Condition<'a>>, Box<Condition<'a>>) -> Condition<'a>,
+    ) -> Self {
+        let depth = 1 + self.depth.max(other.depth);
+        let condition = match (self.condition, other.condition) {
+            (Ok(left), Ok(right)) => connective(Box::new(left), Box::new(right)),
+            (Err(err), _) | (_, Err(err)) => return Self::failed(err, depth),
+        };
+        Self::checked(condition, depth)
+    }
+
+    fn checked(condition: Condition<'a>, depth: usize) -> Self {
+        if depth > ABSOLUTE_MAX_CONDITION_DEPTH {
+            let err = PolicyError::ConditionTooDeep {
+                max: ABSOLUTE_MAX_CONDITION_DEPTH,
+                actual: depth,
+            };
+            return Self::failed(err, depth);
+        }
+        ConditionBuilder {
+            condition: Ok(condition),
+            depth,
+        }
+    }
+
+    fn failed(err: PolicyError, depth: usize) -> Self {
+        ConditionBuilder {
+            condition: Err(err),
+            depth,
+        }
+    }
+}
+
+impl<'a> Not for ConditionBuilder<'a> {
+    type Output = Self;
+
+    /// The condition does not hold.
+    fn not(self) -> Self {
+        let depth = self.depth + 1;
+        match self.condition {
+            Ok(inner) => Self::checked(Condition::Not(Box::new(inner)), depth),
+            Err(err) => Self::failed(err, depth),
+        }
+    }
+}
+
+impl<'a> From<Condition<'a>> for ConditionBuilder<'a> {
+    fn from(condition: Condition<'a>) -> Self {
+        let depth = condition.depth();
+        Self::checked(condition, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builds_same_tree() {
+        let built = Condition::attr("role")
+            .eq("admin")
+            .and(Condition::attr("mfa").eq(true))
+            .or(!ConditionBuilder::from(Condition::MemberOf("contractors")))
+            .build()
+            .unwrap();
+        let by_hand = Condition::Or(
+            Box::new(Condition::And(
+                Box::new(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("admin"),
+                }),
+                Box::new(Condition::Equals {
+                    attr: "mfa",
+                    value: Value::Bool(true),
+                }),
+            )),
+            Box::new(Condition::Not(Box::new(Condition::MemberOf("contractors")))),
+        );
+        assert_eq!(built, by_hand);
+
+        let set = [Value::Int(1), Value::Int(2)];
+        let c = Condition::attr("level")
+            .ge(1)
+            .xor(Condition::attr("level").is_in(&set));
+        assert_eq!(c.depth(), 2);
+        assert_eq!(
+            c.build().unwrap().evaluate(&[("level", Value::Int(3))]),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_depth_checked_incrementally() {
+        let mut c = Condition::attr("a").eq(true);
+        for _ in 1..ABSOLUTE_MAX_CONDITION_DEPTH {
+            c = !c;
+        }
+        assert_eq!(c.depth(), ABSOLUTE_MAX_CONDITION_DEPTH);
+        assert!(c.clone().build().is_ok());
+
+        // One more level fails, and the error survives further chaining
+        let too_deep = PolicyError::ConditionTooDeep {
+            max: ABSOLUTE_MAX_CONDITION_DEPTH,
+            actual: ABSOLUTE_MAX_CONDITION_DEPTH + 1,
+        };
+        assert_eq!(c.clone().and(true_builder()).build(), Err(too_deep.clone()));
+        assert_eq!(
+            true_builder().or(!c).implies(true_builder()).build(),
+            Err(too_deep)
+        );
+    }
+
+    fn true_builder() -> ConditionBuilder<'static> {
+        Condition::True.into()
+    }
+}
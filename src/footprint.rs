@@ -0,0 +1,318 @@
+//! Policy memory footprint.
+//!
+//! `Policy::memory_footprint` estimates the bytes a built policy occupies,
+//! so embedded users can check a policy fits their budget before flashing
+//! it and server users can track growth from release to release.
+//!
+//! A policy owns its rule table, the boxed nodes of its conditions, and
+//! the lookup tables built at construction; everything else (names,
+//! values, matcher option lists) is borrowed, typically from static data.
+//! Both are reported. Borrowed data referenced from several places, such as
+//! one string literal used by many rules, is counted once.
+//!
+//! Sizes are for the current target: pointers and `usize` are half as
+//! wide on 32-bit platforms. Allocator overhead is not included.
+
+use std::mem::size_of;
+
+use crate::condition::Condition;
+use crate::policy::{Policy, Rule};
+use crate::schedule::TimeWindow;
+use crate::target::Matcher;
+use crate::value::Value;
+
+/// Estimated bytes used by a built policy, by category.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryFootprint {
+    /// The `Policy` value itself.
+    pub policy: usize,
+    /// The rule table, one `Rule` per allocated slot.
+    pub rules: usize,
+    /// Heap-allocated condition nodes: the operands of `And`, `Or`, and
+    /// `Not`, and compiled `Matches` patterns. Each rule's root node is
+    /// part of its `Rule`.
+    pub conditions: usize,
+    /// Borrowed tables: `OneOf` options, `In`/`NotIn` value sets,
+    /// `AllOf`/`AnyOf` children, schedule windows, and custom operator
+    /// arguments.
+    pub matcher_tables: usize,
+    /// Distinct borrowed strings: names, attributes, string values, and
+    /// metadata.
+    pub strings: usize,
+    /// Lookup tables built at construction: the action hierarchy and its
+    /// closure, and the custom operator registry.
+    pub indexes: usize,
+}
+
+impl MemoryFootprint {
+    /// Bytes owned by the policy: dropped with it.
+    pub fn owned(&self) -> usize {
+        self.policy + self.rules + self.conditions + self.indexes
+    }
+
+    /// Bytes the policy borrows.
+    pub fn borrowed(&self) -> usize {
+        self.matcher_tables + self.strings
+    }
+
+    /// Owned and borrowed bytes together.
+    pub fn total(&self) -> usize {
+        self.owned() + self.borrowed()
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Estimate the memory this policy occupies.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut borrowed = Borrowed::default();
+        let mut conditions = 0;
+        for rule in self.rules() {
+            conditions += rule_footprint(rule, &mut borrowed);
+        }
+        for (stronger, weaker) in self.action_hierarchy().implications() {
+            borrowed.string(stronger);
+            borrowed.string(weaker);
+        }
+        let metadata = self.metadata();
+        for field in [
+            metadata.name,
+            metadata.version,
+            metadata.author,
+            metadata.description,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            borrowed.string(field);
+        }
+
+        MemoryFootprint {
+            policy: size_of::<Policy<'_>>(),
+            rules: self.rule_capacity() * size_of::<Rule<'_>>(),
+            conditions,
+            matcher_tables: Borrowed::sum(borrowed.tables),
+            strings: Borrowed::sum(borrowed.strings),
+            indexes: self.implied_actions().heap_bytes() + self.custom_ops().heap_bytes(),
+        }
+    }
+}
+
+/// Record `rule`'s borrowed data, returning the bytes of its boxed
+/// condition nodes and compiled patterns.
+fn rule_footprint(rule: &Rule<'_>, borrowed: &mut Borrowed) -> usize {
+    for matcher in [
+        &rule.target.principal,
+        &rule.target.action,
+        &rule.target.resource,
+        &rule.target.environment,
+    ] {
+        match matcher {
+            Matcher::Any => {}
+            Matcher::Exact(name) => borrowed.string(name),
+            Matcher::OneOf(options) => {
+                borrowed.table(options);
+                for option in options.iter() {
+                    borrowed.string(option);
+                }
+            }
+        }
+    }
+    if let Some(schedule) = &rule.schedule {
+        borrowed.table::<TimeWindow>(schedule.windows);
+        borrowed.string(schedule.clock_attr);
+    }
+    if let Some(flag) = rule.break_glass {
+        borrowed.string(flag);
+    }
+    let Some(root) = &rule.condition else {
+        return 0;
+    };
+
+    // Non-recursive, like `Condition::node_count`.
+    let mut boxed = 0;
+    let mut compiled = 0;
+    let mut stack = vec![root];
+    while let Some(cond) = stack.pop() {
+        match cond {
+            Condition::True
+            | Condition::False
+            | Condition::TimeOfDayBetween { .. }
+            | Condition::DayOfWeekIn { .. } => {}
+            Condition::Equals { attr, value }
+            | Condition::NotEquals { attr, value }
+            | Condition::AttrContains { attr, value } => {
+                borrowed.string(attr);
+                borrowed.value(value);
+            }
+            Condition::EqualsIgnoreCase { attr, value } => {
+                borrowed.string(attr);
+                borrowed.string(value);
+            }
+            Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+                borrowed.string(attr);
+                borrowed.table::<Value<'_>>(values);
+                for value in values.iter() {
+                    borrowed.value(value);
+                }
+            }
+            Condition::GreaterThan { attr, .. }
+            | Condition::GreaterOrEqual { attr, .. }
+            | Condition::LessThan { attr, .. }
+            | Condition::LessOrEqual { attr, .. }
+            | Condition::Between { attr, .. }
+            | Condition::HasFlags { attr, .. }
+            | Condition::IpInCidr { attr, .. } => borrowed.string(attr),
+            Condition::GlobMatches { attr, pattern } => {
+                borrowed.string(attr);
+                borrowed.string(pattern);
+            }
+            Condition::Matches { attr, pattern } => {
+                borrowed.string(attr);
+                compiled += pattern.heap_bytes();
+            }
+            Condition::MemberOf(name) | Condition::AttrIsPrincipal(name) | Condition::Ref(name) => {
+                borrowed.string(name)
+            }
+            Condition::Custom { op, args } => {
+                borrowed.string(op);
+                borrowed.table(args);
+                for arg in args.iter() {
+                    borrowed.string(arg);
+                }
+            }
+            Condition::Not(inner) => {
+                boxed += 1;
+                stack.push(inner);
+            }
+            Condition::Require(attr, inner) => {
+                borrowed.string(attr);
+                boxed += 1;
+                stack.push(inner);
+            }
+            Condition::And(a, b)
+            | Condition::Or(a, b)
+            | Condition::Implies(a, b)
+            | Condition::Xor(a, b) => {
+                boxed += 2;
+                stack.push(b);
+                stack.push(a);
+            }
+            Condition::AllOf(children) | Condition::AnyOf(children) => {
+                borrowed.table::<Condition<'_>>(children);
+                stack.extend(children.iter().rev());
+            }
+        }
+    }
+    boxed * size_of::<Condition<'_>>() + compiled
+}
+
+/// Borrowed regions seen so far, as `(address, bytes)`.
+#[derive(Default)]
+struct Borrowed {
+    strings: Vec<(usize, usize)>,
+    tables: Vec<(usize, usize)>,
+}
+
+impl Borrowed {
+    fn string(&mut self, s: &str) {
+        self.strings.push((s.as_ptr() as usize, s.len()));
+    }
+
+    fn table<T>(&mut self, items: &[T]) {
+        self.tables
+            .push((items.as_ptr() as usize, std::mem::size_of_val(items)));
+    }
+
+    fn value(&mut self, value: &Value<'_>) {
+        match value {
+            Value::String(s) => self.string(s),
+            Value::Secret(bytes) => self.strings.push((bytes.as_ptr() as usize, bytes.len())),
+            _ => {}
+        }
+    }
+
+    /// Total bytes, counting each region once.
+    fn sum(mut regions: Vec<(usize, usize)>) -> usize {
+        regions.sort_unstable();
+        regions.dedup();
+        regions.iter().map(|(_, len)| len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hierarchy::ActionHierarchy;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode};
+
+    const ROLES: &[Value<'static>] = &[Value::String("admin"), Value::String("staff")];
+
+    #[test]
+    fn test_empty_policy() {
+        let footprint = Policy::new(vec![]).unwrap().memory_footprint();
+        assert_eq!(
+            footprint,
+            MemoryFootprint {
+                policy: size_of::<Policy<'_>>(),
+                ..MemoryFootprint::default()
+            }
+        );
+        assert_eq!(footprint.total(), footprint.owned());
+    }
+
+    #[test]
+    fn test_categories() {
+        let action = "read";
+        let target = Target {
+            principal: Matcher::Any,
+            action: Matcher::Exact(action),
+            resource: Matcher::OneOf(&["doc", "wiki"]),
+            environment: Matcher::Any,
+        };
+        let role = Condition::In {
+            attr: "role",
+            values: ROLES,
+        };
+        let cond = Condition::And(
+            Box::new(role),
+            Box::new(Condition::Not(Box::new(Condition::False))),
+        );
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                target.clone(),
+                Some(cond),
+                ReasonCode(1),
+            ))
+            .rule(Rule::deny(target, ReasonCode(2)))
+            .action_hierarchy(ActionHierarchy::new().implies("write", action))
+            .build()
+            .unwrap();
+        let footprint = policy.memory_footprint();
+
+        assert_eq!(
+            footprint.rules,
+            policy.rule_capacity() * size_of::<Rule<'_>>()
+        );
+        // And's two operands and Not's one
+        assert_eq!(footprint.conditions, 3 * size_of::<Condition<'_>>());
+        // The shared option list counts once
+        assert_eq!(
+            footprint.matcher_tables,
+            2 * size_of::<&str>() + 2 * size_of::<Value<'_>>()
+        );
+        // "read" is shared by both rules and the hierarchy
+        let strings = ["read", "doc", "wiki", "role", "admin", "staff", "write"];
+        assert_eq!(
+            footprint.strings,
+            strings.iter().map(|s| s.len()).sum::<usize>()
+        );
+        assert!(footprint.indexes > 0);
+        assert_eq!(
+            footprint.total(),
+            footprint.owned() + footprint.matcher_tables + footprint.strings
+        );
+    }
+}
@@ -0,0 +1,1088 @@
+//! gatelang: a small text format for policies.
+//!
+//! A human-authorable alternative to building rules in Rust. Source text is
+//! parsed into a `PolicyDoc`, which borrows every name from the source and
+//! compiles to a `Policy` with `PolicyDoc::to_policy`.
+//!
+//! ```text
+//! # Comments run to the end of the line.
+//! config {
+//!     max_condition_depth = 8;
+//!     indeterminate_on_error = true;
+//! }
+//!
+//! reason SENSITIVE = 10;
+//! reason STAFF_READ = 1;
+//!
+//! deny any on "salaries.pdf" reason SENSITIVE;
+//! allow ["read", "list"] by any on any if role == "staff" and not suspended == true
+//!     reason STAFF_READ;
+//! challenge mfa "delete" on any if member_of "admins" reason 2;
+//! ```
+//!
+//! Rules read `EFFECT ACTION [by PRINCIPAL] on RESOURCE [in ENVIRONMENT]
+//! [if CONDITION] reason REASON;`, where each of ACTION, PRINCIPAL,
+//! RESOURCE, and ENVIRONMENT is `any`, a string, or a `[list]` of strings,
+//! and PRINCIPAL and ENVIRONMENT default to `any`.
+//! Effects are `allow`, `deny`, and `challenge mfa|reauthenticate|N`.
+//! Conditions combine `attr == literal`, `attr != literal`,
+//! case-insensitive `attr ~= "string"`, integer comparisons `attr > N`,
+//! `>=`, `<`, `<=`, `attr between N and M` (inclusive), bit flags
+//! `attr has_flags N` (every bit of N set), network
+//! matches `attr in cidr "10.0.0.0/8"`, patterns `attr matches "^svc-"`
+//! (see `Pattern`), globs `attr glob "proj-*-prod"`, list membership
+//! `groups contains "admins"`,
+//! `attr == principal`,
+//! `member_of "group"`, `true`, and `false` with `not`, `and`, `xor`, `or`,
+//! `implies`, and parentheses, binding in that order from tightest;
+//! `implies` groups to the right. Attribute names may be dotted paths
+//! into nested context maps, such as `device.posture.compliant` (see
+//! `Value::Map`). `require attr (CONDITION)` binds like `not` and makes
+//! a missing `attr` an evaluation error (see `Condition::Require`).
+//! Literals are strings, integers, `true`, or `false`. Reasons are integers or names declared
+//! with `reason NAME = N;` before use. An optional `metadata` block sets
+//! `name`, `version`, `author`, and `description` strings and an integer
+//! `created_at`.
+//!
+//! Strings are borrowed from the source, so escape sequences are not
+//! supported. The parser is hand-written to keep gate0 dependency-free,
+//! and bounds its own recursion by `ABSOLUTE_MAX_CONDITION_DEPTH`.
+
+use std::fmt;
+
+use crate::cidr::Cidr;
+use crate::condition::{Condition, ABSOLUTE_MAX_CONDITION_DEPTH};
+use crate::error::PolicyError;
+use crate::metadata::PolicyMetadata;
+use crate::pattern::{validate_glob, Pattern};
+use crate::policy::{Policy, PolicyConfig, Rule};
+use crate::target::{Matcher, Target};
+use crate::types::{ChallengeMethod, Effect, ReasonCode};
+use crate::value::Value;
+
+/// A syntax or name-resolution error, with its 1-based source position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line number.
+    pub line: usize,
+    /// Column number, in characters.
+    pub column: usize,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A matcher whose option list is owned by the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatcherDoc<'s> {
+    /// `any`
+    Any,
+    /// `"name"`
+    Exact(&'s str),
+    /// `["a", "b"]`
+    OneOf(Vec<&'s str>),
+}
+
+impl<'s> MatcherDoc<'s> {
+    fn to_matcher(&self) -> Matcher<'_> {
+        match self {
+            MatcherDoc::Any => Matcher::Any,
+            MatcherDoc::Exact(s) => Matcher::Exact(s),
+            MatcherDoc::OneOf(options) => Matcher::OneOf(options),
+        }
+    }
+}
+
+/// A parsed rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleDoc<'s> {
+    /// The rule's effect.
+    pub effect: Effect,
+    /// Principal matcher.
+    pub principal: MatcherDoc<'s>,
+    /// Action matcher.
+    pub action: MatcherDoc<'s>,
+    /// Resource matcher.
+    pub resource: MatcherDoc<'s>,
+    /// Environment matcher.
+    pub environment: MatcherDoc<'s>,
+    /// Optional condition.
+    pub condition: Option<Condition<'s>>,
+    /// Resolved reason code.
+    pub reason: ReasonCode,
+    /// Line the rule starts on.
+    pub line: usize,
+}
+
+/// A parsed policy document.
+#[derive(Debug, Clone)]
+pub struct PolicyDoc<'s> {
+    /// Configuration, defaults overridden by the `config` block.
+    pub config: PolicyConfig,
+    /// Metadata from the `metadata` block.
+    pub metadata: PolicyMetadata<'s>,
+    /// Declared reason names, in declaration order.
+    pub reasons: Vec<(&'s str, ReasonCode)>,
+    /// Rules, in source order.
+    pub rules: Vec<RuleDoc<'s>>,
+}
+
+impl<'s> PolicyDoc<'s> {
+    /// Parse gatelang source.
+    pub fn parse(source: &'s str) -> Result<Self, ParseError> {
+        Parser::new(source)?.document()
+    }
+
+    /// Build and validate the policy.
+    pub fn to_policy(&self) -> Result<Policy<'_>, PolicyError> {
+        let rules = self
+            .rules
+            .iter()
+            .map(|r| {
+                Rule::new(
+                    r.effect,
+                    Target {
+                        principal: r.principal.to_matcher(),
+                        action: r.action.to_matcher(),
+                        resource: r.resource.to_matcher(),
+                        environment: r.environment.to_matcher(),
+                    },
+                    r.condition.clone(),
+                    r.reason,
+                )
+            })
+            .collect();
+        Policy::with_config(rules, self.config)?.with_metadata(self.metadata)
+    }
+
+    /// Look up the declared name of a reason code.
+    pub fn reason_name(&self, code: ReasonCode) -> Option<&'s str> {
+        self.reasons
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(name, _)| *name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tok<'s> {
+    Ident(&'s str),
+    Str(&'s str),
+    Int(i64),
+    Sym(&'static str),
+    Eof,
+}
+
+impl fmt::Display for Tok<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Ident(s) => write!(f, "'{}'", s),
+            Tok::Str(s) => write!(f, "string \"{}\"", s),
+            Tok::Int(i) => write!(f, "integer {}", i),
+            Tok::Sym(s) => write!(f, "'{}'", s),
+            Tok::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "~=", ">=", "<=", ">", "<", "(", ")", "[", "]", ",", ";", "{", "}", "=", "-",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(Tok<'_>, usize, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let (mut line, mut col) = (1, 1);
+    let mut rest = source;
+    loop {
+        // Skip whitespace and comments
+        let mut chars = rest.chars();
+        match chars.next() {
+            None => break,
+            Some('\n') => {
+                line += 1;
+                col = 1;
+                rest = chars.as_str();
+                continue;
+            }
+            Some(c) if c.is_whitespace() => {
+                col += 1;
+                rest = chars.as_str();
+                continue;
+            }
+            Some('#') => {
+                rest = rest.find('\n').map_or("", |i| &rest[i..]);
+                continue;
+            }
+            Some(_) => {}
+        }
+
+        let err = |message: String| ParseError {
+            line,
+            column: col,
+            message,
+        };
+        let (tok, len) = if let Some(body) = rest.strip_prefix('"') {
+            let end = body
+                .find(['"', '\n', '\\'])
+                .ok_or_else(|| err("unterminated string".to_string()))?;
+            match body.as_bytes()[end] {
+                b'"' => (Tok::Str(&body[..end]), end + 2),
+                b'\\' => return Err(err("escape sequences are not supported".to_string())),
+                _ => return Err(err("unterminated string".to_string())),
+            }
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let value = rest[..end]
+                .parse()
+                .map_err(|_| err(format!("integer {} is out of range", &rest[..end])))?;
+            (Tok::Int(value), end)
+        } else if rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            (Tok::Ident(&rest[..end]), end)
+        } else if let Some(sym) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            (Tok::Sym(sym), sym.len())
+        } else {
+            let c = rest.chars().next().unwrap_or(' ');
+            return Err(err(format!("unexpected character '{}'", c)));
+        };
+        tokens.push((tok, line, col));
+        col += rest[..len].chars().count();
+        rest = &rest[len..];
+    }
+    tokens.push((Tok::Eof, line, col));
+    Ok(tokens)
+}
+
+struct Parser<'s> {
+    tokens: Vec<(Tok<'s>, usize, usize)>,
+    pos: usize,
+    reasons: Vec<(&'s str, ReasonCode)>,
+}
+
+impl<'s> Parser<'s> {
+    fn new(source: &'s str) -> Result<Self, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            reasons: Vec::new(),
+        })
+    }
+
+    fn peek(&self) -> Tok<'s> {
+        self.tokens[self.pos].0
+    }
+
+    fn next(&mut self) -> Tok<'s> {
+        let tok = self.peek();
+        if tok != Tok::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn error_here(&self, message: String) -> ParseError {
+        let (_, line, column) = self.tokens[self.pos];
+        ParseError {
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn expected(&self, what: &str) -> ParseError {
+        self.error_here(format!("expected {}, found {}", what, self.peek()))
+    }
+
+    fn eat(&mut self, tok: Tok<'_>) -> bool {
+        if self.peek() == tok {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, tok: Tok<'_>) -> Result<(), ParseError> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            Err(self.expected(&tok.to_string()))
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> Result<&'s str, ParseError> {
+        match self.peek() {
+            Tok::Ident(s) => {
+                self.next();
+                Ok(s)
+            }
+            _ => Err(self.expected(what)),
+        }
+    }
+
+    fn int(&mut self) -> Result<i64, ParseError> {
+        let negative = self.eat(Tok::Sym("-"));
+        match self.next() {
+            Tok::Int(i) => Ok(if negative { -i } else { i }),
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("an integer"))
+            }
+        }
+    }
+
+    fn uint(&mut self, what: &str) -> Result<u64, ParseError> {
+        match self.peek() {
+            Tok::Int(i) => {
+                self.next();
+                Ok(i as u64)
+            }
+            _ => Err(self.expected(what)),
+        }
+    }
+
+    fn document(mut self) -> Result<PolicyDoc<'s>, ParseError> {
+        let mut config = PolicyConfig::default();
+        let mut metadata = PolicyMetadata::default();
+        let mut rules = Vec::new();
+        loop {
+            let line = self.tokens[self.pos].1;
+            match self.peek() {
+                Tok::Eof => break,
+                Tok::Ident("config") => {
+                    self.next();
+                    self.config(&mut config)?;
+                }
+                Tok::Ident("metadata") => {
+                    self.next();
+                    self.metadata(&mut metadata)?;
+                }
+                Tok::Ident("reason") => {
+                    self.next();
+                    self.reason_decl()?;
+                }
+                _ => rules.push(self.rule(line)?),
+            }
+        }
+        Ok(PolicyDoc {
+            config,
+            metadata,
+            reasons: self.reasons,
+            rules,
+        })
+    }
+
+    fn config(&mut self, config: &mut PolicyConfig) -> Result<(), ParseError> {
+        self.expect(Tok::Sym("{"))?;
+        while !self.eat(Tok::Sym("}")) {
+            let key = self.ident("a config key or '}'")?;
+            self.expect(Tok::Sym("="))?;
+            if let "indeterminate_on_error"
+            | "short_circuit"
+            | "strict_types"
+            | "three_valued_logic" = key
+            {
+                let value = match self.next() {
+                    Tok::Ident("true") => true,
+                    Tok::Ident("false") => false,
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.expected("true or false"));
+                    }
+                };
+                match key {
+                    "short_circuit" => config.short_circuit = value,
+                    "strict_types" => config.strict_types = value,
+                    "three_valued_logic" => config.three_valued_logic = value,
+                    _ => config.indeterminate_on_error = value,
+                }
+            } else {
+                let slot = match key {
+                    "max_rules" => &mut config.max_rules,
+                    "max_condition_depth" => &mut config.max_condition_depth,
+                    "max_context_attrs" => &mut config.max_context_attrs,
+                    "max_matcher_options" => &mut config.max_matcher_options,
+                    "max_string_len" => &mut config.max_string_len,
+                    "max_group_lookups" => &mut config.max_group_lookups,
+                    "max_path_depth" => &mut config.max_path_depth,
+                    _ => {
+                        self.pos -= 2;
+                        return Err(self.error_here(format!("unknown config key '{}'", key)));
+                    }
+                };
+                *slot = self.uint("a non-negative integer")? as usize;
+            }
+            self.expect(Tok::Sym(";"))?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&mut self, metadata: &mut PolicyMetadata<'s>) -> Result<(), ParseError> {
+        self.expect(Tok::Sym("{"))?;
+        while !self.eat(Tok::Sym("}")) {
+            let key = self.ident("a metadata key or '}'")?;
+            self.expect(Tok::Sym("="))?;
+            if key == "created_at" {
+                metadata.created_at = Some(self.int()?);
+            } else {
+                let slot = match key {
+                    "name" => &mut metadata.name,
+                    "version" => &mut metadata.version,
+                    "author" => &mut metadata.author,
+                    "description" => &mut metadata.description,
+                    _ => {
+                        self.pos -= 2;
+                        return Err(self.error_here(format!("unknown metadata key '{}'", key)));
+                    }
+                };
+                match self.next() {
+                    Tok::Str(s) => *slot = Some(s),
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.expected("a string"));
+                    }
+                }
+            }
+            self.expect(Tok::Sym(";"))?;
+        }
+        Ok(())
+    }
+
+    fn reason_decl(&mut self) -> Result<(), ParseError> {
+        let name = self.ident("a reason name")?;
+        if self.reasons.iter().any(|(n, _)| *n == name) {
+            self.pos -= 1;
+            return Err(self.error_here(format!("reason '{}' is already declared", name)));
+        }
+        self.expect(Tok::Sym("="))?;
+        let code = self.reason_code()?;
+        self.expect(Tok::Sym(";"))?;
+        self.reasons.push((name, code));
+        Ok(())
+    }
+
+    fn reason_code(&mut self) -> Result<ReasonCode, ParseError> {
+        let value = self.uint("a reason code")?;
+        u32::try_from(value).map(ReasonCode).map_err(|_| {
+            self.pos -= 1;
+            self.error_here(format!("reason code {} does not fit in u32", value))
+        })
+    }
+
+    fn rule(&mut self, line: usize) -> Result<RuleDoc<'s>, ParseError> {
+        let effect = match self.next() {
+            Tok::Ident("allow") => Effect::Allow,
+            Tok::Ident("deny") => Effect::Deny,
+            Tok::Ident("challenge") => Effect::Challenge(match self.peek() {
+                Tok::Ident("mfa") => {
+                    self.next();
+                    ChallengeMethod::Mfa
+                }
+                Tok::Ident("reauthenticate") => {
+                    self.next();
+                    ChallengeMethod::Reauthenticate
+                }
+                Tok::Int(_) => ChallengeMethod::Custom(self.reason_code()?.0),
+                _ => return Err(self.expected("mfa, reauthenticate, or a method code")),
+            }),
+            _ => {
+                self.pos -= 1;
+                return Err(self.expected("allow, deny, challenge, config, or reason"));
+            }
+        };
+
+        let action = self.matcher()?;
+        let principal = if self.eat(Tok::Ident("by")) {
+            self.matcher()?
+        } else {
+            MatcherDoc::Any
+        };
+        self.expect(Tok::Ident("on"))?;
+        let resource = self.matcher()?;
+        let environment = if self.eat(Tok::Ident("in")) {
+            self.matcher()?
+        } else {
+            MatcherDoc::Any
+        };
+        let condition = if self.eat(Tok::Ident("if")) {
+            Some(self.implies(0)?)
+        } else {
+            None
+        };
+        self.expect(Tok::Ident("reason"))?;
+        let reason = match self.peek() {
+            Tok::Ident(name) => {
+                let code = self.reasons.iter().find(|(n, _)| *n == name);
+                let code = code
+                    .map(|(_, c)| *c)
+                    .ok_or_else(|| self.error_here(format!("undeclared reason '{}'", name)))?;
+                self.next();
+                code
+            }
+            _ => self.reason_code()?,
+        };
+        self.expect(Tok::Sym(";"))?;
+        Ok(RuleDoc {
+            effect,
+            principal,
+            action,
+            resource,
+            environment,
+            condition,
+            reason,
+            line,
+        })
+    }
+
+    fn matcher(&mut self) -> Result<MatcherDoc<'s>, ParseError> {
+        match self.next() {
+            Tok::Ident("any") => Ok(MatcherDoc::Any),
+            Tok::Str(s) => Ok(MatcherDoc::Exact(s)),
+            Tok::Sym("[") => {
+                let mut options = Vec::new();
+                loop {
+                    match self.next() {
+                        Tok::Str(s) => options.push(s),
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.expected("a string"));
+                        }
+                    }
+                    if self.eat(Tok::Sym("]")) {
+                        return Ok(MatcherDoc::OneOf(options));
+                    }
+                    self.expect(Tok::Sym(","))?;
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("any, a string, or a [list]"))
+            }
+        }
+    }
+
+    /// `a implies b implies c` reads as `a implies (b implies c)`.
+    fn implies(&mut self, depth: usize) -> Result<Condition<'s>, ParseError> {
+        let mut operands = vec![self.or(depth)?];
+        while self.eat(Tok::Ident("implies")) {
+            operands.push(self.or(depth)?);
+        }
+        let mut right = operands.pop().ok_or_else(|| self.expected("a condition"))?;
+        while let Some(left) = operands.pop() {
+            right = Condition::Implies(Box::new(left), Box::new(right));
+        }
+        Ok(right)
+    }
+
+    fn or(&mut self, depth: usize) -> Result<Condition<'s>, ParseError> {
+        let mut left = self.xor(depth)?;
+        while self.eat(Tok::Ident("or")) {
+            let right = self.xor(depth)?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn xor(&mut self, depth: usize) -> Result<Condition<'s>, ParseError> {
+        let mut left = self.and(depth)?;
+        while self.eat(Tok::Ident("xor")) {
+            let right = self.and(depth)?;
+            left = Condition::Xor(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<Condition<'s>, ParseError> {
+        let mut left = self.unary(depth)?;
+        while self.eat(Tok::Ident("and")) {
+            let right = self.unary(depth)?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<Condition<'s>, ParseError> {
+        if depth >= ABSOLUTE_MAX_CONDITION_DEPTH {
+            return Err(self.error_here("condition is nested too deeply".to_string()));
+        }
+        match self.next() {
+            Tok::Ident("not") => Ok(Condition::Not(Box::new(self.unary(depth + 1)?))),
+            Tok::Ident("require") => match self.next() {
+                Tok::Ident(attr) => Ok(Condition::Require(attr, Box::new(self.unary(depth + 1)?))),
+                _ => {
+                    self.pos -= 1;
+                    Err(self.expected("an attribute name"))
+                }
+            },
+            Tok::Sym("(") => {
+                let inner = self.implies(depth + 1)?;
+                self.expect(Tok::Sym(")"))?;
+                Ok(inner)
+            }
+            Tok::Ident("true") => Ok(Condition::True),
+            Tok::Ident("false") => Ok(Condition::False),
+            Tok::Ident("member_of") => match self.next() {
+                Tok::Str(group) => Ok(Condition::MemberOf(group)),
+                _ => {
+                    self.pos -= 1;
+                    Err(self.expected("a group name string"))
+                }
+            },
+            Tok::Ident(attr) => {
+                let equals = match self.next() {
+                    Tok::Sym("==") => true,
+                    Tok::Sym("!=") => false,
+                    Tok::Sym(op @ (">" | ">=" | "<" | "<=")) => return self.comparison(attr, op),
+                    Tok::Sym("~=") => return self.ignore_case(attr),
+                    Tok::Ident("between") => {
+                        let min = self.int()?;
+                        self.expect(Tok::Ident("and"))?;
+                        let max = self.int()?;
+                        return Ok(Condition::Between { attr, min, max });
+                    }
+                    Tok::Ident("in") => {
+                        self.expect(Tok::Ident("cidr"))?;
+                        return self.cidr(attr);
+                    }
+                    Tok::Ident("matches") => return self.pattern(attr),
+                    Tok::Ident("glob") => return self.glob(attr),
+                    Tok::Ident("has_flags") => {
+                        let mask = self.int()?;
+                        return Ok(Condition::HasFlags { attr, mask });
+                    }
+                    Tok::Ident("contains") => {
+                        let value = self.literal()?;
+                        return Ok(Condition::AttrContains { attr, value });
+                    }
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.expected("a comparison operator"));
+                    }
+                };
+                if self.eat(Tok::Ident("principal")) {
+                    let owned = Condition::AttrIsPrincipal(attr);
+                    return Ok(if equals {
+                        owned
+                    } else {
+                        Condition::Not(Box::new(owned))
+                    });
+                }
+                let value = self.literal()?;
+                Ok(if equals {
+                    Condition::Equals { attr, value }
+                } else {
+                    Condition::NotEquals { attr, value }
+                })
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("a condition"))
+            }
+        }
+    }
+
+    /// The integer bound of `attr OP N`, after the operator.
+    fn comparison(&mut self, attr: &'s str, op: &str) -> Result<Condition<'s>, ParseError> {
+        let value = self.int()?;
+        Ok(match op {
+            ">" => Condition::GreaterThan { attr, value },
+            ">=" => Condition::GreaterOrEqual { attr, value },
+            "<" => Condition::LessThan { attr, value },
+            _ => Condition::LessOrEqual { attr, value },
+        })
+    }
+
+    /// The string of `attr ~= "STRING"`, after the operator.
+    fn ignore_case(&mut self, attr: &'s str) -> Result<Condition<'s>, ParseError> {
+        match self.next() {
+            Tok::Str(value) => Ok(Condition::EqualsIgnoreCase { attr, value }),
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("a string"))
+            }
+        }
+    }
+
+    /// The network of `attr in cidr "NETWORK"`, after `cidr`.
+    fn cidr(&mut self, attr: &'s str) -> Result<Condition<'s>, ParseError> {
+        match self.next() {
+            Tok::Str(text) => match Cidr::parse(text) {
+                Ok(cidr) => Ok(Condition::IpInCidr { attr, cidr }),
+                Err(e) => {
+                    self.pos -= 1;
+                    Err(self.error_here(e.to_string()))
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("a CIDR network string"))
+            }
+        }
+    }
+
+    /// The pattern of `attr matches "PATTERN"`, after `matches`.
+    fn pattern(&mut self, attr: &'s str) -> Result<Condition<'s>, ParseError> {
+        match self.next() {
+            Tok::Str(text) => match Pattern::parse(text) {
+                Ok(pattern) => Ok(Condition::Matches { attr, pattern }),
+                Err(e) => {
+                    self.pos -= 1;
+                    Err(self.error_here(e.to_string()))
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("a pattern string"))
+            }
+        }
+    }
+
+    /// The glob of `attr glob "GLOB"`, after `glob`.
+    fn glob(&mut self, attr: &'s str) -> Result<Condition<'s>, ParseError> {
+        match self.next() {
+            Tok::Str(pattern) => match validate_glob(pattern) {
+                Ok(()) => Ok(Condition::GlobMatches { attr, pattern }),
+                Err(e) => {
+                    self.pos -= 1;
+                    Err(self.error_here(e.to_string()))
+                }
+            },
+            _ => {
+                self.pos -= 1;
+                Err(self.expected("a glob string"))
+            }
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value<'s>, ParseError> {
+        match self.peek() {
+            Tok::Str(s) => {
+                self.next();
+                Ok(Value::String(s))
+            }
+            Tok::Ident("true") => {
+                self.next();
+                Ok(Value::Bool(true))
+            }
+            Tok::Ident("false") => {
+                self.next();
+                Ok(Value::Bool(false))
+            }
+            Tok::Int(_) | Tok::Sym("-") => Ok(Value::Int(self.int()?)),
+            _ => Err(self.expected("a string, integer, true, or false")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Request;
+
+    const SOURCE: &str = r#"
+# Payroll policy
+config {
+    max_condition_depth = 8;
+    indeterminate_on_error = true;
+    strict_types = true;
+}
+
+reason SENSITIVE = 10;
+reason STAFF_READ = 1;
+
+deny any on "salaries.pdf" reason SENSITIVE;
+allow ["read", "list"] by any on any
+    if role == "staff" and not suspended == true or level != -1
+    reason STAFF_READ;
+challenge mfa "delete" by "root" on any in ["staging", "prod"] reason 2;
+"#;
+
+    #[test]
+    fn test_parse_and_compile() {
+        let doc = PolicyDoc::parse(SOURCE).unwrap();
+        assert_eq!(doc.config.max_condition_depth, 8);
+        assert!(doc.config.indeterminate_on_error);
+        assert!(doc.config.strict_types);
+        assert_eq!(doc.rules.len(), 3);
+        assert_eq!(doc.rules[0].line, 12);
+        assert_eq!(doc.rules[1].action, MatcherDoc::OneOf(vec!["read", "list"]));
+        assert_eq!(
+            doc.rules[1].condition,
+            Some(Condition::Or(
+                Box::new(Condition::And(
+                    Box::new(Condition::Equals {
+                        attr: "role",
+                        value: Value::String("staff"),
+                    }),
+                    Box::new(Condition::Not(Box::new(Condition::Equals {
+                        attr: "suspended",
+                        value: Value::Bool(true),
+                    }))),
+                )),
+                Box::new(Condition::NotEquals {
+                    attr: "level",
+                    value: Value::Int(-1),
+                }),
+            ))
+        );
+        assert_eq!(doc.rules[2].effect, Effect::Challenge(ChallengeMethod::Mfa));
+        assert_eq!(doc.rules[0].environment, MatcherDoc::Any);
+        assert_eq!(
+            doc.rules[2].environment,
+            MatcherDoc::OneOf(vec!["staging", "prod"])
+        );
+        assert_eq!(doc.reason_name(ReasonCode(10)), Some("SENSITIVE"));
+
+        let policy = doc.to_policy().unwrap();
+        let ctx = [("role", Value::String("staff"))];
+        let salaries = Request::with_context("alice", "read", "salaries.pdf", &ctx);
+        assert_eq!(policy.evaluate(&salaries).unwrap().reason, ReasonCode(10));
+        let report = Request::with_context("alice", "read", "report.pdf", &ctx);
+        assert!(policy.evaluate(&report).unwrap().is_allow());
+        let delete = Request::new("root", "delete", "report.pdf");
+        assert!(!policy.evaluate(&delete).unwrap().is_challenge());
+        let delete = delete.in_environment("prod");
+        assert!(policy.evaluate(&delete).unwrap().is_challenge());
+    }
+
+    #[test]
+    fn test_parse_comparisons() {
+        let doc = PolicyDoc::parse("allow any on any if level >= 3 and age<-1 or n > 0 reason 1;")
+            .unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::Or(
+                Box::new(Condition::And(
+                    Box::new(Condition::GreaterOrEqual {
+                        attr: "level",
+                        value: 3
+                    }),
+                    Box::new(Condition::LessThan {
+                        attr: "age",
+                        value: -1
+                    }),
+                )),
+                Box::new(Condition::GreaterThan {
+                    attr: "n",
+                    value: 0
+                }),
+            ))
+        );
+
+        let doc = PolicyDoc::parse("allow any on any if client in cidr \"10.0.0.0/8\" reason 1;")
+            .unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::IpInCidr {
+                attr: "client",
+                cidr: Cidr::parse("10.0.0.0/8").unwrap(),
+            })
+        );
+        let err = PolicyDoc::parse("allow any on any if client in cidr \"10.0.0.0/40\" reason 1;")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 36: invalid CIDR network");
+
+        let doc = PolicyDoc::parse("allow any on any if name matches \"^svc-[a-z]*$\" reason 1;")
+            .unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::Matches {
+                attr: "name",
+                pattern: Pattern::parse("^svc-[a-z]*$").unwrap(),
+            })
+        );
+        let err =
+            PolicyDoc::parse("allow any on any if name matches \"a+\" reason 1;").unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 34: invalid pattern");
+
+        let doc =
+            PolicyDoc::parse("allow any on any if device.posture.compliant == true reason 1;")
+                .unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::Equals {
+                attr: "device.posture.compliant",
+                value: Value::Bool(true),
+            })
+        );
+
+        let doc =
+            PolicyDoc::parse("allow any on any if project glob \"proj-*-prod\" reason 1;").unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::GlobMatches {
+                attr: "project",
+                pattern: "proj-*-prod",
+            })
+        );
+        let err =
+            PolicyDoc::parse("allow any on any if name glob \"?????????\" reason 1;").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 31: glob pattern exceeds maximum wildcards of 8, got 9"
+        );
+
+        let doc = PolicyDoc::parse(
+            "allow any on any if require user_id (user_id == principal) and mfa == true reason 1;",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::And(
+                Box::new(Condition::Require(
+                    "user_id",
+                    Box::new(Condition::AttrIsPrincipal("user_id")),
+                )),
+                Box::new(Condition::Equals {
+                    attr: "mfa",
+                    value: Value::Bool(true),
+                }),
+            ))
+        );
+
+        let doc = PolicyDoc::parse("allow any on any if caps has_flags 6 reason 1;").unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::HasFlags {
+                attr: "caps",
+                mask: 6,
+            })
+        );
+
+        let doc =
+            PolicyDoc::parse("allow any on any if groups contains \"admins\" reason 1;").unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::AttrContains {
+                attr: "groups",
+                value: Value::String("admins"),
+            })
+        );
+
+        let err = PolicyDoc::parse("allow any on any if level <= \"high\" reason 1;").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 30: expected an integer, found string \"high\""
+        );
+
+        let doc = PolicyDoc::parse("allow any on any if risk between 0 and 40 and n > 0 reason 1;")
+            .unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::And(
+                Box::new(Condition::Between {
+                    attr: "risk",
+                    min: 0,
+                    max: 40
+                }),
+                Box::new(Condition::GreaterThan {
+                    attr: "n",
+                    value: 0
+                }),
+            ))
+        );
+        let err =
+            PolicyDoc::parse("allow any on any if risk between 0 or 40 reason 1;").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 36: expected 'and', found 'or'"
+        );
+
+        let doc =
+            PolicyDoc::parse("allow any on any if email ~= \"Bob@Example.com\" reason 1;").unwrap();
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::EqualsIgnoreCase {
+                attr: "email",
+                value: "Bob@Example.com",
+            })
+        );
+        let err = PolicyDoc::parse("allow any on any if email ~= 3 reason 1;").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 30: expected a string, found integer 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_implies_and_xor() {
+        let doc = PolicyDoc::parse(
+            "allow any on any if managed == false implies mfa == true or a == 1 xor b == 2 \
+             implies true reason 1;",
+        )
+        .unwrap();
+        let equals = |attr, value| {
+            Box::new(Condition::Equals {
+                attr,
+                value: Value::Int(value),
+            })
+        };
+        assert_eq!(
+            doc.rules[0].condition,
+            Some(Condition::Implies(
+                Box::new(Condition::Equals {
+                    attr: "managed",
+                    value: Value::Bool(false),
+                }),
+                Box::new(Condition::Implies(
+                    Box::new(Condition::Or(
+                        Box::new(Condition::Equals {
+                            attr: "mfa",
+                            value: Value::Bool(true),
+                        }),
+                        Box::new(Condition::Xor(equals("a", 1), equals("b", 2))),
+                    )),
+                    Box::new(Condition::True),
+                )),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |source: &str| PolicyDoc::parse(source).unwrap_err().to_string();
+
+        assert_eq!(
+            err("deny any on \"x\" reason NOPE;"),
+            "line 1, column 24: undeclared reason 'NOPE'"
+        );
+        assert_eq!(
+            err("allow \"read\"\n  at any reason 1;"),
+            "line 2, column 3: expected 'on', found 'at'"
+        );
+        assert_eq!(
+            err("config { max_roles = 1; }"),
+            "line 1, column 10: unknown config key 'max_roles'"
+        );
+        assert_eq!(
+            err("allow any on \"a\\\"b\" reason 1;"),
+            "line 1, column 14: escape sequences are not supported"
+        );
+        assert_eq!(
+            err("permit any on any reason 1;"),
+            "line 1, column 1: expected allow, deny, challenge, config, or reason, found 'permit'"
+        );
+
+        let deep = format!(
+            "allow any on any if {}true{} reason 1;",
+            "(".repeat(ABSOLUTE_MAX_CONDITION_DEPTH),
+            ")".repeat(ABSOLUTE_MAX_CONDITION_DEPTH)
+        );
+        assert!(err(&deep).ends_with("condition is nested too deeply"));
+    }
+}
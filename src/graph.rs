@@ -0,0 +1,448 @@
+//! Policy visualization.
+//!
+//! Renders a policy as a Graphviz DOT or Mermaid flowchart for audits. Each
+//! rule is a node colored by effect, linked to its target and to its
+//! condition tree. Dashed "overrides" edges run from each Deny or
+//! break-glass rule to every rule it beats whose target it overlaps,
+//! showing where deny-overrides can take effect.
+
+use std::fmt::Write;
+
+use crate::condition::Condition;
+use crate::policy::{Policy, Rule};
+use crate::target::{Matcher, Target};
+use crate::types::Effect;
+use crate::value::Value;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Rule(Effect),
+    Target,
+    Condition,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    Target,
+    Condition,
+    Overrides,
+}
+
+struct Graph {
+    nodes: Vec<(String, String, NodeKind)>,
+    edges: Vec<(String, String, EdgeKind)>,
+}
+
+/// Render `policy` as a Graphviz DOT digraph.
+pub fn to_dot(policy: &Policy<'_>) -> String {
+    let graph = build(policy);
+    let mut out =
+        String::from("digraph policy {\n  rankdir=LR;\n  node [fontname=\"monospace\"];\n");
+    for (id, label, kind) in &graph.nodes {
+        let style = match kind {
+            NodeKind::Rule(effect) => format!(
+                "shape=box, style=filled, fillcolor=\"{}\"",
+                effect_color(*effect)
+            ),
+            NodeKind::Target => "shape=note".to_string(),
+            NodeKind::Condition => "shape=ellipse".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "  {} [label=\"{}\", {}];",
+            id,
+            dot_escape(label),
+            style
+        );
+    }
+    for (from, to, kind) in &graph.edges {
+        let attrs = match kind {
+            EdgeKind::Target => " [label=\"target\"]",
+            EdgeKind::Condition => "",
+            EdgeKind::Overrides => " [label=\"overrides\", style=dashed, color=\"#c0392b\"]",
+        };
+        let _ = writeln!(out, "  {} -> {}{};", from, to, attrs);
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render `policy` as a Mermaid flowchart.
+pub fn to_mermaid(policy: &Policy<'_>) -> String {
+    let graph = build(policy);
+    let mut out = String::from("flowchart LR\n");
+    for (id, label, kind) in &graph.nodes {
+        let label = mermaid_escape(label);
+        let _ = match kind {
+            NodeKind::Rule(_) => writeln!(out, "  {}[\"{}\"]", id, label),
+            NodeKind::Target => writeln!(out, "  {}>\"{}\"]", id, label),
+            NodeKind::Condition => writeln!(out, "  {}([\"{}\"])", id, label),
+        };
+    }
+    for (from, to, kind) in &graph.edges {
+        let arrow = match kind {
+            EdgeKind::Target => "-->|target|",
+            EdgeKind::Condition => "-->",
+            EdgeKind::Overrides => "-.->|overrides|",
+        };
+        let _ = writeln!(out, "  {} {} {}", from, arrow, to);
+    }
+    for (id, _, kind) in &graph.nodes {
+        if let NodeKind::Rule(effect) = kind {
+            let _ = writeln!(out, "  style {} fill:{}", id, effect_color(*effect));
+        }
+    }
+    out
+}
+
+/// A rule that wins over another whenever both match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Override {
+    /// Index of the winning Deny or break-glass rule.
+    pub rule: usize,
+    /// Index of the rule it overrides.
+    pub overridden: usize,
+}
+
+/// List every pair of rules where one overrides the other and their
+/// targets overlap, ordered by `rule` and then `overridden`.
+///
+/// Conditions are not considered, so an override may never take effect in
+/// practice; these are the places a reviewer should look.
+pub fn overrides(policy: &Policy<'_>) -> Vec<Override> {
+    let rules = policy.rules();
+    let mut found = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        for (other_index, other) in rules.iter().enumerate() {
+            if other_index != index
+                && beats(rule, other)
+                && targets_overlap(&rule.target, &other.target)
+            {
+                found.push(Override {
+                    rule: index,
+                    overridden: other_index,
+                });
+            }
+        }
+    }
+    found
+}
+
+fn build(policy: &Policy<'_>) -> Graph {
+    let mut graph = Graph {
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    let rules = policy.rules();
+    for (index, rule) in rules.iter().enumerate() {
+        let id = format!("r{}", index);
+        graph.nodes.push((
+            id.clone(),
+            rule_label(index, rule),
+            NodeKind::Rule(rule.effect),
+        ));
+
+        let target = format!("t{}", index);
+        graph
+            .nodes
+            .push((target.clone(), target_label(&rule.target), NodeKind::Target));
+        graph.edges.push((id.clone(), target, EdgeKind::Target));
+
+        if let Some(condition) = &rule.condition {
+            let mut next = 0;
+            let root = add_condition(&mut graph, index, &mut next, condition);
+            graph.edges.push((id, root, EdgeKind::Condition));
+        }
+    }
+
+    for o in overrides(policy) {
+        graph.edges.push((
+            format!("r{}", o.rule),
+            format!("r{}", o.overridden),
+            EdgeKind::Overrides,
+        ));
+    }
+    graph
+}
+
+/// Add a condition subtree and return the id of its root.
+///
+/// Recursion is bounded by the validated condition depth.
+fn add_condition(
+    graph: &mut Graph,
+    rule: usize,
+    next: &mut usize,
+    condition: &Condition<'_>,
+) -> String {
+    let id = format!("c{}_{}", rule, next);
+    *next += 1;
+    let label = match condition {
+        Condition::True => "true".to_string(),
+        Condition::False => "false".to_string(),
+        Condition::Equals { attr, value } => format!("{} == {}", attr, value_label(value)),
+        Condition::NotEquals { attr, value } => format!("{} != {}", attr, value_label(value)),
+        Condition::EqualsIgnoreCase { attr, value } => format!("{} ~= {:?}", attr, value),
+        Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+            let values: Vec<String> = values.iter().map(value_label).collect();
+            let op = if matches!(condition, Condition::In { .. }) {
+                "in"
+            } else {
+                "not in"
+            };
+            format!("{} {} [{}]", attr, op, values.join(", "))
+        }
+        Condition::GreaterThan { attr, value } => format!("{} > {}", attr, value),
+        Condition::GreaterOrEqual { attr, value } => format!("{} >= {}", attr, value),
+        Condition::LessThan { attr, value } => format!("{} < {}", attr, value),
+        Condition::LessOrEqual { attr, value } => format!("{} <= {}", attr, value),
+        Condition::Between { attr, min, max } => format!("{} in {}..={}", attr, min, max),
+        Condition::HasFlags { attr, mask } => format!("{} has_flags {:#x}", attr, mask),
+        Condition::IpInCidr { attr, cidr } => format!("{} in {}", attr, cidr),
+        Condition::Matches { attr, pattern } => format!("{} matches {:?}", attr, pattern.as_str()),
+        Condition::GlobMatches { attr, pattern } => format!("{} glob {:?}", attr, pattern),
+        Condition::TimeOfDayBetween {
+            window,
+            utc_offset_minutes,
+        } => format!(
+            "time in {:02}:{:02}..{:02}:{:02} UTC{:+}m",
+            window.start / 60,
+            window.start % 60,
+            window.end / 60,
+            window.end % 60,
+            utc_offset_minutes
+        ),
+        Condition::DayOfWeekIn {
+            days,
+            utc_offset_minutes,
+        } => format!("day in {:07b} UTC{:+}m", days.bits(), utc_offset_minutes),
+        Condition::MemberOf(group) => format!("member_of {:?}", group),
+        Condition::AttrIsPrincipal(attr) => format!("{} == principal", attr),
+        Condition::Custom { op, args } => format!("{}({})", op, args.join(", ")),
+        Condition::Ref(name) => format!("ref {:?}", name),
+        Condition::Not(_) => "NOT".to_string(),
+        Condition::Require(attr, _) => format!("REQUIRE {}", attr),
+        Condition::And(..) => "AND".to_string(),
+        Condition::Or(..) => "OR".to_string(),
+        Condition::Implies(..) => "IMPLIES".to_string(),
+        Condition::Xor(..) => "XOR".to_string(),
+        Condition::AllOf(_) => "ALL OF".to_string(),
+        Condition::AnyOf(_) => "ANY OF".to_string(),
+        Condition::AttrContains { attr, value } => {
+            format!("{} contains {}", attr, value_label(value))
+        }
+    };
+    graph.nodes.push((id.clone(), label, NodeKind::Condition));
+    let children: Vec<&Condition<'_>> = match condition {
+        Condition::Not(inner) | Condition::Require(_, inner) => vec![inner],
+        Condition::And(a, b)
+        | Condition::Or(a, b)
+        | Condition::Implies(a, b)
+        | Condition::Xor(a, b) => vec![a, b],
+        Condition::AllOf(children) | Condition::AnyOf(children) => children.iter().collect(),
+        _ => Vec::new(),
+    };
+    for child in children {
+        let child = add_condition(graph, rule, next, child);
+        graph.edges.push((id.clone(), child, EdgeKind::Condition));
+    }
+    id
+}
+
+/// Whether `rule` wins over `other` when both match.
+fn beats(rule: &Rule<'_>, other: &Rule<'_>) -> bool {
+    if rule.break_glass.is_some() {
+        return other.break_glass.is_none();
+    }
+    rule.effect == Effect::Deny && other.effect != Effect::Deny && other.break_glass.is_none()
+}
+
+fn targets_overlap(a: &Target<'_>, b: &Target<'_>) -> bool {
+    matchers_overlap(&a.principal, &b.principal)
+        && matchers_overlap(&a.action, &b.action)
+        && matchers_overlap(&a.resource, &b.resource)
+        && matchers_overlap(&a.environment, &b.environment)
+}
+
+fn matchers_overlap(a: &Matcher<'_>, b: &Matcher<'_>) -> bool {
+    match (a, b) {
+        (Matcher::Any, _) | (_, Matcher::Any) => true,
+        (Matcher::Exact(x), other) | (other, Matcher::Exact(x)) => other.matches(x),
+        (Matcher::OneOf(xs), other) => xs.iter().any(|x| other.matches(x)),
+    }
+}
+
+fn rule_label(index: usize, rule: &Rule<'_>) -> String {
+    let mut label = format!("#{} {:?} reason {}", index, rule.effect, rule.reason.0);
+    if let Some(flag) = rule.break_glass {
+        let _ = write!(label, "\nbreak-glass: {}", flag);
+    }
+    if rule.schedule.is_some() {
+        label.push_str("\nscheduled");
+    }
+    if let Some(approval) = rule.approval {
+        let _ = write!(label, "\napproval: group {}", approval.approvers);
+    }
+    label
+}
+
+fn target_label(target: &Target<'_>) -> String {
+    let mut label = format!(
+        "principal: {}\naction: {}\nresource: {}",
+        matcher_label(&target.principal),
+        matcher_label(&target.action),
+        matcher_label(&target.resource)
+    );
+    if target.environment != Matcher::Any {
+        let _ = write!(
+            label,
+            "\nenvironment: {}",
+            matcher_label(&target.environment)
+        );
+    }
+    label
+}
+
+fn matcher_label(matcher: &Matcher<'_>) -> String {
+    match matcher {
+        Matcher::Any => "any".to_string(),
+        Matcher::Exact(s) => format!("{:?}", s),
+        Matcher::OneOf(options) => format!("{:?}", options),
+    }
+}
+
+fn value_label(value: &Value<'_>) -> String {
+    match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::String(s) => format!("{:?}", s),
+        Value::Secret(_) => "<redacted>".to_string(),
+        Value::Ip(ip) => ip.to_string(),
+        Value::List(list) => {
+            let values: Vec<String> = list.iter().map(|v| value_label(&v)).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Map(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{:?}: {}", k, value_label(&v)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+fn effect_color(effect: Effect) -> &'static str {
+    match effect {
+        Effect::Allow => "#a9dfbf",
+        Effect::Deny => "#f5b7b1",
+        Effect::Challenge(_) => "#fad7a0",
+        Effect::Indeterminate => "#d5d8dc",
+    }
+}
+
+fn dot_escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn mermaid_escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => out.push_str("#quot;"),
+            '\n' => out.push_str("<br/>"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ReasonCode;
+
+    fn policy() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                Some(Condition::Not(Box::new(Condition::Equals {
+                    attr: "suspended",
+                    value: Value::Bool(true),
+                }))),
+                ReasonCode(1),
+            ))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::OneOf(&["read", "write"]),
+                    resource: Matcher::Exact("vault"),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(2),
+            ))
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("delete"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(3),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = to_dot(&policy());
+        assert!(dot.starts_with("digraph policy {\n"));
+        assert!(dot.contains(
+            "  r1 [label=\"#1 Deny reason 2\", shape=box, style=filled, fillcolor=\"#f5b7b1\"];"
+        ));
+        assert!(dot.contains(
+            "  t1 [label=\"principal: any\\naction: [\\\"read\\\", \\\"write\\\"]\\nresource: \\\"vault\\\"\", shape=note];"
+        ));
+        assert!(dot.contains("  c0_1 [label=\"suspended == true\", shape=ellipse];"));
+        assert!(dot.contains("  r0 -> c0_0;\n"));
+        assert!(dot.contains("  c0_0 -> c0_1;\n"));
+        assert!(dot.contains("  r1 -> r0 [label=\"overrides\""));
+        // The Deny never overlaps the delete rule.
+        assert!(!dot.contains("r1 -> r2"));
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let mermaid = to_mermaid(&policy());
+        assert_eq!(
+            overrides(&policy()),
+            vec![Override {
+                rule: 1,
+                overridden: 0
+            }]
+        );
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("  r0[\"#0 Allow reason 1\"]"));
+        assert!(mermaid
+            .contains("  t2>\"principal: any<br/>action: #quot;delete#quot;<br/>resource: any\"]"));
+        assert!(mermaid.contains("  r1 -.->|overrides| r0"));
+        assert!(mermaid.contains("  style r1 fill:#f5b7b1"));
+    }
+}
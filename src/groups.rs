@@ -0,0 +1,141 @@
+//! Directory-backed group membership.
+//!
+//! `Condition::MemberOf` asks whether the request's principal belongs to a
+//! group. The answer comes from a caller-supplied `GroupProvider`, consulted
+//! at most `PolicyConfig::max_group_lookups` times per request. Answers are
+//! memoized in a fixed-size scratch map, so repeated checks of the same group
+//! cost one lookup and evaluation stays allocation-free.
+
+use crate::error::PolicyError;
+
+/// Number of distinct groups memoized per request.
+const MEMO_SIZE: usize = 16;
+
+/// A provider could not answer a lookup (timeout, unreachable directory).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderError;
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "attribute provider failure")
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Source of group membership, e.g. an LDAP or SCIM directory.
+pub trait GroupProvider {
+    /// Returns whether `principal` is a member of `group`.
+    fn is_member(&self, principal: &str, group: &str) -> Result<bool, ProviderError>;
+}
+
+/// Per-request lookup state: provider, budget, and memo.
+pub(crate) struct GroupLookup<'p, 'g> {
+    provider: Option<&'p dyn GroupProvider>,
+    principal: &'p str,
+    remaining: usize,
+    max: usize,
+    memo: [Option<(&'g str, bool)>; MEMO_SIZE],
+}
+
+impl<'p, 'g> GroupLookup<'p, 'g> {
+    /// Lookups for `principal` against `provider`, bounded by `max`.
+    pub(crate) fn new(
+        provider: Option<&'p dyn GroupProvider>,
+        principal: &'p str,
+        max: usize,
+    ) -> Self {
+        GroupLookup {
+            provider,
+            principal,
+            remaining: max,
+            max,
+            memo: [None; MEMO_SIZE],
+        }
+    }
+
+    /// No provider: every membership check fails.
+    pub(crate) fn none() -> Self {
+        Self::new(None, "", 0)
+    }
+
+    /// The principal lookups are made for; empty for `none()`.
+    pub(crate) fn principal(&self) -> &str {
+        self.principal
+    }
+
+    /// Check membership, consulting the memo before the provider.
+    pub(crate) fn is_member(&mut self, group: &'g str) -> Result<bool, PolicyError> {
+        if let Some((_, member)) = self.memo.iter().flatten().find(|(g, _)| *g == group) {
+            return Ok(*member);
+        }
+        let provider = self.provider.ok_or(PolicyError::GroupLookupFailed)?;
+        if self.remaining == 0 {
+            return Err(PolicyError::GroupLookupBudgetExceeded { max: self.max });
+        }
+        self.remaining -= 1;
+        let member = provider
+            .is_member(self.principal, group)
+            .map_err(|_| PolicyError::GroupLookupFailed)?;
+        // A full memo only costs extra lookups, never correctness.
+        if let Some(slot) = self.memo.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((group, member));
+        }
+        Ok(member)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct Directory {
+        calls: Cell<usize>,
+    }
+
+    impl GroupProvider for Directory {
+        fn is_member(&self, principal: &str, group: &str) -> Result<bool, ProviderError> {
+            self.calls.set(self.calls.get() + 1);
+            match group {
+                "offline" => Err(ProviderError),
+                _ => Ok(principal == "alice" && group == "eng"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_memoized_lookups() {
+        let dir = Directory {
+            calls: Cell::new(0),
+        };
+        let mut lookup = GroupLookup::new(Some(&dir), "alice", 2);
+
+        assert_eq!(lookup.is_member("eng"), Ok(true));
+        assert_eq!(lookup.is_member("eng"), Ok(true));
+        assert_eq!(lookup.is_member("ops"), Ok(false));
+        assert_eq!(dir.calls.get(), 2);
+
+        // Budget spent; memoized answers still available
+        assert_eq!(
+            lookup.is_member("sales"),
+            Err(PolicyError::GroupLookupBudgetExceeded { max: 2 })
+        );
+        assert_eq!(lookup.is_member("ops"), Ok(false));
+    }
+
+    #[test]
+    fn test_failures() {
+        let dir = Directory {
+            calls: Cell::new(0),
+        };
+        let mut lookup = GroupLookup::new(Some(&dir), "alice", 4);
+        assert_eq!(
+            lookup.is_member("offline"),
+            Err(PolicyError::GroupLookupFailed)
+        );
+
+        let mut lookup = GroupLookup::none();
+        assert_eq!(lookup.is_member("eng"), Err(PolicyError::GroupLookupFailed));
+    }
+}
@@ -0,0 +1,214 @@
+//! Action implication.
+//!
+//! An `ActionHierarchy` declares that some actions imply others, e.g.
+//! "admin implies write implies read". Registered on a policy at build
+//! time, it lets an Allow rule for `write` also grant `read` without
+//! duplicating the rule. Only Allow rules are widened: a Deny for `write`
+//! does not deny `read`, and a Challenge for `write` does not challenge it.
+//!
+//! Implications are transitive. The closure is computed once at build time
+//! so evaluation stays allocation-free.
+
+use crate::collation::Collation;
+use crate::error::PolicyError;
+use crate::policy::validate_str;
+use crate::target::Matcher;
+
+/// Maximum number of implications in one hierarchy.
+pub const MAX_ACTION_IMPLICATIONS: usize = 256;
+
+/// Declared implications between actions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActionHierarchy<'a> {
+    implications: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> ActionHierarchy<'a> {
+    /// An empty hierarchy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare that `stronger` implies `weaker`: whoever may `stronger` may `weaker`.
+    pub fn implies(mut self, stronger: &'a str, weaker: &'a str) -> Self {
+        self.implications.push((stronger, weaker));
+        self
+    }
+
+    /// The declared `(stronger, weaker)` pairs, in declaration order.
+    pub fn implications(&self) -> &[(&'a str, &'a str)] {
+        &self.implications
+    }
+
+    /// Returns `true` if no implications are declared.
+    pub fn is_empty(&self) -> bool {
+        self.implications.is_empty()
+    }
+}
+
+/// The transitive closure of a hierarchy: for each implied action, every
+/// action that implies it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ImpliedActions<'a> {
+    hierarchy: ActionHierarchy<'a>,
+    implied_by: Vec<(&'a str, Vec<&'a str>)>,
+}
+
+impl<'a> ImpliedActions<'a> {
+    /// Validate `hierarchy` and compute its closure.
+    pub(crate) fn build(
+        hierarchy: ActionHierarchy<'a>,
+        max_string_len: usize,
+    ) -> Result<Self, PolicyError> {
+        let pairs = hierarchy.implications();
+        if pairs.len() > MAX_ACTION_IMPLICATIONS {
+            return Err(PolicyError::TooManyImplications {
+                max: MAX_ACTION_IMPLICATIONS,
+                actual: pairs.len(),
+            });
+        }
+        for (stronger, weaker) in pairs {
+            validate_str(stronger, max_string_len)?;
+            validate_str(weaker, max_string_len)?;
+        }
+
+        let mut implied_by: Vec<(&'a str, Vec<&'a str>)> = Vec::new();
+        for &(_, weaker) in pairs {
+            if implied_by.iter().any(|(action, _)| *action == weaker) {
+                continue;
+            }
+            // Walk upward from `weaker`; the visited set also stops cycles.
+            let mut stronger: Vec<&'a str> = Vec::new();
+            let mut frontier = vec![weaker];
+            while let Some(action) = frontier.pop() {
+                for &(s, w) in pairs {
+                    if w == action && s != weaker && !stronger.contains(&s) {
+                        stronger.push(s);
+                        frontier.push(s);
+                    }
+                }
+            }
+            implied_by.push((weaker, stronger));
+        }
+        Ok(ImpliedActions {
+            hierarchy,
+            implied_by,
+        })
+    }
+
+    /// Heap bytes held by the declared pairs and the closure.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let pair = std::mem::size_of::<(&str, &str)>();
+        let entry = std::mem::size_of::<(&str, Vec<&str>)>();
+        let closure: usize = self
+            .implied_by
+            .iter()
+            .map(|(_, stronger)| stronger.capacity() * std::mem::size_of::<&str>())
+            .sum();
+        self.hierarchy.implications.capacity() * pair + self.implied_by.capacity() * entry + closure
+    }
+
+    /// The hierarchy this closure was built from.
+    pub(crate) fn hierarchy(&self) -> &ActionHierarchy<'a> {
+        &self.hierarchy
+    }
+
+    /// Whether an Allow rule with `matcher` grants `action`, directly or
+    /// through an action that implies it, comparing names under `collation`.
+    pub(crate) fn grants(&self, matcher: &Matcher<'_>, action: &str, collation: Collation) -> bool {
+        if matcher.matches_with(action, collation) {
+            return true;
+        }
+        self.implied_by
+            .iter()
+            .find(|(implied, _)| collation.eq(implied, action))
+            .is_some_and(|(_, stronger)| {
+                stronger.iter().any(|s| matcher.matches_with(s, collation))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Policy, Rule};
+    use crate::target::Target;
+    use crate::types::{ReasonCode, Request};
+
+    fn grant(action: &'static str, reason: u32) -> Rule<'static> {
+        Rule::allow(
+            Target {
+                principal: Matcher::Exact("alice"),
+                action: Matcher::Exact(action),
+                resource: Matcher::Any,
+                environment: Matcher::Any,
+            },
+            ReasonCode(reason),
+        )
+    }
+
+    #[test]
+    fn test_implied_actions() {
+        let hierarchy = ActionHierarchy::new()
+            .implies("admin", "write")
+            .implies("write", "read")
+            .implies("read", "admin");
+        let closure = ImpliedActions::build(hierarchy, 64).unwrap();
+        let binary = Collation::Binary;
+        assert!(closure.grants(&Matcher::Exact("admin"), "read", binary));
+        assert!(closure.grants(&Matcher::Exact("read"), "write", binary));
+        assert!(!closure.grants(&Matcher::Exact("read"), "delete", binary));
+        let ascii = Collation::AsciiCaseInsensitive;
+        assert!(closure.grants(&Matcher::Exact("Admin"), "READ", ascii));
+    }
+
+    #[test]
+    fn test_hierarchy_widens_allow_only() {
+        let policy = Policy::builder()
+            .action_hierarchy(
+                ActionHierarchy::new()
+                    .implies("admin", "write")
+                    .implies("write", "read"),
+            )
+            .rule(grant("admin", 1))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("write"),
+                    resource: Matcher::Exact("audit.log"),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+
+        let read = Request::new("alice", "read", "doc");
+        assert_eq!(policy.evaluate(&read).unwrap().reason, ReasonCode(1));
+        let read_log = Request::new("alice", "read", "audit.log");
+        assert!(policy.evaluate(&read_log).unwrap().is_allow());
+        let write_log = Request::new("alice", "write", "audit.log");
+        assert!(policy.evaluate(&write_log).unwrap().is_deny());
+        let bob = Request::new("bob", "read", "doc");
+        assert!(policy.evaluate(&bob).unwrap().is_deny());
+
+        let plain = Policy::new(policy.rules().to_vec()).unwrap();
+        assert!(plain.evaluate(&read).unwrap().is_deny());
+        assert_ne!(plain.fingerprint(), policy.fingerprint());
+    }
+
+    #[test]
+    fn test_hierarchy_limits() {
+        let mut hierarchy = ActionHierarchy::new();
+        for _ in 0..=MAX_ACTION_IMPLICATIONS {
+            hierarchy = hierarchy.implies("a", "b");
+        }
+        assert_eq!(
+            Policy::builder().action_hierarchy(hierarchy).build().err(),
+            Some(PolicyError::TooManyImplications {
+                max: MAX_ACTION_IMPLICATIONS,
+                actual: MAX_ACTION_IMPLICATIONS + 1,
+            })
+        );
+    }
+}
@@ -13,6 +13,7 @@
 //! - **Property-Based Testing**: Validates invariants against random inputs.
 //! - **Undefined Behavior Check**: Verified strictly with `cargo miri`.
 //! - **Panic-Free**: Ensured via compile-time analysis and runtime tests.
+//! - **Model Checking**: Kani harnesses in `verify/` prove panic-freedom and bounds.
 //!
 //! See `SECURITY.md` in the repository root for the full security model.
 //!
@@ -22,7 +23,8 @@
 //! - **Determinism**: Ordered evaluation, stable conflict resolution
 //! - **No panics**: All operations return `Result`
 //! - **Explicit errors**: Typed `PolicyError` enum
-//! - **Zero dependencies**: Pure `std` only
+//! - **Zero dependencies**: Pure `std` only (the optional `serde` feature
+//!   adds serde)
 //!
 //! ## Example
 //!
@@ -40,6 +42,7 @@
 //!             principal: Matcher::Exact("blocked_user"),
 //!             action: Matcher::Any,
 //!             resource: Matcher::Any,
+//!             environment: Matcher::Any,
 //!         },
 //!         BLOCKED_USER,
 //!     ))
@@ -48,6 +51,7 @@
 //!             principal: Matcher::Any,
 //!             action: Matcher::Exact("read"),
 //!             resource: Matcher::Any,
+//!             environment: Matcher::Any,
 //!         },
 //!         PUBLIC_READ,
 //!     ))
@@ -66,28 +70,130 @@
 //!
 //! Uses **Deny overrides Allow**:
 //! 1. Evaluate rules in declared order
-//! 2. Collect all matching rules
-//! 3. If any Deny matches → return first Deny's reason
-//! 4. Else if any Allow matches → return first Allow's reason
-//! 5. Else → Deny with `NO_MATCHING_RULE`
+//! 2. Collect all matching rules; an active break-glass rule decides
+//!    immediately with an audit obligation
+//! 3. If any Deny matches → return first Deny's reason, or the one picked
+//!    by `PolicyConfig::deny_aggregation`
+//! 4. Else if a provider failed for a rule set to `ProviderFailure::Deny`
+//!    → Deny with the first such rule's reason
+//! 5. Else if any rule failed to evaluate and `indeterminate_on_error` is
+//!    set, or it is set to `ProviderFailure::Indeterminate` → Indeterminate
+//!    with the first failed rule's reason
+//! 6. Else if any Challenge matches → return first Challenge's method and reason
+//! 7. Else if any Allow matches → return first Allow's reason
+//! 8. Else → Deny with `NO_MATCHING_RULE`
+//!
+//! The decision carries the obligations of every matching rule with its
+//! effect, deduplicated and sorted, up to `PolicyConfig::max_obligations`.
+
+#[macro_use]
+mod macros;
 
+mod anomaly;
+mod attr;
+mod audit;
+mod batch;
+mod cidr;
+mod clock;
+mod collation;
+mod complexity;
+mod compose;
 mod condition;
+mod condition_buf;
+mod coverage;
+mod cursor;
+mod custom;
+mod deadline;
+mod definitions;
+mod denials;
+mod embedded;
 mod error;
+mod fingerprint;
 mod fixed_stack;
+mod fluent;
+mod footprint;
+mod groups;
+mod hierarchy;
+mod manifest;
+mod metadata;
+mod obligation;
+mod observe;
+mod pattern;
 mod policy;
+mod query;
+mod reasons;
+mod redact;
+mod revocation;
+mod schedule;
+mod shard;
+mod simplify;
 mod stats;
 mod target;
+mod tenant;
 mod types;
 mod value;
+mod visit;
+mod warnings;
+
+pub mod canonical;
+pub mod capability;
+pub mod datalog;
+pub mod export;
+pub mod gatelang;
+pub mod graph;
+pub mod matrix;
+pub mod minimize;
+pub mod partial;
+pub mod rbac;
+pub mod replay;
+pub mod report;
+pub mod sql;
+pub mod testkit;
 
 // Public API exports
-pub use condition::Condition;
+pub use anomaly::{Anomaly, AnomalySink, AnomalyState, DenialPattern, MAX_TRACKED_PRINCIPALS};
+pub use attr::{AttrKey, AttrType, ContextBuilder};
+pub use audit::{AuditBuffer, AuditRecord, AuditSampling, AuditSink, TraceEvent, MAX_TRACE_EVENTS};
+pub use cidr::Cidr;
+pub use clock::{Clock, FrozenClock, SystemClock};
+pub use collation::Collation;
+pub use complexity::{MatcherUsage, PolicyStatistics, LARGEST_RULES};
+pub use compose::{ComposedPolicy, Composition};
+pub use condition::{Condition, ABSOLUTE_MAX_CONDITION_DEPTH, ABSOLUTE_MAX_PATH_DEPTH};
+pub use condition_buf::{ConditionBuf, ConditionStorage};
+pub use coverage::{BranchCoverage, ConditionCoverage, CoverageReport, RuleCoverage};
+pub use cursor::{EvalCursor, PolicyCursor};
+pub use custom::{CustomOp, CUSTOM_OP_SCRATCH_LEN, MAX_CUSTOM_OP_ARGS};
+pub use deadline::Deadline;
+pub use denials::{DenialTracker, DENIAL_SKETCH_DEPTH};
 pub use error::PolicyError;
-pub use policy::{Policy, PolicyBuilder, PolicyConfig, Rule};
-pub use stats::EvaluationStats;
+pub use fluent::{AttrBuilder, ConditionBuilder};
+pub use footprint::MemoryFootprint;
+pub use groups::{GroupProvider, ProviderError};
+pub use hierarchy::{ActionHierarchy, MAX_ACTION_IMPLICATIONS};
+pub use manifest::{AttrKind, Manifest, ManifestError};
+pub use metadata::PolicyMetadata;
+pub use obligation::{Approval, Obligation, Obligations, MAX_OBLIGATIONS};
+pub use pattern::{Pattern, MAX_GLOB_WILDCARDS, MAX_PATTERN_STEPS};
+pub use policy::{
+    DenyAggregation, Policy, PolicyBuilder, PolicyConfig, ProviderFailure, Rule,
+    DEFAULT_APPROVAL_ATTR, DEFAULT_OWNER_ATTR, MAX_RULE_TAGS,
+};
+pub use query::MAX_QUERY_EVALUATIONS;
+pub use reasons::{ReasonMap, ReasonUsage};
+pub use redact::Sensitivity;
+pub use revocation::{RevocationList, RevocationSet, DEFAULT_CREDENTIAL_ATTR, MAX_REVOCATIONS};
+pub use schedule::{Days, Schedule, TimeWindow, DEFAULT_CLOCK_ATTR};
+pub use shard::PolicyShardMap;
+pub use stats::{AggregatedStats, EvaluationStats, StatsSnapshot, STATS_BUCKETS};
 pub use target::{Matcher, Target};
-pub use types::{Decision, Effect, ReasonCode, Request, NO_MATCHING_RULE};
-pub use value::Value;
+pub use tenant::{TenantBinding, TenantScopedPolicy, DEFAULT_TENANT_ATTR};
+pub use types::{
+    ChallengeMethod, Decision, Effect, ReasonCode, Request, EVALUATION_FAILED, NO_MATCHING_RULE,
+};
+pub use value::{Value, ValueBuf, ValueList, ValueMap};
+pub use visit::{ConditionVisitor, Walk};
+pub use warnings::{PolicyWarning, Warnings};
 
 #[cfg(test)]
 mod integration_tests {
@@ -113,6 +219,7 @@ mod integration_tests {
                     principal: Matcher::Exact("blocked_user"),
                     action: Matcher::Any,
                     resource: Matcher::Any,
+                    environment: Matcher::Any,
                 },
                 REASON_BLOCKED,
             ))
@@ -132,6 +239,7 @@ mod integration_tests {
                     principal: Matcher::Any,
                     action: Matcher::OneOf(actions),
                     resource: Matcher::Any,
+                    environment: Matcher::Any,
                 },
                 REASON_READ_OK,
             ))
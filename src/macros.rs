@@ -0,0 +1,49 @@
+//! Convenience macros.
+
+/// Build a request context array.
+///
+/// Keys are string slices; values are anything convertible into `Value`
+/// (`bool`, `i64`, `&str`). Expands to a `[(&str, Value); N]` array, so
+/// it borrows like a hand-written literal and allocates nothing.
+///
+/// ```
+/// use gate0::{context, Request, Value};
+///
+/// let ctx = context! { "role" => "admin", "level" => 5, "mfa" => true };
+/// assert_eq!(ctx[1], ("level", Value::Int(5)));
+///
+/// let request = Request::with_context("alice", "read", "doc", &ctx);
+/// assert_eq!(request.context.len(), 3);
+/// ```
+#[macro_export]
+macro_rules! context {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        [$(($key, $crate::Value::from($value))),*]
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn test_context_macro() {
+        let role = String::from("editor");
+        let ctx = context! {
+            "role" => role.as_str(),
+            "level" => -3,
+            "mfa" => false,
+        };
+        assert_eq!(
+            ctx,
+            [
+                ("role", Value::String("editor")),
+                ("level", Value::Int(-3)),
+                ("mfa", Value::Bool(false)),
+            ]
+        );
+
+        let empty: [(&str, Value); 0] = context! {};
+        assert!(empty.is_empty());
+    }
+}
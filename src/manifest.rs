@@ -0,0 +1,435 @@
+//! Policy manifests.
+//!
+//! A `Manifest` bundles everything a service needs to load a policy at
+//! startup: the config, a catalog of reason codes, a schema of the context
+//! attributes the rules may read, and the rules themselves.
+//! `Policy::from_manifest` validates them as a unit, so a rule citing an
+//! undeclared reason or comparing an attribute against the wrong type is
+//! rejected at load time rather than silently never matching.
+//!
+//! gate0 stays dependency-free, so a manifest is plain data; deserialize
+//! into it with whatever format the service already uses.
+
+use std::fmt;
+
+use crate::condition::Condition;
+use crate::error::PolicyError;
+use crate::metadata::PolicyMetadata;
+use crate::policy::{Policy, PolicyConfig, Rule};
+use crate::redact::Sensitivity;
+use crate::schedule::DEFAULT_CLOCK_ATTR;
+use crate::types::ReasonCode;
+use crate::value::Value;
+
+/// The type of a context attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrKind {
+    /// `Value::Bool`.
+    Bool,
+    /// `Value::Int`.
+    Int,
+    /// `Value::String`.
+    String,
+    /// `Value::Secret`.
+    Secret,
+    /// `Value::Ip`.
+    Ip,
+    /// `Value::List`, of any element kind.
+    List,
+    /// `Value::Map`. Conditions read its entries through dotted paths,
+    /// which are declared with their own kinds.
+    Map,
+}
+
+/// Every kind, for attributes a condition reads without constraining
+/// their type.
+const ANY_KIND: &[AttrKind] = &[
+    AttrKind::Bool,
+    AttrKind::Int,
+    AttrKind::String,
+    AttrKind::Secret,
+    AttrKind::Ip,
+    AttrKind::List,
+    AttrKind::Map,
+];
+
+impl AttrKind {
+    /// The kind of `value`.
+    pub fn of(value: &Value<'_>) -> Self {
+        match value {
+            Value::Bool(_) => AttrKind::Bool,
+            Value::Int(_) => AttrKind::Int,
+            Value::String(_) => AttrKind::String,
+            Value::Secret(_) => AttrKind::Secret,
+            Value::Ip(_) => AttrKind::Ip,
+            Value::List(_) => AttrKind::List,
+            Value::Map(_) => AttrKind::Map,
+        }
+    }
+
+    /// The name `Value::type_name` uses for this kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            AttrKind::Bool => "Bool",
+            AttrKind::Int => "Int",
+            AttrKind::String => "String",
+            AttrKind::Secret => "Secret",
+            AttrKind::Ip => "Ip",
+            AttrKind::List => "List",
+            AttrKind::Map => "Map",
+        }
+    }
+}
+
+/// Everything needed to build a policy, validated together.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest<'a> {
+    /// Limits for the policy.
+    pub config: PolicyConfig,
+    /// Descriptive metadata for the policy.
+    pub metadata: PolicyMetadata<'a>,
+    /// Every reason code the rules may use, with its name; also the
+    /// catalog for `Policy::reason_map`.
+    pub reasons: Vec<(&'a str, ReasonCode)>,
+    /// Every context attribute the rules may read, with its type.
+    pub attributes: Vec<(&'a str, AttrKind)>,
+    /// Declared attributes whose values must be redacted in records; see
+    /// `PolicyBuilder::sensitive_attribute`.
+    pub sensitive: Vec<(&'a str, Sensitivity)>,
+    /// The rules, in evaluation order.
+    pub rules: Vec<Rule<'a>>,
+}
+
+impl<'a> Manifest<'a> {
+    /// Look up the declared name of a reason code.
+    pub fn reason_name(&self, code: ReasonCode) -> Option<&'a str> {
+        self.reasons
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(name, _)| *name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<AttrKind> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, kind)| *kind)
+    }
+}
+
+/// Errors loading a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestError<'a> {
+    /// The rules or config failed policy validation.
+    Policy(PolicyError),
+    /// A reason name or code is declared twice.
+    DuplicateReason(&'a str),
+    /// A rule uses a reason code missing from the catalog.
+    UndeclaredReason {
+        /// Index of the rule.
+        rule: usize,
+        /// The undeclared code.
+        reason: ReasonCode,
+    },
+    /// An attribute is declared twice.
+    DuplicateAttribute(&'a str),
+    /// A rule reads an attribute missing from the schema.
+    UndeclaredAttribute {
+        /// Index of the rule.
+        rule: usize,
+        /// The undeclared attribute.
+        attr: &'a str,
+    },
+    /// A rule uses an attribute as a different type than declared.
+    AttributeType {
+        /// Index of the rule.
+        rule: usize,
+        /// The attribute.
+        attr: &'a str,
+        /// The declared type name.
+        expected: &'static str,
+        /// The type name the rule uses.
+        actual: &'static str,
+    },
+    /// An attribute marked sensitive is missing from the schema, or is
+    /// marked twice.
+    SensitiveAttribute(&'a str),
+}
+
+impl fmt::Display for ManifestError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Policy(e) => write!(f, "{}", e),
+            ManifestError::DuplicateReason(name) => {
+                write!(f, "reason '{}' is declared twice", name)
+            }
+            ManifestError::UndeclaredReason { rule, reason } => write!(
+                f,
+                "rule {} uses undeclared reason code {}",
+                rule,
+                reason.value()
+            ),
+            ManifestError::DuplicateAttribute(attr) => {
+                write!(f, "attribute '{}' is declared twice", attr)
+            }
+            ManifestError::UndeclaredAttribute { rule, attr } => {
+                write!(f, "rule {} reads undeclared attribute '{}'", rule, attr)
+            }
+            ManifestError::AttributeType {
+                rule,
+                attr,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "rule {} uses attribute '{}' as {}, but it is declared {}",
+                rule, attr, actual, expected
+            ),
+            ManifestError::SensitiveAttribute(attr) => {
+                write!(
+                    f,
+                    "sensitive attribute '{}' is undeclared or marked twice",
+                    attr
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManifestError<'_> {}
+
+impl From<PolicyError> for ManifestError<'_> {
+    fn from(e: PolicyError) -> Self {
+        ManifestError::Policy(e)
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Build a policy from a manifest, checking every rule against the
+    /// manifest's reason catalog and attribute schema.
+    ///
+    /// Conditions, schedule clocks, and break-glass flags may only read
+    /// declared attributes, and must use them as the declared type.
+    /// A break-glass flag may be declared `Bool` or `String`.
+    pub fn from_manifest(manifest: &Manifest<'a>) -> Result<Policy<'a>, ManifestError<'a>> {
+        for (i, (name, code)) in manifest.reasons.iter().enumerate() {
+            if manifest.reasons[..i]
+                .iter()
+                .any(|(n, c)| n == name || c == code)
+            {
+                return Err(ManifestError::DuplicateReason(name));
+            }
+        }
+        for (i, (name, _)) in manifest.attributes.iter().enumerate() {
+            if manifest.attributes[..i].iter().any(|(n, _)| n == name) {
+                return Err(ManifestError::DuplicateAttribute(name));
+            }
+        }
+        for (i, (name, _)) in manifest.sensitive.iter().enumerate() {
+            if manifest.attribute(name).is_none()
+                || manifest.sensitive[..i].iter().any(|(n, _)| n == name)
+            {
+                return Err(ManifestError::SensitiveAttribute(name));
+            }
+        }
+        for (index, rule) in manifest.rules.iter().enumerate() {
+            check_rule(manifest, index, rule)?;
+        }
+        Ok(
+            Policy::with_config(manifest.rules.clone(), manifest.config)?
+                .with_metadata(manifest.metadata)?
+                .with_sensitive_attributes(manifest.sensitive.clone())?,
+        )
+    }
+}
+
+fn check_rule<'a>(
+    manifest: &Manifest<'a>,
+    index: usize,
+    rule: &Rule<'a>,
+) -> Result<(), ManifestError<'a>> {
+    if manifest.reason_name(rule.reason).is_none() {
+        return Err(ManifestError::UndeclaredReason {
+            rule: index,
+            reason: rule.reason,
+        });
+    }
+    let check = |attr: &'a str, allowed: &[AttrKind]| match manifest.attribute(attr) {
+        None => Err(ManifestError::UndeclaredAttribute { rule: index, attr }),
+        Some(kind) if allowed.contains(&kind) => Ok(()),
+        Some(kind) => Err(ManifestError::AttributeType {
+            rule: index,
+            attr,
+            expected: kind.name(),
+            actual: allowed[0].name(),
+        }),
+    };
+    if let Some(schedule) = &rule.schedule {
+        check(schedule.clock_attr, &[AttrKind::Int])?;
+    }
+    if let Some(flag) = rule.break_glass {
+        check(flag, &[AttrKind::Bool, AttrKind::String])?;
+    }
+    let mut stack: Vec<&Condition<'a>> = rule.condition.iter().collect();
+    while let Some(cond) = stack.pop() {
+        match cond {
+            Condition::Equals { attr, value } | Condition::NotEquals { attr, value } => {
+                check(attr, &[AttrKind::of(value)])?;
+            }
+            Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+                for value in values.iter() {
+                    check(attr, &[AttrKind::of(value)])?;
+                }
+            }
+            Condition::GreaterThan { attr, .. }
+            | Condition::GreaterOrEqual { attr, .. }
+            | Condition::LessThan { attr, .. }
+            | Condition::LessOrEqual { attr, .. }
+            | Condition::Between { attr, .. }
+            | Condition::HasFlags { attr, .. } => check(attr, &[AttrKind::Int])?,
+            Condition::TimeOfDayBetween { .. } | Condition::DayOfWeekIn { .. } => {
+                check(DEFAULT_CLOCK_ATTR, &[AttrKind::Int])?
+            }
+            Condition::IpInCidr { attr, .. } => check(attr, &[AttrKind::Ip])?,
+            Condition::AttrContains { attr, .. } => check(attr, &[AttrKind::List])?,
+            Condition::EqualsIgnoreCase { attr, .. }
+            | Condition::Matches { attr, .. }
+            | Condition::GlobMatches { attr, .. }
+            | Condition::AttrIsPrincipal(attr) => check(attr, &[AttrKind::String])?,
+            // Operators accept any kind; the attributes must still be declared.
+            Condition::Custom { args, .. } => {
+                for arg in args.iter() {
+                    check(arg, ANY_KIND)?;
+                }
+            }
+            Condition::True | Condition::False | Condition::MemberOf(_) | Condition::Ref(_) => {}
+            Condition::Not(inner) => stack.push(inner),
+            Condition::Require(attr, inner) => {
+                check(attr, ANY_KIND)?;
+                stack.push(inner);
+            }
+            Condition::And(a, b)
+            | Condition::Or(a, b)
+            | Condition::Implies(a, b)
+            | Condition::Xor(a, b) => {
+                stack.push(a);
+                stack.push(b);
+            }
+            Condition::AllOf(children) | Condition::AnyOf(children) => {
+                stack.extend(children.iter())
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Target;
+    use crate::types::{Effect, Request};
+
+    fn manifest() -> Manifest<'static> {
+        Manifest {
+            reasons: vec![("STAFF_READ", ReasonCode(1)), ("BLOCKED", ReasonCode(2))],
+            attributes: vec![("role", AttrKind::String), ("suspended", AttrKind::Bool)],
+            rules: vec![
+                Rule::new(
+                    Effect::Allow,
+                    Target::any(),
+                    Some(Condition::Equals {
+                        attr: "role",
+                        value: Value::String("staff"),
+                    }),
+                    ReasonCode(1),
+                ),
+                Rule::new(
+                    Effect::Deny,
+                    Target::any(),
+                    Some(Condition::Equals {
+                        attr: "suspended",
+                        value: Value::Bool(true),
+                    }),
+                    ReasonCode(2),
+                ),
+            ],
+            ..Manifest::default()
+        }
+    }
+
+    #[test]
+    fn test_from_manifest() {
+        let manifest = manifest();
+        let policy = Policy::from_manifest(&manifest).unwrap();
+        let ctx: &[(&str, Value)] = &[("role", Value::String("staff"))];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+        let decision = policy.evaluate(&request).unwrap();
+        assert_eq!(manifest.reason_name(decision.reason), Some("STAFF_READ"));
+    }
+
+    #[test]
+    fn test_manifest_sensitive_attributes() {
+        let mut manifest = manifest();
+        manifest.sensitive.push(("role", Sensitivity::Pii));
+        let policy = Policy::from_manifest(&manifest).unwrap();
+        assert_eq!(policy.sensitivity("role"), Some(Sensitivity::Pii));
+        assert_eq!(policy.sensitivity("suspended"), None);
+
+        for name in ["role", "email"] {
+            let mut bad = manifest.clone();
+            bad.sensitive.push((name, Sensitivity::Secret));
+            assert_eq!(
+                Policy::from_manifest(&bad).err(),
+                Some(ManifestError::SensitiveAttribute(name))
+            );
+        }
+    }
+
+    #[test]
+    fn test_manifest_validation() {
+        let mut bad = manifest();
+        bad.rules[1].reason = ReasonCode(3);
+        assert_eq!(
+            Policy::from_manifest(&bad).err(),
+            Some(ManifestError::UndeclaredReason {
+                rule: 1,
+                reason: ReasonCode(3)
+            })
+        );
+
+        let mut bad = manifest();
+        bad.rules[0].condition = Some(Condition::NotEquals {
+            attr: "suspended",
+            value: Value::String("yes"),
+        });
+        let err = Policy::from_manifest(&bad).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "rule 0 uses attribute 'suspended' as String, but it is declared Bool"
+        );
+
+        let mut bad = manifest();
+        bad.rules[0].condition = Some(Condition::AttrIsPrincipal("owner"));
+        assert_eq!(
+            Policy::from_manifest(&bad).err(),
+            Some(ManifestError::UndeclaredAttribute {
+                rule: 0,
+                attr: "owner"
+            })
+        );
+
+        let mut bad = manifest();
+        bad.reasons.push(("STAFF_READ", ReasonCode(9)));
+        assert_eq!(
+            Policy::from_manifest(&bad).err(),
+            Some(ManifestError::DuplicateReason("STAFF_READ"))
+        );
+
+        let mut bad = manifest();
+        bad.config.max_rules = 1;
+        assert!(matches!(
+            Policy::from_manifest(&bad),
+            Err(ManifestError::Policy(PolicyError::TooManyRules { .. }))
+        ));
+    }
+}
@@ -0,0 +1,359 @@
+//! Effective-permission matrices.
+//!
+//! Evaluates a policy over every combination of a bounded principal,
+//! action, and resource vocabulary and records each decision with its
+//! reason code. Exported as CSV or JSON, the matrix of two policy versions
+//! can be diffed line by line, so reviewers see how the effective
+//! permission surface changed instead of reading rules.
+//!
+//! Cells are ordered principal-major, then action, then resource, in
+//! vocabulary order, so the same vocabularies always produce the same
+//! layout.
+//!
+//! # Example
+//!
+//! ```
+//! use gate0::{Policy, ReasonCode, Rule, Target, Matcher};
+//!
+//! let policy = Policy::builder()
+//!     .rule(Rule::allow(
+//!         Target {
+//!             principal: Matcher::Any,
+//!             action: Matcher::Exact("read"),
+//!             resource: Matcher::Any,
+//!             environment: Matcher::Any,
+//!         },
+//!         ReasonCode(1),
+//!     ))
+//!     .build()
+//!     .unwrap();
+//!
+//! let matrix = policy
+//!     .permission_matrix(&["alice"], &["read", "write"], &["doc"], &[])
+//!     .unwrap();
+//! assert_eq!(
+//!     matrix.to_csv(),
+//!     "principal,action,resource,decision,reason\n\
+//!      alice,read,doc,allow,1\n\
+//!      alice,write,doc,deny,0\n"
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use crate::error::PolicyError;
+use crate::policy::Policy;
+use crate::query::check_query_size;
+use crate::types::{Decision, Effect};
+use crate::value::Value;
+
+/// One principal, action, and resource, and what the policy decided.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixCell<'v> {
+    /// The request principal.
+    pub principal: &'v str,
+    /// The request action.
+    pub action: &'v str,
+    /// The request resource.
+    pub resource: &'v str,
+    /// The decision, or the error evaluation returned.
+    pub outcome: Result<Decision, PolicyError>,
+}
+
+impl MatrixCell<'_> {
+    /// The decision column: `allow`, `deny`, `challenge:<method>`,
+    /// `indeterminate`, or `error`.
+    pub fn decision_label(&self) -> String {
+        match &self.outcome {
+            Ok(decision) => match decision.effect {
+                Effect::Allow => "allow".to_string(),
+                Effect::Deny => "deny".to_string(),
+                Effect::Challenge(method) => format!("challenge:{}", method),
+                Effect::Indeterminate => "indeterminate".to_string(),
+            },
+            Err(_) => "error".to_string(),
+        }
+    }
+
+    /// The reason column: the reason code, or the error message.
+    pub fn reason_label(&self) -> String {
+        match &self.outcome {
+            Ok(decision) => decision.reason.value().to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+}
+
+/// Decisions for every cell of a principal × action × resource grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionMatrix<'v> {
+    cells: Vec<MatrixCell<'v>>,
+}
+
+impl<'v> PermissionMatrix<'v> {
+    /// The cells, principal-major in vocabulary order.
+    pub fn cells(&self) -> &[MatrixCell<'v>] {
+        &self.cells
+    }
+
+    /// The cells whose outcome differs from `other`'s for the same
+    /// principal, action, and resource, as `(self, other)` pairs. Cells
+    /// present in only one matrix are not reported.
+    pub fn changes<'m>(
+        &'m self,
+        other: &'m PermissionMatrix<'_>,
+    ) -> Vec<(&'m MatrixCell<'v>, &'m MatrixCell<'m>)> {
+        self.cells
+            .iter()
+            .filter_map(|cell| {
+                let theirs = other.cells.iter().find(|c| {
+                    c.principal == cell.principal
+                        && c.action == cell.action
+                        && c.resource == cell.resource
+                })?;
+                (theirs.outcome != cell.outcome).then_some((cell, theirs))
+            })
+            .collect()
+    }
+
+    /// Render as CSV with a header row (RFC 4180 quoting).
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("principal,action,resource,decision,reason\n");
+        for cell in &self.cells {
+            let fields = [
+                cell.principal,
+                cell.action,
+                cell.resource,
+                &cell.decision_label(),
+                &cell.reason_label(),
+            ];
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                csv_field(&mut out, field);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render as a JSON array of objects, one cell per line.
+    ///
+    /// Reasons are numbers; for errors the reason is null and the message
+    /// is in an `error` field.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("[");
+        for (i, cell) in self.cells.iter().enumerate() {
+            out.push_str(if i == 0 { "\n  {" } else { ",\n  {" });
+            let _ = write!(
+                out,
+                "\"principal\": {}, \"action\": {}, \"resource\": {}, \"decision\": {}, ",
+                json_string(cell.principal),
+                json_string(cell.action),
+                json_string(cell.resource),
+                json_string(&cell.decision_label()),
+            );
+            match &cell.outcome {
+                Ok(decision) => {
+                    let _ = write!(out, "\"reason\": {}}}", decision.reason.value());
+                }
+                Err(e) => {
+                    let _ = write!(
+                        out,
+                        "\"reason\": null, \"error\": {}}}",
+                        json_string(&e.to_string())
+                    );
+                }
+            }
+        }
+        out.push_str(if self.cells.is_empty() {
+            "]\n"
+        } else {
+            "\n]\n"
+        });
+        out
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Evaluate every principal × action × resource combination.
+    ///
+    /// Every request uses the same `context`. A failed evaluation is
+    /// recorded in its cell rather than aborting the export. Fails only if
+    /// the grid exceeds `MAX_QUERY_EVALUATIONS` cells.
+    pub fn permission_matrix<'v>(
+        &self,
+        principals: &[&'v str],
+        actions: &[&'v str],
+        resources: &[&'v str],
+        context: &[(&str, Value<'_>)],
+    ) -> Result<PermissionMatrix<'v>, PolicyError> {
+        check_query_size(
+            principals
+                .len()
+                .saturating_mul(actions.len())
+                .saturating_mul(resources.len()),
+        )?;
+        let mut cells = Vec::with_capacity(principals.len() * actions.len() * resources.len());
+        for &principal in principals {
+            for &action in actions {
+                let decisions = self.evaluate_batch(principal, action, context, resources);
+                for (&resource, outcome) in resources.iter().zip(decisions) {
+                    cells.push(MatrixCell {
+                        principal,
+                        action,
+                        resource,
+                        outcome,
+                    });
+                }
+            }
+        }
+        Ok(PermissionMatrix { cells })
+    }
+}
+
+fn csv_field(out: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::query::MAX_QUERY_EVALUATIONS;
+    use crate::target::{Matcher, Target};
+    use crate::types::ReasonCode;
+
+    fn policy(write_reason: u32) -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(1),
+            ))
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Exact("alice"),
+                    action: Matcher::Exact("write"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(write_reason),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matrix_layout_and_export() {
+        let matrix = policy(2)
+            .permission_matrix(&["alice", "bob"], &["read", "write"], &["a,b"], &[])
+            .unwrap();
+        assert_eq!(matrix.cells().len(), 4);
+        assert_eq!(
+            matrix.to_csv(),
+            "principal,action,resource,decision,reason\n\
+             alice,read,\"a,b\",allow,1\n\
+             alice,write,\"a,b\",allow,2\n\
+             bob,read,\"a,b\",allow,1\n\
+             bob,write,\"a,b\",deny,0\n"
+        );
+
+        let matrix = policy(2)
+            .permission_matrix(&["al\"ice"], &["write"], &["doc"], &[])
+            .unwrap();
+        assert_eq!(
+            matrix.to_json(),
+            "[\n  {\"principal\": \"al\\\"ice\", \"action\": \"write\", \
+             \"resource\": \"doc\", \"decision\": \"deny\", \"reason\": 0}\n]\n"
+        );
+
+        let empty = policy(2).permission_matrix(&[], &["read"], &["doc"], &[]);
+        assert_eq!(empty.unwrap().to_json(), "[]\n");
+    }
+
+    #[test]
+    fn test_errors_recorded_per_cell() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::MemberOf("staff")),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let matrix = policy
+            .permission_matrix(&["alice"], &["read"], &["doc"], &[])
+            .unwrap();
+        assert_eq!(
+            matrix.to_json(),
+            "[\n  {\"principal\": \"alice\", \"action\": \"read\", \"resource\": \"doc\", \
+             \"decision\": \"error\", \"reason\": null, \
+             \"error\": \"group membership lookup failed\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_changes_between_versions() {
+        let grid = (["alice", "bob"], ["read", "write"], ["doc"]);
+        let old = policy(2)
+            .permission_matrix(&grid.0, &grid.1, &grid.2, &[])
+            .unwrap();
+        let new = policy(3)
+            .permission_matrix(&grid.0, &grid.1, &grid.2, &[])
+            .unwrap();
+        let changes = old.changes(&new);
+        assert_eq!(changes.len(), 1);
+        let (before, after) = changes[0];
+        assert_eq!((before.principal, before.action), ("alice", "write"));
+        assert_eq!(before.reason_label(), "2");
+        assert_eq!(after.reason_label(), "3");
+        assert!(old.changes(&old).is_empty());
+    }
+
+    #[test]
+    fn test_grid_bounded() {
+        let names: Vec<&str> = vec!["x"; 256];
+        let result = policy(2).permission_matrix(&names, &names, &names[..2], &[]);
+        assert_eq!(
+            result.unwrap_err(),
+            PolicyError::QueryTooLarge {
+                max: MAX_QUERY_EVALUATIONS,
+                actual: 256 * 256 * 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Policy metadata.
+//!
+//! Descriptive fields that identify a reviewed policy revision: a name,
+//! a version string, an author, a description, and a creation time. They
+//! are carried on the `Policy`, parsed from gatelang's `metadata` block,
+//! and stamped on every `AuditRecord`, so a logged decision can be tied
+//! back to the revision that made it.
+//!
+//! Metadata never affects decisions and is not part of the fingerprint:
+//! relabelling a policy does not make it a different policy.
+
+use crate::error::PolicyError;
+use crate::policy::validate_str;
+
+/// Descriptive information about a policy revision. Every field is optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PolicyMetadata<'a> {
+    /// Policy name, e.g. `"payments-api"`.
+    pub name: Option<&'a str>,
+    /// Version string, e.g. a release tag or commit id.
+    pub version: Option<&'a str>,
+    /// Who authored or approved this revision.
+    pub author: Option<&'a str>,
+    /// Free-form description.
+    pub description: Option<&'a str>,
+    /// Creation time, in Unix seconds.
+    pub created_at: Option<i64>,
+}
+
+impl<'a> PolicyMetadata<'a> {
+    /// Returns `true` if no field is set.
+    pub fn is_empty(&self) -> bool {
+        *self == PolicyMetadata::default()
+    }
+
+    /// Check every string field against the policy's string limit.
+    pub(crate) fn validate(&self, max_string_len: usize) -> Result<(), PolicyError> {
+        for field in [self.name, self.version, self.author, self.description]
+            .into_iter()
+            .flatten()
+        {
+            validate_str(field, max_string_len)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditBuffer;
+    use crate::gatelang::PolicyDoc;
+    use crate::policy::{Policy, Rule};
+    use crate::target::Target;
+    use crate::types::{ReasonCode, Request};
+
+    #[test]
+    fn test_metadata_travels_to_audit() {
+        let source = r#"
+metadata {
+    name = "payroll";
+    version = "2024.06.1";
+    author = "sec-team";
+    created_at = 1717200000;
+}
+allow "read" on any reason 1;
+"#;
+        let doc = PolicyDoc::parse(source).unwrap();
+        let policy = doc.to_policy().unwrap();
+        assert_eq!(policy.metadata().name, Some("payroll"));
+        assert_eq!(policy.metadata().created_at, Some(1_717_200_000));
+        assert_eq!(policy.metadata().description, None);
+
+        let mut buffer = AuditBuffer::new();
+        let request = Request::new("alice", "read", "doc");
+        policy.evaluate_with_audit(&request, &mut buffer).unwrap();
+        let record = buffer.last().unwrap();
+        assert_eq!(record.policy_name, "payroll");
+        assert_eq!(record.policy_version, "2024.06.1");
+
+        // Relabelling does not change the fingerprint
+        let plain = Policy::new(policy.rules().to_vec()).unwrap();
+        assert!(plain.metadata().is_empty());
+        assert_eq!(plain.fingerprint(), policy.fingerprint());
+    }
+
+    #[test]
+    fn test_metadata_limits() {
+        let long = "x".repeat(300);
+        let result = Policy::builder()
+            .metadata(PolicyMetadata {
+                description: Some(&long),
+                ..PolicyMetadata::default()
+            })
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(PolicyError::StringTooLong {
+                max: 256,
+                actual: 300
+            })
+        );
+    }
+}
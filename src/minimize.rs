@@ -0,0 +1,781 @@
+//! Policy minimization and equivalence checking.
+//!
+//! `Policy::minimize` drops rules that can never affect a decision, then
+//! asks `Policy::check_equivalence` to confirm the result decides every
+//! request exactly like the original.
+//!
+//! The checker is exhaustive over a finite abstraction that is exact for
+//! this policy language: matchers, `Equals`/`NotEquals`, and `In`/`NotIn`
+//! only compare against literals, so each string or attribute only needs
+//! to take every literal the two policies mention, plus one fresh value and "missing".
+//! An attribute compared by order, or matched against a CIDR network, also
+//! takes the Ints or addresses just below and above each of its bounds,
+//! network ends, and literals, which between them land in every interval
+//! those points cut the value space into.
+//! Schedules, time conditions, `MemberOf`, `AttrIsPrincipal`, and `Custom`
+//! depend on inputs outside that abstraction (clocks, directories,
+//! principals, application code), and `EqualsIgnoreCase`, `Matches`,
+//! `GlobMatches`, and any `PolicyConfig::collation` but `Binary` match
+//! strings no literal spells out, and `AttrContains` and `HasFlags` look
+//! into lists and bits the abstraction does not build, so policies using
+//! them are reported `Unknown`.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::collation::Collation;
+use crate::condition::Condition;
+use crate::policy::{Policy, Rule, DEFAULT_APPROVAL_ATTR};
+use crate::target::Matcher;
+use crate::types::{Effect, Request};
+use crate::value::{Value, ValueBuf};
+
+/// Default evaluation budget for `check_equivalence`.
+pub const DEFAULT_EQUIVALENCE_BUDGET: usize = 1 << 16;
+
+/// A request on which two policies disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    /// The request principal.
+    pub principal: String,
+    /// The request action.
+    pub action: String,
+    /// The request resource.
+    pub resource: String,
+    /// The request context.
+    pub context: Vec<(String, ValueBuf)>,
+    /// The request environment.
+    pub environment: Option<String>,
+    /// The token the request was approved with through
+    /// `Policy::evaluate_with_approval`, or `None` for `Policy::evaluate`.
+    pub approval: Option<String>,
+}
+
+/// Result of comparing two policies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    /// The policies return the same result for every request.
+    Proved,
+    /// The policies disagree on this request.
+    Counterexample(Box<Counterexample>),
+    /// The policies use schedules, time conditions, `MemberOf`,
+    /// `AttrIsPrincipal`, `EqualsIgnoreCase`, `Matches`, `GlobMatches`,
+    /// `AttrContains`, `HasFlags`, `Custom`, or a non-binary collation, or
+    /// the request space exceeds the evaluation budget.
+    Unknown,
+}
+
+/// A minimized policy and how it was justified.
+#[derive(Debug)]
+pub struct Minimized<'a> {
+    /// The policy with redundant rules removed.
+    pub policy: Policy<'a>,
+    /// Indices (in the original policy) of the removed rules.
+    pub removed: Vec<usize>,
+    /// The equivalence check between the original and minimized policy.
+    pub proof: Equivalence,
+}
+
+impl<'a> Policy<'a> {
+    /// Remove rules that can never change a decision.
+    ///
+    /// A rule is removed if its condition is `False`, if an earlier rule
+    /// with the same effect and a covering target always matches when it
+    /// does (so it can never be the first of its effect), or if it is an
+    /// Allow or Challenge shadowed by an unconditional Deny. Rules with
+    /// schedules, break-glass flags, approval requirements, time
+    /// conditions, `MemberOf`, `AttrIsPrincipal`, `EqualsIgnoreCase`,
+    /// `Matches`, `GlobMatches`, `AttrContains`, `HasFlags`, or `Custom`
+    /// are never removed.
+    ///
+    /// The result is then checked against the original with
+    /// `DEFAULT_EQUIVALENCE_BUDGET`. If the checker finds a disagreement
+    /// the original rules are kept; `proof` reports what was established.
+    pub fn minimize(&self) -> Minimized<'a> {
+        let rules = self.rules();
+        let mut removed = Vec::new();
+        for (j, rule) in rules.iter().enumerate() {
+            if is_redundant(rules, j, rule, &removed) {
+                removed.push(j);
+            }
+        }
+
+        let kept: Vec<Rule<'a>> = rules
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !removed.contains(i))
+            .map(|(_, rule)| rule.clone())
+            .collect();
+        // A subset of validated rules under the same config always validates.
+        let rebuilt = Policy::with_custom_ops(kept, *self.config(), self.custom_ops().clone())
+            .and_then(|p| p.with_action_hierarchy(self.action_hierarchy().clone()))
+            .and_then(|p| p.with_metadata(*self.metadata()));
+        let Ok(policy) = rebuilt else {
+            return self.unchanged(Equivalence::Unknown);
+        };
+        match self.check_equivalence(&policy, DEFAULT_EQUIVALENCE_BUDGET) {
+            Equivalence::Counterexample(c) => self.unchanged(Equivalence::Counterexample(c)),
+            proof => Minimized {
+                policy,
+                removed,
+                proof,
+            },
+        }
+    }
+
+    fn unchanged(&self, proof: Equivalence) -> Minimized<'a> {
+        Minimized {
+            policy: self.clone(),
+            removed: Vec::new(),
+            proof,
+        }
+    }
+
+    /// Check whether `other` returns the same result as this policy for every request.
+    ///
+    /// At most `max_evaluations` requests are tried; larger request spaces
+    /// are reported as `Unknown`.
+    pub fn check_equivalence(&self, other: &Policy<'_>, max_evaluations: usize) -> Equivalence {
+        let binary = |p: &Policy<'_>| p.config().collation == Collation::Binary;
+        if !binary(self) || !binary(other) {
+            return Equivalence::Unknown;
+        }
+        let mut domain = Domain::default();
+        for rule in self.rules().iter().chain(other.rules()) {
+            if rule.schedule.is_some() || rule.condition.as_ref().is_some_and(reads_principal) {
+                return Equivalence::Unknown;
+            }
+            domain.add_rule(rule);
+        }
+        domain.add_neighbors();
+        let hierarchies = [self.action_hierarchy(), other.action_hierarchy()];
+        for &(stronger, weaker) in hierarchies.iter().flat_map(|h| h.implications()) {
+            for action in [stronger, weaker] {
+                if !domain.actions.contains(&action) {
+                    domain.actions.push(action);
+                }
+            }
+        }
+        let fresh = domain.fresh();
+        let principals = domain.strings(&domain.principals, &fresh);
+        let actions = domain.strings(&domain.actions, &fresh);
+        let resources = domain.strings(&domain.resources, &fresh);
+        // Requests with no environment, plus one per literal and a fresh one
+        // when any rule is scoped to an environment.
+        let mut environments = vec![None];
+        if !domain.environments.is_empty() {
+            let named = domain.strings(&domain.environments, &fresh);
+            environments.extend(named.into_iter().map(Some));
+        }
+        // Each attribute is missing (None) or takes one of its values.
+        let attrs: Vec<(&str, Vec<Option<Value<'_>>>)> = domain
+            .attrs
+            .iter()
+            .map(|(name, values)| {
+                let mut options = vec![None, Some(Value::String(&fresh))];
+                options.extend(values.iter().cloned().map(Some));
+                (*name, options)
+            })
+            .collect();
+        // Requests are evaluated unapproved and, when a rule needs approval,
+        // approved with the fresh token and each literal a condition
+        // compares the approval attribute with.
+        let mut approvals = vec![None];
+        if self
+            .rules()
+            .iter()
+            .chain(other.rules())
+            .any(|rule| rule.approval.is_some())
+        {
+            approvals.push(Some(fresh.as_str()));
+            if let Some((_, values)) = domain
+                .attrs
+                .iter()
+                .find(|(name, _)| *name == DEFAULT_APPROVAL_ATTR)
+            {
+                approvals.extend(values.iter().filter_map(|value| match value {
+                    Value::String(token) if !token.is_empty() => Some(Some(*token)),
+                    _ => None,
+                }));
+            }
+        }
+
+        let total = attrs
+            .iter()
+            .map(|(_, options)| options.len())
+            .chain([
+                principals.len(),
+                actions.len(),
+                resources.len(),
+                environments.len(),
+                approvals.len(),
+            ])
+            .try_fold(1usize, |acc, n| acc.checked_mul(n));
+        if total.is_none_or(|t| t > max_evaluations) {
+            return Equivalence::Unknown;
+        }
+
+        let mut choice = vec![0usize; attrs.len()];
+        let mut context = Vec::with_capacity(attrs.len());
+        loop {
+            context.clear();
+            for ((name, options), &c) in attrs.iter().zip(&choice) {
+                if let Some(value) = &options[c] {
+                    context.push((*name, value.clone()));
+                }
+            }
+            for principal in &principals {
+                for action in &actions {
+                    for (resource, environment) in resources
+                        .iter()
+                        .flat_map(|r| environments.iter().map(move |e| (r, e)))
+                    {
+                        let mut request =
+                            Request::with_context(principal, action, resource, &context);
+                        request.environment = *environment;
+                        for &approval in &approvals {
+                            let decide = |policy: &Policy<'_>| match approval {
+                                Some(token) => policy.evaluate_with_approval(&request, token),
+                                None => policy.evaluate(&request),
+                            };
+                            if decide(self) != decide(other) {
+                                return Equivalence::Counterexample(Box::new(Counterexample {
+                                    principal: principal.to_string(),
+                                    action: action.to_string(),
+                                    resource: resource.to_string(),
+                                    context: context
+                                        .iter()
+                                        .map(|(k, v)| ((*k).to_string(), ValueBuf::from(v)))
+                                        .collect(),
+                                    environment: environment.map(str::to_string),
+                                    approval: approval.map(str::to_string),
+                                }));
+                            }
+                        }
+                    }
+                }
+            }
+            // Advance the odometer over attribute choices.
+            let mut i = 0;
+            loop {
+                if i == choice.len() {
+                    return Equivalence::Proved;
+                }
+                choice[i] += 1;
+                if choice[i] < attrs[i].1.len() {
+                    break;
+                }
+                choice[i] = 0;
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Returns `true` if rule `j` can be dropped given the rules before it.
+fn is_redundant(rules: &[Rule<'_>], j: usize, rule: &Rule<'_>, removed: &[usize]) -> bool {
+    if !is_plain(rule) {
+        return false;
+    }
+    if matches!(rule.condition, Some(Condition::False)) {
+        return true;
+    }
+    let shadowed_by = |other: &Rule<'_>| {
+        is_plain(other)
+            && target_covers(other, rule)
+            && match &other.condition {
+                None => true,
+                Some(c) => rule.condition.as_ref() == Some(c),
+            }
+    };
+    // An earlier kept rule of the same effect always wins the "first" slot.
+    let first_elsewhere = rules[..j].iter().enumerate().any(|(i, other)| {
+        !removed.contains(&i) && other.effect == rule.effect && shadowed_by(other)
+    });
+    // Any unconditional Deny overrides an Allow or Challenge wherever it applies.
+    let overridden = matches!(rule.effect, Effect::Allow | Effect::Challenge(_))
+        && rules.iter().any(|other| {
+            other.effect == Effect::Deny && other.condition.is_none() && shadowed_by(other)
+        });
+    first_elsewhere || overridden
+}
+
+/// Rules whose matching depends only on target and context literals.
+fn is_plain(rule: &Rule<'_>) -> bool {
+    rule.schedule.is_none()
+        && rule.break_glass.is_none()
+        && rule.approval.is_none()
+        && !rule.condition.as_ref().is_some_and(reads_principal)
+}
+
+fn target_covers(outer: &Rule<'_>, inner: &Rule<'_>) -> bool {
+    matcher_covers(&outer.target.principal, &inner.target.principal)
+        && matcher_covers(&outer.target.action, &inner.target.action)
+        && matcher_covers(&outer.target.resource, &inner.target.resource)
+        && matcher_covers(&outer.target.environment, &inner.target.environment)
+}
+
+/// Returns `true` if every value `inner` matches is matched by `outer`.
+fn matcher_covers(outer: &Matcher<'_>, inner: &Matcher<'_>) -> bool {
+    match inner {
+        Matcher::Any => matches!(outer, Matcher::Any),
+        Matcher::Exact(v) => outer.matches(v),
+        Matcher::OneOf(options) => options.iter().all(|v| outer.matches(v)),
+    }
+}
+
+/// Whether `cond` depends on who the principal is, beyond target matching,
+/// on an application-defined operator, on case-insensitive or pattern
+/// matching, on list contents or bit masks, or on the clock.
+fn reads_principal(cond: &Condition<'_>) -> bool {
+    let mut stack = vec![cond];
+    while let Some(c) = stack.pop() {
+        match c {
+            Condition::MemberOf(_)
+            | Condition::AttrIsPrincipal(_)
+            | Condition::EqualsIgnoreCase { .. }
+            | Condition::Matches { .. }
+            | Condition::GlobMatches { .. }
+            | Condition::AttrContains { .. }
+            | Condition::HasFlags { .. }
+            | Condition::TimeOfDayBetween { .. }
+            | Condition::DayOfWeekIn { .. }
+            | Condition::Custom { .. } => return true,
+            Condition::Not(inner) | Condition::Require(_, inner) => stack.push(inner),
+            Condition::And(a, b)
+            | Condition::Or(a, b)
+            | Condition::Implies(a, b)
+            | Condition::Xor(a, b) => {
+                stack.push(a);
+                stack.push(b);
+            }
+            Condition::AllOf(children) | Condition::AnyOf(children) => {
+                stack.extend(children.iter())
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Literals mentioned by the policies under comparison.
+#[derive(Default)]
+struct Domain<'p> {
+    principals: Vec<&'p str>,
+    actions: Vec<&'p str>,
+    resources: Vec<&'p str>,
+    environments: Vec<&'p str>,
+    attrs: Vec<(&'p str, Vec<Value<'p>>)>,
+    /// Attributes compared by order or network, whose values need neighbors.
+    ordered: Vec<&'p str>,
+}
+
+impl<'p> Domain<'p> {
+    fn add_rule(&mut self, rule: &'p Rule<'_>) {
+        add_matcher(&mut self.principals, &rule.target.principal);
+        add_matcher(&mut self.actions, &rule.target.action);
+        add_matcher(&mut self.resources, &rule.target.resource);
+        add_matcher(&mut self.environments, &rule.target.environment);
+        if let Some(flag) = rule.break_glass {
+            // Raised by `true` or any non-empty string (covered by the fresh value).
+            for value in [Value::Bool(true), Value::Bool(false), Value::String("")] {
+                self.add_attr(flag, value);
+            }
+        }
+        let mut stack: Vec<&'p Condition<'_>> = rule.condition.iter().collect();
+        while let Some(c) = stack.pop() {
+            match c {
+                Condition::Equals { attr, value } | Condition::NotEquals { attr, value } => {
+                    self.add_attr(attr, value.clone());
+                }
+                Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+                    for value in values.iter() {
+                        self.add_attr(attr, value.clone());
+                    }
+                }
+                Condition::GreaterThan { attr, value }
+                | Condition::GreaterOrEqual { attr, value }
+                | Condition::LessThan { attr, value }
+                | Condition::LessOrEqual { attr, value } => {
+                    self.add_attr(attr, Value::Int(*value));
+                    self.add_ordered(attr);
+                }
+                Condition::Between { attr, min, max } => {
+                    self.add_attr(attr, Value::Int(*min));
+                    self.add_attr(attr, Value::Int(*max));
+                    self.add_ordered(attr);
+                }
+                Condition::IpInCidr { attr, cidr } => {
+                    self.add_attr(attr, Value::Ip(cidr.network()));
+                    self.add_attr(attr, Value::Ip(cidr.last()));
+                    self.add_ordered(attr);
+                }
+                Condition::Require(attr, inner) => {
+                    // Only presence matters, which the fresh value covers.
+                    if !self.attrs.iter().any(|(name, _)| name == attr) {
+                        self.attrs.push((attr, Vec::new()));
+                    }
+                    stack.push(inner);
+                }
+                Condition::Not(inner) => stack.push(inner),
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push(a);
+                    stack.push(b);
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter())
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_attr(&mut self, attr: &'p str, value: Value<'p>) {
+        match self.attrs.iter_mut().find(|(name, _)| *name == attr) {
+            Some((_, values)) => {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            None => self.attrs.push((attr, vec![value])),
+        }
+    }
+
+    fn add_ordered(&mut self, attr: &'p str) {
+        if !self.ordered.contains(&attr) {
+            self.ordered.push(attr);
+        }
+    }
+
+    /// Add the neighbors of every value of an ordered attribute, so each
+    /// interval between its bounds and literals has a representative.
+    fn add_neighbors(&mut self) {
+        for (name, values) in self.attrs.iter_mut() {
+            if !self.ordered.contains(name) {
+                continue;
+            }
+            let mut neighbors = Vec::new();
+            for value in values.iter() {
+                match value {
+                    Value::Int(i) => {
+                        neighbors.push(Value::Int(i.saturating_sub(1)));
+                        neighbors.push(Value::Int(i.saturating_add(1)));
+                    }
+                    Value::Ip(IpAddr::V4(ip)) => {
+                        let ip = u32::from(*ip);
+                        for n in [ip.saturating_sub(1), ip.saturating_add(1)] {
+                            neighbors.push(Value::Ip(IpAddr::V4(Ipv4Addr::from(n))));
+                        }
+                    }
+                    Value::Ip(IpAddr::V6(ip)) => {
+                        let ip = u128::from(*ip);
+                        for n in [ip.saturating_sub(1), ip.saturating_add(1)] {
+                            neighbors.push(Value::Ip(IpAddr::V6(Ipv6Addr::from(n))));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            for neighbor in neighbors {
+                if !values.contains(&neighbor) {
+                    values.push(neighbor);
+                }
+            }
+        }
+    }
+
+    /// A non-empty string equal to no literal in the domain.
+    fn fresh(&self) -> String {
+        let mut fresh = String::from("~");
+        while self.mentions(&fresh) {
+            fresh.push('~');
+        }
+        fresh
+    }
+
+    fn mentions(&self, s: &str) -> bool {
+        [
+            &self.principals,
+            &self.actions,
+            &self.resources,
+            &self.environments,
+        ]
+        .iter()
+        .any(|list| list.contains(&s))
+            || self
+                .attrs
+                .iter()
+                .any(|(_, values)| values.contains(&Value::String(s)))
+    }
+
+    fn strings<'s>(&self, literals: &[&'s str], fresh: &'s str) -> Vec<&'s str> {
+        let mut out = literals.to_vec();
+        out.push(fresh);
+        out
+    }
+}
+
+fn add_matcher<'p>(out: &mut Vec<&'p str>, matcher: &Matcher<'p>) {
+    let options: &[&'p str] = match matcher {
+        Matcher::Any => &[],
+        Matcher::Exact(v) => std::slice::from_ref(v),
+        Matcher::OneOf(options) => options,
+    };
+    for v in options {
+        if !out.contains(v) {
+            out.push(v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Target;
+    use crate::types::ReasonCode;
+
+    fn target(principal: Matcher<'static>, resource: Matcher<'static>) -> Target<'static> {
+        Target {
+            principal,
+            action: Matcher::Any,
+            resource,
+            environment: Matcher::Any,
+        }
+    }
+
+    fn is_admin() -> Option<Condition<'static>> {
+        Some(Condition::Equals {
+            attr: "role",
+            value: Value::String("admin"),
+        })
+    }
+
+    #[test]
+    fn test_minimize_removes_redundant_rules() {
+        let policy = Policy::builder()
+            // 0: admins may do anything
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                is_admin(),
+                ReasonCode(1),
+            ))
+            // 1: subsumed by 0 (same condition, narrower target)
+            .rule(Rule::new(
+                Effect::Allow,
+                target(Matcher::Exact("alice"), Matcher::Any),
+                is_admin(),
+                ReasonCode(2),
+            ))
+            // 2: never matches
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::False),
+                ReasonCode(3),
+            ))
+            // 3: vault is always denied...
+            .rule(Rule::deny(
+                target(Matcher::Any, Matcher::Exact("vault")),
+                ReasonCode(4),
+            ))
+            // 4: ...so this allow can never win
+            .rule(Rule::allow(
+                target(Matcher::OneOf(&["bob", "carol"]), Matcher::Exact("vault")),
+                ReasonCode(5),
+            ))
+            // 5: not redundant
+            .rule(Rule::allow(
+                target(Matcher::Exact("bob"), Matcher::Any),
+                ReasonCode(6),
+            ))
+            .build()
+            .unwrap();
+
+        let minimized = policy.minimize();
+        assert_eq!(minimized.removed, vec![1, 2, 4]);
+        assert_eq!(minimized.policy.rule_count(), 3);
+        assert_eq!(minimized.proof, Equivalence::Proved);
+    }
+
+    #[test]
+    fn test_check_equivalence_finds_counterexample() {
+        let a = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                is_admin(),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let b = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::NotEquals {
+                    attr: "role",
+                    value: Value::String("guest"),
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            a.check_equivalence(&a, DEFAULT_EQUIVALENCE_BUDGET),
+            Equivalence::Proved
+        );
+        let Equivalence::Counterexample(c) = a.check_equivalence(&b, DEFAULT_EQUIVALENCE_BUDGET)
+        else {
+            panic!("expected a counterexample");
+        };
+        // Missing role: a denies, b allows.
+        assert!(c.context.is_empty());
+
+        assert_eq!(a.check_equivalence(&b, 1), Equivalence::Unknown);
+    }
+
+    #[test]
+    fn test_list_contents_unknown() {
+        let admins = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::AttrContains {
+                    attr: "groups",
+                    value: Value::String("admin"),
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let empty = Policy::new(vec![]).unwrap();
+        assert_ne!(
+            admins.check_equivalence(&empty, DEFAULT_EQUIVALENCE_BUDGET),
+            Equivalence::Proved
+        );
+        assert_eq!(admins.minimize().removed, Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_bit_masks_unknown() {
+        let capable = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::HasFlags {
+                    attr: "caps",
+                    mask: 4,
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let empty = Policy::new(vec![]).unwrap();
+        assert_ne!(
+            capable.check_equivalence(&empty, DEFAULT_EQUIVALENCE_BUDGET),
+            Equivalence::Proved
+        );
+    }
+
+    #[test]
+    fn test_approved_equivalence() {
+        let gated = Rule::allow(Target::any(), ReasonCode(1)).with_approval(7, 600);
+        let a = Policy::new(vec![gated.clone()]).unwrap();
+        let b = Policy::new(vec![gated.with_cache_ttl(60)]).unwrap();
+        assert_eq!(
+            a.check_equivalence(&a, DEFAULT_EQUIVALENCE_BUDGET),
+            Equivalence::Proved
+        );
+        // Both deny pending approval; once approved, the TTLs differ.
+        let Equivalence::Counterexample(c) = a.check_equivalence(&b, DEFAULT_EQUIVALENCE_BUDGET)
+        else {
+            panic!("expected a counterexample");
+        };
+        assert!(c.context.is_empty());
+        assert_eq!(c.approval.as_deref(), Some("~"));
+    }
+
+    #[test]
+    fn test_ordered_comparisons_between_literals() {
+        // Allows exactly the levels above 6; nothing in the policies names 7
+        let a = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::And(
+                    Box::new(Condition::GreaterThan {
+                        attr: "level",
+                        value: 5,
+                    }),
+                    Box::new(Condition::NotEquals {
+                        attr: "level",
+                        value: Value::Int(6),
+                    }),
+                )),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let b = Policy::new(vec![]).unwrap();
+        let Equivalence::Counterexample(c) = a.check_equivalence(&b, DEFAULT_EQUIVALENCE_BUDGET)
+        else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(c.context, vec![("level".to_string(), ValueBuf::Int(7))]);
+    }
+
+    #[test]
+    fn test_cidr_equivalence() {
+        let cidr = |attr, text| {
+            Some(Condition::IpInCidr {
+                attr,
+                cidr: crate::cidr::Cidr::parse(text).unwrap(),
+            })
+        };
+        let allow = |cond| {
+            Policy::builder()
+                .rule(Rule::new(Effect::Allow, Target::any(), cond, ReasonCode(1)))
+                .build()
+                .unwrap()
+        };
+        // Two halves make the whole network
+        let halves = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                cidr("client", "10.0.0.0/9"),
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                cidr("client", "10.128.0.0/9"),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let whole = allow(cidr("client", "10.0.0.0/8"));
+        assert_eq!(
+            halves.check_equivalence(&whole, DEFAULT_EQUIVALENCE_BUDGET),
+            Equivalence::Proved
+        );
+
+        let wider = allow(cidr("client", "10.0.0.0/7"));
+        let Equivalence::Counterexample(c) =
+            whole.check_equivalence(&wider, DEFAULT_EQUIVALENCE_BUDGET)
+        else {
+            panic!("expected a counterexample");
+        };
+        assert_eq!(
+            c.context,
+            vec![(
+                "client".to_string(),
+                ValueBuf::Ip("11.255.255.255".parse().unwrap())
+            )]
+        );
+    }
+}
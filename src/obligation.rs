@@ -0,0 +1,138 @@
+//! Obligations attached to decisions.
+//!
+//! An obligation is something the PEP must do when enforcing a decision,
+//! such as writing an audit record. Obligations are stored inline in a
+//! small fixed-capacity set so `Decision` stays `Copy` and allocation-free.
+//!
+//! `Obligation::Approval` encodes the two-person rule: a rule built with
+//! `Rule::with_approval` denies with this obligation until the request is
+//! re-evaluated with `Policy::evaluate_with_approval` once an approval
+//! token has been obtained. A token in the request context grants nothing.
+//!
+//! Rules may also carry obligations of their own (`Rule::with_obligations`).
+//! A decision carries those of every matching rule with the deciding
+//! effect, deduplicated and in a fixed order, so the PEP sees the same set
+//! whatever order the rules matched in. `PolicyConfig::max_obligations`
+//! bounds the set; a decision that would exceed it fails evaluation with
+//! `PolicyError::TooManyObligations` rather than dropping any.
+
+/// Maximum number of obligations a single decision can carry.
+pub const MAX_OBLIGATIONS: usize = 4;
+
+/// An action the PEP must perform when enforcing a decision.
+///
+/// Obligations are ordered by variant, then by payload: `Audit` first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Obligation {
+    /// The decision must be written to the audit log.
+    Audit,
+    /// Application-defined obligation, mapped to an external table like `ReasonCode`.
+    Custom(u32),
+    /// The request needs a second person's approval before it may proceed.
+    Approval(Approval),
+}
+
+/// Who must approve a request, and for how long the approval holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Approval {
+    /// The group whose members may approve, an application-defined id
+    /// mapped to an external table like `ReasonCode`.
+    pub approvers: u32,
+    /// Seconds an approval stays valid once granted.
+    pub expires_in: u32,
+}
+
+/// A deduplicated set of obligations, kept in `Obligation` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Obligations {
+    items: [Option<Obligation>; MAX_OBLIGATIONS],
+}
+
+impl Obligations {
+    /// Create an empty set.
+    #[inline]
+    pub const fn new() -> Self {
+        Obligations {
+            items: [None; MAX_OBLIGATIONS],
+        }
+    }
+
+    /// Add an obligation unless it is already present.
+    ///
+    /// Returns `false` if the set is full and the obligation was not added.
+    pub fn push(&mut self, obligation: Obligation) -> bool {
+        let len = self.len();
+        let at = match self.items[..len].binary_search(&Some(obligation)) {
+            Ok(_) => return true,
+            Err(at) => at,
+        };
+        if len == MAX_OBLIGATIONS {
+            return false;
+        }
+        self.items[at..=len].rotate_right(1);
+        self.items[at] = Some(obligation);
+        true
+    }
+
+    /// Returns `true` if the obligation is present.
+    pub fn contains(&self, obligation: Obligation) -> bool {
+        self.iter().any(|o| o == obligation)
+    }
+
+    /// Iterate over obligations in `Obligation` order.
+    pub fn iter(&self) -> impl Iterator<Item = Obligation> + '_ {
+        self.items.iter().flatten().copied()
+    }
+
+    /// Number of obligations.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if there are no obligations.
+    pub fn is_empty(&self) -> bool {
+        self.items[0].is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_dedups_and_bounds() {
+        let mut set = Obligations::new();
+        assert!(set.is_empty());
+        assert!(set.push(Obligation::Audit));
+        assert!(set.push(Obligation::Audit));
+        assert_eq!(set.len(), 1);
+
+        for code in 0..(MAX_OBLIGATIONS as u32 - 1) {
+            assert!(set.push(Obligation::Custom(code)));
+        }
+        assert!(!set.push(Obligation::Custom(99)));
+        assert_eq!(set.len(), MAX_OBLIGATIONS);
+        assert_eq!(set.iter().next(), Some(Obligation::Audit));
+    }
+
+    #[test]
+    fn test_push_order_independent() {
+        let approval = Obligation::Approval(Approval {
+            approvers: 1,
+            expires_in: 60,
+        });
+        let mut forward = Obligations::new();
+        let mut backward = Obligations::new();
+        for obligation in [Obligation::Custom(7), approval, Obligation::Audit] {
+            forward.push(obligation);
+        }
+        for obligation in [Obligation::Audit, approval, Obligation::Custom(7)] {
+            backward.push(obligation);
+        }
+        assert_eq!(forward, backward);
+        assert_eq!(
+            forward.iter().collect::<Vec<_>>(),
+            [Obligation::Audit, Obligation::Custom(7), approval]
+        );
+    }
+}
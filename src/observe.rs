@@ -0,0 +1,55 @@
+//! Internal evaluation observer hooks.
+//!
+//! The evaluator is written once and parameterized over an observer.
+//! `evaluate()` uses `NoopObserver`, which monomorphizes away entirely,
+//! so instrumentation never costs the hot path anything.
+
+use crate::condition::Condition;
+use crate::stats::EvaluationStats;
+use crate::types::ReasonCode;
+
+/// Callbacks fired during policy evaluation. All methods default to no-ops.
+pub(crate) trait EvalObserver {
+    /// A rule is about to be checked.
+    fn rule_checked(&mut self, _index: usize) {}
+
+    /// A rule's target matched and its condition (if any) is about to run.
+    fn condition_evaluated(&mut self, _index: usize) {}
+
+    /// A condition node produced a result.
+    fn node_evaluated(&mut self, _node: &Condition<'_>, _result: bool) {}
+
+    /// A rule's target and condition both matched.
+    fn rule_matched(&mut self, _index: usize) {}
+
+    /// A Deny rule matched under `DenyAggregation::CollectAll`.
+    fn deny_collected(&mut self, _index: usize, _reason: ReasonCode) {}
+
+    /// An active break-glass rule matched and decided the request.
+    fn break_glass_used(&mut self, _index: usize) {}
+
+    /// Polled once per step (rule checked or condition node visited);
+    /// returning true aborts with `PolicyError::DeadlineExceeded`.
+    fn expired(&mut self) -> bool {
+        false
+    }
+}
+
+/// Observer that records nothing.
+pub(crate) struct NoopObserver;
+
+impl EvalObserver for NoopObserver {}
+
+impl EvalObserver for EvaluationStats {
+    fn rule_checked(&mut self, _index: usize) {
+        self.inc_rules();
+    }
+
+    fn condition_evaluated(&mut self, _index: usize) {
+        self.inc_condition_evals();
+    }
+
+    fn break_glass_used(&mut self, _index: usize) {
+        self.break_glass = true;
+    }
+}
@@ -0,0 +1,681 @@
+//! Partial evaluation.
+//!
+//! `Policy::partial_evaluate` answers "which resources may this principal
+//! perform this action on?" without enumerating resources. Everything known
+//! up front (principal, action, request context) is evaluated away; what is
+//! left is a residual `Condition` over the resource: the resource name
+//! itself (as the attribute `resource`, see `PartialRequest::with_resource_attr`)
+//! and any context attributes declared as per-resource.
+//!
+//! The residual is true exactly for the resources `evaluate()` would Allow.
+//! It can be evaluated per resource, or rendered as a SQL `WHERE` clause
+//! with `Residual::to_sql_filter` to push authorization into a query.
+//!
+//! # Example
+//!
+//! ```
+//! use gate0::partial::PartialRequest;
+//! use gate0::{Condition, Effect, Policy, ReasonCode, Rule, Target, Value};
+//!
+//! let policy = Policy::builder()
+//!     .rule(Rule::new(
+//!         Effect::Allow,
+//!         Target::any(),
+//!         Some(Condition::Equals { attr: "owner", value: Value::String("alice") }),
+//!         ReasonCode(1),
+//!     ))
+//!     .build()
+//!     .unwrap();
+//!
+//! let request = PartialRequest::new("alice", "read").with_resource_attrs(&["owner"]);
+//! let residual = policy.partial_evaluate(&request).unwrap();
+//! assert_eq!(
+//!     residual.condition(),
+//!     &Condition::Equals { attr: "owner", value: Value::String("alice") }
+//! );
+//! ```
+
+use crate::condition::{lookup_attr, Condition, EvalMode};
+use crate::custom::CustomOpCalls;
+use crate::error::PolicyError;
+use crate::groups::GroupLookup;
+use crate::observe::NoopObserver;
+use crate::policy::{break_glass_active, validate_str, Policy, Rule};
+use crate::target::Matcher;
+use crate::types::Effect;
+use crate::value::Value;
+
+/// Default residual attribute standing in for `Request::resource`.
+pub const DEFAULT_RESOURCE_ATTR: &str = "resource";
+
+/// A request with the resource left open.
+#[derive(Debug, Clone, Copy)]
+pub struct PartialRequest<'r> {
+    /// The principal making the request.
+    pub principal: &'r str,
+    /// The action being performed.
+    pub action: &'r str,
+    /// Attributes known for every resource (e.g. the caller's role).
+    pub context: &'r [(&'r str, Value<'r>)],
+    /// Attributes whose value depends on the resource (e.g. `owner`).
+    /// Paths into them, such as `owner.team`, depend on it too.
+    pub resource_attrs: &'r [&'r str],
+    /// Residual attribute name for the resource itself.
+    pub resource_attr: &'r str,
+    /// The environment the request is made in, if known.
+    pub environment: Option<&'r str>,
+}
+
+impl<'r> PartialRequest<'r> {
+    /// Create a partial request with no context.
+    pub const fn new(principal: &'r str, action: &'r str) -> Self {
+        PartialRequest {
+            principal,
+            action,
+            context: &[],
+            resource_attrs: &[],
+            resource_attr: DEFAULT_RESOURCE_ATTR,
+            environment: None,
+        }
+    }
+
+    /// Set the environment the request is made in.
+    pub const fn in_environment(mut self, environment: &'r str) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Set the known context.
+    pub const fn with_context(mut self, context: &'r [(&'r str, Value<'r>)]) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Declare which context attributes vary per resource.
+    pub const fn with_resource_attrs(mut self, attrs: &'r [&'r str]) -> Self {
+        self.resource_attrs = attrs;
+        self
+    }
+
+    /// Whether `attr` is, or is a path into, a resource attribute.
+    fn varies_per_resource(&self, attr: &str) -> bool {
+        self.resource_attrs.iter().any(|r| {
+            attr.strip_prefix(r)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        })
+    }
+
+    /// Name the residual attribute for the resource (e.g. a column name).
+    pub const fn with_resource_attr(mut self, attr: &'r str) -> Self {
+        self.resource_attr = attr;
+        self
+    }
+}
+
+/// The condition a resource must satisfy to be allowed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Residual<'r> {
+    condition: Condition<'r>,
+}
+
+impl<'r> Residual<'r> {
+    /// Get the residual condition.
+    pub fn condition(&self) -> &Condition<'r> {
+        &self.condition
+    }
+
+    /// Take the residual condition.
+    pub fn into_condition(self) -> Condition<'r> {
+        self.condition
+    }
+
+    /// Returns `true` if every resource is allowed.
+    pub fn is_always(&self) -> bool {
+        matches!(self.condition, Condition::True)
+    }
+
+    /// Returns `true` if no resource is allowed.
+    pub fn is_never(&self) -> bool {
+        matches!(self.condition, Condition::False)
+    }
+}
+
+impl<'a> Policy<'a> {
+    /// Evaluate this policy with the resource unknown.
+    ///
+    /// Same semantics as `evaluate()`: the residual holds for a resource iff
+    /// the full request would be allowed. `MemberOf` conditions fail with
+    /// `GroupLookupFailed`, as in `evaluate()`, and `Custom` conditions with
+    /// `CustomOpFailed`. Schedules and break-glass
+    /// flags are read from the known context.
+    ///
+    /// `PolicyConfig::collation` and `strict_types` apply to everything
+    /// known, so a type mismatch fails as in `evaluate()`; comparisons left
+    /// in the residual, including the resource name, are exact.
+    ///
+    /// A residual is two-valued, so it cannot stand for a rule that
+    /// `evaluate()` finds unknown and turns Indeterminate. Policies using
+    /// `PolicyConfig::three_valued_logic` fail with
+    /// `ThreeValuedPartialEvaluation` rather than allow such requests.
+    pub fn partial_evaluate<'r>(
+        &'r self,
+        request: &PartialRequest<'r>,
+    ) -> Result<Residual<'r>, PolicyError> {
+        let config = self.config();
+        if config.three_valued_logic {
+            return Err(PolicyError::ThreeValuedPartialEvaluation);
+        }
+        let collation = config.collation;
+        validate_str(request.principal, config.max_string_len)?;
+        validate_str(request.action, config.max_string_len)?;
+        if let Some(environment) = request.environment {
+            validate_str(environment, config.max_string_len)?;
+        }
+        if request.context.len() > config.max_context_attrs {
+            return Err(PolicyError::ContextTooLarge {
+                max: config.max_context_attrs,
+                actual: request.context.len(),
+            });
+        }
+
+        let mut break_glass: Vec<(Condition<'r>, Effect)> = Vec::new();
+        let mut allow = Vec::new();
+        let mut block = Vec::new();
+        for rule in self.rules() {
+            if !rule
+                .target
+                .principal
+                .matches_with(request.principal, collation)
+                || !self.action_applies(rule, request.action)
+                || !rule
+                    .target
+                    .matches_environment_with(request.environment, collation)
+            {
+                continue;
+            }
+            if let Some(flag) = rule.break_glass {
+                if !break_glass_active(request.context, flag) {
+                    continue;
+                }
+            }
+            if let Some(schedule) = &rule.schedule {
+                if !schedule.is_active(request.context) {
+                    continue;
+                }
+            }
+            // `evaluate()` never grants an approval, so such a rule can only
+            // deny for lack of one.
+            if rule.approval.is_some() {
+                continue;
+            }
+            let applies = rule_residual(rule, request, config.eval_mode())?;
+            if matches!(applies, Condition::False) {
+                continue;
+            }
+            if rule.break_glass.is_some() {
+                break_glass.push((applies, rule.effect));
+            } else if rule.effect == Effect::Allow {
+                allow.push(applies);
+            } else {
+                // Deny, Challenge, and Indeterminate all prevent an Allow.
+                block.push(applies);
+            }
+        }
+
+        // Allowed = some Allow matches and nothing blocking does...
+        let mut allowed = and(any(allow), not(any(block)));
+        // ...unless the first matching break-glass rule decides first.
+        for (applies, effect) in break_glass.into_iter().rev() {
+            allowed = if effect == Effect::Allow {
+                or(applies, allowed)
+            } else {
+                and(not(applies), allowed)
+            };
+        }
+        Ok(Residual { condition: allowed })
+    }
+}
+
+/// When `rule` applies to a resource, given everything known.
+fn rule_residual<'r>(
+    rule: &'r Rule<'_>,
+    request: &PartialRequest<'r>,
+    mode: EvalMode,
+) -> Result<Condition<'r>, PolicyError> {
+    let target = match &rule.target.resource {
+        Matcher::Any => Condition::True,
+        Matcher::Exact(name) => resource_equals(request.resource_attr, name),
+        Matcher::OneOf(names) => any(names
+            .iter()
+            .map(|name| resource_equals(request.resource_attr, name))
+            .collect()),
+    };
+    match &rule.condition {
+        None => Ok(target),
+        Some(cond) => Ok(and(target, residual(cond, request, mode)?)),
+    }
+}
+
+fn resource_equals<'r>(attr: &'r str, name: &'r str) -> Condition<'r> {
+    Condition::Equals {
+        attr,
+        value: Value::String(name),
+    }
+}
+
+/// Fold known attributes out of `cond`.
+///
+/// Recursion is bounded by the policy's validated condition depth.
+fn residual<'r>(
+    cond: &'r Condition<'_>,
+    request: &PartialRequest<'r>,
+    mode: EvalMode,
+) -> Result<Condition<'r>, PolicyError> {
+    Ok(match cond {
+        Condition::True => Condition::True,
+        Condition::False => Condition::False,
+        Condition::Equals { attr, .. }
+        | Condition::NotEquals { attr, .. }
+        | Condition::EqualsIgnoreCase { attr, .. }
+        | Condition::In { attr, .. }
+        | Condition::NotIn { attr, .. }
+        | Condition::GreaterThan { attr, .. }
+        | Condition::GreaterOrEqual { attr, .. }
+        | Condition::LessThan { attr, .. }
+        | Condition::LessOrEqual { attr, .. }
+        | Condition::Between { attr, .. }
+        | Condition::HasFlags { attr, .. }
+        | Condition::IpInCidr { attr, .. }
+        | Condition::Matches { attr, .. }
+        | Condition::GlobMatches { attr, .. }
+        | Condition::AttrContains { attr, .. } => {
+            if request.varies_per_resource(attr) {
+                cond.clone()
+            } else {
+                constant(known(cond, request, mode)?)
+            }
+        }
+        Condition::TimeOfDayBetween { .. } | Condition::DayOfWeekIn { .. } => {
+            constant(known(cond, request, mode)?)
+        }
+        Condition::MemberOf(_) => return Err(PolicyError::GroupLookupFailed),
+        Condition::Custom { .. } => return Err(PolicyError::CustomOpFailed),
+        Condition::Ref(name) => {
+            return Err(PolicyError::InvalidConditionRef {
+                name: name.to_string(),
+            })
+        }
+        Condition::AttrIsPrincipal(_) if request.principal.is_empty() => Condition::False,
+        Condition::AttrIsPrincipal(attr) => {
+            let principal = Value::String(request.principal);
+            if request.varies_per_resource(attr) {
+                Condition::Equals {
+                    attr,
+                    value: principal,
+                }
+            } else {
+                let found = lookup_attr(request.context, attr);
+                constant(found.is_some_and(|v| mode.collation.values_eq(&v, &principal)))
+            }
+        }
+        Condition::And(a, b) => and(residual(a, request, mode)?, residual(b, request, mode)?),
+        Condition::Or(a, b) => or(residual(a, request, mode)?, residual(b, request, mode)?),
+        Condition::Not(inner) => not(residual(inner, request, mode)?),
+        // Checked even where full evaluation would short-circuit past it.
+        Condition::Require(attr, inner) => {
+            let inner = residual(inner, request, mode)?;
+            if request.varies_per_resource(attr) {
+                Condition::Require(attr, Box::new(inner))
+            } else if lookup_attr(request.context, attr).is_none() {
+                return Err(PolicyError::RequiredAttributeMissing {
+                    attr: attr.to_string(),
+                });
+            } else {
+                inner
+            }
+        }
+        Condition::Implies(a, b) => {
+            implies(residual(a, request, mode)?, residual(b, request, mode)?)
+        }
+        Condition::Xor(a, b) => xor(residual(a, request, mode)?, residual(b, request, mode)?),
+        Condition::AllOf(children) | Condition::AnyOf(children) => {
+            let items = children
+                .iter()
+                .map(|child| residual(child, request, mode))
+                .collect::<Result<Vec<_>, _>>()?;
+            if matches!(cond, Condition::AllOf(_)) {
+                all(items)
+            } else {
+                any(items)
+            }
+        }
+    })
+}
+
+/// Evaluate a leaf over known attributes as `evaluate()` would.
+fn known(
+    cond: &Condition<'_>,
+    request: &PartialRequest<'_>,
+    mode: EvalMode,
+) -> Result<bool, PolicyError> {
+    cond.evaluate_observed(
+        request.context,
+        &mut GroupLookup::none(),
+        &mut CustomOpCalls::none(),
+        mode,
+        &mut NoopObserver,
+    )
+}
+
+pub(crate) fn constant<'r>(value: bool) -> Condition<'r> {
+    if value {
+        Condition::True
+    } else {
+        Condition::False
+    }
+}
+
+pub(crate) fn and<'r>(a: Condition<'r>, b: Condition<'r>) -> Condition<'r> {
+    match (&a, &b) {
+        (Condition::False, _) | (_, Condition::False) => Condition::False,
+        (Condition::True, _) => b,
+        (_, Condition::True) => a,
+        _ => Condition::And(Box::new(a), Box::new(b)),
+    }
+}
+
+pub(crate) fn or<'r>(a: Condition<'r>, b: Condition<'r>) -> Condition<'r> {
+    match (&a, &b) {
+        (Condition::True, _) | (_, Condition::True) => Condition::True,
+        (Condition::False, _) => b,
+        (_, Condition::False) => a,
+        _ => Condition::Or(Box::new(a), Box::new(b)),
+    }
+}
+
+pub(crate) fn implies<'r>(a: Condition<'r>, b: Condition<'r>) -> Condition<'r> {
+    match (&a, &b) {
+        (Condition::False, _) | (_, Condition::True) => Condition::True,
+        (Condition::True, _) => b,
+        (_, Condition::False) => not(a),
+        _ => Condition::Implies(Box::new(a), Box::new(b)),
+    }
+}
+
+pub(crate) fn xor<'r>(a: Condition<'r>, b: Condition<'r>) -> Condition<'r> {
+    match (&a, &b) {
+        (Condition::False, _) => b,
+        (_, Condition::False) => a,
+        (Condition::True, _) => not(b),
+        (_, Condition::True) => not(a),
+        _ => Condition::Xor(Box::new(a), Box::new(b)),
+    }
+}
+
+pub(crate) fn not(mut a: Condition<'_>) -> Condition<'_> {
+    match &mut a {
+        Condition::True => Condition::False,
+        Condition::False => Condition::True,
+        // `Condition` implements Drop, so the inner node is swapped out.
+        Condition::Not(inner) => std::mem::replace(&mut **inner, Condition::True),
+        _ => Condition::Not(Box::new(a)),
+    }
+}
+
+/// Disjunction of `items` as a balanced tree, so depth grows logarithmically.
+pub(crate) fn any(items: Vec<Condition<'_>>) -> Condition<'_> {
+    balanced(items, or).unwrap_or(Condition::False)
+}
+
+/// Conjunction of `items`, balanced like `any`.
+pub(crate) fn all(items: Vec<Condition<'_>>) -> Condition<'_> {
+    balanced(items, and).unwrap_or(Condition::True)
+}
+
+/// Join `items` pairwise, level by level; `None` if there are none.
+pub(crate) fn balanced<'r>(
+    items: Vec<Condition<'r>>,
+    join: fn(Condition<'r>, Condition<'r>) -> Condition<'r>,
+) -> Option<Condition<'r>> {
+    let mut level = items;
+    while level.len() > 1 {
+        let mut next = Vec::with_capacity(level.len().div_ceil(2));
+        let mut items = level.into_iter();
+        while let Some(a) = items.next() {
+            match items.next() {
+                Some(b) => next.push(join(a, b)),
+                None => next.push(a),
+            }
+        }
+        level = next;
+    }
+    level.pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::PolicyConfig;
+    use crate::target::Target;
+    use crate::types::{ReasonCode, Request};
+
+    fn owner_is(name: &'static str) -> Condition<'static> {
+        Condition::Equals {
+            attr: "owner",
+            value: Value::String(name),
+        }
+    }
+
+    fn policy() -> Policy<'static> {
+        Policy::builder()
+            // Owners may read their documents
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(owner_is("alice")),
+                ReasonCode(1),
+            ))
+            // Admins may read anything
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("admin"),
+                }),
+                ReasonCode(2),
+            ))
+            // Nobody reads the vault
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Any,
+                    resource: Matcher::Exact("vault"),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(3),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_residual_matches_full_evaluation() {
+        let policy = policy();
+        for role in ["admin", "user"] {
+            let known = [("role", Value::String(role))];
+            let request = PartialRequest::new("alice", "read")
+                .with_context(&known)
+                .with_resource_attrs(&["owner"]);
+            let residual = policy.partial_evaluate(&request).unwrap();
+
+            for resource in ["vault", "doc"] {
+                for owner in ["alice", "bob"] {
+                    let ctx = [
+                        ("role", Value::String(role)),
+                        ("owner", Value::String(owner)),
+                    ];
+                    let full = Request::with_context("alice", "read", resource, &ctx);
+                    let row = [
+                        ("resource", Value::String(resource)),
+                        ("owner", Value::String(owner)),
+                    ];
+                    assert_eq!(
+                        residual.condition().evaluate(&row).unwrap(),
+                        policy.evaluate(&full).unwrap().is_allow(),
+                        "role={} resource={} owner={}",
+                        role,
+                        resource,
+                        owner
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let policy = policy();
+        // Known admin: only the vault deny is left.
+        let known = [("role", Value::String("admin"))];
+        let request = PartialRequest::new("alice", "read")
+            .with_context(&known)
+            .with_resource_attr("id");
+        let residual = policy.partial_evaluate(&request).unwrap();
+        assert_eq!(
+            residual.condition(),
+            &Condition::Not(Box::new(Condition::Equals {
+                attr: "id",
+                value: Value::String("vault"),
+            }))
+        );
+
+        // Non-owner, non-admin with owner known: nothing is allowed.
+        let known = [("owner", Value::String("bob"))];
+        let request = PartialRequest::new("carol", "read").with_context(&known);
+        assert!(policy.partial_evaluate(&request).unwrap().is_never());
+
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .build()
+            .unwrap();
+        let residual = policy
+            .partial_evaluate(&PartialRequest::new("x", "y"))
+            .unwrap();
+        assert!(residual.is_always());
+    }
+
+    #[test]
+    fn test_implies_and_xor_folding() {
+        let flag = |attr| {
+            Box::new(Condition::Equals {
+                attr,
+                value: Value::Bool(true),
+            })
+        };
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::And(
+                    Box::new(Condition::Implies(flag("unmanaged"), flag("mfa"))),
+                    Box::new(Condition::Xor(flag("internal"), flag("shared"))),
+                )),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let residual = |known: &'static [(&'static str, Value<'static>)]| {
+            let request = PartialRequest::new("alice", "read")
+                .with_context(known)
+                .with_resource_attrs(&["shared"]);
+            policy
+                .partial_evaluate(&request)
+                .unwrap()
+                .condition()
+                .clone()
+        };
+
+        // A false premise folds the implication away; a true one leaves
+        // the conclusion. Xor with a known operand is the other or its
+        // negation.
+        const MANAGED_INTERNAL: &[(&str, Value)] = &[
+            ("unmanaged", Value::Bool(false)),
+            ("internal", Value::Bool(true)),
+        ];
+        assert_eq!(
+            residual(MANAGED_INTERNAL),
+            Condition::Not(Box::new(Condition::Equals {
+                attr: "shared",
+                value: Value::Bool(true),
+            }))
+        );
+        const UNMANAGED: &[(&str, Value)] = &[("unmanaged", Value::Bool(true))];
+        assert_eq!(residual(UNMANAGED), Condition::False);
+        const UNMANAGED_WITH_MFA: &[(&str, Value)] =
+            &[("unmanaged", Value::Bool(true)), ("mfa", Value::Bool(true))];
+        assert_eq!(
+            residual(UNMANAGED_WITH_MFA),
+            Condition::Equals {
+                attr: "shared",
+                value: Value::Bool(true),
+            }
+        );
+    }
+
+    #[test]
+    fn test_strict_types() {
+        let policy = Policy::with_config(
+            vec![Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::NotEquals {
+                    attr: "role",
+                    value: Value::String("guest"),
+                }),
+                ReasonCode(1),
+            )],
+            PolicyConfig {
+                strict_types: true,
+                ..PolicyConfig::default()
+            },
+        )
+        .unwrap();
+        let known = [("role", Value::Int(1))];
+        let mismatch = policy
+            .evaluate(&Request::with_context("alice", "read", "doc", &known))
+            .unwrap_err();
+        assert!(matches!(mismatch, PolicyError::TypeMismatch { .. }));
+        let request = PartialRequest::new("alice", "read").with_context(&known);
+        assert_eq!(policy.partial_evaluate(&request).unwrap_err(), mismatch);
+    }
+
+    #[test]
+    fn test_three_valued_logic_refused() {
+        let policy = Policy::with_config(
+            vec![Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::NotEquals {
+                    attr: "role",
+                    value: Value::String("guest"),
+                }),
+                ReasonCode(1),
+            )],
+            PolicyConfig {
+                three_valued_logic: true,
+                ..PolicyConfig::default()
+            },
+        )
+        .unwrap();
+        // Missing role: unknown, so never allowed
+        let full = policy.evaluate(&Request::new("alice", "read", "doc"));
+        assert!(full.unwrap().is_indeterminate());
+        assert_eq!(
+            policy
+                .partial_evaluate(&PartialRequest::new("alice", "read"))
+                .unwrap_err(),
+            PolicyError::ThreeValuedPartialEvaluation
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! Bounded string patterns for `Condition::Matches` and
+//! `Condition::GlobMatches`.
+//!
+//! Full regular expressions invite catastrophic backtracking and patterns
+//! nobody can review, so gate0 supports a deliberately small subset:
+//!
+//! - a literal character, or `\` before a punctuation character to match
+//!   it literally (e.g. `\.`, `\*`);
+//! - `.`, any one character;
+//! - a class `[abc]`, `[a-z0-9_]`, or negated `[^/]`, matching one
+//!   character;
+//! - `*` after one of the above for zero or more of it, or `?` for zero or
+//!   one;
+//! - `^` at the start and `$` at the end, anchoring the match to the start
+//!   and end of the string.
+//!
+//! Without anchors a pattern matches anywhere in the string, so `^svc-`
+//! is a prefix test and `^[a-z]*$` checks every character. Alternation,
+//! groups, `+`, and counted repetition are rejected rather than read as
+//! literals, as are escapes of letters and digits.
+//!
+//! A `Pattern` is compiled once, when the condition is built, into at most
+//! `MAX_PATTERN_STEPS` steps. Matching tracks the set of live steps as a
+//! bitmask while reading each character once, so it takes time linear in
+//! the string's length whatever the pattern, and never allocates.
+//!
+//! Globs are simpler still, and always match the whole string: `*` matches
+//! any run of characters, `?` any one character, and every other character
+//! itself, so `proj-*-prod` matches `proj-billing-prod`. There is no
+//! escape; use a `Pattern` to match a literal `*` or `?`. A glob may hold
+//! at most `MAX_GLOB_WILDCARDS` wildcards, checked when the policy is
+//! built.
+
+use std::fmt;
+use std::mem::size_of;
+
+use crate::error::PolicyError;
+
+/// Maximum number of steps (characters, classes, and `.`s) in a pattern.
+pub const MAX_PATTERN_STEPS: usize = 64;
+
+/// Maximum number of `*` and `?` wildcards in a glob.
+pub const MAX_GLOB_WILDCARDS: usize = 8;
+
+/// A compiled pattern; see the module docs for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    source: Box<str>,
+    steps: Box<[Step]>,
+    /// Character ranges of every class, in order.
+    ranges: Box<[(char, char)]>,
+    anchored_start: bool,
+    anchored_end: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Step {
+    atom: Atom,
+    repeat: Repeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Atom {
+    Char(char),
+    Any,
+    /// `ranges[start..end]`.
+    Class {
+        start: u16,
+        end: u16,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Repeat {
+    Once,
+    Optional,
+    Many,
+}
+
+impl Pattern {
+    /// Compile `text`.
+    ///
+    /// Fails with `InvalidPattern` if it uses unsupported syntax, has an
+    /// unclosed or empty class, a reversed range, a `*` or `?` with nothing
+    /// to repeat, or more than `MAX_PATTERN_STEPS` steps.
+    pub fn parse(text: &str) -> Result<Self, PolicyError> {
+        let mut chars = text.chars().peekable();
+        let anchored_start = chars.next_if_eq(&'^').is_some();
+        let mut anchored_end = false;
+        let mut steps: Vec<Step> = Vec::new();
+        let mut ranges = Vec::new();
+
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '*' | '?' => {
+                    let last = steps.last_mut().ok_or(PolicyError::InvalidPattern)?;
+                    if last.repeat != Repeat::Once {
+                        return Err(PolicyError::InvalidPattern);
+                    }
+                    last.repeat = if c == '*' {
+                        Repeat::Many
+                    } else {
+                        Repeat::Optional
+                    };
+                    continue;
+                }
+                '$' if chars.peek().is_none() => {
+                    anchored_end = true;
+                    continue;
+                }
+                '^' | '$' | '+' | '|' | '(' | ')' | '{' | '}' => {
+                    return Err(PolicyError::InvalidPattern)
+                }
+                '.' => Atom::Any,
+                '\\' => Atom::Char(escaped(chars.next())?),
+                '[' => {
+                    let negated = chars.next_if_eq(&'^').is_some();
+                    let start = ranges.len();
+                    loop {
+                        let lo = match chars.next().ok_or(PolicyError::InvalidPattern)? {
+                            ']' => break,
+                            '\\' => escaped(chars.next())?,
+                            lo => lo,
+                        };
+                        match chars.next_if_eq(&'-') {
+                            None => ranges.push((lo, lo)),
+                            // A `-` before the closing `]` is literal
+                            Some(dash) if chars.peek() == Some(&']') => {
+                                ranges.push((lo, lo));
+                                ranges.push((dash, dash));
+                            }
+                            Some(_) => {
+                                let hi = match chars.next().ok_or(PolicyError::InvalidPattern)? {
+                                    '\\' => escaped(chars.next())?,
+                                    hi => hi,
+                                };
+                                if lo > hi {
+                                    return Err(PolicyError::InvalidPattern);
+                                }
+                                ranges.push((lo, hi));
+                            }
+                        }
+                    }
+                    if ranges.len() == start {
+                        return Err(PolicyError::InvalidPattern);
+                    }
+                    let index =
+                        |i: usize| u16::try_from(i).map_err(|_| PolicyError::InvalidPattern);
+                    Atom::Class {
+                        start: index(start)?,
+                        end: index(ranges.len())?,
+                        negated,
+                    }
+                }
+                c => Atom::Char(c),
+            };
+            if steps.len() == MAX_PATTERN_STEPS {
+                return Err(PolicyError::InvalidPattern);
+            }
+            steps.push(Step {
+                atom,
+                repeat: Repeat::Once,
+            });
+        }
+
+        Ok(Pattern {
+            source: text.into(),
+            steps: steps.into(),
+            ranges: ranges.into(),
+            anchored_start,
+            anchored_end,
+        })
+    }
+
+    /// The text this pattern was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns `true` if `text` matches, comparing characters exactly.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.matches(text, false)
+    }
+
+    /// Returns `true` if `text` matches, ignoring the case of ASCII letters
+    /// if `fold_case` is set.
+    pub(crate) fn matches(&self, text: &str, fold_case: bool) -> bool {
+        // Bit `i` is set when step `i` is next; bit `steps.len()` means
+        // the whole pattern has matched.
+        let accept = 1u128 << self.steps.len();
+        let mut live = self.skip_optional(1);
+        for c in text.chars() {
+            if live & accept != 0 && !self.anchored_end {
+                return true;
+            }
+            let mut next = if self.anchored_start { 0 } else { 1 };
+            let mut pending = live & !accept;
+            while pending != 0 {
+                let i = pending.trailing_zeros() as usize;
+                pending &= pending - 1;
+                let step = self.steps[i];
+                if self.atom_matches(step.atom, c, fold_case) {
+                    next |= match step.repeat {
+                        Repeat::Many => 1 << i,
+                        Repeat::Once | Repeat::Optional => 1 << (i + 1),
+                    };
+                }
+            }
+            live = self.skip_optional(next);
+            if live == 0 {
+                return false;
+            }
+        }
+        live & accept != 0
+    }
+
+    /// Heap bytes owned by the compiled pattern.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.source.len()
+            + self.steps.len() * size_of::<Step>()
+            + self.ranges.len() * size_of::<(char, char)>()
+    }
+
+    /// Add to `live` every step reachable by skipping `*` and `?` steps.
+    fn skip_optional(&self, mut live: u128) -> u128 {
+        for (i, step) in self.steps.iter().enumerate() {
+            if live & (1 << i) != 0 && step.repeat != Repeat::Once {
+                live |= 1 << (i + 1);
+            }
+        }
+        live
+    }
+
+    fn atom_matches(&self, atom: Atom, c: char, fold_case: bool) -> bool {
+        match atom {
+            Atom::Any => true,
+            Atom::Char(p) => p == c || (fold_case && p.eq_ignore_ascii_case(&c)),
+            Atom::Class {
+                start,
+                end,
+                negated,
+            } => {
+                let ranges = &self.ranges[start as usize..end as usize];
+                let contains = |c: char| ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c));
+                let found = contains(c)
+                    || (fold_case
+                        && (contains(c.to_ascii_lowercase()) || contains(c.to_ascii_uppercase())));
+                found != negated
+            }
+        }
+    }
+}
+
+/// Fails with `TooManyWildcards` if `glob` has more than
+/// `MAX_GLOB_WILDCARDS` wildcards.
+pub(crate) fn validate_glob(glob: &str) -> Result<(), PolicyError> {
+    let wildcards = glob.chars().filter(|c| matches!(c, '*' | '?')).count();
+    if wildcards > MAX_GLOB_WILDCARDS {
+        return Err(PolicyError::TooManyWildcards {
+            max: MAX_GLOB_WILDCARDS,
+            actual: wildcards,
+        });
+    }
+    Ok(())
+}
+
+/// Returns `true` if all of `text` matches `glob`, ignoring the case of
+/// ASCII letters if `fold_case` is set.
+///
+/// On a mismatch only the most recent `*` takes one more character, so
+/// this never backtracks further and runs in time proportional to the
+/// lengths of `text` and `glob` multiplied, without allocating.
+pub(crate) fn glob_matches(glob: &str, text: &str, fold_case: bool) -> bool {
+    let (mut g, mut t) = (0, 0);
+    // Where to resume after the most recent `*`: (glob index, text index).
+    let mut star: Option<(usize, usize)> = None;
+    loop {
+        match (glob[g..].chars().next(), text[t..].chars().next()) {
+            (None, None) => return true,
+            (Some('*'), _) => {
+                g += 1;
+                star = Some((g, t));
+                continue;
+            }
+            (Some('?'), Some(c)) => {
+                g += 1;
+                t += c.len_utf8();
+                continue;
+            }
+            (Some(p), Some(c)) if p == c || (fold_case && p.eq_ignore_ascii_case(&c)) => {
+                g += p.len_utf8();
+                t += c.len_utf8();
+                continue;
+            }
+            _ => {}
+        }
+        let Some((resume, from)) = star else {
+            return false;
+        };
+        let Some(c) = text[from..].chars().next() else {
+            return false;
+        };
+        star = Some((resume, from + c.len_utf8()));
+        g = resume;
+        t = from + c.len_utf8();
+    }
+}
+
+/// The character after a `\`, which must be ASCII punctuation.
+fn escaped(c: Option<char>) -> Result<char, PolicyError> {
+    c.filter(char::is_ascii_punctuation)
+        .ok_or(PolicyError::InvalidPattern)
+}
+
+/// The pattern's source text.
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Serialized as its source text, e.g. `"^svc-[a-z]*$"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Pattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Pattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Pattern::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Pattern::parse(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_matching() {
+        assert!(is_match("svc", "my-svc-1"));
+        assert!(is_match("^svc-", "svc-billing"));
+        assert!(!is_match("^svc-", "my-svc-1"));
+        assert!(is_match("\\.pdf$", "report.pdf"));
+        assert!(!is_match("\\.pdf$", "report.pdf.exe"));
+        assert!(!is_match("\\.pdf$", "reportxpdf"));
+        assert!(is_match("^[a-z][a-z0-9_-]*$", "svc_a-1"));
+        assert!(!is_match("^[a-z][a-z0-9_-]*$", "1svc"));
+        assert!(!is_match("^[a-z][a-z0-9_-]*$", "svc.a"));
+        assert!(is_match("^team/[^/]*$", "team/ops"));
+        assert!(!is_match("^team/[^/]*$", "team/ops/keys"));
+        assert!(is_match("^colou?r$", "color"));
+        assert!(is_match("^colou?r$", "colour"));
+        assert!(!is_match("^colou?r$", "colouur"));
+        assert!(is_match("^a.c$", "aéc"));
+        assert!(is_match("^x*$", ""));
+        assert!(is_match("", "anything"));
+        assert!(is_match("^$", ""));
+        assert!(!is_match("^$", "x"));
+        // `*` and `?` after a class, and a literal trailing `-`
+        assert!(is_match("^v[0-9]*\\.[0-9]?$", "v12."));
+        assert!(is_match("^[+-]$", "-"));
+    }
+
+    #[test]
+    fn test_fold_case() {
+        let pattern = Pattern::parse("^admin-[a-z]*$").unwrap();
+        assert!(!pattern.is_match("Admin-OPS"));
+        assert!(pattern.matches("Admin-OPS", true));
+        assert!(!pattern.matches("Ädmin-ops", true));
+        let negated = Pattern::parse("^[^a-z]$").unwrap();
+        assert!(negated.matches("1", true));
+        assert!(!negated.matches("Q", true));
+    }
+
+    #[test]
+    fn test_linear_time() {
+        // Exponential for a backtracking engine
+        let pattern = Pattern::parse(&format!("^{}{}$", "a?".repeat(30), "a".repeat(30))).unwrap();
+        assert!(pattern.is_match(&"a".repeat(30)));
+        assert!(!pattern.is_match(&"a".repeat(61)));
+        let long = "ab".repeat(100_000);
+        assert!(!Pattern::parse("a*a*a*a*c").unwrap().is_match(&long));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for text in [
+            "*", "a**", "a?*", "^?", "a+", "(a)", "a|b", "a{2}", "a^", "a$b", "[", "[a-z", "[]",
+            "[^]", "[z-a]", "\\", "\\d", "[\\w]",
+        ] {
+            assert_eq!(
+                Pattern::parse(text),
+                Err(PolicyError::InvalidPattern),
+                "{}",
+                text
+            );
+        }
+        let too_long = "a".repeat(MAX_PATTERN_STEPS + 1);
+        assert_eq!(Pattern::parse(&too_long), Err(PolicyError::InvalidPattern));
+        assert!(Pattern::parse(&"a*".repeat(MAX_PATTERN_STEPS)).is_ok());
+        assert_eq!(Pattern::parse("^a\\$$").unwrap().as_str(), "^a\\$$");
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(glob_matches("proj-*-prod", "proj-billing-prod", false));
+        assert!(glob_matches("proj-*-prod", "proj--prod", false));
+        assert!(!glob_matches("proj-*-prod", "proj-billing-prod-2", false));
+        assert!(!glob_matches("proj-*-prod", "my-proj-billing-prod", false));
+        assert!(glob_matches("v?.*", "v2.10", false));
+        assert!(glob_matches("v?.*", "vé.", false));
+        assert!(!glob_matches("v?.*", "v.1", false));
+        assert!(glob_matches("*", "", false));
+        assert!(glob_matches("", "", false));
+        assert!(!glob_matches("", "x", false));
+        assert!(glob_matches("*a*b", "xaxxab", false));
+        assert!(!glob_matches("*a*b", "xaxxa", false));
+        assert!(!glob_matches("PROJ-*", "proj-x", false));
+        assert!(glob_matches("PROJ-*", "proj-x", true));
+
+        // Many stars over a long mismatch finish quickly
+        let long = "a".repeat(10_000);
+        assert!(!glob_matches("*a*a*a*a*a*a*a*b", &long, false));
+
+        assert_eq!(validate_glob(&"*?".repeat(MAX_GLOB_WILDCARDS / 2)), Ok(()));
+        assert_eq!(
+            validate_glob(&"?".repeat(MAX_GLOB_WILDCARDS + 1)),
+            Err(PolicyError::TooManyWildcards {
+                max: MAX_GLOB_WILDCARDS,
+                actual: MAX_GLOB_WILDCARDS + 1
+            })
+        );
+    }
+}
@@ -3,11 +3,85 @@
 //! The core of the authorization system.
 //! Evaluates rules in order, applies deny-overrides conflict resolution.
 
-use crate::condition::Condition;
+use std::task::Poll;
+
+use crate::batch::BatchMemo;
+use crate::clock::Clock;
+use crate::collation::Collation;
+use crate::condition::{Condition, EvalMode};
+use crate::custom::{CustomOp, CustomOpCalls, CustomOps};
+use crate::deadline::Deadline;
+use crate::definitions::Definitions;
 use crate::error::PolicyError;
-use crate::target::Target;
-use crate::types::{Decision, Effect, ReasonCode, Request, NO_MATCHING_RULE};
+use crate::groups::{GroupLookup, GroupProvider};
+use crate::hierarchy::{ActionHierarchy, ImpliedActions};
+use crate::manifest::AttrKind;
+use crate::metadata::PolicyMetadata;
+use crate::obligation::{Approval, Obligation, Obligations, MAX_OBLIGATIONS};
+use crate::observe::{EvalObserver, NoopObserver};
+use crate::redact::Sensitivity;
+use crate::schedule::Schedule;
+use crate::target::{Matcher, Target};
+use crate::types::{
+    ChallengeMethod, Decision, Effect, ReasonCode, Request, EVALUATION_FAILED, NO_MATCHING_RULE,
+};
 use crate::value::Value;
+use crate::warnings::Warnings;
+
+/// Context attribute `Rule::owner_allow` compares with the principal.
+pub const DEFAULT_OWNER_ATTR: &str = "resource_owner";
+
+/// Context attribute holding the token passed to
+/// `Policy::evaluate_with_approval`. A token supplied any other way grants
+/// nothing; see `Rule::with_approval`.
+pub const DEFAULT_APPROVAL_ATTR: &str = "approval_token";
+
+/// Maximum number of tags on a single rule.
+pub const MAX_RULE_TAGS: usize = 16;
+
+/// Which reason a Deny reports when several Deny rules match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DenyAggregation {
+    /// The first matching Deny in declared order.
+    #[default]
+    FirstMatch,
+    /// The matching Deny with the lowest reason code.
+    LowestReason,
+    /// The matching Deny with the highest `Rule::priority`.
+    HighestPriority,
+    /// The first matching Deny, with every matching Deny reported to the
+    /// trace of `Policy::evaluate_with_audit`.
+    CollectAll,
+}
+
+impl DenyAggregation {
+    /// Whether `candidate` should replace `current` as the reported Deny.
+    /// Ties keep the earlier rule, so the result never depends on more
+    /// than declared order and the rules themselves.
+    fn prefers(self, candidate: &Rule<'_>, current: &Rule<'_>) -> bool {
+        match self {
+            DenyAggregation::FirstMatch | DenyAggregation::CollectAll => false,
+            DenyAggregation::LowestReason => candidate.reason.value() < current.reason.value(),
+            DenyAggregation::HighestPriority => candidate.priority > current.priority,
+        }
+    }
+}
+
+/// What a rule does when its condition needs a group provider or custom
+/// operator that fails, e.g. a directory timeout.
+///
+/// Rules without a setting follow `PolicyConfig::indeterminate_on_error`.
+/// Other evaluation failures, such as exhausted lookup budgets, are not
+/// provider failures and are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderFailure {
+    /// Fail closed: count the rule as a matching Deny with its own reason.
+    Deny,
+    /// Favor availability: treat the condition as false.
+    Skip,
+    /// Report the rule as failed, as `indeterminate_on_error` would.
+    Indeterminate,
+}
 
 /// Configuration limits for policy construction and evaluation.
 #[derive(Debug, Clone, Copy)]
@@ -16,12 +90,104 @@ pub struct PolicyConfig {
     pub max_rules: usize,
     /// Maximum depth of nested conditions (default: 10).
     pub max_condition_depth: usize,
-    /// Maximum number of attributes allowed in request context (default: 64).
+    /// Maximum number of nodes in one rule's condition, counting every
+    /// child of `AllOf` and `AnyOf` (default: 1024, enough for any tree of
+    /// binary connectives within the default depth).
+    pub max_condition_nodes: usize,
+    /// Maximum number of attributes allowed in request context, and of
+    /// entries in each `Value::Map` it holds (default: 64).
     pub max_context_attrs: usize,
     /// Maximum number of items in a Matcher::OneOf list (default: 64).
     pub max_matcher_options: usize,
     /// Maximum length of any string identifier or value (default: 256).
     pub max_string_len: usize,
+    /// Maximum number of values in a `Condition::In` or `NotIn` set, or in
+    /// a `Value::List` in the request context (default: 64).
+    pub max_set_values: usize,
+    /// Maximum number of `GroupProvider` lookups per request (default: 16).
+    pub max_group_lookups: usize,
+    /// Report rule evaluation failures as `Effect::Indeterminate` instead of
+    /// returning `Err` (default: false).
+    ///
+    /// Malformed requests (oversized context or strings) are still rejected
+    /// with `Err`; only failures while evaluating an individual rule are
+    /// folded into the decision.
+    pub indeterminate_on_error: bool,
+    /// How the reported reason is chosen among matching Deny rules
+    /// (default: first match).
+    pub deny_aggregation: DenyAggregation,
+    /// Skip the right operand of `And`/`Or` once the left decides the
+    /// result (default: true).
+    ///
+    /// Set to false when condition evaluation must do the same work
+    /// whatever the attribute values, e.g. to avoid timing side channels.
+    /// Eager evaluation also surfaces errors, such as a failed group
+    /// lookup, from operands that would not affect the result.
+    pub short_circuit: bool,
+    /// Fail with `PolicyError::TypeMismatch` when a condition compares an
+    /// attribute with a literal of another type, instead of evaluating the
+    /// comparison to false (default: false).
+    ///
+    /// Missing attributes are not type errors and keep their usual result.
+    /// The error follows `indeterminate_on_error` like any other rule
+    /// failure.
+    pub strict_types: bool,
+    /// Maximum total `CustomOp::cost` of custom operator calls per request
+    /// (default: 64).
+    pub max_custom_op_cost: usize,
+    /// Every action rules and requests may name (default: unchecked).
+    ///
+    /// Rules naming any other action are reported by
+    /// `PolicyBuilder::build_with_warnings`, and requests for one fail
+    /// `Policy::evaluate_checked`, so a typo such as `"raed"` is caught
+    /// instead of silently never matching.
+    pub known_actions: Option<&'static [&'static str]>,
+    /// Every resource name rules may match (default: unchecked).
+    pub known_resources: Option<&'static [&'static str]>,
+    /// Fail construction with `PolicyError::UnknownName` instead of
+    /// warning when a rule names an action or resource outside the known
+    /// vocabularies (default: false).
+    pub reject_unknown_names: bool,
+    /// How target matchers and condition string comparisons treat case
+    /// and Unicode normalization (default: byte for byte).
+    ///
+    /// Validation limits and `known_actions`/`known_resources` checks
+    /// still see the strings as written.
+    pub collation: Collation,
+    /// Maximum number of distinct obligations one decision may carry, at
+    /// most `MAX_OBLIGATIONS` (default: `MAX_OBLIGATIONS`).
+    ///
+    /// A decision whose matching rules carry more fails evaluation with
+    /// `PolicyError::TooManyObligations`, even under
+    /// `indeterminate_on_error`: dropping one would let the PEP skip it.
+    pub max_obligations: usize,
+    /// Maximum number of condition evaluation steps per request, across
+    /// all rules (default: 2_097_152, more than any policy within the
+    /// default rule and node limits can take).
+    ///
+    /// A step is one node evaluated or one connective applied, as
+    /// `EvalCursor::steps` counts them. Depth and node limits bound each
+    /// condition; this bounds the total work of a request. Running out
+    /// fails evaluation with `PolicyError::EvalBudgetExceeded`, even under
+    /// `indeterminate_on_error`, since no later rule could be evaluated.
+    pub max_condition_evals: usize,
+    /// Evaluate conditions under three-valued logic (default: false).
+    ///
+    /// A condition over a missing attribute is unknown instead of false
+    /// (see `Condition::evaluate_three_valued`). A rule whose condition is
+    /// unknown might have matched, so it makes the decision
+    /// `Effect::Indeterminate` where a failed rule would under
+    /// `indeterminate_on_error`: only a matching Deny outranks it. This
+    /// tells "denied" apart from "could not decide" for PDP deployments.
+    pub three_valued_logic: bool,
+    /// Maximum number of `.`-separated segments in a condition's attribute
+    /// path, such as 3 for `device.posture.compliant`, and the deepest a
+    /// request context may nest `Value::Map`s (default: 4, at most
+    /// `ABSOLUTE_MAX_PATH_DEPTH`).
+    ///
+    /// Paths resolve against maps in the context; an attribute named by
+    /// the whole path still matches first (see `Value::Map`).
+    pub max_path_depth: usize,
 }
 
 impl Default for PolicyConfig {
@@ -29,13 +195,68 @@ impl Default for PolicyConfig {
         PolicyConfig {
             max_rules: 1000,
             max_condition_depth: 10,
+            max_condition_nodes: 1024,
             max_context_attrs: 64,
             max_matcher_options: 64,
             max_string_len: 256,
+            max_set_values: 64,
+            max_group_lookups: 16,
+            indeterminate_on_error: false,
+            deny_aggregation: DenyAggregation::FirstMatch,
+            short_circuit: true,
+            strict_types: false,
+            max_custom_op_cost: 64,
+            known_actions: None,
+            known_resources: None,
+            reject_unknown_names: false,
+            collation: Collation::Binary,
+            max_obligations: MAX_OBLIGATIONS,
+            max_condition_evals: 1 << 21,
+            three_valued_logic: false,
+            max_path_depth: 4,
         }
     }
 }
 
+impl PolicyConfig {
+    /// The condition evaluation settings of this config.
+    pub(crate) fn eval_mode(&self) -> EvalMode {
+        EvalMode {
+            short_circuit: self.short_circuit,
+            strict_types: self.strict_types,
+            collation: self.collation,
+            now: None,
+            three_valued: self.three_valued_logic,
+        }
+    }
+
+    /// Names in `rule`'s action and resource matchers that the configured
+    /// vocabularies do not list, as `(field, name)` pairs in matcher order.
+    pub(crate) fn unknown_names<'r>(&self, rule: &Rule<'r>) -> Vec<(&'static str, &'r str)> {
+        let checks = [
+            ("action", self.known_actions, &rule.target.action),
+            ("resource", self.known_resources, &rule.target.resource),
+        ];
+        let mut unknown = Vec::new();
+        for (field, known, matcher) in checks {
+            let Some(known) = known else {
+                continue;
+            };
+            let names: &[&'r str] = match matcher {
+                Matcher::Any => &[],
+                Matcher::Exact(name) => std::slice::from_ref(name),
+                Matcher::OneOf(options) => options,
+            };
+            for name in names {
+                if !known.contains(name) {
+                    unknown.push((field, *name));
+                }
+            }
+        }
+        unknown
+    }
+}
+
 /// A single authorization rule.
 #[derive(Debug, Clone)]
 pub struct Rule<'a> {
@@ -47,6 +268,31 @@ pub struct Rule<'a> {
     pub condition: Option<Condition<'a>>,
     /// The reason code for this rule's decision.
     pub reason: ReasonCode,
+    /// Optional hint, in seconds, for how long a decision made by this rule
+    /// may be reused by a PEP or cache. Propagated into `Decision::cache_ttl`.
+    pub cache_ttl: Option<u32>,
+    /// Optional schedule. Outside it, the rule does not apply.
+    pub schedule: Option<Schedule<'a>>,
+    /// If set, this is a break-glass rule, active only when the named
+    /// context attribute is `true` or a non-empty token string. An active,
+    /// matching break-glass rule overrides every other rule.
+    pub break_glass: Option<&'a str>,
+    /// Rank among matching Deny rules under
+    /// `DenyAggregation::HighestPriority`; higher wins. Ignored otherwise.
+    pub priority: u32,
+    /// How a provider failure while evaluating the condition is handled;
+    /// `None` defers to `PolicyConfig::indeterminate_on_error`.
+    pub on_provider_failure: Option<ProviderFailure>,
+    /// If set, this Allow rule needs a second person's approval: unless
+    /// evaluated by `Policy::evaluate_with_approval` with a non-empty token,
+    /// a match denies with `Obligation::Approval` instead of allowing.
+    pub approval: Option<Approval>,
+    /// Free-form labels such as `"pci"` or `"team:payments"`, for scoping
+    /// introspection and audit. They never affect the decision.
+    pub tags: &'a [&'a str],
+    /// Obligations attached to every decision this rule helps make. A
+    /// decision carries those of all matching rules with its effect.
+    pub obligations: &'a [Obligation],
 }
 
 impl<'a> Rule<'a> {
@@ -62,6 +308,14 @@ impl<'a> Rule<'a> {
             target,
             condition,
             reason,
+            cache_ttl: None,
+            schedule: None,
+            break_glass: None,
+            priority: 0,
+            on_provider_failure: None,
+            approval: None,
+            tags: &[],
+            obligations: &[],
         }
     }
 
@@ -74,13 +328,209 @@ impl<'a> Rule<'a> {
     pub fn deny(target: Target<'a>, reason: ReasonCode) -> Self {
         Rule::new(Effect::Deny, target, None, reason)
     }
+
+    /// Create a Challenge rule with no condition.
+    pub fn challenge(target: Target<'a>, method: ChallengeMethod, reason: ReasonCode) -> Self {
+        Rule::new(Effect::Challenge(method), target, None, reason)
+    }
+
+    /// Create a break-glass Allow rule activated by the `flag_attr` context attribute.
+    ///
+    /// When active and matching it wins over any Deny, marks the decision
+    /// with `break_glass`, and always attaches `Obligation::Audit`. It is
+    /// only reached if no earlier rule fails evaluation, so declare it
+    /// first (see `Policy::evaluate`).
+    pub fn break_glass(target: Target<'a>, flag_attr: &'a str, reason: ReasonCode) -> Self {
+        let mut rule = Rule::new(Effect::Allow, target, None, reason);
+        rule.break_glass = Some(flag_attr);
+        rule
+    }
+
+    /// Create an Allow rule granting `action` on any resource to its owner.
+    ///
+    /// The owner is read from the `DEFAULT_OWNER_ATTR` context attribute;
+    /// for another attribute, use `Condition::AttrIsPrincipal` directly.
+    pub fn owner_allow(action: Matcher<'a>, reason: ReasonCode) -> Self {
+        let target = Target {
+            action,
+            ..Target::any()
+        };
+        let owned = Condition::AttrIsPrincipal(DEFAULT_OWNER_ATTR);
+        Rule::new(Effect::Allow, target, Some(owned), reason)
+    }
+
+    /// Attach a cache TTL hint (in seconds) to this rule.
+    ///
+    /// Use short TTLs for risk-based rules and long TTLs for static grants.
+    pub fn with_cache_ttl(mut self, seconds: u32) -> Self {
+        self.cache_ttl = Some(seconds);
+        self
+    }
+
+    /// Set this rule's Deny priority.
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set how this rule handles a failing group provider or custom operator.
+    pub fn with_provider_failure(mut self, on_failure: ProviderFailure) -> Self {
+        self.on_provider_failure = Some(on_failure);
+        self
+    }
+
+    /// Restrict this rule to the given schedule.
+    pub fn with_schedule(mut self, schedule: Schedule<'a>) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    /// Require approval by a member of the `approvers` group, valid for
+    /// `expires_in` seconds, before this Allow rule grants access.
+    ///
+    /// Only Allow rules that are not break-glass rules may require
+    /// approval. Approval is only ever granted by
+    /// `Policy::evaluate_with_approval`, never by the request context. Once
+    /// approved, the decision's cache TTL never exceeds `expires_in`.
+    pub fn with_approval(mut self, approvers: u32, expires_in: u32) -> Self {
+        self.approval = Some(Approval {
+            approvers,
+            expires_in,
+        });
+        self
+    }
+
+    /// Label this rule with `tags`, replacing any it had.
+    pub fn with_tags(mut self, tags: &'a [&'a str]) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Attach `obligations` to this rule's decisions, replacing any it had.
+    pub fn with_obligations(mut self, obligations: &'a [Obligation]) -> Self {
+        self.obligations = obligations;
+        self
+    }
+
+    /// Whether this rule carries `tag`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// Evaluate this rule on its own, as if it were a one-rule policy with
+    /// the default config.
+    ///
+    /// Returns `None` if the rule does not apply: its target (environment
+    /// included) does not match, its break-glass flag is down, it is
+    /// outside its schedule (read from the context clock), or its condition
+    /// is false. Otherwise returns the decision this rule would contribute,
+    /// with its cache TTL and obligations or, for a break-glass rule, the
+    /// audit obligation too. A rule needing approval denies pending one,
+    /// with its obligations and the approval obligation. There is no action
+    /// hierarchy, group provider, or custom operator registry, so
+    /// `MemberOf` and `Custom` fail. The rule is not validated; use a
+    /// `Policy` for that.
+    pub fn evaluate(&self, request: &Request<'_>) -> Result<Option<Decision>, PolicyError> {
+        if !self
+            .target
+            .matches(request.principal, request.action, request.resource)
+            || !self.target.matches_environment(request.environment)
+        {
+            return Ok(None);
+        }
+        if let Some(flag) = self.break_glass {
+            if !break_glass_active(request.context, flag) {
+                return Ok(None);
+            }
+        }
+        if let Some(schedule) = &self.schedule {
+            if !schedule.is_active(request.context) {
+                return Ok(None);
+            }
+        }
+        if let Some(cond) = &self.condition {
+            let result = cond.evaluate_observed(
+                request.context,
+                &mut GroupLookup::new(None, request.principal, 0),
+                &mut CustomOpCalls::none(),
+                EvalMode::default(),
+                &mut NoopObserver,
+            );
+            match result {
+                Ok(true) => {}
+                Ok(false) => return Ok(None),
+                Err(e) => {
+                    return match self.provider_failure(&e) {
+                        Some(ProviderFailure::Deny) => Ok(Some(Decision::deny(self.reason))),
+                        Some(ProviderFailure::Skip) => Ok(None),
+                        Some(ProviderFailure::Indeterminate) => {
+                            Ok(Some(Decision::indeterminate(self.reason)))
+                        }
+                        None => Err(e),
+                    }
+                }
+            }
+        }
+        let mut decision = if self.break_glass.is_some() {
+            let mut decision =
+                Decision::new(self.effect, self.reason).with_obligation(Obligation::Audit);
+            decision.break_glass = true;
+            decision
+        } else if self.effect == Effect::Indeterminate {
+            return Ok(Some(Decision::indeterminate(self.reason)));
+        } else if self.approval.is_some() {
+            self.pending_approval()
+        } else {
+            Decision::new(self.effect, self.reason).with_cache_ttl(self.decision_ttl())
+        };
+        let mut gathered = Gathered::default();
+        gathered.add(decision.obligations.iter());
+        gathered.add(self.obligations.iter().copied());
+        decision.obligations = gathered.bounded(PolicyConfig::default().max_obligations)?;
+        Ok(Some(decision))
+    }
+
+    /// The decision of an approval rule that matched without an approval.
+    fn pending_approval(&self) -> Decision {
+        let decision = Decision::deny(self.reason);
+        match self.approval {
+            Some(approval) => decision.with_obligation(Obligation::Approval(approval)),
+            None => decision,
+        }
+    }
+
+    /// This rule's cache TTL hint, capped by its approval's lifetime.
+    fn decision_ttl(&self) -> Option<u32> {
+        match self.approval {
+            None => self.cache_ttl,
+            Some(approval) => Some(
+                self.cache_ttl
+                    .map_or(approval.expires_in, |ttl| ttl.min(approval.expires_in)),
+            ),
+        }
+    }
+
+    /// This rule's `on_provider_failure` setting, if `err` is a provider
+    /// failure: a failed group lookup or custom operator.
+    fn provider_failure(&self, err: &PolicyError) -> Option<ProviderFailure> {
+        match err {
+            PolicyError::GroupLookupFailed | PolicyError::CustomOpFailed => {
+                self.on_provider_failure
+            }
+            _ => None,
+        }
+    }
 }
 
 /// A policy is an ordered collection of rules.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Policy<'a> {
     rules: Vec<Rule<'a>>,
     config: PolicyConfig,
+    actions: ImpliedActions<'a>,
+    metadata: PolicyMetadata<'a>,
+    ops: CustomOps<'a>,
+    sensitive: Vec<(&'a str, Sensitivity)>,
 }
 
 impl<'a> Policy<'a> {
@@ -101,6 +551,15 @@ impl<'a> Policy<'a> {
     /// - Rule count exceeds `config.max_rules`
     /// - Any rule violates matcher/string/depth limits
     pub fn with_config(rules: Vec<Rule<'a>>, config: PolicyConfig) -> Result<Self, PolicyError> {
+        Self::with_custom_ops(rules, config, CustomOps::default())
+    }
+
+    /// `with_config`, resolving `Condition::Custom` nodes against `ops`.
+    pub(crate) fn with_custom_ops(
+        rules: Vec<Rule<'a>>,
+        config: PolicyConfig,
+        ops: CustomOps<'a>,
+    ) -> Result<Self, PolicyError> {
         // Enforce hard cap for zero-allocation evaluation
         if config.max_condition_depth > crate::condition::ABSOLUTE_MAX_CONDITION_DEPTH {
             return Err(PolicyError::ConditionTooDeep {
@@ -109,6 +568,19 @@ impl<'a> Policy<'a> {
             });
         }
 
+        if config.max_path_depth > crate::condition::ABSOLUTE_MAX_PATH_DEPTH {
+            return Err(PolicyError::PathTooDeep {
+                max: crate::condition::ABSOLUTE_MAX_PATH_DEPTH,
+                actual: config.max_path_depth,
+            });
+        }
+
+        if config.max_obligations > MAX_OBLIGATIONS {
+            return Err(PolicyError::TooManyObligations {
+                max: MAX_OBLIGATIONS,
+            });
+        }
+
         // Validate rule count
         if rules.len() > config.max_rules {
             return Err(PolicyError::TooManyRules {
@@ -117,8 +589,10 @@ impl<'a> Policy<'a> {
             });
         }
 
+        ops.validate(config.max_string_len)?;
+
         // Validate rules and condition depths
-        for rule in &rules {
+        for (index, rule) in rules.iter().enumerate() {
             // Validate matcher options and string lengths
             rule.target
                 .principal
@@ -129,14 +603,122 @@ impl<'a> Policy<'a> {
             rule.target
                 .resource
                 .validate(config.max_matcher_options, config.max_string_len)?;
+            rule.target
+                .environment
+                .validate(config.max_matcher_options, config.max_string_len)?;
+            if config.reject_unknown_names && !config.unknown_names(rule).is_empty() {
+                return Err(PolicyError::UnknownName { rule: index });
+            }
 
             // Validate condition depth and string lengths
             if let Some(cond) = &rule.condition {
                 cond.validate(config.max_condition_depth, config.max_string_len)?;
+                let nodes = cond.node_count();
+                if nodes > config.max_condition_nodes {
+                    return Err(PolicyError::TooManyConditionNodes {
+                        max: config.max_condition_nodes,
+                        actual: nodes,
+                    });
+                }
+                let set_len = cond.max_set_len();
+                if set_len > config.max_set_values {
+                    return Err(PolicyError::TooManySetValues {
+                        max: config.max_set_values,
+                        actual: set_len,
+                    });
+                }
+                let path_depth = cond.max_path_depth();
+                if path_depth > config.max_path_depth {
+                    return Err(PolicyError::PathTooDeep {
+                        max: config.max_path_depth,
+                        actual: path_depth,
+                    });
+                }
+                ops.resolve(cond)?;
+            }
+
+            if let Some(flag) = rule.break_glass {
+                validate_str(flag, config.max_string_len)?;
+            }
+
+            if rule.tags.len() > MAX_RULE_TAGS {
+                return Err(PolicyError::TooManyTags {
+                    max: MAX_RULE_TAGS,
+                    actual: rule.tags.len(),
+                });
+            }
+            for tag in rule.tags {
+                validate_str(tag, config.max_string_len)?;
+            }
+
+            // Every decision this rule makes on its own must fit.
+            let mut own = Gathered::default();
+            own.add(rule.obligations.iter().copied());
+            own.add(rule.break_glass.map(|_| Obligation::Audit));
+            own.bounded(config.max_obligations)?;
+            // The list itself is bounded too, repeats included.
+            if rule.obligations.len() > config.max_obligations
+                || (rule.approval.is_some() && config.max_obligations == 0)
+            {
+                return Err(PolicyError::TooManyObligations {
+                    max: config.max_obligations,
+                });
+            }
+
+            if let Some(approval) = rule.approval {
+                if rule.effect != Effect::Allow
+                    || rule.break_glass.is_some()
+                    || approval.expires_in == 0
+                {
+                    return Err(PolicyError::InvalidApproval { rule: index });
+                }
+            }
+
+            // Validate schedule windows and offset
+            if let Some(schedule) = &rule.schedule {
+                schedule.validate(config.max_matcher_options, config.max_string_len)?;
             }
         }
 
-        Ok(Policy { rules, config })
+        Ok(Policy {
+            rules,
+            config,
+            actions: ImpliedActions::default(),
+            metadata: PolicyMetadata::default(),
+            ops,
+            sensitive: Vec::new(),
+        })
+    }
+
+    /// Register an action hierarchy, validating it against this policy's config.
+    pub(crate) fn with_action_hierarchy(
+        mut self,
+        hierarchy: ActionHierarchy<'a>,
+    ) -> Result<Self, PolicyError> {
+        self.actions = ImpliedActions::build(hierarchy, self.config.max_string_len)?;
+        Ok(self)
+    }
+
+    /// Attach metadata, validating it against this policy's config.
+    pub(crate) fn with_metadata(
+        mut self,
+        metadata: PolicyMetadata<'a>,
+    ) -> Result<Self, PolicyError> {
+        metadata.validate(self.config.max_string_len)?;
+        self.metadata = metadata;
+        Ok(self)
+    }
+
+    /// Attach the attributes declared sensitive, checking name lengths.
+    pub(crate) fn with_sensitive_attributes(
+        mut self,
+        attributes: Vec<(&'a str, Sensitivity)>,
+    ) -> Result<Self, PolicyError> {
+        for (name, _) in &attributes {
+            validate_str(name, self.config.max_string_len)?;
+        }
+        self.sensitive = attributes;
+        Ok(self)
     }
 
     /// Get the number of rules in this policy.
@@ -151,6 +733,96 @@ impl<'a> Policy<'a> {
         &self.rules
     }
 
+    /// Indices of the rules carrying `tag`, in declared order.
+    pub fn rules_tagged(&self, tag: &str) -> Vec<usize> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.has_tag(tag))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Number of rules the rule table has room for.
+    pub(crate) fn rule_capacity(&self) -> usize {
+        self.rules.capacity()
+    }
+
+    /// The custom operators registered at build time.
+    pub(crate) fn custom_ops(&self) -> &CustomOps<'a> {
+        &self.ops
+    }
+
+    /// Get the metadata attached at build time (empty if none).
+    pub fn metadata(&self) -> &PolicyMetadata<'a> {
+        &self.metadata
+    }
+
+    /// The attributes declared sensitive at build time, in declaration order.
+    pub fn sensitive_attributes(&self) -> &[(&'a str, Sensitivity)] {
+        &self.sensitive
+    }
+
+    /// Get the action hierarchy registered at build time (empty if none).
+    pub fn action_hierarchy(&self) -> &ActionHierarchy<'a> {
+        self.actions.hierarchy()
+    }
+
+    /// The action hierarchy's precomputed closure.
+    pub(crate) fn implied_actions(&self) -> &ImpliedActions<'a> {
+        &self.actions
+    }
+
+    /// Whether `rule` applies to everything in `request` but its resource:
+    /// principal, action, and environment match (widening Allow rules
+    /// through the action hierarchy), break-glass flag raised, schedule
+    /// active.
+    pub(crate) fn rule_active(
+        &self,
+        rule: &Rule<'_>,
+        request: &Request<'_>,
+        now: Option<i64>,
+    ) -> bool {
+        let collation = self.config.collation;
+        if !rule
+            .target
+            .principal
+            .matches_with(request.principal, collation)
+            || !self.action_applies(rule, request.action)
+            || !rule
+                .target
+                .matches_environment_with(request.environment, collation)
+        {
+            return false;
+        }
+
+        // Break-glass rules only exist while their flag is raised
+        if let Some(flag) = rule.break_glass {
+            if !break_glass_active(request.context, flag) {
+                return false;
+            }
+        }
+
+        // Check if the rule is in its schedule (if present)
+        match &rule.schedule {
+            Some(schedule) => match now {
+                Some(now) => schedule.is_active_at(now),
+                None => schedule.is_active(request.context),
+            },
+            None => true,
+        }
+    }
+
+    /// Whether `rule`'s action matcher applies to `action`.
+    pub(crate) fn action_applies(&self, rule: &Rule<'_>, action: &str) -> bool {
+        let collation = self.config.collation;
+        if rule.effect == Effect::Allow {
+            self.actions.grants(&rule.target.action, action, collation)
+        } else {
+            rule.target.action.matches_with(action, collation)
+        }
+    }
+
     /// Get the configuration for this policy.
     pub fn config(&self) -> &PolicyConfig {
         &self.config
@@ -159,80 +831,163 @@ impl<'a> Policy<'a> {
     /// Evaluate this policy against a request.
     ///
     /// Semantics:
-    /// 1. Validate context size
-    /// 2. Evaluate rules in declared order
-    /// 3. Collect all matching (effect, reason) pairs
-    /// 4. If any Deny exists → return first Deny's reason
-    /// 5. Else if any Allow exists → return first Allow's reason
-    /// 6. Else → Deny with NO_MATCHING_RULE
+    /// 1. Validate the request against the configured limits
+    /// 2. Evaluate rules in declared order, skipping any whose target,
+    ///    schedule, or break-glass flag does not apply
+    /// 3. An active break-glass rule that matches decides at once: its
+    ///    effect and reason, with `Obligation::Audit` and `break_glass`
+    ///    set, never cached
+    /// 4. A condition error fails evaluation with `Err` at once, unless the
+    ///    rule's `on_provider_failure` or `PolicyConfig::indeterminate_on_error`
+    ///    handles it; running out of budget or deadline always fails
+    /// 5. Otherwise, once every rule has been checked, the first of these
+    ///    decides:
+    ///    - a matching Deny → Deny with the reason `deny_aggregation` picks
+    ///    - a rule failing closed (`ProviderFailure::Deny`) → Deny with its
+    ///      reason, never cached
+    ///    - a failed rule, or one unknown under three-valued logic →
+    ///      Indeterminate, never cached
+    ///    - a matching Challenge → the first Challenge
+    ///    - a matching Allow → the first Allow, its cache TTL capped by any
+    ///      approval's lifetime
+    ///    - an Allow needing approval → Deny with `Obligation::Approval`
+    ///    - else → Deny with NO_MATCHING_RULE
+    /// 6. A Deny, Challenge, or Allow from step 5 carries the deciding
+    ///    rule's cache TTL hint and the obligations of every matching rule
+    ///    with its effect, or fails with `TooManyObligations` if they
+    ///    exceed `max_obligations`
+    ///
+    /// Because an unhandled error ends evaluation where it happens, a
+    /// break-glass rule declared after a failing rule is never reached.
+    /// Declare break-glass rules before any rule that can fail.
     pub fn evaluate(&self, request: &Request<'_>) -> Result<Decision, PolicyError> {
-        // 1. Validate request string lengths
-        validate_str(request.principal, self.config.max_string_len)?;
-        validate_str(request.action, self.config.max_string_len)?;
-        validate_str(request.resource, self.config.max_string_len)?;
+        self.evaluate_observed(request, None, None, &mut NoopObserver)
+    }
+
+    /// Evaluate this policy with schedules and time conditions reading the
+    /// time from `clock`.
+    ///
+    /// Same semantics as `evaluate()`, except that schedules and time
+    /// conditions ignore their context clock attribute. The clock is read
+    /// once per evaluation, so every rule sees the same instant.
+    pub fn evaluate_at(
+        &self,
+        request: &Request<'_>,
+        clock: &dyn Clock,
+    ) -> Result<Decision, PolicyError> {
+        self.evaluate_observed(request, None, Some(clock), &mut NoopObserver)
+    }
+
+    /// Re-evaluate a request that was denied with `Obligation::Approval`,
+    /// now that an approval `token` has been obtained.
+    ///
+    /// This is the only way to satisfy `Rule::with_approval`: other entry
+    /// points never treat the request as approved, whatever its context
+    /// holds. The token is placed in the `DEFAULT_APPROVAL_ATTR` context
+    /// attribute, replacing any already there. The policy only checks that
+    /// it is non-empty: verifying it (signature, approver group, expiry,
+    /// that the approver is not the requester) is the caller's job. An
+    /// empty token grants nothing.
+    pub fn evaluate_with_approval(
+        &self,
+        request: &Request<'_>,
+        token: &str,
+    ) -> Result<Decision, PolicyError> {
+        let mut context: Vec<(&str, Value<'_>)> = request
+            .context
+            .iter()
+            .filter(|(k, _)| *k != DEFAULT_APPROVAL_ATTR)
+            .cloned()
+            .collect();
+        context.push((DEFAULT_APPROVAL_ATTR, Value::String(token)));
+        let approved = Request {
+            context: &context,
+            ..request.clone()
+        };
+        self.evaluate_memoized(
+            &approved,
+            None,
+            None,
+            None,
+            !token.is_empty(),
+            &mut NoopObserver,
+        )
+    }
+
+    /// Evaluate this policy, failing closed.
+    ///
+    /// Any error is turned into a Deny with `EVALUATION_FAILED`, so the
+    /// caller always gets a decision and can never fail open by mishandling
+    /// an `Err`. Use `evaluate_or_deny_with` to log the underlying error.
+    pub fn evaluate_or_deny(&self, request: &Request<'_>) -> Decision {
+        self.evaluate_or_deny_with(request, |_| {})
+    }
 
-        // 2. Validate context size
+    /// Like `evaluate_or_deny`, but passes any error to `on_error` first.
+    pub fn evaluate_or_deny_with<F: FnOnce(&PolicyError)>(
+        &self,
+        request: &Request<'_>,
+        on_error: F,
+    ) -> Decision {
+        self.evaluate(request).unwrap_or_else(|err| {
+            on_error(&err);
+            Decision::deny(EVALUATION_FAILED)
+        })
+    }
+
+    /// Evaluate this policy, resolving `MemberOf` conditions through `groups`.
+    ///
+    /// Same semantics as `evaluate()`. At most `max_group_lookups` distinct
+    /// lookups are made per request; repeated groups are answered from memory.
+    pub fn evaluate_with_groups(
+        &self,
+        request: &Request<'_>,
+        groups: &dyn GroupProvider,
+    ) -> Result<Decision, PolicyError> {
+        self.evaluate_observed(request, Some(groups), None, &mut NoopObserver)
+    }
+
+    /// Evaluate this policy, rejecting ambiguous contexts.
+    ///
+    /// Same semantics as `evaluate()`, but first fails with
+    /// `ConflictingAttribute` if the context repeats an attribute with a
+    /// different value (see `Request::check_context`).
+    pub fn evaluate_strict(&self, request: &Request<'_>) -> Result<Decision, PolicyError> {
         if request.context.len() > self.config.max_context_attrs {
             return Err(PolicyError::ContextTooLarge {
                 max: self.config.max_context_attrs,
                 actual: request.context.len(),
             });
         }
+        request.check_context()?;
+        self.evaluate(request)
+    }
 
-        // 3. Validate context key/value lengths
-        for (key, value) in request.context {
-            validate_str(key, self.config.max_string_len)?;
-            if let Value::String(s) = value {
-                validate_str(s, self.config.max_string_len)?;
+    /// Evaluate this policy, rejecting unknown actions.
+    ///
+    /// Same semantics as `evaluate()`, but first fails with `UnknownAction`
+    /// if `PolicyConfig::known_actions` is set and does not list the
+    /// request's action, which no rule could then match.
+    pub fn evaluate_checked(&self, request: &Request<'_>) -> Result<Decision, PolicyError> {
+        if let Some(known) = self.config.known_actions {
+            if !known.contains(&request.action) {
+                return Err(PolicyError::UnknownAction);
             }
         }
+        self.evaluate(request)
+    }
 
-        let mut first_allow: Option<ReasonCode> = None;
-        let mut first_deny: Option<ReasonCode> = None;
-
-        // Evaluate rules in order
-        for rule in &self.rules {
-            // Check if target matches
-            if !rule
-                .target
-                .matches(request.principal, request.action, request.resource)
-            {
-                continue;
-            }
-
-            // Check if condition matches (if present)
-            let condition_matches = match &rule.condition {
-                None => true,
-                Some(cond) => cond.evaluate(request.context)?,
-            };
-
-            if !condition_matches {
-                continue;
-            }
-
-            // Rule matches - record the effect
-            match rule.effect {
-                Effect::Allow => {
-                    if first_allow.is_none() {
-                        first_allow = Some(rule.reason);
-                    }
-                }
-                Effect::Deny => {
-                    if first_deny.is_none() {
-                        first_deny = Some(rule.reason);
-                    }
-                }
-            }
-        }
-        // Apply deny-overrides: Deny wins if any Deny matched
-        if let Some(reason) = first_deny {
-            Ok(Decision::deny(reason))
-        } else if let Some(reason) = first_allow {
-            Ok(Decision::allow(reason))
-        } else {
-            // No matching rules - default deny
-            Ok(Decision::deny(NO_MATCHING_RULE))
-        }
+    /// Evaluate this policy, giving up once `deadline` runs out.
+    ///
+    /// Same semantics as `evaluate()`, or `Err(DeadlineExceeded)` if the
+    /// evaluation needs more steps than allowed or the deadline's check
+    /// reports expiry. The deadline keeps its step count for inspection.
+    pub fn evaluate_with_deadline(
+        &self,
+        request: &Request<'_>,
+        deadline: &mut Deadline<'_>,
+    ) -> Result<Decision, PolicyError> {
+        self.evaluate_observed(request, None, None, deadline)
     }
 
     /// Evaluate this policy against a request, returning observable stats.
@@ -247,49 +1002,112 @@ impl<'a> Policy<'a> {
         request: &Request<'_>,
     ) -> Result<(Decision, crate::stats::EvaluationStats), PolicyError> {
         let mut stats = crate::stats::EvaluationStats::new();
+        let decision = self.evaluate_observed(request, None, None, &mut stats)?;
+        Ok((decision, stats))
+    }
 
-        // 1. Validate request string lengths
-        validate_str(request.principal, self.config.max_string_len)?;
-        validate_str(request.action, self.config.max_string_len)?;
-        validate_str(request.resource, self.config.max_string_len)?;
-
-        // 2. Validate context size
-        if request.context.len() > self.config.max_context_attrs {
-            return Err(PolicyError::ContextTooLarge {
-                max: self.config.max_context_attrs,
-                actual: request.context.len(),
-            });
-        }
+    /// The single evaluation loop shared by every public entry point.
+    ///
+    /// The observer is generic so `evaluate()` pays nothing for hooks it
+    /// does not use.
+    pub(crate) fn evaluate_observed<O: EvalObserver>(
+        &self,
+        request: &Request<'_>,
+        groups: Option<&dyn GroupProvider>,
+        clock: Option<&dyn Clock>,
+        observer: &mut O,
+    ) -> Result<Decision, PolicyError> {
+        self.evaluate_memoized(request, groups, clock, None, false, observer)
+    }
 
-        // 3. Validate context key/value lengths
-        for (key, value) in request.context {
-            validate_str(key, self.config.max_string_len)?;
-            if let Value::String(s) = value {
-                validate_str(s, self.config.max_string_len)?;
-            }
+    /// `evaluate_observed`, reusing per-rule results from `memo` when
+    /// every request sharing it has the same principal, action, and context.
+    /// Rules needing approval allow only if `approved` is set.
+    pub(crate) fn evaluate_memoized<O: EvalObserver>(
+        &self,
+        request: &Request<'_>,
+        groups: Option<&dyn GroupProvider>,
+        clock: Option<&dyn Clock>,
+        mut memo: Option<&mut BatchMemo>,
+        approved: bool,
+        observer: &mut O,
+    ) -> Result<Decision, PolicyError> {
+        let check_context = !memo.as_ref().is_some_and(|m| m.context_checked);
+        self.validate_request(request, check_context)?;
+        if let Some(m) = memo.as_deref_mut() {
+            m.context_checked = true;
         }
 
-        let mut first_allow: Option<ReasonCode> = None;
-        let mut first_deny: Option<ReasonCode> = None;
+        let mut groups = GroupLookup::new(groups, request.principal, self.config.max_group_lookups);
+        let mut ops = CustomOpCalls::new(&self.ops, self.config.max_custom_op_cost);
+        let mut evals = self.config.max_condition_evals;
+        let now = clock.map(|c| c.now());
+        let mut tally = RuleTally {
+            approved,
+            ..RuleTally::default()
+        };
 
         // Evaluate rules in order
-        for rule in &self.rules {
-            stats.inc_rules();
+        for (index, rule) in self.rules.iter().enumerate() {
+            observer.rule_checked(index);
+            if observer.expired() {
+                return Err(PolicyError::DeadlineExceeded);
+            }
 
-            // Check if target matches
+            // Check if target matches, and whether the rule is active
             if !rule
                 .target
-                .matches(request.principal, request.action, request.resource)
+                .resource
+                .matches_with(request.resource, self.config.collation)
             {
                 continue;
             }
+            let active = match memo.as_deref_mut() {
+                Some(m) => {
+                    *m.active[index].get_or_insert_with(|| self.rule_active(rule, request, now))
+                }
+                None => self.rule_active(rule, request, now),
+            };
+            if !active {
+                continue;
+            }
 
             // Check if condition matches (if present)
             let condition_matches = match &rule.condition {
                 None => true,
                 Some(cond) => {
-                    stats.inc_condition_evals();
-                    cond.evaluate(request.context)?
+                    observer.condition_evaluated(index);
+                    let mut evaluate = || match cond.evaluate_budgeted(
+                        request.context,
+                        &mut groups,
+                        &mut ops,
+                        EvalMode {
+                            now,
+                            ..self.config.eval_mode()
+                        },
+                        observer,
+                        &mut evals,
+                    )? {
+                        Poll::Ready(result) => Ok(result),
+                        Poll::Pending => Err(PolicyError::EvalBudgetExceeded {
+                            max: self.config.max_condition_evals,
+                        }),
+                    };
+                    let result = match memo.as_deref_mut() {
+                        Some(m) => m.conditions[index].get_or_insert_with(evaluate).clone(),
+                        None => evaluate(),
+                    };
+                    match result {
+                        Ok(Some(matched)) => matched,
+                        Ok(None) => {
+                            tally.unknown(rule);
+                            continue;
+                        }
+                        Err(e) => {
+                            tally.failed(rule, e, &self.config)?;
+                            continue;
+                        }
+                    }
                 }
             };
 
@@ -298,36 +1116,267 @@ impl<'a> Policy<'a> {
             }
 
             // Rule matches - record the effect
-            match rule.effect {
-                Effect::Allow => {
-                    if first_allow.is_none() {
-                        first_allow = Some(rule.reason);
-                    }
-                }
-                Effect::Deny => {
-                    if first_deny.is_none() {
-                        first_deny = Some(rule.reason);
-                    }
-                }
+            if let Some(decision) = tally.matched(index, rule, &self.config, observer) {
+                return Ok(decision);
             }
         }
 
-        // Apply deny-overrides: Deny wins if any Deny matched
-        let decision = if let Some(reason) = first_deny {
-            Decision::deny(reason)
-        } else if let Some(reason) = first_allow {
-            Decision::allow(reason)
-        } else {
-            Decision::deny(NO_MATCHING_RULE)
-        };
+        tally.decide(&self.config)
+    }
 
-        Ok((decision, stats))
+    /// Check the request against the configured size limits. The context
+    /// is skipped when `check_context` is false.
+    pub(crate) fn validate_request(
+        &self,
+        request: &Request<'_>,
+        check_context: bool,
+    ) -> Result<(), PolicyError> {
+        // 1. Validate request string lengths
+        validate_str(request.principal, self.config.max_string_len)?;
+        validate_str(request.action, self.config.max_string_len)?;
+        validate_str(request.resource, self.config.max_string_len)?;
+        if let Some(environment) = request.environment {
+            validate_str(environment, self.config.max_string_len)?;
+        }
+
+        if check_context {
+            // 2. Validate context size
+            if request.context.len() > self.config.max_context_attrs {
+                return Err(PolicyError::ContextTooLarge {
+                    max: self.config.max_context_attrs,
+                    actual: request.context.len(),
+                });
+            }
+
+            // 3. Validate context key/value lengths, lists, and maps
+            for (key, value) in request.context {
+                validate_str(key, self.config.max_string_len)?;
+                self.validate_context_value(value, 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate a context value at path depth `depth`.
+    ///
+    /// Recursion is bounded by `max_path_depth`.
+    fn validate_context_value(&self, value: &Value<'_>, depth: usize) -> Result<(), PolicyError> {
+        let config = &self.config;
+        match value {
+            Value::List(list) if list.len() > config.max_set_values => {
+                Err(PolicyError::TooManySetValues {
+                    max: config.max_set_values,
+                    actual: list.len(),
+                })
+            }
+            Value::Map(_) if depth >= config.max_path_depth => Err(PolicyError::PathTooDeep {
+                max: config.max_path_depth,
+                actual: depth + 1,
+            }),
+            Value::Map(map) if map.len() > config.max_context_attrs => {
+                Err(PolicyError::ContextTooLarge {
+                    max: config.max_context_attrs,
+                    actual: map.len(),
+                })
+            }
+            Value::Map(map) => {
+                for (key, value) in map.iter() {
+                    validate_str(key, config.max_string_len)?;
+                    self.validate_context_value(&value, depth + 1)?;
+                }
+                Ok(())
+            }
+            _ => value.validate_len(config.max_string_len),
+        }
+    }
+}
+
+/// The first matching rule of each kind, combined into a decision once
+/// every rule has been checked, and the obligations of every matching
+/// rule of each effect.
+#[derive(Default)]
+pub(crate) struct RuleTally<'r, 'a> {
+    /// Whether rules needing approval may allow.
+    approved: bool,
+    allow: Option<&'r Rule<'a>>,
+    pending_approval: Option<&'r Rule<'a>>,
+    challenge: Option<&'r Rule<'a>>,
+    indeterminate: Option<&'r Rule<'a>>,
+    deny: Option<&'r Rule<'a>>,
+    failed_deny: Option<&'r Rule<'a>>,
+    allow_obligations: Gathered,
+    challenge_obligations: Gathered,
+    deny_obligations: Gathered,
+}
+
+impl<'r, 'a> RuleTally<'r, 'a> {
+    /// Record a rule whose condition failed with `err`, or return the
+    /// error if neither the rule nor the config handles it.
+    pub(crate) fn failed(
+        &mut self,
+        rule: &'r Rule<'a>,
+        err: PolicyError,
+        config: &PolicyConfig,
+    ) -> Result<(), PolicyError> {
+        if matches!(
+            err,
+            PolicyError::DeadlineExceeded | PolicyError::EvalBudgetExceeded { .. }
+        ) {
+            return Err(err);
+        }
+        match rule.provider_failure(&err) {
+            Some(ProviderFailure::Deny) => {
+                self.failed_deny.get_or_insert(rule);
+            }
+            Some(ProviderFailure::Skip) => {}
+            Some(ProviderFailure::Indeterminate) => {
+                self.indeterminate.get_or_insert(rule);
+            }
+            None if config.indeterminate_on_error => {
+                self.indeterminate.get_or_insert(rule);
+            }
+            None => return Err(err),
+        }
+        Ok(())
+    }
+
+    /// Record a rule whose condition is unknown under three-valued logic.
+    pub(crate) fn unknown(&mut self, rule: &'r Rule<'a>) {
+        // It might have denied, like a rule that failed to evaluate.
+        self.indeterminate.get_or_insert(rule);
+    }
+
+    /// Record a matching rule. A break-glass rule decides at once, so its
+    /// decision is returned.
+    pub(crate) fn matched<O: EvalObserver>(
+        &mut self,
+        index: usize,
+        rule: &'r Rule<'a>,
+        config: &PolicyConfig,
+        observer: &mut O,
+    ) -> Option<Decision> {
+        observer.rule_matched(index);
+        if rule.break_glass.is_some() {
+            // Emergency access always wins and is never cached.
+            observer.break_glass_used(index);
+            // Validation made sure the obligations fit.
+            let mut decision =
+                Decision::new(rule.effect, rule.reason).with_obligation(Obligation::Audit);
+            for &obligation in rule.obligations {
+                decision.obligations.push(obligation);
+            }
+            decision.break_glass = true;
+            return Some(decision);
+        }
+        match rule.effect {
+            Effect::Allow if rule.approval.is_some() && !self.approved => {
+                self.pending_approval.get_or_insert(rule);
+            }
+            Effect::Allow => {
+                self.allow.get_or_insert(rule);
+                self.allow_obligations.add(rule.obligations.iter().copied());
+            }
+            Effect::Deny => {
+                self.deny_obligations.add(rule.obligations.iter().copied());
+                let aggregation = config.deny_aggregation;
+                if aggregation == DenyAggregation::CollectAll {
+                    observer.deny_collected(index, rule.reason);
+                }
+                match self.deny {
+                    Some(current) if !aggregation.prefers(rule, current) => {}
+                    _ => self.deny = Some(rule),
+                }
+            }
+            Effect::Challenge(_) => {
+                self.challenge.get_or_insert(rule);
+                self.challenge_obligations
+                    .add(rule.obligations.iter().copied());
+            }
+            // Rules are never authored as Indeterminate; treat one like
+            // a failed rule so it can never weaken the decision.
+            Effect::Indeterminate => {
+                self.indeterminate.get_or_insert(rule);
+            }
+        }
+        None
+    }
+
+    /// The decision once every rule has been checked.
+    pub(crate) fn decide(self, config: &PolicyConfig) -> Result<Decision, PolicyError> {
+        // Apply deny-overrides: Deny wins if any Deny matched. A rule that
+        // failed to evaluate might have denied, so Indeterminate outranks
+        // Challenge and Allow. A Challenge outranks any Allow.
+        // The deciding rule's cache TTL hint travels with the decision,
+        // and the obligations of every matching rule with its effect.
+        // Rules failing closed deny only when no rule denied outright.
+        let (mut decision, gathered) = if let Some(rule) = self.deny {
+            let decision = Decision::deny(rule.reason).with_cache_ttl(rule.cache_ttl);
+            (decision, self.deny_obligations)
+        } else if let Some(rule) = self.failed_deny {
+            // Like Indeterminate, a failure is never cached.
+            return Ok(Decision::deny(rule.reason));
+        } else if let Some(rule) = self.indeterminate {
+            // Failures are transient by nature; never advise caching them.
+            return Ok(Decision::indeterminate(rule.reason));
+        } else if let Some(rule) = self.challenge {
+            let decision = Decision::new(rule.effect, rule.reason).with_cache_ttl(rule.cache_ttl);
+            (decision, self.challenge_obligations)
+        } else if let Some(rule) = self.allow {
+            let decision = Decision::allow(rule.reason).with_cache_ttl(rule.decision_ttl());
+            (decision, self.allow_obligations)
+        } else if let Some(rule) = self.pending_approval {
+            // Only asks for approval when nothing else grants access.
+            let decision = rule.pending_approval();
+            let mut gathered = Gathered::default();
+            gathered.add(decision.obligations.iter());
+            gathered.add(rule.obligations.iter().copied());
+            (decision, gathered)
+        } else {
+            // No matching rules - default deny
+            return Ok(Decision::deny(NO_MATCHING_RULE));
+        };
+        decision.obligations = gathered.bounded(config.max_obligations)?;
+        Ok(decision)
+    }
+}
+
+/// Obligations gathered from matching rules, deduplicated and ordered.
+#[derive(Default)]
+struct Gathered {
+    set: Obligations,
+    /// Set once an obligation did not fit in `set`.
+    overflow: bool,
+}
+
+impl Gathered {
+    fn add(&mut self, obligations: impl IntoIterator<Item = Obligation>) {
+        for obligation in obligations {
+            self.overflow |= !self.set.push(obligation);
+        }
+    }
+
+    /// The gathered set, unless it holds more than `max` obligations.
+    fn bounded(self, max: usize) -> Result<Obligations, PolicyError> {
+        if self.overflow || self.set.len() > max {
+            return Err(PolicyError::TooManyObligations { max });
+        }
+        Ok(self.set)
     }
 }
 
+/// A break-glass flag is raised by `true` or a non-empty token, either a
+/// string or a secret.
+pub(crate) fn break_glass_active(context: &[(&str, Value<'_>)], flag: &str) -> bool {
+    match context.iter().find(|(k, _)| *k == flag).map(|(_, v)| v) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(token)) => !token.is_empty(),
+        Some(Value::Secret(token)) => !token.is_empty(),
+        _ => false,
+    }
+}
 
 /// Validate that a string does not exceed the maximum allowed length.
-fn validate_str(s: &str, max_len: usize) -> Result<(), PolicyError> {
+pub(crate) fn validate_str(s: &str, max_len: usize) -> Result<(), PolicyError> {
     if s.len() > max_len {
         Err(PolicyError::StringTooLong {
             max: max_len,
@@ -343,6 +1392,12 @@ fn validate_str(s: &str, max_len: usize) -> Result<(), PolicyError> {
 pub struct PolicyBuilder<'a> {
     rules: Vec<Rule<'a>>,
     config: PolicyConfig,
+    hierarchy: ActionHierarchy<'a>,
+    metadata: PolicyMetadata<'a>,
+    ops: CustomOps<'a>,
+    schema: Option<&'a [(&'a str, AttrKind)]>,
+    definitions: Vec<(&'a str, Condition<'a>)>,
+    sensitive: Vec<(&'a str, Sensitivity)>,
 }
 
 impl<'a> PolicyBuilder<'a> {
@@ -351,6 +1406,12 @@ impl<'a> PolicyBuilder<'a> {
         PolicyBuilder {
             rules: Vec::new(),
             config: PolicyConfig::default(),
+            hierarchy: ActionHierarchy::new(),
+            metadata: PolicyMetadata::default(),
+            ops: CustomOps::default(),
+            schema: None,
+            definitions: Vec::new(),
+            sensitive: Vec::new(),
         }
     }
 
@@ -366,9 +1427,76 @@ impl<'a> PolicyBuilder<'a> {
         self
     }
 
+    /// Register an action hierarchy consulted when matching Allow rules.
+    pub fn action_hierarchy(mut self, hierarchy: ActionHierarchy<'a>) -> Self {
+        self.hierarchy = hierarchy;
+        self
+    }
+
+    /// Attach descriptive metadata identifying this policy revision.
+    pub fn metadata(mut self, metadata: PolicyMetadata<'a>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Register a custom operator for `Condition::Custom` to refer to.
+    pub fn custom_op(mut self, op: &'a dyn CustomOp) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    /// Declare the type of every context attribute the rules may read.
+    ///
+    /// Only used by `build_with_warnings`, to find rules that can never
+    /// match; requests are not checked against it.
+    pub fn attribute_schema(mut self, schema: &'a [(&'a str, AttrKind)]) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Declare context attribute `attr` sensitive, replacing any earlier
+    /// declaration for it.
+    ///
+    /// Evaluation is unchanged; `Policy::redact_context` and replay
+    /// records redact its values.
+    pub fn sensitive_attribute(mut self, attr: &'a str, sensitivity: Sensitivity) -> Self {
+        self.sensitive.retain(|(name, _)| *name != attr);
+        self.sensitive.push((attr, sensitivity));
+        self
+    }
+
+    /// Name a condition that rules can then use as `Condition::Ref(name)`.
+    ///
+    /// `build` replaces each reference with the definition, so the policy
+    /// behaves exactly as if it had been written out in every rule. A
+    /// definition may itself refer to earlier definitions. Unknown,
+    /// forward, and duplicate names fail with
+    /// `PolicyError::InvalidConditionRef`.
+    pub fn define_condition(mut self, name: &'a str, condition: Condition<'a>) -> Self {
+        self.definitions.push((name, condition));
+        self
+    }
+
     /// Build the policy.
-    pub fn build(self) -> Result<Policy<'a>, PolicyError> {
-        Policy::with_config(self.rules, self.config)
+    pub fn build(mut self) -> Result<Policy<'a>, PolicyError> {
+        let definitions = Definitions::resolve(self.definitions, &self.config)?;
+        for rule in &mut self.rules {
+            definitions.expand_rule(rule, &self.config)?;
+        }
+        Policy::with_custom_ops(self.rules, self.config, self.ops)?
+            .with_action_hierarchy(self.hierarchy)?
+            .with_metadata(self.metadata)?
+            .with_sensitive_attributes(self.sensitive)
+    }
+
+    /// Build the policy, also reporting non-fatal `Warnings`.
+    ///
+    /// Fails exactly when `build` does.
+    pub fn build_with_warnings(self) -> Result<(Policy<'a>, Warnings<'a>), PolicyError> {
+        let schema = self.schema;
+        let policy = self.build()?;
+        let warnings = Warnings::check(policy.rules(), policy.config(), schema);
+        Ok((policy, warnings))
     }
 }
 
@@ -381,6 +1509,9 @@ impl<'a> Default for PolicyBuilder<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::attr::{AttrKey, ContextBuilder};
+    use crate::condition::{ABSOLUTE_MAX_CONDITION_DEPTH, ABSOLUTE_MAX_PATH_DEPTH};
+    use crate::partial::PartialRequest;
     use crate::target::Matcher;
     use crate::value::Value;
 
@@ -443,6 +1574,48 @@ mod tests {
         assert_eq!(decision.reason, REASON_BLOCKED_USER);
     }
 
+    #[test]
+    fn test_challenge_precedence() {
+        // Challenge outranks Allow regardless of order
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), REASON_PUBLIC_READ))
+            .rule(Rule::challenge(
+                Target::any(),
+                ChallengeMethod::Mfa,
+                ReasonCode(10),
+            ))
+            .rule(Rule::challenge(
+                Target::any(),
+                ChallengeMethod::Reauthenticate,
+                ReasonCode(11),
+            ))
+            .build()
+            .unwrap();
+
+        let request = Request::new("alice", "read", "doc");
+        let decision = policy.evaluate(&request).unwrap();
+
+        assert!(decision.is_challenge());
+        assert!(!decision.is_allow());
+        assert_eq!(decision.challenge_method(), Some(ChallengeMethod::Mfa));
+        assert_eq!(decision.reason, ReasonCode(10));
+
+        // Deny outranks Challenge
+        let policy = Policy::builder()
+            .rule(Rule::challenge(
+                Target::any(),
+                ChallengeMethod::Mfa,
+                ReasonCode(10),
+            ))
+            .rule(Rule::deny(Target::any(), REASON_BLOCKED_USER))
+            .build()
+            .unwrap();
+
+        let decision = policy.evaluate(&request).unwrap();
+        assert!(decision.is_deny());
+        assert_eq!(decision.challenge_method(), None);
+    }
+
     #[test]
     fn test_first_deny_reason_returned() {
         let policy = Policy::builder()
@@ -481,6 +1654,7 @@ mod tests {
                     principal: Matcher::Exact("admin"),
                     action: Matcher::Any,
                     resource: Matcher::Any,
+                    environment: Matcher::Any,
                 },
                 REASON_ADMIN_ACCESS,
             ))
@@ -538,6 +1712,7 @@ mod tests {
             principal: Matcher::OneOf(&["a", "b", "c"]),
             action: Matcher::Any,
             resource: Matcher::Any,
+            environment: Matcher::Any,
         };
 
         let rule = Rule::allow(target, ReasonCode(1));
@@ -562,6 +1737,7 @@ mod tests {
             principal: Matcher::Exact("too-long-string"),
             action: Matcher::Any,
             resource: Matcher::Any,
+            environment: Matcher::Any,
         };
 
         let rule = Rule::allow(target, ReasonCode(1));
@@ -672,6 +1848,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_too_many_condition_nodes() {
+        let config = PolicyConfig {
+            max_condition_nodes: 4,
+            ..Default::default()
+        };
+        let children = [
+            Condition::True,
+            Condition::True,
+            Condition::True,
+            Condition::True,
+        ];
+
+        let result = Policy::with_config(
+            vec![Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::AnyOf(&children)),
+                ReasonCode(1),
+            )],
+            config,
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            PolicyError::TooManyConditionNodes { max: 4, actual: 5 }
+        );
+    }
+
     #[test]
     fn test_deterministic_evaluation() {
         let actions: &[&str] = &["read", "write"];
@@ -681,6 +1886,7 @@ mod tests {
                     principal: Matcher::Exact("blocked"),
                     action: Matcher::Any,
                     resource: Matcher::Any,
+                    environment: Matcher::Any,
                 },
                 REASON_BLOCKED_USER,
             ))
@@ -689,6 +1895,7 @@ mod tests {
                     principal: Matcher::Any,
                     action: Matcher::OneOf(actions),
                     resource: Matcher::Any,
+                    environment: Matcher::Any,
                 },
                 REASON_WRITE_ALLOWED,
             ))
@@ -743,49 +1950,1192 @@ mod tests {
             policy.evaluate(&req),
             Err(PolicyError::StringTooLong { max: 10, .. })
         ));
+
+        // 5. Secret too long
+        let ctx: &[(&str, Value)] = &[("key", Value::Secret(b"0123456789a"))];
+        let req = Request::with_context("alice", "read", "doc", ctx);
+        assert!(matches!(
+            policy.evaluate(&req),
+            Err(PolicyError::StringTooLong { max: 10, .. })
+        ));
     }
 
     #[test]
-    fn test_evaluate_with_stats() {
-        use crate::condition::Condition;
-        use crate::value::Value;
-
-        // Policy with 3 rules, one with a condition
+    fn test_secret_attribute() {
+        const API_KEY: AttrKey<&[u8]> = AttrKey::new("api_key");
         let policy = Policy::builder()
             .rule(Rule::new(
                 Effect::Allow,
-                Target {
-                    principal: Matcher::Exact("admin"),
-                    action: Matcher::Any,
-                    resource: Matcher::Any,
-                },
-                None,
+                Target::any(),
+                Some(API_KEY.equals(b"sk_live_123")),
                 ReasonCode(1),
             ))
+            .build()
+            .unwrap();
+
+        let ctx = ContextBuilder::new().set(API_KEY, b"sk_live_123").build();
+        let req = Request::with_context("svc", "call", "api", &ctx);
+        assert!(policy.evaluate(&req).unwrap().is_allow());
+
+        // A string with the same bytes is a different type.
+        let ctx: &[(&str, Value)] = &[("api_key", Value::String("sk_live_123"))];
+        let req = Request::with_context("svc", "call", "api", ctx);
+        assert!(policy.evaluate(&req).unwrap().is_deny());
+
+        assert!(!format!("{:?}", policy).contains("sk_live"));
+    }
+
+    /// Bypass validation to get a rule whose evaluation fails.
+    fn policy_with_failing_rule(config: PolicyConfig, rest: Vec<Rule<'static>>) -> Policy<'static> {
+        // Left-nested And chains grow the traversal stack past its hard cap.
+        let mut cond = Condition::True;
+        for _ in 0..4 * ABSOLUTE_MAX_CONDITION_DEPTH {
+            cond = Condition::And(Box::new(cond), Box::new(Condition::True));
+        }
+        let mut rules = vec![Rule::new(
+            Effect::Allow,
+            Target::any(),
+            Some(cond),
+            ReasonCode(99),
+        )];
+        rules.extend(rest);
+        Policy {
+            rules,
+            config,
+            actions: ImpliedActions::default(),
+            metadata: PolicyMetadata::default(),
+            ops: CustomOps::default(),
+            sensitive: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_indeterminate_on_error() {
+        let request = Request::new("alice", "read", "doc");
+
+        // Default: evaluation failures are errors
+        let policy = policy_with_failing_rule(PolicyConfig::default(), vec![]);
+        assert!(matches!(
+            policy.evaluate(&request),
+            Err(PolicyError::EvalStackOverflow { .. })
+        ));
+
+        let config = PolicyConfig {
+            indeterminate_on_error: true,
+            ..PolicyConfig::default()
+        };
+
+        // Indeterminate outranks Allow
+        let policy = policy_with_failing_rule(
+            config,
+            vec![Rule::allow(Target::any(), REASON_PUBLIC_READ).with_cache_ttl(60)],
+        );
+        let decision = policy.evaluate(&request).unwrap();
+        assert!(decision.is_indeterminate());
+        assert!(!decision.is_allow());
+        assert_eq!(decision.reason, ReasonCode(99));
+        assert_eq!(decision.cache_ttl, None);
+
+        // Deny outranks Indeterminate
+        let policy =
+            policy_with_failing_rule(config, vec![Rule::deny(Target::any(), REASON_BLOCKED_USER)]);
+        let decision = policy.evaluate(&request).unwrap();
+        assert!(decision.is_deny());
+        assert_eq!(decision.reason, REASON_BLOCKED_USER);
+
+        // Malformed requests are still rejected
+        let ctx: Vec<(&str, Value)> = (0..65).map(|i| ("k", Value::Int(i))).collect();
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert!(matches!(
+            policy.evaluate(&request),
+            Err(PolicyError::ContextTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_on_provider_failure() {
+        use crate::groups::ProviderError;
+
+        struct Directory;
+        impl GroupProvider for Directory {
+            fn is_member(&self, _principal: &str, group: &str) -> Result<bool, ProviderError> {
+                match group {
+                    "offline" => Err(ProviderError),
+                    _ => Ok(group == "eng"),
+                }
+            }
+        }
+
+        let offline = |on_failure| {
+            let rule = Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::MemberOf("offline")),
+                ReasonCode(7),
+            );
+            match on_failure {
+                Some(on_failure) => rule.with_provider_failure(on_failure),
+                None => rule,
+            }
+        };
+        let eng = Rule::new(
+            Effect::Allow,
+            Target::any(),
+            Some(Condition::MemberOf("eng")),
+            REASON_ADMIN_ACCESS,
+        )
+        .with_cache_ttl(60);
+        let policy = |on_failure| Policy::new(vec![offline(on_failure), eng.clone()]).unwrap();
+        let request = Request::new("alice", "read", "repo");
+
+        // Default: the failure is an error
+        assert_eq!(
+            policy(None).evaluate_with_groups(&request, &Directory),
+            Err(PolicyError::GroupLookupFailed)
+        );
+
+        let decision = policy(Some(ProviderFailure::Skip))
+            .evaluate_with_groups(&request, &Directory)
+            .unwrap();
+        assert_eq!(
+            decision,
+            Decision::allow(REASON_ADMIN_ACCESS).with_cache_ttl(Some(60))
+        );
+
+        let decision = policy(Some(ProviderFailure::Deny))
+            .evaluate_with_groups(&request, &Directory)
+            .unwrap();
+        assert_eq!(decision, Decision::deny(ReasonCode(7)));
+
+        let decision = policy(Some(ProviderFailure::Indeterminate))
+            .evaluate_with_groups(&request, &Directory)
+            .unwrap();
+        assert_eq!(decision, Decision::indeterminate(ReasonCode(7)));
+
+        // A real Deny still decides over a rule failing closed
+        let rules = vec![
+            offline(Some(ProviderFailure::Deny)),
+            Rule::deny(Target::any(), REASON_BLOCKED_USER),
+        ];
+        let decision = Policy::new(rules)
+            .unwrap()
+            .evaluate_with_groups(&request, &Directory)
+            .unwrap();
+        assert_eq!(decision.reason, REASON_BLOCKED_USER);
+
+        // Budget exhaustion is not a provider failure
+        let config = PolicyConfig {
+            max_group_lookups: 0,
+            ..PolicyConfig::default()
+        };
+        let rules = vec![offline(Some(ProviderFailure::Skip))];
+        let policy = Policy::with_config(rules, config).unwrap();
+        assert_eq!(
+            policy.evaluate_with_groups(&request, &Directory),
+            Err(PolicyError::GroupLookupBudgetExceeded { max: 0 })
+        );
+
+        // Standalone rules have no provider, so always fail
+        let rule = offline(Some(ProviderFailure::Deny));
+        assert_eq!(
+            rule.evaluate(&request),
+            Ok(Some(Decision::deny(ReasonCode(7))))
+        );
+        assert_eq!(
+            offline(None).evaluate(&request),
+            Err(PolicyError::GroupLookupFailed)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_with_groups() {
+        use crate::groups::ProviderError;
+
+        struct Directory;
+        impl GroupProvider for Directory {
+            fn is_member(&self, principal: &str, group: &str) -> Result<bool, ProviderError> {
+                Ok(principal == "alice" && group == "eng")
+            }
+        }
+
+        let policy = Policy::builder()
             .rule(Rule::new(
                 Effect::Allow,
                 Target::any(),
-                Some(Condition::Equals {
-                    attr: "role",
-                    value: Value::String("editor"),
-                }),
-                ReasonCode(2),
+                Some(Condition::MemberOf("eng")),
+                REASON_ADMIN_ACCESS,
             ))
-            .rule(Rule::allow(Target::any(), ReasonCode(3)))
             .build()
             .unwrap();
 
-        // Request that matches rule 3 (after checking all rules)
-        let ctx: &[(&str, Value)] = &[("role", Value::String("viewer"))];
-        let request = Request::with_context("alice", "read", "doc", ctx);
+        let alice = Request::new("alice", "read", "repo");
+        let bob = Request::new("bob", "read", "repo");
+        assert!(policy
+            .evaluate_with_groups(&alice, &Directory)
+            .unwrap()
+            .is_allow());
+        assert!(policy
+            .evaluate_with_groups(&bob, &Directory)
+            .unwrap()
+            .is_deny());
+
+        // Without a provider, membership cannot be established
+        assert_eq!(policy.evaluate(&alice), Err(PolicyError::GroupLookupFailed));
+
+        // The lookup budget is enforced per request
+        let config = PolicyConfig {
+            max_group_lookups: 0,
+            ..PolicyConfig::default()
+        };
+        let rules = vec![Rule::new(
+            Effect::Allow,
+            Target::any(),
+            Some(Condition::MemberOf("eng")),
+            REASON_ADMIN_ACCESS,
+        )];
+        let policy = Policy::with_config(rules, config).unwrap();
+        assert_eq!(
+            policy.evaluate_with_groups(&alice, &Directory),
+            Err(PolicyError::GroupLookupBudgetExceeded { max: 0 })
+        );
+    }
 
-        let (decision, stats) = policy.evaluate_with_stats(&request).unwrap();
+    #[test]
+    fn test_condition_eval_budget() {
+        let both = Condition::And(Box::new(Condition::True), Box::new(Condition::True));
+        let steps = {
+            let mut cursor = both.cursor(&[]);
+            assert_eq!(cursor.resume(usize::MAX), Poll::Ready(Ok(true)));
+            cursor.steps() as usize
+        };
 
-        assert!(decision.is_allow());
-        assert_eq!(decision.reason, ReasonCode(3));
-        // Should have checked all 3 rules
-        assert_eq!(stats.rules_checked, 3);
-        // Rule 2 has a condition that was evaluated
-        assert_eq!(stats.condition_evals, 1);
+        // The budget is shared by every rule of the request.
+        let rules = vec![
+            Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(both.clone()),
+                REASON_BLOCKED_USER,
+            ),
+            Rule::new(Effect::Allow, Target::any(), Some(both), REASON_PUBLIC_READ),
+        ];
+        let request = Request::new("alice", "read", "doc");
+        for (budget, expected) in [
+            (2 * steps, Ok(Decision::deny(REASON_BLOCKED_USER))),
+            (
+                2 * steps - 1,
+                Err(PolicyError::EvalBudgetExceeded { max: 2 * steps - 1 }),
+            ),
+        ] {
+            for indeterminate_on_error in [false, true] {
+                let config = PolicyConfig {
+                    max_condition_evals: budget,
+                    indeterminate_on_error,
+                    ..PolicyConfig::default()
+                };
+                let policy = Policy::with_config(rules.clone(), config).unwrap();
+                assert_eq!(policy.evaluate(&request), expected);
+                for slice in [1, 3, usize::MAX] {
+                    let mut cursor = policy.cursor(&request);
+                    let result = loop {
+                        if let Poll::Ready(result) = cursor.resume(slice) {
+                            break result;
+                        }
+                    };
+                    assert_eq!(result, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rule_schedule() {
+        use crate::schedule::{Days, TimeWindow};
+
+        const BUSINESS_HOURS: &[TimeWindow] = &[TimeWindow::new(9, 0, 17, 0)];
+        // 2024-01-01 was a Monday.
+        const MONDAY_10AM: i64 = 1_704_067_200 + 10 * 3600;
+        const SATURDAY_10AM: i64 = MONDAY_10AM + 5 * 24 * 3600;
+
+        let policy = Policy::builder()
+            .rule(
+                Rule::allow(Target::any(), REASON_WRITE_ALLOWED)
+                    .with_schedule(Schedule::new(Days::WEEKDAYS, BUSINESS_HOURS)),
+            )
+            .build()
+            .unwrap();
+
+        let ctx: &[(&str, Value)] = &[("now", Value::Int(MONDAY_10AM))];
+        let request = Request::with_context("alice", "write", "doc", ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+
+        let ctx: &[(&str, Value)] = &[("now", Value::Int(SATURDAY_10AM))];
+        let request = Request::with_context("alice", "write", "doc", ctx);
+        assert_eq!(
+            policy.evaluate(&request).unwrap(),
+            Decision::deny(NO_MATCHING_RULE)
+        );
+
+        // No clock: the rule does not apply
+        let request = Request::new("alice", "write", "doc");
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+
+        // Invalid schedules are rejected at construction
+        const BAD: &[TimeWindow] = &[TimeWindow::new(9, 0, 30, 0)];
+        let result = Policy::builder()
+            .rule(
+                Rule::allow(Target::any(), REASON_WRITE_ALLOWED)
+                    .with_schedule(Schedule::new(Days::ALL, BAD)),
+            )
+            .build();
+        assert!(matches!(result, Err(PolicyError::InvalidSchedule)));
+        let result = Policy::builder()
+            .rule(
+                Rule::allow(Target::any(), REASON_WRITE_ALLOWED)
+                    .with_schedule(Schedule::new(Days::ALL, &[]).with_utc_offset(i32::MIN)),
+            )
+            .build();
+        assert!(matches!(result, Err(PolicyError::InvalidSchedule)));
+    }
+
+    #[test]
+    fn test_break_glass() {
+        let policy = Policy::builder()
+            .rule(Rule::deny(Target::any(), REASON_BLOCKED_USER))
+            .rule(Rule::break_glass(
+                Target::any(),
+                "emergency",
+                ReasonCode(911),
+            ))
+            .build()
+            .unwrap();
+
+        // Flag down: the break-glass rule does not exist
+        let request = Request::new("oncall", "write", "prod-db");
+        let (decision, stats) = policy.evaluate_with_stats(&request).unwrap();
+        assert_eq!(decision, Decision::deny(REASON_BLOCKED_USER));
+        assert!(!stats.break_glass);
+
+        // Flag raised: overrides the deny and demands an audit
+        let ctx: &[(&str, Value)] = &[("emergency", Value::String("INC-4312"))];
+        let request = Request::with_context("oncall", "write", "prod-db", ctx);
+        let (decision, stats) = policy.evaluate_with_stats(&request).unwrap();
+        assert!(decision.is_allow());
+        assert!(decision.break_glass);
+        assert_eq!(decision.reason, ReasonCode(911));
+        assert!(decision.obligations.contains(Obligation::Audit));
+        assert!(stats.break_glass);
+
+        // Explicitly lowered flag
+        let ctx: &[(&str, Value)] = &[("emergency", Value::Bool(false))];
+        let request = Request::with_context("oncall", "write", "prod-db", ctx);
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+    }
+
+    #[test]
+    fn test_owner_allow() {
+        let policy = Policy::builder()
+            .rule(Rule::owner_allow(
+                Matcher::OneOf(&["read", "write"]),
+                ReasonCode(7),
+            ))
+            .build()
+            .unwrap();
+
+        let ctx: &[(&str, Value)] = &[(DEFAULT_OWNER_ATTR, Value::String("alice"))];
+        let own = Request::with_context("alice", "write", "doc-1", ctx);
+        assert_eq!(
+            policy.evaluate(&own).unwrap(),
+            Decision::allow(ReasonCode(7))
+        );
+        let other = Request::with_context("bob", "write", "doc-1", ctx);
+        assert!(policy.evaluate(&other).unwrap().is_deny());
+        let delete = Request::with_context("alice", "delete", "doc-1", ctx);
+        assert!(policy.evaluate(&delete).unwrap().is_deny());
+
+        // No owner recorded, or an anonymous caller matching an empty owner
+        let unowned = Request::new("alice", "read", "doc-1");
+        assert!(policy.evaluate(&unowned).unwrap().is_deny());
+        let ctx: &[(&str, Value)] = &[(DEFAULT_OWNER_ATTR, Value::String(""))];
+        let anonymous = Request::with_context("", "read", "doc-1", ctx);
+        assert!(policy.evaluate(&anonymous).unwrap().is_deny());
+    }
+
+    #[test]
+    fn test_deny_aggregation() {
+        let rules = vec![
+            Rule::deny(Target::any(), ReasonCode(30)).with_priority(1),
+            Rule::deny(Target::any(), ReasonCode(10)),
+            Rule::deny(Target::any(), ReasonCode(20)).with_priority(5),
+            Rule::deny(Target::any(), ReasonCode(40)).with_priority(5),
+        ];
+        let request = Request::new("alice", "read", "doc");
+        let reason = |deny_aggregation| {
+            let config = PolicyConfig {
+                deny_aggregation,
+                ..PolicyConfig::default()
+            };
+            let policy = Policy::with_config(rules.clone(), config).unwrap();
+            policy.evaluate(&request).unwrap().reason
+        };
+
+        assert_eq!(reason(DenyAggregation::FirstMatch), ReasonCode(30));
+        assert_eq!(reason(DenyAggregation::LowestReason), ReasonCode(10));
+        // Equal priorities keep declared order
+        assert_eq!(reason(DenyAggregation::HighestPriority), ReasonCode(20));
+        assert_eq!(reason(DenyAggregation::CollectAll), ReasonCode(30));
+    }
+
+    #[test]
+    fn test_short_circuit_config() {
+        let rule = Rule::new(
+            Effect::Allow,
+            Target::any(),
+            Some(Condition::Or(
+                Box::new(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("admin"),
+                }),
+                Box::new(Condition::MemberOf("admins")),
+            )),
+            REASON_PUBLIC_READ,
+        );
+        let ctx: &[(&str, Value)] = &[("role", Value::String("admin"))];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+
+        let lazy = Policy::new(vec![rule.clone()]).unwrap();
+        assert!(lazy.evaluate(&request).unwrap().is_allow());
+
+        let config = PolicyConfig {
+            short_circuit: false,
+            ..PolicyConfig::default()
+        };
+        let eager = Policy::with_config(vec![rule], config).unwrap();
+        assert_eq!(
+            eager.evaluate(&request),
+            Err(PolicyError::GroupLookupFailed)
+        );
+        assert_ne!(lazy.fingerprint(), eager.fingerprint());
+    }
+
+    #[test]
+    fn test_evaluate_or_deny() {
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .build()
+            .unwrap();
+
+        let ok = Request::new("alice", "read", "doc");
+        assert_eq!(policy.evaluate_or_deny(&ok), Decision::allow(ReasonCode(1)));
+
+        let long = "x".repeat(1000);
+        let bad = Request::new(&long, "read", "doc");
+        let mut seen = None;
+        let decision = policy.evaluate_or_deny_with(&bad, |e| seen = Some(e.clone()));
+        assert_eq!(decision, Decision::deny(EVALUATION_FAILED));
+        assert!(matches!(seen, Some(PolicyError::StringTooLong { .. })));
+    }
+
+    #[test]
+    fn test_rule_evaluate() {
+        let admin = Rule::new(
+            Effect::Allow,
+            Target {
+                principal: Matcher::Any,
+                action: Matcher::Exact("write"),
+                resource: Matcher::Any,
+                environment: Matcher::Any,
+            },
+            Some(Condition::Equals {
+                attr: "role",
+                value: Value::String("admin"),
+            }),
+            REASON_ADMIN_ACCESS,
+        )
+        .with_cache_ttl(30);
+
+        let ctx: &[(&str, Value)] = &[("role", Value::String("admin"))];
+        let request = Request::with_context("alice", "write", "doc", ctx);
+        let decision = admin.evaluate(&request).unwrap().unwrap();
+        assert!(decision.is_allow());
+        assert_eq!(decision.reason, REASON_ADMIN_ACCESS);
+        assert_eq!(decision.cache_ttl, Some(30));
+
+        // Target mismatch and false condition both mean "does not apply"
+        let request = Request::with_context("alice", "read", "doc", ctx);
+        assert_eq!(admin.evaluate(&request), Ok(None));
+        let request = Request::new("alice", "write", "doc");
+        assert_eq!(admin.evaluate(&request), Ok(None));
+
+        // Owner rules see the request's principal
+        let owner = Rule::owner_allow(Matcher::Any, REASON_WRITE_ALLOWED);
+        let ctx: &[(&str, Value)] = &[(DEFAULT_OWNER_ATTR, Value::String("alice"))];
+        let request = Request::with_context("alice", "write", "doc", ctx);
+        assert!(owner.evaluate(&request).unwrap().is_some());
+        let request = Request::with_context("bob", "write", "doc", ctx);
+        assert_eq!(owner.evaluate(&request), Ok(None));
+
+        // Break-glass rules carry their audit obligation
+        let glass = Rule::break_glass(Target::any(), "emergency", REASON_ADMIN_ACCESS);
+        assert_eq!(glass.evaluate(&Request::new("a", "b", "c")), Ok(None));
+        let ctx: &[(&str, Value)] = &[("emergency", Value::Bool(true))];
+        let decision = glass
+            .evaluate(&Request::with_context("a", "b", "c", ctx))
+            .unwrap()
+            .unwrap();
+        assert!(decision.break_glass);
+
+        let member = Rule::new(
+            Effect::Deny,
+            Target::any(),
+            Some(Condition::MemberOf("staff")),
+            REASON_BLOCKED_USER,
+        );
+        assert_eq!(
+            member.evaluate(&Request::new("a", "b", "c")),
+            Err(PolicyError::GroupLookupFailed)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_strict() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::Equals {
+                    attr: "suspended",
+                    value: Value::Bool(true),
+                }),
+                REASON_BLOCKED_USER,
+            ))
+            .rule(Rule::allow(Target::any(), REASON_PUBLIC_READ))
+            .build()
+            .unwrap();
+
+        // The lenient path reads the first entry and allows
+        let ctx: &[(&str, Value)] = &[
+            ("suspended", Value::Bool(false)),
+            ("suspended", Value::Bool(true)),
+        ];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+        assert_eq!(
+            policy.evaluate_strict(&request),
+            Err(PolicyError::ConflictingAttribute { index: 1 })
+        );
+
+        let request = Request::with_context("alice", "read", "doc", &ctx[1..]);
+        assert!(policy.evaluate_strict(&request).unwrap().is_deny());
+    }
+
+    #[test]
+    fn test_strict_types() {
+        let rules = || {
+            vec![Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Equals {
+                    attr: "count",
+                    value: Value::String("3"),
+                }),
+                REASON_PUBLIC_READ,
+            )]
+        };
+        let ctx: &[(&str, Value)] = &[("count", Value::Int(3))];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+
+        let policy = Policy::new(rules()).unwrap();
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+
+        let config = PolicyConfig {
+            strict_types: true,
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::with_config(rules(), config).unwrap();
+        assert_eq!(
+            policy.evaluate(&request),
+            Err(PolicyError::TypeMismatch {
+                attr: "count".to_string(),
+                expected: "String",
+                actual: "Int",
+            })
+        );
+
+        let config = PolicyConfig {
+            indeterminate_on_error: true,
+            ..config
+        };
+        let policy = Policy::with_config(rules(), config).unwrap();
+        assert!(policy.evaluate(&request).unwrap().is_indeterminate());
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        let rules = || {
+            vec![
+                Rule::deny(
+                    Target {
+                        resource: Matcher::Exact("vault"),
+                        ..Target::any()
+                    },
+                    REASON_BLOCKED_USER,
+                ),
+                Rule::new(
+                    Effect::Deny,
+                    Target::any(),
+                    Some(Condition::Equals {
+                        attr: "suspended",
+                        value: Value::Bool(true),
+                    }),
+                    REASON_BLOCKED_USER,
+                ),
+                Rule::new(
+                    Effect::Allow,
+                    Target::any(),
+                    Some(Condition::Or(
+                        Box::new(Condition::Equals {
+                            attr: "role",
+                            value: Value::String("admin"),
+                        }),
+                        Box::new(Condition::Equals {
+                            attr: "owner",
+                            value: Value::Bool(true),
+                        }),
+                    )),
+                    REASON_ADMIN_ACCESS,
+                ),
+            ]
+        };
+        let config = PolicyConfig {
+            three_valued_logic: true,
+            ..PolicyConfig::default()
+        };
+        let lenient = Policy::new(rules()).unwrap();
+        let policy = Policy::with_config(rules(), config).unwrap();
+        let decide = |policy: &Policy<'_>, resource, ctx| {
+            let request = Request::with_context("alice", "read", resource, ctx);
+            let cursor_decision = policy.cursor(&request).resume(usize::MAX);
+            let decision = policy.evaluate(&request);
+            assert_eq!(cursor_decision, Poll::Ready(decision.clone()));
+            decision.unwrap()
+        };
+
+        // Nothing is missing: the usual decision.
+        let known: &[(&str, Value)] = &[
+            ("suspended", Value::Bool(false)),
+            ("role", Value::String("admin")),
+        ];
+        assert_eq!(
+            decide(&policy, "doc", known),
+            decide(&lenient, "doc", known)
+        );
+        assert!(decide(&policy, "doc", known).is_allow());
+
+        // Without `suspended`, the Deny rule might have matched.
+        let unknown: &[(&str, Value)] = &[("role", Value::String("admin"))];
+        assert!(decide(&lenient, "doc", unknown).is_allow());
+        let decision = decide(&policy, "doc", unknown);
+        assert!(decision.is_indeterminate());
+        assert_eq!(decision.reason, REASON_BLOCKED_USER);
+
+        // A definite Deny still wins.
+        assert!(decide(&policy, "vault", unknown).is_deny());
+
+        // `role` alone decided the Or above without `owner`; a staff
+        // member's access depends on it.
+        let staff: &[(&str, Value)] = &[
+            ("suspended", Value::Bool(false)),
+            ("role", Value::String("staff")),
+        ];
+        assert!(decide(&lenient, "doc", staff).is_deny());
+        assert!(decide(&policy, "doc", staff).is_indeterminate());
+    }
+
+    #[test]
+    fn test_approval_rule() {
+        let deploy = Target {
+            action: Matcher::Exact("deploy"),
+            ..Target::any()
+        };
+        let gated = Rule::allow(deploy, REASON_PUBLIC_READ)
+            .with_approval(7, 600)
+            .with_cache_ttl(3600);
+        let policy = Policy::new(vec![
+            gated.clone(),
+            Rule::allow(Target::any(), REASON_ADMIN_ACCESS).with_approval(7, 60),
+        ])
+        .unwrap();
+        let request = Request::new("alice", "deploy", "api");
+
+        let pending = policy.evaluate(&request).unwrap();
+        assert!(pending.is_deny());
+        assert_eq!(pending.reason, REASON_PUBLIC_READ);
+        let approval = Approval {
+            approvers: 7,
+            expires_in: 600,
+        };
+        assert!(pending.obligations.contains(Obligation::Approval(approval)));
+        assert_eq!(gated.evaluate(&request), Ok(Some(pending)));
+
+        let approved = policy.evaluate_with_approval(&request, "tok-1").unwrap();
+        assert!(approved.is_allow());
+        assert!(approved.obligations.is_empty());
+        // Never cached beyond the approval's lifetime
+        assert_eq!(approved.cache_ttl, Some(600));
+        assert!(policy
+            .evaluate_with_approval(&request, "")
+            .unwrap()
+            .is_deny());
+
+        // A token in the context is not an approval, whatever its type
+        for token in [Value::String("tok-1"), Value::Secret(b"tok-1")] {
+            let ctx = [(DEFAULT_APPROVAL_ATTR, token)];
+            let injected = Request::with_context("alice", "deploy", "api", &ctx);
+            assert_eq!(policy.evaluate(&injected), Ok(pending));
+            assert_eq!(gated.evaluate(&injected), Ok(Some(pending)));
+            let residual = policy
+                .partial_evaluate(&PartialRequest::new("alice", "deploy").with_context(&ctx))
+                .unwrap();
+            assert!(residual.is_never());
+        }
+
+        // The rule's own obligations travel with the pending decision
+        let audited = gated.clone().with_obligations(&[Obligation::Audit]);
+        let pending = audited.evaluate(&request).unwrap().unwrap();
+        assert!(pending.obligations.contains(Obligation::Audit));
+        assert!(pending.obligations.contains(Obligation::Approval(approval)));
+        let policy = Policy::new(vec![audited]).unwrap();
+        assert_eq!(policy.evaluate(&request), Ok(pending));
+
+        // An ordinary grant needs no approval; a Deny still wins
+        let open = Policy::new(vec![
+            gated.clone(),
+            Rule::allow(Target::any(), REASON_ADMIN_ACCESS),
+        ])
+        .unwrap();
+        assert!(open.evaluate(&request).unwrap().is_allow());
+        let closed = Policy::new(vec![
+            gated.clone(),
+            Rule::deny(Target::any(), REASON_BLOCKED_USER),
+        ]);
+        let denied = closed
+            .unwrap()
+            .evaluate_with_approval(&request, "tok-1")
+            .unwrap();
+        assert_eq!(denied, Decision::deny(REASON_BLOCKED_USER));
+
+        for rule in [
+            Rule::deny(Target::any(), REASON_BLOCKED_USER).with_approval(7, 600),
+            Rule::break_glass(Target::any(), "sos", REASON_BLOCKED_USER).with_approval(7, 600),
+            Rule::allow(Target::any(), REASON_BLOCKED_USER).with_approval(7, 0),
+        ] {
+            assert_eq!(
+                Policy::new(vec![rule]).unwrap_err(),
+                PolicyError::InvalidApproval { rule: 0 }
+            );
+        }
+    }
+
+    #[test]
+    fn test_rule_tags() {
+        let rules = vec![
+            Rule::allow(Target::any(), REASON_PUBLIC_READ).with_tags(&["pci", "team:payments"]),
+            Rule::deny(Target::any(), REASON_ADMIN_ACCESS),
+            Rule::allow(Target::any(), REASON_PUBLIC_READ).with_tags(&["pci"]),
+        ];
+        let policy = Policy::new(rules.clone()).unwrap();
+        assert!(policy.rules()[0].has_tag("team:payments"));
+        assert_eq!(policy.rules_tagged("pci"), [0, 2]);
+        assert!(policy.rules_tagged("temporary").is_empty());
+
+        let untagged = rules.into_iter().map(|r| r.with_tags(&[])).collect();
+        assert_ne!(
+            policy.fingerprint(),
+            Policy::new(untagged).unwrap().fingerprint()
+        );
+
+        let many = Rule::allow(Target::any(), REASON_PUBLIC_READ).with_tags(&["t"; 17]);
+        assert_eq!(
+            Policy::new(vec![many]).unwrap_err(),
+            PolicyError::TooManyTags {
+                max: MAX_RULE_TAGS,
+                actual: 17
+            }
+        );
+        let long = "x".repeat(300);
+        let tags = [long.as_str()];
+        let rule = Rule::allow(Target::any(), REASON_PUBLIC_READ).with_tags(&tags);
+        assert!(matches!(
+            Policy::new(vec![rule]),
+            Err(PolicyError::StringTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rule_obligations() {
+        let request = Request::new("alice", "read", "doc");
+        let rules = vec![
+            Rule::allow(Target::any(), REASON_PUBLIC_READ)
+                .with_obligations(&[Obligation::Custom(2), Obligation::Audit]),
+            Rule::allow(Target::any(), REASON_ADMIN_ACCESS)
+                .with_obligations(&[Obligation::Custom(1), Obligation::Custom(2)]),
+        ];
+        // Every matching Allow rule contributes, deduplicated and in
+        // `Obligation` order whatever the rule order.
+        let expected = [
+            Obligation::Audit,
+            Obligation::Custom(1),
+            Obligation::Custom(2),
+        ];
+        let mut reversed = rules.clone();
+        reversed.reverse();
+        for rules in [rules.clone(), reversed] {
+            let decision = Policy::new(rules).unwrap().evaluate(&request).unwrap();
+            assert_eq!(decision.obligations.iter().collect::<Vec<_>>(), expected);
+        }
+
+        // Only rules with the deciding effect contribute.
+        let mut with_deny = rules.clone();
+        with_deny.push(
+            Rule::deny(Target::any(), REASON_BLOCKED_USER)
+                .with_obligations(&[Obligation::Custom(9)]),
+        );
+        let decision = Policy::new(with_deny).unwrap().evaluate(&request).unwrap();
+        assert_eq!(decision.effect, Effect::Deny);
+        assert_eq!(
+            decision.obligations.iter().collect::<Vec<_>>(),
+            [Obligation::Custom(9)]
+        );
+
+        // Each rule fits on its own, but the decision would not.
+        let config = PolicyConfig {
+            max_obligations: 2,
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::with_config(rules.clone(), config).unwrap();
+        assert_eq!(
+            policy.evaluate(&request),
+            Err(PolicyError::TooManyObligations { max: 2 })
+        );
+
+        let crowded = Rule::break_glass(Target::any(), "emergency", REASON_PUBLIC_READ)
+            .with_obligations(&[Obligation::Custom(1), Obligation::Custom(2)]);
+        assert_eq!(
+            Policy::with_config(vec![crowded], config).unwrap_err(),
+            PolicyError::TooManyObligations { max: 2 }
+        );
+        let config = PolicyConfig {
+            max_obligations: MAX_OBLIGATIONS + 1,
+            ..PolicyConfig::default()
+        };
+        assert_eq!(
+            Policy::with_config(vec![], config).unwrap_err(),
+            PolicyError::TooManyObligations {
+                max: MAX_OBLIGATIONS
+            }
+        );
+    }
+
+    #[test]
+    fn test_collation() {
+        let rules = || {
+            vec![Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Exact("Alice"),
+                    action: Matcher::Exact("Read"),
+                    resource: Matcher::OneOf(&["Report.pdf"]),
+                    environment: Matcher::Exact("PROD"),
+                },
+                Some(Condition::And(
+                    Box::new(Condition::Equals {
+                        attr: "dept",
+                        value: Value::String("Eng"),
+                    }),
+                    Box::new(Condition::AttrIsPrincipal("owner")),
+                )),
+                REASON_PUBLIC_READ,
+            )]
+        };
+        let ctx: &[(&str, Value)] = &[
+            ("dept", Value::String("ENG")),
+            ("owner", Value::String("alice")),
+        ];
+        let request =
+            Request::with_context("alice", "read", "report.PDF", ctx).in_environment("prod");
+
+        let binary = Policy::new(rules()).unwrap();
+        assert!(binary.evaluate(&request).unwrap().is_deny());
+
+        let config = PolicyConfig {
+            collation: Collation::AsciiCaseInsensitive,
+            ..PolicyConfig::default()
+        };
+        let folded = Policy::with_config(rules(), config).unwrap();
+        assert!(folded.evaluate(&request).unwrap().is_allow());
+        assert_ne!(binary.fingerprint(), folded.fingerprint());
+
+        // Non-ASCII letters still compare exactly
+        let request =
+            Request::with_context("ALİCE", "read", "report.pdf", ctx).in_environment("prod");
+        assert!(folded.evaluate(&request).unwrap().is_deny());
+    }
+
+    #[test]
+    fn test_max_set_values() {
+        let values = [Value::Int(1), Value::Int(2), Value::Int(3)];
+        let rule = || {
+            Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::In {
+                    attr: "tier",
+                    values: &values,
+                }),
+                REASON_PUBLIC_READ,
+            )
+        };
+        let config = PolicyConfig {
+            max_set_values: 2,
+            ..PolicyConfig::default()
+        };
+        assert_eq!(
+            Policy::with_config(vec![rule()], config).unwrap_err(),
+            PolicyError::TooManySetValues { max: 2, actual: 3 }
+        );
+
+        let policy = Policy::new(vec![rule()]).unwrap();
+        let ctx = [("tier", Value::Int(3))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+
+        // Context lists share the limit
+        let policy = Policy::with_config(vec![], config).unwrap();
+        let ctx = [("groups", Value::list(&values))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert_eq!(
+            policy.evaluate(&request).unwrap_err(),
+            PolicyError::TooManySetValues { max: 2, actual: 3 }
+        );
+    }
+
+    #[test]
+    fn test_require_attribute() {
+        let lenient = Condition::Equals {
+            attr: "team",
+            value: Value::String("ops"),
+        };
+        let required =
+            AttrKey::<&str>::new("user_id").require(Condition::AttrIsPrincipal("user_id"));
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(lenient),
+                REASON_PUBLIC_READ,
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(required),
+                REASON_PUBLIC_READ,
+            ))
+            .build()
+            .unwrap();
+
+        let ctx = [("user_id", Value::String("alice"))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+        let ctx = [("user_id", Value::String("bob"))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert!(policy.evaluate(&request).unwrap().is_deny());
+
+        // A missing `team` only fails its rule; a missing `user_id` fails
+        // the request.
+        let request = Request::new("alice", "read", "doc");
+        assert_eq!(
+            policy.evaluate(&request).unwrap_err(),
+            PolicyError::RequiredAttributeMissing {
+                attr: "user_id".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_max_path_depth() {
+        let rule = |attr| {
+            Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Equals {
+                    attr,
+                    value: Value::Bool(true),
+                }),
+                REASON_PUBLIC_READ,
+            )
+        };
+        let config = PolicyConfig {
+            max_path_depth: 2,
+            ..PolicyConfig::default()
+        };
+        assert_eq!(
+            Policy::with_config(vec![rule("device.posture.compliant")], config).unwrap_err(),
+            PolicyError::PathTooDeep { max: 2, actual: 3 }
+        );
+        let too_high = PolicyConfig {
+            max_path_depth: ABSOLUTE_MAX_PATH_DEPTH + 1,
+            ..PolicyConfig::default()
+        };
+        assert!(matches!(
+            Policy::with_config(vec![], too_high),
+            Err(PolicyError::PathTooDeep { .. })
+        ));
+
+        let policy = Policy::with_config(vec![rule("device.managed")], config).unwrap();
+        let device = [("managed", Value::Bool(true))];
+        let ctx = [("device", Value::map(&device))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+
+        // Context maps may not nest deeper than the limit
+        let posture = [("compliant", Value::Bool(true))];
+        let device = [("posture", Value::map(&posture))];
+        let ctx = [("device", Value::map(&device))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert_eq!(
+            policy.evaluate(&request).unwrap_err(),
+            PolicyError::PathTooDeep { max: 2, actual: 3 }
+        );
+
+        // Map entries share the context size limit
+        let config = PolicyConfig {
+            max_context_attrs: 2,
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::with_config(vec![], config).unwrap();
+        let device = [
+            ("a", Value::Int(1)),
+            ("b", Value::Int(2)),
+            ("c", Value::Int(3)),
+        ];
+        let ctx = [("device", Value::map(&device))];
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert_eq!(
+            policy.evaluate(&request).unwrap_err(),
+            PolicyError::ContextTooLarge { max: 2, actual: 3 }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_checked() {
+        let config = PolicyConfig {
+            known_actions: Some(&["read", "write"]),
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::builder()
+            .config(config)
+            .rule(Rule::allow(Target::any(), REASON_PUBLIC_READ))
+            .build()
+            .unwrap();
+
+        let request = Request::new("alice", "write", "doc");
+        assert!(policy.evaluate_checked(&request).unwrap().is_allow());
+        let typo = Request::new("alice", "raed", "doc");
+        assert!(policy.evaluate(&typo).unwrap().is_allow());
+        assert_eq!(
+            policy.evaluate_checked(&typo),
+            Err(PolicyError::UnknownAction)
+        );
+
+        // Without a vocabulary nothing is checked
+        let policy = Policy::new(vec![Rule::allow(Target::any(), REASON_PUBLIC_READ)]).unwrap();
+        assert!(policy.evaluate_checked(&typo).unwrap().is_allow());
+    }
+
+    #[test]
+    fn test_evaluate_with_stats() {
+        use crate::condition::Condition;
+        use crate::value::Value;
+
+        // Policy with 3 rules, one with a condition
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Exact("admin"),
+                    action: Matcher::Any,
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                None,
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("editor"),
+                }),
+                ReasonCode(2),
+            ))
+            .rule(Rule::allow(Target::any(), ReasonCode(3)))
+            .build()
+            .unwrap();
+
+        // Request that matches rule 3 (after checking all rules)
+        let ctx: &[(&str, Value)] = &[("role", Value::String("viewer"))];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+
+        let (decision, stats) = policy.evaluate_with_stats(&request).unwrap();
+
+        assert!(decision.is_allow());
+        assert_eq!(decision.reason, ReasonCode(3));
+        // Should have checked all 3 rules
+        assert_eq!(stats.rules_checked, 3);
+        // Rule 2 has a condition that was evaluated
+        assert_eq!(stats.condition_evals, 1);
+    }
+
+    #[test]
+    fn test_cache_ttl_follows_deciding_rule() {
+        let policy = Policy::builder()
+            .rule(Rule::allow(Target::any(), REASON_PUBLIC_READ).with_cache_ttl(3600))
+            .rule(
+                Rule::new(
+                    Effect::Deny,
+                    Target::any(),
+                    Some(Condition::Equals {
+                        attr: "risky",
+                        value: Value::Bool(true),
+                    }),
+                    REASON_BLOCKED_USER,
+                )
+                .with_cache_ttl(30),
+            )
+            .build()
+            .unwrap();
+
+        // Static grant: long TTL
+        let decision = policy
+            .evaluate(&Request::new("alice", "read", "doc"))
+            .unwrap();
+        assert!(decision.is_allow());
+        assert_eq!(decision.cache_ttl, Some(3600));
+
+        // Risk-based deny: short TTL
+        let ctx: &[(&str, Value)] = &[("risky", Value::Bool(true))];
+        let request = Request::with_context("alice", "read", "doc", ctx);
+        let decision = policy.evaluate(&request).unwrap();
+        assert!(decision.is_deny());
+        assert_eq!(decision.cache_ttl, Some(30));
+
+        // Default deny carries no hint
+        let empty = Policy::new(vec![]).unwrap();
+        let decision = empty
+            .evaluate(&Request::new("alice", "read", "doc"))
+            .unwrap();
+        assert_eq!(decision.cache_ttl, None);
     }
 }
@@ -0,0 +1,217 @@
+//! Reverse queries.
+//!
+//! Instead of asking "may this request proceed?", ask what a principal can
+//! do, or who can do something, across bounded vocabularies. Each query
+//! evaluates the policy once per combination with the normal semantics, so
+//! answers always agree with `evaluate()`. Vocabularies are bounded by
+//! `MAX_QUERY_EVALUATIONS` to keep queries predictable.
+
+use crate::error::PolicyError;
+use crate::policy::Policy;
+use crate::target::Matcher;
+use crate::types::{Effect, Request};
+use crate::value::Value;
+
+/// Maximum number of evaluations a single query may perform.
+pub const MAX_QUERY_EVALUATIONS: usize = 65_536;
+
+impl<'a> Policy<'a> {
+    /// List the (action, resource) pairs `principal` is allowed, in vocabulary order.
+    ///
+    /// Useful for rendering "your permissions" pages from the policy itself.
+    /// Fails if `actions.len() * resources.len()` exceeds
+    /// `MAX_QUERY_EVALUATIONS`, or if any evaluation fails.
+    pub fn enumerate_allowed<'v>(
+        &self,
+        principal: &str,
+        actions: &[&'v str],
+        resources: &[&'v str],
+        context: &[(&str, Value<'_>)],
+    ) -> Result<Vec<(&'v str, &'v str)>, PolicyError> {
+        check_query_size(actions.len().saturating_mul(resources.len()))?;
+        let mut allowed = Vec::new();
+        for &action in actions {
+            let decisions = self.evaluate_batch(principal, action, context, resources);
+            for (&resource, decision) in resources.iter().zip(decisions) {
+                if decision?.is_allow() {
+                    allowed.push((action, resource));
+                }
+            }
+        }
+        Ok(allowed)
+    }
+
+    /// List the principals allowed to perform `action` on `resource`, in vocabulary order.
+    ///
+    /// Every principal is evaluated with the same `context`. Intended for
+    /// access reviews; see `allow_grants` for grants that are not tied to a
+    /// bounded list of principals.
+    pub fn who_can<'v>(
+        &self,
+        action: &str,
+        resource: &str,
+        principals: &[&'v str],
+        context: &[(&str, Value<'_>)],
+    ) -> Result<Vec<&'v str>, PolicyError> {
+        check_query_size(principals.len())?;
+        let mut allowed = Vec::new();
+        for &principal in principals {
+            let request = Request::with_context(principal, action, resource, context);
+            if self.evaluate(&request)?.is_allow() {
+                allowed.push(principal);
+            }
+        }
+        Ok(allowed)
+    }
+
+    /// Get the principal matchers of Allow rules that target `action` on `resource`.
+    ///
+    /// These are candidate grants: conditions, environments, schedules,
+    /// and Deny rules may still refuse a particular request. A `Matcher::Any` here means the
+    /// resource is potentially open to everyone and deserves review.
+    pub fn allow_grants(&self, action: &str, resource: &str) -> Vec<(usize, &Matcher<'a>)> {
+        self.rules()
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| {
+                rule.effect == Effect::Allow
+                    && self.action_applies(rule, action)
+                    && rule
+                        .target
+                        .resource
+                        .matches_with(resource, self.config().collation)
+            })
+            .map(|(index, rule)| (index, &rule.target.principal))
+            .collect()
+    }
+}
+
+pub(crate) fn check_query_size(evaluations: usize) -> Result<(), PolicyError> {
+    if evaluations > MAX_QUERY_EVALUATIONS {
+        return Err(PolicyError::QueryTooLarge {
+            max: MAX_QUERY_EVALUATIONS,
+            actual: evaluations,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::ReasonCode;
+
+    fn policy() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("write"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                Some(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("editor"),
+                }),
+                ReasonCode(2),
+            ))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Any,
+                    resource: Matcher::Exact("payroll"),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(3),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_enumerate_allowed() {
+        let policy = policy();
+        let actions = ["read", "write", "delete"];
+        let resources = ["doc", "payroll"];
+
+        let viewer = policy
+            .enumerate_allowed("alice", &actions, &resources, &[])
+            .unwrap();
+        assert_eq!(viewer, vec![("read", "doc")]);
+
+        let ctx = [("role", Value::String("editor"))];
+        let editor = policy
+            .enumerate_allowed("bob", &actions, &resources, &ctx)
+            .unwrap();
+        assert_eq!(editor, vec![("read", "doc"), ("write", "doc")]);
+    }
+
+    #[test]
+    fn test_who_can() {
+        let policy = Policy::builder()
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::OneOf(&["alice", "bob"]),
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(1),
+            ))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Exact("bob"),
+                    action: Matcher::Any,
+                    resource: Matcher::Exact("payroll"),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+        let staff = ["alice", "bob", "carol"];
+
+        assert_eq!(
+            policy.who_can("read", "payroll", &staff, &[]).unwrap(),
+            vec!["alice"]
+        );
+        assert_eq!(
+            policy.who_can("read", "doc", &staff, &[]).unwrap(),
+            vec!["alice", "bob"]
+        );
+        assert!(policy
+            .who_can("write", "doc", &staff, &[])
+            .unwrap()
+            .is_empty());
+
+        let grants = policy.allow_grants("read", "payroll");
+        assert_eq!(grants, vec![(0, &Matcher::OneOf(&["alice", "bob"]))]);
+    }
+
+    #[test]
+    fn test_query_bounded() {
+        let policy = policy();
+        let many: Vec<&str> = (0..300).map(|_| "x").collect();
+        assert_eq!(
+            policy.enumerate_allowed("alice", &many, &many, &[]),
+            Err(PolicyError::QueryTooLarge {
+                max: MAX_QUERY_EVALUATIONS,
+                actual: 90_000,
+            })
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Role hierarchies.
+//!
+//! A `RoleGraph` records which roles inherit the permissions of which
+//! others (`admin ⊃ maintainer ⊃ viewer`). Instead of duplicating a rule for
+//! every senior role, write the rule once against the most junior role and
+//! let the graph expand it into a `Matcher::OneOf` or a `Condition`.
+//!
+//! The graph is bounded and validated when built: role counts are capped and
+//! cycles are rejected, so expansion always terminates.
+//!
+//! # Example
+//!
+//! ```
+//! use gate0::rbac::RoleGraph;
+//! use gate0::{Policy, ReasonCode, Request, Rule, Target, Value};
+//!
+//! let roles = RoleGraph::builder()
+//!     .inherits("admin", "maintainer")
+//!     .inherits("maintainer", "viewer")
+//!     .build()
+//!     .unwrap();
+//!
+//! let policy = Policy::builder()
+//!     .rule(Rule::new(
+//!         gate0::Effect::Allow,
+//!         Target::any(),
+//!         roles.condition("role", "viewer"),
+//!         ReasonCode(1),
+//!     ))
+//!     .build()
+//!     .unwrap();
+//!
+//! let ctx: &[(&str, Value)] = &[("role", Value::String("admin"))];
+//! let request = Request::with_context("alice", "read", "doc", ctx);
+//! assert!(policy.evaluate(&request).unwrap().is_allow());
+//! ```
+
+use std::fmt;
+
+use crate::condition::Condition;
+use crate::target::Matcher;
+use crate::value::Value;
+
+/// Default maximum number of roles in a graph.
+pub const DEFAULT_MAX_ROLES: usize = 256;
+
+/// Errors from building a role graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RbacError {
+    /// The graph declares more roles than allowed.
+    TooManyRoles {
+        /// The configured maximum.
+        max: usize,
+        /// The actual number of roles.
+        actual: usize,
+    },
+    /// The inheritance relation contains a cycle reachable from this role.
+    Cycle(String),
+}
+
+impl fmt::Display for RbacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RbacError::TooManyRoles { max, actual } => {
+                write!(
+                    f,
+                    "role graph exceeds maximum of {} roles, got {}",
+                    max, actual
+                )
+            }
+            RbacError::Cycle(role) => {
+                write!(f, "role '{}' is part of or inherits from a cycle", role)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RbacError {}
+
+/// Builder for a `RoleGraph`.
+#[derive(Debug, Clone)]
+pub struct RoleGraphBuilder<'a> {
+    roles: Vec<&'a str>,
+    edges: Vec<(usize, usize)>,
+    max_roles: usize,
+}
+
+impl<'a> RoleGraphBuilder<'a> {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        RoleGraphBuilder {
+            roles: Vec::new(),
+            edges: Vec::new(),
+            max_roles: DEFAULT_MAX_ROLES,
+        }
+    }
+
+    /// Set the maximum number of roles.
+    pub fn max_roles(mut self, max: usize) -> Self {
+        self.max_roles = max;
+        self
+    }
+
+    /// Declare a role with no inheritance.
+    pub fn role(mut self, role: &'a str) -> Self {
+        self.intern(role);
+        self
+    }
+
+    /// Declare that `senior` holds every permission of `junior`.
+    pub fn inherits(mut self, senior: &'a str, junior: &'a str) -> Self {
+        let s = self.intern(senior);
+        let j = self.intern(junior);
+        if !self.edges.contains(&(s, j)) {
+            self.edges.push((s, j));
+        }
+        self
+    }
+
+    fn intern(&mut self, role: &'a str) -> usize {
+        match self.roles.iter().position(|r| *r == role) {
+            Some(i) => i,
+            None => {
+                self.roles.push(role);
+                self.roles.len() - 1
+            }
+        }
+    }
+
+    /// Validate the graph and compute the role closures.
+    pub fn build(self) -> Result<RoleGraph<'a>, RbacError> {
+        let n = self.roles.len();
+        if n > self.max_roles {
+            return Err(RbacError::TooManyRoles {
+                max: self.max_roles,
+                actual: n,
+            });
+        }
+
+        // Kahn's algorithm: any role left with unresolved juniors is on a cycle.
+        let mut pending = vec![0usize; n];
+        for &(s, _) in &self.edges {
+            pending[s] += 1;
+        }
+        let mut ready: Vec<usize> = (0..n).filter(|&i| pending[i] == 0).collect();
+        let mut resolved = 0;
+        while let Some(j) = ready.pop() {
+            resolved += 1;
+            for &(s, _) in self.edges.iter().filter(|&&(_, jj)| jj == j) {
+                pending[s] -= 1;
+                if pending[s] == 0 {
+                    ready.push(s);
+                }
+            }
+        }
+        if resolved < n {
+            let on_cycle = (0..n).find(|&i| pending[i] > 0).unwrap_or(0);
+            return Err(RbacError::Cycle(self.roles[on_cycle].to_string()));
+        }
+
+        // For each role, collect every role that holds it: itself first,
+        // then seniors in breadth-first order.
+        let satisfying = (0..n)
+            .map(|role| {
+                let mut found = vec![role];
+                let mut next = 0;
+                while next < found.len() {
+                    let junior = found[next];
+                    next += 1;
+                    for &(s, j) in &self.edges {
+                        if j == junior && !found.contains(&s) {
+                            found.push(s);
+                        }
+                    }
+                }
+                found.into_iter().map(|i| self.roles[i]).collect()
+            })
+            .collect();
+
+        Ok(RoleGraph {
+            roles: self.roles,
+            satisfying,
+        })
+    }
+}
+
+impl<'a> Default for RoleGraphBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A validated, acyclic role hierarchy.
+#[derive(Debug, Clone)]
+pub struct RoleGraph<'a> {
+    roles: Vec<&'a str>,
+    /// Per role (same index as `roles`): the roles that hold it.
+    satisfying: Vec<Vec<&'a str>>,
+}
+
+impl<'a> RoleGraph<'a> {
+    /// Create a new role graph builder.
+    pub fn builder() -> RoleGraphBuilder<'a> {
+        RoleGraphBuilder::new()
+    }
+
+    /// Get all declared roles in declaration order.
+    pub fn roles(&self) -> &[&'a str] {
+        &self.roles
+    }
+
+    /// Get the roles that hold `required`: the role itself and every senior.
+    ///
+    /// Returns `None` if the role was never declared.
+    pub fn satisfying(&self, required: &str) -> Option<&[&'a str]> {
+        let i = self.roles.iter().position(|r| *r == required)?;
+        Some(&self.satisfying[i])
+    }
+
+    /// Returns `true` if holding `held` grants `required`.
+    pub fn implies(&self, held: &str, required: &str) -> bool {
+        self.satisfying(required)
+            .map(|roles| roles.contains(&held))
+            .unwrap_or(false)
+    }
+
+    /// Expand `required` into a matcher over the satisfying roles.
+    ///
+    /// Use it where the principal is the role name. The option count must
+    /// fit the policy's `max_matcher_options`.
+    pub fn matcher(&self, required: &str) -> Option<Matcher<'_>> {
+        self.satisfying(required).map(Matcher::OneOf)
+    }
+
+    /// Expand `required` into a condition that `attr` equals any satisfying role.
+    ///
+    /// The alternatives are combined into a balanced `Or` tree, so the
+    /// condition depth grows logarithmically with the number of roles.
+    pub fn condition<'g>(&'g self, attr: &'g str, required: &str) -> Option<Condition<'g>> {
+        let roles = self.satisfying(required)?;
+        let mut level: Vec<Condition<'g>> = roles
+            .iter()
+            .map(|role| Condition::Equals {
+                attr,
+                value: Value::String(role),
+            })
+            .collect();
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity(level.len().div_ceil(2));
+            let mut items = level.into_iter();
+            while let Some(a) = items.next() {
+                match items.next() {
+                    Some(b) => next.push(Condition::Or(Box::new(a), Box::new(b))),
+                    None => next.push(a),
+                }
+            }
+            level = next;
+        }
+        level.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> RoleGraph<'static> {
+        RoleGraph::builder()
+            .inherits("admin", "maintainer")
+            .inherits("maintainer", "viewer")
+            .inherits("auditor", "viewer")
+            .role("guest")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_closure() {
+        let g = graph();
+        assert_eq!(
+            g.satisfying("viewer").unwrap(),
+            &["viewer", "maintainer", "auditor", "admin"]
+        );
+        assert_eq!(g.satisfying("admin").unwrap(), &["admin"]);
+        assert!(g.implies("admin", "viewer"));
+        assert!(!g.implies("viewer", "admin"));
+        assert!(!g.implies("guest", "viewer"));
+        assert!(g.satisfying("unknown").is_none());
+        assert_eq!(
+            g.matcher("maintainer"),
+            Some(Matcher::OneOf(&["maintainer", "admin"]))
+        );
+    }
+
+    #[test]
+    fn test_condition_expansion() {
+        let g = graph();
+        let cond = g.condition("role", "viewer").unwrap();
+        // Four alternatives: balanced Or of depth 2 over leaves of depth 1.
+        assert_eq!(cond.depth(), 3);
+        for role in ["viewer", "maintainer", "auditor", "admin"] {
+            let ctx = [("role", Value::String(role))];
+            assert!(cond.evaluate(&ctx).unwrap(), "{} should match", role);
+        }
+        let ctx = [("role", Value::String("guest"))];
+        assert!(!cond.evaluate(&ctx).unwrap());
+    }
+
+    #[test]
+    fn test_cycle_rejected() {
+        let err = RoleGraph::builder()
+            .inherits("a", "b")
+            .inherits("b", "c")
+            .inherits("c", "a")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, RbacError::Cycle(_)));
+
+        let err = RoleGraph::builder().inherits("a", "a").build().unwrap_err();
+        assert_eq!(err, RbacError::Cycle("a".to_string()));
+    }
+
+    #[test]
+    fn test_too_many_roles() {
+        let err = RoleGraph::builder()
+            .max_roles(2)
+            .inherits("a", "b")
+            .role("c")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, RbacError::TooManyRoles { max: 2, actual: 3 });
+    }
+}
@@ -0,0 +1,123 @@
+//! Reason-code usage map.
+//!
+//! `Policy::reason_map` lists which rules produce each reason code, checked
+//! against a catalog of named codes (such as `Manifest::reasons`), so docs
+//! and alerting rules can be generated from the policy instead of kept in
+//! sync by hand. The built-in `NO_MATCHING_RULE` and `EVALUATION_FAILED`
+//! codes belong to no rule and are not listed.
+
+use crate::policy::Policy;
+use crate::types::ReasonCode;
+
+/// The rules that produce one reason code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReasonUsage<'c> {
+    /// The reason code.
+    pub reason: ReasonCode,
+    /// Its name in the catalog, or `None` if the catalog lacks it.
+    pub name: Option<&'c str>,
+    /// Indices of the rules using it, in evaluation order.
+    pub rules: Vec<usize>,
+}
+
+/// Every reason code a policy uses, against a catalog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReasonMap<'c> {
+    /// Codes used by at least one rule, in ascending order.
+    pub used: Vec<ReasonUsage<'c>>,
+    /// Catalog entries no rule uses, in catalog order.
+    pub unused: Vec<(&'c str, ReasonCode)>,
+}
+
+impl<'c> ReasonMap<'c> {
+    /// Used codes missing from the catalog.
+    pub fn undeclared(&self) -> impl Iterator<Item = &ReasonUsage<'c>> {
+        self.used.iter().filter(|usage| usage.name.is_none())
+    }
+
+    /// Returns `true` if every used code is in the catalog.
+    pub fn is_complete(&self) -> bool {
+        self.undeclared().next().is_none()
+    }
+}
+
+impl Policy<'_> {
+    /// Map each reason code to the rules using it, naming codes from
+    /// `catalog`. If a code is listed twice, the first name wins.
+    pub fn reason_map<'c>(&self, catalog: &[(&'c str, ReasonCode)]) -> ReasonMap<'c> {
+        let mut used: Vec<ReasonUsage<'c>> = Vec::new();
+        for (index, rule) in self.rules().iter().enumerate() {
+            match used.iter_mut().find(|usage| usage.reason == rule.reason) {
+                Some(usage) => usage.rules.push(index),
+                None => used.push(ReasonUsage {
+                    reason: rule.reason,
+                    name: catalog
+                        .iter()
+                        .find(|(_, code)| *code == rule.reason)
+                        .map(|(name, _)| *name),
+                    rules: vec![index],
+                }),
+            }
+        }
+        used.sort_by_key(|usage| usage.reason.value());
+
+        let unused = catalog
+            .iter()
+            .filter(|(_, code)| !used.iter().any(|usage| usage.reason == *code))
+            .copied()
+            .collect();
+        ReasonMap { used, unused }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+    use crate::target::Target;
+
+    #[test]
+    fn test_reason_map() {
+        let policy = Policy::builder()
+            .rule(Rule::deny(Target::any(), ReasonCode(20)))
+            .rule(Rule::allow(Target::any(), ReasonCode(10)))
+            .rule(Rule::allow(Target::any(), ReasonCode(20)))
+            .rule(Rule::allow(Target::any(), ReasonCode(30)))
+            .build()
+            .unwrap();
+        let catalog = [
+            ("STAFF_READ", ReasonCode(10)),
+            ("BLOCKED", ReasonCode(20)),
+            ("RETIRED", ReasonCode(40)),
+        ];
+
+        let map = policy.reason_map(&catalog);
+        assert_eq!(
+            map.used,
+            vec![
+                ReasonUsage {
+                    reason: ReasonCode(10),
+                    name: Some("STAFF_READ"),
+                    rules: vec![1],
+                },
+                ReasonUsage {
+                    reason: ReasonCode(20),
+                    name: Some("BLOCKED"),
+                    rules: vec![0, 2],
+                },
+                ReasonUsage {
+                    reason: ReasonCode(30),
+                    name: None,
+                    rules: vec![3],
+                },
+            ]
+        );
+        assert_eq!(map.unused, vec![("RETIRED", ReasonCode(40))]);
+        let undeclared: Vec<_> = map.undeclared().map(|usage| usage.reason).collect();
+        assert_eq!(undeclared, vec![ReasonCode(30)]);
+        assert!(!map.is_complete());
+
+        assert!(policy.reason_map(&[]).unused.is_empty());
+        assert_eq!(policy.reason_map(&[]).undeclared().count(), 3);
+    }
+}
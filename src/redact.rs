@@ -0,0 +1,166 @@
+//! Attribute sensitivity and redaction.
+//!
+//! Context attributes can be declared sensitive with
+//! `PolicyBuilder::sensitive_attribute` or `Manifest::sensitive`. Rules
+//! evaluate them like any other attribute, but `Policy::redact_context`,
+//! and every record built on it, never shows their values:
+//!
+//! - `Sensitivity::Pii` values are replaced by a stable hash, so records
+//!   about the same person still correlate.
+//! - `Sensitivity::Secret` values are removed, like `Value::Secret`.
+//!
+//! Entries of a `Value::Map` are declared by their dotted path, such as
+//! `device.serial`.
+//!
+//! Replay records (`ReplayRecord::capture`) go through `redact_context`, so
+//! they can be kept and shared without leaking sensitive values. A redacted
+//! record replays as if the attribute held its redacted form, so rules that
+//! compare a sensitive attribute will not match on replay.
+//!
+//! The PII hash is FNV-1a, which hides values from casual reading but not
+//! from someone guessing a low-entropy value such as a birth date;
+//! declare those `Secret`.
+
+use crate::fingerprint::{hash_value, Fnv64};
+use crate::policy::Policy;
+use crate::value::{Value, ValueBuf};
+
+/// How a sensitive attribute is redacted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sensitivity {
+    /// Personal data: shown as `pii:` and a 16-digit hex hash of the
+    /// attribute name and value.
+    Pii,
+    /// A credential or other secret: shown as an empty `Value::Secret`.
+    Secret,
+}
+
+impl Policy<'_> {
+    /// The declared sensitivity of context attribute `attr`, if any.
+    pub fn sensitivity(&self, attr: &str) -> Option<Sensitivity> {
+        self.sensitive_attributes()
+            .iter()
+            .find(|(name, _)| *name == attr)
+            .map(|(_, sensitivity)| *sensitivity)
+    }
+
+    /// An owned copy of `context` with every sensitive attribute redacted.
+    ///
+    /// `Value::Secret` values, including list elements and map entries,
+    /// are always redacted, declared or not. Use
+    /// this instead of the raw context when logging or explaining a
+    /// request.
+    pub fn redact_context(&self, context: &[(&str, Value<'_>)]) -> Vec<(String, ValueBuf)> {
+        context
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), self.redact_value(name, value)))
+            .collect()
+    }
+
+    /// Redact `value`, found at attribute path `path`.
+    ///
+    /// Map entries are redacted by their own paths, so a nested attribute
+    /// such as `device.serial` can be declared sensitive. Recursion is
+    /// bounded by the map nesting of a validated context.
+    fn redact_value(&self, path: &str, value: &Value<'_>) -> ValueBuf {
+        match (value, self.sensitivity(path)) {
+            (Value::Secret(_), _) | (_, Some(Sensitivity::Secret)) => ValueBuf::Secret(Vec::new()),
+            (_, Some(Sensitivity::Pii)) => {
+                let mut h = Fnv64::new();
+                h.write_str(path);
+                hash_value(&mut h, value);
+                ValueBuf::String(format!("pii:{:016x}", h.finish()))
+            }
+            (Value::List(list), None) => ValueBuf::List(
+                list.iter()
+                    .map(|item| self.redact_value(path, &item))
+                    .collect(),
+            ),
+            (Value::Map(map), None) => ValueBuf::Map(
+                map.iter()
+                    .map(|(key, item)| {
+                        let nested = format!("{}.{}", path, key);
+                        (key.to_string(), self.redact_value(&nested, &item))
+                    })
+                    .collect(),
+            ),
+            (_, None) => ValueBuf::from(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode, Request};
+
+    #[test]
+    fn test_redact_context() {
+        let email = Condition::Equals {
+            attr: "email",
+            value: Value::String("alice@example.com"),
+        };
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(email),
+                ReasonCode(1),
+            ))
+            .sensitive_attribute("email", Sensitivity::Pii)
+            .sensitive_attribute("ssn", Sensitivity::Secret)
+            .build()
+            .unwrap();
+        assert_eq!(policy.sensitivity("email"), Some(Sensitivity::Pii));
+        assert_eq!(policy.sensitivity("team"), None);
+
+        let ctx = [
+            ("email", Value::String("alice@example.com")),
+            ("ssn", Value::String("078-05-1120")),
+            ("team", Value::String("payments")),
+            ("token", Value::Secret(b"sk_live_123")),
+        ];
+        // Sensitive attributes still decide.
+        let request = Request::with_context("alice", "read", "doc", &ctx);
+        assert!(policy.evaluate(&request).unwrap().is_allow());
+
+        let redacted = policy.redact_context(&ctx);
+        let ValueBuf::String(hashed) = &redacted[0].1 else {
+            panic!("expected a hash, got {:?}", redacted[0].1);
+        };
+        assert!(hashed.starts_with("pii:") && hashed.len() == 20);
+        assert!(!hashed.contains("alice"));
+        assert_eq!(redacted[1].1, ValueBuf::Secret(Vec::new()));
+        assert_eq!(redacted[2].1, ValueBuf::String("payments".to_string()));
+        assert_eq!(redacted[3].1, ValueBuf::Secret(Vec::new()));
+
+        // Equal values hash alike, so redacted records still correlate.
+        let again = policy.redact_context(&[("email", Value::String("alice@example.com"))]);
+        assert_eq!(again[0], redacted[0]);
+        let other = policy.redact_context(&[("email", Value::String("bob@example.com"))]);
+        assert_ne!(other[0], redacted[0]);
+    }
+
+    #[test]
+    fn test_redact_nested() {
+        let policy = Policy::builder()
+            .sensitive_attribute("device.serial", Sensitivity::Secret)
+            .build()
+            .unwrap();
+        let device = [
+            ("os", Value::String("linux")),
+            ("serial", Value::String("C02XK1")),
+        ];
+        let redacted = policy.redact_context(&[("device", Value::map(&device))]);
+        assert_eq!(
+            redacted[0].1,
+            ValueBuf::Map(vec![
+                ("os".to_string(), ValueBuf::String("linux".to_string())),
+                ("serial".to_string(), ValueBuf::Secret(Vec::new())),
+            ])
+        );
+    }
+}
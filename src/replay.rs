@@ -0,0 +1,884 @@
+//! Deterministic decision replay.
+//!
+//! A `ReplayWriter` appends compact binary records of
+//! (request, context, policy fingerprint, outcome) to any `Write`.
+//! A `ReplayReader` reads them back, and `replay()` re-evaluates the
+//! recorded requests against another policy revision and reports every
+//! outcome that changed. This answers forensic questions like "what would
+//! the new policy have said about last Tuesday's traffic?".
+//!
+//! # Format (version 2)
+//!
+//! ```text
+//! header:  b"G0RL" u8(version)
+//! record:  u64(fingerprint)
+//!          str(principal) str(action) str(resource) env
+//!          u16(context len) { str(key) value }*
+//!          outcome
+//! str:     u32(len) bytes (UTF-8)
+//! env:     u8(0) | u8(1) str
+//! value:   u8(0) u8(bool) | u8(1) i64 | u8(2) str | u8(3) | u8(4) ip
+//!          | u8(5) u16(len) value* | u8(6) u16(len) { str(key) value }*
+//!          (list elements are never lists or maps)
+//! ip:      u8(4) [u8; 4] | u8(6) [u8; 16]
+//! outcome: u8(0) effect u32(reason) ttl u8(break_glass) obligations
+//!          | u8(1) str(error message)
+//! ttl:     u8(0) | u8(1) u32(seconds)
+//! obligations: u8(count) { u8(0) Audit | u8(1) u32 Custom
+//!              | u8(2) u32(approvers) u32(expires_in) Approval }*
+//! effect:  u8(0) Allow | u8(1) Deny | u8(2) method Challenge | u8(3) Indeterminate
+//! method:  u8(0) Mfa | u8(1) Reauthenticate | u8(2) u32 Custom
+//! ```
+//!
+//! Version 1 logs, which predate environments and have no `env`, are
+//! still read; their requests have no environment.
+//!
+//! All integers are little-endian. Secrets are not written: tag 3 has no
+//! payload and reads back as an empty secret, so rules that compare a
+//! secret will not match on replay. Attributes the policy declares
+//! sensitive are captured redacted (see `Policy::redact_context`).
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::condition::ABSOLUTE_MAX_PATH_DEPTH;
+use crate::error::PolicyError;
+use crate::obligation::{Approval, Obligation, MAX_OBLIGATIONS};
+use crate::policy::Policy;
+use crate::types::{ChallengeMethod, Decision, Effect, ReasonCode, Request};
+use crate::value::ValueBuf;
+
+const MAGIC: &[u8; 4] = b"G0RL";
+const VERSION: u8 = 2;
+
+/// Upper bound on any string read from a log, to bound memory on corrupt input.
+const MAX_STRING_BYTES: u32 = 64 * 1024;
+
+/// The outcome recorded for a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedOutcome {
+    /// Evaluation produced a decision.
+    Decision(Decision),
+    /// Evaluation failed; the error's display text.
+    Error(String),
+}
+
+impl RecordedOutcome {
+    /// Capture an evaluation result.
+    pub fn from_result(result: &Result<Decision, PolicyError>) -> Self {
+        match result {
+            Ok(decision) => RecordedOutcome::Decision(*decision),
+            Err(e) => RecordedOutcome::Error(e.to_string()),
+        }
+    }
+}
+
+/// An owned, replayable decision record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayRecord {
+    /// Fingerprint of the policy that made the decision.
+    pub fingerprint: u64,
+    /// The request principal.
+    pub principal: String,
+    /// The request action.
+    pub action: String,
+    /// The request resource.
+    pub resource: String,
+    /// The request context in original order.
+    pub context: Vec<(String, ValueBuf)>,
+    /// The request environment.
+    pub environment: Option<String>,
+    /// The recorded outcome.
+    pub outcome: RecordedOutcome,
+}
+
+impl ReplayRecord {
+    /// Capture a request and its outcome under the given policy, with the
+    /// policy's sensitive attributes redacted.
+    pub fn capture(
+        policy: &Policy<'_>,
+        request: &Request<'_>,
+        result: &Result<Decision, PolicyError>,
+    ) -> Self {
+        ReplayRecord {
+            fingerprint: policy.fingerprint(),
+            principal: request.principal.to_string(),
+            action: request.action.to_string(),
+            resource: request.resource.to_string(),
+            context: policy.redact_context(request.context),
+            environment: request.environment.map(str::to_string),
+            outcome: RecordedOutcome::from_result(result),
+        }
+    }
+
+    /// Re-evaluate this record's request against `policy`.
+    pub fn evaluate(&self, policy: &Policy<'_>) -> RecordedOutcome {
+        let context: Vec<_> = self
+            .context
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_value()))
+            .collect();
+        let mut request =
+            Request::with_context(&self.principal, &self.action, &self.resource, &context);
+        request.environment = self.environment.as_deref();
+        RecordedOutcome::from_result(&policy.evaluate(&request))
+    }
+}
+
+/// Errors reading or writing a replay log.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Underlying I/O failure.
+    Io(io::Error),
+    /// The stream does not start with a replay log header.
+    BadMagic,
+    /// The log was written by an unsupported format version.
+    UnsupportedVersion(u8),
+    /// A record is malformed (bad tag, invalid UTF-8, or oversized field).
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "replay log io error: {}", e),
+            ReplayError::BadMagic => write!(f, "not a gate0 replay log"),
+            ReplayError::UnsupportedVersion(v) => {
+                write!(f, "unsupported replay log version {}", v)
+            }
+            ReplayError::Corrupt(what) => write!(f, "corrupt replay record: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+/// Appends decision records to a byte stream.
+pub struct ReplayWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ReplayWriter<W> {
+    /// Create a writer and emit the log header.
+    pub fn new(mut inner: W) -> Result<Self, ReplayError> {
+        inner.write_all(MAGIC)?;
+        inner.write_all(&[VERSION])?;
+        Ok(ReplayWriter { inner })
+    }
+
+    /// Evaluate `request` against `policy`, record it, and return the result.
+    pub fn evaluate_and_record(
+        &mut self,
+        policy: &Policy<'_>,
+        request: &Request<'_>,
+    ) -> Result<Result<Decision, PolicyError>, ReplayError> {
+        let result = policy.evaluate(request);
+        self.write(&ReplayRecord::capture(policy, request, &result))?;
+        Ok(result)
+    }
+
+    /// Append a single record.
+    pub fn write(&mut self, record: &ReplayRecord) -> Result<(), ReplayError> {
+        let w = &mut self.inner;
+        w.write_all(&record.fingerprint.to_le_bytes())?;
+        write_str(w, &record.principal)?;
+        write_str(w, &record.action)?;
+        write_str(w, &record.resource)?;
+        match &record.environment {
+            None => w.write_all(&[0])?,
+            Some(environment) => {
+                w.write_all(&[1])?;
+                write_str(w, environment)?;
+            }
+        }
+        let len = u16::try_from(record.context.len())
+            .map_err(|_| ReplayError::Corrupt("context too large"))?;
+        w.write_all(&len.to_le_bytes())?;
+        for (key, value) in &record.context {
+            write_str(w, key)?;
+            write_value(w, value)?;
+        }
+        match &record.outcome {
+            RecordedOutcome::Decision(d) => {
+                w.write_all(&[0])?;
+                match d.effect {
+                    Effect::Allow => w.write_all(&[0])?,
+                    Effect::Deny => w.write_all(&[1])?,
+                    Effect::Challenge(ChallengeMethod::Mfa) => w.write_all(&[2, 0])?,
+                    Effect::Challenge(ChallengeMethod::Reauthenticate) => w.write_all(&[2, 1])?,
+                    Effect::Challenge(ChallengeMethod::Custom(code)) => {
+                        w.write_all(&[2, 2])?;
+                        w.write_all(&code.to_le_bytes())?;
+                    }
+                    Effect::Indeterminate => w.write_all(&[3])?,
+                }
+                w.write_all(&d.reason.value().to_le_bytes())?;
+                match d.cache_ttl {
+                    None => w.write_all(&[0])?,
+                    Some(ttl) => {
+                        w.write_all(&[1])?;
+                        w.write_all(&ttl.to_le_bytes())?;
+                    }
+                }
+                w.write_all(&[d.break_glass as u8, d.obligations.len() as u8])?;
+                for obligation in d.obligations.iter() {
+                    match obligation {
+                        Obligation::Audit => w.write_all(&[0])?,
+                        Obligation::Custom(code) => {
+                            w.write_all(&[1])?;
+                            w.write_all(&code.to_le_bytes())?;
+                        }
+                        Obligation::Approval(approval) => {
+                            w.write_all(&[2])?;
+                            w.write_all(&approval.approvers.to_le_bytes())?;
+                            w.write_all(&approval.expires_in.to_le_bytes())?;
+                        }
+                    }
+                }
+            }
+            RecordedOutcome::Error(msg) => {
+                w.write_all(&[1])?;
+                write_str(w, msg)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, ReplayError> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads decision records from a byte stream.
+///
+/// Iterates over `Result<ReplayRecord, ReplayError>`; iteration ends
+/// cleanly at end of stream.
+pub struct ReplayReader<R: Read> {
+    inner: R,
+    version: u8,
+    done: bool,
+}
+
+impl<R: Read> ReplayReader<R> {
+    /// Create a reader and validate the log header.
+    pub fn new(mut inner: R) -> Result<Self, ReplayError> {
+        let mut magic = [0u8; 4];
+        inner.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = read_u8(&mut inner)?;
+        if !(1..=VERSION).contains(&version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        Ok(ReplayReader {
+            inner,
+            version,
+            done: false,
+        })
+    }
+
+    fn read_record(&mut self) -> Result<Option<ReplayRecord>, ReplayError> {
+        let r = &mut self.inner;
+
+        // A clean EOF is only valid at a record boundary.
+        let mut first = [0u8; 1];
+        if r.read(&mut first)? == 0 {
+            return Ok(None);
+        }
+        let mut rest = [0u8; 7];
+        r.read_exact(&mut rest)?;
+        let mut fp = [0u8; 8];
+        fp[0] = first[0];
+        fp[1..].copy_from_slice(&rest);
+        let fingerprint = u64::from_le_bytes(fp);
+
+        let principal = read_str(r)?;
+        let action = read_str(r)?;
+        let resource = read_str(r)?;
+        let environment = match self.version {
+            1 => None,
+            _ => match read_u8(r)? {
+                0 => None,
+                1 => Some(read_str(r)?),
+                _ => return Err(ReplayError::Corrupt("unknown environment tag")),
+            },
+        };
+
+        let len = u16::from_le_bytes(read_array(r)?);
+        let mut context = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            let key = read_str(r)?;
+            let value = read_value(r, 1)?;
+            context.push((key, value));
+        }
+
+        let outcome = match read_u8(r)? {
+            0 => {
+                let effect = match read_u8(r)? {
+                    0 => Effect::Allow,
+                    1 => Effect::Deny,
+                    2 => Effect::Challenge(match read_u8(r)? {
+                        0 => ChallengeMethod::Mfa,
+                        1 => ChallengeMethod::Reauthenticate,
+                        2 => ChallengeMethod::Custom(u32::from_le_bytes(read_array(r)?)),
+                        _ => return Err(ReplayError::Corrupt("unknown challenge method tag")),
+                    }),
+                    3 => Effect::Indeterminate,
+                    _ => return Err(ReplayError::Corrupt("unknown effect tag")),
+                };
+                let reason = ReasonCode(u32::from_le_bytes(read_array(r)?));
+                let cache_ttl = match read_u8(r)? {
+                    0 => None,
+                    1 => Some(u32::from_le_bytes(read_array(r)?)),
+                    _ => return Err(ReplayError::Corrupt("unknown ttl tag")),
+                };
+                let mut decision = Decision::new(effect, reason).with_cache_ttl(cache_ttl);
+                decision.break_glass = match read_u8(r)? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(ReplayError::Corrupt("invalid break-glass flag")),
+                };
+                let count = read_u8(r)? as usize;
+                if count > MAX_OBLIGATIONS {
+                    return Err(ReplayError::Corrupt("too many obligations"));
+                }
+                for _ in 0..count {
+                    let obligation = match read_u8(r)? {
+                        0 => Obligation::Audit,
+                        1 => Obligation::Custom(u32::from_le_bytes(read_array(r)?)),
+                        2 => Obligation::Approval(Approval {
+                            approvers: u32::from_le_bytes(read_array(r)?),
+                            expires_in: u32::from_le_bytes(read_array(r)?),
+                        }),
+                        _ => return Err(ReplayError::Corrupt("unknown obligation tag")),
+                    };
+                    decision.obligations.push(obligation);
+                }
+                RecordedOutcome::Decision(decision)
+            }
+            1 => RecordedOutcome::Error(read_str(r)?),
+            _ => return Err(ReplayError::Corrupt("unknown outcome tag")),
+        };
+
+        Ok(Some(ReplayRecord {
+            fingerprint,
+            principal,
+            action,
+            resource,
+            context,
+            environment,
+            outcome,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for ReplayReader<R> {
+    type Item = Result<ReplayRecord, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                // Stop after the first error; the stream position is unknown.
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A record whose outcome differs under the replayed policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayDiff {
+    /// Zero-based position of the record in the log.
+    pub index: usize,
+    /// The original record.
+    pub record: ReplayRecord,
+    /// The outcome under the replayed policy.
+    pub replayed: RecordedOutcome,
+}
+
+/// Summary of a replay run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Number of records replayed.
+    pub total: usize,
+    /// Number of records made by a policy with a different fingerprint.
+    pub other_fingerprints: usize,
+    /// Records whose outcome changed.
+    pub changed: Vec<ReplayDiff>,
+}
+
+/// Re-evaluate recorded requests against `policy` and diff the outcomes.
+pub fn replay<I>(policy: &Policy<'_>, records: I) -> Result<ReplayReport, ReplayError>
+where
+    I: IntoIterator<Item = Result<ReplayRecord, ReplayError>>,
+{
+    let fingerprint = policy.fingerprint();
+    let mut report = ReplayReport::default();
+    for (index, record) in records.into_iter().enumerate() {
+        let record = record?;
+        report.total += 1;
+        if record.fingerprint != fingerprint {
+            report.other_fingerprints += 1;
+        }
+        let replayed = record.evaluate(policy);
+        if replayed != record.outcome {
+            report.changed.push(ReplayDiff {
+                index,
+                record,
+                replayed,
+            });
+        }
+    }
+    Ok(report)
+}
+
+fn write_str<W: Write>(w: &mut W, s: &str) -> Result<(), ReplayError> {
+    let len = u32::try_from(s.len())
+        .ok()
+        .filter(|l| *l <= MAX_STRING_BYTES)
+        .ok_or(ReplayError::Corrupt("string too long"))?;
+    w.write_all(&len.to_le_bytes())?;
+    w.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn write_value<W: Write>(w: &mut W, value: &ValueBuf) -> Result<(), ReplayError> {
+    match value {
+        ValueBuf::Bool(b) => w.write_all(&[0, *b as u8])?,
+        ValueBuf::Int(i) => {
+            w.write_all(&[1])?;
+            w.write_all(&i.to_le_bytes())?;
+        }
+        ValueBuf::String(s) => {
+            w.write_all(&[2])?;
+            write_str(w, s)?;
+        }
+        ValueBuf::Secret(_) => w.write_all(&[3])?,
+        ValueBuf::Ip(IpAddr::V4(v4)) => {
+            w.write_all(&[4, 4])?;
+            w.write_all(&v4.octets())?;
+        }
+        ValueBuf::Ip(IpAddr::V6(v6)) => {
+            w.write_all(&[4, 6])?;
+            w.write_all(&v6.octets())?;
+        }
+        ValueBuf::List(items) => {
+            let len =
+                u16::try_from(items.len()).map_err(|_| ReplayError::Corrupt("list too long"))?;
+            w.write_all(&[5])?;
+            w.write_all(&len.to_le_bytes())?;
+            for item in items {
+                write_value(w, item)?;
+            }
+        }
+        ValueBuf::Map(entries) => {
+            let len =
+                u16::try_from(entries.len()).map_err(|_| ReplayError::Corrupt("map too large"))?;
+            w.write_all(&[6])?;
+            w.write_all(&len.to_le_bytes())?;
+            for (key, value) in entries {
+                write_str(w, key)?;
+                write_value(w, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `depth` is the path depth of the value: 1 for a context attribute.
+/// Maps nest at most `ABSOLUTE_MAX_PATH_DEPTH` deep, and lists hold only
+/// scalars.
+fn read_value<R: Read>(r: &mut R, depth: usize) -> Result<ValueBuf, ReplayError> {
+    Ok(match read_u8(r)? {
+        5 => {
+            let len = u16::from_le_bytes(read_array(r)?);
+            let mut items = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let tag = read_u8(r)?;
+                items.push(read_scalar(r, tag)?);
+            }
+            ValueBuf::List(items)
+        }
+        6 if depth < ABSOLUTE_MAX_PATH_DEPTH => {
+            let len = u16::from_le_bytes(read_array(r)?);
+            let mut entries = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let key = read_str(r)?;
+                entries.push((key, read_value(r, depth + 1)?));
+            }
+            ValueBuf::Map(entries)
+        }
+        6 => return Err(ReplayError::Corrupt("map nested too deeply")),
+        tag => read_scalar(r, tag)?,
+    })
+}
+
+fn read_scalar<R: Read>(r: &mut R, tag: u8) -> Result<ValueBuf, ReplayError> {
+    Ok(match tag {
+        0 => ValueBuf::Bool(read_u8(r)? != 0),
+        1 => ValueBuf::Int(i64::from_le_bytes(read_array(r)?)),
+        2 => ValueBuf::String(read_str(r)?),
+        3 => ValueBuf::Secret(Vec::new()),
+        4 => match read_u8(r)? {
+            4 => ValueBuf::Ip(IpAddr::V4(Ipv4Addr::from(read_array::<_, 4>(r)?))),
+            6 => ValueBuf::Ip(IpAddr::V6(Ipv6Addr::from(read_array::<_, 16>(r)?))),
+            _ => return Err(ReplayError::Corrupt("unknown address family")),
+        },
+        5 | 6 => return Err(ReplayError::Corrupt("list holds a list or map")),
+        _ => return Err(ReplayError::Corrupt("unknown value tag")),
+    })
+}
+
+fn read_array<R: Read, const N: usize>(r: &mut R) -> Result<[u8; N], ReplayError> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u8<R: Read>(r: &mut R) -> Result<u8, ReplayError> {
+    Ok(read_array::<R, 1>(r)?[0])
+}
+
+fn read_str<R: Read>(r: &mut R) -> Result<String, ReplayError> {
+    let len = u32::from_le_bytes(read_array(r)?);
+    if len > MAX_STRING_BYTES {
+        return Err(ReplayError::Corrupt("string too long"));
+    }
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| ReplayError::Corrupt("invalid utf-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::{PolicyConfig, Rule};
+    use crate::redact::Sensitivity;
+    use crate::target::Target;
+    use crate::value::Value;
+
+    fn policy(mfa_required: bool) -> Policy<'static> {
+        let cond = if mfa_required {
+            Some(Condition::Equals {
+                attr: "mfa",
+                value: Value::Bool(true),
+            })
+        } else {
+            None
+        };
+        Policy::builder()
+            .rule(Rule::new(Effect::Allow, Target::any(), cond, ReasonCode(7)))
+            .build()
+            .unwrap()
+    }
+
+    fn record_log(policy: &Policy<'_>) -> Vec<u8> {
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        let with_mfa: &[(&str, Value)] = &[("mfa", Value::Bool(true)), ("n", Value::Int(-3))];
+        let posture = [("compliant", Value::Bool(true))];
+        let device = [
+            ("groups", Value::list(&[Value::String("ops")])),
+            ("posture", Value::map(&posture)),
+        ];
+        let without_mfa: &[(&str, Value)] = &[
+            ("team", Value::String("ops")),
+            ("client", Value::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))),
+            ("peer", Value::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST))),
+            ("device", Value::map(&device)),
+        ];
+        writer
+            .evaluate_and_record(policy, &Request::with_context("a", "read", "x", with_mfa))
+            .unwrap()
+            .unwrap();
+        writer
+            .evaluate_and_record(
+                policy,
+                &Request::with_context("b", "read", "y", without_mfa),
+            )
+            .unwrap()
+            .unwrap();
+        writer.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let policy = policy(false);
+        let log = record_log(&policy);
+        let records: Vec<ReplayRecord> = ReplayReader::new(&log[..])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].principal, "a");
+        assert_eq!(records[0].fingerprint, policy.fingerprint());
+        assert_eq!(
+            records[0].context,
+            vec![
+                ("mfa".to_string(), ValueBuf::Bool(true)),
+                ("n".to_string(), ValueBuf::Int(-3)),
+            ]
+        );
+        assert_eq!(
+            records[1].context[1..3],
+            [
+                (
+                    "client".to_string(),
+                    ValueBuf::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))
+                ),
+                (
+                    "peer".to_string(),
+                    ValueBuf::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST))
+                ),
+            ]
+        );
+        assert_eq!(
+            records[1].context[3].1,
+            ValueBuf::Map(vec![
+                (
+                    "groups".to_string(),
+                    ValueBuf::List(vec![ValueBuf::String("ops".to_string())])
+                ),
+                (
+                    "posture".to_string(),
+                    ValueBuf::Map(vec![("compliant".to_string(), ValueBuf::Bool(true))])
+                ),
+            ])
+        );
+        assert_eq!(
+            records[1].outcome,
+            RecordedOutcome::Decision(Decision::allow(ReasonCode(7)))
+        );
+    }
+
+    #[test]
+    fn test_environment_roundtrip() {
+        let policy = policy(false);
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        writer
+            .evaluate_and_record(
+                &policy,
+                &Request::new("a", "read", "x").in_environment("prod"),
+            )
+            .unwrap()
+            .unwrap();
+        let log = writer.into_inner().unwrap();
+        let record = ReplayReader::new(&log[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.environment.as_deref(), Some("prod"));
+
+        // A version 1 log is the same record without the environment byte
+        let mut log = ReplayWriter::new(Vec::new()).unwrap();
+        log.evaluate_and_record(&policy, &Request::new("a", "read", "x"))
+            .unwrap()
+            .unwrap();
+        let mut v1 = log.into_inner().unwrap();
+        v1[4] = 1;
+        let env_at = 5 + 8 + (4 + 1) + (4 + 4) + (4 + 1);
+        assert_eq!(v1.remove(env_at), 0);
+        let record = ReplayReader::new(&v1[..]).unwrap().next().unwrap().unwrap();
+        assert_eq!(record.environment, None);
+        assert_eq!(
+            record.outcome,
+            RecordedOutcome::Decision(Decision::allow(ReasonCode(7)))
+        );
+    }
+
+    #[test]
+    fn test_replay_same_policy_is_clean() {
+        let policy = policy(false);
+        let log = record_log(&policy);
+        let report = replay(&policy, ReplayReader::new(&log[..]).unwrap()).unwrap();
+        assert_eq!(report.total, 2);
+        assert_eq!(report.other_fingerprints, 0);
+        assert!(report.changed.is_empty());
+    }
+
+    #[test]
+    fn test_replay_reports_changes() {
+        let old = policy(false);
+        let new = policy(true);
+        let log = record_log(&old);
+        let report = replay(&new, ReplayReader::new(&log[..]).unwrap()).unwrap();
+
+        assert_eq!(report.other_fingerprints, 2);
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.changed[0].index, 1);
+        assert_eq!(
+            report.changed[0].replayed,
+            RecordedOutcome::Decision(Decision::deny(crate::types::NO_MATCHING_RULE))
+        );
+    }
+
+    #[test]
+    fn test_secrets_not_written() {
+        let policy = policy(false);
+        let ctx: &[(&str, Value)] = &[("api_key", Value::Secret(b"sk_live_123"))];
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        writer
+            .evaluate_and_record(&policy, &Request::with_context("a", "read", "x", ctx))
+            .unwrap()
+            .unwrap();
+        let log = writer.into_inner().unwrap();
+        assert!(!log.windows(11).any(|w| w == b"sk_live_123"));
+
+        let record = ReplayReader::new(&log[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            record.context,
+            vec![("api_key".to_string(), ValueBuf::Secret(Vec::new()))]
+        );
+    }
+
+    #[test]
+    fn test_sensitive_attributes_redacted() {
+        let policy = Policy::builder()
+            .rule(Rule::new(Effect::Allow, Target::any(), None, ReasonCode(7)))
+            .sensitive_attribute("email", Sensitivity::Pii)
+            .build()
+            .unwrap();
+        let ctx: &[(&str, Value)] = &[("email", Value::String("alice@example.com"))];
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        writer
+            .evaluate_and_record(&policy, &Request::with_context("a", "read", "x", ctx))
+            .unwrap()
+            .unwrap();
+        let log = writer.into_inner().unwrap();
+        assert!(!log.windows(17).any(|w| w == b"alice@example.com"));
+
+        let record = ReplayReader::new(&log[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.context, policy.redact_context(ctx));
+        assert!(matches!(&record.context[0].1, ValueBuf::String(s) if s.starts_with("pii:")));
+    }
+
+    #[test]
+    fn test_error_outcomes_recorded() {
+        let config = PolicyConfig {
+            max_string_len: 3,
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::with_config(vec![], config).unwrap();
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        let result = writer
+            .evaluate_and_record(&policy, &Request::new("toolong", "a", "b"))
+            .unwrap();
+        assert!(result.is_err());
+
+        let log = writer.into_inner().unwrap();
+        let record = ReplayReader::new(&log[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            record.outcome,
+            RecordedOutcome::Error("string exceeds maximum length of 3, got 7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decision_metadata_roundtrip() {
+        let policy = Policy::builder()
+            .rule(
+                Rule::challenge(Target::any(), ChallengeMethod::Custom(42), ReasonCode(5))
+                    .with_cache_ttl(60),
+            )
+            .rule(Rule::break_glass(Target::any(), "sos", ReasonCode(6)))
+            .build()
+            .unwrap();
+        let sos: &[(&str, Value)] = &[("sos", Value::Bool(true))];
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        let challenged = writer
+            .evaluate_and_record(&policy, &Request::new("a", "b", "c"))
+            .unwrap()
+            .unwrap();
+        let emergency = writer
+            .evaluate_and_record(&policy, &Request::with_context("a", "b", "c", sos))
+            .unwrap()
+            .unwrap();
+        assert!(emergency.break_glass);
+
+        let log = writer.into_inner().unwrap();
+        let outcomes: Vec<RecordedOutcome> = ReplayReader::new(&log[..])
+            .unwrap()
+            .map(|r| r.unwrap().outcome)
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                RecordedOutcome::Decision(challenged),
+                RecordedOutcome::Decision(emergency),
+            ]
+        );
+        assert_eq!(
+            challenged,
+            Decision::challenge(ChallengeMethod::Custom(42), ReasonCode(5))
+                .with_cache_ttl(Some(60))
+        );
+
+        let gated = Rule::allow(Target::any(), ReasonCode(7)).with_approval(3, 900);
+        let policy = Policy::new(vec![gated]).unwrap();
+        let mut writer = ReplayWriter::new(Vec::new()).unwrap();
+        let pending = writer
+            .evaluate_and_record(&policy, &Request::new("a", "b", "c"))
+            .unwrap()
+            .unwrap();
+        let log = writer.into_inner().unwrap();
+        let record = ReplayReader::new(&log[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.outcome, RecordedOutcome::Decision(pending));
+        assert_eq!(pending.obligations.len(), 1);
+    }
+
+    #[test]
+    fn test_corrupt_input() {
+        assert!(matches!(
+            ReplayReader::new(&b"NOPE\x01"[..]),
+            Err(ReplayError::BadMagic)
+        ));
+        assert!(matches!(
+            ReplayReader::new(&b"G0RL\x09"[..]),
+            Err(ReplayError::UnsupportedVersion(9))
+        ));
+
+        // Truncated record
+        let log = record_log(&policy(false));
+        let mut reader = ReplayReader::new(&log[..log.len() - 2]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next(), Some(Err(ReplayError::Io(_)))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,299 @@
+//! HTML audit reports.
+//!
+//! Renders a policy and the results of gate0's analyses into one
+//! standalone HTML document (inline styles, no scripts) for compliance
+//! reviews. Every finding comes from a public API, so a report never says
+//! anything the analyses themselves do not:
+//!
+//! - rules and complexity: `Policy::rules` and `Policy::statistics`
+//! - conflicts: `graph::overrides`
+//! - redundant rules: `Policy::minimize`
+//! - coverage: a `CoverageReport` from `Policy::coverage`, if supplied
+
+use std::fmt::Write;
+
+use crate::coverage::CoverageReport;
+use crate::graph;
+use crate::minimize::Equivalence;
+use crate::policy::Policy;
+use crate::target::Matcher;
+use crate::types::Effect;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #bbb;padding:4px 8px;text-align:left}\
+th{background:#eee}code{font-size:90%}\
+.allow{background:#d5f5e3}.deny{background:#fadbd8}\
+.challenge{background:#fdebd0}.indeterminate{background:#eaecee}";
+
+/// Render an audit report for `policy` as a standalone HTML document.
+///
+/// `coverage` adds a coverage section; pass the report from running the
+/// policy's test suite through `Policy::coverage`.
+pub fn to_html(policy: &Policy<'_>, title: &str, coverage: Option<&CoverageReport>) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n<p>Fingerprint <code>{:016x}</code>, {} rule(s).</p>\n",
+        policy.fingerprint(),
+        policy.rule_count(),
+        title = escape(title),
+    );
+    rules_section(&mut out, policy);
+    conflicts_section(&mut out, policy);
+    redundancy_section(&mut out, policy);
+    if let Some(coverage) = coverage {
+        coverage_section(&mut out, coverage);
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn rules_section(out: &mut String, policy: &Policy<'_>) {
+    out.push_str(
+        "<h2>Rules</h2>\n<table>\n<tr><th>#</th><th>Effect</th><th>Principal</th>\
+         <th>Action</th><th>Resource</th><th>Condition depth</th>\
+         <th>Condition nodes</th><th>Reason</th><th>Notes</th></tr>\n",
+    );
+    for (index, rule) in policy.rules().iter().enumerate() {
+        let (depth, nodes) = rule
+            .condition
+            .as_ref()
+            .map_or((0, 0), |c| (c.depth(), c.node_count()));
+        let mut notes = Vec::new();
+        if rule.target.environment != Matcher::Any {
+            notes.push(format!("in {}", matcher_cell(&rule.target.environment)));
+        }
+        if let Some(flag) = rule.break_glass {
+            notes.push(format!("break-glass on <code>{}</code>", escape(flag)));
+        }
+        if rule.schedule.is_some() {
+            notes.push("scheduled".to_string());
+        }
+        if let Some(approval) = rule.approval {
+            notes.push(format!(
+                "approval by group {} for {}s",
+                approval.approvers, approval.expires_in
+            ));
+        }
+        if let Some(ttl) = rule.cache_ttl {
+            notes.push(format!("cache {}s", ttl));
+        }
+        let _ = writeln!(
+            out,
+            "<tr class=\"{}\"><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            effect_class(rule.effect),
+            index,
+            rule.effect,
+            matcher_cell(&rule.target.principal),
+            matcher_cell(&rule.target.action),
+            matcher_cell(&rule.target.resource),
+            depth,
+            nodes,
+            rule.reason.0,
+            notes.join(", "),
+        );
+    }
+    out.push_str("</table>\n");
+    let stats = policy.statistics();
+    let largest: Vec<String> = stats
+        .largest_rules
+        .iter()
+        .filter(|(_, nodes)| *nodes > 0)
+        .map(|(index, nodes)| format!("#{} ({})", index, nodes))
+        .collect();
+    let _ = writeln!(
+        out,
+        "<p>{} allow, {} deny, {} challenge, {} break-glass. Deepest condition: {}. \
+         Total condition nodes: {}. Largest conditions: {}.</p>",
+        stats.allow,
+        stats.deny,
+        stats.challenge,
+        stats.break_glass,
+        stats.max_depth(),
+        stats.total_nodes,
+        if largest.is_empty() {
+            "none".to_string()
+        } else {
+            largest.join(", ")
+        },
+    );
+}
+
+fn conflicts_section(out: &mut String, policy: &Policy<'_>) {
+    out.push_str("<h2>Conflicts</h2>\n");
+    let overrides = graph::overrides(policy);
+    if overrides.is_empty() {
+        out.push_str("<p>No rule overrides another.</p>\n");
+        return;
+    }
+    out.push_str(
+        "<p>Rules whose targets overlap, where the first wins whenever both match.</p>\n\
+         <table>\n<tr><th>Rule</th><th>Overrides</th></tr>\n",
+    );
+    let rules = policy.rules();
+    for o in overrides {
+        let _ = writeln!(
+            out,
+            "<tr><td>#{} {:?}</td><td>#{} {:?}</td></tr>",
+            o.rule, rules[o.rule].effect, o.overridden, rules[o.overridden].effect
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn redundancy_section(out: &mut String, policy: &Policy<'_>) {
+    out.push_str("<h2>Redundant rules</h2>\n");
+    let minimized = policy.minimize();
+    let proof = match &minimized.proof {
+        Equivalence::Proved => "verified exhaustively",
+        Equivalence::Unknown => "not verified: policy outside the checkable fragment",
+        Equivalence::Counterexample(_) => "rejected: the checker found a disagreement",
+    };
+    if minimized.removed.is_empty() {
+        let _ = writeln!(out, "<p>No rule can be removed ({}).</p>", proof);
+        return;
+    }
+    let removed: Vec<String> = minimized
+        .removed
+        .iter()
+        .map(|i| format!("#{}", i))
+        .collect();
+    let _ = writeln!(
+        out,
+        "<p>These rules never change a decision and can be removed ({}): {}.</p>",
+        proof,
+        removed.join(", ")
+    );
+}
+
+fn coverage_section(out: &mut String, coverage: &CoverageReport) {
+    let _ = writeln!(
+        out,
+        "<h2>Coverage</h2>\n<p>{} request(s), {} error(s). Rules fired: {:.0}%. \
+         Condition outcomes observed: {:.0}%.</p>",
+        coverage.requests,
+        coverage.errors,
+        coverage.rule_ratio() * 100.0,
+        coverage.branch_ratio() * 100.0,
+    );
+    out.push_str(
+        "<table>\n<tr><th>#</th><th>Target hits</th><th>Fired</th>\
+         <th>Uncovered branches</th></tr>\n",
+    );
+    for rule in &coverage.rules {
+        let uncovered: Vec<String> = rule
+            .branches
+            .iter()
+            .filter(|b| !b.is_covered())
+            .map(|b| format!("{} {}", b.kind, b.node))
+            .collect();
+        let _ = writeln!(
+            out,
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            if rule.fired == 0 { "deny" } else { "allow" },
+            rule.index,
+            rule.target_hits,
+            rule.fired,
+            uncovered.join(", "),
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn matcher_cell(matcher: &Matcher<'_>) -> String {
+    match matcher {
+        Matcher::Any => "<em>any</em>".to_string(),
+        Matcher::Exact(s) => format!("<code>{}</code>", escape(s)),
+        Matcher::OneOf(options) => {
+            let options: Vec<String> = options
+                .iter()
+                .map(|s| format!("<code>{}</code>", escape(s)))
+                .collect();
+            options.join(" ")
+        }
+    }
+}
+
+fn effect_class(effect: Effect) -> &'static str {
+    match effect {
+        Effect::Allow => "allow",
+        Effect::Deny => "deny",
+        Effect::Challenge(_) => "challenge",
+        Effect::Indeterminate => "indeterminate",
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::policy::Rule;
+    use crate::target::Target;
+    use crate::types::{ReasonCode, Request};
+
+    #[test]
+    fn test_html_report() {
+        let policy = Policy::builder()
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(1),
+            ))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Any,
+                    resource: Matcher::Exact("<vault>"),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(2),
+            ))
+            .rule(Rule::new(
+                Effect::Allow,
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact("read"),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                Some(Condition::False),
+                ReasonCode(3),
+            ))
+            .build()
+            .unwrap();
+        let coverage = policy.coverage(&[Request::new("alice", "read", "doc")]);
+        let html = to_html(&policy, "Docs & files", Some(&coverage));
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Docs &amp; files</title>"));
+        assert!(html.contains("<code>&lt;vault&gt;</code>"));
+        assert!(html.contains("<tr><td>#1 Deny</td><td>#0 Allow</td></tr>"));
+        assert!(html.contains("Largest conditions: #2 (1)."));
+        assert!(html.contains("can be removed (verified exhaustively): #2."));
+        assert!(html.contains("Rules fired: 33%."));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
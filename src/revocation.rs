@@ -0,0 +1,407 @@
+//! Fast revocation overlay.
+//!
+//! A `RevocationList` holds principals, resources, and credential ids that
+//! must be denied regardless of the rules, and is consulted before the
+//! policy is evaluated. Blocking a compromised principal is one `update`
+//! call on a running process instead of a policy rebuild and rollout.
+//!
+//! Readers take a snapshot of the current set (an `Arc` clone under a read
+//! lock) and look entries up in O(1). Writers copy the set, change the
+//! copy, and swap it in, so a batch of changes becomes visible at once and
+//! a failed batch changes nothing.
+//!
+//! `RevocationList::evaluate` matches entries under the policy's
+//! `PolicyConfig::collation`, as its rules match names, so a principal
+//! revoked as `"alice"` cannot get in as `"ALICE"` under a case-insensitive
+//! collation. Credential ids sent as secrets always match byte for byte.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::collation::Collation;
+use crate::error::PolicyError;
+use crate::policy::Policy;
+use crate::types::{Decision, ReasonCode, Request};
+use crate::value::Value;
+
+/// Maximum number of entries in a `RevocationSet`, across all kinds.
+pub const MAX_REVOCATIONS: usize = 65_536;
+
+/// Default context attribute holding the request's credential id.
+pub const DEFAULT_CREDENTIAL_ATTR: &str = "credential_id";
+
+/// Revoked principals, resources, and credential ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevocationSet {
+    principals: HashSet<String>,
+    resources: HashSet<String>,
+    credentials: HashSet<String>,
+}
+
+impl RevocationSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Revoke a principal.
+    pub fn revoke_principal(&mut self, principal: &str) -> Result<(), PolicyError> {
+        if !self.principals.contains(principal) {
+            self.check_room()?;
+            self.principals.insert(principal.to_string());
+        }
+        Ok(())
+    }
+
+    /// Revoke a resource.
+    pub fn revoke_resource(&mut self, resource: &str) -> Result<(), PolicyError> {
+        if !self.resources.contains(resource) {
+            self.check_room()?;
+            self.resources.insert(resource.to_string());
+        }
+        Ok(())
+    }
+
+    /// Revoke a credential id.
+    pub fn revoke_credential(&mut self, credential: &str) -> Result<(), PolicyError> {
+        if !self.credentials.contains(credential) {
+            self.check_room()?;
+            self.credentials.insert(credential.to_string());
+        }
+        Ok(())
+    }
+
+    /// Lift a principal's revocation. Returns `true` if it was revoked.
+    pub fn reinstate_principal(&mut self, principal: &str) -> bool {
+        self.principals.remove(principal)
+    }
+
+    /// Lift a resource's revocation. Returns `true` if it was revoked.
+    pub fn reinstate_resource(&mut self, resource: &str) -> bool {
+        self.resources.remove(resource)
+    }
+
+    /// Lift a credential's revocation. Returns `true` if it was revoked.
+    pub fn reinstate_credential(&mut self, credential: &str) -> bool {
+        self.credentials.remove(credential)
+    }
+
+    /// Number of entries of all kinds.
+    pub fn len(&self) -> usize {
+        self.principals.len() + self.resources.len() + self.credentials.len()
+    }
+
+    /// Returns `true` if nothing is revoked.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the request's principal, resource, or credential
+    /// (the string or secret attribute `credential_attr`) is revoked,
+    /// comparing byte for byte.
+    pub fn is_revoked(&self, request: &Request<'_>, credential_attr: &str) -> bool {
+        self.is_revoked_folded(request, credential_attr, Collation::Binary, self)
+    }
+
+    /// `is_revoked` on a set folded by `collation`, looking up the
+    /// request's keys under it. Secret credential ids are looked up in
+    /// `raw`, the set before folding.
+    fn is_revoked_folded(
+        &self,
+        request: &Request<'_>,
+        credential_attr: &str,
+        collation: Collation,
+        raw: &RevocationSet,
+    ) -> bool {
+        self.principals.contains(&*collation.key(request.principal))
+            || self.resources.contains(&*collation.key(request.resource))
+            || match request.get_attr(credential_attr) {
+                Some(Value::String(id)) => self.credentials.contains(&*collation.key(id)),
+                Some(Value::Secret(id)) => {
+                    std::str::from_utf8(id).is_ok_and(|id| raw.credentials.contains(id))
+                }
+                _ => false,
+            }
+    }
+
+    /// A copy with every entry replaced by its `collation` key.
+    fn folded(&self, collation: Collation) -> RevocationSet {
+        let fold = |set: &HashSet<String>| {
+            set.iter()
+                .map(|entry| collation.key(entry).into_owned())
+                .collect()
+        };
+        RevocationSet {
+            principals: fold(&self.principals),
+            resources: fold(&self.resources),
+            credentials: fold(&self.credentials),
+        }
+    }
+
+    /// Fail if one more entry would exceed `MAX_REVOCATIONS`.
+    fn check_room(&self) -> Result<(), PolicyError> {
+        if self.len() >= MAX_REVOCATIONS {
+            return Err(PolicyError::TooManyRevocations {
+                max: MAX_REVOCATIONS,
+                actual: self.len() + 1,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A collation, the set folded under it, and the folded result.
+type FoldedSet = (Collation, Arc<RevocationSet>, Arc<RevocationSet>);
+
+/// A shared, atomically updated `RevocationSet` that denies matching
+/// requests before any rule runs.
+#[derive(Debug)]
+pub struct RevocationList<'a> {
+    current: RwLock<Arc<RevocationSet>>,
+    /// The last folded set: its collation, the set it was folded from, and
+    /// the result. Stale once `current` is replaced.
+    folded: Mutex<Option<FoldedSet>>,
+    credential_attr: &'a str,
+    deny_reason: ReasonCode,
+}
+
+impl<'a> RevocationList<'a> {
+    /// Create an empty list. Revoked requests are denied with `deny_reason`;
+    /// credential ids are read from `credential_id`.
+    pub fn new(deny_reason: ReasonCode) -> Self {
+        RevocationList {
+            current: RwLock::new(Arc::new(RevocationSet::new())),
+            folded: Mutex::new(None),
+            credential_attr: DEFAULT_CREDENTIAL_ATTR,
+            deny_reason,
+        }
+    }
+
+    /// Read credential ids from a different context attribute.
+    pub fn with_credential_attr(mut self, attr: &'a str) -> Self {
+        self.credential_attr = attr;
+        self
+    }
+
+    /// Get the reason code returned for revoked requests.
+    pub fn deny_reason(&self) -> ReasonCode {
+        self.deny_reason
+    }
+
+    /// The current set. Later updates do not change a snapshot.
+    pub fn snapshot(&self) -> Arc<RevocationSet> {
+        // A panicking `update` closure poisons the lock, but never leaves a
+        // half-applied set behind it.
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&current)
+    }
+
+    /// Replace the whole set, e.g. with one fetched from a central store.
+    pub fn replace(&self, set: RevocationSet) {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        *current = Arc::new(set);
+    }
+
+    /// Apply a batch of changes atomically.
+    ///
+    /// `change` edits a copy of the current set; the copy is swapped in
+    /// only if it returns `Ok`, so readers see all of the batch or none.
+    pub fn update<F>(&self, change: F) -> Result<(), PolicyError>
+    where
+        F: FnOnce(&mut RevocationSet) -> Result<(), PolicyError>,
+    {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        let mut next = RevocationSet::clone(&current);
+        change(&mut next)?;
+        *current = Arc::new(next);
+        Ok(())
+    }
+
+    /// Returns `true` if the request is revoked, comparing byte for byte.
+    pub fn is_revoked(&self, request: &Request<'_>) -> bool {
+        self.snapshot().is_revoked(request, self.credential_attr)
+    }
+
+    /// Returns `true` if the request is revoked, comparing names and
+    /// string credential ids under `collation`.
+    pub fn is_revoked_under(&self, request: &Request<'_>, collation: Collation) -> bool {
+        let current = self.snapshot();
+        if collation == Collation::Binary {
+            return current.is_revoked(request, self.credential_attr);
+        }
+        let mut cache = self.folded.lock().unwrap_or_else(|e| e.into_inner());
+        let folded = match &*cache {
+            Some((c, source, folded)) if *c == collation && Arc::ptr_eq(source, &current) => {
+                Arc::clone(folded)
+            }
+            _ => {
+                let folded = Arc::new(current.folded(collation));
+                *cache = Some((collation, Arc::clone(&current), Arc::clone(&folded)));
+                folded
+            }
+        };
+        drop(cache);
+        folded.is_revoked_folded(request, self.credential_attr, collation, &current)
+    }
+
+    /// Evaluate the request against `policy` unless it is revoked.
+    ///
+    /// A revoked request is denied with the configured reason without
+    /// evaluating any rule; otherwise this is `policy.evaluate(request)`.
+    /// Entries match under `policy`'s collation (see `is_revoked_under`).
+    pub fn evaluate(
+        &self,
+        policy: &Policy<'_>,
+        request: &Request<'_>,
+    ) -> Result<Decision, PolicyError> {
+        if self.is_revoked_under(request, policy.config().collation) {
+            return Ok(Decision::deny(self.deny_reason));
+        }
+        policy.evaluate(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{PolicyConfig, Rule};
+    use crate::target::Target;
+
+    const ALLOW_ALL: ReasonCode = ReasonCode(1);
+    const REVOKED: ReasonCode = ReasonCode(401);
+
+    fn allow_all() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(Target::any(), ALLOW_ALL))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_revoked_requests_are_denied() {
+        let policy = allow_all();
+        let list = RevocationList::new(REVOKED);
+        let alice = Request::new("alice", "read", "doc");
+        assert_eq!(
+            list.evaluate(&policy, &alice).unwrap(),
+            Decision::allow(ALLOW_ALL)
+        );
+
+        list.update(|set| set.revoke_principal("alice")).unwrap();
+        assert_eq!(
+            list.evaluate(&policy, &alice).unwrap(),
+            Decision::deny(REVOKED)
+        );
+        let bob = Request::new("bob", "read", "doc");
+        assert!(list.evaluate(&policy, &bob).unwrap().is_allow());
+
+        list.update(|set| {
+            set.reinstate_principal("alice");
+            set.revoke_resource("doc")
+        })
+        .unwrap();
+        assert!(list.is_revoked(&alice));
+        assert!(list
+            .evaluate(&policy, &Request::new("alice", "read", "other"))
+            .unwrap()
+            .is_allow());
+    }
+
+    #[test]
+    fn test_credentials() {
+        let list = RevocationList::new(REVOKED).with_credential_attr("token_id");
+        list.update(|set| set.revoke_credential("tok-1")).unwrap();
+
+        let ctx: &[(&str, Value)] = &[("token_id", Value::String("tok-1"))];
+        assert!(list.is_revoked(&Request::with_context("alice", "read", "doc", ctx)));
+        let ctx: &[(&str, Value)] = &[("credential_id", Value::String("tok-1"))];
+        assert!(!list.is_revoked(&Request::with_context("alice", "read", "doc", ctx)));
+        // Credential ids are tokens, so they often arrive as secrets
+        let ctx: &[(&str, Value)] = &[("token_id", Value::Secret(b"tok-1"))];
+        assert!(list.is_revoked(&Request::with_context("alice", "read", "doc", ctx)));
+        let ctx: &[(&str, Value)] = &[("token_id", Value::Secret(b"tok-2"))];
+        assert!(!list.is_revoked(&Request::with_context("alice", "read", "doc", ctx)));
+    }
+
+    #[test]
+    fn test_collation() {
+        let policy = Policy::with_config(
+            vec![Rule::allow(Target::any(), ALLOW_ALL)],
+            PolicyConfig {
+                collation: Collation::AsciiCaseInsensitive,
+                ..PolicyConfig::default()
+            },
+        )
+        .unwrap();
+        let list = RevocationList::new(REVOKED);
+        list.update(|set| {
+            set.revoke_principal("alice")?;
+            set.revoke_credential("Tok-1")
+        })
+        .unwrap();
+
+        let shouting = Request::new("ALICE", "read", "doc");
+        assert!(!list.is_revoked(&shouting));
+        assert_eq!(
+            list.evaluate(&policy, &shouting).unwrap(),
+            Decision::deny(REVOKED)
+        );
+        let ctx: &[(&str, Value)] = &[("credential_id", Value::String("TOK-1"))];
+        let request = Request::with_context("bob", "read", "doc", ctx);
+        assert!(list.evaluate(&policy, &request).unwrap().is_deny());
+        // Secrets still match byte for byte
+        let ctx: &[(&str, Value)] = &[("credential_id", Value::Secret(b"TOK-1"))];
+        let request = Request::with_context("bob", "read", "doc", ctx);
+        assert!(list.evaluate(&policy, &request).unwrap().is_allow());
+
+        // The folded set follows updates
+        list.update(|set| {
+            set.reinstate_principal("alice");
+            Ok(())
+        })
+        .unwrap();
+        assert!(list.evaluate(&policy, &shouting).unwrap().is_allow());
+        // A byte-for-byte policy only denies the exact name
+        let binary = allow_all();
+        list.update(|set| set.revoke_principal("alice")).unwrap();
+        assert!(list.evaluate(&binary, &shouting).unwrap().is_allow());
+    }
+
+    #[test]
+    fn test_updates_are_atomic() {
+        let list = RevocationList::new(REVOKED);
+        let before = list.snapshot();
+
+        // A failing batch leaves the set untouched
+        let result = list.update(|set| {
+            set.revoke_principal("alice")?;
+            Err(PolicyError::InternalError)
+        });
+        assert_eq!(result, Err(PolicyError::InternalError));
+        assert!(list.snapshot().is_empty());
+
+        let mut set = RevocationSet::new();
+        set.revoke_principal("mallory").unwrap();
+        list.replace(set.clone());
+        assert_eq!(*list.snapshot(), set);
+        // Earlier snapshots are unaffected
+        assert!(before.is_empty());
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut set = RevocationSet::new();
+        for i in 0..MAX_REVOCATIONS {
+            set.revoke_resource(&format!("doc-{}", i)).unwrap();
+        }
+        // Re-revoking takes no room
+        assert_eq!(set.revoke_resource("doc-0"), Ok(()));
+        assert_eq!(
+            set.revoke_principal("alice"),
+            Err(PolicyError::TooManyRevocations {
+                max: MAX_REVOCATIONS,
+                actual: MAX_REVOCATIONS + 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Rule schedules.
+//!
+//! A `Schedule` restricts a rule to certain days of the week and times of
+//! day, e.g. "weekdays 09:00-17:00 UTC+1". The current time is read from a
+//! context attribute (default `now`) holding Unix seconds as `Value::Int`,
+//! or from a `Clock` passed to `Policy::evaluate_at`, so evaluation stays
+//! deterministic and the caller controls the clock.
+//!
+//! Time zones are fixed UTC offsets; daylight saving is the caller's
+//! concern (pick the offset when building the policy, or supply local time).
+
+use crate::error::PolicyError;
+use crate::value::Value;
+
+/// Default context attribute holding the current time in Unix seconds.
+pub const DEFAULT_CLOCK_ATTR: &str = "now";
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// Real-world offsets lie within ±14h; allow a little slack.
+const MAX_UTC_OFFSET_MINUTES: i32 = 18 * 60;
+
+/// A set of days of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Days(u8);
+
+impl Days {
+    /// Monday.
+    pub const MON: Days = Days(1 << 0);
+    /// Tuesday.
+    pub const TUE: Days = Days(1 << 1);
+    /// Wednesday.
+    pub const WED: Days = Days(1 << 2);
+    /// Thursday.
+    pub const THU: Days = Days(1 << 3);
+    /// Friday.
+    pub const FRI: Days = Days(1 << 4);
+    /// Saturday.
+    pub const SAT: Days = Days(1 << 5);
+    /// Sunday.
+    pub const SUN: Days = Days(1 << 6);
+    /// Monday through Friday.
+    pub const WEEKDAYS: Days = Days(0b001_1111);
+    /// Saturday and Sunday.
+    pub const WEEKEND: Days = Days(0b110_0000);
+    /// Every day.
+    pub const ALL: Days = Days(0b111_1111);
+
+    /// Combine two day sets.
+    pub const fn union(self, other: Days) -> Days {
+        Days(self.0 | other.0)
+    }
+
+    /// Returns `true` if the set contains the day (0 = Monday .. 6 = Sunday).
+    pub const fn contains_index(self, day: u8) -> bool {
+        day < 7 && self.0 & (1 << day) != 0
+    }
+
+    /// The set with the given raw bitmask; bits above Sunday are dropped.
+    pub const fn from_bits(bits: u8) -> Days {
+        Days(bits & Days::ALL.0)
+    }
+
+    /// Get the raw bitmask (bit 0 = Monday).
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+/// A half-open window `[start, end)` of minutes since local midnight.
+///
+/// If `end < start` the window wraps past midnight (e.g. 22:00-06:00).
+/// `end == start` is treated as empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeWindow {
+    /// Start, in minutes since midnight.
+    pub start: u16,
+    /// End (exclusive), in minutes since midnight. 1440 means midnight.
+    pub end: u16,
+}
+
+impl TimeWindow {
+    /// Create a window from `HH:MM` to `HH:MM`.
+    ///
+    /// Out-of-range values are caught by `Schedule::validate`.
+    pub const fn new(start_hour: u16, start_minute: u16, end_hour: u16, end_minute: u16) -> Self {
+        TimeWindow {
+            start: start_hour.saturating_mul(60).saturating_add(start_minute),
+            end: end_hour.saturating_mul(60).saturating_add(end_minute),
+        }
+    }
+
+    /// The whole day.
+    pub const fn all_day() -> Self {
+        TimeWindow {
+            start: 0,
+            end: MINUTES_PER_DAY,
+        }
+    }
+
+    /// Returns `true` if both ends are within the day.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.start <= MINUTES_PER_DAY && self.end <= MINUTES_PER_DAY
+    }
+
+    /// Returns `true` if the minute of day falls inside this window.
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start <= self.end {
+            minute >= self.start && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// When a rule applies: days of the week, time windows, and a UTC offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule<'a> {
+    /// Days on which the rule applies, evaluated in local time.
+    pub days: Days,
+    /// Time windows within those days. The rule applies if any window matches.
+    pub windows: &'a [TimeWindow],
+    /// Offset of local time from UTC, in minutes.
+    pub utc_offset_minutes: i32,
+    /// Context attribute holding the current Unix time in seconds.
+    pub clock_attr: &'a str,
+}
+
+impl<'a> Schedule<'a> {
+    /// Create a UTC schedule reading the clock from `now`.
+    pub const fn new(days: Days, windows: &'a [TimeWindow]) -> Self {
+        Schedule {
+            days,
+            windows,
+            utc_offset_minutes: 0,
+            clock_attr: DEFAULT_CLOCK_ATTR,
+        }
+    }
+
+    /// Evaluate in a fixed-offset time zone.
+    pub const fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset_minutes = minutes;
+        self
+    }
+
+    /// Read the clock from a different context attribute.
+    pub const fn with_clock_attr(mut self, attr: &'a str) -> Self {
+        self.clock_attr = attr;
+        self
+    }
+
+    /// Returns `true` if the schedule is active at the given Unix time.
+    pub fn is_active_at(&self, unix_seconds: i64) -> bool {
+        let (weekday, minute) = local_time(unix_seconds, self.utc_offset_minutes);
+        self.days.contains_index(weekday) && self.windows.iter().any(|w| w.contains(minute))
+    }
+
+    /// Returns `true` if the schedule is active at the clock time in `context`.
+    ///
+    /// A missing or non-integer clock attribute means the schedule is not
+    /// active, matching how `Equals` treats missing attributes.
+    pub fn is_active(&self, context: &[(&str, Value<'_>)]) -> bool {
+        match context.iter().find(|(k, _)| *k == self.clock_attr) {
+            Some((_, Value::Int(now))) => self.is_active_at(*now),
+            _ => false,
+        }
+    }
+
+    /// Validate window bounds, offset range, and string lengths.
+    pub fn validate(&self, max_windows: usize, max_string_len: usize) -> Result<(), PolicyError> {
+        if self.windows.len() > max_windows {
+            return Err(PolicyError::InvalidSchedule);
+        }
+        if self.clock_attr.len() > max_string_len {
+            return Err(PolicyError::StringTooLong {
+                max: max_string_len,
+                actual: self.clock_attr.len(),
+            });
+        }
+        if !valid_utc_offset(self.utc_offset_minutes)
+            || !self.windows.iter().all(TimeWindow::is_valid)
+        {
+            return Err(PolicyError::InvalidSchedule);
+        }
+        Ok(())
+    }
+}
+
+/// The local weekday (0 = Monday) and minute of day at `unix_seconds`.
+pub(crate) fn local_time(unix_seconds: i64, utc_offset_minutes: i32) -> (u8, u16) {
+    let local = unix_seconds.saturating_add(utc_offset_minutes as i64 * 60);
+    let days_since_epoch = local.div_euclid(SECONDS_PER_DAY);
+    // 1970-01-01 was a Thursday (index 3 with Monday = 0).
+    let weekday = (days_since_epoch + 3).rem_euclid(7) as u8;
+    let minute = (local.rem_euclid(SECONDS_PER_DAY) / 60) as u16;
+    (weekday, minute)
+}
+
+/// Returns `true` if the offset is within the range schedules accept.
+pub(crate) fn valid_utc_offset(minutes: i32) -> bool {
+    minutes.unsigned_abs() <= MAX_UTC_OFFSET_MINUTES as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-01 00:00:00 UTC, a Monday.
+    const MONDAY_MIDNIGHT: i64 = 1_704_067_200;
+    const HOUR: i64 = 3600;
+
+    const BUSINESS_HOURS: &[TimeWindow] = &[TimeWindow::new(9, 0, 17, 0)];
+
+    #[test]
+    fn test_business_hours() {
+        let s = Schedule::new(Days::WEEKDAYS, BUSINESS_HOURS);
+        assert!(!s.is_active_at(MONDAY_MIDNIGHT + 8 * HOUR));
+        assert!(s.is_active_at(MONDAY_MIDNIGHT + 9 * HOUR));
+        assert!(s.is_active_at(MONDAY_MIDNIGHT + 17 * HOUR - 1));
+        assert!(!s.is_active_at(MONDAY_MIDNIGHT + 17 * HOUR));
+        // Saturday 10:00
+        assert!(!s.is_active_at(MONDAY_MIDNIGHT + 5 * 24 * HOUR + 10 * HOUR));
+    }
+
+    #[test]
+    fn test_utc_offset_shifts_day() {
+        // Sunday 23:30 UTC is Monday 09:30 at UTC+10.
+        let s = Schedule::new(Days::MON, BUSINESS_HOURS).with_utc_offset(10 * 60);
+        assert!(s.is_active_at(MONDAY_MIDNIGHT - HOUR / 2));
+        // Before the epoch still works.
+        let all_day = [TimeWindow::all_day()];
+        let s = Schedule::new(Days::WED, &all_day);
+        assert!(s.is_active_at(-SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        let night = [TimeWindow::new(22, 0, 6, 0)];
+        let s = Schedule::new(Days::ALL, &night);
+        assert!(s.is_active_at(MONDAY_MIDNIGHT + 23 * HOUR));
+        assert!(s.is_active_at(MONDAY_MIDNIGHT + 5 * HOUR));
+        assert!(!s.is_active_at(MONDAY_MIDNIGHT + 12 * HOUR));
+    }
+
+    #[test]
+    fn test_clock_from_context() {
+        let s = Schedule::new(Days::ALL, BUSINESS_HOURS).with_clock_attr("ts");
+        let ctx: &[(&str, Value)] = &[("ts", Value::Int(MONDAY_MIDNIGHT + 10 * HOUR))];
+        assert!(s.is_active(ctx));
+        let ctx: &[(&str, Value)] = &[("ts", Value::String("10:00"))];
+        assert!(!s.is_active(ctx));
+        assert!(!s.is_active(&[]));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Schedule::new(Days::ALL, BUSINESS_HOURS)
+            .validate(4, 256)
+            .is_ok());
+        let bad = [TimeWindow::new(25, 0, 26, 0)];
+        assert_eq!(
+            Schedule::new(Days::ALL, &bad).validate(4, 256),
+            Err(PolicyError::InvalidSchedule)
+        );
+        assert_eq!(
+            Schedule::new(Days::ALL, BUSINESS_HOURS)
+                .with_utc_offset(24 * 60)
+                .validate(4, 256),
+            Err(PolicyError::InvalidSchedule)
+        );
+        assert_eq!(
+            Schedule::new(Days::ALL, BUSINESS_HOURS)
+                .with_utc_offset(i32::MIN)
+                .validate(4, 256),
+            Err(PolicyError::InvalidSchedule)
+        );
+    }
+}
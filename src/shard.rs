@@ -0,0 +1,193 @@
+//! Per-tenant policy shards.
+//!
+//! Multi-tenant PDPs usually keep one small policy per tenant plus a shared
+//! policy for tenants without their own. `PolicyShardMap` is that container:
+//! a lookup by tenant id that falls back to the shared policy, bulk loading
+//! and swapping of shards, and a fingerprint per shard so deployments can
+//! tell which tenants' policies changed.
+//!
+//! Shards are kept in tenant-id order, so iteration and fingerprint
+//! listings are deterministic.
+
+use std::collections::BTreeMap;
+
+use crate::error::PolicyError;
+use crate::policy::Policy;
+use crate::types::{Decision, Request};
+
+/// Policies keyed by tenant id, with a shared fallback.
+#[derive(Debug, Clone)]
+pub struct PolicyShardMap<'a> {
+    shards: BTreeMap<String, Policy<'a>>,
+    fallback: Policy<'a>,
+}
+
+impl<'a> PolicyShardMap<'a> {
+    /// An empty map; every tenant uses `fallback`.
+    pub fn new(fallback: Policy<'a>) -> Self {
+        PolicyShardMap {
+            shards: BTreeMap::new(),
+            fallback,
+        }
+    }
+
+    /// Set `tenant`'s policy, returning the one it replaces.
+    pub fn insert(&mut self, tenant: impl Into<String>, policy: Policy<'a>) -> Option<Policy<'a>> {
+        self.shards.insert(tenant.into(), policy)
+    }
+
+    /// Remove `tenant`'s policy, so it uses the fallback again.
+    pub fn remove(&mut self, tenant: &str) -> Option<Policy<'a>> {
+        self.shards.remove(tenant)
+    }
+
+    /// Insert or replace every shard in `shards`; later entries win.
+    pub fn load<I>(&mut self, shards: I)
+    where
+        I: IntoIterator<Item = (String, Policy<'a>)>,
+    {
+        self.shards.extend(shards);
+    }
+
+    /// Replace all shards at once, returning the previous ones in
+    /// tenant-id order. The fallback is kept.
+    pub fn swap<I>(&mut self, shards: I) -> Vec<(String, Policy<'a>)>
+    where
+        I: IntoIterator<Item = (String, Policy<'a>)>,
+    {
+        let old = std::mem::replace(&mut self.shards, shards.into_iter().collect());
+        old.into_iter().collect()
+    }
+
+    /// Replace the fallback policy, returning the previous one.
+    pub fn set_fallback(&mut self, fallback: Policy<'a>) -> Policy<'a> {
+        std::mem::replace(&mut self.fallback, fallback)
+    }
+
+    /// Get the fallback policy.
+    pub fn fallback(&self) -> &Policy<'a> {
+        &self.fallback
+    }
+
+    /// Get `tenant`'s own policy, if it has one.
+    pub fn shard(&self, tenant: &str) -> Option<&Policy<'a>> {
+        self.shards.get(tenant)
+    }
+
+    /// Get the policy that decides for `tenant`: its shard or the fallback.
+    pub fn policy_for(&self, tenant: &str) -> &Policy<'a> {
+        self.shards.get(tenant).unwrap_or(&self.fallback)
+    }
+
+    /// Number of tenants with their own policy.
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns `true` if every tenant uses the fallback.
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    /// Iterate over the tenants with their own policy, in id order.
+    pub fn tenants(&self) -> impl Iterator<Item = &str> {
+        self.shards.keys().map(String::as_str)
+    }
+
+    /// Evaluate `request` against `tenant`'s policy.
+    pub fn evaluate(&self, tenant: &str, request: &Request<'_>) -> Result<Decision, PolicyError> {
+        self.policy_for(tenant).evaluate(request)
+    }
+
+    /// Fingerprint of the policy that decides for `tenant`.
+    pub fn fingerprint(&self, tenant: &str) -> u64 {
+        self.policy_for(tenant).fingerprint()
+    }
+
+    /// Fingerprint of every shard, in tenant-id order.
+    pub fn fingerprints(&self) -> Vec<(&str, u64)> {
+        self.shards
+            .iter()
+            .map(|(tenant, policy)| (tenant.as_str(), policy.fingerprint()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+    use crate::target::{Matcher, Target};
+    use crate::types::ReasonCode;
+
+    const SHARED: ReasonCode = ReasonCode(1);
+    const ACME: ReasonCode = ReasonCode(2);
+    const GLOBEX: ReasonCode = ReasonCode(3);
+
+    fn allow(action: &'static str, reason: ReasonCode) -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Exact(action),
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                reason,
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lookup_and_fallback() {
+        let mut shards = PolicyShardMap::new(allow("read", SHARED));
+        assert!(shards.is_empty());
+        assert!(shards.insert("acme", allow("write", ACME)).is_none());
+
+        let write = Request::new("alice", "write", "doc");
+        assert_eq!(shards.evaluate("acme", &write).unwrap().reason, ACME);
+        assert!(shards.evaluate("globex", &write).unwrap().is_deny());
+        let read = Request::new("alice", "read", "doc");
+        assert_eq!(shards.evaluate("globex", &read).unwrap().reason, SHARED);
+
+        assert!(shards.remove("acme").is_some());
+        assert!(shards.evaluate("acme", &write).unwrap().is_deny());
+
+        let old = shards.set_fallback(allow("write", GLOBEX));
+        assert_eq!(old.rule_count(), 1);
+        assert_eq!(shards.evaluate("acme", &write).unwrap().reason, GLOBEX);
+    }
+
+    #[test]
+    fn test_bulk_load_and_swap() {
+        let mut shards = PolicyShardMap::new(allow("read", SHARED));
+        shards.load(vec![
+            ("globex".to_string(), allow("read", GLOBEX)),
+            ("acme".to_string(), allow("read", SHARED)),
+            ("acme".to_string(), allow("read", ACME)),
+        ]);
+        assert_eq!(shards.tenants().collect::<Vec<_>>(), ["acme", "globex"]);
+        assert_eq!(
+            shards.fingerprints(),
+            vec![
+                ("acme", allow("read", ACME).fingerprint()),
+                ("globex", allow("read", GLOBEX).fingerprint()),
+            ]
+        );
+        assert_eq!(
+            shards.fingerprint("initech"),
+            shards.fallback().fingerprint()
+        );
+
+        let old = shards.swap(vec![("initech".to_string(), allow("read", GLOBEX))]);
+        let old_tenants: Vec<&str> = old.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(old_tenants, ["acme", "globex"]);
+        assert_eq!(shards.len(), 1);
+        assert!(shards.shard("acme").is_none());
+        assert_eq!(
+            shards.fingerprint("initech"),
+            allow("read", GLOBEX).fingerprint()
+        );
+    }
+}
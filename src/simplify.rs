@@ -0,0 +1,240 @@
+//! Condition simplification.
+//!
+//! Generated policies are full of nodes that cannot change the outcome:
+//! `And(True, x)`, `Not(Not(x))`, an `In` over an empty set. Each still
+//! costs an evaluation step and counts against node limits.
+//! `Condition::simplify` folds them away once, before the policy is built.
+
+use crate::condition::Condition;
+use crate::partial::{all, and, any, constant, implies, not, or, xor};
+
+impl<'a> Condition<'a> {
+    /// Return an equivalent condition with trivially reducible nodes removed.
+    ///
+    /// - Constants are folded through connectives: `And(True, x)` becomes
+    ///   `x`, `Or(True, x)` becomes `True`, and so on.
+    /// - `Not(Not(x))` becomes `x`.
+    /// - `In` over an empty set becomes `False`, `NotIn` over one `True`.
+    /// - `AllOf`/`AnyOf` drop children that cannot affect the result and
+    ///   collapse to a constant if one child decides it.
+    ///
+    /// The result gives the same answer for every request on which the
+    /// original evaluates without error. It may evaluate where the original
+    /// failed, since a pruned branch (say, a `MemberOf` with no group
+    /// provider) is never reached. An `AllOf`/`AnyOf` that loses children
+    /// is rebuilt as a balanced `And`/`Or` tree, because the result cannot
+    /// borrow a new slice; untouched ones keep their original slice.
+    ///
+    /// Runs in a single non-recursive pass, so deep trees are safe.
+    pub fn simplify(&self) -> Condition<'a> {
+        // Each result carries whether it differs from the original node.
+        let mut results: Vec<(Condition<'a>, bool)> = Vec::with_capacity(16);
+        for node in self.postorder() {
+            let result = match node {
+                Condition::In { values: [], .. } => (Condition::False, true),
+                Condition::NotIn { values: [], .. } => (Condition::True, true),
+                Condition::Not(_) => {
+                    let (inner, changed) = pop(&mut results);
+                    let folds = matches!(
+                        inner,
+                        Condition::True | Condition::False | Condition::Not(_)
+                    );
+                    (not(inner), changed || folds)
+                }
+                // Kept even around a constant: it still fails on a
+                // missing attribute.
+                Condition::Require(attr, _) => {
+                    let (inner, changed) = pop(&mut results);
+                    (Condition::Require(attr, Box::new(inner)), changed)
+                }
+                Condition::And(..)
+                | Condition::Or(..)
+                | Condition::Implies(..)
+                | Condition::Xor(..) => {
+                    let (b, b_changed) = pop(&mut results);
+                    let (a, a_changed) = pop(&mut results);
+                    let folds = is_constant(&a) || is_constant(&b);
+                    let join = match node {
+                        Condition::And(..) => and,
+                        Condition::Or(..) => or,
+                        Condition::Implies(..) => implies,
+                        _ => xor,
+                    };
+                    (join(a, b), a_changed || b_changed || folds)
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    // `AllOf` is decided by a `False` child and ignores
+                    // `True` ones; `AnyOf` the other way round.
+                    let decisive = matches!(node, Condition::AnyOf(_));
+                    let start = results.len() - children.len();
+                    let mut changed = children.is_empty();
+                    let mut decided = false;
+                    let mut kept = Vec::with_capacity(children.len());
+                    for (child, child_changed) in results.drain(start..) {
+                        changed |= child_changed;
+                        match child {
+                            Condition::True | Condition::False => {
+                                changed = true;
+                                decided |= matches!(child, Condition::True) == decisive;
+                            }
+                            other => kept.push(other),
+                        }
+                    }
+                    if decided {
+                        (constant(decisive), true)
+                    } else if !changed {
+                        (node.clone_shallow(), false)
+                    } else if decisive {
+                        (any(kept), true)
+                    } else {
+                        (all(kept), true)
+                    }
+                }
+                leaf => (leaf.clone_shallow(), false),
+            };
+            results.push(result);
+        }
+        pop(&mut results).0
+    }
+
+    /// Clone a node whose children, if any, are borrowed rather than boxed.
+    pub(crate) fn clone_shallow(&self) -> Condition<'a> {
+        match self {
+            Condition::AllOf(children) => Condition::AllOf(children),
+            Condition::AnyOf(children) => Condition::AnyOf(children),
+            // Leaves have no children, so the derived clone does not recurse.
+            leaf => leaf.clone(),
+        }
+    }
+}
+
+fn pop<'a>(results: &mut Vec<(Condition<'a>, bool)>) -> (Condition<'a>, bool) {
+    // The postorder walk always leaves a node's operands on the stack.
+    results.pop().unwrap_or((Condition::False, true))
+}
+
+fn is_constant(condition: &Condition<'_>) -> bool {
+    matches!(condition, Condition::True | Condition::False)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    fn admin() -> Condition<'static> {
+        Condition::Equals {
+            attr: "role",
+            value: Value::String("admin"),
+        }
+    }
+
+    fn boxed(c: Condition<'_>) -> Box<Condition<'_>> {
+        Box::new(c)
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let c = Condition::And(boxed(Condition::True), boxed(admin()));
+        assert_eq!(c.simplify(), admin());
+        let c = Condition::Or(boxed(admin()), boxed(Condition::True));
+        assert_eq!(c.simplify(), Condition::True);
+        let c = Condition::Implies(boxed(admin()), boxed(Condition::False));
+        assert_eq!(c.simplify(), Condition::Not(boxed(admin())));
+        let c = Condition::Xor(boxed(Condition::True), boxed(Condition::True));
+        assert_eq!(c.simplify(), Condition::False);
+
+        let empty: &[Value] = &[];
+        let c = Condition::Or(
+            boxed(Condition::In {
+                attr: "role",
+                values: empty,
+            }),
+            boxed(Condition::NotIn {
+                attr: "role",
+                values: empty,
+            }),
+        );
+        assert_eq!(c.simplify(), Condition::True);
+
+        // Nothing to do leaves the tree as it was
+        let c = Condition::And(boxed(admin()), boxed(Condition::MemberOf("staff")));
+        assert_eq!(c.simplify(), c);
+    }
+
+    #[test]
+    fn test_double_negation() {
+        let c = Condition::Not(boxed(Condition::Not(boxed(Condition::Not(boxed(admin()))))));
+        assert_eq!(c.simplify(), Condition::Not(boxed(admin())));
+        let c = Condition::Not(boxed(Condition::Not(boxed(Condition::False))));
+        assert_eq!(c.simplify(), Condition::False);
+    }
+
+    #[test]
+    fn test_dead_branch_pruning() {
+        let children = [
+            Condition::True,
+            admin(),
+            Condition::Not(boxed(Condition::False)),
+        ];
+        assert_eq!(Condition::AllOf(&children).simplify(), admin());
+        assert_eq!(Condition::AnyOf(&children).simplify(), Condition::True);
+
+        let children = [Condition::False, admin(), Condition::MemberOf("staff")];
+        assert_eq!(Condition::AllOf(&children).simplify(), Condition::False);
+        assert_eq!(
+            Condition::AnyOf(&children).simplify(),
+            Condition::Or(boxed(admin()), boxed(Condition::MemberOf("staff")))
+        );
+        assert_eq!(Condition::AnyOf(&[]).simplify(), Condition::False);
+
+        // Untouched slices are kept, not rebuilt
+        let children = [admin(), Condition::MemberOf("staff")];
+        assert_eq!(
+            Condition::AllOf(&children).simplify(),
+            Condition::AllOf(&children)
+        );
+
+        // A pruned branch no longer fails
+        let c = Condition::And(boxed(Condition::False), boxed(Condition::MemberOf("staff")));
+        assert_eq!(c.simplify().evaluate(&[]), Ok(false));
+    }
+
+    #[test]
+    fn test_preserves_meaning() {
+        let levels = [Value::Int(1), Value::Int(2)];
+        let c = Condition::Or(
+            boxed(Condition::And(
+                boxed(Condition::Not(boxed(Condition::Not(boxed(admin()))))),
+                boxed(Condition::In {
+                    attr: "level",
+                    values: &levels,
+                }),
+            )),
+            boxed(Condition::Implies(
+                boxed(Condition::True),
+                boxed(Condition::GreaterThan {
+                    attr: "level",
+                    value: 5,
+                }),
+            )),
+        );
+        let simplified = c.simplify();
+        assert!(simplified.depth() < c.depth());
+        for role in ["admin", "user"] {
+            for level in 0..8 {
+                let ctx = [("role", Value::String(role)), ("level", Value::Int(level))];
+                assert_eq!(simplified.evaluate(&ctx), c.evaluate(&ctx));
+            }
+        }
+    }
+
+    #[test]
+    fn test_deep_tree() {
+        let mut c = admin();
+        for _ in 0..100_000 {
+            c = Condition::Not(boxed(Condition::Not(boxed(c))));
+        }
+        assert_eq!(c.simplify(), admin());
+    }
+}
@@ -0,0 +1,702 @@
+//! SQL row filters.
+//!
+//! Renders a partial-evaluation `Residual` as a parameterized `WHERE`
+//! clause, so list endpoints can let the database return only the rows the
+//! caller may see. Residual attributes become column names; values become
+//! bind parameters and are never spliced into the SQL text.
+//!
+//! Missing attributes in gate0 correspond to `NULL` columns. Negations are
+//! pushed down to the leaves first, so `Equals` on `NULL` is unknown (row
+//! excluded, like gate0's false) and `NotEquals` uses a null-safe comparison
+//! (row included, like gate0's true). `IN` and ordered comparisons on
+//! `NULL` are likewise unknown, and their negations explicitly admit
+//! `NULL`. A CIDR match becomes a range from the network's first to its
+//! last address. A pattern match becomes the dialect's regular expression
+//! operator, which accepts gate0's pattern syntax unchanged; MySQL's
+//! `REGEXP` ignores case under case-insensitive column collations. List
+//! membership expects an array column in PostgreSQL (`= ANY`) and a JSON
+//! array column in MySQL (`JSON_CONTAINS`). Columns
+//! are assumed to hold values of the compared type; gate0's "wrong type
+//! never matches" is not emulated.
+
+use crate::condition::Condition;
+use crate::partial::Residual;
+use crate::value::{Value, ValueBuf};
+
+/// Target SQL dialect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// PostgreSQL: `"ident"`, `$n` placeholders, `IS DISTINCT FROM`.
+    Postgres,
+    /// MySQL: `` `ident` ``, `?` placeholders, `<=>`.
+    MySql,
+}
+
+/// A parameterized `WHERE` clause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlFilter {
+    /// The boolean SQL expression, without the `WHERE` keyword.
+    pub clause: String,
+    /// Bind parameters, in placeholder order.
+    pub params: Vec<ValueBuf>,
+}
+
+impl Residual<'_> {
+    /// Render this residual as a SQL filter.
+    pub fn to_sql_filter(&self, dialect: SqlDialect) -> SqlFilter {
+        let mut filter = SqlFilter {
+            clause: String::new(),
+            params: Vec::new(),
+        };
+        render(&mut filter, dialect, self.condition(), false);
+        filter
+    }
+}
+
+/// Render `cond`, or its negation, in negation normal form.
+///
+/// Recursion is bounded by the residual's depth, which partial evaluation
+/// keeps logarithmic in the rule count.
+fn render(out: &mut SqlFilter, dialect: SqlDialect, cond: &Condition<'_>, negated: bool) {
+    match cond {
+        Condition::True | Condition::False => {
+            let value = matches!(cond, Condition::True) != negated;
+            out.clause.push_str(if value { "TRUE" } else { "FALSE" });
+        }
+        Condition::Equals { attr, value } | Condition::NotEquals { attr, value } => {
+            let equals = matches!(cond, Condition::Equals { .. }) != negated;
+            let column = quote_ident(dialect, attr);
+            let param = bind(out, dialect, value);
+            let text = match (equals, dialect) {
+                (true, _) => format!("{} = {}", column, param),
+                (false, SqlDialect::Postgres) => format!("{} IS DISTINCT FROM {}", column, param),
+                (false, SqlDialect::MySql) => format!("NOT ({} <=> {})", column, param),
+            };
+            out.clause.push_str(&text);
+        }
+        // `LOWER` follows the database's collation, which agrees with
+        // gate0's lowercasing for ASCII but may not beyond it.
+        Condition::EqualsIgnoreCase { attr, value } => {
+            let column = quote_ident(dialect, attr);
+            let param = bind(out, dialect, &Value::String(value));
+            let text = if negated {
+                format!(
+                    "({} IS NULL OR LOWER({}) <> LOWER({}))",
+                    column, column, param
+                )
+            } else {
+                format!("LOWER({}) = LOWER({})", column, param)
+            };
+            out.clause.push_str(&text);
+        }
+        Condition::In { attr, values } | Condition::NotIn { attr, values } => {
+            let member = matches!(cond, Condition::In { .. }) != negated;
+            if values.is_empty() {
+                out.clause.push_str(if member { "FALSE" } else { "TRUE" });
+                return;
+            }
+            let column = quote_ident(dialect, attr);
+            let params: Vec<String> = values.iter().map(|v| bind(out, dialect, v)).collect();
+            let text = if member {
+                format!("{} IN ({})", column, params.join(", "))
+            } else {
+                format!(
+                    "({} IS NULL OR {} NOT IN ({}))",
+                    column,
+                    column,
+                    params.join(", ")
+                )
+            };
+            out.clause.push_str(&text);
+        }
+        Condition::GreaterThan { .. }
+        | Condition::GreaterOrEqual { .. }
+        | Condition::LessThan { .. }
+        | Condition::LessOrEqual { .. } => {
+            if let Some((attr, op, value)) = cond.comparison() {
+                let column = quote_ident(dialect, attr);
+                let param = bind(out, dialect, &Value::Int(value));
+                let text = if negated {
+                    let flipped = match op {
+                        ">" => "<=",
+                        ">=" => "<",
+                        "<" => ">=",
+                        _ => ">",
+                    };
+                    format!("({} IS NULL OR {} {} {})", column, column, flipped, param)
+                } else {
+                    format!("{} {} {}", column, op, param)
+                };
+                out.clause.push_str(&text);
+            }
+        }
+        Condition::Between { attr, min, max } => {
+            let column = quote_ident(dialect, attr);
+            let min = bind(out, dialect, &Value::Int(*min));
+            let max = bind(out, dialect, &Value::Int(*max));
+            let text = if negated {
+                format!(
+                    "({} IS NULL OR {} < {} OR {} > {})",
+                    column, column, min, column, max
+                )
+            } else {
+                format!("({} >= {} AND {} <= {})", column, min, column, max)
+            };
+            out.clause.push_str(&text);
+        }
+        // Bound twice: MySQL placeholders cannot be reused.
+        Condition::HasFlags { attr, mask } => {
+            let column = quote_ident(dialect, attr);
+            let bits = bind(out, dialect, &Value::Int(*mask));
+            let mask = bind(out, dialect, &Value::Int(*mask));
+            let text = if negated {
+                format!(
+                    "({} IS NULL OR ({} & {}) <> {})",
+                    column, column, bits, mask
+                )
+            } else {
+                format!("({} & {}) = {}", column, bits, mask)
+            };
+            out.clause.push_str(&text);
+        }
+        // A network is the range from its first to its last address.
+        Condition::IpInCidr { attr, cidr } => {
+            let column = quote_ident(dialect, attr);
+            let first = bind(out, dialect, &Value::Ip(cidr.network()));
+            let last = bind(out, dialect, &Value::Ip(cidr.last()));
+            let text = if negated {
+                format!(
+                    "({} IS NULL OR {} < {} OR {} > {})",
+                    column, column, first, column, last
+                )
+            } else {
+                format!("({} >= {} AND {} <= {})", column, first, column, last)
+            };
+            out.clause.push_str(&text);
+        }
+        Condition::Matches { attr, pattern } => {
+            let column = quote_ident(dialect, attr);
+            let param = bind(out, dialect, &Value::String(pattern.as_str()));
+            let text = match (negated, dialect) {
+                (false, SqlDialect::Postgres) => format!("{} ~ {}", column, param),
+                (false, SqlDialect::MySql) => format!("{} REGEXP {}", column, param),
+                (true, SqlDialect::Postgres) => {
+                    format!("({} IS NULL OR {} !~ {})", column, column, param)
+                }
+                (true, SqlDialect::MySql) => {
+                    format!("({} IS NULL OR NOT ({} REGEXP {}))", column, column, param)
+                }
+            };
+            out.clause.push_str(&text);
+        }
+        // Both dialects escape LIKE wildcards with `\` by default.
+        Condition::GlobMatches { attr, pattern } => {
+            let column = quote_ident(dialect, attr);
+            let mut like = String::with_capacity(pattern.len());
+            for c in pattern.chars() {
+                match c {
+                    '*' => like.push('%'),
+                    '?' => like.push('_'),
+                    '%' | '_' | '\\' => {
+                        like.push('\\');
+                        like.push(c);
+                    }
+                    c => like.push(c),
+                }
+            }
+            let param = bind(out, dialect, &Value::String(&like));
+            let text = if negated {
+                format!("({} IS NULL OR {} NOT LIKE {})", column, column, param)
+            } else {
+                format!("{} LIKE {}", column, param)
+            };
+            out.clause.push_str(&text);
+        }
+        Condition::AttrContains { attr, value } => {
+            let column = quote_ident(dialect, attr);
+            let param = bind(out, dialect, value);
+            let member = match dialect {
+                SqlDialect::Postgres => format!("{} = ANY({})", param, column),
+                SqlDialect::MySql => format!("JSON_CONTAINS({}, JSON_ARRAY({}))", column, param),
+            };
+            let text = if negated {
+                format!("({} IS NULL OR NOT ({}))", column, member)
+            } else {
+                member
+            };
+            out.clause.push_str(&text);
+        }
+        // Partial evaluation resolves or rejects these.
+        Condition::MemberOf(_)
+        | Condition::AttrIsPrincipal(_)
+        | Condition::TimeOfDayBetween { .. }
+        | Condition::DayOfWeekIn { .. }
+        | Condition::Custom { .. }
+        | Condition::Ref(_) => out.clause.push_str("FALSE"),
+        Condition::Not(inner) => render(out, dialect, inner, !negated),
+        // Evaluation fails on a row without the attribute, so whatever the
+        // polarity the filter leaves such rows out.
+        Condition::Require(attr, inner) => {
+            let column = quote_ident(dialect, attr);
+            out.clause
+                .push_str(&format!("({} IS NOT NULL AND ", column));
+            render(out, dialect, inner, negated);
+            out.clause.push(')');
+        }
+        Condition::And(a, b) | Condition::Or(a, b) => {
+            let is_and = matches!(cond, Condition::And(..)) != negated;
+            out.clause.push('(');
+            render(out, dialect, a, negated);
+            out.clause.push_str(if is_and { " AND " } else { " OR " });
+            render(out, dialect, b, negated);
+            out.clause.push(')');
+        }
+        // `a -> b` is `NOT a OR b`.
+        Condition::Implies(a, b) => {
+            let is_and = negated;
+            out.clause.push('(');
+            render(out, dialect, a, !negated);
+            out.clause.push_str(if is_and { " AND " } else { " OR " });
+            render(out, dialect, b, negated);
+            out.clause.push(')');
+        }
+        // `a XOR b` is `(a AND NOT b) OR (NOT a AND b)`; its negation
+        // `(a AND b) OR (NOT a AND NOT b)`. Each operand is rendered twice.
+        Condition::Xor(a, b) => {
+            out.clause.push_str("((");
+            render(out, dialect, a, false);
+            out.clause.push_str(" AND ");
+            render(out, dialect, b, !negated);
+            out.clause.push_str(") OR (");
+            render(out, dialect, a, true);
+            out.clause.push_str(" AND ");
+            render(out, dialect, b, negated);
+            out.clause.push_str("))");
+        }
+        Condition::AllOf(children) | Condition::AnyOf(children) => {
+            let is_and = matches!(cond, Condition::AllOf(_)) != negated;
+            if children.is_empty() {
+                out.clause.push_str(if is_and { "TRUE" } else { "FALSE" });
+                return;
+            }
+            out.clause.push('(');
+            for (i, child) in children.iter().enumerate() {
+                if i > 0 {
+                    out.clause.push_str(if is_and { " AND " } else { " OR " });
+                }
+                render(out, dialect, child, negated);
+            }
+            out.clause.push(')');
+        }
+    }
+}
+
+fn bind(out: &mut SqlFilter, dialect: SqlDialect, value: &Value<'_>) -> String {
+    out.params.push(ValueBuf::from(value));
+    match dialect {
+        SqlDialect::Postgres => format!("${}", out.params.len()),
+        SqlDialect::MySql => "?".to_string(),
+    }
+}
+
+fn quote_ident(dialect: SqlDialect, ident: &str) -> String {
+    let q = match dialect {
+        SqlDialect::Postgres => '"',
+        SqlDialect::MySql => '`',
+    };
+    let mut out = String::with_capacity(ident.len() + 2);
+    out.push(q);
+    for c in ident.chars() {
+        if c == q {
+            out.push(q);
+        }
+        out.push(c);
+    }
+    out.push(q);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::PartialRequest;
+    use crate::policy::{Policy, Rule};
+    use crate::target::{Matcher, Target};
+    use crate::types::{Effect, ReasonCode};
+
+    fn residual_policy() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Or(
+                    Box::new(Condition::Equals {
+                        attr: "owner",
+                        value: Value::String("alice"),
+                    }),
+                    Box::new(Condition::Equals {
+                        attr: "public",
+                        value: Value::Bool(true),
+                    }),
+                )),
+                ReasonCode(1),
+            ))
+            .rule(Rule::deny(
+                Target {
+                    principal: Matcher::Any,
+                    action: Matcher::Any,
+                    resource: Matcher::OneOf(&["vault", "keys"]),
+                    environment: Matcher::Any,
+                },
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_postgres_filter() {
+        let policy = residual_policy();
+        let request = PartialRequest::new("alice", "read")
+            .with_resource_attrs(&["owner", "public"])
+            .with_resource_attr("id");
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(
+            filter.clause,
+            "((\"owner\" = $1 OR \"public\" = $2) AND \
+             (\"id\" IS DISTINCT FROM $3 AND \"id\" IS DISTINCT FROM $4))"
+        );
+        assert_eq!(
+            filter.params,
+            vec![
+                ValueBuf::String("alice".to_string()),
+                ValueBuf::Bool(true),
+                ValueBuf::String("vault".to_string()),
+                ValueBuf::String("keys".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mysql_filter_and_quoting() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::NotEquals {
+                    attr: "odd`col",
+                    value: Value::Int(0),
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("bob", "list").with_resource_attrs(&["odd`col"]);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::MySql);
+        assert_eq!(filter.clause, "NOT (`odd``col` <=> ?)");
+        assert_eq!(filter.params, vec![ValueBuf::Int(0)]);
+
+        let nothing = Policy::new(vec![]).unwrap();
+        let filter = nothing
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::MySql);
+        assert_eq!(filter.clause, "FALSE");
+        assert!(filter.params.is_empty());
+    }
+
+    #[test]
+    fn test_equals_ignore_case() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::EqualsIgnoreCase {
+                    attr: "owner",
+                    value: "Alice",
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["owner"]);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(filter.clause, "LOWER(\"owner\") = LOWER($1)");
+        assert_eq!(filter.params, vec![ValueBuf::String("Alice".to_string())]);
+    }
+
+    #[test]
+    fn test_ordered_comparisons() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::And(
+                    Box::new(Condition::LessOrEqual {
+                        attr: "size",
+                        value: 100,
+                    }),
+                    Box::new(Condition::GreaterOrEqual {
+                        attr: "clearance",
+                        value: 3,
+                    }),
+                )),
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::LessThan {
+                    attr: "size",
+                    value: 10,
+                }),
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+        let ctx = [("clearance", Value::Int(5))];
+        let request = PartialRequest::new("alice", "read")
+            .with_resource_attrs(&["size"])
+            .with_context(&ctx);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::Postgres);
+        // A row with no size is not allowed, but it is not denied either
+        assert_eq!(
+            filter.clause,
+            "(\"size\" <= $1 AND (\"size\" IS NULL OR \"size\" >= $2))"
+        );
+        assert_eq!(filter.params, vec![ValueBuf::Int(100), ValueBuf::Int(10)]);
+    }
+
+    #[test]
+    fn test_between() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Between {
+                    attr: "risk",
+                    min: 0,
+                    max: 40,
+                }),
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::Between {
+                    attr: "risk",
+                    min: 30,
+                    max: 35,
+                }),
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["risk"]);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(
+            filter.clause,
+            "((\"risk\" >= $1 AND \"risk\" <= $2) AND \
+             (\"risk\" IS NULL OR \"risk\" < $3 OR \"risk\" > $4))"
+        );
+        let bounds = [0, 40, 30, 35].map(ValueBuf::Int);
+        assert_eq!(filter.params, bounds);
+    }
+
+    #[test]
+    fn test_set_membership() {
+        let kinds = [Value::String("pdf"), Value::String("doc")];
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::In {
+                    attr: "kind",
+                    values: &kinds,
+                }),
+                ReasonCode(1),
+            ))
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::In {
+                    attr: "owner",
+                    values: &[Value::String("mallory")],
+                }),
+                ReasonCode(2),
+            ))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["kind", "owner"]);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::MySql);
+        assert_eq!(
+            filter.clause,
+            "(`kind` IN (?, ?) AND (`owner` IS NULL OR `owner` NOT IN (?)))"
+        );
+        assert_eq!(filter.params.len(), 3);
+    }
+
+    #[test]
+    fn test_implies_and_xor() {
+        let flag = |attr| {
+            Box::new(Condition::Equals {
+                attr,
+                value: Value::Bool(true),
+            })
+        };
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::And(
+                    Box::new(Condition::Implies(
+                        flag("archived"),
+                        Box::new(Condition::Equals {
+                            attr: "role",
+                            value: Value::String("admin"),
+                        }),
+                    )),
+                    Box::new(Condition::Xor(flag("shared"), flag("public"))),
+                )),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let known = [("role", Value::String("user"))];
+        let request = PartialRequest::new("alice", "read")
+            .with_context(&known)
+            .with_resource_attrs(&["archived", "shared", "public"]);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(
+            filter.clause,
+            "(\"archived\" IS DISTINCT FROM $1 AND \
+             ((\"shared\" = $2 AND \"public\" IS DISTINCT FROM $3) OR \
+             (\"shared\" IS DISTINCT FROM $4 AND \"public\" = $5)))"
+        );
+        assert_eq!(filter.params.len(), 5);
+    }
+
+    #[test]
+    fn test_pattern_match() {
+        let pattern = crate::pattern::Pattern::parse("^svc-").unwrap();
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::Matches {
+                    attr: "owner",
+                    pattern,
+                }),
+                ReasonCode(1),
+            ))
+            .rule(Rule::allow(Target::any(), ReasonCode(2)))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["owner"]);
+        let residual = policy.partial_evaluate(&request).unwrap();
+        let filter = residual.to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(filter.clause, "(\"owner\" IS NULL OR \"owner\" !~ $1)");
+        assert_eq!(filter.params, vec![ValueBuf::String("^svc-".to_string())]);
+        let filter = residual.to_sql_filter(SqlDialect::MySql);
+        assert_eq!(filter.clause, "(`owner` IS NULL OR NOT (`owner` REGEXP ?))");
+    }
+
+    #[test]
+    fn test_require() {
+        let owner = Condition::Equals {
+            attr: "owner",
+            value: Value::String("alice"),
+        };
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::Require("owner", Box::new(owner))),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["owner"]);
+        let residual = policy.partial_evaluate(&request).unwrap();
+        let filter = residual.to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(filter.clause, "(\"owner\" IS NOT NULL AND \"owner\" = $1)");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Allow,
+                Target::any(),
+                Some(Condition::GlobMatches {
+                    attr: "project",
+                    pattern: "proj_?-*-prod",
+                }),
+                ReasonCode(1),
+            ))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["project"]);
+        let residual = policy.partial_evaluate(&request).unwrap();
+        let filter = residual.to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(filter.clause, "\"project\" LIKE $1");
+        assert_eq!(
+            filter.params,
+            vec![ValueBuf::String("proj\\__-%-prod".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_cidr_range() {
+        let policy = Policy::builder()
+            .rule(Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::IpInCidr {
+                    attr: "origin",
+                    cidr: crate::cidr::Cidr::parse("192.168.0.0/16").unwrap(),
+                }),
+                ReasonCode(1),
+            ))
+            .rule(Rule::allow(Target::any(), ReasonCode(2)))
+            .build()
+            .unwrap();
+        let request = PartialRequest::new("alice", "read").with_resource_attrs(&["origin"]);
+        let filter = policy
+            .partial_evaluate(&request)
+            .unwrap()
+            .to_sql_filter(SqlDialect::Postgres);
+        assert_eq!(
+            filter.clause,
+            "(\"origin\" IS NULL OR \"origin\" < $1 OR \"origin\" > $2)"
+        );
+        assert_eq!(
+            filter.params,
+            vec![
+                ValueBuf::Ip("192.168.0.0".parse().unwrap()),
+                ValueBuf::Ip("192.168.255.255".parse().unwrap()),
+            ]
+        );
+    }
+}
@@ -1,7 +1,11 @@
 //! Evaluation statistics for observable bound usage.
 //!
 //! This module provides the `EvaluationStats` struct which tracks
-//! how close an evaluation got to its configured limits.
+//! how close an evaluation got to its configured limits, and
+//! `AggregatedStats`, which folds many of them into one summary per
+//! scrape interval.
+
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Observable bound usage during policy evaluation.
 ///
@@ -14,21 +18,26 @@
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct EvaluationStats {
     /// Number of rules checked before reaching a decision.
-    /// 
+    ///
     /// For deny-overrides semantics, this may be less than the total
     /// rule count if an early deny is found.
     pub rules_checked: u16,
 
     /// Maximum stack depth reached during condition evaluation.
-    /// 
+    ///
     /// Compare against `ABSOLUTE_MAX_CONDITION_DEPTH` to see how
     /// close you got to the limit.
     pub max_depth_reached: u8,
 
     /// Total number of condition nodes evaluated.
-    /// 
+    ///
     /// Includes all And, Or, Not, Equals, NotEquals nodes visited.
     pub condition_evals: u16,
+
+    /// True if a break-glass rule decided this evaluation.
+    ///
+    /// Emergency access should be alerted on, not just logged.
+    pub break_glass: bool,
 }
 
 impl EvaluationStats {
@@ -39,6 +48,7 @@ impl EvaluationStats {
             rules_checked: 0,
             max_depth_reached: 0,
             condition_evals: 0,
+            break_glass: false,
         }
     }
 
@@ -63,6 +73,184 @@ impl EvaluationStats {
     }
 }
 
+/// Number of histogram buckets: one for zero and one per power of two up
+/// to `u16::MAX`.
+pub const STATS_BUCKETS: usize = 17;
+
+/// Bucket `0` holds 0; bucket `i > 0` holds `2^(i-1) ..= 2^i - 1`.
+fn bucket(value: u16) -> usize {
+    (u16::BITS - value.leading_zeros()) as usize
+}
+
+/// A power-of-two histogram of `u16` samples.
+#[derive(Debug, Default)]
+struct AtomicHistogram([AtomicU64; STATS_BUCKETS]);
+
+impl AtomicHistogram {
+    fn record(&self, value: u16) {
+        self.0[bucket(value)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn merge(&self, other: &[u64; STATS_BUCKETS]) {
+        for (slot, count) in self.0.iter().zip(other) {
+            slot.fetch_add(*count, Ordering::Relaxed);
+        }
+    }
+
+    fn read(&self, reset: bool) -> [u64; STATS_BUCKETS] {
+        let mut out = [0; STATS_BUCKETS];
+        for (slot, count) in self.0.iter().zip(&mut out) {
+            *count = if reset {
+                slot.swap(0, Ordering::Relaxed)
+            } else {
+                slot.load(Ordering::Relaxed)
+            };
+        }
+        out
+    }
+}
+
+/// Lock-free totals, maxima, and histograms over many evaluations.
+///
+/// Share one instance (e.g. in a `static` or an `Arc`) across request
+/// threads, `record` each evaluation's stats, and call `take` once per
+/// scrape interval. Counters are independent atomics, so a snapshot taken
+/// while other threads record may be off by the in-flight evaluations.
+#[derive(Debug, Default)]
+pub struct AggregatedStats {
+    evaluations: AtomicU64,
+    break_glass: AtomicU64,
+    rules_checked: AtomicU64,
+    condition_evals: AtomicU64,
+    max_rules_checked: AtomicU64,
+    max_depth_reached: AtomicU64,
+    max_condition_evals: AtomicU64,
+    rules_histogram: AtomicHistogram,
+    condition_histogram: AtomicHistogram,
+}
+
+impl AggregatedStats {
+    /// An empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold in one evaluation.
+    pub fn record(&self, stats: &EvaluationStats) {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.break_glass
+            .fetch_add(stats.break_glass as u64, Ordering::Relaxed);
+        self.rules_checked
+            .fetch_add(stats.rules_checked as u64, Ordering::Relaxed);
+        self.condition_evals
+            .fetch_add(stats.condition_evals as u64, Ordering::Relaxed);
+        self.max_rules_checked
+            .fetch_max(stats.rules_checked as u64, Ordering::Relaxed);
+        self.max_depth_reached
+            .fetch_max(stats.max_depth_reached as u64, Ordering::Relaxed);
+        self.max_condition_evals
+            .fetch_max(stats.condition_evals as u64, Ordering::Relaxed);
+        self.rules_histogram.record(stats.rules_checked);
+        self.condition_histogram.record(stats.condition_evals);
+    }
+
+    /// Fold in a snapshot, e.g. one taken from a per-thread accumulator.
+    pub fn merge(&self, other: &StatsSnapshot) {
+        self.evaluations
+            .fetch_add(other.evaluations, Ordering::Relaxed);
+        self.break_glass
+            .fetch_add(other.break_glass, Ordering::Relaxed);
+        self.rules_checked
+            .fetch_add(other.rules_checked, Ordering::Relaxed);
+        self.condition_evals
+            .fetch_add(other.condition_evals, Ordering::Relaxed);
+        self.max_rules_checked
+            .fetch_max(other.max_rules_checked, Ordering::Relaxed);
+        self.max_depth_reached
+            .fetch_max(other.max_depth_reached, Ordering::Relaxed);
+        self.max_condition_evals
+            .fetch_max(other.max_condition_evals, Ordering::Relaxed);
+        self.rules_histogram.merge(&other.rules_histogram);
+        self.condition_histogram.merge(&other.condition_histogram);
+    }
+
+    /// Read the current totals.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.read(false)
+    }
+
+    /// Read the current totals and reset them to zero.
+    pub fn take(&self) -> StatsSnapshot {
+        self.read(true)
+    }
+
+    fn read(&self, reset: bool) -> StatsSnapshot {
+        let get = |counter: &AtomicU64| {
+            if reset {
+                counter.swap(0, Ordering::Relaxed)
+            } else {
+                counter.load(Ordering::Relaxed)
+            }
+        };
+        StatsSnapshot {
+            evaluations: get(&self.evaluations),
+            break_glass: get(&self.break_glass),
+            rules_checked: get(&self.rules_checked),
+            condition_evals: get(&self.condition_evals),
+            max_rules_checked: get(&self.max_rules_checked),
+            max_depth_reached: get(&self.max_depth_reached),
+            max_condition_evals: get(&self.max_condition_evals),
+            rules_histogram: self.rules_histogram.read(reset),
+            condition_histogram: self.condition_histogram.read(reset),
+        }
+    }
+}
+
+/// A point-in-time copy of an `AggregatedStats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Evaluations recorded.
+    pub evaluations: u64,
+    /// Evaluations decided by a break-glass rule.
+    pub break_glass: u64,
+    /// Total rules checked.
+    pub rules_checked: u64,
+    /// Total conditions evaluated.
+    pub condition_evals: u64,
+    /// Most rules checked by one evaluation.
+    pub max_rules_checked: u64,
+    /// Deepest condition stack reached by one evaluation.
+    pub max_depth_reached: u64,
+    /// Most conditions evaluated by one evaluation.
+    pub max_condition_evals: u64,
+    /// Evaluations per `rules_checked` bucket (see `percentile`).
+    pub rules_histogram: [u64; STATS_BUCKETS],
+    /// Evaluations per `condition_evals` bucket (see `percentile`).
+    pub condition_histogram: [u64; STATS_BUCKETS],
+}
+
+impl StatsSnapshot {
+    /// An upper bound on the `p`-th percentile (0.0 to 1.0) of a histogram
+    /// from this snapshot, or 0 if it is empty.
+    ///
+    /// Buckets double in width, so the bound is within a factor of two.
+    pub fn percentile(histogram: &[u64; STATS_BUCKETS], p: f64) -> u64 {
+        let total: u64 = histogram.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        let rank = ((p.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return (1u64 << i) - 1;
+            }
+        }
+        u16::MAX as u64
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,7 +266,7 @@ mod tests {
     #[test]
     fn test_stats_increment() {
         let mut stats = EvaluationStats::new();
-        
+
         stats.inc_rules();
         stats.inc_rules();
         assert_eq!(stats.rules_checked, 2);
@@ -91,4 +279,34 @@ mod tests {
         stats.inc_condition_evals();
         assert_eq!(stats.condition_evals, 1);
     }
+
+    #[test]
+    fn test_aggregated_stats() {
+        let totals = AggregatedStats::new();
+        for rules_checked in [1, 2, 3, 40] {
+            totals.record(&EvaluationStats {
+                rules_checked,
+                condition_evals: 1,
+                ..EvaluationStats::new()
+            });
+        }
+        let thread_local = AggregatedStats::new();
+        thread_local.record(&EvaluationStats {
+            break_glass: true,
+            max_depth_reached: 4,
+            ..EvaluationStats::new()
+        });
+        totals.merge(&thread_local.take());
+        assert_eq!(thread_local.snapshot(), StatsSnapshot::default());
+
+        let snap = totals.take();
+        assert_eq!((snap.evaluations, snap.break_glass), (5, 1));
+        assert_eq!((snap.rules_checked, snap.max_rules_checked), (46, 40));
+        assert_eq!(snap.max_depth_reached, 4);
+        assert_eq!(snap.rules_histogram[..3], [1, 1, 2]);
+        assert_eq!(StatsSnapshot::percentile(&snap.rules_histogram, 0.4), 1);
+        assert_eq!(StatsSnapshot::percentile(&snap.rules_histogram, 0.8), 3);
+        assert_eq!(StatsSnapshot::percentile(&snap.rules_histogram, 1.0), 63);
+        assert_eq!(totals.snapshot().evaluations, 0);
+    }
 }
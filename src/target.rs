@@ -1,5 +1,6 @@
 //! No Prefix matcher - intentionally omitted to avoid footguns.
 
+use crate::collation::Collation;
 use crate::error::PolicyError;
 
 /// A target specifies which requests a rule applies to.
@@ -11,6 +12,11 @@ pub struct Target<'a> {
     pub action: Matcher<'a>,
     /// Matcher for the resource.
     pub resource: Matcher<'a>,
+    /// Matcher for the request's environment, e.g. `Exact("prod")`.
+    ///
+    /// `Any` also matches requests that name no environment; any other
+    /// matcher never does.
+    pub environment: Matcher<'a>,
 }
 
 impl<'a> Target<'a> {
@@ -20,18 +26,46 @@ impl<'a> Target<'a> {
             principal: Matcher::Any,
             action: Matcher::Any,
             resource: Matcher::Any,
+            environment: Matcher::Any,
         }
     }
 
+    /// Restrict this target to the given environments.
+    pub fn in_environment(mut self, environment: Matcher<'a>) -> Self {
+        self.environment = environment;
+        self
+    }
+
     /// Check if this target matches the given request fields.
+    ///
+    /// The environment is checked separately, by `matches_environment`.
     pub fn matches(&self, principal: &str, action: &str, resource: &str) -> bool {
         self.principal.matches(principal)
             && self.action.matches(action)
             && self.resource.matches(resource)
     }
+
+    /// Check if this target applies in the request's environment, if any.
+    pub fn matches_environment(&self, environment: Option<&str>) -> bool {
+        self.matches_environment_with(environment, Collation::Binary)
+    }
+
+    /// Like `matches_environment`, comparing names under `collation`.
+    pub fn matches_environment_with(
+        &self,
+        environment: Option<&str>,
+        collation: Collation,
+    ) -> bool {
+        match (&self.environment, environment) {
+            (Matcher::Any, _) => true,
+            (matcher, Some(environment)) => matcher.matches_with(environment, collation),
+            (_, None) => false,
+        }
+    }
 }
 
-/// A matcher for a single field (principal, action, or resource).
+/// A matcher for a single field (principal, action, resource, or
+/// environment).
 #[derive(Debug, Clone, PartialEq)]
 pub enum Matcher<'a> {
     /// Matches any value.
@@ -52,6 +86,15 @@ impl<'a> Matcher<'a> {
         }
     }
 
+    /// Like `matches`, comparing names under `collation`.
+    pub fn matches_with(&self, value: &str, collation: Collation) -> bool {
+        match self {
+            Matcher::Any => true,
+            Matcher::Exact(expected) => collation.eq(value, expected),
+            Matcher::OneOf(options) => options.iter().any(|o| collation.eq(value, o)),
+        }
+    }
+
     /// Validate that this matcher does not exceed the maximum options
     /// and that all strings are within length limits.
     pub fn validate(&self, max_options: usize, max_string_len: usize) -> Result<(), PolicyError> {
@@ -140,6 +183,7 @@ mod tests {
             principal: Matcher::Exact("alice"),
             action: Matcher::OneOf(actions),
             resource: Matcher::Any,
+            environment: Matcher::Any,
         };
 
         assert!(t.matches("alice", "read", "anything"));
@@ -154,6 +198,7 @@ mod tests {
             principal: Matcher::Exact("service-account"),
             action: Matcher::Exact("invoke"),
             resource: Matcher::Exact("api/v1/health"),
+            environment: Matcher::Any,
         };
 
         assert!(t.matches("service-account", "invoke", "api/v1/health"));
@@ -161,6 +206,18 @@ mod tests {
         assert!(!t.matches("user", "invoke", "api/v1/health"));
     }
 
+    #[test]
+    fn test_target_environment() {
+        let any = Target::any();
+        assert!(any.matches_environment(None));
+        assert!(any.matches_environment(Some("prod")));
+
+        let prod = Target::any().in_environment(Matcher::Exact("prod"));
+        assert!(prod.matches_environment(Some("prod")));
+        assert!(!prod.matches_environment(Some("staging")));
+        assert!(!prod.matches_environment(None));
+    }
+
     #[test]
     fn test_matcher_too_many_options() {
         let options = vec!["a", "b", "c"];
@@ -0,0 +1,172 @@
+//! Tenant isolation wrapper.
+//!
+//! `TenantScopedPolicy` enforces that the caller's tenant equals the
+//! resource's tenant on every evaluation, independent of the rules inside
+//! the wrapped policy. A rule author cannot accidentally grant cross-tenant
+//! access, because the tenant check behaves like an implicit first Deny rule.
+
+use crate::error::PolicyError;
+use crate::policy::Policy;
+use crate::types::{Decision, ReasonCode, Request};
+use crate::value::Value;
+
+/// Default context attribute holding the caller's tenant.
+pub const DEFAULT_TENANT_ATTR: &str = "tenant_id";
+
+/// Where the resource's tenant comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TenantBinding<'a> {
+    /// The resource's tenant is the named context attribute.
+    Attribute(&'a str),
+    /// The resource name starts with `<tenant><separator>`, e.g. `acme/doc-1`.
+    Prefix(char),
+}
+
+/// A policy wrapper that denies any request whose caller and resource
+/// tenants differ.
+///
+/// Missing, empty, or mismatched tenant information is denied
+/// (fail-closed).
+#[derive(Debug)]
+pub struct TenantScopedPolicy<'a> {
+    policy: Policy<'a>,
+    tenant_attr: &'a str,
+    binding: TenantBinding<'a>,
+    deny_reason: ReasonCode,
+}
+
+impl<'a> TenantScopedPolicy<'a> {
+    /// Wrap a policy. The caller's tenant is read from `tenant_id`.
+    pub fn new(policy: Policy<'a>, binding: TenantBinding<'a>, deny_reason: ReasonCode) -> Self {
+        TenantScopedPolicy {
+            policy,
+            tenant_attr: DEFAULT_TENANT_ATTR,
+            binding,
+            deny_reason,
+        }
+    }
+
+    /// Read the caller's tenant from a different context attribute.
+    pub fn with_tenant_attr(mut self, attr: &'a str) -> Self {
+        self.tenant_attr = attr;
+        self
+    }
+
+    /// Get the wrapped policy.
+    pub fn policy(&self) -> &Policy<'a> {
+        &self.policy
+    }
+
+    /// Get the reason code returned for cross-tenant requests.
+    pub fn deny_reason(&self) -> ReasonCode {
+        self.deny_reason
+    }
+
+    /// Evaluate the request.
+    ///
+    /// Malformed requests are rejected exactly as by `Policy::evaluate`.
+    /// Otherwise a tenant mismatch returns Deny with the configured reason,
+    /// overriding whatever the wrapped policy decided.
+    pub fn evaluate(&self, request: &Request<'_>) -> Result<Decision, PolicyError> {
+        let decision = self.policy.evaluate(request)?;
+        if self.same_tenant(request) {
+            Ok(decision)
+        } else {
+            Ok(Decision::deny(self.deny_reason))
+        }
+    }
+
+    /// Returns `true` if the request's caller and resource tenants match.
+    ///
+    /// An empty tenant matches nothing, not even another empty tenant.
+    pub fn same_tenant(&self, request: &Request<'_>) -> bool {
+        let caller = match request.get_attr(self.tenant_attr) {
+            Some(Value::String("")) | Some(Value::Secret([])) | None => return false,
+            Some(v) => v,
+        };
+        match self.binding {
+            TenantBinding::Attribute(attr) => request.get_attr(attr) == Some(caller),
+            TenantBinding::Prefix(separator) => {
+                match (caller, request.resource.split_once(separator)) {
+                    (Value::String(tenant), Some((prefix, _))) => prefix == *tenant,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+    use crate::target::Target;
+
+    const ALLOW_ALL: ReasonCode = ReasonCode(1);
+    const CROSS_TENANT: ReasonCode = ReasonCode(403);
+
+    fn allow_all() -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(Target::any(), ALLOW_ALL))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_prefix_binding() {
+        let scoped = TenantScopedPolicy::new(allow_all(), TenantBinding::Prefix('/'), CROSS_TENANT);
+        let ctx: &[(&str, Value)] = &[("tenant_id", Value::String("acme"))];
+
+        let same = Request::with_context("alice", "read", "acme/doc-1", ctx);
+        assert_eq!(scoped.evaluate(&same).unwrap(), Decision::allow(ALLOW_ALL));
+
+        let other = Request::with_context("alice", "read", "globex/doc-1", ctx);
+        assert_eq!(
+            scoped.evaluate(&other).unwrap(),
+            Decision::deny(CROSS_TENANT)
+        );
+
+        // No separator means no tenant
+        let bare = Request::with_context("alice", "read", "acme", ctx);
+        assert!(scoped.evaluate(&bare).unwrap().is_deny());
+
+        // Missing caller tenant is denied
+        let anonymous = Request::new("alice", "read", "acme/doc-1");
+        assert!(scoped.evaluate(&anonymous).unwrap().is_deny());
+
+        // So is an empty one, even on a resource with an empty prefix
+        let ctx: &[(&str, Value)] = &[("tenant_id", Value::String(""))];
+        let rootless = Request::with_context("alice", "read", "/doc-1", ctx);
+        assert!(scoped.evaluate(&rootless).unwrap().is_deny());
+    }
+
+    #[test]
+    fn test_attribute_binding() {
+        let scoped = TenantScopedPolicy::new(
+            allow_all(),
+            TenantBinding::Attribute("resource_tenant"),
+            CROSS_TENANT,
+        )
+        .with_tenant_attr("org");
+
+        let same: &[(&str, Value)] = &[("org", Value::Int(7)), ("resource_tenant", Value::Int(7))];
+        let request = Request::with_context("alice", "read", "doc", same);
+        assert!(scoped.evaluate(&request).unwrap().is_allow());
+
+        let other: &[(&str, Value)] = &[("org", Value::Int(7)), ("resource_tenant", Value::Int(8))];
+        let request = Request::with_context("alice", "read", "doc", other);
+        assert_eq!(scoped.evaluate(&request).unwrap().reason, CROSS_TENANT);
+
+        let missing: &[(&str, Value)] = &[("org", Value::Int(7))];
+        let request = Request::with_context("alice", "read", "doc", missing);
+        assert!(scoped.evaluate(&request).unwrap().is_deny());
+
+        // Two empty tenants are not the same tenant
+        let empty: &[(&str, Value)] = &[
+            ("org", Value::String("")),
+            ("resource_tenant", Value::String("")),
+        ];
+        let request = Request::with_context("alice", "read", "doc", empty);
+        assert_eq!(scoped.evaluate(&request).unwrap().reason, CROSS_TENANT);
+    }
+}
@@ -0,0 +1,468 @@
+//! Golden decision snapshot helpers for policy regression tests.
+//!
+//! Policy authors define a corpus of named requests, record the decisions a
+//! policy makes for them into a plain-text snapshot file, and then fail the
+//! test suite whenever a policy edit changes any recorded decision.
+//!
+//! The snapshot format is one line per case, sorted by the corpus order:
+//!
+//! ```text
+//! # gate0 decision snapshot v1
+//! admin writes secret = allow 1
+//! guest writes secret = deny 0
+//! oversized context = error context exceeds maximum attribute count of 2, got 3
+//! ```
+//!
+//! Set `GATE0_UPDATE_SNAPSHOTS=1` to record a new snapshot file or accept
+//! intentional changes. Without it, a missing file is a failure, so a
+//! deleted or misnamed snapshot cannot pass silently.
+//!
+//! # Example
+//!
+//! ```
+//! use gate0::testkit::{Corpus, Snapshot};
+//! use gate0::{Policy, ReasonCode, Request, Rule, Target};
+//!
+//! let policy = Policy::builder()
+//!     .rule(Rule::allow(Target::any(), ReasonCode(1)))
+//!     .build()
+//!     .unwrap();
+//!
+//! let corpus = Corpus::new().case("anyone reads", Request::new("alice", "read", "doc"));
+//! let snapshot = Snapshot::record(&policy, &corpus);
+//!
+//! assert_eq!(snapshot.to_text(), "# gate0 decision snapshot v1\nanyone reads = allow 1\n");
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use crate::error::PolicyError;
+use crate::obligation::Obligation;
+use crate::policy::Policy;
+use crate::types::{Decision, Effect, Request};
+
+/// Header written at the top of every snapshot file.
+const SNAPSHOT_HEADER: &str = "# gate0 decision snapshot v1";
+
+/// Environment variable that switches `assert_snapshot` into update mode.
+pub const UPDATE_ENV_VAR: &str = "GATE0_UPDATE_SNAPSHOTS";
+
+/// A named request in a test corpus.
+#[derive(Debug, Clone)]
+pub struct Case<'a> {
+    /// Human-readable case name. Must be unique within a corpus.
+    pub name: &'a str,
+    /// The request to evaluate.
+    pub request: Request<'a>,
+}
+
+/// An ordered collection of named requests.
+#[derive(Debug, Clone, Default)]
+pub struct Corpus<'a> {
+    cases: Vec<Case<'a>>,
+}
+
+impl<'a> Corpus<'a> {
+    /// Create an empty corpus.
+    pub fn new() -> Self {
+        Corpus { cases: Vec::new() }
+    }
+
+    /// Add a named case to the corpus.
+    pub fn case(mut self, name: &'a str, request: Request<'a>) -> Self {
+        self.cases.push(Case { name, request });
+        self
+    }
+
+    /// Get the cases in declared order.
+    pub fn cases(&self) -> &[Case<'a>] {
+        &self.cases
+    }
+}
+
+/// A single recorded outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    /// The case name.
+    pub name: String,
+    /// The rendered outcome, e.g. `allow 3` or `error <message>`.
+    pub outcome: String,
+}
+
+/// A recorded set of decisions for a corpus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    entries: Vec<SnapshotEntry>,
+}
+
+/// A single difference between an expected and an actual snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotChange {
+    /// The case exists in both snapshots but its outcome differs.
+    Changed {
+        /// The case name.
+        name: String,
+        /// The recorded outcome.
+        expected: String,
+        /// The outcome produced by the current policy.
+        actual: String,
+    },
+    /// The case was recorded but is no longer in the corpus.
+    Removed {
+        /// The case name.
+        name: String,
+    },
+    /// The case is in the corpus but was never recorded.
+    Added {
+        /// The case name.
+        name: String,
+    },
+}
+
+impl fmt::Display for SnapshotChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotChange::Changed {
+                name,
+                expected,
+                actual,
+            } => write!(f, "~ {}: {} -> {}", name, expected, actual),
+            SnapshotChange::Removed { name } => write!(f, "- {}", name),
+            SnapshotChange::Added { name } => write!(f, "+ {}", name),
+        }
+    }
+}
+
+/// Errors from reading or comparing snapshot files.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot file could not be read or written.
+    Io(std::io::Error),
+    /// The snapshot file does not exist and update mode is off.
+    Missing,
+    /// A line in the snapshot file is malformed.
+    Parse {
+        /// 1-based line number.
+        line: usize,
+    },
+    /// The policy's decisions differ from the recorded snapshot.
+    Mismatch(Vec<SnapshotChange>),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot io error: {}", e),
+            SnapshotError::Missing => {
+                write!(
+                    f,
+                    "snapshot file missing; re-run with {}=1 to record it",
+                    UPDATE_ENV_VAR
+                )
+            }
+            SnapshotError::Parse { line } => {
+                write!(f, "malformed snapshot line {}", line)
+            }
+            SnapshotError::Mismatch(changes) => {
+                writeln!(f, "{} decision(s) changed:", changes.len())?;
+                for change in changes {
+                    writeln!(f, "  {}", change)?;
+                }
+                write!(f, "re-run with {}=1 to accept", UPDATE_ENV_VAR)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl Snapshot {
+    /// Evaluate every case in the corpus and record the outcomes.
+    pub fn record(policy: &Policy<'_>, corpus: &Corpus<'_>) -> Self {
+        let entries = corpus
+            .cases()
+            .iter()
+            .map(|case| SnapshotEntry {
+                name: case.name.to_string(),
+                outcome: render_outcome(&policy.evaluate(&case.request)),
+            })
+            .collect();
+        Snapshot { entries }
+    }
+
+    /// Get the recorded entries in corpus order.
+    pub fn entries(&self) -> &[SnapshotEntry] {
+        &self.entries
+    }
+
+    /// Render this snapshot in the text file format.
+    pub fn to_text(&self) -> String {
+        let mut out = String::from(SNAPSHOT_HEADER);
+        out.push('\n');
+        for entry in &self.entries {
+            out.push_str(&entry.name);
+            out.push_str(" = ");
+            out.push_str(&entry.outcome);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parse a snapshot from the text file format.
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Result<Self, SnapshotError> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Split on the last separator so names may contain " = ".
+            let (name, outcome) = line
+                .rsplit_once(" = ")
+                .ok_or(SnapshotError::Parse { line: i + 1 })?;
+            entries.push(SnapshotEntry {
+                name: name.to_string(),
+                outcome: outcome.to_string(),
+            });
+        }
+        Ok(Snapshot { entries })
+    }
+
+    /// Compare this (expected) snapshot against an actual one.
+    ///
+    /// Changes are reported in expected order, followed by added cases.
+    pub fn diff(&self, actual: &Snapshot) -> Vec<SnapshotChange> {
+        let mut changes = Vec::new();
+        for expected in &self.entries {
+            match actual.entries.iter().find(|e| e.name == expected.name) {
+                Some(found) if found.outcome != expected.outcome => {
+                    changes.push(SnapshotChange::Changed {
+                        name: expected.name.clone(),
+                        expected: expected.outcome.clone(),
+                        actual: found.outcome.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => changes.push(SnapshotChange::Removed {
+                    name: expected.name.clone(),
+                }),
+            }
+        }
+        for found in &actual.entries {
+            if !self.entries.iter().any(|e| e.name == found.name) {
+                changes.push(SnapshotChange::Added {
+                    name: found.name.clone(),
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// Render an evaluation outcome in the snapshot format.
+pub fn render_outcome(outcome: &Result<Decision, PolicyError>) -> String {
+    match outcome {
+        Ok(decision) => {
+            let effect = match decision.effect {
+                Effect::Allow => "allow".to_string(),
+                Effect::Deny => "deny".to_string(),
+                Effect::Challenge(method) => format!("challenge:{}", method),
+                Effect::Indeterminate => "indeterminate".to_string(),
+            };
+            let mut out = format!("{} {}", effect, decision.reason.value());
+            if let Some(ttl) = decision.cache_ttl {
+                out.push_str(&format!(" ttl={}", ttl));
+            }
+            if decision.break_glass {
+                out.push_str(" break-glass");
+            }
+            for obligation in decision.obligations.iter() {
+                match obligation {
+                    Obligation::Audit => out.push_str(" +audit"),
+                    Obligation::Custom(code) => out.push_str(&format!(" +obligation({})", code)),
+                    Obligation::Approval(approval) => out.push_str(&format!(
+                        " +approval({}, {}s)",
+                        approval.approvers, approval.expires_in
+                    )),
+                }
+            }
+            out
+        }
+        Err(e) => format!("error {}", e),
+    }
+}
+
+/// Compare a policy's decisions against a snapshot file without panicking.
+///
+/// - If `update` is true, the file is (re)written and the check passes.
+/// - If the file does not exist, `SnapshotError::Missing` is returned.
+/// - Otherwise any difference is returned as `SnapshotError::Mismatch`.
+pub fn check_snapshot(
+    path: &Path,
+    policy: &Policy<'_>,
+    corpus: &Corpus<'_>,
+    update: bool,
+) -> Result<(), SnapshotError> {
+    let actual = Snapshot::record(policy, corpus);
+
+    if update {
+        std::fs::write(path, actual.to_text())?;
+        return Ok(());
+    }
+    if !path.exists() {
+        return Err(SnapshotError::Missing);
+    }
+
+    let expected = Snapshot::parse(&std::fs::read_to_string(path)?)?;
+    let changes = expected.diff(&actual);
+    if changes.is_empty() {
+        Ok(())
+    } else {
+        Err(SnapshotError::Mismatch(changes))
+    }
+}
+
+/// Assert that a policy's decisions match a snapshot file.
+///
+/// Intended for use inside `#[test]` functions. Panics with a readable diff
+/// when any recorded decision changed, or if the file is missing. Set
+/// `GATE0_UPDATE_SNAPSHOTS=1` to record the file or accept the new decisions.
+pub fn assert_snapshot(path: impl AsRef<Path>, policy: &Policy<'_>, corpus: &Corpus<'_>) {
+    let update = std::env::var_os(UPDATE_ENV_VAR).is_some_and(|v| v == "1");
+    if let Err(e) = check_snapshot(path.as_ref(), policy, corpus, update) {
+        panic!("{}: {}", path.as_ref().display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{PolicyConfig, Rule};
+    use crate::target::{Matcher, Target};
+    use crate::types::ReasonCode;
+    use crate::value::Value;
+
+    fn sample_policy(admin_reason: u32) -> Policy<'static> {
+        Policy::builder()
+            .rule(Rule::allow(
+                Target {
+                    principal: Matcher::Exact("admin"),
+                    action: Matcher::Any,
+                    resource: Matcher::Any,
+                    environment: Matcher::Any,
+                },
+                ReasonCode(admin_reason),
+            ))
+            .build()
+            .unwrap()
+    }
+
+    fn sample_corpus() -> Corpus<'static> {
+        Corpus::new()
+            .case("admin writes", Request::new("admin", "write", "secret"))
+            .case("guest writes", Request::new("guest", "write", "secret"))
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("gate0-testkit-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_record_and_render() {
+        let snapshot = Snapshot::record(&sample_policy(1), &sample_corpus());
+        assert_eq!(
+            snapshot.to_text(),
+            "# gate0 decision snapshot v1\nadmin writes = allow 1\nguest writes = deny 0\n"
+        );
+    }
+
+    #[test]
+    fn test_render_error() {
+        let config = PolicyConfig {
+            max_context_attrs: 0,
+            ..PolicyConfig::default()
+        };
+        let policy = Policy::with_config(vec![], config).unwrap();
+        let ctx: &[(&str, Value)] = &[("a", Value::Int(1))];
+        let corpus = Corpus::new().case("too big", Request::with_context("a", "b", "c", ctx));
+        let snapshot = Snapshot::record(&policy, &corpus);
+        assert_eq!(
+            snapshot.entries()[0].outcome,
+            "error context exceeds maximum attribute count of 0, got 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let snapshot = Snapshot::record(&sample_policy(1), &sample_corpus());
+        let parsed = Snapshot::parse(&snapshot.to_text()).unwrap();
+        assert_eq!(parsed, snapshot);
+
+        assert!(matches!(
+            Snapshot::parse("# header\nno separator\n"),
+            Err(SnapshotError::Parse { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_diff_reports_changes() {
+        let before = Snapshot::record(&sample_policy(1), &sample_corpus());
+        let after = Snapshot::record(&sample_policy(2), &sample_corpus());
+
+        assert!(before.diff(&before).is_empty());
+        assert_eq!(
+            before.diff(&after),
+            vec![SnapshotChange::Changed {
+                name: "admin writes".to_string(),
+                expected: "allow 1".to_string(),
+                actual: "allow 2".to_string(),
+            }]
+        );
+
+        let fewer = Corpus::new().case("admin writes", Request::new("admin", "write", "secret"));
+        let after = Snapshot::record(&sample_policy(1), &fewer);
+        assert_eq!(
+            before.diff(&after),
+            vec![SnapshotChange::Removed {
+                name: "guest writes".to_string()
+            }]
+        );
+        assert_eq!(
+            after.diff(&before),
+            vec![SnapshotChange::Added {
+                name: "guest writes".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_snapshot_file() {
+        let path = temp_path("check.snap");
+        let _ = std::fs::remove_file(&path);
+
+        // A missing file fails unless recording.
+        let err = check_snapshot(&path, &sample_policy(1), &sample_corpus(), false).unwrap_err();
+        assert!(matches!(err, SnapshotError::Missing));
+        assert!(!path.exists());
+        check_snapshot(&path, &sample_policy(1), &sample_corpus(), true).unwrap();
+        // Unchanged policy passes.
+        check_snapshot(&path, &sample_policy(1), &sample_corpus(), false).unwrap();
+        // Changed policy fails.
+        let err = check_snapshot(&path, &sample_policy(2), &sample_corpus(), false).unwrap_err();
+        assert!(matches!(err, SnapshotError::Mismatch(ref c) if c.len() == 1));
+        // Update mode accepts the change.
+        check_snapshot(&path, &sample_policy(2), &sample_corpus(), true).unwrap();
+        check_snapshot(&path, &sample_policy(2), &sample_corpus(), false).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
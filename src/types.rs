@@ -2,6 +2,10 @@
 //!
 //! All types use borrowed data to avoid allocation in the hot path.
 
+use std::fmt;
+
+use crate::error::PolicyError;
+use crate::obligation::{Obligation, Obligations};
 use crate::value::Value;
 
 /// The effect of a policy decision.
@@ -11,6 +15,16 @@ pub enum Effect {
     Allow,
     /// Access is denied.
     Deny,
+    /// Access would be allowed after additional verification.
+    ///
+    /// A challenge is not an allow: PEPs that only check `is_allow()`
+    /// fail closed. Combining precedence is Deny > Challenge > Allow.
+    Challenge(ChallengeMethod),
+    /// A rule could not be evaluated, so the outcome is unknown.
+    ///
+    /// Only produced when `PolicyConfig::indeterminate_on_error` is set.
+    /// Like a challenge it is not an allow, so PEPs fail closed.
+    Indeterminate,
 }
 
 impl Effect {
@@ -25,6 +39,39 @@ impl Effect {
     pub fn is_deny(&self) -> bool {
         matches!(self, Effect::Deny)
     }
+
+    /// Returns `true` if this effect is `Challenge`.
+    #[inline]
+    pub fn is_challenge(&self) -> bool {
+        matches!(self, Effect::Challenge(_))
+    }
+
+    /// Returns `true` if this effect is `Indeterminate`.
+    #[inline]
+    pub fn is_indeterminate(&self) -> bool {
+        matches!(self, Effect::Indeterminate)
+    }
+}
+
+/// The verification a `Challenge` effect asks the caller to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChallengeMethod {
+    /// Multi-factor authentication.
+    Mfa,
+    /// Re-enter credentials for the current session.
+    Reauthenticate,
+    /// Application-defined method, mapped to an external table like `ReasonCode`.
+    Custom(u32),
+}
+
+impl fmt::Display for ChallengeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChallengeMethod::Mfa => write!(f, "mfa"),
+            ChallengeMethod::Reauthenticate => write!(f, "reauthenticate"),
+            ChallengeMethod::Custom(code) => write!(f, "custom({})", code),
+        }
+    }
 }
 
 /// A stable reason code for audit logs.
@@ -54,6 +101,9 @@ impl ReasonCode {
 /// Reason code returned when no rules match the request.
 pub const NO_MATCHING_RULE: ReasonCode = ReasonCode(0);
 
+/// Reason code returned by `Policy::evaluate_or_deny` when evaluation fails.
+pub const EVALUATION_FAILED: ReasonCode = ReasonCode(u32::MAX);
+
 /// An authorization request.
 ///
 /// All fields are borrowed to avoid allocation during evaluation.
@@ -73,6 +123,9 @@ pub struct Request<'a> {
     /// - No heap allocation
     /// - Deterministic iteration order
     pub context: &'a [(&'a str, Value<'a>)],
+    /// The environment the request is made in (dev, staging, prod, ...),
+    /// if known. Matched by `Target::environment`.
+    pub environment: Option<&'a str>,
 }
 
 impl<'a> Request<'a> {
@@ -83,6 +136,7 @@ impl<'a> Request<'a> {
             action,
             resource,
             context: &[],
+            environment: None,
         }
     }
 
@@ -98,9 +152,16 @@ impl<'a> Request<'a> {
             action,
             resource,
             context,
+            environment: None,
         }
     }
 
+    /// Set the environment the request is made in.
+    pub fn in_environment(mut self, environment: &'a str) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
     /// Look up a context attribute by name.
     ///
     /// Linear scan is acceptable because context is bounded and small.
@@ -110,22 +171,75 @@ impl<'a> Request<'a> {
             .find(|(k, _)| *k == name)
             .map(|(_, v)| v)
     }
+
+    /// Check that no attribute name appears twice with different values.
+    ///
+    /// Evaluation reads the first entry for a name, so a context where a
+    /// later entry disagrees is ambiguous: whoever appended it may have
+    /// expected it to win. Repeating an entry with the same value is
+    /// harmless and accepted. Fails with `ConflictingAttribute`, giving the
+    /// index of the first entry that contradicts an earlier one.
+    ///
+    /// Quadratic in the context size; check `max_context_attrs` first
+    /// (`Policy::evaluate_strict` does).
+    pub fn check_context(&self) -> Result<(), PolicyError> {
+        for (index, (key, value)) in self.context.iter().enumerate() {
+            let conflict = self.context[..index]
+                .iter()
+                .any(|(k, v)| k == key && v != value);
+            if conflict {
+                return Err(PolicyError::ConflictingAttribute { index });
+            }
+        }
+        Ok(())
+    }
 }
 
 /// The result of evaluating a policy against a request.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Decision {
-    /// The final effect (Allow or Deny).
+    /// The final effect (Allow, Deny, Challenge, or Indeterminate).
     pub effect: Effect,
     /// The reason code explaining the decision.
     pub reason: ReasonCode,
+    /// How long, in seconds, this decision may be reused, if the deciding
+    /// rule provided a hint. `None` means no guidance; callers should apply
+    /// their own default.
+    pub cache_ttl: Option<u32>,
+    /// Actions the PEP must perform when enforcing this decision.
+    pub obligations: Obligations,
+    /// True if a break-glass rule decided this request.
+    pub break_glass: bool,
 }
 
 impl Decision {
     /// Create a new decision.
     #[inline]
     pub const fn new(effect: Effect, reason: ReasonCode) -> Self {
-        Decision { effect, reason }
+        Decision {
+            effect,
+            reason,
+            cache_ttl: None,
+            obligations: Obligations::new(),
+            break_glass: false,
+        }
+    }
+
+    /// Return this decision with the given cache TTL hint.
+    #[inline]
+    pub const fn with_cache_ttl(mut self, cache_ttl: Option<u32>) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Return this decision with an obligation added.
+    ///
+    /// The obligation is dropped if the decision already carries
+    /// `MAX_OBLIGATIONS` distinct obligations.
+    #[inline]
+    pub fn with_obligation(mut self, obligation: Obligation) -> Self {
+        self.obligations.push(obligation);
+        self
     }
 
     /// Create an Allow decision with the given reason.
@@ -140,6 +254,18 @@ impl Decision {
         Decision::new(Effect::Deny, reason)
     }
 
+    /// Create a Challenge decision with the given method and reason.
+    #[inline]
+    pub const fn challenge(method: ChallengeMethod, reason: ReasonCode) -> Self {
+        Decision::new(Effect::Challenge(method), reason)
+    }
+
+    /// Create an Indeterminate decision with the given reason.
+    #[inline]
+    pub const fn indeterminate(reason: ReasonCode) -> Self {
+        Decision::new(Effect::Indeterminate, reason)
+    }
+
     /// Returns `true` if this decision allows access.
     #[inline]
     pub fn is_allow(&self) -> bool {
@@ -151,6 +277,27 @@ impl Decision {
     pub fn is_deny(&self) -> bool {
         self.effect.is_deny()
     }
+
+    /// Returns `true` if this decision requires additional verification.
+    #[inline]
+    pub fn is_challenge(&self) -> bool {
+        self.effect.is_challenge()
+    }
+
+    /// Returns `true` if a rule could not be evaluated.
+    #[inline]
+    pub fn is_indeterminate(&self) -> bool {
+        self.effect.is_indeterminate()
+    }
+
+    /// The verification method requested, if this is a Challenge decision.
+    #[inline]
+    pub fn challenge_method(&self) -> Option<ChallengeMethod> {
+        match self.effect {
+            Effect::Challenge(method) => Some(method),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -163,6 +310,16 @@ mod tests {
         assert!(!Effect::Allow.is_deny());
         assert!(!Effect::Deny.is_allow());
         assert!(Effect::Deny.is_deny());
+
+        let challenge = Effect::Challenge(ChallengeMethod::Mfa);
+        assert!(challenge.is_challenge());
+        assert!(!challenge.is_allow());
+        assert!(!challenge.is_deny());
+        assert_eq!(ChallengeMethod::Custom(7).to_string(), "custom(7)");
+
+        assert!(Effect::Indeterminate.is_indeterminate());
+        assert!(!Effect::Indeterminate.is_allow());
+        assert!(!Effect::Indeterminate.is_deny());
     }
 
     #[test]
@@ -170,6 +327,7 @@ mod tests {
         let code = ReasonCode::new(42);
         assert_eq!(code.value(), 42);
         assert_eq!(NO_MATCHING_RULE.value(), 0);
+        assert_ne!(EVALUATION_FAILED, NO_MATCHING_RULE);
     }
 
     #[test]
@@ -192,6 +350,23 @@ mod tests {
         assert_eq!(req.get_attr("missing"), None);
     }
 
+    #[test]
+    fn test_check_context() {
+        let ctx: &[(&str, Value)] = &[
+            ("role", Value::String("viewer")),
+            ("level", Value::Int(5)),
+            ("role", Value::String("viewer")),
+            ("role", Value::String("admin")),
+        ];
+        let req = Request::with_context("alice", "read", "doc", &ctx[..3]);
+        assert_eq!(req.check_context(), Ok(()));
+        let req = Request::with_context("alice", "read", "doc", ctx);
+        assert_eq!(
+            req.check_context(),
+            Err(PolicyError::ConflictingAttribute { index: 3 })
+        );
+    }
+
     #[test]
     fn test_decision() {
         let allow = Decision::allow(ReasonCode(1));
@@ -203,5 +378,7 @@ mod tests {
         assert!(!deny.is_allow());
         assert!(deny.is_deny());
         assert_eq!(deny.reason.value(), 2);
+        assert_eq!(deny.cache_ttl, None);
+        assert_eq!(deny.with_cache_ttl(Some(60)).cache_ttl, Some(60));
     }
 }
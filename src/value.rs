@@ -1,12 +1,18 @@
 //! Context value types.
 //!
-//! Minimal set: Bool, Int, String only.
-//! No Float, List, or Null - smaller surface = stronger guarantees.
+//! Minimal set: Bool, Int, String, Secret, Ip, flat List, and Map for
+//! nested documents read through dotted paths. No Float, Null, or nested
+//! lists - smaller surface = stronger guarantees.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::error::PolicyError;
 
 /// A value that can appear in request context.
 ///
 /// Intentionally minimal to reduce complexity and attack surface.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone)]
 pub enum Value<'a> {
     /// Boolean value.
     Bool(bool),
@@ -14,6 +20,66 @@ pub enum Value<'a> {
     Int(i64),
     /// Borrowed string slice.
     String(&'a str),
+    /// Secret bytes, such as an API key or session token.
+    ///
+    /// Compared in constant time (only the length can leak) and never
+    /// printed: `Debug` and every renderer show `<redacted>`. A secret only
+    /// equals another secret, never a `String` with the same bytes.
+    Secret(&'a [u8]),
+    /// IPv4 or IPv6 address, e.g. the client's, for `Condition::IpInCidr`.
+    Ip(IpAddr),
+    /// A list of values, e.g. the principal's groups, for
+    /// `Condition::AttrContains`.
+    ///
+    /// Only context attributes may hold lists, and lists do not nest:
+    /// validation fails with `PolicyError::InvalidList` otherwise.
+    List(ValueList<'a>),
+    /// Named values, e.g. a device posture document, read by conditions
+    /// through dotted attribute paths such as `device.posture.compliant`.
+    ///
+    /// Only context attributes may hold maps, and lists cannot hold them:
+    /// validation fails with `PolicyError::InvalidMap` otherwise. Nesting
+    /// is bounded by `PolicyConfig::max_path_depth`.
+    Map(ValueMap<'a>),
+}
+
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Secret(a), Value::Secret(b)) => constant_time_eq(a, b),
+            (Value::Ip(a), Value::Ip(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            Value::Int(i) => f.debug_tuple("Int").field(i).finish(),
+            Value::String(s) => f.debug_tuple("String").field(s).finish(),
+            Value::Secret(_) => f.write_str("Secret(<redacted>)"),
+            Value::Ip(ip) => f.debug_tuple("Ip").field(ip).finish(),
+            Value::List(list) => f.debug_tuple("List").field(list).finish(),
+            Value::Map(map) => f.debug_tuple("Map").field(map).finish(),
+        }
+    }
+}
+
+/// Compare without an early exit, so timing reveals only the lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    // Keep the optimizer from turning the fold back into an early exit.
+    std::hint::black_box(diff) == 0
 }
 
 impl<'a> Value<'a> {
@@ -35,6 +101,30 @@ impl<'a> Value<'a> {
         matches!(self, Value::String(_))
     }
 
+    /// Returns `true` if this is a `Secret` variant.
+    #[inline]
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Value::Secret(_))
+    }
+
+    /// Returns `true` if this is an `Ip` variant.
+    #[inline]
+    pub fn is_ip(&self) -> bool {
+        matches!(self, Value::Ip(_))
+    }
+
+    /// Returns `true` if this is a `List` variant.
+    #[inline]
+    pub fn is_list(&self) -> bool {
+        matches!(self, Value::List(_))
+    }
+
+    /// Returns `true` if this is a `Map` variant.
+    #[inline]
+    pub fn is_map(&self) -> bool {
+        matches!(self, Value::Map(_))
+    }
+
     /// Returns the boolean value if this is a `Bool`, otherwise `None`.
     #[inline]
     pub fn as_bool(&self) -> Option<bool> {
@@ -62,12 +152,349 @@ impl<'a> Value<'a> {
         }
     }
 
+    /// Returns the secret bytes if this is a `Secret`, otherwise `None`.
+    #[inline]
+    pub fn as_secret(&self) -> Option<&'a [u8]> {
+        match self {
+            Value::Secret(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the address if this is an `Ip`, otherwise `None`.
+    #[inline]
+    pub fn as_ip(&self) -> Option<IpAddr> {
+        match self {
+            Value::Ip(ip) => Some(*ip),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this is a `List`, otherwise `None`.
+    #[inline]
+    pub fn as_list(&self) -> Option<ValueList<'a>> {
+        match self {
+            Value::List(list) => Some(*list),
+            _ => None,
+        }
+    }
+
+    /// A `List` of `values`.
+    pub const fn list(values: &'a [Value<'a>]) -> Self {
+        Value::List(ValueList::new(values))
+    }
+
+    /// Returns the entries if this is a `Map`, otherwise `None`.
+    #[inline]
+    pub fn as_map(&self) -> Option<ValueMap<'a>> {
+        match self {
+            Value::Map(map) => Some(*map),
+            _ => None,
+        }
+    }
+
+    /// A `Map` of `entries`.
+    pub const fn map(entries: &'a [(&'a str, Value<'a>)]) -> Self {
+        Value::Map(ValueMap::new(entries))
+    }
+
     /// Returns a string describing the type of this value.
     pub fn type_name(&self) -> &'static str {
         match self {
             Value::Bool(_) => "Bool",
             Value::Int(_) => "Int",
             Value::String(_) => "String",
+            Value::Secret(_) => "Secret",
+            Value::Ip(_) => "Ip",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+        }
+    }
+
+    /// Check a `String` or `Secret`, or each one in a `List`, against
+    /// `max_len` bytes, and that a `List` holds no lists or maps.
+    ///
+    /// A `Map`'s entries are not visited: the caller walks them, so it can
+    /// bound the nesting first.
+    pub(crate) fn validate_len(&self, max_len: usize) -> Result<(), PolicyError> {
+        let len = match self {
+            Value::String(s) => s.len(),
+            Value::Secret(b) => b.len(),
+            Value::Bool(_) | Value::Int(_) | Value::Ip(_) | Value::Map(_) => return Ok(()),
+            Value::List(list) => {
+                for value in list.iter() {
+                    match value {
+                        Value::List(_) => return Err(PolicyError::InvalidList),
+                        Value::Map(_) => return Err(PolicyError::InvalidMap),
+                        _ => value.validate_len(max_len)?,
+                    }
+                }
+                return Ok(());
+            }
+        };
+        if len > max_len {
+            Err(PolicyError::StringTooLong {
+                max: max_len,
+                actual: len,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Value<'static> {
+    /// An `Int` from any integer type, such as a `u64` ID or an `i128`.
+    ///
+    /// Fails with `IntegerOutOfRange` instead of truncating when the value
+    /// does not fit in an `i64`.
+    pub fn try_int<N: TryInto<i64>>(n: N) -> Result<Self, PolicyError> {
+        checked_int(n).map(Value::Int)
+    }
+
+    /// Parse an `Int` from decimal text, e.g. a number received as JSON.
+    ///
+    /// Accepts an optional sign followed by ASCII digits. Fails with
+    /// `IntegerOutOfRange` if the number does not fit in an `i64`, and with
+    /// `InvalidInteger` for anything else, including fractions and
+    /// exponents such as `1e21`, so precision is never silently lost.
+    pub fn parse_int(text: &str) -> Result<Self, PolicyError> {
+        parse_int(text).map(Value::Int)
+    }
+}
+
+/// Convert to `i64` without truncating.
+pub(crate) fn checked_int<N: TryInto<i64>>(n: N) -> Result<i64, PolicyError> {
+    n.try_into().map_err(|_| PolicyError::IntegerOutOfRange)
+}
+
+/// Parse a decimal `i64`; see `Value::parse_int`.
+pub(crate) fn parse_int(text: &str) -> Result<i64, PolicyError> {
+    use std::num::IntErrorKind;
+
+    text.parse::<i64>().map_err(|e| match e.kind() {
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => PolicyError::IntegerOutOfRange,
+        _ => PolicyError::InvalidInteger,
+    })
+}
+
+impl From<bool> for Value<'_> {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value<'_> {
+    fn from(i: i64) -> Self {
+        Value::Int(i)
+    }
+}
+
+impl From<IpAddr> for Value<'_> {
+    fn from(ip: IpAddr) -> Self {
+        Value::Ip(ip)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(s: &'a str) -> Self {
+        Value::String(s)
+    }
+}
+
+/// The elements of a `Value::List`.
+///
+/// Borrowed from a slice of `Value`s, or from the elements of a
+/// `ValueBuf::List` so recorded lists evaluate without copying.
+#[derive(Clone, Copy)]
+pub struct ValueList<'a>(Elements<'a>);
+
+#[derive(Clone, Copy)]
+enum Elements<'a> {
+    Borrowed(&'a [Value<'a>]),
+    Owned(&'a [ValueBuf]),
+}
+
+impl<'a> ValueList<'a> {
+    /// A list of `values`.
+    pub const fn new(values: &'a [Value<'a>]) -> Self {
+        ValueList(Elements::Borrowed(values))
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> usize {
+        match self.0 {
+            Elements::Borrowed(values) => values.len(),
+            Elements::Owned(values) => values.len(),
+        }
+    }
+
+    /// Returns `true` if the list has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The element at `index`, if any.
+    pub fn get(&self, index: usize) -> Option<Value<'a>> {
+        match self.0 {
+            Elements::Borrowed(values) => values.get(index).cloned(),
+            Elements::Owned(values) => values.get(index).map(ValueBuf::as_value),
+        }
+    }
+
+    /// The elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = Value<'a>> + 'a {
+        let list = *self;
+        (0..list.len()).filter_map(move |i| list.get(i))
+    }
+}
+
+impl PartialEq for ValueList<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl fmt::Debug for ValueList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The entries of a `Value::Map`.
+///
+/// Borrowed like `ValueList`. Lookups scan the entries in order and take
+/// the first with a matching key; maps are small documents, not indexes.
+#[derive(Clone, Copy)]
+pub struct ValueMap<'a>(Entries<'a>);
+
+#[derive(Clone, Copy)]
+enum Entries<'a> {
+    Borrowed(&'a [(&'a str, Value<'a>)]),
+    Owned(&'a [(String, ValueBuf)]),
+}
+
+impl<'a> ValueMap<'a> {
+    /// A map of `entries`.
+    pub const fn new(entries: &'a [(&'a str, Value<'a>)]) -> Self {
+        ValueMap(Entries::Borrowed(entries))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        match self.0 {
+            Entries::Borrowed(entries) => entries.len(),
+            Entries::Owned(entries) => entries.len(),
+        }
+    }
+
+    /// Returns `true` if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of the first entry named `key`, if any.
+    pub fn get(&self, key: &str) -> Option<Value<'a>> {
+        match self.0 {
+            Entries::Borrowed(entries) => entries
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.clone()),
+            Entries::Owned(entries) => entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_value()),
+        }
+    }
+
+    /// The entries in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, Value<'a>)> + 'a {
+        let map = *self;
+        (0..map.len()).map(move |i| match map.0 {
+            Entries::Borrowed(entries) => (entries[i].0, entries[i].1.clone()),
+            Entries::Owned(entries) => (entries[i].0.as_str(), entries[i].1.as_value()),
+        })
+    }
+}
+
+impl PartialEq for ValueMap<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl fmt::Debug for ValueMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An owned counterpart of `Value`, for storage and transport.
+///
+/// Evaluation always works on borrowed `Value`s; use `as_value()` to lend
+/// one out.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValueBuf {
+    /// Boolean value.
+    Bool(bool),
+    /// 64-bit signed integer.
+    Int(i64),
+    /// Owned string.
+    String(String),
+    /// Owned secret bytes.
+    Secret(Vec<u8>),
+    /// IP address.
+    Ip(IpAddr),
+    /// Owned list elements.
+    List(Vec<ValueBuf>),
+    /// Owned map entries.
+    Map(Vec<(String, ValueBuf)>),
+}
+
+impl PartialEq for ValueBuf {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_value() == other.as_value()
+    }
+}
+
+impl Eq for ValueBuf {}
+
+impl fmt::Debug for ValueBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_value().fmt(f)
+    }
+}
+
+impl ValueBuf {
+    /// Borrow this value as a `Value`.
+    pub fn as_value(&self) -> Value<'_> {
+        match self {
+            ValueBuf::Bool(b) => Value::Bool(*b),
+            ValueBuf::Int(i) => Value::Int(*i),
+            ValueBuf::String(s) => Value::String(s),
+            ValueBuf::Secret(b) => Value::Secret(b),
+            ValueBuf::Ip(ip) => Value::Ip(*ip),
+            ValueBuf::List(values) => Value::List(ValueList(Elements::Owned(values))),
+            ValueBuf::Map(entries) => Value::Map(ValueMap(Entries::Owned(entries))),
+        }
+    }
+}
+
+impl<'a> From<&Value<'a>> for ValueBuf {
+    fn from(value: &Value<'a>) -> Self {
+        match value {
+            Value::Bool(b) => ValueBuf::Bool(*b),
+            Value::Int(i) => ValueBuf::Int(*i),
+            Value::String(s) => ValueBuf::String((*s).to_string()),
+            Value::Secret(b) => ValueBuf::Secret(b.to_vec()),
+            Value::Ip(ip) => ValueBuf::Ip(*ip),
+            Value::List(list) => ValueBuf::List(list.iter().map(|v| ValueBuf::from(&v)).collect()),
+            Value::Map(map) => ValueBuf::Map(
+                map.iter()
+                    .map(|(k, v)| (k.to_string(), ValueBuf::from(&v)))
+                    .collect(),
+            ),
         }
     }
 }
@@ -76,6 +503,33 @@ impl<'a> Value<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_checked_ints() {
+        assert_eq!(Value::try_int(42u64), Ok(Value::Int(42)));
+        assert_eq!(Value::try_int(i64::MIN as i128), Ok(Value::Int(i64::MIN)));
+        assert_eq!(
+            Value::try_int(u64::MAX),
+            Err(PolicyError::IntegerOutOfRange)
+        );
+        assert_eq!(
+            Value::try_int(i128::MIN),
+            Err(PolicyError::IntegerOutOfRange)
+        );
+
+        assert_eq!(Value::parse_int("-17"), Ok(Value::Int(-17)));
+        assert_eq!(
+            Value::parse_int("9223372036854775807"),
+            Ok(Value::Int(i64::MAX))
+        );
+        assert_eq!(
+            Value::parse_int("9223372036854775808"),
+            Err(PolicyError::IntegerOutOfRange)
+        );
+        for text in ["", "1e21", "3.0", " 1", "0x10"] {
+            assert_eq!(Value::parse_int(text), Err(PolicyError::InvalidInteger));
+        }
+    }
+
     #[test]
     fn test_value_bool() {
         let v = Value::Bool(true);
@@ -121,4 +575,78 @@ mod tests {
         assert_eq!(Value::String("a"), Value::String("a"));
         assert_ne!(Value::String("a"), Value::String("b"));
     }
+
+    #[test]
+    fn test_value_ip() {
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let v = Value::from(ip);
+        assert!(v.is_ip());
+        assert_eq!(v.as_ip(), Some(ip));
+        assert_eq!(v.as_str(), None);
+        assert_eq!(v.type_name(), "Ip");
+        assert_eq!(v, Value::Ip(ip));
+        assert_ne!(v, Value::String("10.0.0.1"));
+        assert_eq!(ValueBuf::from(&v).as_value(), v);
+    }
+
+    #[test]
+    fn test_value_secret() {
+        let v = Value::Secret(b"sk_live_123");
+        assert!(v.is_secret());
+        assert_eq!(v.as_str(), None);
+        assert_eq!(v.as_secret(), Some(&b"sk_live_123"[..]));
+        assert_eq!(v.type_name(), "Secret");
+        assert_eq!(v, Value::Secret(b"sk_live_123"));
+        assert_ne!(v, Value::Secret(b"sk_live_124"));
+        assert_ne!(v, Value::Secret(b"sk_live"));
+        assert_ne!(v, Value::String("sk_live_123"));
+
+        assert_eq!(format!("{:?}", v), "Secret(<redacted>)");
+        let buf = ValueBuf::from(&v);
+        assert_eq!(format!("{:?}", buf), "Secret(<redacted>)");
+        assert!(!format!("{:?}", buf).contains("sk_live"));
+    }
+
+    #[test]
+    fn test_value_buf_roundtrip() {
+        for v in [
+            Value::Bool(true),
+            Value::Int(-7),
+            Value::String("x"),
+            Value::Secret(b"k"),
+            Value::list(&[Value::Int(1), Value::String("a")]),
+            Value::map(&[
+                ("os", Value::String("linux")),
+                ("patched", Value::Bool(true)),
+            ]),
+        ] {
+            assert_eq!(ValueBuf::from(&v).as_value(), v);
+        }
+    }
+
+    #[test]
+    fn test_value_map() {
+        let inner = [("compliant", Value::Bool(true))];
+        let entries = [
+            ("os", Value::String("linux")),
+            ("posture", Value::map(&inner)),
+        ];
+        let v = Value::map(&entries);
+        assert!(v.is_map());
+        assert_eq!(v.type_name(), "Map");
+        let map = v.as_map().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("os"), Some(Value::String("linux")));
+        assert_eq!(map.get("posture"), Some(Value::map(&inner)));
+        assert_eq!(map.get("missing"), None);
+        assert_eq!(
+            format!("{:?}", v),
+            "Map({\"os\": String(\"linux\"), \"posture\": Map({\"compliant\": Bool(true)})})"
+        );
+        // Lists cannot hold maps
+        assert_eq!(
+            Value::list(std::slice::from_ref(&v)).validate_len(256),
+            Err(PolicyError::InvalidMap)
+        );
+    }
 }
@@ -0,0 +1,205 @@
+//! Condition tree traversal.
+//!
+//! Linters, doc generators, and exporters all need to walk a condition
+//! tree, and a recursive walk overflows the stack on the deep trees that
+//! generated policies produce. `Condition::walk` does the walk once, with
+//! an explicit stack, and calls back into a `ConditionVisitor`:
+//!
+//! ```
+//! use gate0::{Condition, ConditionVisitor, Walk};
+//!
+//! /// Collects every attribute name a condition compares.
+//! #[derive(Default)]
+//! struct Attrs<'a>(Vec<&'a str>);
+//!
+//! impl<'a> ConditionVisitor<'a> for Attrs<'a> {
+//!     fn enter(&mut self, node: &Condition<'a>, _depth: usize) -> Walk {
+//!         if let Condition::Equals { attr, .. } = node {
+//!             self.0.push(attr);
+//!         }
+//!         Walk::Continue
+//!     }
+//! }
+//!
+//! let condition = Condition::And(
+//!     Box::new(Condition::Equals { attr: "role", value: gate0::Value::String("admin") }),
+//!     Box::new(Condition::Not(Box::new(Condition::Equals {
+//!         attr: "locked",
+//!         value: gate0::Value::Bool(true),
+//!     }))),
+//! );
+//! let mut attrs = Attrs::default();
+//! condition.walk(&mut attrs);
+//! assert_eq!(attrs.0, ["role", "locked"]);
+//! ```
+
+use crate::condition::Condition;
+
+/// What `Condition::walk` does after `ConditionVisitor::enter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Walk {
+    /// Visit the node's children, then leave it.
+    Continue,
+    /// Leave the node without visiting its children.
+    SkipChildren,
+    /// End the walk. No further callbacks are made, not even `leave`.
+    Stop,
+}
+
+/// Callbacks fired by `Condition::walk`. All methods default to no-ops.
+///
+/// Nodes are entered parents first, children left to right, and left
+/// once all their children have been; `depth` is 1 for the root, matching
+/// `Condition::depth`.
+pub trait ConditionVisitor<'a> {
+    /// A node is about to have its children visited.
+    fn enter(&mut self, _node: &Condition<'a>, _depth: usize) -> Walk {
+        Walk::Continue
+    }
+
+    /// All of a node's children have been visited (or skipped).
+    fn leave(&mut self, _node: &Condition<'a>, _depth: usize) {}
+}
+
+impl<'a> Condition<'a> {
+    /// Walk this condition tree, calling `visitor` on every node.
+    ///
+    /// This implementation is non-recursive, so trees of any depth are safe.
+    pub fn walk<V: ConditionVisitor<'a> + ?Sized>(&self, visitor: &mut V) {
+        // (node, depth, entered)
+        let mut stack: Vec<(&Condition<'a>, usize, bool)> = vec![(self, 1, false)];
+        while let Some((node, depth, entered)) = stack.pop() {
+            if entered {
+                visitor.leave(node, depth);
+                continue;
+            }
+            let walk = visitor.enter(node, depth);
+            if walk == Walk::Stop {
+                return;
+            }
+            stack.push((node, depth, true));
+            if walk == Walk::SkipChildren {
+                continue;
+            }
+            // Pushed right to left so they pop left to right.
+            match node {
+                Condition::Not(inner) | Condition::Require(_, inner) => {
+                    stack.push((inner, depth + 1, false))
+                }
+                Condition::And(a, b)
+                | Condition::Or(a, b)
+                | Condition::Implies(a, b)
+                | Condition::Xor(a, b) => {
+                    stack.push((b, depth + 1, false));
+                    stack.push((a, depth + 1, false));
+                }
+                Condition::AllOf(children) | Condition::AnyOf(children) => {
+                    stack.extend(children.iter().rev().map(|c| (c, depth + 1, false)));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    /// Records each callback as a line.
+    #[derive(Default)]
+    struct Trace {
+        events: Vec<String>,
+        skip: Option<&'static str>,
+        stop: Option<&'static str>,
+    }
+
+    impl<'a> ConditionVisitor<'a> for Trace {
+        fn enter(&mut self, node: &Condition<'a>, depth: usize) -> Walk {
+            let kind = node.kind_name();
+            self.events.push(format!("enter {} {}", kind, depth));
+            if self.stop == Some(kind) {
+                Walk::Stop
+            } else if self.skip == Some(kind) {
+                Walk::SkipChildren
+            } else {
+                Walk::Continue
+            }
+        }
+
+        fn leave(&mut self, node: &Condition<'a>, depth: usize) {
+            self.events
+                .push(format!("leave {} {}", node.kind_name(), depth));
+        }
+    }
+
+    fn sample() -> Condition<'static> {
+        static CHILDREN: [Condition<'static>; 2] = [Condition::True, Condition::MemberOf("staff")];
+        Condition::Or(
+            Box::new(Condition::Not(Box::new(Condition::False))),
+            Box::new(Condition::AllOf(&CHILDREN)),
+        )
+    }
+
+    #[test]
+    fn test_walk_order() {
+        let mut trace = Trace::default();
+        sample().walk(&mut trace);
+        let expected = [
+            "enter Or 1",
+            "enter Not 2",
+            "enter False 3",
+            "leave False 3",
+            "leave Not 2",
+            "enter AllOf 2",
+            "enter True 3",
+            "leave True 3",
+            "enter MemberOf 3",
+            "leave MemberOf 3",
+            "leave AllOf 2",
+            "leave Or 1",
+        ];
+        assert_eq!(trace.events, expected);
+    }
+
+    #[test]
+    fn test_walk_skip_and_stop() {
+        let mut trace = Trace {
+            skip: Some("Not"),
+            ..Trace::default()
+        };
+        sample().walk(&mut trace);
+        assert_eq!(trace.events[1..3], ["enter Not 2", "leave Not 2"]);
+
+        let mut trace = Trace {
+            stop: Some("True"),
+            ..Trace::default()
+        };
+        sample().walk(&mut trace);
+        assert_eq!(trace.events.last().unwrap(), "enter True 3");
+        assert_eq!(trace.events.len(), 7);
+    }
+
+    #[test]
+    fn test_walk_deep_tree() {
+        struct Deepest(usize);
+        impl ConditionVisitor<'_> for Deepest {
+            fn enter(&mut self, _node: &Condition<'_>, depth: usize) -> Walk {
+                self.0 = self.0.max(depth);
+                Walk::Continue
+            }
+        }
+
+        let mut c = Condition::Equals {
+            attr: "role",
+            value: Value::String("admin"),
+        };
+        for _ in 0..100_000 {
+            c = Condition::Not(Box::new(c));
+        }
+        let mut deepest = Deepest(0);
+        c.walk(&mut deepest);
+        assert_eq!(deepest.0, c.depth());
+    }
+}
@@ -0,0 +1,437 @@
+//! Non-fatal policy diagnostics.
+//!
+//! `PolicyBuilder::build_with_warnings` accepts every policy `build` does,
+//! and also reports smells that are legal but almost always mistakes, so
+//! CI can surface them without blocking a deploy:
+//!
+//! - a condition that is always true or always false on its own
+//! - a condition that can never hold given the builder's attribute schema,
+//!   e.g. comparing an attribute with a value of another type
+//! - a `Matcher::OneOf` listing the same option twice
+//! - an action or resource missing from `PolicyConfig::known_actions` or
+//!   `known_resources`, usually a typo
+
+use std::fmt;
+
+use crate::condition::Condition;
+use crate::manifest::AttrKind;
+use crate::policy::{PolicyConfig, Rule};
+use crate::target::Matcher;
+use crate::value::Value;
+
+/// A legal construct that is probably a mistake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyWarning<'a> {
+    /// The rule's condition has the same result for every request.
+    ConstantCondition {
+        /// Index of the rule.
+        rule: usize,
+        /// The result it always has.
+        value: bool,
+    },
+    /// The rule's condition is false for every request that follows the
+    /// attribute schema, so the rule never matches.
+    UnsatisfiableRule {
+        /// Index of the rule.
+        rule: usize,
+    },
+    /// A `OneOf` matcher lists an option more than once.
+    DuplicateMatcherOption {
+        /// Index of the rule.
+        rule: usize,
+        /// `"principal"`, `"action"`, or `"resource"`.
+        field: &'static str,
+        /// The repeated option.
+        option: &'a str,
+    },
+    /// The rule names an action or resource outside the configured
+    /// vocabulary.
+    UnknownName {
+        /// Index of the rule.
+        rule: usize,
+        /// `"action"` or `"resource"`.
+        field: &'static str,
+        /// The unknown name.
+        name: &'a str,
+    },
+}
+
+impl fmt::Display for PolicyWarning<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyWarning::ConstantCondition { rule, value } => {
+                write!(f, "rule {}: condition is always {}", rule, value)
+            }
+            PolicyWarning::UnsatisfiableRule { rule } => {
+                write!(f, "rule {}: never matches given the attribute schema", rule)
+            }
+            PolicyWarning::DuplicateMatcherOption {
+                rule,
+                field,
+                option,
+            } => {
+                write!(
+                    f,
+                    "rule {}: {} option '{}' is listed twice",
+                    rule, field, option
+                )
+            }
+            PolicyWarning::UnknownName { rule, field, name } => {
+                write!(
+                    f,
+                    "rule {}: {} '{}' is not a known {}",
+                    rule, field, name, field
+                )
+            }
+        }
+    }
+}
+
+/// Warnings collected while building a policy, in rule order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Warnings<'a> {
+    warnings: Vec<PolicyWarning<'a>>,
+}
+
+impl<'a> Warnings<'a> {
+    /// Returns `true` if there are no warnings.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Number of warnings.
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Iterate over the warnings.
+    pub fn iter(&self) -> std::slice::Iter<'_, PolicyWarning<'a>> {
+        self.warnings.iter()
+    }
+
+    /// Check `rules` against `config`'s vocabularies, reading attribute
+    /// types from `schema` if given.
+    pub(crate) fn check(
+        rules: &[Rule<'a>],
+        config: &PolicyConfig,
+        schema: Option<&[(&str, AttrKind)]>,
+    ) -> Self {
+        let mut warnings = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            let matchers = [
+                ("principal", &rule.target.principal),
+                ("action", &rule.target.action),
+                ("resource", &rule.target.resource),
+                ("environment", &rule.target.environment),
+            ];
+            for (field, matcher) in matchers {
+                if let Matcher::OneOf(options) = matcher {
+                    for (i, option) in options.iter().enumerate() {
+                        // Report each repeated option once, at its second use.
+                        if options[..i].iter().filter(|o| *o == option).count() == 1 {
+                            warnings.push(PolicyWarning::DuplicateMatcherOption {
+                                rule: index,
+                                field,
+                                option,
+                            });
+                        }
+                    }
+                }
+            }
+
+            for (field, name) in config.unknown_names(rule) {
+                warnings.push(PolicyWarning::UnknownName {
+                    rule: index,
+                    field,
+                    name,
+                });
+            }
+
+            let Some(cond) = &rule.condition else {
+                continue;
+            };
+            if let Some(value) = fold(cond, None) {
+                warnings.push(PolicyWarning::ConstantCondition { rule: index, value });
+            } else if schema.is_some() && fold(cond, schema) == Some(false) {
+                warnings.push(PolicyWarning::UnsatisfiableRule { rule: index });
+            }
+        }
+        Warnings { warnings }
+    }
+}
+
+impl<'w, 'a> IntoIterator for &'w Warnings<'a> {
+    type Item = &'w PolicyWarning<'a>;
+    type IntoIter = std::slice::Iter<'w, PolicyWarning<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.iter()
+    }
+}
+
+/// One warning per line.
+impl fmt::Display for Warnings<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for warning in &self.warnings {
+            writeln!(f, "{}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// The result `cond` always has, if it does not depend on the request.
+///
+/// With a schema, leaves that read an undeclared attribute or compare one
+/// with a value of another type are decided the way evaluation decides a
+/// missing or unequal attribute. Recursion is bounded by the policy's
+/// validated condition depth.
+fn fold(cond: &Condition<'_>, schema: Option<&[(&str, AttrKind)]>) -> Option<bool> {
+    let declared = |attr: &str| {
+        schema.map(|attrs| {
+            attrs
+                .iter()
+                .find(|(name, _)| *name == attr)
+                .map(|(_, k)| *k)
+        })
+    };
+    // Whether `attr` can never equal `value`.
+    let never_equal = |attr: &str, value: &Value<'_>| match declared(attr) {
+        Some(Some(kind)) => kind != AttrKind::of(value),
+        Some(None) => true,
+        None => false,
+    };
+    match cond {
+        Condition::True => Some(true),
+        Condition::False => Some(false),
+        Condition::Equals { attr, value } if never_equal(attr, value) => Some(false),
+        Condition::NotEquals { attr, value } if never_equal(attr, value) => Some(true),
+        Condition::In { attr, values } if values.iter().all(|v| never_equal(attr, v)) => {
+            Some(false)
+        }
+        Condition::NotIn { attr, values } if values.iter().all(|v| never_equal(attr, v)) => {
+            Some(true)
+        }
+        Condition::EqualsIgnoreCase { attr, .. }
+        | Condition::Matches { attr, .. }
+        | Condition::GlobMatches { attr, .. }
+        | Condition::AttrIsPrincipal(attr) => match declared(attr) {
+            Some(Some(AttrKind::String)) | None => None,
+            Some(_) => Some(false),
+        },
+        Condition::GreaterThan { attr, .. }
+        | Condition::GreaterOrEqual { attr, .. }
+        | Condition::LessThan { attr, .. }
+        | Condition::LessOrEqual { attr, .. }
+        | Condition::Between { attr, .. }
+        | Condition::HasFlags { attr, .. } => match declared(attr) {
+            Some(Some(AttrKind::Int)) | None => None,
+            Some(_) => Some(false),
+        },
+        Condition::IpInCidr { attr, .. } => match declared(attr) {
+            Some(Some(AttrKind::Ip)) | None => None,
+            Some(_) => Some(false),
+        },
+        Condition::AttrContains { attr, .. } => match declared(attr) {
+            Some(Some(AttrKind::List)) | None => None,
+            Some(_) => Some(false),
+        },
+        // The clock may be injected, so its attribute's kind says nothing.
+        Condition::TimeOfDayBetween { window, .. } if window.start == window.end => Some(false),
+        Condition::DayOfWeekIn { days, .. } if days.bits() == 0 => Some(false),
+        Condition::Equals { .. }
+        | Condition::NotEquals { .. }
+        | Condition::In { .. }
+        | Condition::NotIn { .. }
+        | Condition::TimeOfDayBetween { .. }
+        | Condition::DayOfWeekIn { .. }
+        | Condition::MemberOf(_)
+        | Condition::Custom { .. }
+        | Condition::Ref(_) => None,
+        Condition::Not(inner) => fold(inner, schema).map(|v| !v),
+        // Decided by the inner condition whenever it does not fail.
+        Condition::Require(_, inner) => fold(inner, schema),
+        Condition::And(a, b) => match (fold(a, schema), fold(b, schema)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Condition::Or(a, b) => match (fold(a, schema), fold(b, schema)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Condition::Implies(a, b) => match (fold(a, schema), fold(b, schema)) {
+            (Some(false), _) | (_, Some(true)) => Some(true),
+            (Some(true), Some(false)) => Some(false),
+            _ => None,
+        },
+        Condition::Xor(a, b) => match (fold(a, schema), fold(b, schema)) {
+            (Some(a), Some(b)) => Some(a != b),
+            _ => None,
+        },
+        Condition::AllOf(children) | Condition::AnyOf(children) => {
+            // The value that decides the connective, and its empty result.
+            let decisive = matches!(cond, Condition::AnyOf(_));
+            let mut known = true;
+            for child in children.iter() {
+                match fold(child, schema) {
+                    Some(v) if v == decisive => return Some(decisive),
+                    Some(_) => {}
+                    None => known = false,
+                }
+            }
+            known.then_some(!decisive)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PolicyError;
+    use crate::policy::Policy;
+    use crate::target::Target;
+    use crate::types::{Effect, ReasonCode};
+
+    fn rule(condition: Condition<'static>) -> Rule<'static> {
+        Rule::new(Effect::Allow, Target::any(), Some(condition), ReasonCode(1))
+    }
+
+    #[test]
+    fn test_constant_conditions() {
+        let (_, warnings) = Policy::builder()
+            .rule(rule(Condition::Or(
+                Box::new(Condition::MemberOf("staff")),
+                Box::new(Condition::Not(Box::new(Condition::False))),
+            )))
+            .rule(rule(Condition::MemberOf("staff")))
+            .rule(rule(Condition::And(
+                Box::new(Condition::MemberOf("staff")),
+                Box::new(Condition::False),
+            )))
+            .build_with_warnings()
+            .unwrap();
+        assert_eq!(
+            warnings.iter().collect::<Vec<_>>(),
+            [
+                &PolicyWarning::ConstantCondition {
+                    rule: 0,
+                    value: true
+                },
+                &PolicyWarning::ConstantCondition {
+                    rule: 2,
+                    value: false
+                },
+            ]
+        );
+        assert_eq!(
+            warnings.to_string(),
+            "rule 0: condition is always true\nrule 2: condition is always false\n"
+        );
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema: &[(&str, AttrKind)] = &[("role", AttrKind::String), ("level", AttrKind::Int)];
+        let (_, warnings) = Policy::builder()
+            .attribute_schema(schema)
+            // Wrong type
+            .rule(rule(Condition::Equals {
+                attr: "level",
+                value: Value::String("high"),
+            }))
+            // Undeclared attribute
+            .rule(rule(Condition::And(
+                Box::new(Condition::Equals {
+                    attr: "role",
+                    value: Value::String("admin"),
+                }),
+                Box::new(Condition::AttrIsPrincipal("owner")),
+            )))
+            // Fine
+            .rule(rule(Condition::Equals {
+                attr: "role",
+                value: Value::String("admin"),
+            }))
+            // NotEquals with the wrong type always holds
+            .rule(rule(Condition::NotEquals {
+                attr: "level",
+                value: Value::Bool(true),
+            }))
+            .build_with_warnings()
+            .unwrap();
+        assert_eq!(
+            warnings.iter().collect::<Vec<_>>(),
+            [
+                &PolicyWarning::UnsatisfiableRule { rule: 0 },
+                &PolicyWarning::UnsatisfiableRule { rule: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_options() {
+        let actions: &[&str] = &["read", "write", "read", "read", "list"];
+        let target = Target {
+            principal: Matcher::Any,
+            action: Matcher::OneOf(actions),
+            resource: Matcher::Any,
+            environment: Matcher::Any,
+        };
+        let (policy, warnings) = Policy::builder()
+            .rule(Rule::allow(target, ReasonCode(1)))
+            .build_with_warnings()
+            .unwrap();
+        assert_eq!(policy.rule_count(), 1);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings.to_string(),
+            "rule 0: action option 'read' is listed twice\n"
+        );
+
+        let (_, warnings) = Policy::builder()
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .build_with_warnings()
+            .unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        const ACTIONS: &[&str] = &["read", "write"];
+        const RESOURCES: &[&str] = &["doc", "wiki"];
+        let config = PolicyConfig {
+            known_actions: Some(ACTIONS),
+            known_resources: Some(RESOURCES),
+            ..PolicyConfig::default()
+        };
+        let typo = Target {
+            principal: Matcher::Exact("anyone-goes"),
+            action: Matcher::OneOf(&["read", "raed"]),
+            resource: Matcher::Exact("wki"),
+            environment: Matcher::Any,
+        };
+        let (_, warnings) = Policy::builder()
+            .config(config)
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .rule(Rule::allow(typo.clone(), ReasonCode(2)))
+            .build_with_warnings()
+            .unwrap();
+        assert_eq!(
+            warnings.to_string(),
+            "rule 1: action 'raed' is not a known action\n\
+             rule 1: resource 'wki' is not a known resource\n"
+        );
+
+        let strict = PolicyConfig {
+            reject_unknown_names: true,
+            ..config
+        };
+        let result = Policy::builder()
+            .config(strict)
+            .rule(Rule::allow(Target::any(), ReasonCode(1)))
+            .rule(Rule::allow(typo, ReasonCode(2)))
+            .build();
+        assert_eq!(result.unwrap_err(), PolicyError::UnknownName { rule: 1 });
+    }
+}
@@ -12,7 +12,10 @@
 use std::alloc::{GlobalAlloc, Layout, System};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-use gate0::{Condition, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target, Value};
+use gate0::{
+    Condition, Decision, DenialTracker, Effect, Matcher, Policy, ReasonCode, Request, Rule, Target,
+    Value,
+};
 
 /// A counting allocator that wraps the system allocator.
 struct CountingAllocator;
@@ -107,6 +110,7 @@ fn test_zero_allocations_no_match_path() {
                 principal: Matcher::Exact("admin"),
                 action: Matcher::Any,
                 resource: Matcher::Any,
+                environment: Matcher::Any,
             },
             ReasonCode(1),
         ))
@@ -210,3 +214,22 @@ fn test_zero_allocations_max_depth_condition() {
         "evaluate() with deep condition should perform zero allocations, but performed {count}"
     );
 }
+
+/// Test that DenialTracker allocates only at construction.
+#[test]
+fn test_zero_allocations_denial_tracker() {
+    let mut tracker = DenialTracker::new(256, 60);
+    let deny = Decision::deny(ReasonCode(1));
+
+    reset_alloc_count();
+    for t in 0..1000 {
+        tracker.record("alice", &deny, t);
+        let _ = tracker.exceeds("alice", t, 5);
+    }
+    let count = get_alloc_count();
+
+    assert_eq!(
+        count, 0,
+        "DenialTracker should not allocate after construction, but performed {count}"
+    );
+}
@@ -0,0 +1,419 @@
+//! Decision stability tests.
+//!
+//! gate0 guarantees that an identical (policy, request) pair produces an
+//! identical decision on every platform and in every release of the same
+//! major version (see docs/STABILITY.md). These tests enforce that promise:
+//!
+//! - `tests/golden/decisions.txt` is a corpus of decisions recorded by an
+//!   earlier release. It is checked in and must never change, except with a
+//!   major version bump.
+//! - The policy fingerprint and canonical decision encoding are pinned to
+//!   fixed values, since deployments store both.
+//! - Every evaluation entry point agrees with `evaluate()` on the corpus,
+//!   however often and in whatever order it is called.
+//!
+//! To record a new golden file after an intentional major-version change:
+//! ```text
+//! GATE0_UPDATE_SNAPSHOTS=1 cargo test --test determinism
+//! ```
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+
+use gate0::canonical::encode_decision;
+use gate0::testkit::{render_outcome, Corpus, Snapshot, UPDATE_ENV_VAR};
+use gate0::{
+    ActionHierarchy, ChallengeMethod, Cidr, Condition, Days, DenyAggregation, Effect, Matcher,
+    Policy, PolicyConfig, ProviderFailure, ReasonCode, Request, Rule, Schedule, Target, TimeWindow,
+    Value,
+};
+
+const GOLDEN_DECISIONS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/decisions.txt");
+
+/// Fingerprint of `golden_policy()`, as recorded by gate0 0.2.
+const GOLDEN_FINGERPRINT: u64 = 0x46f9_ae96_5dde_1455;
+
+const DEPARTMENTS: &[Value<'static>] = &[Value::String("eng"), Value::String("ops")];
+const OFFICE_HOURS: &[TimeWindow] = &[TimeWindow::new(9, 0, 17, 0)];
+
+const PRIVATE_CLIENT: Value<'static> = Value::Ip(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)));
+const PUBLIC_CLIENT: Value<'static> = Value::Ip(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 9)));
+
+/// 2024-01-01 10:00 UTC, a Monday.
+const MONDAY_10AM: i64 = 1_704_103_200;
+/// 2024-01-06 10:00 UTC, a Saturday.
+const SATURDAY_10AM: i64 = 1_704_535_200;
+
+fn target(
+    principal: Matcher<'static>,
+    action: Matcher<'static>,
+    resource: Matcher<'static>,
+) -> Target<'static> {
+    Target {
+        principal,
+        action,
+        resource,
+        environment: Matcher::Any,
+    }
+}
+
+/// A policy touching every part of the decision procedure: deny
+/// aggregation, challenges, break-glass, schedules, the action hierarchy,
+/// cache TTLs, provider failures, and each kind of condition.
+fn golden_policy() -> Policy<'static> {
+    let config = PolicyConfig {
+        deny_aggregation: DenyAggregation::HighestPriority,
+        ..PolicyConfig::default()
+    };
+    Policy::builder()
+        .config(config)
+        .action_hierarchy(ActionHierarchy::new().implies("write", "read"))
+        .rule(Rule::deny(
+            target(Matcher::Exact("mallory"), Matcher::Any, Matcher::Any),
+            ReasonCode(10),
+        ))
+        .rule(
+            Rule::new(
+                Effect::Deny,
+                Target::any(),
+                Some(Condition::GreaterOrEqual {
+                    attr: "risk",
+                    value: 80,
+                }),
+                ReasonCode(11),
+            )
+            .with_priority(5),
+        )
+        .rule(Rule::challenge(
+            target(Matcher::Any, Matcher::Exact("delete"), Matcher::Any),
+            ChallengeMethod::Mfa,
+            ReasonCode(20),
+        ))
+        .rule(
+            Rule::allow(
+                target(Matcher::Exact("admin"), Matcher::Any, Matcher::Any),
+                ReasonCode(1),
+            )
+            .with_cache_ttl(300),
+        )
+        .rule(Rule::new(
+            Effect::Allow,
+            target(Matcher::Any, Matcher::Exact("read"), Matcher::Any),
+            Some(Condition::In {
+                attr: "department",
+                values: DEPARTMENTS,
+            }),
+            ReasonCode(2),
+        ))
+        .rule(Rule::owner_allow(Matcher::Exact("write"), ReasonCode(3)))
+        .rule(Rule::new(
+            Effect::Allow,
+            target(Matcher::Any, Matcher::Exact("ssh"), Matcher::Any),
+            Some(Condition::And(
+                Box::new(Condition::IpInCidr {
+                    attr: "client",
+                    cidr: Cidr::parse("10.0.0.0/8").unwrap(),
+                }),
+                Box::new(Condition::Not(Box::new(Condition::Equals {
+                    attr: "suspended",
+                    value: Value::Bool(true),
+                }))),
+            )),
+            ReasonCode(4),
+        ))
+        .rule(
+            Rule::allow(
+                target(Matcher::Any, Matcher::Exact("deploy"), Matcher::Any),
+                ReasonCode(5),
+            )
+            .with_schedule(Schedule::new(Days::WEEKDAYS, OFFICE_HOURS)),
+        )
+        .rule(Rule::break_glass(
+            target(Matcher::Any, Matcher::Any, Matcher::Exact("prod-db")),
+            "emergency",
+            ReasonCode(6),
+        ))
+        .rule(
+            Rule::new(
+                Effect::Allow,
+                target(Matcher::Any, Matcher::Exact("restart"), Matcher::Any),
+                Some(Condition::MemberOf("sre")),
+                ReasonCode(7),
+            )
+            .with_provider_failure(ProviderFailure::Indeterminate),
+        )
+        .rule(
+            Rule::new(
+                Effect::Deny,
+                target(
+                    Matcher::Any,
+                    Matcher::Any,
+                    Matcher::OneOf(&["vault", "hsm"]),
+                ),
+                Some(Condition::NotEquals {
+                    attr: "network",
+                    value: Value::String("corp"),
+                }),
+                ReasonCode(12),
+            )
+            .with_priority(1),
+        )
+        .build()
+        .unwrap()
+}
+
+fn golden_corpus() -> Corpus<'static> {
+    Corpus::new()
+        .case("blocked principal", Request::new("mallory", "read", "doc"))
+        .case("admin writes", Request::new("admin", "write", "doc"))
+        .case("admin deletes", Request::new("admin", "delete", "doc"))
+        .case(
+            "high risk admin",
+            Request::with_context("admin", "write", "doc", &[("risk", Value::Int(90))]),
+        )
+        .case(
+            "low risk admin",
+            Request::with_context("admin", "write", "doc", &[("risk", Value::Int(79))]),
+        )
+        .case(
+            "engineer reads",
+            Request::with_context(
+                "bob",
+                "read",
+                "doc",
+                &[("department", Value::String("eng"))],
+            ),
+        )
+        .case(
+            "sales reads",
+            Request::with_context(
+                "bob",
+                "read",
+                "doc",
+                &[("department", Value::String("sales"))],
+            ),
+        )
+        .case(
+            "owner writes",
+            Request::with_context(
+                "carol",
+                "write",
+                "notes",
+                &[("resource_owner", Value::String("carol"))],
+            ),
+        )
+        .case(
+            "owner reads through hierarchy",
+            Request::with_context(
+                "carol",
+                "read",
+                "notes",
+                &[("resource_owner", Value::String("carol"))],
+            ),
+        )
+        .case(
+            "stranger writes",
+            Request::with_context(
+                "dave",
+                "write",
+                "notes",
+                &[("resource_owner", Value::String("carol"))],
+            ),
+        )
+        .case(
+            "ssh from private network",
+            Request::with_context("erin", "ssh", "bastion", &[("client", PRIVATE_CLIENT)]),
+        )
+        .case(
+            "ssh from internet",
+            Request::with_context("erin", "ssh", "bastion", &[("client", PUBLIC_CLIENT)]),
+        )
+        .case(
+            "suspended ssh",
+            Request::with_context(
+                "erin",
+                "ssh",
+                "bastion",
+                &[("client", PRIVATE_CLIENT), ("suspended", Value::Bool(true))],
+            ),
+        )
+        .case(
+            "deploy in office hours",
+            Request::with_context(
+                "frank",
+                "deploy",
+                "api",
+                &[("now", Value::Int(MONDAY_10AM))],
+            ),
+        )
+        .case(
+            "deploy on the weekend",
+            Request::with_context(
+                "frank",
+                "deploy",
+                "api",
+                &[("now", Value::Int(SATURDAY_10AM))],
+            ),
+        )
+        .case(
+            "break-glass",
+            Request::with_context(
+                "grace",
+                "drop",
+                "prod-db",
+                &[("emergency", Value::Bool(true))],
+            ),
+        )
+        .case(
+            "restart without directory",
+            Request::new("heidi", "restart", "api"),
+        )
+        .case(
+            "vault off network",
+            Request::with_context(
+                "admin",
+                "read",
+                "vault",
+                &[("network", Value::String("home"))],
+            ),
+        )
+        .case(
+            "vault off network at high risk",
+            Request::with_context(
+                "admin",
+                "read",
+                "vault",
+                &[("network", Value::String("home")), ("risk", Value::Int(80))],
+            ),
+        )
+        .case(
+            "vault on network",
+            Request::with_context(
+                "admin",
+                "read",
+                "vault",
+                &[("network", Value::String("corp"))],
+            ),
+        )
+        .case("no matching rule", Request::new("ivan", "print", "doc"))
+}
+
+#[test]
+fn test_golden_decisions() {
+    let actual = Snapshot::record(&golden_policy(), &golden_corpus());
+    let path = Path::new(GOLDEN_DECISIONS);
+    if std::env::var_os(UPDATE_ENV_VAR).is_some_and(|v| v == "1") {
+        std::fs::write(path, actual.to_text()).unwrap();
+        return;
+    }
+    // As with `assert_snapshot`, a missing file is a failure: the corpus
+    // must come from a previous release, not from this build.
+    let text = std::fs::read_to_string(path).expect("golden decision corpus is checked in");
+    let expected = Snapshot::parse(&text).unwrap();
+    let changes = expected.diff(&actual);
+    assert!(
+        changes.is_empty(),
+        "decisions changed across versions:\n{}",
+        changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+#[test]
+fn test_golden_fingerprint() {
+    assert_eq!(
+        golden_policy().fingerprint(),
+        GOLDEN_FINGERPRINT,
+        "fingerprint is {:#018x}",
+        golden_policy().fingerprint()
+    );
+}
+
+#[test]
+fn test_golden_canonical_encoding() {
+    let request = Request::with_context(
+        "alice",
+        "read",
+        "doc",
+        &[("b", Value::Int(-1)), ("a", Value::Bool(true))],
+    );
+    let decision = golden_policy().evaluate(&request).unwrap();
+    let bytes = encode_decision(&request, GOLDEN_FINGERPRINT, &decision);
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let expected = concat!(
+        "4730434404",         // magic, version
+        "5514de5d96aef946",   // fingerprint
+        "05000000616c696365", // principal
+        "0400000072656164",   // action
+        "03000000646f63",     // resource
+        "00",                 // no environment
+        "02000000",           // context, sorted by key
+        "010000006100",       // "a"
+        "01",                 //   Bool(true)
+        "010000006201",       // "b"
+        "ffffffffffffffff",   //   Int(-1)
+        "0100000000000000",   // deny 0, no extras
+    );
+    assert_eq!(hex, expected);
+}
+
+#[test]
+fn test_entry_points_agree() {
+    let policy = golden_policy();
+    let rebuilt = golden_policy();
+    let corpus = golden_corpus();
+    for case in corpus.cases() {
+        let request = &case.request;
+        let expected = policy.evaluate(request);
+        // Repeated, on a clone, and on an independently built policy
+        assert_eq!(policy.evaluate(request), expected, "{}", case.name);
+        assert_eq!(policy.clone().evaluate(request), expected, "{}", case.name);
+        assert_eq!(rebuilt.evaluate(request), expected, "{}", case.name);
+
+        let (decision, _) = policy.evaluate_with_stats(request).unwrap();
+        assert_eq!(Ok(decision), expected, "{}", case.name);
+        assert_eq!(
+            Ok(policy.evaluate_or_deny(request)),
+            expected,
+            "{}",
+            case.name
+        );
+        let batch = policy.evaluate_batch(
+            request.principal,
+            request.action,
+            request.context,
+            &[request.resource],
+        );
+        assert_eq!(batch, vec![expected.clone()], "{}", case.name);
+        assert_eq!(
+            render_outcome(&policy.evaluate(request)),
+            render_outcome(&expected),
+            "{}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn test_corpus_order_does_not_matter() {
+    // Evaluation keeps no state between requests, so the corpus decides
+    // the same in reverse.
+    let policy = golden_policy();
+    let corpus = golden_corpus();
+    let forward: Vec<_> = corpus
+        .cases()
+        .iter()
+        .map(|case| policy.evaluate(&case.request))
+        .collect();
+    let mut backward: Vec<_> = corpus
+        .cases()
+        .iter()
+        .rev()
+        .map(|case| policy.evaluate(&case.request))
+        .collect();
+    backward.reverse();
+    assert_eq!(forward, backward);
+}
@@ -56,6 +56,7 @@ fn arb_target_simple() -> impl Strategy<Value = Target<'static>> {
         principal: Matcher::Any,
         action: Matcher::Any,
         resource: Matcher::Any,
+        environment: Matcher::Any,
     })
 }
 
@@ -67,11 +68,8 @@ fn arb_rule_simple() -> impl Strategy<Value = Rule<'static>> {
         prop::option::of(arb_condition(4)),
         arb_reason(),
     )
-        .prop_map(|(effect, target, condition, reason)| Rule {
-            effect,
-            target,
-            condition,
-            reason,
+        .prop_map(|(effect, target, condition, reason)| {
+            Rule::new(effect, target, condition, reason)
         })
 }
 
@@ -186,6 +184,7 @@ proptest! {
             max_context_attrs: 64,
             max_matcher_options: 64,
             max_string_len: 256,
+            ..PolicyConfig::default()
         };
 
         let rule = Rule::new(
@@ -214,6 +213,7 @@ proptest! {
             max_context_attrs: 64,
             max_matcher_options: 64,
             max_string_len: 256,
+            ..PolicyConfig::default()
         };
 
         let rules: Vec<Rule> = (0..rule_count)
@@ -243,6 +243,7 @@ fn test_worst_case_policy() {
         max_context_attrs: 64,
         max_matcher_options: 64,
         max_string_len: 256,
+        ..PolicyConfig::default()
     };
 
     // Create a policy with maximum rules
@@ -302,6 +303,7 @@ fn test_context_too_large() {
         max_context_attrs: 5, // Very small limit
         max_matcher_options: 64,
         max_string_len: 256,
+        ..PolicyConfig::default()
     };
 
     let policy =
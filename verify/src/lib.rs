@@ -0,0 +1,219 @@
+//! Formal verification harnesses for Gate0.
+//!
+//! These are [Kani](https://model-checking.github.io/kani/) proofs that turn
+//! the crate's safety claims into checked artifacts:
+//!
+//! - `evaluate` never panics for any policy accepted by `build`
+//! - every accepted condition respects the configured depth bound
+//! - configs above `ABSOLUTE_MAX_CONDITION_DEPTH` are always rejected
+//! - oversized request contexts are always rejected before evaluation
+//!
+//! `FixedStack` is private to the core crate, so its memory-safety proofs
+//! live next to it in `src/fixed_stack.rs` under `#[cfg(kani)]`.
+//!
+//! Outside of Kani this crate compiles to nothing.
+//!
+//! ```text
+//! cargo kani                  # FixedStack proofs (repository root)
+//! cd verify && cargo kani     # public API proofs
+//! ```
+
+#[cfg(kani)]
+mod proofs {
+    use gate0::{
+        Collation, Condition, DenyAggregation, Effect, Matcher, Policy, PolicyConfig, PolicyError,
+        ReasonCode, Request, Rule, Target, Value, ABSOLUTE_MAX_CONDITION_DEPTH,
+    };
+
+    /// Symbolic identifiers. A tiny alphabet keeps the state space tractable
+    /// while still exercising both matching and non-matching paths.
+    const NAMES: [&str; 3] = ["a", "b", "role"];
+
+    fn any_name() -> &'static str {
+        let i: usize = kani::any();
+        kani::assume(i < NAMES.len());
+        NAMES[i]
+    }
+
+    fn any_value() -> Value<'static> {
+        match kani::any::<u8>() % 3 {
+            0 => Value::Bool(kani::any()),
+            1 => Value::Int(kani::any()),
+            _ => Value::String(any_name()),
+        }
+    }
+
+    fn any_matcher() -> Matcher<'static> {
+        const OPTIONS: &[&str] = &["a", "b"];
+        match kani::any::<u8>() % 3 {
+            0 => Matcher::Any,
+            1 => Matcher::Exact(any_name()),
+            _ => Matcher::OneOf(OPTIONS),
+        }
+    }
+
+    /// A symbolic condition tree of at most `depth` levels.
+    fn any_condition(depth: usize) -> Condition<'static> {
+        let choice: u8 = kani::any();
+        if depth <= 1 || choice % 7 < 4 {
+            return match choice % 4 {
+                0 => Condition::True,
+                1 => Condition::False,
+                2 => Condition::Equals {
+                    attr: any_name(),
+                    value: any_value(),
+                },
+                _ => Condition::NotEquals {
+                    attr: any_name(),
+                    value: any_value(),
+                },
+            };
+        }
+        match choice % 5 {
+            0 => Condition::Not(Box::new(any_condition(depth - 1))),
+            1 => Condition::And(
+                Box::new(any_condition(depth - 1)),
+                Box::new(any_condition(depth - 1)),
+            ),
+            2 => Condition::Implies(
+                Box::new(any_condition(depth - 1)),
+                Box::new(any_condition(depth - 1)),
+            ),
+            3 => Condition::Xor(
+                Box::new(any_condition(depth - 1)),
+                Box::new(any_condition(depth - 1)),
+            ),
+            _ => Condition::Or(
+                Box::new(any_condition(depth - 1)),
+                Box::new(any_condition(depth - 1)),
+            ),
+        }
+    }
+
+    fn any_rule() -> Rule<'static> {
+        let effect = if kani::any() {
+            Effect::Allow
+        } else {
+            Effect::Deny
+        };
+        let target = Target {
+            principal: any_matcher(),
+            action: any_matcher(),
+            resource: any_matcher(),
+            environment: any_matcher(),
+        };
+        let condition = if kani::any() {
+            Some(any_condition(3))
+        } else {
+            None
+        };
+        Rule::new(effect, target, condition, ReasonCode(kani::any()))
+    }
+
+    fn any_config() -> PolicyConfig {
+        PolicyConfig {
+            max_rules: kani::any(),
+            max_condition_depth: kani::any(),
+            max_condition_nodes: kani::any(),
+            max_context_attrs: kani::any(),
+            max_matcher_options: kani::any(),
+            max_string_len: kani::any(),
+            max_set_values: kani::any(),
+            max_group_lookups: kani::any(),
+            indeterminate_on_error: kani::any(),
+            deny_aggregation: if kani::any() {
+                DenyAggregation::FirstMatch
+            } else {
+                DenyAggregation::HighestPriority
+            },
+            short_circuit: kani::any(),
+            strict_types: kani::any(),
+            max_custom_op_cost: kani::any(),
+            known_actions: None,
+            known_resources: None,
+            reject_unknown_names: kani::any(),
+            collation: if kani::any() {
+                Collation::Binary
+            } else {
+                Collation::AsciiCaseInsensitive
+            },
+            max_obligations: kani::any(),
+            max_condition_evals: kani::any(),
+            three_valued_logic: kani::any(),
+            max_path_depth: kani::any(),
+        }
+    }
+
+    /// `evaluate` never panics for any policy accepted by `build`.
+    #[kani::proof]
+    #[kani::unwind(8)]
+    fn evaluate_never_panics() {
+        let policy = Policy::builder()
+            .config(any_config())
+            .rule(any_rule())
+            .rule(any_rule())
+            .build();
+
+        if let Ok(policy) = policy {
+            let ctx = [(any_name(), any_value()), (any_name(), any_value())];
+            let len: usize = kani::any();
+            kani::assume(len <= ctx.len());
+            let request = Request::with_context(any_name(), any_name(), any_name(), &ctx[..len]);
+
+            let _ = policy.evaluate(&request);
+            let _ = policy.evaluate_with_stats(&request);
+        }
+    }
+
+    /// Every condition in an accepted policy respects the depth bound.
+    #[kani::proof]
+    #[kani::unwind(8)]
+    fn accepted_conditions_respect_depth() {
+        let config = any_config();
+        let policy = Policy::builder().config(config).rule(any_rule()).build();
+
+        if let Ok(policy) = policy {
+            assert!(policy.config().max_condition_depth <= ABSOLUTE_MAX_CONDITION_DEPTH);
+            for rule in policy.rules() {
+                if let Some(cond) = &rule.condition {
+                    assert!(cond.depth() <= config.max_condition_depth);
+                }
+            }
+        }
+    }
+
+    /// Configs above the hard cap are always rejected.
+    #[kani::proof]
+    fn hard_depth_cap_is_enforced() {
+        let config = any_config();
+        kani::assume(config.max_condition_depth > ABSOLUTE_MAX_CONDITION_DEPTH);
+
+        let result = Policy::with_config(Vec::new(), config);
+        assert!(matches!(
+            result,
+            Err(PolicyError::ConditionTooDeep {
+                max: ABSOLUTE_MAX_CONDITION_DEPTH,
+                ..
+            })
+        ));
+    }
+
+    /// Oversized contexts are rejected before any rule is evaluated.
+    #[kani::proof]
+    #[kani::unwind(4)]
+    fn context_bound_is_enforced() {
+        let mut config = PolicyConfig::default();
+        config.max_context_attrs = kani::any();
+        kani::assume(config.max_context_attrs < 2);
+
+        let policy = Policy::with_config(vec![Rule::allow(Target::any(), ReasonCode(1))], config);
+        if let Ok(policy) = policy {
+            let ctx = [("a", any_value()), ("b", any_value())];
+            let request = Request::with_context("p", "a", "r", &ctx);
+            assert!(matches!(
+                policy.evaluate(&request),
+                Err(PolicyError::ContextTooLarge { actual: 2, .. })
+            ));
+        }
+    }
+}